@@ -0,0 +1,81 @@
+//! Plan generation benchmarks.
+//!
+//! Run with:
+//!
+//! ```bash
+//! cargo bench --bench planner_benchmarks
+//! ```
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use megamaid::detector::DetectionResult;
+use megamaid::models::{EntryType, FileEntry};
+use megamaid::planner::PlanGenerator;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Builds detections for `dirs` artifact directories, each with
+/// `files_per_dir` flagged children that should be collapsed away, plus an
+/// equal number of standalone large files.
+fn create_detections(dirs: usize, files_per_dir: usize) -> Vec<DetectionResult> {
+    let now = SystemTime::now();
+    let mut detections = Vec::with_capacity(dirs * (files_per_dir * 2 + 1));
+
+    for d in 0..dirs {
+        let project = PathBuf::from(format!("/bench/project_{}", d));
+        let target = project.join("target");
+
+        detections.push(DetectionResult {
+            entry: FileEntry::new(target.clone(), 1024, now, EntryType::Directory),
+            rule_name: "build_artifact".to_string(),
+            reason: "Common build artifact directory".to_string(),
+            downgrade: None,
+        });
+
+        for f in 0..files_per_dir {
+            detections.push(DetectionResult {
+                entry: FileEntry::new(
+                    target.join(format!("debug/deps/lib_{}.rlib", f)),
+                    200_000_000,
+                    now,
+                    EntryType::File,
+                ),
+                rule_name: "large_file".to_string(),
+                reason: "Large file".to_string(),
+                downgrade: None,
+            });
+            detections.push(DetectionResult {
+                entry: FileEntry::new(
+                    project.join(format!("assets/video_{}.mp4", f)),
+                    200_000_000,
+                    now,
+                    EntryType::File,
+                ),
+                rule_name: "large_file".to_string(),
+                reason: "Large file".to_string(),
+                downgrade: None,
+            });
+        }
+    }
+
+    detections
+}
+
+fn bench_generate_1m(c: &mut Criterion) {
+    // 2,000 directories x 250 files x 2 = 1,000,000 file detections
+    let detections = create_detections(2_000, 250);
+    let generator = PlanGenerator::new(PathBuf::from("/bench"));
+
+    let mut group = c.benchmark_group("plan_generation");
+    group.sample_size(10);
+    group.bench_function("generate_1m_detections", |b| {
+        b.iter_batched(
+            || detections.clone(),
+            |detections| generator.generate(detections),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_generate_1m);
+criterion_main!(benches);
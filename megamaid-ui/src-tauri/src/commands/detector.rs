@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectorConfig {
     pub size_threshold_mb: Option<u64>,
+    #[serde(default)]
+    pub directory_threshold_mb: Option<u64>,
     pub enable_build_artifacts: bool,
 }
 
@@ -21,7 +23,13 @@ pub async fn detect_cleanup_candidates(
     // Add size threshold rule if configured
     if let Some(threshold_mb) = config.size_threshold_mb {
         let threshold_bytes = threshold_mb * 1024 * 1024;
-        engine.add_rule(Box::new(SizeThresholdRule { threshold_bytes }));
+        let directory_bytes = config
+            .directory_threshold_mb
+            .map_or(threshold_bytes, |mb| mb * 1024 * 1024);
+        engine.add_rule(Box::new(SizeThresholdRule::with_thresholds(
+            threshold_bytes,
+            directory_bytes,
+        )));
     }
 
     // Add build artifact rule if enabled
@@ -52,6 +60,7 @@ mod tests {
     fn test_detector_config_serialization() {
         let config = DetectorConfig {
             size_threshold_mb: Some(100),
+            directory_threshold_mb: None,
             enable_build_artifacts: true,
         };
 
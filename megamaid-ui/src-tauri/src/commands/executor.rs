@@ -1,24 +1,208 @@
-use megamaid::executor::{ExecutionConfig, ExecutionEngine};
-use megamaid::models::CleanupPlan;
+use crate::tasks::{JobId, TaskHandle, TaskKind, TaskOutput, TaskRegistry, TASK_UPDATED_EVENT};
+use megamaid::dto::ExecutionSummary;
+use megamaid::executor::{
+    ExecutionConfig, ExecutionEngine, ExecutionMode, TransactionLogger, TransactionOptions,
+    TransactionStatus,
+};
+use megamaid::models::{CleanupPlan, PathKey};
+use megamaid::planner::{PlanReader, PlanWriter};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often a running execution saves its progress
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(2);
 
 /// Execute a cleanup plan
+///
+/// The execution is registered as a job; cancelling it pauses the run after
+/// the entry in progress. The plan and a transaction log are kept in the
+/// app data directory, so a run stopped early, or cut short by closing the
+/// app, can be picked up again with [`resume_execution`].
 #[tauri::command]
 pub async fn execute_cleanup_plan(
+    app: AppHandle,
+    tasks: State<'_, Arc<TaskRegistry>>,
     plan: CleanupPlan,
     config: ExecutionConfig,
-) -> Result<String, String> {
+) -> Result<ExecutionSummary, String> {
+    let jobs_dir = jobs_dir(&app)?;
+    let options = TransactionOptions {
+        dry_run: config.mode == ExecutionMode::DryRun,
+        backup_dir: config.backup_dir.clone(),
+        use_recycle_bin: config.use_recycle_bin,
+        fail_fast: config.fail_fast,
+    };
     let engine = ExecutionEngine::new(config);
-    let result = engine.execute(&plan).map_err(|e| e.to_string())?;
-
-    // Convert result to JSON for now
-    Ok(format!(
-        "Executed {} operations: {} successful, {} failed, {} skipped. Space freed: {} bytes",
-        result.summary.total_operations,
-        result.summary.successful,
-        result.summary.failed,
-        result.summary.skipped,
-        result.summary.space_freed
-    ))
+    let target = plan.base_path.to_string_lossy().into_owned();
+    let handle = tasks.start(TaskKind::Execution, target, engine.pause_handle());
+
+    // Save the plan and an empty log first, so an interrupted run leaves both
+    let plan_file = jobs_dir.join(format!("{}.plan.yaml", handle.id));
+    let log_file = jobs_dir.join(format!("{}.log.yaml", handle.id));
+    let logger = TransactionLogger::new(&plan_file, log_file.clone(), options);
+    let saved = PlanWriter::write(&plan, &plan_file)
+        .map_err(|e| format!("Failed to save plan: {}", e))
+        .and_then(|_| {
+            logger
+                .write()
+                .map_err(|e| format!("Failed to write transaction log: {}", e))
+        });
+    if let Err(error) = saved {
+        let info = tasks.finish(handle.id, Err(error.clone()));
+        let _ = app.emit(TASK_UPDATED_EVENT, &info);
+        return Err(error);
+    }
+    tasks.set_files(handle.id, plan_file, log_file);
+
+    run_execution(&app, &tasks, handle, engine, plan, logger, 0).await
+}
+
+/// Resume an execution that was paused, cancelled or interrupted by closing
+/// the app
+///
+/// The run continues the job's transaction log with the options recorded in
+/// it, skipping the entries the log already covers.
+#[tauri::command]
+pub async fn resume_execution(
+    app: AppHandle,
+    tasks: State<'_, Arc<TaskRegistry>>,
+    job_id: JobId,
+) -> Result<ExecutionSummary, String> {
+    let info = tasks
+        .get(job_id)
+        .ok_or_else(|| format!("No job with id {}", job_id))?;
+    let (Some(plan_file), Some(log_file)) = (info.plan_file, info.log_file) else {
+        return Err(format!("Job {} has no transaction log to resume", job_id));
+    };
+
+    let log = TransactionLogger::read(&log_file)
+        .map_err(|e| format!("Failed to read transaction log: {}", e))?;
+    let logger = TransactionLogger::resume(log_file)
+        .map_err(|e| format!("Failed to resume transaction log: {}", e))?;
+    let mut plan =
+        PlanReader::read(&plan_file).map_err(|e| format!("Failed to read plan file: {}", e))?;
+
+    // Drop entries handled by earlier runs
+    let processed = logger.processed_paths();
+    let entries = std::mem::take(&mut plan.entries);
+    let (done, remaining): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|e| processed.contains(&PathKey::new(&plan.entry_path(e))));
+    plan.entries = remaining;
+
+    let config = ExecutionConfig {
+        mode: if log.options.dry_run {
+            ExecutionMode::DryRun
+        } else {
+            ExecutionMode::Batch
+        },
+        backup_dir: log.options.backup_dir,
+        use_recycle_bin: log.options.use_recycle_bin,
+        fail_fast: log.options.fail_fast,
+        ..Default::default()
+    };
+    let engine = ExecutionEngine::new(config);
+    let handle = tasks
+        .resume(job_id, engine.pause_handle())
+        .ok_or_else(|| format!("Job {} can't be resumed", job_id))?;
+
+    run_execution(&app, &tasks, handle, engine, plan, logger, done.len()).await
+}
+
+/// Runs `plan` as the job of `handle` and records the outcome in `logger`
+///
+/// Each operation is appended to the log as soon as it's done, so a run cut
+/// short by closing the app can be resumed. Progress, counting the `done`
+/// entries of earlier runs, is saved every [`CHECKPOINT_INTERVAL`] while the
+/// plan runs.
+async fn run_execution(
+    app: &AppHandle,
+    tasks: &TaskRegistry,
+    handle: TaskHandle,
+    engine: ExecutionEngine,
+    plan: CleanupPlan,
+    mut logger: TransactionLogger,
+    done: usize,
+) -> Result<ExecutionSummary, String> {
+    let _ = app.emit(TASK_UPDATED_EVENT, &tasks.get(handle.id));
+    handle.set_progress(done);
+
+    let engine = Arc::new(engine);
+    let running = Arc::clone(&engine);
+    let mut execution = tokio::task::spawn_blocking(move || {
+        let executed = running.execute_with(&plan, |operation| logger.append_operation(operation));
+        let result = match executed {
+            Ok(result) => result,
+            Err(e) => {
+                // Keep what was done so far resumable
+                let _ = logger.abort();
+                return Err(e.to_string());
+            }
+        };
+        let status = if result.paused {
+            TransactionStatus::Paused
+        } else if result.summary.failed > 0 {
+            TransactionStatus::Failed
+        } else {
+            TransactionStatus::Completed
+        };
+        logger
+            .finalize(&result, status)
+            .map_err(|e| format!("Failed to write transaction log: {}", e))?;
+        Ok::<_, String>(ExecutionSummary::from(&result))
+    });
+
+    let mut checkpoints = tokio::time::interval(CHECKPOINT_INTERVAL);
+    let joined = loop {
+        tokio::select! {
+            joined = &mut execution => break joined,
+            _ = checkpoints.tick() => {
+                handle.set_progress(done + engine.progress().get_processed() as usize);
+                tasks.checkpoint();
+            }
+        }
+    };
+
+    let result = joined
+        .map_err(|e| format!("Execution task failed: {}", e))
+        .and_then(|result| result);
+
+    if let Ok(summary) = &result {
+        handle.set_progress(done + summary.total_operations);
+    }
+    let info = tasks.finish(handle.id, result.clone().map(TaskOutput::Execution));
+    let _ = app.emit(TASK_UPDATED_EVENT, &info);
+
+    result
+}
+
+/// Directory holding the plans and transaction logs of execution jobs
+fn jobs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to locate app data directory: {}", e))?
+        .join("jobs");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Pause a running execution after the entry in progress
+///
+/// Without a job id, every running execution is paused. Returns false if no
+/// matching execution is running.
+#[tauri::command]
+pub async fn pause_execution(
+    tasks: State<'_, Arc<TaskRegistry>>,
+    job_id: Option<JobId>,
+) -> Result<bool, String> {
+    Ok(match job_id {
+        Some(id) => tasks.cancel(id),
+        None => tasks.cancel_all(TaskKind::Execution) > 0,
+    })
 }
 
 /// Get default executor configuration
@@ -26,5 +210,3 @@ pub async fn execute_cleanup_plan(
 pub async fn get_default_executor_config() -> Result<ExecutionConfig, String> {
     Ok(ExecutionConfig::default())
 }
-
-// Transaction log commands removed for Phase 4.1 - will be added in future phases
@@ -4,6 +4,7 @@ pub mod detector;
 pub mod executor;
 pub mod planner;
 pub mod scanner;
+pub mod tasks;
 pub mod verifier;
 
 // Re-export all commands for easy registration
@@ -11,4 +12,5 @@ pub use detector::*;
 pub use executor::*;
 pub use planner::*;
 pub use scanner::*;
+pub use tasks::*;
 pub use verifier::*;
@@ -1,6 +1,7 @@
 use megamaid::detector::DetectionResult;
-use megamaid::models::{CleanupAction, CleanupPlan};
-use megamaid::planner::{PlanGenerator, PlanWriter};
+use megamaid::dto::PlanStats;
+use megamaid::models::CleanupPlan;
+use megamaid::planner::{PlanGenerator, PlanReader, PlanWriter, ReadError};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -10,15 +11,6 @@ pub struct PlanConfig {
     pub output_path: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlanStats {
-    pub total_entries: usize,
-    pub delete_count: usize,
-    pub review_count: usize,
-    pub keep_count: usize,
-    pub total_size: u64,
-}
-
 /// Generate a cleanup plan from detection results
 #[tauri::command]
 pub async fn generate_cleanup_plan(
@@ -50,11 +42,12 @@ pub async fn load_cleanup_plan(path: String) -> Result<CleanupPlan, String> {
         return Err(format!("Plan file does not exist: {:?}", plan_path));
     }
 
-    let content = std::fs::read_to_string(&plan_path)
-        .map_err(|e| format!("Failed to read plan file: {}", e))?;
-
-    let plan: CleanupPlan =
-        serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse plan file: {}", e))?;
+    let plan = PlanReader::read(&plan_path).map_err(|e| match e {
+        ReadError::Io(e) => format!("Failed to read plan file: {}", e),
+        ReadError::Deserialization(e) => format!("Failed to parse plan file: {}", e),
+        ReadError::JsonDeserialization(e) => format!("Failed to parse plan file: {}", e),
+        e @ ReadError::InvalidRoot { .. } => format!("Invalid plan file: {}", e),
+    })?;
 
     Ok(plan)
 }
@@ -62,31 +55,7 @@ pub async fn load_cleanup_plan(path: String) -> Result<CleanupPlan, String> {
 /// Get statistics from a cleanup plan
 #[tauri::command]
 pub async fn get_plan_stats(plan: CleanupPlan) -> Result<PlanStats, String> {
-    let total_entries = plan.entries.len();
-    let delete_count = plan
-        .entries
-        .iter()
-        .filter(|e| e.action == CleanupAction::Delete)
-        .count();
-    let review_count = plan
-        .entries
-        .iter()
-        .filter(|e| e.action == CleanupAction::Review)
-        .count();
-    let keep_count = plan
-        .entries
-        .iter()
-        .filter(|e| e.action == CleanupAction::Keep)
-        .count();
-    let total_size: u64 = plan.entries.iter().map(|e| e.size).sum();
-
-    Ok(PlanStats {
-        total_entries,
-        delete_count,
-        review_count,
-        keep_count,
-        total_size,
-    })
+    Ok(PlanStats::from(&plan))
 }
 
 #[cfg(test)]
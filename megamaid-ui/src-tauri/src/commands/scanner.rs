@@ -1,26 +1,22 @@
-use crate::AppState;
-use megamaid::models::FileEntry;
+use crate::tasks::{JobId, TaskKind, TaskOutput, TaskRegistry, TASK_UPDATED_EVENT};
+use megamaid::dto::{
+    ScanProgressEvent, ScanResult, ScanSummary, SCAN_COMPLETE_EVENT, SCAN_ERROR_EVENT,
+    SCAN_PROGRESS_EVENT, SCAN_STARTED_EVENT,
+};
 use megamaid::scanner::{ParallelScanner, ScannerConfig};
-use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc as StdArc;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 use tauri::{AppHandle, Emitter, State};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScanResult {
-    pub entries: Vec<FileEntry>,
-    pub total_files: usize,
-    pub total_size: u64,
-    pub errors: Vec<String>,
-}
-
 /// Scan a directory and return file entries
+///
+/// The scan is registered as a job, so several can run at once; each can be
+/// cancelled with `cancel_task` using the id from the `task:updated` event.
 #[tauri::command]
 pub async fn scan_directory(
     app: AppHandle,
-    state: State<'_, Arc<Mutex<AppState>>>,
+    tasks: State<'_, Arc<TaskRegistry>>,
     path: String,
     config: ScannerConfig,
 ) -> Result<ScanResult, String> {
@@ -30,63 +26,81 @@ pub async fn scan_directory(
         return Err(format!("Path does not exist: {}", path));
     }
 
-    // Emit start event
-    let _ = app.emit("scan:started", &path);
-
     let scanner = ParallelScanner::new(config);
-    let progress = StdArc::new(AtomicUsize::new(0));
+    let handle = tasks.start(TaskKind::Scan, path.clone(), scanner.cancel_handle());
+    let _ = app.emit(TASK_UPDATED_EVENT, &tasks.get(handle.id));
+
+    // Emit start event
+    let _ = app.emit(SCAN_STARTED_EVENT, &path);
+    let (started_at, started) = (SystemTime::now(), Instant::now());
 
-    let entries = scanner
-        .scan_with_progress(&scan_path, |count| {
-            progress.store(count, Ordering::Relaxed);
-            let _ = app.emit(
-                "scan:progress",
-                &serde_json::json!({
-                    "path": path,
-                    "files_scanned": count,
-                }),
-            );
+    let scan = {
+        let app = app.clone();
+        let handle = handle.clone();
+        let path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            scanner.scan_with_progress(&scan_path, |count| {
+                handle.set_progress(count);
+                let _ = app.emit(
+                    SCAN_PROGRESS_EVENT,
+                    &ScanProgressEvent {
+                        path: path.clone(),
+                        files_scanned: count,
+                    },
+                );
+            })
         })
-        .map_err(|e| {
-            let _ = app.emit("scan:error", &e.to_string());
-            e.to_string()
-        })?;
+    };
 
-    let total_files = entries.len();
-    let total_size: u64 = entries.iter().map(|e| e.size).sum();
+    let entries = match scan.await {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            let message = e.to_string();
+            let info = tasks.finish(handle.id, Err(message.clone()));
+            let _ = app.emit(TASK_UPDATED_EVENT, &info);
+            let _ = app.emit(SCAN_ERROR_EVENT, &message);
+            return Err(message);
+        }
+        Err(e) => {
+            let message = format!("Scan task failed: {}", e);
+            let info = tasks.finish(handle.id, Err(message.clone()));
+            let _ = app.emit(TASK_UPDATED_EVENT, &info);
+            return Err(message);
+        }
+    };
 
+    handle.set_progress(entries.len());
+    let root = PathBuf::from(&path);
     let result = ScanResult {
+        summary: ScanSummary::from_entries(&root, &entries, vec![])
+            .with_roots(&[root.clone()])
+            .with_top_directories(&entries, 10)
+            .with_timing(started_at, started.elapsed()),
         entries,
-        total_files,
-        total_size,
-        errors: vec![],
     };
 
-    {
-        let mut guard = state.lock().map_err(|_| "Failed to lock app state")?;
-        guard.current_scan_path = Some(path.clone());
-        guard.scan_in_progress = false;
-        guard.last_scan_result = Some(result.clone());
-    }
+    let info = tasks.finish(handle.id, Ok(TaskOutput::Scan(Arc::new(result.clone()))));
+    let _ = app.emit(TASK_UPDATED_EVENT, &info);
 
     // Emit completion event with summary
-    let _ = app.emit(
-        "scan:complete",
-        &serde_json::json!({
-            "path": path,
-            "total_files": result.total_files,
-            "total_size": result.total_size,
-        }),
-    );
+    let _ = app.emit(SCAN_COMPLETE_EVENT, &result.summary);
 
     Ok(result)
 }
 
-/// Retrieve the most recent scan result stored in state (if any)
+/// Retrieve a stored scan result: the given job's, or the most recent one
 #[tauri::command]
 pub async fn get_scan_results(
-    state: State<'_, Arc<Mutex<AppState>>>,
+    tasks: State<'_, Arc<TaskRegistry>>,
+    job_id: Option<JobId>,
 ) -> Result<Option<ScanResult>, String> {
-    let guard = state.lock().map_err(|_| "Failed to lock app state")?;
-    Ok(guard.last_scan_result.clone())
+    let result = match job_id {
+        Some(id) => match tasks.output(id) {
+            Some(TaskOutput::Scan(result)) => Some(result),
+            _ => None,
+        },
+        None => tasks.latest_scan(),
+    };
+
+    Ok(result.map(|result| (*result).clone()))
 }
@@ -0,0 +1,57 @@
+use crate::tasks::{JobId, TaskInfo, TaskRegistry};
+use megamaid::executor::{TransactionLog, TransactionLogger};
+use std::sync::Arc;
+use tauri::State;
+
+/// List all scan and execution jobs, oldest first
+#[tauri::command]
+pub async fn list_tasks(tasks: State<'_, Arc<TaskRegistry>>) -> Result<Vec<TaskInfo>, String> {
+    Ok(tasks.list())
+}
+
+/// Get the current state of a job
+#[tauri::command]
+pub async fn get_task(
+    tasks: State<'_, Arc<TaskRegistry>>,
+    job_id: JobId,
+) -> Result<Option<TaskInfo>, String> {
+    Ok(tasks.get(job_id))
+}
+
+/// Ask a running job to stop
+///
+/// Returns false if the job doesn't exist or has already finished.
+#[tauri::command]
+pub async fn cancel_task(
+    tasks: State<'_, Arc<TaskRegistry>>,
+    job_id: JobId,
+) -> Result<bool, String> {
+    Ok(tasks.cancel(job_id))
+}
+
+/// Forget a finished job and its result
+///
+/// Returns false if the job doesn't exist or is still running.
+#[tauri::command]
+pub async fn remove_task(
+    tasks: State<'_, Arc<TaskRegistry>>,
+    job_id: JobId,
+) -> Result<bool, String> {
+    Ok(tasks.remove(job_id))
+}
+
+/// Read the transaction log of an execution job, including a partial one
+///
+/// Returns None if the job has no log.
+#[tauri::command]
+pub async fn get_task_log(
+    tasks: State<'_, Arc<TaskRegistry>>,
+    job_id: JobId,
+) -> Result<Option<TransactionLog>, String> {
+    let Some(log_file) = tasks.get(job_id).and_then(|info| info.log_file) else {
+        return Ok(None);
+    };
+    TransactionLogger::read(&log_file)
+        .map(Some)
+        .map_err(|e| format!("Failed to read transaction log: {}", e))
+}
@@ -1,31 +1,13 @@
 mod commands;
+mod tasks;
 
-use crate::commands::scanner::ScanResult;
-use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
-
-/// Application state shared across Tauri commands
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AppState {
-    pub current_scan_path: Option<String>,
-    pub scan_in_progress: bool,
-    pub last_scan_result: Option<ScanResult>,
-}
-
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            current_scan_path: None,
-            scan_in_progress: false,
-            last_scan_result: None,
-        }
-    }
-}
+use std::sync::Arc;
+use std::time::Duration;
+use tasks::{TaskKind, TaskRegistry};
+use tauri::{Manager, WindowEvent};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let app_state = Arc::new(Mutex::new(AppState::default()));
-
     tauri::Builder::default()
         .setup(|app| {
             if cfg!(debug_assertions) {
@@ -35,10 +17,30 @@ pub fn run() {
                         .build(),
                 )?;
             }
+
+            // Jobs of earlier sessions, so interrupted executions can be resumed
+            let store = app.path().app_data_dir()?.join("jobs.json");
+            app.manage(Arc::new(TaskRegistry::load(store)));
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Pause executions and let them record where they stopped before closing
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                let tasks = Arc::clone(window.state::<Arc<TaskRegistry>>().inner());
+                if tasks.interrupt_executions() == 0 {
+                    return;
+                }
+                api.prevent_close();
+                let window = window.clone();
+                tauri::async_runtime::spawn(async move {
+                    while tasks.is_running(TaskKind::Execution) {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                    let _ = window.destroy();
+                });
+            }
+        })
         .plugin(tauri_plugin_dialog::init())
-        .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             // Scanner commands
             commands::scan_directory,
@@ -56,7 +58,15 @@ pub fn run() {
             commands::get_default_verifier_config,
             // Executor commands
             commands::execute_cleanup_plan,
+            commands::resume_execution,
+            commands::pause_execution,
             commands::get_default_executor_config,
+            // Task commands
+            commands::list_tasks,
+            commands::get_task,
+            commands::cancel_task,
+            commands::remove_task,
+            commands::get_task_log,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,555 @@
+// Task registry - tracks the scans and executions started from the UI
+
+use megamaid::dto::{ExecutionSummary, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Identifier of a job in the registry
+pub type JobId = u64;
+
+/// Event emitted when a job starts or finishes, with a [`TaskInfo`] payload
+pub const TASK_UPDATED_EVENT: &str = "task:updated";
+
+/// What a job does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskKind {
+    Scan,
+    Execution,
+}
+
+/// Where a job is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    /// Still running when the app was closed
+    Interrupted,
+}
+
+/// Snapshot of a job, as reported to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    pub id: JobId,
+    pub kind: TaskKind,
+    /// Scanned directory or base path of the executed plan
+    pub target: String,
+    pub status: TaskStatus,
+    /// Entries processed so far
+    pub progress: usize,
+    pub error: Option<String>,
+    /// Plan an execution runs, saved for resuming it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_file: Option<PathBuf>,
+    /// Transaction log of an execution, including a partial one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<PathBuf>,
+}
+
+/// Output of a finished job
+#[derive(Debug, Clone)]
+pub enum TaskOutput {
+    Scan(Arc<ScanResult>),
+    Execution(ExecutionSummary),
+}
+
+/// Handle a running job uses to report progress
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    pub id: JobId,
+    progress: Arc<AtomicUsize>,
+}
+
+impl TaskHandle {
+    pub fn set_progress(&self, processed: usize) {
+        self.progress.store(processed, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+struct Task {
+    kind: TaskKind,
+    target: String,
+    status: TaskStatus,
+    error: Option<String>,
+    progress: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    output: Option<TaskOutput>,
+    plan_file: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+}
+
+impl Task {
+    fn info(&self, id: JobId) -> TaskInfo {
+        TaskInfo {
+            id,
+            kind: self.kind,
+            target: self.target.clone(),
+            status: self.status,
+            progress: self.progress.load(Ordering::Relaxed),
+            error: self.error.clone(),
+            plan_file: self.plan_file.clone(),
+            log_file: self.log_file.clone(),
+        }
+    }
+
+    /// Restores a job saved by an earlier session
+    fn saved(info: TaskInfo) -> Self {
+        let status = match info.status {
+            TaskStatus::Running => TaskStatus::Interrupted,
+            status => status,
+        };
+        Task {
+            kind: info.kind,
+            target: info.target,
+            status,
+            error: info.error,
+            progress: Arc::new(AtomicUsize::new(info.progress)),
+            cancel: Arc::default(),
+            output: None,
+            plan_file: info.plan_file,
+            log_file: info.log_file,
+        }
+    }
+
+    /// Whether this is an execution that stopped early and left a log
+    fn resumable(&self) -> bool {
+        self.kind == TaskKind::Execution
+            && matches!(self.status, TaskStatus::Cancelled | TaskStatus::Interrupted)
+            && self.log_file.is_some()
+    }
+}
+
+/// Jobs started from the UI, keyed by id
+///
+/// Each job runs independently: it can be queried, cancelled and its result
+/// fetched without waiting on any other job. Finished jobs stay in the
+/// registry until removed, so their results remain available.
+///
+/// A registry with a store saves every job to it when the job changes, so
+/// the next session can show the jobs, outputs aside, and resume executions
+/// the app was closed in the middle of.
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<JobId, Task>>,
+    store: Option<PathBuf>,
+    closing: AtomicBool,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the jobs saved in `store` by an earlier session and keeps
+    /// saving them there
+    ///
+    /// Jobs that were still running when that session ended are marked
+    /// interrupted. A missing or unreadable store starts an empty registry.
+    pub fn load(store: PathBuf) -> Self {
+        let saved: Vec<TaskInfo> = match std::fs::read(&store) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("Ignoring unreadable job store {}: {}", store.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        let next_id = saved.iter().map(|info| info.id).max().unwrap_or(0);
+        let tasks = saved
+            .into_iter()
+            .map(|info| (info.id, Task::saved(info)))
+            .collect();
+
+        Self {
+            next_id: AtomicU64::new(next_id),
+            tasks: Mutex::new(tasks),
+            store: Some(store),
+            closing: AtomicBool::new(false),
+        }
+    }
+
+    /// Registers a running job
+    ///
+    /// `cancel` is the flag the job polls to stop early, such as a scanner's
+    /// cancel handle or an execution engine's pause handle.
+    pub fn start(&self, kind: TaskKind, target: String, cancel: Arc<AtomicBool>) -> TaskHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let progress = Arc::new(AtomicUsize::new(0));
+
+        self.lock().insert(
+            id,
+            Task {
+                kind,
+                target,
+                status: TaskStatus::Running,
+                error: None,
+                progress: Arc::clone(&progress),
+                cancel,
+                output: None,
+                plan_file: None,
+                log_file: None,
+            },
+        );
+        self.save();
+
+        TaskHandle { id, progress }
+    }
+
+    /// Records where an execution keeps its plan and transaction log
+    pub fn set_files(&self, id: JobId, plan_file: PathBuf, log_file: PathBuf) {
+        if let Some(task) = self.lock().get_mut(&id) {
+            task.plan_file = Some(plan_file);
+            task.log_file = Some(log_file);
+        }
+        self.save();
+    }
+
+    /// Saves the progress of running jobs
+    pub fn checkpoint(&self) {
+        self.save();
+    }
+
+    /// Records the outcome of a job and returns its final state
+    ///
+    /// A job whose cancel flag was set is reported as cancelled, or as
+    /// interrupted if the app is closing; an execution stopped this way still
+    /// keeps its summary of the entries it processed.
+    pub fn finish(&self, id: JobId, result: Result<TaskOutput, String>) -> Option<TaskInfo> {
+        let info = {
+            let mut tasks = self.lock();
+            let task = tasks.get_mut(&id)?;
+            let stopped = if self.closing.load(Ordering::SeqCst) {
+                TaskStatus::Interrupted
+            } else {
+                TaskStatus::Cancelled
+            };
+            let cancelled = task.cancel.load(Ordering::SeqCst);
+
+            match result {
+                Ok(output) => {
+                    task.status = if cancelled {
+                        stopped
+                    } else {
+                        TaskStatus::Completed
+                    };
+                    task.output = Some(output);
+                }
+                Err(_) if cancelled => task.status = stopped,
+                Err(error) => {
+                    task.status = TaskStatus::Failed;
+                    task.error = Some(error);
+                }
+            }
+
+            task.info(id)
+        };
+        self.save();
+
+        Some(info)
+    }
+
+    /// Puts an execution that stopped early back to running, with a new
+    /// cancel flag
+    ///
+    /// Returns None if the job isn't an interrupted or cancelled execution
+    /// with a transaction log.
+    pub fn resume(&self, id: JobId, cancel: Arc<AtomicBool>) -> Option<TaskHandle> {
+        let handle = {
+            let mut tasks = self.lock();
+            let task = tasks.get_mut(&id).filter(|task| task.resumable())?;
+            task.status = TaskStatus::Running;
+            task.error = None;
+            task.cancel = cancel;
+            task.output = None;
+            TaskHandle {
+                id,
+                progress: Arc::clone(&task.progress),
+            }
+        };
+        self.save();
+
+        Some(handle)
+    }
+
+    /// Pauses every running execution because the app is closing; returns
+    /// how many were paused
+    ///
+    /// The executions then finish as interrupted, so the next session offers
+    /// to resume them.
+    pub fn interrupt_executions(&self) -> usize {
+        self.closing.store(true, Ordering::SeqCst);
+        self.cancel_all(TaskKind::Execution)
+    }
+
+    /// Returns whether any job of `kind` is still running
+    pub fn is_running(&self, kind: TaskKind) -> bool {
+        self.lock()
+            .values()
+            .any(|t| t.kind == kind && t.status == TaskStatus::Running)
+    }
+
+    /// Asks a running job to stop
+    ///
+    /// Returns false if the job doesn't exist or has already finished.
+    pub fn cancel(&self, id: JobId) -> bool {
+        match self.lock().get(&id) {
+            Some(task) if task.status == TaskStatus::Running => {
+                task.cancel.store(true, Ordering::SeqCst);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Asks every running job of `kind` to stop; returns how many were asked
+    pub fn cancel_all(&self, kind: TaskKind) -> usize {
+        let tasks = self.lock();
+        let running = tasks
+            .values()
+            .filter(|t| t.kind == kind && t.status == TaskStatus::Running);
+
+        let mut count = 0;
+        for task in running {
+            task.cancel.store(true, Ordering::SeqCst);
+            count += 1;
+        }
+        count
+    }
+
+    pub fn get(&self, id: JobId) -> Option<TaskInfo> {
+        self.lock().get(&id).map(|task| task.info(id))
+    }
+
+    /// Returns all jobs, oldest first
+    pub fn list(&self) -> Vec<TaskInfo> {
+        infos(&self.lock())
+    }
+
+    pub fn output(&self, id: JobId) -> Option<TaskOutput> {
+        self.lock().get(&id).and_then(|task| task.output.clone())
+    }
+
+    /// Returns the result of the most recently started scan that completed
+    pub fn latest_scan(&self) -> Option<Arc<ScanResult>> {
+        self.lock()
+            .iter()
+            .filter(|(_, task)| task.status == TaskStatus::Completed)
+            .filter_map(|(id, task)| match &task.output {
+                Some(TaskOutput::Scan(result)) => Some((*id, result)),
+                _ => None,
+            })
+            .max_by_key(|(id, _)| *id)
+            .map(|(_, result)| Arc::clone(result))
+    }
+
+    /// Forgets a finished job and its result
+    ///
+    /// Returns false if the job doesn't exist or is still running.
+    pub fn remove(&self, id: JobId) -> bool {
+        let removed = {
+            let mut tasks = self.lock();
+            match tasks.get(&id) {
+                Some(task) if task.status != TaskStatus::Running => {
+                    tasks.remove(&id);
+                    true
+                }
+                _ => false,
+            }
+        };
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Writes all jobs to the store, if any
+    ///
+    /// The job map stays locked while writing, so saves don't interleave.
+    /// Failing to save only costs the next session its view of the jobs, so
+    /// errors are logged rather than returned.
+    fn save(&self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let tasks = self.lock();
+        if let Err(e) = write_store(store, &infos(&tasks)) {
+            log::warn!("Failed to save jobs to {}: {}", store.display(), e);
+        }
+    }
+
+    /// Locks the job map, recovering it if a command panicked while holding it
+    fn lock(&self) -> MutexGuard<'_, HashMap<JobId, Task>> {
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Snapshots of `tasks`, oldest first
+fn infos(tasks: &HashMap<JobId, Task>) -> Vec<TaskInfo> {
+    let mut infos: Vec<_> = tasks.iter().map(|(id, task)| task.info(*id)).collect();
+    infos.sort_by_key(|info| info.id);
+    infos
+}
+
+/// Writes `jobs` to `store`, replacing it atomically
+fn write_store(store: &Path, jobs: &[TaskInfo]) -> std::io::Result<()> {
+    if let Some(dir) = store.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = store.with_extension("tmp");
+    std::fs::write(&temp, serde_json::to_vec_pretty(jobs)?)?;
+    std::fs::rename(temp, store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use megamaid::dto::ScanSummary;
+
+    fn scan_output(path: &str) -> TaskOutput {
+        TaskOutput::Scan(Arc::new(ScanResult {
+            entries: Vec::new(),
+            summary: ScanSummary {
+                path: path.to_string(),
+                ..Default::default()
+            },
+        }))
+    }
+
+    #[test]
+    fn test_jobs_are_tracked_independently() {
+        let registry = TaskRegistry::new();
+        let first = registry.start(TaskKind::Scan, "/a".to_string(), Arc::default());
+        let second = registry.start(TaskKind::Scan, "/b".to_string(), Arc::default());
+        assert_ne!(first.id, second.id);
+
+        second.set_progress(42);
+        let info = registry.finish(second.id, Ok(scan_output("/b"))).unwrap();
+        assert_eq!(info.status, TaskStatus::Completed);
+        assert_eq!(info.progress, 42);
+
+        assert_eq!(registry.get(first.id).unwrap().status, TaskStatus::Running);
+        assert_eq!(registry.latest_scan().unwrap().summary.path, "/b");
+
+        registry.finish(first.id, Ok(scan_output("/a")));
+        assert_eq!(registry.latest_scan().unwrap().summary.path, "/b");
+
+        let ids: Vec<_> = registry.list().iter().map(|info| info.id).collect();
+        assert_eq!(ids, vec![first.id, second.id]);
+    }
+
+    #[test]
+    fn test_cancel_and_failure() {
+        let registry = TaskRegistry::new();
+        let flag = Arc::new(AtomicBool::new(false));
+        let scan = registry.start(TaskKind::Scan, "/a".to_string(), Arc::clone(&flag));
+        let execution = registry.start(TaskKind::Execution, "/b".to_string(), Arc::default());
+
+        assert!(registry.cancel(scan.id));
+        assert!(flag.load(Ordering::SeqCst));
+        let info = registry
+            .finish(scan.id, Err("Scan cancelled".to_string()))
+            .unwrap();
+        assert_eq!(info.status, TaskStatus::Cancelled);
+        assert_eq!(info.error, None);
+        assert!(!registry.cancel(scan.id));
+
+        let info = registry
+            .finish(execution.id, Err("Plan is empty".to_string()))
+            .unwrap();
+        assert_eq!(info.status, TaskStatus::Failed);
+        assert_eq!(info.error.as_deref(), Some("Plan is empty"));
+        assert!(registry.latest_scan().is_none());
+
+        assert!(registry.remove(scan.id));
+        assert!(registry.get(scan.id).is_none());
+        assert!(!registry.remove(99));
+    }
+
+    #[test]
+    fn test_task_info_serializes_camel_case() {
+        let registry = TaskRegistry::new();
+        let handle = registry.start(TaskKind::Execution, "/a".to_string(), Arc::default());
+
+        let json = serde_json::to_value(registry.get(handle.id).unwrap()).unwrap();
+        assert_eq!(json["kind"], "execution");
+        assert_eq!(json["status"], "running");
+        assert_eq!(json["id"], handle.id);
+        assert!(json.get("logFile").is_none());
+    }
+
+    #[test]
+    fn test_jobs_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("jobs.json");
+
+        let registry = TaskRegistry::load(store.clone());
+        let scan = registry.start(TaskKind::Scan, "/a".to_string(), Arc::default());
+        let execution = registry.start(TaskKind::Execution, "/b".to_string(), Arc::default());
+        registry.set_files(
+            execution.id,
+            PathBuf::from("/jobs/2.plan.yaml"),
+            PathBuf::from("/jobs/2.log.yaml"),
+        );
+        registry.finish(scan.id, Ok(scan_output("/a")));
+        execution.set_progress(7);
+        registry.checkpoint();
+        drop(registry);
+
+        // The app was closed without the execution finishing
+        let registry = TaskRegistry::load(store);
+        assert_eq!(registry.get(scan.id).unwrap().status, TaskStatus::Completed);
+        assert!(registry.latest_scan().is_none());
+
+        let info = registry.get(execution.id).unwrap();
+        assert_eq!(info.status, TaskStatus::Interrupted);
+        assert_eq!(info.progress, 7);
+        assert_eq!(info.log_file, Some(PathBuf::from("/jobs/2.log.yaml")));
+
+        let next = registry.start(TaskKind::Scan, "/c".to_string(), Arc::default());
+        assert!(next.id > execution.id);
+
+        let resumed = registry.resume(execution.id, Arc::default()).unwrap();
+        assert_eq!(resumed.id, execution.id);
+        assert_eq!(
+            registry.get(execution.id).unwrap().status,
+            TaskStatus::Running
+        );
+        assert!(registry.resume(execution.id, Arc::default()).is_none());
+        assert!(registry.resume(scan.id, Arc::default()).is_none());
+    }
+
+    #[test]
+    fn test_closing_interrupts_executions() {
+        let registry = TaskRegistry::new();
+        let flag = Arc::new(AtomicBool::new(false));
+        let execution = registry.start(TaskKind::Execution, "/a".to_string(), Arc::clone(&flag));
+        let scan = registry.start(TaskKind::Scan, "/b".to_string(), Arc::default());
+
+        assert_eq!(registry.interrupt_executions(), 1);
+        assert!(flag.load(Ordering::SeqCst));
+        assert!(registry.is_running(TaskKind::Execution));
+
+        let summary = ExecutionSummary {
+            paused: true,
+            ..Default::default()
+        };
+        let info = registry
+            .finish(execution.id, Ok(TaskOutput::Execution(summary)))
+            .unwrap();
+        assert_eq!(info.status, TaskStatus::Interrupted);
+        assert!(!registry.is_running(TaskKind::Execution));
+
+        // No log was recorded, so there is nothing to resume from
+        assert!(registry.resume(execution.id, Arc::default()).is_none());
+        assert_eq!(registry.get(scan.id).unwrap().status, TaskStatus::Running);
+    }
+}
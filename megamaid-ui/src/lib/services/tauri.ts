@@ -6,14 +6,23 @@ export type ScannerConfig = {
   skip_hidden: boolean;
   follow_symlinks: boolean;
   thread_count: number;
+  exclude_globs?: string[];
+  respect_ignore_files?: boolean;
+  same_filesystem?: boolean;
+  dir_size?: {
+    parallel_depth: number;
+    min_parallel_entries: number;
+  };
 };
 
 export type FileEntry = {
   path: string;
   size: number;
   modified: number;
-  entry_type: 'File' | 'Directory';
+  entry_type: 'File' | 'Directory' | 'Symlink' | 'Junction';
+  link_target?: string | null;
   file_id?: number | null;
+  cloud_placeholder?: boolean;
 };
 
 export type DetectionResult = {
@@ -26,30 +35,78 @@ export type CleanupAction = 'delete' | 'keep' | 'review';
 
 export type CleanupEntry = {
   path: string;
+  root?: number;
   size: number;
   modified: string;
   action: CleanupAction;
   rule_name: string;
   reason: string;
+  collapsed?: CollapsedSummary | null;
+  never_delete?: boolean;
+};
+
+export type CollapsedSummary = {
+  file_count: number;
+  dir_count: number;
+  largest?: SampledFile[];
+  newest?: SampledFile | null;
+};
+
+export type SampledFile = {
+  path: string;
+  size: number;
+  modified: string;
+};
+
+export type RuleStats = {
+  rule_name: string;
+  entries_evaluated: number;
+  entries_flagged: number;
+  bytes_flagged: number;
+  evaluation_time_ms: number;
 };
 
 export type CleanupPlan = {
   version: string;
   created_at: string;
   base_path: string;
+  roots?: string[];
   entries: CleanupEntry[];
+  detection_stats?: RuleStats[];
+};
+
+export type AggregateStats = {
+  count: number;
+  size: number;
+};
+
+// Shapes below mirror megamaid::dto and are camelCase on the wire
+
+export type RuleCoverage = {
+  ruleName: string;
+  entriesEvaluated: number;
+  entriesFlagged: number;
+  bytesFlagged: number;
+  evaluationTimeMs: number;
+  largestEntry: string | null;
+  largestEntryBytes: number;
 };
 
 export type PlanStats = {
-  total_entries: number;
-  delete_count: number;
-  review_count: number;
-  keep_count: number;
-  total_size: number;
+  totalEntries: number;
+  deleteCount: number;
+  reviewCount: number;
+  keepCount: number;
+  totalSize: number;
+  detectionStats?: RuleCoverage[];
+  byRule?: Record<string, AggregateStats>;
+  byExtension?: Record<string, AggregateStats>;
+  byProtection?: Record<string, AggregateStats>;
 };
 
 export type DetectorConfig = {
   size_threshold_mb: number | null;
+  directory_threshold_mb?: number | null;
   enable_build_artifacts: boolean;
 };
 
@@ -62,11 +119,86 @@ export type PlanFileDialogOptions = {
   defaultPath?: string;
 };
 
-export type ScanResult = {
-  entries: FileEntry[];
-  total_files: number;
-  total_size: number;
+export type VolumeSummary = {
+  root: string;
+  kind: "rotational" | "solid_state" | "network" | "unknown";
+  roots: string[];
+};
+
+export type DirectorySize = {
+  path: string;
+  bytes: number;
+};
+
+export type ScanSummary = {
+  path: string;
+  totalEntries: number;
+  totalFiles: number;
+  totalSize: number;
   errors: string[];
+  permissionDenied: number;
+  startedAt?: string;
+  durationMs: number;
+  roots: string[];
+  volumes: VolumeSummary[];
+  ruleStats: RuleCoverage[];
+  topDirectories: DirectorySize[];
+  planFile?: string;
+};
+
+export type ScanResult = ScanSummary & {
+  entries: FileEntry[];
+};
+
+export type ScanProgressEvent = {
+  path: string;
+  filesScanned: number;
+};
+
+export type ExecutionSummary = {
+  totalOperations: number;
+  successful: number;
+  failed: number;
+  skipped: number;
+  spaceFreed: number;
+  bytesDeleted: number;
+  bytesRecycled: number;
+  bytesBackedUp: number;
+  bytesSkipped: number;
+  durationMs: number;
+  paused: boolean;
+};
+
+export type TaskKind = 'scan' | 'execution';
+
+export type TaskStatus = 'running' | 'completed' | 'failed' | 'cancelled' | 'interrupted';
+
+export type TaskInfo = {
+  id: number;
+  kind: TaskKind;
+  target: string;
+  status: TaskStatus;
+  progress: number;
+  error: string | null;
+  planFile?: string;
+  logFile?: string;
+};
+
+export type LoggedOperation = {
+  path: string;
+  action: string;
+  status: string;
+  size_freed: number | null;
+  timestamp: string;
+};
+
+export type TransactionLog = {
+  execution_id: string;
+  plan_file: string;
+  started_at: string;
+  completed_at: string | null;
+  status: 'inprogress' | 'completed' | 'failed' | 'aborted' | 'paused';
+  operations: LoggedOperation[];
 };
 
 declare global {
@@ -148,6 +280,30 @@ export async function getLastScanResult(): Promise<ScanResult | null> {
   return result;
 }
 
+export async function listTasks(): Promise<TaskInfo[]> {
+  await ensureTauri();
+
+  return invoke<TaskInfo[]>('list_tasks');
+}
+
+export async function cancelTask(jobId: number): Promise<boolean> {
+  await ensureTauri();
+
+  return invoke<boolean>('cancel_task', { jobId });
+}
+
+export async function resumeExecution(jobId: number): Promise<ExecutionSummary> {
+  await ensureTauri();
+
+  return invoke<ExecutionSummary>('resume_execution', { jobId });
+}
+
+export async function getTaskLog(jobId: number): Promise<TransactionLog | null> {
+  await ensureTauri();
+
+  return invoke<TransactionLog | null>('get_task_log', { jobId });
+}
+
 export async function loadPlanFromFile(): Promise<CleanupPlan | null> {
   await ensureTauri();
   const path = await open({
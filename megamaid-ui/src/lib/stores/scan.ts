@@ -28,16 +28,16 @@ function sanitizePlan(plan?: CleanupPlan): CleanupPlan | undefined {
 
 function recomputePlanStats(plan?: CleanupPlan): PlanStats | undefined {
   if (!plan) return undefined;
-  const delete_count = plan.entries.filter((e) => e.action === 'delete').length;
-  const review_count = plan.entries.filter((e) => e.action === 'review').length;
-  const keep_count = plan.entries.filter((e) => e.action === 'keep').length;
-  const total_size = plan.entries.reduce((acc, e) => acc + e.size, 0);
+  const deleteCount = plan.entries.filter((e) => e.action === 'delete').length;
+  const reviewCount = plan.entries.filter((e) => e.action === 'review').length;
+  const keepCount = plan.entries.filter((e) => e.action === 'keep').length;
+  const totalSize = plan.entries.reduce((acc, e) => acc + e.size, 0);
   return {
-    total_entries: plan.entries.length,
-    delete_count,
-    review_count,
-    keep_count,
-    total_size,
+    totalEntries: plan.entries.length,
+    deleteCount,
+    reviewCount,
+    keepCount,
+    totalSize,
   };
 }
 
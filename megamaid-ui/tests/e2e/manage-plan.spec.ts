@@ -13,11 +13,11 @@ const seedPlanScript = `
     ]
   };
   const stats = {
-    total_entries: plan.entries.length,
-    delete_count: plan.entries.filter(e => e.action === 'delete').length,
-    review_count: plan.entries.filter(e => e.action === 'review').length,
-    keep_count: plan.entries.filter(e => e.action === 'keep').length,
-    total_size: plan.entries.reduce((a, e) => a + e.size, 0),
+    totalEntries: plan.entries.length,
+    deleteCount: plan.entries.filter(e => e.action === 'delete').length,
+    reviewCount: plan.entries.filter(e => e.action === 'review').length,
+    keepCount: plan.entries.filter(e => e.action === 'keep').length,
+    totalSize: plan.entries.reduce((a, e) => a + e.size, 0),
   };
   const state = {
     directory: 'C:/workspace',
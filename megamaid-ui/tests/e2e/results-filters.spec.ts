@@ -13,16 +13,16 @@ const seedPlanScript = `
     ]
   };
   const stats = {
-    total_entries: plan.entries.length,
-    delete_count: plan.entries.filter(e => e.action === 'delete').length,
-    review_count: plan.entries.filter(e => e.action === 'review').length,
-    keep_count: plan.entries.filter(e => e.action === 'keep').length,
-    total_size: plan.entries.reduce((a, e) => a + e.size, 0),
+    totalEntries: plan.entries.length,
+    deleteCount: plan.entries.filter(e => e.action === 'delete').length,
+    reviewCount: plan.entries.filter(e => e.action === 'review').length,
+    keepCount: plan.entries.filter(e => e.action === 'keep').length,
+    totalSize: plan.entries.reduce((a, e) => a + e.size, 0),
   };
   const scanResult = {
     entries: [],
-    total_files: 10,
-    total_size: 7168,
+    totalFiles: 10,
+    totalSize: 7168,
     errors: []
   };
   const state = {
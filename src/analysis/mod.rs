@@ -0,0 +1,9 @@
+//! Aggregated reports on scan results, independent of cleanup plans.
+//!
+//! [`UsageAnalyzer`] breaks the entries of a scan down into the largest
+//! directories, the space taken by each file extension and the age of the
+//! files, for `megamaid analyze`.
+
+mod usage;
+
+pub use usage::{AgeBucket, DirectoryUsage, Usage, UsageAnalyzer, UsageReport};
@@ -0,0 +1,246 @@
+//! Disk usage breakdowns of scan results.
+
+use crate::models::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const DAY: u64 = 86_400;
+
+/// How long ago files were last modified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgeBucket {
+    /// Within the last 7 days
+    Week,
+    /// 7 to 30 days ago
+    Month,
+    /// 30 to 90 days ago
+    Quarter,
+    /// 90 to 365 days ago
+    Year,
+    /// More than a year ago
+    Older,
+}
+
+impl AgeBucket {
+    /// All buckets, newest first.
+    pub const ALL: [AgeBucket; 5] = [
+        AgeBucket::Week,
+        AgeBucket::Month,
+        AgeBucket::Quarter,
+        AgeBucket::Year,
+        AgeBucket::Older,
+    ];
+
+    /// Returns the bucket of a file modified `age` ago.
+    pub fn of(age: Duration) -> Self {
+        match age.as_secs() / DAY {
+            0..7 => Self::Week,
+            7..30 => Self::Month,
+            30..90 => Self::Quarter,
+            90..365 => Self::Year,
+            _ => Self::Older,
+        }
+    }
+
+    /// Returns a short label such as "7-30 days".
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Week => "< 7 days",
+            Self::Month => "7-30 days",
+            Self::Quarter => "30-90 days",
+            Self::Year => "90-365 days",
+            Self::Older => "> 1 year",
+        }
+    }
+}
+
+/// Files and bytes in one group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// A directory and its size.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryUsage {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Where the space of a scanned tree goes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// All files counted
+    pub total: Usage,
+
+    /// Largest directories below the scanned roots, largest first
+    pub largest_directories: Vec<DirectoryUsage>,
+
+    /// Files by lowercase extension ("" for none), largest first
+    pub by_extension: Vec<(String, Usage)>,
+
+    /// Files by age, newest bucket first; empty buckets are listed too
+    pub by_age: Vec<(AgeBucket, Usage)>,
+}
+
+/// Aggregates scan results into a [`UsageReport`].
+///
+/// Only files count towards the extension, age and total breakdowns:
+/// directory sizes already include their contents, and links and cloud
+/// placeholders take up no space locally. Directories are ranked by the
+/// sizes the scanner summed, leaving out the scanned roots themselves.
+#[derive(Debug, Clone)]
+pub struct UsageAnalyzer {
+    top: usize,
+    now: SystemTime,
+}
+
+impl Default for UsageAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UsageAnalyzer {
+    /// Creates an analyzer listing the 10 largest directories and extensions.
+    pub fn new() -> Self {
+        Self {
+            top: 10,
+            now: SystemTime::now(),
+        }
+    }
+
+    /// Sets how many directories and extensions are listed.
+    pub fn with_top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Measures file ages from `now` instead of the time of creation.
+    pub fn with_now(mut self, now: SystemTime) -> Self {
+        self.now = now;
+        self
+    }
+
+    /// Analyzes the entries of a scan.
+    pub fn analyze(&self, entries: &[FileEntry]) -> UsageReport {
+        let mut total = Usage::default();
+        let mut by_extension: HashMap<String, Usage> = HashMap::new();
+        let mut by_age: HashMap<AgeBucket, Usage> = HashMap::new();
+
+        for entry in entries
+            .iter()
+            .filter(|e| e.is_file() && !e.cloud_placeholder)
+        {
+            total.add(entry.size);
+            let extension = entry
+                .path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            by_extension.entry(extension).or_default().add(entry.size);
+            let age = self.now.duration_since(entry.modified).unwrap_or_default();
+            by_age
+                .entry(AgeBucket::of(age))
+                .or_default()
+                .add(entry.size);
+        }
+
+        let mut by_extension: Vec<_> = by_extension.into_iter().collect();
+        by_extension.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+        by_extension.truncate(self.top);
+
+        UsageReport {
+            total,
+            largest_directories: self.largest_directories(entries),
+            by_extension,
+            by_age: AgeBucket::ALL
+                .iter()
+                .map(|bucket| (*bucket, by_age.get(bucket).copied().unwrap_or_default()))
+                .collect(),
+        }
+    }
+
+    fn largest_directories(&self, entries: &[FileEntry]) -> Vec<DirectoryUsage> {
+        let paths: HashSet<&Path> = entries.iter().map(|e| e.path.as_path()).collect();
+        let is_root = |path: &Path| path.parent().is_none_or(|parent| !paths.contains(parent));
+
+        let mut directories: Vec<DirectoryUsage> = entries
+            .iter()
+            .filter(|e| e.is_directory() && !is_root(&e.path))
+            .map(|e| DirectoryUsage {
+                path: e.path.clone(),
+                bytes: e.size,
+            })
+            .collect();
+        directories.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+        directories.truncate(self.top);
+        directories
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+
+    fn entry(path: &str, size: u64, days: u64, entry_type: EntryType) -> FileEntry {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000 * DAY - days * DAY);
+        FileEntry::new(PathBuf::from(path), size, modified, entry_type)
+    }
+
+    #[test]
+    fn test_analyze() {
+        let entries = vec![
+            entry("/r", 1_750, 0, EntryType::Directory),
+            entry("/r/videos", 1_500, 0, EntryType::Directory),
+            entry("/r/videos/a.MP4", 1_000, 400, EntryType::File),
+            entry("/r/videos/b.mp4", 500, 10, EntryType::File),
+            entry("/r/docs", 250, 0, EntryType::Directory),
+            entry("/r/docs/notes.txt", 200, 1, EntryType::File),
+            entry("/r/docs/README", 50, 100, EntryType::File),
+            FileEntry {
+                cloud_placeholder: true,
+                ..entry("/r/docs/cloud.iso", 9_000, 1, EntryType::File)
+            },
+        ];
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000 * DAY);
+        let report = UsageAnalyzer::new()
+            .with_top(2)
+            .with_now(now)
+            .analyze(&entries);
+
+        assert_eq!(
+            report.total,
+            Usage {
+                files: 4,
+                bytes: 1_750
+            }
+        );
+        let dirs: Vec<_> = report
+            .largest_directories
+            .iter()
+            .map(|d| (d.path.to_str().unwrap(), d.bytes))
+            .collect();
+        assert_eq!(dirs, vec![("/r/videos", 1_500), ("/r/docs", 250)]);
+
+        assert_eq!(report.by_extension.len(), 2);
+        assert_eq!(report.by_extension[0].0, "mp4");
+        assert_eq!(report.by_extension[0].1.files, 2);
+        assert_eq!(report.by_extension[1].0, "txt");
+
+        let ages: Vec<_> = report.by_age.iter().map(|(_, u)| u.bytes).collect();
+        assert_eq!(ages, vec![200, 500, 0, 50, 1_000]);
+    }
+}
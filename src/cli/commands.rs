@@ -1,5 +1,8 @@
 //! Command-line argument definitions.
 
+use crate::executor::Shard;
+use crate::models::{ByteSize, CleanupAction};
+use crate::scanner::ImportFormat;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -13,6 +16,20 @@ pub struct Cli {
     #[arg(short, long, value_name = "FILE", global = true)]
     pub config: Option<PathBuf>,
 
+    /// Assume "yes" to confirmation prompts (required when stdin is not a terminal)
+    #[arg(short = 'y', long, visible_alias = "non-interactive", global = true)]
+    pub yes: bool,
+
+    /// Write plans, logs and scan caches unencrypted, whatever encryption.enabled says
+    /// (for debugging; encrypted files are still read)
+    #[arg(long, global = true)]
+    pub no_encrypt: bool,
+
+    /// Line-oriented output without progress bars, spinners or emoji, for screen
+    /// readers and log scraping
+    #[arg(long, global = true)]
+    pub plain: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -22,11 +39,11 @@ pub struct Cli {
 pub enum Commands {
     /// Scan a directory and generate a cleanup plan
     Scan {
-        /// Directory to scan
-        #[arg(value_name = "PATH")]
-        path: PathBuf,
+        /// Directories to scan into one plan, such as several drives
+        #[arg(value_name = "PATH", required = true, num_args = 1..)]
+        paths: Vec<PathBuf>,
 
-        /// Output plan file path
+        /// Output plan file path (a `.zst` extension writes a compressed plan)
         #[arg(short, long, value_name = "FILE", default_value = "cleanup-plan.yaml")]
         output: PathBuf,
 
@@ -34,13 +51,256 @@ pub enum Commands {
         #[arg(short = 'd', long)]
         max_depth: Option<usize>,
 
-        /// Skip hidden files and directories
-        #[arg(long, default_value_t = true)]
-        skip_hidden: bool,
+        /// Skip hidden files and directories [default: from config, true]
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        skip_hidden: Option<bool>,
+
+        /// Minimum file size to flag as large (e.g. 500MB, 1.5GiB; bare numbers are MB)
+        /// [default: from config, 100MB]
+        #[arg(long, value_name = "SIZE", value_parser = ByteSize::parse_mb)]
+        large_file_threshold: Option<ByteSize>,
+
+        /// Include detections vetoed by keep rules as Keep entries for auditing
+        #[arg(long)]
+        include_vetoed: bool,
+
+        /// Collapse directories marked for deletion into single summary entries
+        #[arg(long)]
+        summary: bool,
+
+        /// Incremental scan cache: subtrees whose directory mtime is unchanged
+        /// are reused from it, and it is refreshed after the scan
+        #[arg(long, value_name = "FILE", group = "cache")]
+        scan_cache: Option<PathBuf>,
+
+        /// Rescan incrementally, with a scan cache kept per scan root in the
+        /// user's cache directory
+        #[arg(long, group = "cache")]
+        incremental: bool,
+
+        /// Walk every directory even if the scan cache says it is unchanged
+        #[arg(long, requires = "cache")]
+        full_walk: bool,
+
+        /// Enumerate NTFS alternate data streams of files (Windows only)
+        /// [default: from config, false]
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        include_streams: Option<bool>,
+
+        /// Use the network filesystem strategy even if the path isn't detected as remote;
+        /// --remote=false never uses it [default: from config, scanner.remote.mode]
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        remote: Option<bool>,
+
+        /// Leave out paths matching this glob, without walking into them (repeatable;
+        /// added to scanner.exclude). Bare names match anywhere, e.g. node_modules
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Leave out what .gitignore and .megamaidignore files ignore
+        /// [default: from config, false]
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        respect_ignore_files: Option<bool>,
+
+        /// Don't descend into other filesystems, such as network shares, USB drives
+        /// and bind mounts [default: from config, false]
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        same_filesystem: Option<bool>,
+
+        /// Walk the largest subdirectories first and list each as it finishes, so the
+        /// biggest space consumers show up early [default: from config, false]
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true"
+        )]
+        largest_first: Option<bool>,
+
+        /// Also write every scanned entry to this file, as Parquet (.parquet, needs the
+        /// `parquet` feature) or CSV (any other extension)
+        #[arg(long, value_name = "FILE")]
+        export_entries: Option<PathBuf>,
+
+        /// Also write every scanned entry to this file as JSON Lines, for running
+        /// detection again with `megamaid detect --from-scan` without rescanning
+        #[arg(long, value_name = "FILE")]
+        raw_output: Option<PathBuf>,
+
+        /// Also write a JSON summary of the scan (duration, errors, per-rule statistics,
+        /// largest directories, volumes) to FILE for dashboards and monitoring
+        /// [default: scan-summary.json next to the plan]
+        #[arg(long, value_name = "FILE", num_args = 0..=1, require_equals = true)]
+        summary_file: Option<Option<PathBuf>>,
+
+        /// Write the plan even if it marks more than detector.max_delete_fraction of the
+        /// scanned bytes for deletion
+        #[arg(long)]
+        force: bool,
+
+        /// After detection, interactively adjust the large file threshold, re-running
+        /// detection on the scanned entries without walking the disk again
+        #[arg(long)]
+        tune: bool,
+    },
+
+    /// Generate a cleanup plan from a raw scan written by `scan --raw-output`
+    ///
+    /// Runs the detection rules of the current configuration on the recorded
+    /// entries without walking the disk again.
+    Detect {
+        /// Raw scan file (JSON Lines)
+        #[arg(long, value_name = "FILE")]
+        from_scan: PathBuf,
+
+        /// Output plan file path (a `.zst` extension writes a compressed plan)
+        #[arg(short, long, value_name = "FILE", default_value = "cleanup-plan.yaml")]
+        output: PathBuf,
+
+        /// Minimum file size to flag as large (e.g. 500MB, 1.5GiB; bare numbers are MB)
+        /// [default: from config, 100MB]
+        #[arg(long, value_name = "SIZE", value_parser = ByteSize::parse_mb)]
+        large_file_threshold: Option<ByteSize>,
+
+        /// Include detections vetoed by keep rules as Keep entries for auditing
+        #[arg(long)]
+        include_vetoed: bool,
+
+        /// Collapse directories marked for deletion into single summary entries
+        #[arg(long)]
+        summary: bool,
+
+        /// Write the plan even if it marks more than detector.max_delete_fraction of the
+        /// scanned bytes for deletion
+        #[arg(long)]
+        force: bool,
+
+        /// After detection, interactively adjust the large file threshold
+        #[arg(long)]
+        tune: bool,
+    },
+
+    /// Quickly estimate subtree sizes by sampling instead of walking everything
+    Estimate {
+        /// Directory to estimate
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        /// Random probes per subtree; more probes narrow the confidence intervals
+        #[arg(long, default_value_t = 200, value_parser = clap::value_parser!(u64).range(1..))]
+        probes: u64,
+
+        /// Subtrees with at most this many directories are counted exactly
+        #[arg(long, value_name = "DIRS", default_value_t = 200)]
+        exact_limit: usize,
+
+        /// Seed for the probe sequence (same seed, same estimate)
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Number of subtrees to list
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+
+    /// Show where the space goes: largest directories, extensions and file ages
+    ///
+    /// Scans the directory without generating a plan.
+    Analyze {
+        /// Directory to analyze
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        /// Number of directories and extensions to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// List groups of identical files and the space their extra copies take
+    ///
+    /// Scans the directory without generating a plan; files are compared by
+    /// size, then by content hash.
+    Duplicates {
+        /// Directory to search
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        /// Leave out files smaller than this (e.g. 100KB; bare numbers are MB)
+        /// [default: from config, 1MB]
+        #[arg(long, value_name = "SIZE", value_parser = ByteSize::parse_mb)]
+        min_size: Option<ByteSize>,
+
+        /// Number of groups to list
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+
+    /// Keep watching a directory, printing updated cleanup candidates as it changes
+    Watch {
+        /// Directory to watch
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
 
-        /// Minimum file size in MB to flag as large
-        #[arg(long, default_value_t = 100)]
-        large_file_threshold: u64,
+        /// Seconds between checks for changes
+        #[arg(long, value_name = "SECS", default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+
+        /// Without file change notifications, only walk directories whose mtime
+        /// changed since the last check; faster on large trees, but misses changes
+        /// deeper below unchanged directories
+        #[arg(long)]
+        incremental: bool,
+
+        /// Print each update as a one-line JSON plan, for dashboards and the UI
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Expand a collapsed directory entry into its immediate children
+    Expand {
+        /// Path to cleanup plan file
+        #[arg(value_name = "FILE")]
+        plan: PathBuf,
+
+        /// Entry path as listed in the plan, or its full path when the plan
+        /// has several roots
+        #[arg(value_name = "ENTRY")]
+        entry: String,
+
+        /// Write the expanded plan here instead of updating it in place
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
     },
 
     /// Display statistics about a cleanup plan
@@ -48,6 +308,14 @@ pub enum Commands {
         /// Path to cleanup plan file
         #[arg(value_name = "FILE")]
         plan: PathBuf,
+
+        /// Print the statistics as JSON, in the same shape the desktop UI uses
+        #[arg(long)]
+        json: bool,
+
+        /// List the detections that protections suppressed or downgraded
+        #[arg(long)]
+        protections: bool,
     },
 
     /// Verify a cleanup plan against current filesystem state
@@ -67,6 +335,20 @@ pub enum Commands {
         /// Skip modification time checks
         #[arg(long)]
         skip_mtime: bool,
+
+        /// Re-verify every SECS seconds until the plan is clean
+        #[arg(
+            long,
+            value_name = "SECS",
+            num_args = 0..=1,
+            default_missing_value = "5",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        watch: Option<u64>,
+
+        /// Stop watching after SECS seconds and fail if drift remains
+        #[arg(long, value_name = "SECS", requires = "watch")]
+        watch_timeout: Option<u64>,
     },
 
     /// Execute a cleanup plan
@@ -99,6 +381,47 @@ pub enum Commands {
         #[arg(long)]
         skip_verify: bool,
 
+        /// Reuse a recent verification record instead of re-verifying
+        /// (only quick per-entry checks are performed)
+        #[arg(long, value_name = "FILE", conflicts_with = "skip_verify")]
+        use_verification: Option<PathBuf>,
+
+        /// Execute only shard K of N (e.g. 2/4); shards partition entries deterministically
+        #[arg(long, value_name = "K/N")]
+        shard: Option<Shard>,
+
+        /// Resume a paused execution, appending to its transaction log
+        #[arg(long, value_name = "LOG", conflicts_with_all = ["log_file", "shard"])]
+        resume: Option<PathBuf>,
+
+        /// Skip entries an earlier execution's log records as removed
+        /// (matched by entry ID, so a regenerated plan of the same tree works)
+        #[arg(long, value_name = "LOG", conflicts_with = "resume")]
+        since_log: Option<PathBuf>,
+
+        /// Retry only the entries an earlier execution's log records as failed
+        #[arg(long, value_name = "LOG", conflicts_with_all = ["resume", "since_log"])]
+        only_failed: Option<PathBuf>,
+
+        /// Process the largest entries first
+        #[arg(long)]
+        largest_first: bool,
+
+        /// Execute a plan older than executor.max_plan_age_days
+        #[arg(long)]
+        allow_stale_plan: bool,
+
+        /// After recycling, permanently delete this run's items from the recycle bin
+        /// (asks for confirmation unless --yes)
+        #[arg(long)]
+        empty_recycle_bin_after: bool,
+
+        /// Snapshot the volume first (Windows VSS, btrfs, ZFS or executor.snapshot_command),
+        /// so the run can be rolled back with `snapshot rollback`
+        /// [default: when deleting at least executor.snapshot_threshold]
+        #[arg(long)]
+        snapshot: bool,
+
         /// Transaction log file path
         #[arg(long, value_name = "FILE", default_value = "execution-log.yaml")]
         log_file: PathBuf,
@@ -107,72 +430,841 @@ pub enum Commands {
         #[arg(long)]
         parallel: bool,
 
-        /// Batch size for parallel processing
-        #[arg(long, default_value = "100")]
-        batch_size: usize,
+        /// Batch size for parallel processing [default: from config, 100]
+        #[arg(long)]
+        batch_size: Option<usize>,
+
+        /// Stop waiting for a deletion or move that hasn't finished after this many
+        /// seconds and continue; a blocked call may still finish in the background
+        /// [default: from config, no limit]
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        operation_timeout: Option<u64>,
+
+        /// Only delete while nobody has used the keyboard or mouse for MINUTES and the
+        /// disks are quiet, pausing as soon as input resumes
+        /// [default: executor.idle.min_idle_minutes from config, 5]
+        #[arg(
+            long,
+            value_name = "MINUTES",
+            num_args = 0..=1,
+            require_equals = true,
+            conflicts_with = "interactive"
+        )]
+        when_idle: Option<Option<u64>>,
+
+        /// Delete as another account (DOMAIN\user, Windows only), so recycled items go
+        /// to that user's recycle bin and their permissions apply; the password comes
+        /// from executor.run_as_secret or is prompted for [default: executor.run_as]
+        #[arg(long, value_name = "ACCOUNT")]
+        run_as: Option<String>,
+    },
+
+    /// Build cleanup plans without scanning
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommands,
+    },
+
+    /// Inspect transaction logs
+    Log {
+        #[command(subcommand)]
+        command: LogCommands,
+    },
+
+    /// Manage volume snapshots taken before executions
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
+    /// Manage the backups left by backup-mode executions
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommands,
+    },
+
+    /// Manage the items recycle-bin executions moved to the recycle bin
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+
+    /// Manage the custom rules in the config file
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommands,
+    },
+
+    /// Manage secrets stored in the OS keyring
+    Secrets {
+        #[command(subcommand)]
+        command: SecretsCommands,
+    },
+
+    /// Collect information for bug reports
+    Diagnostics {
+        #[command(subcommand)]
+        command: DiagnosticsCommands,
+    },
+}
+
+/// Plan building subcommands
+#[derive(Subcommand, Debug)]
+pub enum PlanCommands {
+    /// Build a plan from a list of paths (one per line; blank lines and # comments ignored)
+    FromPaths {
+        /// File listing the paths to include
+        #[arg(value_name = "FILE")]
+        list: PathBuf,
+
+        /// Action for every listed path: delete, review or keep
+        #[arg(long, default_value = "review")]
+        action: CleanupAction,
+
+        /// Reason recorded on every entry (e.g. a ticket number)
+        #[arg(long)]
+        reason: String,
+
+        /// Base directory of the plan; relative paths in the list are resolved against it
+        /// [default: deepest directory containing all listed paths]
+        #[arg(long, value_name = "DIR")]
+        base: Option<PathBuf>,
+
+        /// Output plan file path
+        #[arg(short, long, default_value = "cleanup-plan.yaml")]
+        output: PathBuf,
+
+        /// Leave out listed paths that don't exist instead of failing
+        #[arg(long)]
+        skip_missing: bool,
+    },
+
+    /// Build a plan from another tool's scan (an ncdu JSON export or du output)
+    Import {
+        /// File written by the other tool
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Format of the file: ncdu or du [default: ncdu for .json files, otherwise du]
+        #[arg(long)]
+        format: Option<ImportFormat>,
+
+        /// du sizes are in bytes (du -b) rather than 1K blocks
+        #[arg(long)]
+        du_bytes: bool,
+
+        /// Output plan file path
+        #[arg(short, long, default_value = "cleanup-plan.yaml")]
+        output: PathBuf,
+
+        /// Minimum file size to flag as large (e.g. 500MB, 1.5GiB; bare numbers are MB)
+        /// [default: from config, 100MB]
+        #[arg(long, value_name = "SIZE", value_parser = ByteSize::parse_mb)]
+        large_file_threshold: Option<ByteSize>,
+
+        /// Write the plan even if it marks more than detector.max_delete_fraction of the
+        /// imported bytes for deletion
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Transaction log subcommands
+#[derive(Subcommand, Debug)]
+pub enum LogCommands {
+    /// Merge one or more transaction logs (e.g. from sharded runs) into one summary
+    Summarize {
+        /// Transaction log files
+        #[arg(value_name = "FILE", required = true)]
+        logs: Vec<PathBuf>,
+    },
+}
+
+/// Volume snapshot subcommands
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommands {
+    /// List the snapshots recorded in transaction logs and whether they still exist
+    List {
+        /// Transaction log files
+        #[arg(value_name = "FILE", required = true)]
+        logs: Vec<PathBuf>,
+    },
+
+    /// Restore the entries an execution deleted from its snapshot
+    Rollback {
+        /// Transaction log of the execution
+        #[arg(value_name = "FILE")]
+        log: PathBuf,
+    },
+}
+
+/// Backup subcommands
+#[derive(Subcommand, Debug)]
+pub enum BackupCommands {
+    /// Permanently delete the backups recorded in transaction logs
+    ///
+    /// Each backup is first checked against its original path; if anything
+    /// exists there again, nothing is deleted unless --force is given.
+    Prune {
+        /// Transaction log files of backup-mode executions
+        #[arg(value_name = "FILE", required = true)]
+        logs: Vec<PathBuf>,
+
+        /// Prune even if backed-up entries exist again at their original path
+        #[arg(long)]
+        force: bool,
+
+        /// Only verify the backups and show what would be deleted
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Recycle bin subcommands
+#[derive(Subcommand, Debug)]
+pub enum TrashCommands {
+    /// List the items megamaid recycled that are still in the recycle bin
+    List,
+
+    /// Permanently delete the items megamaid recycled from the recycle bin
+    ///
+    /// Items other programs recycled are left alone.
+    Purge {
+        /// Only purge items recycled at least this many days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+
+        /// Only show what would be purged
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Custom rule subcommands
+///
+/// They edit the `detector.custom_rules` section of the file given with
+/// --config, or of the first default config file (megamaid.yaml if there is
+/// none yet).
+#[derive(Subcommand, Debug)]
+pub enum RulesCommands {
+    /// List the custom rules, in the order they are checked
+    List,
+
+    /// Add a custom rule after the existing ones
+    Add {
+        /// Rule name
+        name: String,
+
+        /// Rule description
+        #[arg(long)]
+        description: String,
+
+        /// Glob pattern the path has to match (e.g. '**/*.log')
+        #[arg(long)]
+        pattern: Option<String>,
+
+        /// File extension to match, with the dot (repeatable)
+        #[arg(long = "ext", value_name = "EXT")]
+        extensions: Vec<String>,
+
+        /// Minimum age in days
+        #[arg(long, value_name = "DAYS")]
+        min_age_days: Option<u64>,
+
+        /// Minimum size (e.g. 500MB, 1.5GiB; bare numbers are MB)
+        #[arg(long, value_name = "SIZE", value_parser = ByteSize::parse_mb)]
+        min_size: Option<ByteSize>,
+
+        /// Expression combining rules and entry properties (e.g. 'build_artifact AND age > 30d')
+        #[arg(long, value_name = "EXPR")]
+        when: Option<String>,
+
+        /// Action for what the rule flags: delete, review or keep
+        #[arg(long, default_value = "review")]
+        action: CleanupAction,
+
+        /// Replace a rule with the same name, keeping its place in the order
+        #[arg(long)]
+        replace: bool,
+    },
+
+    /// Scan a directory and show what a custom rule flags there
+    Test {
+        /// Rule name
+        name: String,
+
+        /// Directory to scan
+        #[arg(long, value_name = "DIR")]
+        path: PathBuf,
+
+        /// Number of the largest flagged entries to list
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Remove a custom rule
+    Remove {
+        /// Rule name
+        name: String,
+    },
+}
+
+/// Keyring secret subcommands
+#[derive(Subcommand, Debug)]
+pub enum SecretsCommands {
+    /// Store a secret, read from standard input
+    Set {
+        /// Secret name (letters, digits, '.', '_' and '-')
+        name: String,
+    },
+
+    /// Print a stored secret
+    Get {
+        /// Secret name
+        name: String,
+    },
+
+    /// Remove a stored secret
+    Remove {
+        /// Secret name
+        name: String,
+    },
+}
+
+/// Diagnostics subcommands
+#[derive(Subcommand, Debug)]
+pub enum DiagnosticsCommands {
+    /// Zip version, redacted config, recent logs, scan errors and crash reports
+    Collect {
+        /// Output zip file path
+        #[arg(short, long, default_value = "megamaid-diagnostics.zip")]
+        output: PathBuf,
+
+        /// Transaction log to include (repeatable)
+        /// [default: the newest logs in the current directory]
+        #[arg(long = "log", value_name = "FILE")]
+        logs: Vec<PathBuf>,
+
+        /// Number of logs to pick from the current directory when no --log is given
+        #[arg(long, value_name = "N", default_value_t = 3)]
+        max_logs: usize,
+
+        /// Walk this directory and summarize the errors a scan would meet (repeatable)
+        #[arg(long = "scan-path", value_name = "DIR")]
+        scan_paths: Vec<PathBuf>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_parsing_scan_command() {
+        let args = vec!["megamaid", "scan", "/test/path"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Scan { paths, output, .. } => {
+                assert_eq!(paths, vec![PathBuf::from("/test/path")]);
+                assert_eq!(output, PathBuf::from("cleanup-plan.yaml"));
+            }
+            _ => panic!("Expected Scan command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_scan_multiple_roots() {
+        let args = vec!["megamaid", "scan", "C:\\dev", "D:\\data", "-o", "plan.yaml"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Scan { paths, output, .. } => {
+                assert_eq!(
+                    paths,
+                    vec![PathBuf::from("C:\\dev"), PathBuf::from("D:\\data")]
+                );
+                assert_eq!(output, PathBuf::from("plan.yaml"));
+            }
+            _ => panic!("Expected Scan command"),
+        }
+        assert!(Cli::try_parse_from(["megamaid", "scan"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_scan_exclude() {
+        let args = vec![
+            "megamaid",
+            "scan",
+            "/test",
+            "--exclude",
+            "node_modules",
+            "--exclude",
+            "%APPDATA%",
+        ];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Scan {
+                exclude,
+                respect_ignore_files,
+                same_filesystem,
+                largest_first,
+                ..
+            } => {
+                assert_eq!(exclude, vec!["node_modules", "%APPDATA%"]);
+                assert_eq!(respect_ignore_files, None);
+                assert_eq!(same_filesystem, None);
+                assert_eq!(largest_first, None);
+            }
+            _ => panic!("Expected Scan command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_scan_cache() {
+        let args = vec![
+            "megamaid",
+            "scan",
+            "/test",
+            "--scan-cache",
+            "scan-cache.json",
+            "--full-walk",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Scan {
+                scan_cache,
+                full_walk,
+                ..
+            } => {
+                assert_eq!(scan_cache, Some(PathBuf::from("scan-cache.json")));
+                assert!(full_walk);
+            }
+            _ => panic!("Expected Scan command"),
+        }
+
+        // --full-walk only makes sense with a cache
+        assert!(Cli::try_parse_from(["megamaid", "scan", "/test", "--full-walk"]).is_err());
+
+        let args = vec!["megamaid", "scan", "/test", "--incremental", "--full-walk"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Scan {
+                incremental,
+                scan_cache,
+                full_walk,
+                ..
+            } => {
+                assert!(incremental);
+                assert_eq!(scan_cache, None);
+                assert!(full_walk);
+            }
+            _ => panic!("Expected Scan command"),
+        }
+        let args = [
+            "megamaid",
+            "scan",
+            "/t",
+            "--incremental",
+            "--scan-cache",
+            "c",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        let args = vec!["megamaid", "scan", "/test", "--export-entries", "e.parquet"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Scan {
+                export_entries,
+                force,
+                tune,
+                ..
+            } => {
+                assert_eq!(export_entries, Some(PathBuf::from("e.parquet")));
+                assert!(!force);
+                assert!(!tune);
+            }
+            _ => panic!("Expected Scan command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_scan_with_options() {
+        let args = vec![
+            "megamaid",
+            "scan",
+            "/test",
+            "--output",
+            "my-plan.yaml",
+            "--max-depth",
+            "5",
+            "--large-file-threshold",
+            "200",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Scan {
+                paths,
+                output,
+                max_depth,
+                large_file_threshold,
+                ..
+            } => {
+                assert_eq!(paths, vec![PathBuf::from("/test")]);
+                assert_eq!(output, PathBuf::from("my-plan.yaml"));
+                assert_eq!(max_depth, Some(5));
+                assert_eq!(large_file_threshold, Some(ByteSize::from_mb(200)));
+            }
+            _ => panic!("Expected Scan command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_human_size_threshold() {
+        let args = vec![
+            "megamaid",
+            "scan",
+            "/test",
+            "--large-file-threshold",
+            "1.5GiB",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Scan {
+                large_file_threshold,
+                ..
+            } => assert_eq!(large_file_threshold, Some(ByteSize::from_mb(1536))),
+            _ => panic!("Expected Scan command"),
+        }
+
+        let args = vec![
+            "megamaid",
+            "scan",
+            "/test",
+            "--large-file-threshold",
+            "500Mb",
+        ];
+        let err = Cli::try_parse_from(args).unwrap_err();
+        assert!(err.to_string().contains("bits"));
+    }
+
+    #[test]
+    fn test_cli_parsing_skip_hidden_override() {
+        let cases = [
+            (
+                vec!["megamaid", "scan", "--skip-hidden", "/test"],
+                Some(true),
+            ),
+            (
+                vec!["megamaid", "scan", "/test", "--skip-hidden=false"],
+                Some(false),
+            ),
+            (vec!["megamaid", "scan", "/test"], None),
+        ];
+
+        for (args, expected) in cases {
+            match Cli::try_parse_from(args).unwrap().command {
+                Commands::Scan {
+                    paths, skip_hidden, ..
+                } => {
+                    assert_eq!(paths, vec![PathBuf::from("/test")]);
+                    assert_eq!(skip_hidden, expected);
+                }
+                _ => panic!("Expected Scan command"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_respect_ignore_files_override() {
+        let cases = [
+            (
+                vec!["megamaid", "scan", "--respect-ignore-files", "/test"],
+                Some(true),
+            ),
+            (
+                vec!["megamaid", "scan", "/test", "--respect-ignore-files=false"],
+                Some(false),
+            ),
+            (vec!["megamaid", "scan", "/test"], None),
+        ];
+
+        for (args, expected) in cases {
+            match Cli::try_parse_from(args).unwrap().command {
+                Commands::Scan {
+                    respect_ignore_files,
+                    ..
+                } => assert_eq!(respect_ignore_files, expected),
+                _ => panic!("Expected Scan command"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_same_filesystem_override() {
+        let cases = [
+            (
+                vec!["megamaid", "scan", "--same-filesystem", "/test"],
+                Some(true),
+            ),
+            (
+                vec!["megamaid", "scan", "/test", "--same-filesystem=false"],
+                Some(false),
+            ),
+            (vec!["megamaid", "scan", "/test"], None),
+        ];
+
+        for (args, expected) in cases {
+            match Cli::try_parse_from(args).unwrap().command {
+                Commands::Scan {
+                    same_filesystem, ..
+                } => assert_eq!(same_filesystem, expected),
+                _ => panic!("Expected Scan command"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_include_streams_and_remote_override() {
+        let cases = [
+            (
+                vec!["megamaid", "scan", "--include-streams", "--remote", "/test"],
+                Some(true),
+            ),
+            (
+                vec![
+                    "megamaid",
+                    "scan",
+                    "/test",
+                    "--include-streams=false",
+                    "--remote=false",
+                ],
+                Some(false),
+            ),
+            (vec!["megamaid", "scan", "/test"], None),
+        ];
+
+        for (args, expected) in cases {
+            match Cli::try_parse_from(args).unwrap().command {
+                Commands::Scan {
+                    include_streams,
+                    remote,
+                    ..
+                } => {
+                    assert_eq!(include_streams, expected);
+                    assert_eq!(remote, expected);
+                }
+                _ => panic!("Expected Scan command"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_largest_first_override() {
+        let cases = [
+            (
+                vec!["megamaid", "scan", "--largest-first", "/test"],
+                Some(true),
+            ),
+            (
+                vec!["megamaid", "scan", "/test", "--largest-first=false"],
+                Some(false),
+            ),
+            (vec!["megamaid", "scan", "/test"], None),
+        ];
+
+        for (args, expected) in cases {
+            match Cli::try_parse_from(args).unwrap().command {
+                Commands::Scan { largest_first, .. } => assert_eq!(largest_first, expected),
+                _ => panic!("Expected Scan command"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_global_yes() {
+        let cli = Cli::try_parse_from(["megamaid", "execute", "plan.yaml", "--yes"]).unwrap();
+        assert!(cli.yes);
+
+        let cli =
+            Cli::try_parse_from(["megamaid", "--non-interactive", "execute", "plan.yaml"]).unwrap();
+        assert!(cli.yes);
+
+        let cli = Cli::try_parse_from(["megamaid", "execute", "plan.yaml"]).unwrap();
+        assert!(!cli.yes);
+        assert!(!cli.no_encrypt);
+
+        let cli = Cli::try_parse_from(["megamaid", "scan", ".", "--no-encrypt"]).unwrap();
+        assert!(cli.no_encrypt);
+        assert!(!cli.plain);
+
+        let cli = Cli::try_parse_from(["megamaid", "--plain", "stats", "plan.yaml"]).unwrap();
+        assert!(cli.plain);
+    }
+
+    #[test]
+    fn test_cli_parsing_stats_command() {
+        let args = vec!["megamaid", "stats", "plan.yaml"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Stats {
+                plan,
+                json,
+                protections,
+            } => {
+                assert_eq!(plan, PathBuf::from("plan.yaml"));
+                assert!(!json);
+                assert!(!protections);
+            }
+            _ => panic!("Expected Stats command"),
+        }
+
+        let args = vec!["megamaid", "stats", "plan.yaml", "--json", "--protections"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Stats {
+                json, protections, ..
+            } => assert!(json && protections),
+            _ => panic!("Expected Stats command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_estimate_command() {
+        let args = vec![
+            "megamaid", "estimate", "/data", "--probes", "50", "--seed", "7",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Estimate {
+                path,
+                probes,
+                exact_limit,
+                seed,
+                top,
+            } => {
+                assert_eq!(path, PathBuf::from("/data"));
+                assert_eq!(probes, 50);
+                assert_eq!(exact_limit, 200);
+                assert_eq!(seed, Some(7));
+                assert_eq!(top, 20);
+            }
+            _ => panic!("Expected Estimate command"),
+        }
+
+        let args = vec!["megamaid", "estimate", "/data", "--probes", "0"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
 
     #[test]
-    fn test_cli_parsing_scan_command() {
-        let args = vec!["megamaid", "scan", "/test/path"];
+    fn test_cli_parsing_plan_from_paths() {
+        let args = vec![
+            "megamaid",
+            "plan",
+            "from-paths",
+            "paths.txt",
+            "--action",
+            "delete",
+            "--reason",
+            "ticket-1234",
+        ];
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Scan { path, output, .. } => {
-                assert_eq!(path, PathBuf::from("/test/path"));
+            Commands::Plan {
+                command:
+                    PlanCommands::FromPaths {
+                        list,
+                        action,
+                        reason,
+                        base,
+                        output,
+                        skip_missing,
+                    },
+            } => {
+                assert_eq!(list, PathBuf::from("paths.txt"));
+                assert_eq!(action, CleanupAction::Delete);
+                assert_eq!(reason, "ticket-1234");
+                assert_eq!(base, None);
                 assert_eq!(output, PathBuf::from("cleanup-plan.yaml"));
+                assert!(!skip_missing);
             }
-            _ => panic!("Expected Scan command"),
+            _ => panic!("Expected Plan from-paths command"),
         }
+
+        let args = vec![
+            "megamaid",
+            "plan",
+            "from-paths",
+            "paths.txt",
+            "--action",
+            "remove",
+            "--reason",
+            "x",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        let args = vec!["megamaid", "plan", "from-paths", "paths.txt"];
+        assert!(Cli::try_parse_from(args).is_err(), "--reason is required");
     }
 
     #[test]
-    fn test_cli_parsing_scan_with_options() {
+    fn test_cli_parsing_plan_import() {
         let args = vec![
             "megamaid",
-            "scan",
-            "/test",
-            "--output",
-            "my-plan.yaml",
-            "--max-depth",
-            "5",
+            "plan",
+            "import",
+            "export.json",
+            "--format",
+            "ncdu",
             "--large-file-threshold",
-            "200",
+            "1GB",
         ];
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Scan {
-                path,
-                output,
-                max_depth,
-                large_file_threshold,
-                ..
+            Commands::Plan {
+                command:
+                    PlanCommands::Import {
+                        file,
+                        format,
+                        du_bytes,
+                        output,
+                        large_file_threshold,
+                        force,
+                    },
             } => {
-                assert_eq!(path, PathBuf::from("/test"));
-                assert_eq!(output, PathBuf::from("my-plan.yaml"));
-                assert_eq!(max_depth, Some(5));
-                assert_eq!(large_file_threshold, 200);
+                assert_eq!(file, PathBuf::from("export.json"));
+                assert_eq!(format, Some(ImportFormat::Ncdu));
+                assert!(!du_bytes);
+                assert_eq!(output, PathBuf::from("cleanup-plan.yaml"));
+                assert_eq!(large_file_threshold, Some(ByteSize::from_mb(1024)));
+                assert!(!force);
             }
-            _ => panic!("Expected Scan command"),
+            _ => panic!("Expected Plan import command"),
         }
+
+        let args = vec!["megamaid", "plan", "import", "x", "--format", "windirstat"];
+        assert!(Cli::try_parse_from(args).is_err());
     }
 
     #[test]
-    fn test_cli_parsing_stats_command() {
-        let args = vec!["megamaid", "stats", "plan.yaml"];
+    fn test_cli_parsing_expand_command() {
+        let args = vec!["megamaid", "expand", "plan.yaml", "node_modules"];
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Stats { plan } => {
+            Commands::Expand {
+                plan,
+                entry,
+                output,
+            } => {
                 assert_eq!(plan, PathBuf::from("plan.yaml"));
+                assert_eq!(entry, "node_modules");
+                assert!(output.is_none());
             }
-            _ => panic!("Expected Stats command"),
+            _ => panic!("Expected Expand command"),
         }
     }
 
@@ -188,8 +1280,9 @@ mod tests {
                 max_depth,
                 ..
             } => {
-                assert!(skip_hidden);
-                assert_eq!(large_file_threshold, 100);
+                // Absent flags defer to the config file
+                assert_eq!(skip_hidden, None);
+                assert_eq!(large_file_threshold, None);
                 assert_eq!(max_depth, None);
             }
             _ => panic!("Expected Scan command"),
@@ -207,11 +1300,15 @@ mod tests {
                 output,
                 fail_fast,
                 skip_mtime,
+                watch,
+                watch_timeout,
             } => {
                 assert_eq!(plan, PathBuf::from("plan.yaml"));
                 assert_eq!(output, None);
                 assert!(!fail_fast);
                 assert!(!skip_mtime);
+                assert_eq!(watch, None);
+                assert_eq!(watch_timeout, None);
             }
             _ => panic!("Expected Verify command"),
         }
@@ -236,6 +1333,7 @@ mod tests {
                 output,
                 fail_fast,
                 skip_mtime,
+                ..
             } => {
                 assert_eq!(plan, PathBuf::from("plan.yaml"));
                 assert_eq!(output, Some(PathBuf::from("drift-report.txt")));
@@ -246,6 +1344,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_watch() {
+        let cli = Cli::try_parse_from(["megamaid", "watch", "/data"]).unwrap();
+        match cli.command {
+            Commands::Watch {
+                path,
+                interval,
+                incremental,
+                json,
+            } => {
+                assert_eq!(path, PathBuf::from("/data"));
+                assert_eq!(interval, 30);
+                assert!(!incremental);
+                assert!(!json);
+            }
+            _ => panic!("Expected Watch command"),
+        }
+
+        let args = ["megamaid", "watch", "/data", "--interval", "0"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_verify_watch() {
+        let cli = Cli::try_parse_from(["megamaid", "verify", "plan.yaml", "--watch"]).unwrap();
+        match cli.command {
+            Commands::Verify { watch, .. } => assert_eq!(watch, Some(5)),
+            _ => panic!("Expected Verify command"),
+        }
+
+        let args = [
+            "megamaid",
+            "verify",
+            "plan.yaml",
+            "--watch=10",
+            "--watch-timeout",
+            "600",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Verify {
+                watch,
+                watch_timeout,
+                ..
+            } => {
+                assert_eq!(watch, Some(10));
+                assert_eq!(watch_timeout, Some(600));
+            }
+            _ => panic!("Expected Verify command"),
+        }
+
+        // A timeout without --watch is rejected
+        let args = ["megamaid", "verify", "plan.yaml", "--watch-timeout", "600"];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        // A zero interval would re-verify in a busy loop
+        let args = ["megamaid", "verify", "plan.yaml", "--watch=0"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
     #[test]
     fn test_cli_parsing_execute_command() {
         let args = vec!["megamaid", "execute", "plan.yaml"];
@@ -260,20 +1418,44 @@ mod tests {
                 recycle_bin,
                 fail_fast,
                 skip_verify,
+                use_verification,
+                shard,
+                resume,
+                since_log,
+                only_failed,
+                largest_first,
+                allow_stale_plan,
+                empty_recycle_bin_after,
+                snapshot,
                 log_file,
                 parallel,
                 batch_size,
+                operation_timeout,
+                when_idle,
+                run_as,
             } => {
                 assert_eq!(plan, PathBuf::from("plan.yaml"));
+                assert_eq!(run_as, None);
                 assert!(!dry_run);
                 assert!(!interactive);
                 assert_eq!(backup_dir, None);
                 assert!(!recycle_bin);
                 assert!(!fail_fast);
                 assert!(!skip_verify);
+                assert_eq!(use_verification, None);
+                assert_eq!(shard, None);
+                assert_eq!(resume, None);
+                assert_eq!(since_log, None);
+                assert_eq!(only_failed, None);
+                assert!(!largest_first);
+                assert!(!allow_stale_plan);
+                assert!(!empty_recycle_bin_after);
+                assert!(!snapshot);
                 assert_eq!(log_file, PathBuf::from("execution-log.yaml"));
                 assert!(!parallel);
-                assert_eq!(batch_size, 100);
+                assert_eq!(batch_size, None);
+                assert_eq!(operation_timeout, None);
+                assert_eq!(when_idle, None);
             }
             _ => panic!("Expected Execute command"),
         }
@@ -308,6 +1490,433 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_execute_when_idle() {
+        let when_idle = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+            Commands::Execute { when_idle, .. } => when_idle,
+            _ => panic!("Expected Execute command"),
+        };
+
+        let base = ["megamaid", "execute", "plan.yaml"];
+        assert_eq!(when_idle(&base), None);
+        assert_eq!(
+            when_idle(&[&base[..], &["--when-idle"]].concat()),
+            Some(None)
+        );
+        assert_eq!(
+            when_idle(&[&base[..], &["--when-idle=15"]].concat()),
+            Some(Some(15))
+        );
+
+        // Nobody is around to answer prompts while the system is idle
+        let args = ["megamaid", "execute", "plan.yaml", "--when-idle", "-i"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_execute_run_as() {
+        let args = ["megamaid", "execute", "plan.yaml", "--run-as", "CORP\\jdoe"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Execute { run_as, .. } => assert_eq!(run_as.as_deref(), Some("CORP\\jdoe")),
+            _ => panic!("Expected Execute command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_execute_with_verification_record() {
+        let args = vec![
+            "megamaid",
+            "execute",
+            "plan.yaml",
+            "--use-verification",
+            "plan.yaml.verification.yaml",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Execute {
+                use_verification, ..
+            } => {
+                assert_eq!(
+                    use_verification,
+                    Some(PathBuf::from("plan.yaml.verification.yaml"))
+                );
+            }
+            _ => panic!("Expected Execute command"),
+        }
+
+        // Reusing a record and skipping verification are mutually exclusive
+        let args = vec![
+            "megamaid",
+            "execute",
+            "plan.yaml",
+            "--skip-verify",
+            "--use-verification",
+            "record.yaml",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_execute_with_shard() {
+        let args = vec!["megamaid", "execute", "plan.yaml", "--shard", "2/4"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Execute { shard, .. } => {
+                assert_eq!(shard, Some(Shard { index: 2, count: 4 }));
+            }
+            _ => panic!("Expected Execute command"),
+        }
+
+        let args = vec!["megamaid", "execute", "plan.yaml", "--shard", "5/4"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_execute_resume() {
+        let args = vec![
+            "megamaid",
+            "execute",
+            "plan.yaml",
+            "--resume",
+            "execution-log.yaml",
+            "--largest-first",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Execute {
+                resume,
+                largest_first,
+                ..
+            } => {
+                assert_eq!(resume, Some(PathBuf::from("execution-log.yaml")));
+                assert!(largest_first);
+            }
+            _ => panic!("Expected Execute command"),
+        }
+
+        // The resumed log is the output log
+        let args = vec![
+            "megamaid",
+            "execute",
+            "plan.yaml",
+            "--resume",
+            "a.yaml",
+            "--log-file",
+            "b.yaml",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_execute_only_failed() {
+        let args = vec![
+            "megamaid",
+            "execute",
+            "plan.yaml",
+            "--only-failed",
+            "execution-log.yaml",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Execute { only_failed, .. } => {
+                assert_eq!(only_failed, Some(PathBuf::from("execution-log.yaml")));
+            }
+            _ => panic!("Expected Execute command"),
+        }
+
+        // Retrying failures of a log can't also skip what another log removed
+        let args = vec![
+            "megamaid",
+            "execute",
+            "plan.yaml",
+            "--only-failed",
+            "a.yaml",
+            "--since-log",
+            "b.yaml",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_log_summarize() {
+        let args = vec!["megamaid", "log", "summarize", "shard1.yaml", "shard2.yaml"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Log {
+                command: LogCommands::Summarize { logs },
+            } => {
+                assert_eq!(
+                    logs,
+                    vec![PathBuf::from("shard1.yaml"), PathBuf::from("shard2.yaml")]
+                );
+            }
+            _ => panic!("Expected Log command"),
+        }
+
+        assert!(Cli::try_parse_from(["megamaid", "log", "summarize"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_snapshot_commands() {
+        let cli = Cli::try_parse_from(["megamaid", "snapshot", "rollback", "log.yaml"]).unwrap();
+        match cli.command {
+            Commands::Snapshot {
+                command: SnapshotCommands::Rollback { log },
+            } => assert_eq!(log, PathBuf::from("log.yaml")),
+            _ => panic!("Expected Snapshot rollback command"),
+        }
+
+        let cli =
+            Cli::try_parse_from(["megamaid", "snapshot", "list", "a.yaml", "b.yaml"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Snapshot {
+                command: SnapshotCommands::List { logs }
+            } if logs.len() == 2
+        ));
+        assert!(Cli::try_parse_from(["megamaid", "snapshot", "list"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_backup_prune() {
+        let cli =
+            Cli::try_parse_from(["megamaid", "backup", "prune", "log.yaml", "--force"]).unwrap();
+        match cli.command {
+            Commands::Backup {
+                command:
+                    BackupCommands::Prune {
+                        logs,
+                        force,
+                        dry_run,
+                    },
+            } => {
+                assert_eq!(logs, vec![PathBuf::from("log.yaml")]);
+                assert!(force);
+                assert!(!dry_run);
+            }
+            _ => panic!("Expected Backup prune command"),
+        }
+        assert!(Cli::try_parse_from(["megamaid", "backup", "prune"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_trash() {
+        let cli =
+            Cli::try_parse_from(["megamaid", "trash", "purge", "--older-than", "30"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Trash {
+                command: TrashCommands::Purge {
+                    older_than: Some(30),
+                    dry_run: false,
+                },
+            }
+        ));
+        let cli = Cli::try_parse_from(["megamaid", "trash", "list"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Trash {
+                command: TrashCommands::List,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parsing_analyze() {
+        let cli = Cli::try_parse_from(["megamaid", "analyze", "/data", "--top", "5"]).unwrap();
+        match cli.command {
+            Commands::Analyze { path, top } => {
+                assert_eq!(path, PathBuf::from("/data"));
+                assert_eq!(top, 5);
+            }
+            _ => panic!("Expected Analyze command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_duplicates() {
+        let cli = Cli::try_parse_from(["megamaid", "duplicates", "/data", "--min-size", "100KB"])
+            .unwrap();
+        match cli.command {
+            Commands::Duplicates {
+                path,
+                min_size,
+                top,
+            } => {
+                assert_eq!(path, PathBuf::from("/data"));
+                assert_eq!(min_size, Some(ByteSize::from_bytes(100 * 1024)));
+                assert_eq!(top, 20);
+            }
+            _ => panic!("Expected Duplicates command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_detect() {
+        let cli = Cli::try_parse_from([
+            "megamaid",
+            "detect",
+            "--from-scan",
+            "scan.jsonl",
+            "--large-file-threshold",
+            "2GB",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Detect {
+                from_scan,
+                output,
+                large_file_threshold,
+                ..
+            } => {
+                assert_eq!(from_scan, PathBuf::from("scan.jsonl"));
+                assert_eq!(output, PathBuf::from("cleanup-plan.yaml"));
+                assert!(large_file_threshold.is_some());
+            }
+            _ => panic!("Expected Detect command"),
+        }
+        assert!(Cli::try_parse_from(["megamaid", "detect"]).is_err());
+
+        let cli = Cli::try_parse_from(["megamaid", "scan", "/share", "--raw-output", "scan.jsonl"])
+            .unwrap();
+        match cli.command {
+            Commands::Scan { raw_output, .. } => {
+                assert_eq!(raw_output, Some(PathBuf::from("scan.jsonl")))
+            }
+            _ => panic!("Expected Scan command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_scan_summary_file() {
+        let summary_file = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+            Commands::Scan { summary_file, .. } => summary_file,
+            _ => panic!("Expected Scan command"),
+        };
+        assert_eq!(summary_file(&["megamaid", "scan", "/data"]), None);
+        assert_eq!(
+            summary_file(&["megamaid", "scan", "/data", "--summary-file"]),
+            Some(None)
+        );
+        assert_eq!(
+            summary_file(&["megamaid", "scan", "--summary-file=out/scan.json", "/data"]),
+            Some(Some(PathBuf::from("out/scan.json")))
+        );
+    }
+
+    #[test]
+    fn test_cli_parsing_secrets() {
+        let cli = Cli::try_parse_from(["megamaid", "secrets", "set", "smtp.password"]).unwrap();
+        match cli.command {
+            Commands::Secrets {
+                command: SecretsCommands::Set { name },
+            } => assert_eq!(name, "smtp.password"),
+            _ => panic!("Expected Secrets Set command"),
+        }
+        let cli = Cli::try_parse_from(["megamaid", "secrets", "remove", "key"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Secrets {
+                command: SecretsCommands::Remove { .. },
+            }
+        ));
+        assert!(Cli::try_parse_from(["megamaid", "secrets", "get"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_rules() {
+        let cli = Cli::try_parse_from([
+            "megamaid",
+            "rules",
+            "add",
+            "old_logs",
+            "--description",
+            "Old logs",
+            "--ext",
+            ".log",
+            "--ext",
+            ".txt",
+            "--min-size",
+            "2GB",
+            "--action",
+            "delete",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Rules {
+                command:
+                    RulesCommands::Add {
+                        name,
+                        extensions,
+                        min_size,
+                        action,
+                        replace,
+                        ..
+                    },
+            } => {
+                assert_eq!(name, "old_logs");
+                assert_eq!(extensions, vec![".log", ".txt"]);
+                assert_eq!(min_size, Some(ByteSize::from_mb(2048)));
+                assert_eq!(action, CleanupAction::Delete);
+                assert!(!replace);
+            }
+            _ => panic!("Expected Rules Add command"),
+        }
+
+        let cli = Cli::try_parse_from(["megamaid", "rules", "test", "old_logs", "--path", "/logs"])
+            .unwrap();
+        match cli.command {
+            Commands::Rules {
+                command: RulesCommands::Test { name, path, top },
+            } => {
+                assert_eq!(name, "old_logs");
+                assert_eq!(path, PathBuf::from("/logs"));
+                assert_eq!(top, 10);
+            }
+            _ => panic!("Expected Rules Test command"),
+        }
+        assert!(Cli::try_parse_from(["megamaid", "rules", "test", "old_logs"]).is_err());
+        assert!(Cli::try_parse_from(["megamaid", "rules", "add", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_diagnostics_collect() {
+        let cli = Cli::try_parse_from([
+            "megamaid",
+            "diagnostics",
+            "collect",
+            "--log",
+            "a.yaml",
+            "--log",
+            "b.yaml",
+            "--scan-path",
+            "/data",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Diagnostics {
+                command:
+                    DiagnosticsCommands::Collect {
+                        output,
+                        logs,
+                        max_logs,
+                        scan_paths,
+                    },
+            } => {
+                assert_eq!(output, PathBuf::from("megamaid-diagnostics.zip"));
+                assert_eq!(logs.len(), 2);
+                assert_eq!(max_logs, 3);
+                assert_eq!(scan_paths, vec![PathBuf::from("/data")]);
+            }
+            _ => panic!("Expected Diagnostics collect command"),
+        }
+    }
+
     #[test]
     fn test_cli_parsing_execute_with_backup() {
         let args = vec![
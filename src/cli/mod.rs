@@ -2,6 +2,10 @@
 
 pub mod commands;
 pub mod orchestrator;
+pub mod style;
 
-pub use commands::{Cli, Commands};
+pub use commands::{
+    BackupCommands, Cli, Commands, DiagnosticsCommands, LogCommands, PlanCommands, RulesCommands,
+    SecretsCommands, SnapshotCommands, TrashCommands,
+};
 pub use orchestrator::run_command;
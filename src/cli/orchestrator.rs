@@ -1,19 +1,56 @@
 //! Command orchestration and execution.
 
-use crate::cli::Commands;
-use crate::config::{load_config, load_default_config, validate_config, MegamaidConfig};
-use crate::detector::{DetectionEngine, ScanContext, SizeThresholdRule};
+use crate::analysis::{Usage, UsageAnalyzer};
+use crate::cli::style::{self, out, outln};
+use crate::cli::{
+    BackupCommands, Commands, DiagnosticsCommands, LogCommands, PlanCommands, RulesCommands,
+    SecretsCommands, SnapshotCommands, TrashCommands,
+};
+use crate::config::{
+    default_config_path, get_secret, load_config, load_default_config, remove_secret, set_secret,
+    validate_config, write_custom_rules, ConfigReloader, CustomRule, EncryptionConfig,
+    LastChanceConfig, MegamaidConfig,
+};
+use crate::detector::{
+    AlternateStreamRule, DeleteMarkerRule, DetectionEngine, DetectionReport, DetectionRule,
+    DuplicateFinder, DuplicateGroup, DuplicateRule, EmptyEntryRule, EntryKinds, ExprRule,
+    GrowthRule, KeepRule, OrphanedEnvRule, PatternRule, ScanContext, ScopedRule, SizeThresholdRule,
+    StaleRepoRule, SystemCacheRule, SYSTEM_CACHE_RULES,
+};
+use crate::diagnostics::{install_panic_hook, BundleOptions, DiagnosticBundle};
+use crate::dto::{PlanStats, ScanSummary, SCAN_SUMMARY_FILE};
 use crate::executor::{
-    ExecutionConfig, ExecutionEngine, ExecutionMode, TransactionLogger, TransactionOptions,
-    TransactionStatus,
+    backup, recycle_bin, run_as, snapshot, Account, ConfirmationMethod, DesktopNotifier,
+    ExecutionConfig, ExecutionEngine, ExecutionMode, IdleGate, IdleState, LastChanceOutcome,
+    LogSummary, NoUserPolicy, OperationAction, OperationStatus, ProcessedEntries, RecycledItem,
+    RunConstraints, Shard, SnapshotError, SystemMonitor, TransactionLogger, TransactionOptions,
+    TransactionStatus, TrashIndex, UserToken,
+};
+use crate::models::{
+    paths_equal, strip_path_prefix, ArtifactKind, ByteSize, CleanupAction, OwnArtifacts, PathKey,
+    TimeDisplay,
+};
+use crate::planner::encryption::{self, EncryptionKey};
+use crate::planner::{
+    expand_entry, read_path_list, DeleteShare, PathListPlanner, PlanGenerator, PlanReader,
+    PlanWriter,
+};
+use crate::scanner::{
+    import_scan, read_raw_scan, refresh_mtime, write_raw_scan, AdvancedProgress, EstimateConfig,
+    Estimator, FileScanner, ImportFormat, MftScanner, RemoteMode, RemoteScanConfig, ScanBackend,
+    ScanCache, ScanConfig, ScanError, ScanErrorReport, ScanHistory, WatchChanges, Watcher,
+};
+use crate::verifier::{
+    DriftReporter, VerificationConfig, VerificationEngine, VerificationRecord, VerificationResult,
 };
-use crate::planner::{PlanGenerator, PlanWriter};
-use crate::scanner::{FileScanner, ScanConfig};
-use crate::verifier::{DriftReporter, VerificationConfig, VerificationEngine};
 use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::fs;
+use std::collections::HashMap;
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 
 /// Loads configuration from file or defaults.
 fn load_config_with_fallback(config_path: Option<&Path>) -> Result<MegamaidConfig> {
@@ -31,32 +68,175 @@ fn load_config_with_fallback(config_path: Option<&Path>) -> Result<MegamaidConfi
     Ok(config)
 }
 
+/// Sets up encryption of the files this run writes and reads.
+fn configure_encryption(cfg: &EncryptionConfig, no_encrypt: bool) -> Result<()> {
+    let key = EncryptionKey::resolve(cfg.key_secret.as_deref(), cfg.key_file.as_deref())?;
+    if cfg.enabled && key.is_none() {
+        anyhow::bail!(
+            "encryption.enabled needs a key: set {}, encryption.key_secret or encryption.key_file",
+            encryption::KEY_ENV
+        );
+    }
+    if cfg.enabled && no_encrypt {
+        outln!("⚠️  --no-encrypt: files are written unencrypted");
+    }
+    encryption::configure(key, cfg.enabled && !no_encrypt);
+    Ok(())
+}
+
 /// Runs the specified command.
-pub fn run_command(command: Commands, config_path: Option<PathBuf>) -> Result<()> {
+///
+/// `assume_yes` skips confirmation prompts (the global `--yes` flag), and
+/// `no_encrypt` writes files unencrypted (the global `--no-encrypt` flag).
+pub fn run_command(
+    command: Commands,
+    config_path: Option<PathBuf>,
+    assume_yes: bool,
+    no_encrypt: bool,
+) -> Result<()> {
     // Load configuration
     let config = load_config_with_fallback(config_path.as_deref())?;
+    if config.diagnostics.crash_reports {
+        if let Some(dir) = config.diagnostics.crash_dir() {
+            install_panic_hook(dir);
+        }
+    }
+    configure_encryption(&config.encryption, no_encrypt)?;
+
     match command {
         Commands::Scan {
-            path,
+            paths,
             output,
             max_depth,
             skip_hidden,
             large_file_threshold,
+            include_vetoed,
+            summary,
+            scan_cache,
+            incremental,
+            full_walk,
+            include_streams,
+            remote,
+            exclude,
+            respect_ignore_files,
+            same_filesystem,
+            largest_first,
+            export_entries,
+            raw_output,
+            summary_file,
+            force,
+            tune,
         } => run_scan(
             &config,
-            &path,
-            &output,
-            max_depth,
-            skip_hidden,
+            ScanOptions {
+                paths,
+                output,
+                max_depth,
+                skip_hidden,
+                large_file_threshold,
+                include_vetoed,
+                summary,
+                scan_cache,
+                incremental,
+                full_walk,
+                include_streams,
+                remote,
+                exclude,
+                respect_ignore_files,
+                same_filesystem,
+                largest_first,
+                export_entries,
+                raw_output,
+                summary_file,
+                force,
+                tune,
+            },
+        ),
+        Commands::Detect {
+            from_scan,
+            output,
             large_file_threshold,
+            include_vetoed,
+            summary,
+            force,
+            tune,
+        } => run_detect(
+            &config,
+            &from_scan,
+            PlanOptions {
+                output: &output,
+                large_file_threshold,
+                include_vetoed,
+                summary,
+                force,
+                tune,
+            },
+        ),
+        Commands::Estimate {
+            path,
+            probes,
+            exact_limit,
+            seed,
+            top,
+        } => run_estimate(
+            &path,
+            EstimateConfig {
+                probes: probes as usize,
+                exact_dir_limit: exact_limit,
+                seed: seed.unwrap_or(EstimateConfig::default().seed),
+                ..Default::default()
+            },
+            top,
+        ),
+        Commands::Analyze { path, top } => run_analyze(&config, &path, top),
+        Commands::Duplicates {
+            path,
+            min_size,
+            top,
+        } => run_duplicates(&config, &path, min_size, top),
+        Commands::Watch {
+            path,
+            interval,
+            incremental,
+            json,
+        } => run_watch(
+            &config,
+            WatchOptions {
+                path,
+                interval: Duration::from_secs(interval),
+                incremental,
+                json,
+                config_file: config_file(config_path.as_deref()),
+            },
         ),
-        Commands::Stats { plan } => run_stats(&plan),
+        Commands::Stats {
+            plan,
+            json,
+            protections,
+        } => run_stats(&plan, json, protections, config.output.time_display),
+        Commands::Expand {
+            plan,
+            entry,
+            output,
+        } => run_expand(&plan, &entry, output.as_deref()),
         Commands::Verify {
             plan,
             output,
             fail_fast,
             skip_mtime,
-        } => run_verify(&config, &plan, output, fail_fast, skip_mtime),
+            watch,
+            watch_timeout,
+        } => run_verify(
+            &config,
+            VerifyOptions {
+                plan_path: plan,
+                output,
+                fail_fast,
+                skip_mtime,
+                watch_interval: watch.map(Duration::from_secs),
+                watch_timeout: watch_timeout.map(Duration::from_secs),
+            },
+        ),
         Commands::Execute {
             plan,
             dry_run,
@@ -65,9 +245,21 @@ pub fn run_command(command: Commands, config_path: Option<PathBuf>) -> Result<()
             recycle_bin,
             fail_fast,
             skip_verify,
+            use_verification,
+            shard,
+            resume,
+            since_log,
+            only_failed,
+            largest_first,
+            allow_stale_plan,
+            empty_recycle_bin_after,
+            snapshot,
             log_file,
             parallel,
             batch_size,
+            operation_timeout,
+            when_idle,
+            run_as,
         } => run_execute(
             &config,
             ExecuteOptions {
@@ -78,410 +270,5110 @@ pub fn run_command(command: Commands, config_path: Option<PathBuf>) -> Result<()
                 recycle_bin,
                 fail_fast,
                 skip_verify,
+                use_verification,
+                shard,
+                resume,
+                since_log,
+                only_failed,
+                largest_first,
+                allow_stale_plan,
+                empty_recycle_bin_after,
+                snapshot,
                 log_file,
                 parallel,
                 batch_size,
+                operation_timeout: operation_timeout.map(Duration::from_secs),
+                when_idle,
+                run_as,
+                assume_yes,
+            },
+        ),
+        Commands::Plan {
+            command:
+                PlanCommands::FromPaths {
+                    list,
+                    action,
+                    reason,
+                    base,
+                    output,
+                    skip_missing,
+                },
+        } => run_plan_from_paths(
+            FromPathsOptions {
+                list,
+                action,
+                reason,
+                base,
+                output,
+                skip_missing,
+            },
+            config.output.time_display,
+        ),
+        Commands::Plan {
+            command:
+                PlanCommands::Import {
+                    file,
+                    format,
+                    du_bytes,
+                    output,
+                    large_file_threshold,
+                    force,
+                },
+        } => run_plan_import(
+            &config,
+            ImportOptions {
+                file,
+                format,
+                du_bytes,
+                output,
+                large_file_threshold,
+                force,
+            },
+        ),
+        Commands::Log {
+            command: LogCommands::Summarize { logs },
+        } => run_log_summarize(&logs),
+        Commands::Snapshot {
+            command: SnapshotCommands::List { logs },
+        } => run_snapshot_list(&logs, config.output.time_display),
+        Commands::Snapshot {
+            command: SnapshotCommands::Rollback { log },
+        } => run_snapshot_rollback(&log),
+        Commands::Backup {
+            command:
+                BackupCommands::Prune {
+                    logs,
+                    force,
+                    dry_run,
+                },
+        } => run_backup_prune(&logs, force, dry_run),
+        Commands::Trash {
+            command: TrashCommands::List,
+        } => run_trash_list(&trash_index_path()?, config.output.time_display),
+        Commands::Trash {
+            command:
+                TrashCommands::Purge {
+                    older_than,
+                    dry_run,
+                },
+        } => run_trash_purge(&trash_index_path()?, older_than, dry_run, assume_yes),
+        Commands::Rules { command } => run_rules(&config, config_path.as_deref(), command),
+        Commands::Secrets { command } => run_secrets(command),
+        Commands::Diagnostics {
+            command:
+                DiagnosticsCommands::Collect {
+                    output,
+                    logs,
+                    max_logs,
+                    scan_paths,
+                },
+        } => run_diagnostics_collect(
+            &config,
+            config_path.as_deref(),
+            DiagnosticsOptions {
+                output,
+                logs,
+                max_logs,
+                scan_paths,
             },
         ),
     }
 }
 
-/// Executes the scan command.
-fn run_scan(
-    cfg: &MegamaidConfig,
-    path: &Path,
-    output: &Path,
+/// Options for scan command.
+struct ScanOptions {
+    /// Roots scanned into one plan
+    paths: Vec<PathBuf>,
+    output: PathBuf,
     max_depth: Option<usize>,
-    skip_hidden: bool,
-    large_file_threshold: u64,
-) -> Result<()> {
-    // Validate input path
-    if !path.exists() {
-        anyhow::bail!("Path does not exist: {}", path.display());
-    }
-
-    println!("🔍 Scanning directory: {}", path.display());
-    println!();
+    skip_hidden: Option<bool>,
+    large_file_threshold: Option<ByteSize>,
+    include_vetoed: bool,
+    summary: bool,
+    scan_cache: Option<PathBuf>,
+    /// Use the default scan cache of each scan root
+    incremental: bool,
+    full_walk: bool,
+    include_streams: Option<bool>,
+    /// Force the network filesystem strategy on or off
+    remote: Option<bool>,
+    /// Exclude globs added to the configured ones
+    exclude: Vec<String>,
+    respect_ignore_files: Option<bool>,
+    /// Don't descend into other filesystems
+    same_filesystem: Option<bool>,
+    /// Walk the largest subdirectories first, listing each as it finishes
+    largest_first: Option<bool>,
+    /// Write the scanned entries to this file for external analysis
+    export_entries: Option<PathBuf>,
+    /// Write the scanned entries to this file for `megamaid detect`
+    raw_output: Option<PathBuf>,
+    /// Write a scan summary, to this file or next to the plan
+    summary_file: Option<Option<PathBuf>>,
+    /// Write the plan even if it marks too much for deletion
+    force: bool,
+    /// Adjust the large file threshold interactively after detection
+    tune: bool,
+}
 
-    // Configure scanner - use config defaults but allow CLI overrides
-    let scan_config = ScanConfig {
-        follow_links: cfg.scanner.follow_symlinks,
-        max_depth: max_depth.or(cfg.scanner.max_depth),
-        skip_hidden,
-    };
+/// megamaid's output files named in the configuration, relative to the
+/// working directory.
+fn configured_artifacts(cfg: &MegamaidConfig) -> OwnArtifacts {
+    let mut artifacts = OwnArtifacts::new()
+        .with(ArtifactKind::Plan, &cfg.output.plan_file)
+        .with(ArtifactKind::TransactionLog, &cfg.output.log_file)
+        .with(ArtifactKind::Report, &cfg.output.drift_report);
+    if let Some(dir) = &cfg.executor.backup_dir {
+        artifacts.add(ArtifactKind::BackupDir, dir);
+    }
+    artifacts
+}
 
-    // Create progress bar
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    spinner.set_message("Scanning filesystem...");
+/// Validates the roots to scan, dropping any inside another one, whose
+/// entries the outer root's scan already covers.
+fn scan_roots(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    for path in &paths {
+        if !path.exists() {
+            anyhow::bail!("Path does not exist: {}", path.display());
+        }
+    }
 
-    // Scan the directory
-    let scanner = FileScanner::new(scan_config);
-    let entries = scanner.scan(path).context("Failed to scan directory")?;
+    let absolute: Vec<PathBuf> = paths
+        .iter()
+        .map(|p| std::path::absolute(p).unwrap_or_else(|_| p.clone()))
+        .collect();
+    let mut roots = Vec::with_capacity(paths.len());
+    for (i, path) in paths.into_iter().enumerate() {
+        let outer = absolute.iter().enumerate().find(|(j, other)| {
+            *j != i
+                && strip_path_prefix(&absolute[i], other).is_some()
+                && (*j < i || !paths_equal(&absolute[i], other))
+        });
+        match outer {
+            Some((j, _)) => outln!(
+                "⚠️  Skipping {}: already covered by {}",
+                path.display(),
+                absolute[j].display()
+            ),
+            None => roots.push(path),
+        }
+    }
+    Ok(roots)
+}
 
-    spinner.finish_with_message(format!("✓ Scanned {} entries", entries.len()));
-    println!();
+/// Shows the percentage and ETA of a scan seeded from earlier runs next to
+/// the spinner's message, until dropped.
+struct ProgressTicker {
+    spinner: ProgressBar,
+    stop: Arc<std::sync::atomic::AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
 
-    // Configure detection engine
-    // NOTE: Rule order matters! First match wins.
-    // Build artifacts should be detected before size checks so they're always marked
-    // for deletion (and their children filtered out), regardless of size.
-    let mut engine = DetectionEngine::empty();
+impl ProgressTicker {
+    const INTERVAL: Duration = Duration::from_millis(250);
 
-    // Add build artifact rule if enabled in config
-    if cfg.detector.rules.build_artifacts.enabled {
-        engine.add_rule(Box::new(crate::detector::BuildArtifactRule::default()));
+    fn start(spinner: &ProgressBar, progress: Arc<AdvancedProgress>) -> Self {
+        spinner.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {msg} {prefix}")
+                .unwrap(),
+        );
+        let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let thread = {
+            let (spinner, stop) = (spinner.clone(), Arc::clone(&stop));
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    spinner.set_prefix(format!(
+                        "[{:.0}%, ETA {}]",
+                        progress.percentage(),
+                        progress.format_eta()
+                    ));
+                    spinner.tick();
+                    std::thread::sleep(Self::INTERVAL);
+                }
+            })
+        };
+        Self {
+            spinner: spinner.clone(),
+            stop,
+            thread: Some(thread),
+        }
     }
+}
 
-    // Add size threshold rule if enabled - CLI arg overrides config
-    if cfg.detector.rules.size_threshold.enabled {
-        let threshold = large_file_threshold * 1_048_576; // Convert MB to bytes
-        engine.add_rule(Box::new(SizeThresholdRule {
-            threshold_bytes: threshold,
-        }));
+impl Drop for ProgressTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        self.spinner.set_prefix("");
     }
+}
 
-    // Run detection
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    spinner.set_message("Analyzing for cleanup candidates...");
-
-    let context = ScanContext::default();
-    let detections = engine.analyze(&entries, &context);
-
-    spinner.finish_with_message(format!("✓ Found {} cleanup candidates", detections.len()));
-    println!();
+/// Loads a scan cache usable for `root`, or `None` to fall back to a full walk.
+fn load_scan_cache(cache_path: &Path, root: &Path, config: &ScanConfig) -> Option<ScanCache> {
+    match ScanCache::load(cache_path) {
+        Ok(cache) if cache.is_compatible(root, config) => Some(cache),
+        Ok(_) => {
+            outln!("⚠️  Scan cache was built with different settings; doing a full walk");
+            None
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            outln!(
+                "⚠️  Ignoring unreadable scan cache ({}); doing a full walk",
+                e
+            );
+            None
+        }
+    }
+}
 
-    // Generate plan
-    let generator = PlanGenerator::new(path.to_path_buf());
-    let plan = generator.generate(detections);
+/// Warns about entries a scan couldn't read, listing the first few.
+fn print_unreadable(report: &ScanErrorReport) {
+    const LISTED: usize = 5;
 
-    // Write plan
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
+    if report.is_empty() {
+        return;
+    }
+    let denied = match report.permission_denied() {
+        0 => String::new(),
+        n => format!(" ({} access denied)", format_count(n)),
+    };
+    outln!(
+        "⚠️  Couldn't read {} entries{}; they and their contents are missing from the results",
+        format_count(report.len()),
+        denied
     );
-    spinner.set_message(format!("Writing plan to {}...", output.display()));
-
-    PlanWriter::write(&plan, output).context("Failed to write cleanup plan")?;
-
-    spinner.finish_with_message(format!("✓ Plan written to {}", output.display()));
-    println!();
-
-    // Print summary
-    print_plan_summary(&plan);
+    for entry in report.entries.iter().take(LISTED) {
+        outln!("   {}: {}", entry.path.display(), entry.message);
+    }
+    if report.len() > LISTED {
+        outln!("   ... and {} more", format_count(report.len() - LISTED));
+    }
+    outln!();
+}
 
-    Ok(())
+/// Finds the duplicate files among `entries`, if the duplicate rule is enabled.
+fn find_duplicates(
+    cfg: &MegamaidConfig,
+    entries: &[crate::models::FileEntry],
+) -> Vec<DuplicateGroup> {
+    let duplicates_cfg = &cfg.detector.rules.duplicates;
+    if !duplicates_cfg.enabled {
+        return Vec::new();
+    }
+    let spinner = style::spinner("Hashing duplicate candidates...");
+    let groups = DuplicateFinder::new()
+        .with_min_size(duplicates_cfg.min_size.as_bytes())
+        .find(entries);
+    style::finish(
+        &spinner,
+        format!(
+            "✓ Found {} groups of duplicate files",
+            format_count(groups.len())
+        ),
+    );
+    groups
 }
 
-/// Executes the stats command.
-fn run_stats(plan_path: &Path) -> Result<()> {
-    // Read plan file
-    let content = fs::read_to_string(plan_path)
-        .context(format!("Failed to read plan file: {}", plan_path.display()))?;
+/// Configures the detection engine from config; the CLI threshold overrides the file threshold.
+///
+/// `previous_sizes` holds the directory sizes of the previous scan, for the
+/// growth rule, and `duplicates` the duplicate files found by
+/// [`find_duplicates`], for the duplicate rule.
+fn build_detection_engine(
+    cfg: &MegamaidConfig,
+    large_file_threshold: Option<ByteSize>,
+    previous_sizes: HashMap<PathBuf, u64>,
+    duplicates: &[DuplicateGroup],
+    roots: &[PathBuf],
+) -> Result<DetectionEngine> {
+    // Configure detection engine
+    // NOTE: Rule order matters! First match wins.
+    // Custom rules come first, in config order, so their actions apply to what they flag;
+    // pattern rules follow, then the built-in rules.
+    // Build artifacts should be detected before size checks so they're always marked
+    // for deletion (and their children filtered out), regardless of size.
+    let mut engine = DetectionEngine::empty();
+    let rules_cfg = &cfg.detector.rules;
 
-    // Deserialize
-    let plan: crate::models::CleanupPlan =
-        serde_yaml::from_str(&content).context("Failed to parse plan file")?;
+    // Every built-in rule is built, enabled or not, so custom rules can reference it
+    let mut builtins: Vec<(bool, Arc<dyn DetectionRule>)> = Vec::new();
 
-    println!("📊 Cleanup Plan Statistics");
-    println!();
-    print_plan_summary(&plan);
+    // Marked directories come first so their reason names the marker
+    let markers_cfg = &rules_cfg.delete_markers;
+    builtins.push((
+        markers_cfg.enabled,
+        Arc::new(DeleteMarkerRule::new(markers_cfg.marker.clone())),
+    ));
+    builtins.push((
+        rules_cfg.build_artifacts.enabled,
+        Arc::new(crate::detector::BuildArtifactRule::from(
+            &rules_cfg.build_artifacts,
+        )),
+    ));
+    builtins.push((
+        rules_cfg.dev_caches.enabled,
+        Arc::new(crate::detector::DevCacheRule::from(&rules_cfg.dev_caches)),
+    ));
 
-    Ok(())
-}
+    // Temporary files and system caches, under rules of their own so each
+    // kind gets its default action
+    let system_cfg = &rules_cfg.system_caches;
+    let system_names = system_cfg.caches.enabled_names();
+    for name in SYSTEM_CACHE_RULES {
+        let rule = SystemCacheRule::new(name, &system_names);
+        builtins.push((system_cfg.enabled && !rule.is_empty(), Arc::new(rule)));
+    }
 
-/// Executes the verify command.
-fn run_verify(
-    cfg: &MegamaidConfig,
-    plan_path: &Path,
-    output: Option<PathBuf>,
-    fail_fast: bool,
-    skip_mtime: bool,
-) -> Result<()> {
-    println!("📋 Verifying cleanup plan: {}", plan_path.display());
-    println!();
+    // Orphaned environments are checked before sizes so they get a specific reason
+    let envs_cfg = &rules_cfg.orphaned_envs;
+    builtins.push((
+        envs_cfg.enabled,
+        Arc::new(OrphanedEnvRule::new(envs_cfg.stale_days)),
+    ));
 
-    // Read plan file
-    let content = fs::read_to_string(plan_path)
-        .context(format!("Failed to read plan file: {}", plan_path.display()))?;
+    // Stale repositories are flagged as a whole, the one rule that sees
+    // repository roots
+    let repos_cfg = &rules_cfg.stale_repos;
+    builtins.push((
+        repos_cfg.enabled,
+        Arc::new(StaleRepoRule::new(repos_cfg.stale_days)),
+    ));
 
-    // Deserialize
-    let plan: crate::models::CleanupPlan =
-        serde_yaml::from_str(&content).context("Failed to parse plan file")?;
+    // Growth is checked before sizes, since it catches directories still below the threshold
+    let growth_cfg = &rules_cfg.growth;
+    builtins.push((
+        growth_cfg.enabled,
+        Arc::new(GrowthRule::new(
+            previous_sizes,
+            growth_cfg.min_growth.as_bytes(),
+            growth_cfg.min_growth_percent,
+            growth_cfg.min_size.as_bytes(),
+        )),
+    ));
 
-    // Configure verification - CLI args override config
-    let ver_config = VerificationConfig {
-        check_mtime: !skip_mtime && cfg.verifier.check_mtime,
-        check_size: cfg.verifier.check_size,
-        fail_fast: fail_fast || cfg.verifier.fail_fast,
-    };
+    // Duplicates are checked before sizes so a redundant copy says what it duplicates
+    builtins.push((
+        rules_cfg.duplicates.enabled,
+        Arc::new(DuplicateRule::new(duplicates)),
+    ));
 
-    // Run verification
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    spinner.set_message("Verifying entries...");
+    // Empty entries hold nothing, so no later rule would flag them
+    let empty_cfg = &rules_cfg.empty_entries;
+    builtins.push((
+        empty_cfg.enabled,
+        Arc::new(EmptyEntryRule::new(empty_cfg.keep_names.iter().cloned())),
+    ));
 
-    let verifier = VerificationEngine::new(ver_config);
-    let result = verifier.verify(&plan)?;
+    // Size threshold rule - CLI arg overrides the file threshold
+    let size_cfg = &rules_cfg.size_threshold;
+    let file_threshold =
+        large_file_threshold.unwrap_or_else(|| size_cfg.effective_file_threshold());
+    let rule = SizeThresholdRule::with_thresholds(
+        file_threshold.as_bytes(),
+        size_cfg.effective_directory_threshold().as_bytes(),
+    )
+    .with_cloud_placeholders(size_cfg.include_cloud_placeholders);
+    let applies_to = size_cfg.applies_to;
+    let size_rule: Arc<dyn DetectionRule> = if applies_to == EntryKinds::ALL {
+        Arc::new(rule)
+    } else {
+        Arc::new(ScopedRule::new(Box::new(rule), applies_to))
+    };
+    builtins.push((size_cfg.enabled, size_rule));
 
-    spinner.finish_with_message(format!(
-        "✓ Verified {} of {} entries",
-        result.verified, result.total_entries
+    // Flag files hiding data in large alternate data streams
+    let streams_cfg = &rules_cfg.alternate_streams;
+    builtins.push((
+        streams_cfg.enabled,
+        Arc::new(AlternateStreamRule::new(streams_cfg.threshold.as_bytes())),
     ));
-    println!();
 
-    // Print report
-    let report = DriftReporter::generate_report(&result);
-    println!("{}", report);
+    let mut named: HashMap<String, Arc<dyn DetectionRule>> = builtins
+        .iter()
+        .map(|(_, rule)| (rule.name().to_string(), Arc::clone(rule)))
+        .collect();
+    let mut pattern_rules: Vec<Arc<dyn DetectionRule>> = Vec::new();
+    for pattern in &cfg.detector.pattern_rules {
+        let mut rule = PatternRule::new(&pattern.name, &pattern.patterns, pattern.syntax)
+            .with_context(|| format!("Invalid pattern rule: {}", pattern.name))?
+            .with_roots(roots);
+        if let Some(reason) = &pattern.reason {
+            rule = rule.with_reason(reason.clone());
+        }
+        let rule: Arc<dyn DetectionRule> = Arc::new(rule);
+        named.insert(pattern.name.clone(), Arc::clone(&rule));
+        pattern_rules.push(rule);
+    }
+    for custom in &cfg.detector.custom_rules {
+        let rule = custom
+            .expression()
+            .and_then(|expr| ExprRule::new(&custom.name, &custom.description, expr, &named))
+            .with_context(|| format!("Invalid custom rule: {}", custom.name))?;
+        let rule: Arc<dyn DetectionRule> = Arc::new(rule);
+        named.insert(custom.name.clone(), Arc::clone(&rule));
+        engine.add_rule(Box::new(rule));
+    }
+    for rule in pattern_rules {
+        engine.add_rule(Box::new(rule));
+    }
 
-    // Write report file if requested
-    if let Some(output_path) = output {
-        DriftReporter::write_report(&result, &output_path)?;
-        println!("📄 Drift report written to: {}", output_path.display());
-        println!();
+    for (enabled, rule) in builtins {
+        if enabled {
+            engine.add_rule(Box::new(rule));
+        }
     }
 
-    // Exit with error if drift detected
-    if !result.is_safe_to_execute() {
-        anyhow::bail!("Drift detected - plan is not safe to execute");
+    // Keep rules veto detections regardless of which rule matched
+    for keep in &cfg.detector.keep_rules {
+        let rule = KeepRule::new(keep.name.clone(), &keep.patterns)
+            .with_context(|| format!("Invalid keep rule: {}", keep.name))?;
+        engine.add_keep_rule(rule);
+    }
+    let archive = &cfg.detector.archive;
+    if archive.enabled {
+        let mut rule = KeepRule::new("archive", &[] as &[&str])?
+            .with_dir_names(&archive.dir_names)
+            .context("Invalid detector.archive.dir_names")?;
+        if !archive.marker.is_empty() {
+            rule = rule.with_marker(archive.marker.clone());
+        }
+        engine.add_keep_rule(rule);
     }
 
-    Ok(())
+    Ok(engine)
 }
 
-/// Options for execute command.
-struct ExecuteOptions {
-    plan_path: PathBuf,
-    dry_run: bool,
-    interactive: bool,
-    backup_dir: Option<PathBuf>,
-    recycle_bin: bool,
-    fail_fast: bool,
-    skip_verify: bool,
-    log_file: PathBuf,
-    parallel: bool,
-    batch_size: usize,
+/// Interactive adjustment of the large file threshold after a scan.
+///
+/// Each new threshold re-runs detection on the entries already scanned, so
+/// trying values doesn't walk the disk again.
+struct Tuning<'a> {
+    cfg: &'a MegamaidConfig,
+    previous_sizes: HashMap<PathBuf, u64>,
+    duplicates: &'a [DuplicateGroup],
+    roots: &'a [PathBuf],
+    entries: &'a [crate::models::FileEntry],
+    context: &'a ScanContext,
 }
 
-/// Executes the execute command.
-fn run_execute(cfg: &MegamaidConfig, options: ExecuteOptions) -> Result<()> {
-    let plan_path = &options.plan_path;
-    println!("🗑️  Executing cleanup plan: {}", plan_path.display());
-    println!();
-
-    // Read plan file
-    let content = fs::read_to_string(plan_path)
-        .context(format!("Failed to read plan file: {}", plan_path.display()))?;
-
-    // Deserialize
-    let plan: crate::models::CleanupPlan =
-        serde_yaml::from_str(&content).context("Failed to parse plan file")?;
+impl Tuning<'_> {
+    /// Offers to change the threshold until the user keeps the current one,
+    /// and returns the report of the last detection run.
+    fn run(
+        &self,
+        mut report: DetectionReport,
+        mut threshold: ByteSize,
+        input: &mut impl BufRead,
+    ) -> Result<DetectionReport> {
+        loop {
+            // Nothing to tune when the size rule is disabled
+            let Some(stat) = report
+                .rule_stats
+                .iter()
+                .find(|s| s.rule_name == "large_file")
+            else {
+                return Ok(report);
+            };
+            out!(
+                "large_file flagged {} entries / {:.1} GB at {} — raise threshold? [enter new value / keep]: ",
+                format_count(stat.entries_flagged as usize),
+                stat.bytes_flagged as f64 / 1_073_741_824.0,
+                threshold
+            );
+            io::stdout().flush()?;
 
-    // Verify unless skipped
-    if !options.skip_verify && !options.dry_run {
-        println!("🔍 Verifying plan before execution...");
-        let ver_config: VerificationConfig = cfg.verifier.clone().into();
-        let verifier = VerificationEngine::new(ver_config);
-        let verification = verifier.verify(&plan)?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                outln!();
+                return Ok(report);
+            }
+            let answer = line.trim();
+            if answer.is_empty() || answer.eq_ignore_ascii_case("keep") {
+                return Ok(report);
+            }
+            match ByteSize::parse_mb(answer) {
+                Ok(value) if value.as_bytes() > 0 => threshold = value,
+                _ => {
+                    outln!(
+                        "⚠️  Not a size: {} (e.g. 500MB, 2GiB; bare numbers are MB)",
+                        answer
+                    );
+                    continue;
+                }
+            }
 
-        if !verification.is_safe_to_execute() {
-            let report = DriftReporter::generate_report(&verification);
-            println!("{}", report);
-            anyhow::bail!(
-                "Drift detected - cannot execute. Use --skip-verify to override (not recommended)."
+            let engine = build_detection_engine(
+                self.cfg,
+                Some(threshold),
+                self.previous_sizes.clone(),
+                self.duplicates,
+                self.roots,
+            )?;
+            report = engine.analyze_with_stats(self.entries, self.context);
+            outln!(
+                "✓ Re-ran detection: {} cleanup candidates",
+                report.detections.len()
             );
         }
-        println!("✓ Verification passed\n");
     }
+}
 
-    // Configure execution - CLI args override config
-    let mode = if options.dry_run {
-        ExecutionMode::DryRun
-    } else if options.interactive {
-        ExecutionMode::Interactive
-    } else {
-        ExecutionMode::Batch
-    };
+/// Returns the actions of the custom and pattern rules in config, for the
+/// plan generator.
+fn custom_rule_actions(cfg: &MegamaidConfig) -> HashMap<String, CleanupAction> {
+    let detector = &cfg.detector;
+    let custom = detector
+        .custom_rules
+        .iter()
+        .map(|r| (r.name.clone(), r.action));
+    let pattern = detector
+        .pattern_rules
+        .iter()
+        .map(|r| (r.name.clone(), r.action));
+    custom.chain(pattern).collect()
+}
 
-    let exec_config = ExecutionConfig {
-        mode,
-        backup_dir: options
-            .backup_dir
-            .clone()
-            .or(cfg.executor.backup_dir.clone()),
-        fail_fast: options.fail_fast || cfg.executor.fail_fast,
-        use_recycle_bin: options.recycle_bin || cfg.executor.use_recycle_bin,
-        parallel: options.parallel || cfg.executor.parallel,
-        batch_size: options.batch_size,
-    };
+/// Executes the scan command.
+fn run_scan(cfg: &MegamaidConfig, options: ScanOptions) -> Result<()> {
+    let ScanOptions {
+        paths,
+        output,
+        max_depth,
+        skip_hidden,
+        large_file_threshold,
+        include_vetoed,
+        summary,
+        scan_cache,
+        incremental,
+        full_walk,
+        include_streams,
+        remote,
+        exclude,
+        respect_ignore_files,
+        same_filesystem,
+        largest_first,
+        export_entries,
+        raw_output,
+        summary_file,
+        force,
+        tune,
+    } = options;
+    let output = output.as_path();
+    let (scan_started_at, scan_started) = (SystemTime::now(), Instant::now());
+    let summary_file =
+        summary_file.map(|file| file.unwrap_or_else(|| output.with_file_name(SCAN_SUMMARY_FILE)));
 
-    // Display mode
-    if options.dry_run {
-        println!("🔄 DRY RUN MODE - No files will be deleted");
-        println!();
-    } else if options.interactive {
-        println!("💬 INTERACTIVE MODE - You will be prompted for each deletion");
-        println!();
-    } else if let Some(ref backup_path) = options.backup_dir {
-        println!(
-            "📦 BACKUP MODE - Files will be moved to: {}",
-            backup_path.display()
-        );
-        println!();
-    } else if options.recycle_bin {
-        println!("♻️  RECYCLE BIN MODE - Files will be moved to recycle bin");
-        println!();
+    // Validate input paths
+    let roots = scan_roots(paths)?;
+    if tune && !io::stdin().is_terminal() {
+        anyhow::bail!("--tune prompts for new thresholds, but stdin is not a terminal");
     }
-
-    // Display parallel execution mode
-    if options.parallel {
-        println!(
-            "⚡ PARALLEL EXECUTION - Using batch size: {}",
-            options.batch_size
+    if scan_cache.is_some() && roots.len() > 1 {
+        anyhow::bail!(
+            "--scan-cache takes a single root; use --incremental to keep a cache per root"
         );
-        println!();
     }
 
-    // Count Delete actions
-    let delete_count = plan
-        .entries
-        .iter()
-        .filter(|e| e.action == crate::models::CleanupAction::Delete)
-        .count();
+    for root in &roots {
+        outln!("🔍 Scanning directory: {}", root.display());
+    }
+    outln!();
 
-    if delete_count == 0 {
-        println!("No entries marked for deletion.");
-        return Ok(());
+    // Each root keeps its own scan cache
+    let mut cache_paths = Vec::with_capacity(roots.len());
+    for root in &roots {
+        let cache_path = match &scan_cache {
+            Some(cache_path) => Some(cache_path.clone()),
+            None if incremental => {
+                let cache_path = ScanCache::default_path(root)
+                    .context("No user cache directory for the incremental scan cache")?;
+                if let Some(dir) = cache_path.parent() {
+                    std::fs::create_dir_all(dir).context(format!(
+                        "Failed to create scan cache directory: {}",
+                        dir.display()
+                    ))?;
+                }
+                Some(cache_path)
+            }
+            None => None,
+        };
+        cache_paths.push(cache_path);
     }
 
-    println!("Processing {} deletion(s)...", delete_count);
-    println!();
+    // Directories no enabled rule flags anything in aren't walked at all
+    let pruned = build_detection_engine(cfg, large_file_threshold, HashMap::new(), &[], &roots)?
+        .prune_hints(&ScanContext::default());
+    if !pruned.is_empty() {
+        outln!(
+            "⏭️  Skipping {} directories: no enabled rule flags anything inside",
+            pruned.skip_dir_names.join(", ")
+        );
+        outln!();
+    }
 
-    // Create transaction logger
-    let transaction_opts = TransactionOptions {
-        dry_run: options.dry_run,
-        backup_dir: options.backup_dir.clone(),
-        use_recycle_bin: options.recycle_bin,
-        fail_fast: options.fail_fast,
+    // Configure scanner - use config defaults but allow CLI overrides
+    let scan_config = ScanConfig {
+        follow_links: cfg.scanner.follow_symlinks,
+        max_depth: max_depth.or(cfg.scanner.max_depth),
+        skip_hidden: skip_hidden.unwrap_or(cfg.scanner.skip_hidden),
+        include_streams: include_streams.unwrap_or(cfg.scanner.include_streams),
+        remote: RemoteScanConfig {
+            mode: match remote {
+                Some(true) => RemoteMode::Always,
+                Some(false) => RemoteMode::Never,
+                None => cfg.scanner.remote.mode,
+            },
+            ..cfg.scanner.remote
+        },
+        exclude_globs: cfg
+            .scanner
+            .exclude
+            .iter()
+            .cloned()
+            .chain(exclude)
+            .chain(pruned.skip_dir_names)
+            .collect(),
+        respect_ignore_files: respect_ignore_files.unwrap_or(cfg.scanner.respect_ignore_files),
+        same_filesystem: same_filesystem.unwrap_or(cfg.scanner.same_filesystem),
+        collect_owner: cfg.scanner.collect_owner,
+        largest_first: largest_first.unwrap_or(cfg.scanner.largest_first),
+        max_iops: cfg.scanner.max_iops,
     };
-    let mut logger = TransactionLogger::new(plan_path, options.log_file.clone(), transaction_opts);
-
-    println!("📋 Transaction ID: {}", logger.execution_id());
-    println!();
-
-    // Execute
-    let executor = ExecutionEngine::new(exec_config);
-    let progress = ProgressBar::new(delete_count as u64);
-    progress.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("#>-"),
-    );
-
-    let result = executor.execute(&plan)?;
 
-    // Log all operations
-    for op in &result.operations {
-        logger.log_operation(op);
-        progress.inc(1);
-        if op.status == crate::executor::OperationStatus::Failed {
-            progress.set_message(format!("Failed: {}", op.path.display()));
+    if let Some(root) = roots.iter().find(|r| scan_config.remote.is_active(r)) {
+        outln!(
+            "🌐 Network filesystem at {} - using remote scan mode (single pass, no stream listing)",
+            root.display()
+        );
+        if scan_config.include_streams {
+            outln!("⚠️  Alternate data streams are not listed in remote scan mode");
+        }
+        if scan_config.remote.skip_dir_sizes {
+            outln!("⚠️  Directory sizes are skipped (scanner.remote.skip_dir_sizes)");
         }
+        outln!();
     }
 
-    progress.finish_with_message("Done");
-    println!();
+    // Create progress bar
+    let spinner = style::spinner("Scanning filesystem...");
 
-    // Finalize transaction log
-    let status = if result.summary.failed > 0 {
-        TransactionStatus::Failed
-    } else {
-        TransactionStatus::Completed
-    };
-    logger.finalize(&result, status)?;
+    // Scan the directory, reusing unchanged subtrees from the cache if possible
+    // Leave megamaid's own files out of the scan, and out of the plan
+    let mut artifacts = configured_artifacts(cfg)
+        .with(ArtifactKind::Plan, output)
+        .with(
+            ArtifactKind::Report,
+            VerificationRecord::default_path(output),
+        );
+    for cache_path in cache_paths.iter().flatten() {
+        artifacts.add(ArtifactKind::ScanCache, cache_path);
+    }
+    for export_path in export_entries.iter().chain(&raw_output) {
+        artifacts.add(ArtifactKind::Export, export_path);
+    }
+    if let Some(summary_path) = &summary_file {
+        artifacts.add(ArtifactKind::Report, summary_path);
+    }
 
-    // Print summary
-    print_execution_summary(&result.summary, options.dry_run);
-    println!();
-    println!("📄 Transaction log: {}", options.log_file.display());
+    let scanner = FileScanner::new(scan_config.clone()).with_exclusions(artifacts.clone());
+    let mft_scanner = (cfg.scanner.backend == ScanBackend::Mft)
+        .then(|| MftScanner::new(scan_config.clone()).with_exclusions(artifacts.clone()));
 
-    // Exit with error if any failures
-    if result.summary.failed > 0 {
-        anyhow::bail!("{} operation(s) failed", result.summary.failed);
+    // Ctrl-C stops the scan before anything is written; a second one
+    // terminates right away
+    let cancel = scanner.cancel_handle();
+    for signal in signal_hook::consts::TERM_SIGNALS {
+        signal_hook::flag::register_conditional_shutdown(*signal, 1, Arc::clone(&cancel))?;
+        signal_hook::flag::register(*signal, Arc::clone(&cancel))?;
     }
 
-    Ok(())
-}
+    // Earlier scans of the same roots tell how far along this one is
+    let history_path = ScanHistory::default_path();
+    let mut history = history_path
+        .as_deref()
+        .and_then(|path| ScanHistory::load(path).ok())
+        .unwrap_or_default();
+    let progress = scanner.progress_handle();
+    let ticker = history.expected(&roots).map(|(total, duration)| {
+        progress.seed(total, duration);
+        ProgressTicker::start(&spinner, Arc::clone(&progress))
+    });
+
+    // With the growth rule, the cache also provides the previous sizes
+    let growth_enabled = cfg.detector.rules.growth.enabled;
+    let mut entries = Vec::new();
+    let mut previous_sizes = HashMap::new();
+    for (path, scan_cache) in roots.iter().zip(&cache_paths) {
+        if roots.len() > 1 {
+            spinner.set_message(format!("Scanning {}...", path.display()));
+        }
+        let started_at = SystemTime::now();
+        let (walk_started, processed_before) = (Instant::now(), progress.get_processed());
+        let cache = match scan_cache {
+            Some(cache_path) if !full_walk || growth_enabled => {
+                load_scan_cache(cache_path, path, &scan_config)
+            }
+            _ => None,
+        };
+        if let Some(cache) = cache.as_ref().filter(|_| growth_enabled) {
+            previous_sizes.extend(cache.directory_sizes());
+        }
+        // The MFT backend reads the whole volume; where it is unavailable the
+        // tree is walked instead
+        let from_mft = mft_scanner.as_ref().and_then(|mft| match mft.scan(path) {
+            Ok(entries) => {
+                progress.increment_by(entries.len() as u64);
+                Some(entries)
+            }
+            Err(e) => {
+                style::println_above(
+                    &spinner,
+                    format!(
+                        "⚠️  Can't read the MFT for {} ({}); walking the directory tree",
+                        path.display(),
+                        e
+                    ),
+                );
+                None
+            }
+        });
+        let scanned = match (from_mft, &cache) {
+            (Some(entries), _) => Ok(entries),
+            (None, Some(cache)) if !full_walk => {
+                scanner
+                    .scan_incremental(path, cache)
+                    .map(|(entries, stats)| {
+                        spinner.set_message(format!(
+                            "Reused {} cached entries from {} unchanged directories",
+                            stats.reused_entries, stats.reused_subtrees
+                        ));
+                        entries
+                    })
+            }
+            (None, _) => scanner.scan_streaming(path, |subtree| {
+                if scan_config.largest_first {
+                    style::println_above(
+                        &spinner,
+                        format!(
+                            "  {:>10}  {}",
+                            format_bytes(subtree.bytes),
+                            subtree.path.display()
+                        ),
+                    );
+                }
+            }),
+        };
+        let scanned = match scanned {
+            Err(ScanError::Cancelled) => {
+                style::abandon(&spinner, "✗ Scan cancelled");
+                anyhow::bail!("Scan cancelled - no plan was written");
+            }
+            result => result.context(format!("Failed to scan directory: {}", path.display()))?,
+        };
+        history.record(
+            path,
+            progress.get_processed() - processed_before,
+            walk_started.elapsed(),
+        );
+        // Release the old cache before the refreshed one is built
+        drop(cache);
+
+        // Refresh the scan cache for the next incremental run
+        let scanned = match scan_cache {
+            Some(cache_path) => {
+                let cache = ScanCache::new(path, &scan_config, started_at, scanned);
+                cache.save(cache_path).context(format!(
+                    "Failed to write scan cache: {}",
+                    cache_path.display()
+                ))?;
+                cache.into_entries()
+            }
+            None => scanned,
+        };
+        entries.extend(scanned);
+    }
+
+    drop(ticker);
+    if let Some(path) = &history_path {
+        if let Err(e) = history.save(path) {
+            outln!("⚠️  Could not update the scan history: {}", e);
+        }
+    }
+    style::finish(&spinner, format!("✓ Scanned {} entries", entries.len()));
+    outln!();
+    let errors = scanner.errors();
+    print_unreadable(&errors);
+
+    if let Some(export_path) = &export_entries {
+        let summary = crate::scanner::export_entries(&entries, &roots, export_path).context(
+            format!("Failed to export entries: {}", export_path.display()),
+        )?;
+        if summary.path != *export_path {
+            outln!("⚠️  Parquet export needs the `parquet` feature; wrote CSV instead");
+        }
+        outln!(
+            "✓ Exported {} entries to {}",
+            format_count(summary.rows),
+            summary.path.display()
+        );
+        outln!();
+    }
+    if let Some(raw_path) = &raw_output {
+        write_raw_scan(raw_path, &roots, &entries)
+            .context(format!("Failed to write raw scan: {}", raw_path.display()))?;
+        outln!(
+            "✓ Wrote {} entries to {} (run detection on them with `megamaid detect --from-scan`)",
+            format_count(entries.len()),
+            raw_path.display()
+        );
+        outln!();
+    }
+
+    if growth_enabled && cache_paths.iter().all(Option::is_none) {
+        outln!(
+            "ℹ️  The growth rule compares with the scan cache; scan with --incremental to use it"
+        );
+    } else if growth_enabled && previous_sizes.is_empty() {
+        outln!(
+            "ℹ️  No previous scan in the scan cache yet; the growth rule flags nothing this time"
+        );
+    }
+    let plan_options = PlanOptions {
+        output,
+        large_file_threshold,
+        include_vetoed,
+        summary,
+        force,
+        tune,
+    };
+    let context = ScanContext {
+        own_artifacts: artifacts,
+    };
+    let plan = detect_plan(
+        cfg,
+        &entries,
+        &roots,
+        &context,
+        previous_sizes,
+        &plan_options,
+    )?;
+
+    // Analysis doesn't poll the flag, so honor a Ctrl-C pressed meanwhile here
+    if cancel.load(Ordering::SeqCst) {
+        anyhow::bail!("Scan cancelled - no plan was written");
+    }
+
+    write_checked_plan(cfg, &plan, &entries, &roots, &plan_options)?;
+
+    if let Some(summary_path) = &summary_file {
+        ScanSummary::from_entries(&plan.base_path, &entries, Vec::new())
+            .with_errors(&errors)
+            .with_roots(&roots)
+            .with_plan(&plan, output)
+            .with_top_directories(&entries, SUMMARY_TOP_DIRECTORIES)
+            .with_timing(scan_started_at, scan_started.elapsed())
+            .write(summary_path)
+            .context(format!(
+                "Failed to write scan summary: {}",
+                summary_path.display()
+            ))?;
+        outln!();
+        outln!("✓ Scan summary written to {}", summary_path.display());
+    }
+    Ok(())
+}
+
+/// Number of directories listed in a scan summary.
+const SUMMARY_TOP_DIRECTORIES: usize = 10;
+
+/// Executes the detect command: detection on the entries of a raw scan
+/// written by `scan --raw-output`, without walking the disk.
+fn run_detect(cfg: &MegamaidConfig, from_scan: &Path, options: PlanOptions) -> Result<()> {
+    if options.tune && !io::stdin().is_terminal() {
+        anyhow::bail!("--tune prompts for new thresholds, but stdin is not a terminal");
+    }
+
+    outln!("📂 Reading raw scan: {}", from_scan.display());
+    let scan = read_raw_scan(from_scan)
+        .context(format!("Failed to read raw scan: {}", from_scan.display()))?;
+    if scan.roots.is_empty() {
+        anyhow::bail!("The raw scan names no scan roots: {}", from_scan.display());
+    }
+    let roots: Vec<String> = scan.roots.iter().map(|r| r.display().to_string()).collect();
+    outln!(
+        "✓ Read {} entries scanned from {}",
+        format_count(scan.entries.len()),
+        roots.join(", ")
+    );
+    outln!();
+
+    if cfg.detector.rules.growth.enabled {
+        outln!("ℹ️  The growth rule compares with the scan cache; it flags nothing on a raw scan");
+    }
+    let context = ScanContext {
+        own_artifacts: configured_artifacts(cfg)
+            .with(ArtifactKind::Plan, options.output)
+            .with(
+                ArtifactKind::Report,
+                VerificationRecord::default_path(options.output),
+            ),
+    };
+    let plan = detect_plan(
+        cfg,
+        &scan.entries,
+        &scan.roots,
+        &context,
+        HashMap::new(),
+        &options,
+    )?;
+    write_checked_plan(cfg, &plan, &scan.entries, &scan.roots, &options)
+}
+
+/// Detection and plan settings shared by scan and detect.
+struct PlanOptions<'a> {
+    output: &'a Path,
+    large_file_threshold: Option<ByteSize>,
+    include_vetoed: bool,
+    summary: bool,
+    /// Write the plan even if it marks too much for deletion
+    force: bool,
+    /// Adjust the large file threshold interactively after detection
+    tune: bool,
+}
+
+/// Runs detection on the entries scanned from `roots` and generates the
+/// plan, printing the per-rule totals.
+fn detect_plan(
+    cfg: &MegamaidConfig,
+    entries: &[crate::models::FileEntry],
+    roots: &[PathBuf],
+    context: &ScanContext,
+    previous_sizes: HashMap<PathBuf, u64>,
+    options: &PlanOptions,
+) -> Result<crate::models::CleanupPlan> {
+    // Tuning rebuilds the engine, so it keeps its own copy of the sizes
+    let tuning_sizes = options.tune.then(|| previous_sizes.clone());
+    let duplicates = find_duplicates(cfg, entries);
+    let engine = build_detection_engine(
+        cfg,
+        options.large_file_threshold,
+        previous_sizes,
+        &duplicates,
+        roots,
+    )?;
+
+    // Run detection
+    let spinner = style::spinner("Analyzing for cleanup candidates...");
+
+    let mut report = engine.analyze_with_stats(entries, context);
+
+    style::finish(
+        &spinner,
+        format!("✓ Found {} cleanup candidates", report.detections.len()),
+    );
+    if !report.vetoed.is_empty() {
+        outln!("🛡  {} candidates vetoed by keep rules", report.vetoed.len());
+    }
+    outln!();
+
+    if let Some(previous_sizes) = tuning_sizes {
+        let threshold = options
+            .large_file_threshold
+            .unwrap_or_else(|| cfg.detector.rules.size_threshold.effective_file_threshold());
+        let tuning = Tuning {
+            cfg,
+            previous_sizes,
+            duplicates: &duplicates,
+            roots,
+            entries,
+            context,
+        };
+        report = tuning.run(report, threshold, &mut io::stdin().lock())?;
+        outln!();
+    }
+
+    // Generate plan
+    let generator = PlanGenerator::new(roots[0].clone())
+        .with_roots(roots.to_vec())
+        .with_vetoed_entries(options.include_vetoed)
+        .with_summary_mode(options.summary)
+        .with_rule_actions(custom_rule_actions(cfg));
+    let plan = generator.generate_with_stats(report);
+
+    // Per-rule totals, with paths now relative to the scanned directory
+    if plan.detection_stats.iter().any(|s| s.entries_flagged > 0) {
+        print_rule_totals(&plan.detection_stats);
+        outln!();
+    }
+
+    Ok(plan)
+}
+
+/// Writes `plan` and prints its summary, unless it would delete most of
+/// the scanned entries and `force` isn't set.
+fn write_checked_plan(
+    cfg: &MegamaidConfig,
+    plan: &crate::models::CleanupPlan,
+    entries: &[crate::models::FileEntry],
+    roots: &[PathBuf],
+    options: &PlanOptions,
+) -> Result<()> {
+    let output = options.output;
+
+    // A plan deleting most of the tree usually means a misconfigured rule
+    let share = DeleteShare::new(plan, entries, roots);
+    let max_fraction = cfg.detector.max_delete_fraction;
+    if share.exceeds(max_fraction) {
+        outln!(
+            "🚨 WARNING: this plan marks {:.0}% of the scanned bytes for deletion ({:.1} of {:.1} MB), over the {:.0}% set by detector.max_delete_fraction",
+            share.fraction() * 100.0,
+            share.delete_bytes as f64 / 1_048_576.0,
+            share.scanned_bytes as f64 / 1_048_576.0,
+            max_fraction * 100.0
+        );
+        outln!("   Check the detector rules before executing it.");
+        outln!();
+        if !options.force {
+            anyhow::bail!(
+                "Plan not written: it would delete most of the scanned tree; rerun with --force to write it anyway"
+            );
+        }
+    }
+
+    // Write plan
+    let spinner = style::spinner(format!("Writing plan to {}...", output.display()));
+
+    PlanWriter::write(plan, output).context("Failed to write cleanup plan")?;
+
+    style::finish(&spinner, format!("✓ Plan written to {}", output.display()));
+    outln!();
+
+    // Print summary
+    print_plan_summary(plan, cfg.output.time_display);
+
+    Ok(())
+}
+
+/// Executes the stats command; `json` prints the [`PlanStats`] DTO instead.
+fn run_stats(plan_path: &Path, json: bool, protections: bool, time: TimeDisplay) -> Result<()> {
+    // Read plan file (decompressing .zst plans transparently)
+    let plan = PlanReader::read(plan_path)
+        .context(format!("Failed to read plan file: {}", plan_path.display()))?;
+
+    if json {
+        let stats = serde_json::to_string_pretty(&PlanStats::from(&plan))?;
+        outln!("{}", stats);
+        return Ok(());
+    }
+
+    outln!("📊 Cleanup Plan Statistics");
+    outln!();
+    print_plan_summary(&plan, time);
+
+    if protections {
+        outln!();
+        print_protections(&plan);
+    } else if !plan.protections.is_empty() {
+        let size: u64 = plan.protections.iter().map(|e| e.size).sum();
+        outln!();
+        outln!(
+            "🛡️  Protections held back {} detections ({} MB); run with --protections for details",
+            plan.protections.len(),
+            size / 1_048_576
+        );
+    }
+
+    Ok(())
+}
+
+/// Number of protected entries listed by `print_protections`.
+const MAX_PROTECTION_ROWS: usize = 20;
+
+/// Prints why detections were suppressed or downgraded, largest first.
+fn print_protections(plan: &crate::models::CleanupPlan) {
+    if plan.protections.is_empty() {
+        outln!("Protections: none recorded");
+        return;
+    }
+
+    let groups: std::collections::BTreeMap<String, crate::models::AggregateStats> = plan
+        .aggregate_by_protection()
+        .into_iter()
+        .map(|(protection, stats)| (protection.to_string(), stats))
+        .collect();
+    print_aggregates("Protection", &groups);
+
+    let mut events: Vec<_> = plan.protections.iter().collect();
+    events.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+
+    outln!();
+    outln!("Largest Protected Entries:");
+    for event in events.iter().take(MAX_PROTECTION_ROWS) {
+        let outcome = match event.outcome {
+            crate::models::ProtectionOutcome::Suppressed => "not flagged",
+            crate::models::ProtectionOutcome::Downgraded => "review instead of delete",
+        };
+        outln!(
+            "  {} ({} MB) - {}: {} ({}), rule {}",
+            event.path,
+            event.size / 1_048_576,
+            outcome,
+            event.protection,
+            event.detail,
+            event.rule_name
+        );
+    }
+    if events.len() > MAX_PROTECTION_ROWS {
+        outln!("  ... and {} more", events.len() - MAX_PROTECTION_ROWS);
+    }
+}
+
+/// Options for the plan from-paths command.
+struct FromPathsOptions {
+    list: PathBuf,
+    action: CleanupAction,
+    reason: String,
+    base: Option<PathBuf>,
+    output: PathBuf,
+    skip_missing: bool,
+}
+
+/// Executes the plan from-paths command.
+fn run_plan_from_paths(options: FromPathsOptions, time: TimeDisplay) -> Result<()> {
+    let paths = read_path_list(&options.list).context(format!(
+        "Failed to read path list: {}",
+        options.list.display()
+    ))?;
+
+    let mut planner = PathListPlanner::new(options.action, options.reason);
+    if let Some(base) = options.base {
+        planner = planner.with_base_path(base);
+    }
+    let result = planner
+        .build(&paths)
+        .context("Failed to build plan from path list")?;
+
+    if !result.missing.is_empty() {
+        outln!("⚠️  {} listed paths don't exist:", result.missing.len());
+        for path in result.missing.iter().take(10) {
+            outln!("  {}", path.display());
+        }
+        if result.missing.len() > 10 {
+            outln!("  ... and {} more", result.missing.len() - 10);
+        }
+        if !options.skip_missing {
+            anyhow::bail!("Listed paths are missing; fix the list or pass --skip-missing");
+        }
+        outln!();
+    }
+    if !result.covered.is_empty() {
+        outln!(
+            "ℹ️  {} listed paths are duplicates or inside a listed directory; left out",
+            result.covered.len()
+        );
+        outln!();
+    }
+
+    PlanWriter::write(&result.plan, &options.output).context("Failed to write cleanup plan")?;
+    outln!("✓ Plan written to {}", options.output.display());
+    outln!();
+
+    print_plan_summary(&result.plan, time);
+
+    Ok(())
+}
+
+/// Options for the plan import command.
+struct ImportOptions {
+    file: PathBuf,
+    format: Option<ImportFormat>,
+    du_bytes: bool,
+    output: PathBuf,
+    large_file_threshold: Option<ByteSize>,
+    /// Write the plan even if it marks too much for deletion
+    force: bool,
+}
+
+/// Executes the plan import command.
+fn run_plan_import(cfg: &MegamaidConfig, options: ImportOptions) -> Result<()> {
+    let format = options
+        .format
+        .unwrap_or_else(|| ImportFormat::from_path(&options.file));
+    let block_size = if options.du_bytes { 1 } else { 1024 };
+
+    outln!("📥 Importing {} output: {}", format, options.file.display());
+    outln!();
+
+    let scan = import_scan(&options.file, format, block_size)
+        .context(format!("Failed to import {}", options.file.display()))?;
+    outln!(
+        "✓ Imported {} entries under {}",
+        format_count(scan.entries.len()),
+        scan.root.display()
+    );
+
+    // Imported entries may not be on this machine, so duplicates aren't hashed
+    let engine = build_detection_engine(
+        cfg,
+        options.large_file_threshold,
+        HashMap::new(),
+        &[],
+        std::slice::from_ref(&scan.root),
+    )?;
+    let context = ScanContext {
+        own_artifacts: configured_artifacts(cfg).with(ArtifactKind::Plan, &options.output),
+    };
+    let mut report = engine.analyze_with_stats(&scan.entries, &context);
+    outln!(
+        "✓ Found {} cleanup candidates",
+        format_count(report.detections.len())
+    );
+    if !report.vetoed.is_empty() {
+        outln!("🛡  {} candidates vetoed by keep rules", report.vetoed.len());
+    }
+
+    // Only flagged entries need real mtimes, so only they are statted
+    if !scan.has_mtimes {
+        for detection in &mut report.detections {
+            refresh_mtime(&mut detection.entry);
+        }
+        outln!("ℹ️  Modification times read from disk (not in the import)");
+    }
+    if format == ImportFormat::Du && !options.du_bytes {
+        outln!("⚠️  du reports disk usage, not file sizes; verify will report size drift (use du -b and --du-bytes)");
+    }
+    outln!();
+
+    let plan = PlanGenerator::new(scan.root.clone())
+        .with_rule_actions(custom_rule_actions(cfg))
+        .generate_with_stats(report);
+    write_checked_plan(
+        cfg,
+        &plan,
+        &scan.entries,
+        std::slice::from_ref(&scan.root),
+        &PlanOptions {
+            output: &options.output,
+            large_file_threshold: options.large_file_threshold,
+            include_vetoed: false,
+            summary: false,
+            force: options.force,
+            tune: false,
+        },
+    )
+}
+
+/// Options for watch command.
+struct WatchOptions {
+    path: PathBuf,
+    /// Time between polls
+    interval: Duration,
+    /// Only walk directories whose mtime changed
+    incremental: bool,
+    /// Print updates as JSON plans
+    json: bool,
+    /// Config file whose edits are applied while watching
+    config_file: PathBuf,
+}
+
+/// Executes the watch command.
+///
+/// Polls the tree until Ctrl-C, re-running detection on the in-memory
+/// entries after every poll that finds changes.
+///
+/// Edits to the config file are picked up at each poll: a valid config
+/// replaces the detection settings at once, while an invalid one is
+/// reported and the previous config stays in use. Scanner settings only
+/// apply to the next watch.
+fn run_watch(cfg: &MegamaidConfig, options: WatchOptions) -> Result<()> {
+    let root = &options.path;
+    if !root.is_dir() {
+        anyhow::bail!("Not a directory: {}", root.display());
+    }
+    if !options.json {
+        outln!(
+            "👀 Watching {} - checking every {}s, Ctrl+C to stop",
+            root.display(),
+            options.interval.as_secs()
+        );
+        outln!();
+    }
+
+    let scan_config = ScanConfig {
+        follow_links: cfg.scanner.follow_symlinks,
+        max_depth: cfg.scanner.max_depth,
+        skip_hidden: cfg.scanner.skip_hidden,
+        include_streams: cfg.scanner.include_streams,
+        remote: cfg.scanner.remote,
+        exclude_globs: cfg.scanner.exclude.clone(),
+        respect_ignore_files: cfg.scanner.respect_ignore_files,
+        same_filesystem: cfg.scanner.same_filesystem,
+        collect_owner: cfg.scanner.collect_owner,
+        largest_first: cfg.scanner.largest_first,
+        max_iops: cfg.scanner.max_iops,
+    };
+    let artifacts = configured_artifacts(cfg);
+    let scanner = FileScanner::new(scan_config).with_exclusions(artifacts.clone());
+
+    // Ctrl-C stops watching; a second one terminates right away
+    let cancel = scanner.cancel_handle();
+    for signal in signal_hook::consts::TERM_SIGNALS {
+        signal_hook::flag::register_conditional_shutdown(*signal, 1, Arc::clone(&cancel))?;
+        signal_hook::flag::register(*signal, Arc::clone(&cancel))?;
+    }
+
+    let mut watcher = match Watcher::start(scanner, root) {
+        Err(ScanError::Cancelled) => return Ok(()),
+        result => result.context(format!("Failed to scan directory: {}", root.display()))?,
+    }
+    .with_incremental(options.incremental);
+    if !watcher.is_notified() {
+        watch_notice(
+            options.json,
+            "⚠️  File change notifications aren't available here - rescanning the tree at every check",
+        );
+    }
+
+    // The growth rule compares with the sizes when watching started, also
+    // if a reload enables it
+    let previous_sizes: HashMap<PathBuf, u64> = watcher
+        .entries()
+        .iter()
+        .filter(|e| e.entry_type == crate::models::EntryType::Directory)
+        .map(|e| (e.path.clone(), e.size))
+        .collect();
+    // Hashing again on every change would be too slow to keep up
+    let build = |cfg: &MegamaidConfig| -> Result<(DetectionEngine, PlanGenerator)> {
+        let engine = build_detection_engine(
+            cfg,
+            None,
+            previous_sizes.clone(),
+            &[],
+            std::slice::from_ref(root),
+        )?;
+        let generator =
+            PlanGenerator::new(root.clone()).with_rule_actions(custom_rule_actions(cfg));
+        Ok((engine, generator))
+    };
+    let context = ScanContext {
+        own_artifacts: artifacts,
+    };
+    let update = |(engine, generator): &(DetectionEngine, PlanGenerator),
+                  cfg: &MegamaidConfig,
+                  entries: &[crate::models::FileEntry],
+                  changes: Option<WatchChanges>| {
+        let plan = generator.generate_with_stats(engine.analyze_with_stats(entries, &context));
+        print_watch_update(&plan, changes, options.json, cfg.output.time_display)
+    };
+
+    let mut current = cfg.clone();
+    let mut detection = build(&current)?;
+    let mut reloader = ConfigReloader::new(&options.config_file);
+    update(&detection, &current, watcher.entries(), None)?;
+    loop {
+        // Sleep in short steps so Ctrl-C doesn't wait for the next poll
+        let next_poll = Instant::now() + options.interval;
+        while Instant::now() < next_poll && !cancel.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(250));
+        }
+        // A poll without notified changes doesn't scan, so it can't notice
+        if cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        let changes = match watcher.poll() {
+            Err(ScanError::Cancelled) => break,
+            result => result.context(format!("Failed to scan directory: {}", root.display()))?,
+        };
+
+        // Everything is built from an edited config before any of it is used
+        let reloaded = match reloader.poll() {
+            None => false,
+            Some(result) => match result.and_then(|cfg| Ok((build(&cfg)?, cfg))) {
+                Ok((built, cfg)) => {
+                    let note = if cfg.scanner != current.scanner {
+                        " (scanner settings apply to the next watch)"
+                    } else {
+                        ""
+                    };
+                    watch_notice(
+                        options.json,
+                        &format!("🔄 Reloaded {}{}", reloader.path().display(), note),
+                    );
+                    detection = built;
+                    current = cfg;
+                    true
+                }
+                Err(e) => {
+                    watch_notice(
+                        options.json,
+                        &format!(
+                            "⚠️  Ignoring the edit to {}, keeping the previous config: {:#}",
+                            reloader.path().display(),
+                            e
+                        ),
+                    );
+                    false
+                }
+            },
+        };
+        let changes = (!changes.is_empty()).then_some(changes);
+        if reloaded || changes.is_some() {
+            update(&detection, &current, watcher.entries(), changes)?;
+        }
+    }
+
+    if !options.json {
+        outln!("✓ Stopped watching {}", root.display());
+    }
+    Ok(())
+}
+
+/// Prints a message about the watch itself, to stderr when stdout carries
+/// JSON plans.
+fn watch_notice(json: bool, message: &str) {
+    if json {
+        eprintln!("{}", style::styled(message));
+    } else {
+        outln!("{}", message);
+    }
+}
+
+/// Prints the candidates found by a watch poll, with what changed since the
+/// previous one; `json` prints the whole plan on one line instead.
+fn print_watch_update(
+    plan: &crate::models::CleanupPlan,
+    changes: Option<WatchChanges>,
+    json: bool,
+    time: TimeDisplay,
+) -> Result<()> {
+    if json {
+        outln!("{}", serde_json::to_string(plan)?);
+        return Ok(());
+    }
+
+    out!(
+        "[{}] {} cleanup candidates, {:.1} GB",
+        time.format(plan.created_at),
+        format_count(plan.entries.len()),
+        plan.total_size() as f64 / 1_073_741_824.0
+    );
+    match changes {
+        Some(changes) => outln!(
+            " ({} added, {} removed, {} changed)",
+            format_count(changes.added),
+            format_count(changes.removed),
+            format_count(changes.modified)
+        ),
+        None => outln!(),
+    }
+    if plan.detection_stats.iter().any(|s| s.entries_flagged > 0) {
+        print_rule_totals(&plan.detection_stats);
+    }
+    outln!();
+    io::stdout().flush()?;
+    Ok(())
+}
+
+/// Executes the estimate command.
+fn run_estimate(path: &Path, config: EstimateConfig, top: usize) -> Result<()> {
+    if !path.is_dir() {
+        anyhow::bail!("Not a directory: {}", path.display());
+    }
+
+    outln!("📏 Estimating sizes under: {}", path.display());
+    outln!();
+
+    let report = Estimator::new(config)
+        .estimate(path)
+        .context("Failed to read directory")?;
+
+    for subtree in report.subtrees.iter().take(top) {
+        let name = subtree
+            .path
+            .strip_prefix(path)
+            .unwrap_or(&subtree.path)
+            .display();
+        outln!(
+            "  {:>24}  {:>16}  {}{}",
+            format_estimate_size(&subtree.estimate),
+            format_estimate_files(&subtree.estimate),
+            name,
+            std::path::MAIN_SEPARATOR
+        );
+    }
+    if report.subtrees.len() > top {
+        outln!("  … {} more", report.subtrees.len() - top);
+    }
+    if report.root_files.files > 0.0 {
+        outln!(
+            "  {:>24}  {:>16}  (files in the root)",
+            format_estimate_size(&report.root_files),
+            format_estimate_files(&report.root_files)
+        );
+    }
+
+    outln!();
+    outln!(
+        "Total: {}{}, {}",
+        format_estimate_size(&report.total),
+        if report.total.exact {
+            ""
+        } else {
+            " (95% confidence)"
+        },
+        format_estimate_files(&report.total)
+    );
+    out!(
+        "Listed {} directories in {:.1}s",
+        format_count(report.dirs_read),
+        report.elapsed.as_secs_f64()
+    );
+    if report.dirs_unreadable > 0 {
+        out!(" ({} unreadable, counted as empty)", report.dirs_unreadable);
+    }
+    outln!();
+
+    if !report.total.exact {
+        outln!();
+        outln!(
+            "Sizes marked ~ are sampled; run `megamaid scan` on the largest subtrees for exact sizes."
+        );
+    }
+
+    Ok(())
+}
+
+/// Formats an estimated size as "~12.3 GB ± 1.1 GB", or "12.3 GB" if counted exactly.
+fn format_estimate_size(estimate: &crate::scanner::Estimate) -> String {
+    let (unit, scale) = [("GB", 1_073_741_824.0), ("MB", 1_048_576.0)]
+        .into_iter()
+        .find(|(_, scale)| estimate.bytes >= *scale)
+        .unwrap_or(("KB", 1024.0));
+    if estimate.exact {
+        format!("{:.1} {}", estimate.bytes / scale, unit)
+    } else {
+        format!(
+            "~{:.1} {} ± {:.1} {}",
+            estimate.bytes / scale,
+            unit,
+            estimate.bytes_margin / scale,
+            unit
+        )
+    }
+}
+
+/// Formats an estimated file count as "~1,204 files", without the ~ if counted exactly.
+fn format_estimate_files(estimate: &crate::scanner::Estimate) -> String {
+    format!(
+        "{}{} files",
+        if estimate.exact { "" } else { "~" },
+        format_count(estimate.files.round() as usize)
+    )
+}
+
+/// Executes the analyze command: scans `path` and prints where its space
+/// goes, without generating a plan.
+fn run_analyze(cfg: &MegamaidConfig, path: &Path, top: usize) -> Result<()> {
+    if !path.is_dir() {
+        anyhow::bail!("Not a directory: {}", path.display());
+    }
+
+    outln!("📊 Analyzing disk usage: {}", path.display());
+    outln!();
+
+    let spinner = style::spinner("Scanning filesystem...");
+    let scanner =
+        FileScanner::new(cfg.scanner.clone().into()).with_exclusions(configured_artifacts(cfg));
+    let entries = scanner.scan(path).context("Failed to scan directory")?;
+    style::finish(
+        &spinner,
+        format!("✓ Scanned {} entries", format_count(entries.len())),
+    );
+    outln!();
+    print_unreadable(&scanner.errors());
+
+    let report = UsageAnalyzer::new().with_top(top).analyze(&entries);
+    let total = report.total.bytes.max(1) as f64;
+    let row = |name: &str, usage: Usage| {
+        outln!(
+            "  {:<40} {:>10} {:>10} {:>6.1}%",
+            name,
+            format_count(usage.files as usize),
+            format_bytes(usage.bytes),
+            usage.bytes as f64 * 100.0 / total
+        );
+    };
+
+    outln!("Largest directories:");
+    outln!("  {:<40} {:>10} {:>7}", "Directory", "Size", "Share");
+    for dir in &report.largest_directories {
+        let name = dir.path.strip_prefix(path).unwrap_or(&dir.path).display();
+        outln!(
+            "  {:<40} {:>10} {:>6.1}%",
+            name.to_string(),
+            format_bytes(dir.bytes),
+            dir.bytes as f64 * 100.0 / total
+        );
+    }
+    outln!();
+
+    outln!("By extension:");
+    outln!(
+        "  {:<40} {:>10} {:>10} {:>7}",
+        "Extension",
+        "Files",
+        "Size",
+        "Share"
+    );
+    for (extension, usage) in &report.by_extension {
+        row(
+            if extension.is_empty() {
+                "(none)"
+            } else {
+                extension
+            },
+            *usage,
+        );
+    }
+    outln!();
+
+    outln!("By age (last modified):");
+    outln!(
+        "  {:<40} {:>10} {:>10} {:>7}",
+        "Age",
+        "Files",
+        "Size",
+        "Share"
+    );
+    for (bucket, usage) in &report.by_age {
+        row(bucket.label(), *usage);
+    }
+    outln!();
+
+    outln!(
+        "Total: {} files, {}",
+        format_count(report.total.files as usize),
+        format_bytes(report.total.bytes)
+    );
+    Ok(())
+}
+
+/// Lists the groups of identical files below `path`, those wasting the most
+/// space first.
+fn run_duplicates(
+    cfg: &MegamaidConfig,
+    path: &Path,
+    min_size: Option<ByteSize>,
+    top: usize,
+) -> Result<()> {
+    if !path.is_dir() {
+        anyhow::bail!("Not a directory: {}", path.display());
+    }
+
+    outln!("🔁 Finding duplicate files: {}", path.display());
+    outln!();
+
+    let spinner = style::spinner("Scanning filesystem...");
+    let scanner =
+        FileScanner::new(cfg.scanner.clone().into()).with_exclusions(configured_artifacts(cfg));
+    let entries = scanner.scan(path).context("Failed to scan directory")?;
+    style::finish(
+        &spinner,
+        format!("✓ Scanned {} entries", format_count(entries.len())),
+    );
+    print_unreadable(&scanner.errors());
+
+    let min_size = min_size.unwrap_or(cfg.detector.rules.duplicates.min_size);
+    let spinner = style::spinner("Hashing duplicate candidates...");
+    let groups = DuplicateFinder::new()
+        .with_min_size(min_size.as_bytes())
+        .find(&entries);
+    style::finish(
+        &spinner,
+        format!(
+            "✓ Found {} groups of duplicate files",
+            format_count(groups.len())
+        ),
+    );
+    outln!();
+
+    let relative = |file: &Path| {
+        file.strip_prefix(path)
+            .unwrap_or(file)
+            .display()
+            .to_string()
+    };
+    for group in groups.iter().take(top) {
+        outln!(
+            "{} × {} ({} in extra copies)",
+            format_bytes(group.size),
+            group.files.len(),
+            format_bytes(group.wasted_bytes())
+        );
+        outln!("  keep    {}", relative(&group.keeper().path));
+        for file in group.redundant() {
+            outln!("  extra   {}", relative(&file.path));
+        }
+        outln!();
+    }
+    if groups.len() > top {
+        outln!(
+            "... and {} more groups (--top lists more)",
+            format_count(groups.len() - top)
+        );
+        outln!();
+    }
+
+    let wasted: u64 = groups.iter().map(|g| g.wasted_bytes()).sum();
+    outln!(
+        "Total: {} reclaimable by keeping the newest copy of each file",
+        format_bytes(wasted)
+    );
+    if !groups.is_empty() && !cfg.detector.rules.duplicates.enabled {
+        outln!(
+            "ℹ️  Enable detector.rules.duplicates to flag the extra copies for review in scan plans"
+        );
+    }
+    Ok(())
+}
+
+/// Formats a size as "12.3 GB", "4.0 MB" or "0.5 KB".
+fn format_bytes(bytes: u64) -> String {
+    let (unit, scale) = [("GB", 1_073_741_824.0), ("MB", 1_048_576.0)]
+        .into_iter()
+        .find(|(_, scale)| bytes as f64 >= *scale)
+        .unwrap_or(("KB", 1024.0));
+    format!("{:.1} {}", bytes as f64 / scale, unit)
+}
+
+/// Options for verify command.
+struct VerifyOptions {
+    plan_path: PathBuf,
+    output: Option<PathBuf>,
+    fail_fast: bool,
+    skip_mtime: bool,
+    /// Re-verify at this interval until the plan is clean
+    watch_interval: Option<Duration>,
+    /// Give up watching after this long
+    watch_timeout: Option<Duration>,
+}
+
+/// Executes the verify command.
+fn run_verify(cfg: &MegamaidConfig, options: VerifyOptions) -> Result<()> {
+    let plan_path = &options.plan_path;
+    outln!("📋 Verifying cleanup plan: {}", plan_path.display());
+    outln!();
+
+    // Read plan file (decompressing .zst plans transparently)
+    let plan = PlanReader::read(plan_path)
+        .context(format!("Failed to read plan file: {}", plan_path.display()))?;
+
+    // Configure verification - CLI args override config
+    let ver_config = VerificationConfig {
+        check_mtime: !options.skip_mtime && cfg.verifier.check_mtime,
+        check_size: cfg.verifier.check_size,
+        fail_fast: options.fail_fast || cfg.verifier.fail_fast,
+        check_permissions: cfg.verifier.check_permissions,
+    };
+
+    // Run verification
+    let spinner = style::spinner("Verifying entries...");
+
+    let verifier = VerificationEngine::new(ver_config);
+    let started = Instant::now();
+    let mut result = verifier.verify(&plan)?;
+
+    // In watch mode, keep re-verifying until the plan is clean or we time out
+    if let Some(interval) = options.watch_interval {
+        while !result.is_safe_to_execute() {
+            if options
+                .watch_timeout
+                .is_some_and(|timeout| started.elapsed() + interval > timeout)
+            {
+                break;
+            }
+
+            spinner.set_message(format!(
+                "{} drifted, {} missing - re-checking in {}s...",
+                result.drifted.len(),
+                result.missing.len(),
+                interval.as_secs()
+            ));
+            std::thread::sleep(interval);
+            result = verifier.verify(&plan)?;
+        }
+    }
+
+    style::finish(
+        &spinner,
+        format!(
+            "✓ Verified {} of {} entries",
+            result.verified, result.total_entries
+        ),
+    );
+    outln!();
+
+    // Print report
+    let report = DriftReporter::generate_report(&result);
+    outln!("{}", report);
+
+    // Write report file if requested
+    if let Some(output_path) = &options.output {
+        DriftReporter::write_report(&result, output_path)?;
+        outln!("📄 Drift report written to: {}", output_path.display());
+        outln!();
+    }
+
+    // Record the outcome so execute can reuse it via --use-verification
+    let record_path = VerificationRecord::default_path(plan_path);
+    VerificationRecord::new(plan_path, &result)
+        .and_then(|record| record.write(&record_path))
+        .context("Failed to write verification record")?;
+    outln!(
+        "🧾 Verification record written to: {}",
+        record_path.display()
+    );
+    outln!();
+
+    // Exit with error if drift detected
+    if !result.is_safe_to_execute() {
+        if options.watch_interval.is_some() {
+            anyhow::bail!("Drift detected - plan did not become clean before the watch timeout");
+        }
+        anyhow::bail!("Drift detected - plan is not safe to execute");
+    }
+
+    Ok(())
+}
+
+/// Executes the expand command.
+fn run_expand(plan_path: &Path, entry: &str, output: Option<&Path>) -> Result<()> {
+    // Read plan file (decompressing .zst plans transparently)
+    let mut plan = PlanReader::read(plan_path)
+        .context(format!("Failed to read plan file: {}", plan_path.display()))?;
+
+    let count = expand_entry(&mut plan, entry)
+        .with_context(|| format!("Failed to expand entry: {}", entry))?;
+
+    let output = output.unwrap_or(plan_path);
+    PlanWriter::write(&plan, output).context("Failed to write plan file")?;
+
+    outln!("✓ Expanded {} into {} entries", entry, count);
+    outln!("  Plan written to: {}", output.display());
+
+    Ok(())
+}
+
+/// Validates a stored verification record and runs only the quick per-entry checks.
+fn quick_check_with_record(
+    cfg: &MegamaidConfig,
+    verifier: &VerificationEngine,
+    plan: &crate::models::CleanupPlan,
+    plan_path: &Path,
+    record_path: &Path,
+) -> Result<VerificationResult> {
+    let record = VerificationRecord::read(record_path).context(format!(
+        "Failed to read verification record: {}",
+        record_path.display()
+    ))?;
+    let max_age = chrono::Duration::minutes(cfg.verifier.record_max_age_minutes as i64);
+    record
+        .validate_for(plan_path, max_age)
+        .context("Verification record cannot be used - re-run verify or omit --use-verification")?;
+
+    outln!(
+        "🔍 Using verification from {} (quick checks only)...",
+        cfg.output.time_display.format(record.verified_at)
+    );
+    Ok(verifier.quick_check(plan))
+}
+
+/// Options for execute command.
+struct ExecuteOptions {
+    plan_path: PathBuf,
+    dry_run: bool,
+    interactive: bool,
+    backup_dir: Option<PathBuf>,
+    recycle_bin: bool,
+    fail_fast: bool,
+    skip_verify: bool,
+    use_verification: Option<PathBuf>,
+    shard: Option<Shard>,
+    resume: Option<PathBuf>,
+    /// Log of an earlier execution whose successful entries are skipped
+    since_log: Option<PathBuf>,
+    /// Log of an earlier execution whose failed entries are the only ones run
+    only_failed: Option<PathBuf>,
+    largest_first: bool,
+    allow_stale_plan: bool,
+    /// Permanently delete this run's recycled items afterwards
+    empty_recycle_bin_after: bool,
+    /// Snapshot the volume before deleting
+    snapshot: bool,
+    log_file: PathBuf,
+    parallel: bool,
+    batch_size: Option<usize>,
+    /// Per-operation timeout overriding executor.operation_timeout_secs
+    operation_timeout: Option<Duration>,
+    /// Only run while the system is idle, optionally overriding
+    /// executor.idle.min_idle_minutes
+    when_idle: Option<Option<u64>>,
+    /// Account to delete as, overriding executor.run_as
+    run_as: Option<String>,
+    /// Skip the confirmation prompt before a batch execution
+    assume_yes: bool,
+}
+
+/// Executes the execute command.
+fn run_execute(cfg: &MegamaidConfig, options: ExecuteOptions) -> Result<()> {
+    let plan_path = &options.plan_path;
+    outln!("🗑️  Executing cleanup plan: {}", plan_path.display());
+    outln!();
+
+    // Read plan file (decompressing .zst plans transparently)
+    let mut plan = PlanReader::read(plan_path)
+        .context(format!("Failed to read plan file: {}", plan_path.display()))?;
+
+    // Old plans are the main source of drift; a dry run is always allowed
+    if !options.dry_run && !options.allow_stale_plan {
+        check_plan_age(
+            &plan,
+            cfg.executor.max_plan_age_days,
+            cfg.output.time_display,
+        )?;
+    }
+
+    // A wrong password stops the run before anything is verified or logged
+    let run_as = match options.run_as.as_ref().or(cfg.executor.run_as.as_ref()) {
+        Some(account) if !options.dry_run => Some(logon_run_as(cfg, account)?),
+        _ => None,
+    };
+
+    // Restrict to this invocation's shard
+    if let Some(shard) = options.shard {
+        let total = plan.entries.len();
+        plan = shard.apply(&plan);
+        outln!(
+            "🧩 Shard {}: {} of {} entries",
+            shard,
+            plan.entries.len(),
+            total
+        );
+        outln!();
+    }
+
+    // Drop entries an earlier execution already removed
+    if let Some(since_log) = &options.since_log {
+        let log = TransactionLogger::read(since_log).context(format!(
+            "Failed to read transaction log: {}",
+            since_log.display()
+        ))?;
+        let processed = ProcessedEntries::from_log(&log);
+        let before = plan.entries.len();
+        let entries = std::mem::take(&mut plan.entries);
+        plan.entries = entries
+            .into_iter()
+            .filter(|e| !processed.contains(plan.root_of(e), e))
+            .collect();
+        outln!(
+            "⏭️  Skipping {} entries already processed by execution {}",
+            before - plan.entries.len(),
+            log.execution_id
+        );
+        outln!();
+    }
+
+    // Keep only the entries an earlier execution failed on
+    if let Some(only_failed) = &options.only_failed {
+        let log = TransactionLogger::read(only_failed).context(format!(
+            "Failed to read transaction log: {}",
+            only_failed.display()
+        ))?;
+        let failed = ProcessedEntries::failed(&log);
+        let entries = std::mem::take(&mut plan.entries);
+        plan.entries = entries
+            .into_iter()
+            .filter(|e| failed.contains(plan.root_of(e), e))
+            .collect();
+        outln!(
+            "🔁 Retrying {} entries that failed in execution {}",
+            plan.entries.len(),
+            log.execution_id
+        );
+        outln!();
+    }
+
+    // Batch runs only start when they won't get in anyone's way
+    if !options.dry_run && !options.interactive {
+        check_run_constraints(&cfg.executor.constraints.to_constraints())?;
+    }
+
+    // Create transaction logger, or continue a paused one
+    let log_path = options.resume.as_ref().unwrap_or(&options.log_file).clone();
+    let mut logger = match &options.resume {
+        Some(resume_path) => {
+            let logger = TransactionLogger::resume(resume_path.clone()).context(format!(
+                "Failed to resume transaction log: {}",
+                resume_path.display()
+            ))?;
+            if !same_file(logger.plan_file(), plan_path) {
+                anyhow::bail!(
+                    "{} was recorded for plan {}, not {}",
+                    resume_path.display(),
+                    logger.plan_file().display(),
+                    plan_path.display()
+                );
+            }
+            // Dry-run results say nothing about what a real run has done
+            if logger.options().dry_run != options.dry_run {
+                anyhow::bail!(
+                    "{} records a {}; resume it {} --dry-run",
+                    resume_path.display(),
+                    if logger.options().dry_run {
+                        "dry run"
+                    } else {
+                        "real execution"
+                    },
+                    if logger.options().dry_run {
+                        "with"
+                    } else {
+                        "without"
+                    }
+                );
+            }
+            let recorded = logger.options();
+            if options
+                .backup_dir
+                .as_ref()
+                .is_some_and(|dir| Some(dir) != recorded.backup_dir.as_ref())
+                || (options.recycle_bin && !recorded.use_recycle_bin)
+                || (options.fail_fast && !recorded.fail_fast)
+            {
+                outln!("⚠️  Resuming with the options of the first run; --backup-dir, --recycle-bin and --fail-fast are ignored");
+            }
+
+            // A resumed shard keeps working on the same shard
+            if let Some(shard) = logger.shard() {
+                plan = shard.apply(&plan);
+            }
+
+            // Drop entries handled by earlier runs
+            let processed = logger.processed_paths();
+            let before = plan.entries.len();
+            let entries = std::mem::take(&mut plan.entries);
+            plan.entries = entries
+                .into_iter()
+                .filter(|e| !processed.contains(&PathKey::new(&plan.entry_path(e))))
+                .collect();
+            outln!(
+                "⏯️  Resuming transaction {}: {} entries already processed",
+                logger.execution_id(),
+                before - plan.entries.len()
+            );
+            outln!();
+            logger
+        }
+        None => {
+            let transaction_opts = TransactionOptions {
+                dry_run: options.dry_run,
+                backup_dir: options
+                    .backup_dir
+                    .clone()
+                    .or(cfg.executor.backup_dir.clone()),
+                use_recycle_bin: options.recycle_bin || cfg.executor.use_recycle_bin,
+                fail_fast: options.fail_fast || cfg.executor.fail_fast,
+            };
+            let logger = TransactionLogger::new(plan_path, log_path.clone(), transaction_opts);
+            match options.shard {
+                Some(shard) => logger.with_shard(shard),
+                None => logger,
+            }
+        }
+    };
+
+    // Verify unless skipped
+    if !options.skip_verify && !options.dry_run {
+        let ver_config: VerificationConfig = cfg.verifier.clone().into();
+        let verifier = VerificationEngine::new(ver_config);
+        let verification = match &options.use_verification {
+            Some(record_path) => {
+                quick_check_with_record(cfg, &verifier, &plan, plan_path, record_path)?
+            }
+            None => {
+                outln!("🔍 Verifying plan before execution...");
+                verifier.verify(&plan)?
+            }
+        };
+
+        if !verification.is_safe_to_execute() {
+            let report = DriftReporter::generate_report(&verification);
+            outln!("{}", report);
+            anyhow::bail!(
+                "Drift detected - cannot execute. Use --skip-verify to override (not recommended)."
+            );
+        }
+        outln!("✓ Verification passed\n");
+
+        // Entries that will fail anyway are worth a word before the run
+        let matrix = DriftReporter::permission_matrix(&verification);
+        if !matrix.is_empty() {
+            outln!(
+                "⚠️  {} entries can't be deleted with the current rights",
+                format_count(verification.missing_permissions.len())
+            );
+            outln!("{}", matrix);
+        }
+    }
+
+    // Configure execution - CLI args override config
+    let mode = if options.dry_run {
+        ExecutionMode::DryRun
+    } else if options.interactive {
+        ExecutionMode::Interactive
+    } else {
+        ExecutionMode::Batch
+    };
+
+    // The log holds these, so a resumed run carries on the way it started
+    let recorded = logger.options().clone();
+    let batch_size = options.batch_size.unwrap_or(cfg.executor.batch_size);
+    let exec_config = ExecutionConfig {
+        mode,
+        backup_dir: recorded.backup_dir,
+        fail_fast: recorded.fail_fast,
+        use_recycle_bin: recorded.use_recycle_bin,
+        parallel: options.parallel || cfg.executor.parallel,
+        batch_size,
+        operation_timeout: options
+            .operation_timeout
+            .or(cfg.executor.operation_timeout_secs.map(Duration::from_secs)),
+        volume_concurrency: cfg.executor.per_volume_concurrency.clone(),
+    };
+
+    if options.empty_recycle_bin_after && !exec_config.use_recycle_bin {
+        anyhow::bail!("--empty-recycle-bin-after requires recycle bin mode (--recycle-bin)");
+    }
+
+    // Rollback restores deleted entries; moved or recycled ones can be restored from there
+    let deletes_permanently = exec_config.backup_dir.is_none() && !exec_config.use_recycle_bin;
+    if options.snapshot && !deletes_permanently {
+        anyhow::bail!(
+            "--snapshot only applies to permanent deletion, not backup or recycle bin mode"
+        );
+    }
+
+    // Display mode
+    if options.dry_run {
+        outln!("🔄 DRY RUN MODE - No files will be deleted");
+        outln!();
+    } else if options.interactive {
+        outln!("💬 INTERACTIVE MODE - You will be prompted for each deletion");
+        outln!();
+    } else if let Some(ref backup_path) = exec_config.backup_dir {
+        outln!(
+            "📦 BACKUP MODE - Files will be moved to: {}",
+            backup_path.display()
+        );
+        outln!();
+    } else if exec_config.use_recycle_bin {
+        outln!("♻️  RECYCLE BIN MODE - Files will be moved to recycle bin");
+        outln!();
+    }
+
+    // Display parallel execution mode
+    if options.parallel {
+        outln!("⚡ PARALLEL EXECUTION - Using batch size: {}", batch_size);
+        outln!();
+    }
+
+    // Count Delete actions
+    let delete_count = plan
+        .entries
+        .iter()
+        .filter(|e| e.action == crate::models::CleanupAction::Delete)
+        .count();
+
+    // A resumed run always finalizes its log, even with nothing left to do
+    if delete_count == 0 && options.resume.is_none() {
+        outln!("No entries marked for deletion.");
+        return Ok(());
+    }
+
+    let delete_bytes: u64 = plan
+        .entries
+        .iter()
+        .filter(|e| e.action == crate::models::CleanupAction::Delete)
+        .map(|e| e.size)
+        .sum();
+
+    // Recycling what's already in the bin keeps two copies there
+    if exec_config.use_recycle_bin {
+        warn_recycled_duplicates(&plan);
+    }
+
+    // Batch runs delete without per-entry prompts, so get one explicit approval
+    if exec_config.mode == ExecutionMode::Batch {
+        let verb = if exec_config.backup_dir.is_some() {
+            "move to backup"
+        } else if exec_config.use_recycle_bin {
+            "recycle"
+        } else {
+            "delete"
+        };
+        let method = confirm_batch_execution(delete_count, delete_bytes, verb, options.assume_yes)?;
+        logger.record_confirmation(method, delete_count, delete_bytes);
+
+        // Unattended runs can still be stopped by whoever is logged on
+        if method == ConfirmationMethod::Flag && cfg.executor.last_chance.enabled {
+            give_last_chance(&cfg.executor.last_chance, delete_count, delete_bytes)?;
+        }
+    }
+
+    // A resumed run keeps the snapshot taken before its first run
+    if !options.dry_run && deletes_permanently && options.resume.is_none() {
+        take_snapshot(cfg, &plan, options.snapshot, delete_bytes, &mut logger)?;
+    }
+
+    outln!("Processing {} deletion(s)...", delete_count);
+    outln!();
+
+    outln!("📋 Transaction ID: {}", logger.execution_id());
+    outln!();
+
+    let use_recycle_bin = exec_config.use_recycle_bin;
+    // Whatever the plan says, this run's log, plan and backup directory stay
+    let mut protected = configured_artifacts(cfg)
+        .with(ArtifactKind::TransactionLog, &log_path)
+        .with(
+            ArtifactKind::TransactionLog,
+            TransactionLogger::journal_path(&log_path),
+        )
+        .with(ArtifactKind::Plan, plan_path);
+    if let Some(dir) = &exec_config.backup_dir {
+        protected.add(ArtifactKind::BackupDir, dir);
+    }
+    for log in options.since_log.iter().chain(&options.only_failed) {
+        protected.add(ArtifactKind::TransactionLog, log);
+    }
+
+    let mut executor = ExecutionEngine::new(exec_config)
+        .with_largest_first(options.largest_first)
+        .with_protected(protected);
+    if let Some(token) = run_as {
+        executor = executor.with_run_as(token);
+    }
+    if let Some(min_idle_minutes) = options.when_idle {
+        let policy = cfg.executor.idle.to_policy(min_idle_minutes);
+        let gate = IdleGate::new(policy).with_listener(|state| match state {
+            IdleState::Waiting => outln!("⏸  System in use - waiting for it to go idle"),
+            IdleState::Running => outln!("▶  System idle - deleting"),
+        });
+        outln!(
+            "🌙 IDLE MODE - Deleting only after {} minute(s) without input, pausing on activity",
+            policy.min_idle.as_secs() / 60
+        );
+        if !gate.can_read_input() {
+            outln!("⚠️  Input idle time can't be read here; only disk activity is checked");
+        }
+        outln!();
+        executor = executor.with_idle_gate(gate);
+    }
+
+    // Execute; the first Ctrl+C pauses after the current entry, a second one
+    // saves the log and exits
+    let logger = Arc::new(Mutex::new(logger));
+    let signals = watch_exit_signals(executor.pause_handle(), Arc::clone(&logger))?;
+
+    let progress = style::progress_bar(delete_count as u64);
+
+    let started = SystemTime::now();
+    // Each operation is logged as soon as it's done, so an interrupted run
+    // can be resumed
+    let executed = executor.execute_with(&plan, |op| {
+        progress.inc(1);
+        if op.status == crate::executor::OperationStatus::Failed {
+            let label = op.error.as_ref().map_or("Failed", |e| e.label());
+            progress.set_message(format!("{}: {}", label, op.path.display()));
+        }
+        lock(&logger).append_operation(op)
+    });
+    signals.close();
+    let mut logger = lock(&logger);
+    let result = match executed {
+        Ok(result) => result,
+        Err(e) => {
+            if let Err(log_error) = logger.abort() {
+                outln!("⚠️  Could not save the transaction log: {}", log_error);
+            }
+            return Err(e.into());
+        }
+    };
+
+    style::finish(&progress, "Done");
+    outln!();
+
+    // Finalize transaction log
+    let status = if result.paused {
+        TransactionStatus::Paused
+    } else if result.summary.failed > 0 {
+        TransactionStatus::Failed
+    } else {
+        TransactionStatus::Completed
+    };
+    logger.finalize(&result, status)?;
+
+    // Print summary
+    print_execution_summary(&result.summary, options.dry_run);
+    print_failures(&result, plan_path, &log_path);
+    outln!();
+    outln!("📄 Transaction log: {}", log_path.display());
+
+    // Recycled entries still take up space until the recycle bin is emptied
+    if use_recycle_bin && !options.dry_run {
+        if options.empty_recycle_bin_after {
+            empty_recycled_entries(&result, started, options.assume_yes)?;
+        }
+        update_trash_index(&result, logger.execution_id());
+        outln!();
+        print_recycle_bin_report();
+    }
+
+    if result.paused {
+        outln!();
+        outln!("⏸️  Execution paused. Resume with:");
+        outln!(
+            "  megamaid execute {} --resume {}",
+            plan_path.display(),
+            log_path.display()
+        );
+    }
+
+    // Exit with error if any failures
+    if result.summary.failed > 0 {
+        anyhow::bail!("{} operation(s) failed", result.summary.failed);
+    }
+
+    Ok(())
+}
+
+/// Locks `mutex`, using the data of a thread that panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Handles Ctrl+C and termination signals during an execution.
+///
+/// The first signal sets `pause`, stopping the run after the entry in
+/// progress; the next one saves `logger` as aborted and exits. The returned
+/// handle stops watching when closed.
+fn watch_exit_signals(
+    pause: Arc<std::sync::atomic::AtomicBool>,
+    logger: Arc<Mutex<TransactionLogger>>,
+) -> Result<signal_hook::iterator::Handle> {
+    let mut signals = signal_hook::iterator::Signals::new(signal_hook::consts::TERM_SIGNALS)?;
+    let handle = signals.handle();
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            if pause.swap(true, Ordering::SeqCst) {
+                if let Err(e) = lock(&logger).abort() {
+                    eprintln!("Could not save the transaction log: {}", e);
+                }
+                std::process::exit(1);
+            }
+        }
+    });
+    Ok(handle)
+}
+
+/// Permanently deletes the items this run moved to the recycle bin, after confirmation.
+fn empty_recycled_entries(
+    result: &crate::executor::ExecutionResult,
+    started: SystemTime,
+    assume_yes: bool,
+) -> Result<()> {
+    let recycled: Vec<PathBuf> = result
+        .operations
+        .iter()
+        .filter(|op| {
+            op.action == OperationAction::MoveToRecycleBin && op.status == OperationStatus::Success
+        })
+        .map(|op| op.path.clone())
+        .collect();
+    if recycled.is_empty() {
+        return Ok(());
+    }
+
+    let bytes: u64 = result
+        .operations
+        .iter()
+        .filter(|op| op.action == OperationAction::MoveToRecycleBin)
+        .filter_map(|op| op.size_freed)
+        .sum();
+    let prompt = format!(
+        "\n⚠️  Permanently delete the {} recycled entries ({:.1} GB) from the recycle bin? \
+         This cannot be undone — type 'empty' to proceed: ",
+        format_count(recycled.len()),
+        bytes as f64 / 1_073_741_824.0
+    );
+    if confirm(&prompt, "empty", assume_yes)?.is_none() {
+        outln!("Recycle bin left untouched.");
+        return Ok(());
+    }
+
+    match recycle_bin::purge_recycled(&recycled, started) {
+        Ok(purged) => outln!(
+            "🧹 Emptied {} item(s) ({:.2} GB) from the recycle bin",
+            purged.items,
+            purged.bytes as f64 / 1_073_741_824.0
+        ),
+        Err(e) => outln!("⚠️  Could not empty the recycle bin: {}", e),
+    }
+    Ok(())
+}
+
+/// Warns about Delete entries matching an item already in the recycle bin by
+/// name and size, such as ones an earlier run recycled before they reappeared.
+///
+/// Nothing is printed where the bin can't be listed.
+fn warn_recycled_duplicates(plan: &crate::models::CleanupPlan) {
+    let Ok(bin) = recycle_bin::contents() else {
+        return;
+    };
+    let candidates: Vec<(PathBuf, u64)> = plan
+        .entries
+        .iter()
+        .filter(|e| e.action == CleanupAction::Delete)
+        .map(|e| (plan.entry_path(e), e.size))
+        .collect();
+    let duplicates = recycle_bin::find_duplicates(&candidates, &bin);
+    if duplicates.is_empty() {
+        return;
+    }
+
+    let index = trash_index_path()
+        .and_then(|path| Ok(TrashIndex::load(&path)?))
+        .unwrap_or_default();
+    outln!(
+        "⚠️  {} entr(ies) match an item already in the recycle bin (same name and size); \
+         recycling them again keeps both copies:",
+        format_count(duplicates.len())
+    );
+    for duplicate in duplicates.iter().take(10) {
+        let origin = match index.find(&duplicate.item) {
+            Some(record) => format!("recycled by megamaid run {}", record.execution_id),
+            None => "recycled outside megamaid".to_string(),
+        };
+        outln!(
+            "  {} (bin: {}, {})",
+            duplicate.path.display(),
+            duplicate.item.original_path.display(),
+            origin
+        );
+    }
+    if duplicates.len() > 10 {
+        outln!("  ... and {} more", format_count(duplicates.len() - 10));
+    }
+    outln!();
+}
+
+/// Returns the trash index file in the user's data directory.
+fn trash_index_path() -> Result<PathBuf> {
+    TrashIndex::default_path().context("No user data directory for the trash index")
+}
+
+/// Adds this run's recycled items to the trash index, dropping the records
+/// of items no longer in the bin; failures only print a warning.
+fn update_trash_index(result: &crate::executor::ExecutionResult, execution_id: &str) {
+    let updated = trash_index_path().and_then(|path| {
+        let mut index = TrashIndex::load(&path)?;
+        index.record(execution_id, result);
+        if let Ok(bin) = recycle_bin::contents() {
+            index.retain_present(&bin);
+        }
+        index.save(&path)?;
+        Ok(())
+    });
+    if let Err(e) = updated {
+        outln!("⚠️  Could not update the trash index: {:#}", e);
+    }
+}
+
+/// Loads the trash index at `index_path`, reconciled with the recycle bin,
+/// and the bin's contents.
+fn reconciled_trash_index(index_path: &Path) -> Result<(TrashIndex, Vec<RecycledItem>)> {
+    let mut index = TrashIndex::load(index_path).context(format!(
+        "Failed to read trash index: {}",
+        index_path.display()
+    ))?;
+    let bin = recycle_bin::contents().context("Failed to list the recycle bin")?;
+    let before = index.items.len();
+    index.retain_present(&bin);
+    if index.items.len() != before {
+        index.save(index_path).context(format!(
+            "Failed to write trash index: {}",
+            index_path.display()
+        ))?;
+    }
+    Ok((index, bin))
+}
+
+/// Logs `account` on for `execute --run-as`, with the password from
+/// `executor.run_as_secret` or prompted for.
+fn logon_run_as(cfg: &MegamaidConfig, account: &str) -> Result<Arc<UserToken>> {
+    let account: Account = account.parse()?;
+    if !run_as::SUPPORTED {
+        anyhow::bail!(
+            "--run-as is only supported on Windows; run megamaid as {} instead (e.g. with sudo -u)",
+            account.user
+        );
+    }
+    let password = match &cfg.executor.run_as_secret {
+        Some(name) => {
+            get_secret(name)?.with_context(|| format!("Secret '{}' is not in the keyring", name))?
+        }
+        None if io::stdin().is_terminal() => {
+            out!("Password for {}: ", account);
+            io::stdout().flush()?;
+            run_as::read_password()?
+        }
+        None => anyhow::bail!(
+            "No password for {}: set executor.run_as_secret when stdin is not a terminal",
+            account
+        ),
+    };
+    let token = UserToken::logon(&account, &password)?;
+    outln!("👤 Deleting as {}", account);
+    outln!();
+    Ok(Arc::new(token))
+}
+
+/// Returns the config file commands editing or watching the configuration
+/// use: the one given with `--config`, or the first default config file,
+/// `megamaid.yaml` if there is none yet.
+fn config_file(config_path: Option<&Path>) -> PathBuf {
+    config_path
+        .map(Path::to_path_buf)
+        .or_else(default_config_path)
+        .unwrap_or_else(|| PathBuf::from("megamaid.yaml"))
+}
+
+/// Executes the custom rule commands.
+///
+/// Rules are added to and removed from [`config_file`], which is created if
+/// it doesn't exist.
+fn run_rules(
+    cfg: &MegamaidConfig,
+    config_path: Option<&Path>,
+    command: RulesCommands,
+) -> Result<()> {
+    let file = config_file(config_path);
+    let mut rules = cfg.detector.custom_rules.clone();
+
+    match command {
+        RulesCommands::List => {
+            if rules.is_empty() {
+                outln!("No custom rules in {}", file.display());
+                return Ok(());
+            }
+            outln!(
+                "📋 Custom rules in {}, in the order they are checked",
+                file.display()
+            );
+            outln!();
+            for rule in &rules {
+                outln!("{} ({}): {}", rule.name, rule.action, rule.description);
+                outln!("  {}", describe_criteria(rule));
+            }
+        }
+        RulesCommands::Add {
+            name,
+            description,
+            pattern,
+            extensions,
+            min_age_days,
+            min_size,
+            when,
+            action,
+            replace,
+        } => {
+            let rule = CustomRule {
+                name,
+                description,
+                pattern,
+                extensions: (!extensions.is_empty()).then_some(extensions),
+                min_age_days,
+                min_size,
+                when,
+                action,
+            };
+            let message = match rules.iter().position(|r| r.name == rule.name) {
+                Some(_) if !replace => anyhow::bail!(
+                    "Custom rule '{}' is already in {} (--replace overwrites it)",
+                    rule.name,
+                    file.display()
+                ),
+                Some(index) => {
+                    rules[index] = rule.clone();
+                    format!("Replaced custom rule '{}' in", rule.name)
+                }
+                None => {
+                    rules.push(rule.clone());
+                    format!("Added custom rule '{}' to", rule.name)
+                }
+            };
+            write_custom_rules(&file, &rules)?;
+            outln!("✅ {} {}", message, file.display());
+            outln!("  {}", describe_criteria(&rule));
+        }
+        RulesCommands::Test { name, path, top } => run_rules_test(cfg, &name, &path, top)?,
+        RulesCommands::Remove { name } => {
+            let count = rules.len();
+            rules.retain(|r| r.name != name);
+            if rules.len() == count {
+                outln!("Custom rule '{}' is not in {}", name, file.display());
+                return Ok(());
+            }
+            write_custom_rules(&file, &rules)?;
+            outln!("🗑️  Removed custom rule '{}' from {}", name, file.display());
+        }
+    }
+    Ok(())
+}
+
+/// Describes what a custom rule matches, e.g. "pattern **/*.log, at least
+/// 30 days old".
+fn describe_criteria(rule: &CustomRule) -> String {
+    let mut criteria = Vec::new();
+    if let Some(pattern) = &rule.pattern {
+        criteria.push(format!("pattern {}", pattern));
+    }
+    if let Some(extensions) = &rule.extensions {
+        criteria.push(format!("extension {}", extensions.join(" or ")));
+    }
+    if let Some(days) = rule.min_age_days {
+        criteria.push(format!("at least {} days old", days));
+    }
+    if let Some(size) = rule.min_size {
+        criteria.push(format!("at least {}", size));
+    }
+    if let Some(when) = &rule.when {
+        criteria.push(format!("when {}", when));
+    }
+    criteria.join(", ")
+}
+
+/// Executes the rules test command: scans `path` and shows what the custom
+/// rule flags there in a scan with the current configuration.
+fn run_rules_test(cfg: &MegamaidConfig, name: &str, path: &Path, top: usize) -> Result<()> {
+    let Some(rule) = cfg.detector.custom_rules.iter().find(|r| r.name == name) else {
+        anyhow::bail!(
+            "No custom rule named '{}' (megamaid rules list shows them)",
+            name
+        );
+    };
+    if !path.is_dir() {
+        anyhow::bail!("Not a directory: {}", path.display());
+    }
+
+    outln!("🧪 Testing custom rule '{}': {}", name, path.display());
+    outln!("  {}", describe_criteria(rule));
+    outln!();
+
+    let spinner = style::spinner("Scanning filesystem...");
+    let scanner =
+        FileScanner::new(cfg.scanner.clone().into()).with_exclusions(configured_artifacts(cfg));
+    let entries = scanner.scan(path).context("Failed to scan directory")?;
+    style::finish(
+        &spinner,
+        format!("✓ Scanned {} entries", format_count(entries.len())),
+    );
+    print_unreadable(&scanner.errors());
+
+    let engine = build_detection_engine(cfg, None, HashMap::new(), &[], &[path.to_path_buf()])?;
+    let context = ScanContext {
+        own_artifacts: configured_artifacts(cfg),
+    };
+    let report = engine.analyze_with_stats(&entries, &context);
+
+    let mut flagged: Vec<_> = report
+        .detections
+        .iter()
+        .filter(|d| d.rule_name == name)
+        .map(|d| &d.entry)
+        .collect();
+    let kept = report
+        .vetoed
+        .iter()
+        .filter(|v| v.detection.rule_name == name)
+        .count();
+    let files = flagged
+        .iter()
+        .filter(|e| e.entry_type == crate::models::EntryType::File)
+        .count();
+    let bytes: u64 = flagged.iter().map(|e| e.size).sum();
+    outln!(
+        "{} flags {} entries ({} files, {} directories), {} in total (action: {})",
+        name,
+        format_count(flagged.len()),
+        format_count(files),
+        format_count(flagged.len() - files),
+        format_bytes(bytes),
+        rule.action
+    );
+    if kept > 0 {
+        outln!(
+            "ℹ️  {} more matches are kept by keep rules or archive directories",
+            format_count(kept)
+        );
+    }
+    if flagged.is_empty() {
+        return Ok(());
+    }
+
+    outln!();
+    flagged.sort_by_key(|e| std::cmp::Reverse(e.size));
+    for entry in flagged.iter().take(top) {
+        let relative = entry.path.strip_prefix(path).unwrap_or(&entry.path);
+        outln!("  {:>10}  {}", format_bytes(entry.size), relative.display());
+    }
+    if flagged.len() > top {
+        outln!(
+            "  ... and {} more (--top lists more)",
+            format_count(flagged.len() - top)
+        );
+    }
+    Ok(())
+}
+
+/// Executes the secrets commands.
+fn run_secrets(command: SecretsCommands) -> Result<()> {
+    match command {
+        SecretsCommands::Set { name } => {
+            if io::stdin().is_terminal() {
+                out!("Value for secret '{}': ", name);
+                io::stdout().flush()?;
+            }
+            let mut value = String::new();
+            io::stdin().read_line(&mut value)?;
+            let value = value.trim_end_matches(['\r', '\n']);
+            if value.is_empty() {
+                anyhow::bail!("No value given for secret '{}'", name);
+            }
+            set_secret(&name, value)?;
+            outln!("✅ Stored secret '{}' in the keyring", name);
+        }
+        SecretsCommands::Get { name } => match get_secret(&name)? {
+            Some(value) => outln!("{}", value),
+            None => anyhow::bail!("Secret '{}' is not in the keyring", name),
+        },
+        SecretsCommands::Remove { name } => {
+            if remove_secret(&name)? {
+                outln!("🗑️  Removed secret '{}'", name);
+            } else {
+                outln!("Secret '{}' is not in the keyring", name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Executes the trash list command.
+fn run_trash_list(index_path: &Path, time: TimeDisplay) -> Result<()> {
+    let (index, bin) = reconciled_trash_index(index_path)?;
+    let present = index.present(&bin);
+
+    outln!("♻️  Items megamaid recycled");
+    outln!();
+    if present.is_empty() {
+        outln!("None of the items megamaid recycled are in the recycle bin.");
+        return Ok(());
+    }
+
+    for (item, record) in &present {
+        let recycled = chrono::DateTime::from_timestamp(item.time_deleted, 0)
+            .map(|at| time.format(at))
+            .unwrap_or_default();
+        let size = item.size.or(record.size).unwrap_or(0);
+        outln!(
+            "  {:>10.2} GB  {}  {}  ({})",
+            size as f64 / 1_073_741_824.0,
+            recycled,
+            item.original_path.display(),
+            record.execution_id
+        );
+    }
+    let bytes: u64 = present
+        .iter()
+        .map(|(item, record)| item.size.or(record.size).unwrap_or(0))
+        .sum();
+    outln!();
+    outln!(
+        "{} item(s), {:.2} GB; {} other item(s) in the recycle bin",
+        format_count(present.len()),
+        bytes as f64 / 1_073_741_824.0,
+        format_count(bin.len() - present.len())
+    );
+    Ok(())
+}
+
+/// Executes the trash purge command: permanently deletes the items megamaid
+/// recycled, optionally only those at least `older_than` days old.
+fn run_trash_purge(
+    index_path: &Path,
+    older_than: Option<u64>,
+    dry_run: bool,
+    assume_yes: bool,
+) -> Result<()> {
+    let (mut index, bin) = reconciled_trash_index(index_path)?;
+    let cutoff = older_than.map(|days| {
+        let now = chrono::Utc::now().timestamp();
+        now - (days as i64) * 86_400
+    });
+    let selected: Vec<RecycledItem> = index
+        .present(&bin)
+        .into_iter()
+        .filter(|(item, _)| cutoff.is_none_or(|cutoff| item.time_deleted <= cutoff))
+        .map(|(item, _)| item.clone())
+        .collect();
+    let bytes: u64 = selected.iter().filter_map(|item| item.size).sum();
+
+    if selected.is_empty() {
+        outln!("No items megamaid recycled to purge.");
+        return Ok(());
+    }
+    if dry_run {
+        outln!(
+            "🔄 DRY RUN - {} item(s) ({:.2} GB) would be purged from the recycle bin",
+            format_count(selected.len()),
+            bytes as f64 / 1_073_741_824.0
+        );
+        return Ok(());
+    }
+
+    let prompt = format!(
+        "\n⚠️  Permanently delete the {} item(s) ({:.1} GB) megamaid recycled from the recycle \
+         bin? This cannot be undone — type 'empty' to proceed: ",
+        format_count(selected.len()),
+        bytes as f64 / 1_073_741_824.0
+    );
+    if confirm(&prompt, "empty", assume_yes)?.is_none() {
+        outln!("Recycle bin left untouched.");
+        return Ok(());
+    }
+
+    let purged = recycle_bin::purge_items(&selected).context("Failed to empty the recycle bin")?;
+    index
+        .items
+        .retain(|record| !selected.iter().any(|item| record.matches(item)));
+    index.save(index_path).context(format!(
+        "Failed to write trash index: {}",
+        index_path.display()
+    ))?;
+    outln!(
+        "🧹 Emptied {} item(s) ({:.2} GB) from the recycle bin",
+        purged.items,
+        purged.bytes as f64 / 1_073_741_824.0
+    );
+    Ok(())
+}
+
+/// Prints what the recycle bin holds per volume, or why it can't be listed.
+fn print_recycle_bin_report() {
+    let report = match recycle_bin::occupancy() {
+        Ok(report) => report,
+        Err(e) => {
+            outln!(
+                "ℹ️  Recycled entries use disk space until the recycle bin is emptied ({})",
+                e
+            );
+            return;
+        }
+    };
+    if report.volumes.is_empty() {
+        outln!("♻️  Recycle bin is empty");
+        return;
+    }
+
+    outln!("♻️  Recycle bin occupancy (space is freed only once it is emptied):");
+    for volume in &report.volumes {
+        let unknown = if volume.unsized_items > 0 {
+            format!(" (+{} folder(s) of unknown size)", volume.unsized_items)
+        } else {
+            String::new()
+        };
+        outln!(
+            "  {:<20} {:>8} item(s) {:>10.2} GB{}",
+            volume.volume.display(),
+            volume.items,
+            volume.bytes as f64 / 1_073_741_824.0,
+            unknown
+        );
+    }
+}
+
+/// Snapshots the plan's volume if requested, or if the run deletes at least
+/// `executor.snapshot_threshold`, and records it in the transaction log.
+///
+/// A snapshot that was asked for must succeed; one triggered by the
+/// threshold is skipped with a warning where snapshots aren't supported.
+fn take_snapshot(
+    cfg: &MegamaidConfig,
+    plan: &crate::models::CleanupPlan,
+    requested: bool,
+    delete_bytes: u64,
+    logger: &mut TransactionLogger,
+) -> Result<()> {
+    let over_threshold = cfg
+        .executor
+        .snapshot_threshold
+        .is_some_and(|threshold| delete_bytes >= threshold.as_bytes());
+    if !requested && !over_threshold {
+        return Ok(());
+    }
+
+    let volume = snapshot_volume(plan.all_roots(), recycle_bin::volume_of)?;
+    outln!("📸 Creating volume snapshot of {}...", volume.display());
+    let created = match &cfg.executor.snapshot_command {
+        Some(command) => snapshot::create_with_command(command, &volume),
+        None => snapshot::create(&volume),
+    };
+    match created {
+        Ok(snapshot) => {
+            outln!("✓ Snapshot {} created", snapshot.id);
+            outln!();
+            logger.record_snapshot(snapshot);
+            // Record the snapshot now, in case the run is interrupted
+            logger.write()?;
+        }
+        Err(e @ SnapshotError::Unsupported) if !requested => {
+            outln!("⚠️  Skipping volume snapshot: {}", e);
+            outln!();
+        }
+        Err(e) => anyhow::bail!(
+            "Failed to create volume snapshot: {}. Execute without --snapshot, or raise \
+             executor.snapshot_threshold, to delete without one.",
+            e
+        ),
+    }
+    Ok(())
+}
+
+/// Returns the volume holding every root, as found by `volume_of`.
+///
+/// A snapshot covers one volume, so a plan spanning several couldn't be
+/// rolled back as a whole and is refused.
+fn snapshot_volume(roots: &[PathBuf], volume_of: impl Fn(&Path) -> PathBuf) -> Result<PathBuf> {
+    let mut volumes: Vec<PathBuf> = roots.iter().map(|root| volume_of(root)).collect();
+    volumes.sort();
+    volumes.dedup();
+    match volumes.as_slice() {
+        [volume] => Ok(volume.clone()),
+        _ => anyhow::bail!(
+            "The plan spans several volumes ({}), but a snapshot covers only one. Plan and \
+             execute each volume's roots separately, or execute without --snapshot, and raise \
+             executor.snapshot_threshold, to delete without one.",
+            volumes
+                .iter()
+                .map(|v| v.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Returns true if `a` and `b` name the same file, comparing the resolved
+/// paths when both exist.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Fails if the plan is older than `max_age_days`.
+fn check_plan_age(
+    plan: &crate::models::CleanupPlan,
+    max_age_days: Option<u64>,
+    time: TimeDisplay,
+) -> Result<()> {
+    let Some(max_age_days) = max_age_days else {
+        return Ok(());
+    };
+
+    let age = chrono::Utc::now() - plan.created_at;
+    if age > chrono::Duration::days(max_age_days as i64) {
+        anyhow::bail!(
+            "Plan was created {} days ago ({}), which exceeds executor.max_plan_age_days ({}). \
+             Re-scan to get a fresh plan, or pass --allow-stale-plan to execute it anyway.",
+            age.num_days(),
+            time.format(plan.created_at),
+            max_age_days
+        );
+    }
+
+    Ok(())
+}
+
+/// Asks for approval before a batch execution and returns how it was given.
+///
+/// Fails if the user declines, or if no terminal is available to ask on and
+/// `--yes` was not passed.
+fn confirm_batch_execution(
+    entries: usize,
+    bytes: u64,
+    verb: &str,
+    assume_yes: bool,
+) -> Result<ConfirmationMethod> {
+    confirm(
+        &confirmation_prompt(entries, bytes, verb),
+        "delete",
+        assume_yes,
+    )?
+    .context("Execution cancelled - nothing was changed")
+}
+
+/// Shows the last-chance notice before an unattended run.
+///
+/// Fails if the user cancels the run, or if nobody is logged on and the
+/// configured policy is to abort.
+fn give_last_chance(config: &LastChanceConfig, entries: usize, bytes: u64) -> Result<()> {
+    outln!(
+        "🔔 Showing last-chance notice ({} minute delay)...",
+        config.delay_minutes
+    );
+    let outcome =
+        config
+            .to_last_chance()
+            .ask(&DesktopNotifier, entries, bytes, &mut std::thread::sleep);
+
+    match outcome {
+        LastChanceOutcome::Proceed { snoozes: 0 } => outln!("✓ Proceeding\n"),
+        LastChanceOutcome::Proceed { snoozes } => {
+            outln!("✓ Proceeding after {} postponement(s)\n", snoozes)
+        }
+        LastChanceOutcome::Cancelled => {
+            anyhow::bail!("Execution cancelled from the last-chance notice - nothing was changed")
+        }
+        LastChanceOutcome::NoUser(NoUserPolicy::Proceed) => {
+            outln!("⚠️  No user to notify - proceeding (executor.last_chance.no_user: proceed)\n")
+        }
+        LastChanceOutcome::NoUser(NoUserPolicy::Abort) => anyhow::bail!(
+            "No user to notify - execution aborted (executor.last_chance.no_user: abort)"
+        ),
+    }
+    Ok(())
+}
+
+/// Asks the user to type `word` after `prompt`.
+///
+/// Returns `None` if they typed anything else. Fails if approval is needed
+/// but stdin is not a terminal.
+fn confirm(prompt: &str, word: &str, assume_yes: bool) -> Result<Option<ConfirmationMethod>> {
+    if assume_yes {
+        return Ok(Some(ConfirmationMethod::Flag));
+    }
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("Confirmation required but stdin is not a terminal - re-run with --yes");
+    }
+
+    out!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input)?;
+    outln!();
+
+    Ok((input.trim() == word).then_some(ConfirmationMethod::Prompt))
+}
+
+/// Formats the batch confirmation prompt.
+fn confirmation_prompt(entries: usize, bytes: u64, verb: &str) -> String {
+    format!(
+        "⚠️  About to {} {} entries totalling {:.1} GB — type 'delete' to proceed: ",
+        verb,
+        format_count(entries),
+        bytes as f64 / 1_073_741_824.0
+    )
+}
+
+/// Formats a count with thousands separators (e.g. 1,204).
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// Executes the snapshot list command.
+fn run_snapshot_list(log_paths: &[PathBuf], time: TimeDisplay) -> Result<()> {
+    outln!("📸 Volume Snapshots");
+    outln!("===================");
+    outln!();
+
+    let mut found = 0;
+    for path in log_paths {
+        let log = TransactionLogger::read(path).context(format!(
+            "Failed to read transaction log: {}",
+            path.display()
+        ))?;
+        let Some(snapshot) = &log.snapshot else {
+            continue;
+        };
+        found += 1;
+
+        let state = match snapshot::exists(snapshot) {
+            Ok(true) => "available".to_string(),
+            Ok(false) => "deleted".to_string(),
+            Err(e) => format!("unknown ({})", e),
+        };
+        outln!("{} ({})", path.display(), log.execution_id);
+        outln!("  Snapshot: {}", snapshot.id);
+        outln!("  Volume:   {}", snapshot.volume.display());
+        outln!("  Created:  {}", time.format(snapshot.created_at));
+        outln!("  State:    {}", state);
+        for instruction in snapshot.instructions() {
+            outln!("  {}", instruction);
+        }
+    }
+
+    if found == 0 {
+        outln!("No snapshots recorded in the given logs.");
+    }
+    Ok(())
+}
+
+/// Executes the snapshot rollback command.
+fn run_snapshot_rollback(log_path: &Path) -> Result<()> {
+    let log = TransactionLogger::read(log_path).context(format!(
+        "Failed to read transaction log: {}",
+        log_path.display()
+    ))?;
+
+    outln!(
+        "⏪ Restoring entries deleted by transaction {}",
+        log.execution_id
+    );
+    outln!();
+
+    let summary = snapshot::rollback(&log)?;
+
+    outln!("  Restored: {}", format_count(summary.restored));
+    outln!(
+        "  Size:     {:.2} GB",
+        summary.bytes as f64 / 1_073_741_824.0
+    );
+    if !summary.existing.is_empty() {
+        outln!();
+        outln!(
+            "⚠️  Left {} path(s) alone because they exist again:",
+            summary.existing.len()
+        );
+        for path in &summary.existing {
+            outln!("  {}", path.display());
+        }
+    }
+    if !summary.failed.is_empty() {
+        outln!();
+        outln!("❌ Failed to restore {} path(s):", summary.failed.len());
+        for (path, error) in &summary.failed {
+            outln!("  {}: {}", path.display(), error);
+        }
+        anyhow::bail!("Rollback incomplete");
+    }
+    Ok(())
+}
+
+/// Executes the backup prune command.
+fn run_backup_prune(log_paths: &[PathBuf], force: bool, dry_run: bool) -> Result<()> {
+    let logs = log_paths
+        .iter()
+        .map(|path| {
+            TransactionLogger::read(path).context(format!(
+                "Failed to read transaction log: {}",
+                path.display()
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    outln!("🗄️  Verifying backups");
+    outln!();
+
+    let report = backup::verify(&logs);
+    let present: Vec<_> = report.present().collect();
+    let bytes: u64 = present.iter().map(|check| check.bytes).sum();
+    outln!("  Backups:      {}", format_count(present.len()));
+    outln!("  Size:         {:.2} GB", bytes as f64 / 1_073_741_824.0);
+    outln!(
+        "  Already gone: {}",
+        format_count(report.checks.len() - present.len())
+    );
+    if report.untracked > 0 {
+        outln!(
+            "  Untracked:    {} (logged without a backup path; not pruned)",
+            format_count(report.untracked)
+        );
+    }
+
+    let reappeared: Vec<_> = report.reappeared().collect();
+    if !reappeared.is_empty() {
+        outln!();
+        outln!(
+            "⚠️  {} backed-up path(s) exist again at their original location:",
+            reappeared.len()
+        );
+        for check in &reappeared {
+            outln!("  {} ({})", check.original.display(), check.state);
+            outln!("    backup: {}", check.backup.display());
+        }
+    }
+
+    if dry_run {
+        outln!();
+        outln!(
+            "🔄 DRY RUN - {} backup(s) would be deleted",
+            format_count(present.len())
+        );
+        return Ok(());
+    }
+
+    let summary = backup::prune(&report, force)?;
+    outln!();
+    outln!("✅ Deleted {} backup(s)", format_count(summary.removed));
+    outln!("  Freed: {:.2} GB", summary.bytes as f64 / 1_073_741_824.0);
+    if !summary.failed.is_empty() {
+        outln!();
+        outln!("❌ Failed to delete {} backup(s):", summary.failed.len());
+        for (path, error) in &summary.failed {
+            outln!("  {}: {}", path.display(), error);
+        }
+        anyhow::bail!("Backup prune incomplete");
+    }
+    Ok(())
+}
+
+/// Refuses to start an execution that doesn't meet `constraints`, so the
+/// scheduler that launched it tries again later.
+///
+/// Watching the disks takes `disk_idle`; Ctrl+C ends it.
+fn check_run_constraints(constraints: &RunConstraints) -> Result<()> {
+    if constraints.is_empty() {
+        return Ok(());
+    }
+    if let Some(window) = constraints.disk_idle {
+        outln!(
+            "⏳ Waiting {} minute(s) to check that the disks stay quiet...",
+            window.as_secs() / 60
+        );
+    }
+
+    let stop = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let ids = signal_hook::consts::TERM_SIGNALS
+        .iter()
+        .map(|signal| signal_hook::flag::register(*signal, Arc::clone(&stop)))
+        .collect::<io::Result<Vec<_>>>()?;
+    let checked = constraints.check(
+        &mut SystemMonitor::default(),
+        chrono::Local::now().time(),
+        &stop,
+    );
+    for id in ids {
+        signal_hook::low_level::unregister(id);
+    }
+
+    if let Err(unmet) = checked {
+        anyhow::bail!("Not starting: {} (see executor.constraints)", unmet);
+    }
+    outln!("✓ Run constraints met");
+    outln!();
+    Ok(())
+}
+
+/// Executes the log summarize command.
+fn run_log_summarize(log_paths: &[PathBuf]) -> Result<()> {
+    let logs = log_paths
+        .iter()
+        .map(|path| {
+            TransactionLogger::read(path).context(format!(
+                "Failed to read transaction log: {}",
+                path.display()
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let summary = LogSummary::from_logs(&logs);
+
+    outln!("📊 Transaction Log Summary");
+    outln!("==========================");
+    outln!();
+    outln!("Logs: {}", summary.logs);
+    for plan_file in &summary.plan_files {
+        outln!("  Plan: {}", plan_file.display());
+    }
+    if let Some(count) = summary.shard_count {
+        outln!(
+            "Shards: {} of {}",
+            count - summary.missing_shards.len(),
+            count
+        );
+    }
+    outln!(
+        "Runs: {} completed, {} failed, {} incomplete",
+        summary.completed,
+        summary.failed_runs,
+        summary.incomplete
+    );
+    outln!();
+    outln!("Operations:");
+    outln!("  Total: {}", summary.total_operations);
+    outln!("  Successful: {}", summary.successful);
+    outln!("  Failed: {}", summary.failed);
+    outln!("  Skipped: {}", summary.skipped);
+    outln!(
+        "  Space freed: {:.2} GB",
+        summary.space_freed as f64 / 1_073_741_824.0
+    );
+    print_space_by_action(&summary.space_by_action);
+    outln!("  Longest run: {:.2}s", summary.max_duration_seconds);
+
+    if summary.plan_files.len() > 1 {
+        outln!();
+        outln!("⚠️  Logs refer to different plan files");
+    }
+
+    if !summary.missing_shards.is_empty() {
+        let missing: Vec<_> = summary
+            .missing_shards
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        outln!();
+        outln!("⚠️  Missing shards: {}", missing.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Options for diagnostics collect command.
+struct DiagnosticsOptions {
+    output: PathBuf,
+    /// Logs to include; empty picks the newest in the current directory
+    logs: Vec<PathBuf>,
+    max_logs: usize,
+    scan_paths: Vec<PathBuf>,
+}
+
+/// Crash reports included in a diagnostic bundle.
+const BUNDLED_CRASH_REPORTS: usize = 5;
+
+fn run_diagnostics_collect(
+    cfg: &MegamaidConfig,
+    config_path: Option<&Path>,
+    options: DiagnosticsOptions,
+) -> Result<()> {
+    let logs = if options.logs.is_empty() {
+        DiagnosticBundle::recent_logs(Path::new("."), options.max_logs)
+    } else {
+        options.logs
+    };
+    let bundle = BundleOptions {
+        config_source: config_path.map_or_else(
+            || "default locations or built-in defaults".to_string(),
+            |path| path.display().to_string(),
+        ),
+        logs,
+        scan_paths: options.scan_paths,
+        crash_dir: cfg.diagnostics.crash_dir(),
+        max_crash_reports: BUNDLED_CRASH_REPORTS,
+    };
+
+    outln!("🩺 Collecting diagnostics...");
+    outln!();
+    let summary = DiagnosticBundle::write(cfg, &bundle, &options.output).context(format!(
+        "Failed to write diagnostic bundle: {}",
+        options.output.display()
+    ))?;
+
+    for file in &summary.files {
+        outln!("  {}", file);
+    }
+    if !bundle.scan_paths.is_empty() {
+        outln!();
+        outln!("Scan errors: {}", summary.scan_errors);
+    }
+    for skipped in &summary.skipped {
+        outln!("⚠️  Skipped {}", skipped);
+    }
+    outln!();
+    outln!(
+        "✓ Diagnostics written to {} ({} files)",
+        options.output.display(),
+        summary.files.len()
+    );
+    outln!("   Logs and scan errors list file paths; review the bundle before sharing it");
+
+    Ok(())
+}
+
+/// Prints the non-zero byte totals of each action, below "Space freed".
+fn print_space_by_action(space: &crate::executor::SpaceByAction) {
+    let lines = [
+        ("Deleted", space.deleted),
+        ("Recycled", space.recycled),
+        ("Moved to backup", space.backed_up),
+        ("Skipped", space.skipped),
+    ];
+    for (label, bytes) in lines.into_iter().filter(|(_, bytes)| *bytes > 0) {
+        outln!("    {}: {:.2} GB", label, bytes as f64 / 1_073_741_824.0);
+    }
+}
+
+/// Prints failed operations grouped by cause, with what to do about each and
+/// how to retry them.
+fn print_failures(result: &crate::executor::ExecutionResult, plan_path: &Path, log_path: &Path) {
+    const SHOWN: usize = 3;
+
+    let groups = result.failures_by_cause();
+    if groups.is_empty() {
+        return;
+    }
+    outln!();
+    outln!("⚠️  {} failures:", result.summary.failed);
+    let mut retryable = false;
+    for ops in groups.values() {
+        let Some(error) = ops[0].error.as_ref() else {
+            continue;
+        };
+        retryable |= error.is_retryable();
+        outln!(
+            "  {} {} — {}",
+            ops.len(),
+            error.label().to_lowercase(),
+            error.recommendation()
+        );
+        for op in ops.iter().take(SHOWN) {
+            outln!("    {}", op.path.display());
+        }
+        if ops.len() > SHOWN {
+            outln!("    ... and {} more", ops.len() - SHOWN);
+        }
+    }
+    if retryable {
+        outln!();
+        outln!("Retry just the failed entries with:");
+        outln!(
+            "  megamaid execute {} --only-failed {}",
+            plan_path.display(),
+            log_path.display()
+        );
+    }
+}
 
 fn print_execution_summary(summary: &crate::executor::ExecutionSummary, dry_run: bool) {
-    println!("Summary:");
-    println!("  Total operations: {}", summary.total_operations);
-    println!("  Successful: {}", summary.successful);
-    println!("  Failed: {}", summary.failed);
-    println!("  Skipped: {}", summary.skipped);
-    println!(
+    outln!("Summary:");
+    outln!("  Total operations: {}", summary.total_operations);
+    outln!("  Successful: {}", summary.successful);
+    outln!("  Failed: {}", summary.failed);
+    outln!("  Skipped: {}", summary.skipped);
+    outln!(
         "  Space freed: {:.2} GB",
         summary.space_freed as f64 / 1_073_741_824.0
     );
-    println!("  Duration: {:.2}s", summary.duration.as_secs_f64());
+    print_space_by_action(&summary.space_by_action);
+    outln!("  Duration: {:.2}s", summary.duration.as_secs_f64());
+
+    if dry_run {
+        outln!();
+        outln!("This was a dry run. No files were actually deleted.");
+    }
+}
+
+/// Prints a summary of the cleanup plan.
+fn print_plan_summary(plan: &crate::models::CleanupPlan, time: TimeDisplay) {
+    outln!("Base Path: {}", plan.base_path.display());
+    for root in plan.roots.iter().skip(1) {
+        outln!("           {}", root.display());
+    }
+    outln!("Version:   {}", plan.version);
+    outln!("Created:   {}", time.format(plan.created_at));
+    outln!();
+    outln!("Entries:   {}", plan.entries.len());
+    outln!("  • Delete: {}", plan.delete_count());
+    outln!("  • Review: {}", plan.review_count());
+    outln!("  • Keep:   {}", plan.keep_count());
+    outln!();
+    outln!("Total Size: {} MB", plan.total_size() / 1_048_576);
+
+    let streams = plan.stream_totals();
+    if streams.count > 0 {
+        outln!(
+            "Alternate Data Streams: {} ({} MB, not included above)",
+            streams.count,
+            streams.size / 1_048_576
+        );
+    }
+
+    if !plan.entries.is_empty() {
+        outln!();
+        print_aggregates("Rule", &plan.aggregate_by_rule());
+        outln!();
+        print_aggregates("Extension", &plan.aggregate_by_extension());
+    }
+
+    if !plan.detection_stats.is_empty() {
+        outln!();
+        print_detection_stats(&plan.detection_stats);
+    }
+}
+
+/// Number of groups shown by `print_aggregates`.
+const MAX_AGGREGATE_ROWS: usize = 10;
+
+/// Prints the largest groups of a plan aggregate, by total size.
+fn print_aggregates(
+    label: &str,
+    groups: &std::collections::BTreeMap<String, crate::models::AggregateStats>,
+) {
+    let mut rows: Vec<_> = groups.iter().collect();
+    rows.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(b.0)));
+
+    outln!("By {}:", label);
+    outln!("  {:<20} {:>10} {:>12}", label, "Entries", "Size (MB)");
+    for (name, stats) in rows.iter().take(MAX_AGGREGATE_ROWS) {
+        let name = if name.is_empty() {
+            "(none)"
+        } else {
+            name.as_str()
+        };
+        outln!(
+            "  {:<20} {:>10} {:>12}",
+            name,
+            stats.count,
+            stats.size / 1_048_576
+        );
+    }
+    if rows.len() > MAX_AGGREGATE_ROWS {
+        outln!("  ... and {} more", rows.len() - MAX_AGGREGATE_ROWS);
+    }
+}
+
+/// Prints per-rule detection statistics recorded in a plan.
+fn print_detection_stats(stats: &[crate::models::RuleStats]) {
+    outln!("Detection Statistics:");
+    outln!(
+        "  {:<20} {:>12} {:>10} {:>12} {:>13} {:>10}",
+        "Rule",
+        "Evaluated",
+        "Flagged",
+        "Size (MB)",
+        "Largest (MB)",
+        "Time (ms)"
+    );
+    for stat in stats {
+        outln!(
+            "  {:<20} {:>12} {:>10} {:>12} {:>13} {:>10.1}",
+            stat.rule_name,
+            stat.entries_evaluated,
+            stat.entries_flagged,
+            stat.bytes_flagged / 1_048_576,
+            stat.largest_entry_bytes / 1_048_576,
+            stat.evaluation_time_ms
+        );
+    }
+}
+
+/// Prints what each rule flagged during a scan, largest total first.
+fn print_rule_totals(stats: &[crate::models::RuleStats]) {
+    let mut flagged: Vec<_> = stats.iter().filter(|s| s.entries_flagged > 0).collect();
+    flagged.sort_by(|a, b| {
+        b.bytes_flagged
+            .cmp(&a.bytes_flagged)
+            .then_with(|| a.rule_name.cmp(&b.rule_name))
+    });
+
+    outln!("Flagged by rule:");
+    for stat in flagged {
+        outln!("  {}", rule_total_line(stat));
+    }
+}
+
+/// Formats one rule's totals, e.g.
+/// `build_artifact: 44.0 GB in 12 entries (largest: app/target, 20.1 GB)`.
+fn rule_total_line(stat: &crate::models::RuleStats) -> String {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let mut line = format!(
+        "{}: {:.1} GB in {} entries",
+        stat.rule_name,
+        gb(stat.bytes_flagged),
+        format_count(stat.entries_flagged as usize)
+    );
+    if let Some(largest) = &stat.largest_entry {
+        line.push_str(&format!(
+            " (largest: {}, {:.1} GB)",
+            largest,
+            gb(stat.largest_entry_bytes)
+        ));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PatternRuleConfig;
+    use crate::detector::PatternSyntax;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn scan_options(path: &Path, output: &Path) -> ScanOptions {
+        ScanOptions {
+            paths: vec![path.to_path_buf()],
+            output: output.to_path_buf(),
+            max_depth: None,
+            skip_hidden: None,
+            large_file_threshold: None,
+            include_vetoed: false,
+            summary: false,
+            scan_cache: None,
+            incremental: false,
+            full_walk: false,
+            include_streams: None,
+            remote: None,
+            exclude: Vec::new(),
+            respect_ignore_files: None,
+            same_filesystem: None,
+            largest_first: None,
+            export_entries: None,
+            raw_output: None,
+            summary_file: None,
+            force: false,
+            tune: false,
+        }
+    }
+
+    fn verify_options(plan_path: &Path) -> VerifyOptions {
+        VerifyOptions {
+            plan_path: plan_path.to_path_buf(),
+            output: None,
+            fail_fast: false,
+            skip_mtime: false,
+            watch_interval: None,
+            watch_timeout: None,
+        }
+    }
+
+    #[test]
+    fn test_run_scan_with_temp_dir() {
+        let temp = TempDir::new().unwrap();
+
+        // Create some test files
+        fs::write(temp.path().join("test.txt"), "hello").unwrap();
+        fs::create_dir_all(temp.path().join("target")).unwrap();
+
+        let output_path = temp.path().join("plan.yaml");
+        let config = MegamaidConfig::default();
+
+        let result = run_scan(&config, scan_options(temp.path(), &output_path));
+
+        assert!(result.is_ok());
+        assert!(output_path.exists());
+    }
+
+    #[test]
+    fn test_run_scan_refuses_plan_deleting_most_of_the_tree() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("target/out.bin"), vec![0u8; 4096]).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let output_path = temp.path().join("plan.yaml");
+        let config = MegamaidConfig::default();
+
+        let err = run_scan(&config, scan_options(&project, &output_path)).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+        assert!(!output_path.exists());
+
+        let options = ScanOptions {
+            force: true,
+            ..scan_options(&project, &output_path)
+        };
+        run_scan(&config, options).unwrap();
+        assert_eq!(PlanReader::read(&output_path).unwrap().delete_count(), 1);
+    }
+
+    #[test]
+    fn test_tuning_reruns_detection_with_new_thresholds() {
+        let entries: Vec<_> = [("/data/a.bin", 2 << 20), ("/data/b.bin", 8 << 20)]
+            .into_iter()
+            .map(|(path, size)| {
+                crate::models::FileEntry::new(
+                    PathBuf::from(path),
+                    size,
+                    SystemTime::now(),
+                    crate::models::EntryType::File,
+                )
+            })
+            .collect();
+        let config = MegamaidConfig::default();
+        let context = ScanContext::default();
+        let tuning = Tuning {
+            cfg: &config,
+            previous_sizes: HashMap::new(),
+            duplicates: &[],
+            roots: &[],
+            entries: &entries,
+            context: &context,
+        };
+        let threshold = ByteSize::from_mb(1);
+        let engine =
+            build_detection_engine(&config, Some(threshold), HashMap::new(), &[], &[]).unwrap();
+        let report = engine.analyze_with_stats(&entries, &context);
+        assert_eq!(report.detections.len(), 2);
+
+        // Invalid values are asked again; a bare number is MB
+        let mut input = io::Cursor::new("lots\n4\nkeep\n");
+        let report = tuning.run(report, threshold, &mut input).unwrap();
+        assert_eq!(report.detections.len(), 1);
+        assert!(report.detections[0].entry.path.ends_with("b.bin"));
+
+        // End of input keeps the last report
+        let report = tuning
+            .run(report, threshold, &mut io::Cursor::new("16MB\n"))
+            .unwrap();
+        assert!(report.detections.is_empty());
+    }
+
+    #[test]
+    fn test_archive_directories_veto_detections() {
+        let entries: Vec<_> = ["/data/Archive/2019.iso", "/data/new.iso"]
+            .into_iter()
+            .map(|path| {
+                crate::models::FileEntry::new(
+                    PathBuf::from(path),
+                    2 << 20,
+                    SystemTime::now(),
+                    crate::models::EntryType::File,
+                )
+            })
+            .collect();
+        let threshold = Some(ByteSize::from_mb(1));
+        let context = ScanContext::default();
+
+        let mut config = MegamaidConfig::default();
+        let engine = build_detection_engine(&config, threshold, HashMap::new(), &[], &[]).unwrap();
+        let report = engine.analyze_with_stats(&entries, &context);
+        assert_eq!(report.detections.len(), 1);
+        assert_eq!(report.vetoed.len(), 1);
+        assert_eq!(report.vetoed[0].keep_rule, "archive");
+
+        config.detector.archive.enabled = false;
+        let engine = build_detection_engine(&config, threshold, HashMap::new(), &[], &[]).unwrap();
+        let report = engine.analyze_with_stats(&entries, &context);
+        assert_eq!(report.detections.len(), 2);
+    }
+
+    #[test]
+    fn test_run_scan_records_detection_stats() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("target")).unwrap();
+        fs::write(temp.path().join("target/out.bin"), "data").unwrap();
+        fs::write(temp.path().join("Cargo.toml"), "[package]").unwrap();
+
+        let output_path = temp.path().join("plan.yaml");
+        let config = MegamaidConfig::default();
+
+        run_scan(&config, scan_options(temp.path(), &output_path)).unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        let plan: crate::models::CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        let rules: Vec<_> = plan
+            .detection_stats
+            .iter()
+            .map(|s| s.rule_name.as_str())
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                "delete_marker",
+                "build_artifact",
+                "dev_cache",
+                "temp_files",
+                "browser_cache",
+                "thumbnail_cache",
+                "crash_dump",
+                "orphaned_env",
+                "large_file"
+            ]
+        );
+        assert!(plan.detection_stats[1].entries_flagged >= 1);
+    }
+
+    #[test]
+    fn test_run_scan_deletes_marked_directories() {
+        let temp = TempDir::new().unwrap();
+        for dir in ["renders", "kept"] {
+            fs::create_dir(temp.path().join(dir)).unwrap();
+            fs::write(temp.path().join(dir).join("frame.png"), "data").unwrap();
+            fs::write(temp.path().join(dir).join(".megamaid-delete"), "").unwrap();
+        }
+        fs::write(temp.path().join("kept/.megamaid-keep"), "").unwrap();
+
+        let output_path = temp.path().join("plan.yaml");
+        run_scan(
+            &MegamaidConfig::default(),
+            scan_options(temp.path(), &output_path),
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        let plan: crate::models::CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "renders");
+        assert_eq!(plan.entries[0].rule_name, "delete_marker");
+        assert_eq!(plan.entries[0].action, CleanupAction::Delete);
+        assert!(plan.entries[0].reason.contains(".megamaid-delete"));
+    }
+
+    #[test]
+    fn test_run_scan_flags_duplicate_copies() {
+        let temp = TempDir::new().unwrap();
+        for name in ["old.iso", "new.iso"] {
+            fs::write(temp.path().join(name), "image").unwrap();
+        }
+        fs::File::options()
+            .write(true)
+            .open(temp.path().join("new.iso"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.duplicates.enabled = true;
+        config.detector.rules.duplicates.min_size = ByteSize::from_bytes(1);
+        let output_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(temp.path(), &output_path)).unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        let plan: crate::models::CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "old.iso");
+        assert_eq!(plan.entries[0].rule_name, "duplicate");
+        assert_eq!(plan.entries[0].action, CleanupAction::Review);
+        assert!(plan.entries[0].reason.contains("new.iso"));
+    }
+
+    #[test]
+    fn test_run_scan_flags_empty_entries() {
+        let temp = TempDir::new().unwrap();
+        let data = temp.path().join("data");
+        fs::create_dir_all(data.join("old/a/b")).unwrap();
+        fs::create_dir_all(data.join("placeholder")).unwrap();
+        fs::write(data.join("placeholder/.gitkeep"), "").unwrap();
+        fs::write(data.join("touched.txt"), "").unwrap();
+        fs::write(data.join("notes.txt"), "notes").unwrap();
+
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.empty_entries.enabled = true;
+        let output_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(&data, &output_path)).unwrap();
+
+        let plan = PlanReader::read(&output_path).unwrap();
+        let mut flagged: Vec<_> = plan
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.rule_name.as_str(), e.action))
+            .collect();
+        flagged.sort_by_key(|(path, ..)| *path);
+        // The empty tree is planned as a single entry
+        assert_eq!(
+            flagged,
+            vec![
+                ("old", "empty_entry", CleanupAction::Delete),
+                ("touched.txt", "empty_entry", CleanupAction::Delete),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_scan_flags_system_caches() {
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("home");
+        fs::create_dir_all(home.join(".cache/thumbnails/large")).unwrap();
+        fs::write(home.join(".cache/thumbnails/large/a.png"), vec![0u8; 1000]).unwrap();
+        fs::create_dir_all(home.join("games")).unwrap();
+        fs::write(home.join("games/crash.dmp"), vec![0u8; 1000]).unwrap();
+        fs::write(home.join("thesis.pdf"), vec![0u8; 8000]).unwrap();
+
+        let output_path = temp.path().join("plan.yaml");
+        let mut options = scan_options(&home, &output_path);
+        options.skip_hidden = Some(false);
+        run_scan(&MegamaidConfig::default(), options).unwrap();
+
+        let plan = PlanReader::read(&output_path).unwrap();
+        let mut flagged: Vec<_> = plan
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.rule_name.as_str(), e.action))
+            .collect();
+        flagged.sort_by_key(|(path, ..)| *path);
+        assert_eq!(
+            flagged,
+            vec![
+                (
+                    ".cache/thumbnails",
+                    "thumbnail_cache",
+                    CleanupAction::Delete
+                ),
+                ("games/crash.dmp", "crash_dump", CleanupAction::Review),
+            ]
+        );
+
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.system_caches.caches.crash_dumps = false;
+        let mut options = scan_options(&home, &output_path);
+        options.skip_hidden = Some(false);
+        run_scan(&config, options).unwrap();
+        let plan = PlanReader::read(&output_path).unwrap();
+        assert!(plan.entries.iter().all(|e| e.rule_name != "crash_dump"));
+    }
+
+    #[test]
+    fn test_rules_add_test_and_remove() {
+        let temp = TempDir::new().unwrap();
+        let config_path = temp.path().join("megamaid.yaml");
+        fs::write(&config_path, "scanner:\n  max_depth: 8\n").unwrap();
+        let logs = temp.path().join("logs");
+        fs::create_dir_all(&logs).unwrap();
+        fs::write(logs.join("app.log"), "log").unwrap();
+
+        let add = |name: &str, replace: bool| RulesCommands::Add {
+            name: name.to_string(),
+            description: "Log files".to_string(),
+            pattern: None,
+            extensions: vec![".log".to_string()],
+            min_age_days: None,
+            min_size: None,
+            when: None,
+            action: CleanupAction::Delete,
+            replace,
+        };
+        let rules = || load_config(&config_path).unwrap().detector.custom_rules;
+        let run = |command| {
+            run_rules(
+                &load_config(&config_path).unwrap(),
+                Some(&config_path),
+                command,
+            )
+        };
+
+        run(add("logs", false)).unwrap();
+        assert_eq!(rules()[0].extensions, Some(vec![".log".to_string()]));
+        assert_eq!(
+            load_config(&config_path).unwrap().scanner.max_depth,
+            Some(8)
+        );
+
+        // Names are unique unless the rule is replaced
+        assert!(run(add("logs", false)).is_err());
+        run(add("logs", true)).unwrap();
+        assert_eq!(rules().len(), 1);
+
+        run(RulesCommands::Test {
+            name: "logs".to_string(),
+            path: logs.clone(),
+            top: 10,
+        })
+        .unwrap();
+        assert!(run(RulesCommands::Test {
+            name: "missing".to_string(),
+            path: logs,
+            top: 10,
+        })
+        .is_err());
+
+        run(RulesCommands::Remove {
+            name: "logs".to_string(),
+        })
+        .unwrap();
+        assert!(rules().is_empty());
+    }
+
+    #[test]
+    fn test_run_scan_flags_dev_caches() {
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("home");
+        fs::create_dir_all(home.join(".npm/_cacache/content-v2")).unwrap();
+        fs::write(home.join(".npm/_cacache/content-v2/blob"), vec![0u8; 1000]).unwrap();
+        fs::create_dir_all(home.join(".nuget/packages/newtonsoft.json")).unwrap();
+        fs::write(
+            home.join(".nuget/packages/newtonsoft.json/lib"),
+            vec![0u8; 1000],
+        )
+        .unwrap();
+        fs::create_dir_all(home.join(".gradle/caches")).unwrap();
+        fs::write(home.join(".gradle/caches/jar"), vec![0u8; 1000]).unwrap();
+        fs::write(home.join("thesis.pdf"), vec![0u8; 8000]).unwrap();
+
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.dev_caches.caches.gradle = false;
+        let output_path = temp.path().join("plan.yaml");
+        let mut options = scan_options(&home, &output_path);
+        options.skip_hidden = Some(false);
+        run_scan(&config, options).unwrap();
+
+        let plan = PlanReader::read(&output_path).unwrap();
+        let mut flagged: Vec<_> = plan
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.rule_name.as_str(), e.action))
+            .collect();
+        flagged.sort_by_key(|(path, ..)| *path);
+        // `packages` is a cache here, not a source directory
+        assert_eq!(
+            flagged,
+            vec![
+                (".npm/_cacache", "dev_cache", CleanupAction::Delete),
+                (".nuget/packages", "dev_cache", CleanupAction::Delete),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_scan_flags_stale_repos() {
+        let temp = TempDir::new().unwrap();
+        let projects = temp.path().join("projects");
+        let long_ago = SystemTime::now() - Duration::from_secs(800 * 86_400);
+        for name in ["old", "current"] {
+            let git = projects.join(name).join(".git");
+            fs::create_dir_all(&git).unwrap();
+            fs::write(projects.join(name).join("notes.txt"), vec![b'x'; 1000]).unwrap();
+            let head = fs::File::create(git.join("HEAD")).unwrap();
+            if name == "old" {
+                head.set_modified(long_ago).unwrap();
+            }
+        }
+
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.stale_repos.enabled = true;
+        let output_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(&projects, &output_path)).unwrap();
+
+        let plan = PlanReader::read(&output_path).unwrap();
+        let flagged: Vec<_> = plan
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.rule_name.as_str(), e.action))
+            .collect();
+        assert_eq!(flagged, vec![("old", "stale_repo", CleanupAction::Review)]);
+        assert!(plan.entries[0]
+            .reason
+            .contains("without activity for 800 days"));
+    }
+
+    #[test]
+    fn test_run_scan_writes_summary_file() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target/debug")).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(project.join("target/debug/app"), vec![0u8; 4000]).unwrap();
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("src/main.rs"), vec![b' '; 8000]).unwrap();
+
+        let output_path = temp.path().join("plan.yaml");
+        let mut options = scan_options(&project, &output_path);
+        options.summary_file = Some(None);
+        run_scan(&MegamaidConfig::default(), options).unwrap();
+
+        let json = fs::read(temp.path().join(SCAN_SUMMARY_FILE)).unwrap();
+        let summary: ScanSummary = serde_json::from_slice(&json).unwrap();
+        assert_eq!(summary.path, project.display().to_string());
+        assert_eq!(summary.roots, vec![project.display().to_string()]);
+        assert_eq!(summary.total_files, 3);
+        assert!(summary.started_at.is_some());
+        assert_eq!(summary.volumes.len(), 1);
+        assert_eq!(summary.plan_file, Some(output_path.display().to_string()));
+        let build_artifacts = summary
+            .rule_stats
+            .iter()
+            .find(|stats| stats.rule_name == "build_artifact")
+            .unwrap();
+        assert_eq!(build_artifacts.entries_flagged, 1);
+        assert_eq!(
+            summary.top_directories[0].path,
+            project.join("src").display().to_string()
+        );
+    }
+
+    #[test]
+    fn test_run_scan_skips_directories_every_rule_prunes() {
+        let temp = TempDir::new().unwrap();
+        let data = temp.path().join("data");
+        fs::create_dir_all(data.join("repo/.git/objects")).unwrap();
+        fs::write(data.join("repo/.git/objects/pack"), vec![0u8; 4096]).unwrap();
+        fs::write(data.join("repo/readme.txt"), "readme").unwrap();
+        let output_path = temp.path().join("plan.yaml");
+        let export = temp.path().join("entries.csv");
+
+        let exported = |config: &MegamaidConfig| {
+            let mut options = scan_options(&data, &output_path);
+            options.skip_hidden = Some(false);
+            options.export_entries = Some(export.clone());
+            run_scan(config, options).unwrap();
+            fs::read_to_string(&export).unwrap()
+        };
+
+        // The large file rule looks everywhere, so nothing is skipped
+        let mut config = MegamaidConfig::default();
+        assert!(exported(&config).contains(".git"));
+
+        // The remaining built-in rules never flag anything in .git
+        config.detector.rules.size_threshold.enabled = false;
+        config.detector.rules.empty_entries.enabled = true;
+        let entries = exported(&config);
+        assert!(!entries.contains(".git"));
+        assert!(entries.contains("readme.txt"));
+    }
+
+    #[test]
+    fn test_run_scan_threshold_defers_to_config_unless_flag_given() {
+        let temp = TempDir::new().unwrap();
+        let data = temp.path().join("data");
+        fs::create_dir_all(&data).unwrap();
+        fs::write(data.join("blob.bin"), vec![0u8; 4096]).unwrap();
+
+        let output_path = temp.path().join("plan.yaml");
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.size_threshold.threshold_mb = ByteSize::from_bytes(1024);
+
+        let flagged = |options: ScanOptions| {
+            run_scan(&config, options).unwrap();
+            let plan = PlanReader::read(&output_path).unwrap();
+            plan.entries.iter().any(|e| e.path.ends_with("blob.bin"))
+        };
+
+        // No flag: the config threshold (1KiB) applies
+        assert!(flagged(scan_options(&data, &output_path)));
+
+        // An explicit flag overrides it
+        let mut options = scan_options(&data, &output_path);
+        options.large_file_threshold = Some(ByteSize::from_mb(1));
+        assert!(!flagged(options));
+    }
+
+    #[test]
+    fn test_run_scan_applies_keep_rules() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("target")).unwrap();
+        fs::write(temp.path().join("Cargo.toml"), "[package]").unwrap();
 
-    if dry_run {
-        println!();
-        println!("This was a dry run. No files were actually deleted.");
+        let output_path = temp.path().join("plan.yaml");
+        let mut config = MegamaidConfig::default();
+        config
+            .detector
+            .keep_rules
+            .push(crate::config::KeepRuleConfig {
+                name: "keep_target".to_string(),
+                patterns: vec!["**/target".to_string()],
+            });
+
+        run_scan(&config, scan_options(temp.path(), &output_path)).unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        let plan: crate::models::CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        assert!(plan.entries.is_empty());
+        let options = ScanOptions {
+            include_vetoed: true,
+            ..scan_options(temp.path(), &output_path)
+        };
+        run_scan(&config, options).unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        let plan: crate::models::CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].action, crate::models::CleanupAction::Keep);
     }
-}
 
-/// Prints a summary of the cleanup plan.
-fn print_plan_summary(plan: &crate::models::CleanupPlan) {
-    println!("Base Path: {}", plan.base_path.display());
-    println!("Version:   {}", plan.version);
-    println!("Created:   {}", plan.created_at.format("%Y-%m-%d %H:%M:%S"));
-    println!();
-    println!("Entries:   {}", plan.entries.len());
-    println!("  • Delete: {}", plan.delete_count());
-    println!("  • Review: {}", plan.review_count());
-    println!("  • Keep:   {}", plan.keep_count());
-    println!();
-    println!("Total Size: {} MB", plan.total_size() / 1_048_576);
-}
+    #[test]
+    fn test_run_scan_exclude_globs_from_config_and_flag() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("target/debug")).unwrap();
+        fs::write(temp.path().join("target/debug/app"), "binary").unwrap();
+        fs::write(temp.path().join("Cargo.toml"), "[package]").unwrap();
+        let output_path = temp.path().join("plan.yaml");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::PathBuf;
-    use tempfile::TempDir;
+        let mut config = MegamaidConfig::default();
+        config.scanner.exclude = vec!["target".to_string()];
+        run_scan(&config, scan_options(temp.path(), &output_path)).unwrap();
+        assert!(PlanReader::read(&output_path).unwrap().entries.is_empty());
+
+        let options = ScanOptions {
+            exclude: vec!["targ*".to_string()],
+            ..scan_options(temp.path(), &output_path)
+        };
+        run_scan(&MegamaidConfig::default(), options).unwrap();
+        assert!(PlanReader::read(&output_path).unwrap().entries.is_empty());
+
+        run_scan(
+            &MegamaidConfig::default(),
+            scan_options(temp.path(), &output_path),
+        )
+        .unwrap();
+        assert_eq!(PlanReader::read(&output_path).unwrap().entries.len(), 1);
+    }
 
     #[test]
-    fn test_run_scan_with_temp_dir() {
+    fn test_run_scan_applies_custom_rule_criteria() {
         let temp = TempDir::new().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("logs")).unwrap();
+        for name in ["logs/old.log", "logs/new.log", "logs/tiny.log", "old.log"] {
+            let size = if name.ends_with("tiny.log") { 10 } else { 4096 };
+            fs::write(root.join(name), vec![0u8; size]).unwrap();
+        }
+        for name in ["logs/old.log", "logs/tiny.log", "old.log"] {
+            fs::File::options()
+                .write(true)
+                .open(root.join(name))
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(40 * 86_400))
+                .unwrap();
+        }
+        let output_path = temp.path().join("plan.yaml");
 
-        // Create some test files
-        fs::write(temp.path().join("test.txt"), "hello").unwrap();
-        fs::create_dir_all(temp.path().join("target")).unwrap();
+        let mut config = MegamaidConfig::default();
+        config.detector.custom_rules = vec![CustomRule {
+            name: "stale_logs".to_string(),
+            description: "Old log files".to_string(),
+            pattern: Some("**/logs/*".to_string()),
+            extensions: Some(vec![".log".to_string()]),
+            min_age_days: Some(30),
+            min_size: Some(ByteSize::from_bytes(1024)),
+            when: None,
+            action: CleanupAction::Delete,
+        }];
+        run_scan(&config, scan_options(&root, &output_path)).unwrap();
+
+        let plan = PlanReader::read(&output_path).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "logs/old.log");
+        assert_eq!(plan.entries[0].rule_name, "stale_logs");
+        assert_eq!(plan.entries[0].action, CleanupAction::Delete);
+    }
+
+    #[test]
+    fn test_run_scan_applies_pattern_rules() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("docs/old")).unwrap();
+        fs::write(root.join("docs/old/report.orig"), "backup").unwrap();
+        fs::write(root.join("docs/report.txt"), vec![0u8; 4096]).unwrap();
+        fs::write(root.join("Thumbs.db"), "cache").unwrap();
+        let output_path = temp.path().join("plan.yaml");
+
+        let mut config = MegamaidConfig::default();
+        config.detector.pattern_rules = vec![PatternRuleConfig {
+            name: "junk".to_string(),
+            patterns: vec![r"(.*/)?[^/]*\.orig".to_string(), "Thumbs\\.db".to_string()],
+            syntax: PatternSyntax::Regex,
+            action: CleanupAction::Delete,
+            reason: Some("Junk file {path}".to_string()),
+        }];
+        run_scan(&config, scan_options(&root, &output_path)).unwrap();
+
+        let mut plan = PlanReader::read(&output_path).unwrap();
+        plan.entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let flagged: Vec<_> = plan
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.rule_name.as_str(), e.reason.as_str()))
+            .collect();
+        assert_eq!(
+            flagged,
+            [
+                ("Thumbs.db", "junk", "Junk file Thumbs.db"),
+                (
+                    "docs/old/report.orig",
+                    "junk",
+                    "Junk file docs/old/report.orig"
+                ),
+            ]
+        );
+        assert!(plan
+            .entries
+            .iter()
+            .all(|e| e.action == CleanupAction::Delete));
+    }
+
+    #[test]
+    fn test_run_scan_applies_custom_rule_expressions() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("archive")).unwrap();
+        fs::write(root.join("dump.bin"), vec![0u8; 4096]).unwrap();
+        fs::write(root.join("archive/old.bin"), vec![0u8; 4096]).unwrap();
+        fs::write(root.join("notes.txt"), "small").unwrap();
+        let output_path = temp.path().join("plan.yaml");
+
+        // large_file is only referenced, not enabled on its own
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.size_threshold.enabled = false;
+        config.detector.rules.size_threshold.file_threshold = Some(ByteSize::from_bytes(1024));
+        config.detector.custom_rules = vec![CustomRule {
+            name: "unarchived_large".to_string(),
+            description: "Large files outside the archive".to_string(),
+            pattern: None,
+            extensions: Some(vec![".bin".to_string()]),
+            min_age_days: None,
+            min_size: None,
+            when: Some(format!(
+                "large_file AND NOT under('{}')",
+                root.join("archive").display()
+            )),
+            action: CleanupAction::Delete,
+        }];
+        run_scan(&config, scan_options(&root, &output_path)).unwrap();
+
+        let plan = PlanReader::read(&output_path).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "dump.bin");
+        assert_eq!(plan.entries[0].rule_name, "unarchived_large");
+        assert_eq!(plan.entries[0].action, CleanupAction::Delete);
+    }
+
+    #[test]
+    fn test_run_scan_multiple_roots() {
+        let temp = TempDir::new().unwrap();
+        let (dev, data) = (temp.path().join("dev"), temp.path().join("data"));
+        fs::create_dir_all(dev.join("sub")).unwrap();
+        fs::create_dir_all(&data).unwrap();
+        fs::write(dev.join("sub/dump.bin"), vec![0u8; 4096]).unwrap();
+        fs::write(data.join("dump.bin"), vec![0u8; 4096]).unwrap();
+        let output_path = temp.path().join("plan.yaml");
+
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.size_threshold.file_threshold = Some(ByteSize::from_bytes(1024));
+        let options = ScanOptions {
+            // The nested root is already covered by the first one
+            paths: vec![dev.clone(), data.clone(), dev.join("sub")],
+            ..scan_options(&dev, &output_path)
+        };
+        run_scan(&config, options).unwrap();
+
+        let plan = PlanReader::read(&output_path).unwrap();
+        assert_eq!(plan.base_path, dev);
+        assert_eq!(plan.roots, vec![dev.clone(), data.clone()]);
+        let mut paths: Vec<_> = plan.entries.iter().map(|e| plan.entry_path(e)).collect();
+        paths.sort();
+        assert_eq!(paths, vec![data.join("dump.bin"), dev.join("sub/dump.bin")]);
+
+        let err = run_scan(
+            &config,
+            ScanOptions {
+                paths: vec![dev.clone(), data.clone()],
+                scan_cache: Some(temp.path().join("cache.json.zst")),
+                ..scan_options(&dev, &output_path)
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--scan-cache"));
+    }
+
+    #[test]
+    fn test_run_scan_summary_then_expand() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("target/debug")).unwrap();
+        fs::write(temp.path().join("target/debug/app"), "binary").unwrap();
+        fs::write(temp.path().join("Cargo.toml"), "[package]").unwrap();
 
         let output_path = temp.path().join("plan.yaml");
+        let options = ScanOptions {
+            summary: true,
+            ..scan_options(temp.path(), &output_path)
+        };
+        run_scan(&MegamaidConfig::default(), options).unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        let plan: crate::models::CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "target");
+        assert!(plan.entries[0].is_collapsed());
+
+        run_expand(&output_path, "target", None).unwrap();
+
+        let content = fs::read_to_string(&output_path).unwrap();
+        let plan: crate::models::CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(
+            PathBuf::from(&plan.entries[0].path),
+            PathBuf::from("target/debug")
+        );
+        assert!(plan.entries[0].is_collapsed());
+    }
+
+    #[test]
+    fn test_run_verify_writes_record_used_by_execute() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("target/out.bin"), "data").unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
         let config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+        run_verify(&config, verify_options(&plan_path)).unwrap();
 
-        let result = run_scan(&config, temp.path(), &output_path, None, true, 100);
+        let record_path = VerificationRecord::default_path(&plan_path);
+        let record = VerificationRecord::read(&record_path).unwrap();
+        assert!(record.passed);
 
-        assert!(result.is_ok());
-        assert!(output_path.exists());
+        let options = |plan_path: &Path| ExecuteOptions {
+            plan_path: plan_path.to_path_buf(),
+            dry_run: false,
+            interactive: false,
+            backup_dir: None,
+            recycle_bin: false,
+            fail_fast: false,
+            skip_verify: false,
+            use_verification: Some(record_path.clone()),
+            shard: None,
+            resume: None,
+            since_log: None,
+            only_failed: None,
+            largest_first: false,
+            allow_stale_plan: false,
+            empty_recycle_bin_after: false,
+            snapshot: false,
+            log_file: temp.path().join("execution-log.yaml"),
+            parallel: false,
+            batch_size: None,
+            operation_timeout: None,
+            when_idle: None,
+            run_as: None,
+            assume_yes: true,
+        };
+
+        // A plan that changed after verification is rejected
+        let original = fs::read_to_string(&plan_path).unwrap();
+        fs::write(&plan_path, format!("{}\n", original)).unwrap();
+        assert!(run_execute(&config, options(&plan_path)).is_err());
+        assert!(project.join("target").exists());
+
+        fs::write(&plan_path, original).unwrap();
+        run_execute(&config, options(&plan_path)).unwrap();
+        assert!(!project.join("target").exists());
+    }
+
+    #[cfg(not(all(windows, feature = "vss")))]
+    #[test]
+    fn test_run_execute_snapshot_unsupported() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("target/out.bin"), "data").unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let mut config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        let log_path = temp.path().join("execution-log.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+
+        let options = |snapshot: bool| ExecuteOptions {
+            plan_path: plan_path.clone(),
+            dry_run: false,
+            interactive: false,
+            backup_dir: None,
+            recycle_bin: false,
+            fail_fast: false,
+            skip_verify: false,
+            use_verification: None,
+            shard: None,
+            resume: None,
+            since_log: None,
+            only_failed: None,
+            largest_first: false,
+            allow_stale_plan: false,
+            empty_recycle_bin_after: false,
+            snapshot,
+            log_file: log_path.clone(),
+            parallel: false,
+            batch_size: None,
+            operation_timeout: None,
+            when_idle: None,
+            run_as: None,
+            assume_yes: true,
+        };
+
+        // An explicit snapshot request fails before anything is deleted
+        assert!(run_execute(&config, options(true)).is_err());
+        assert!(project.join("target").exists());
+
+        // A threshold-triggered snapshot is skipped where unsupported
+        config.executor.snapshot_threshold = Some(ByteSize::from_bytes(1));
+        run_execute(&config, options(false)).unwrap();
+        assert!(!project.join("target").exists());
+
+        let log = TransactionLogger::read(&log_path).unwrap();
+        assert!(log.snapshot.is_none());
+        assert!(run_snapshot_rollback(&log_path).is_err());
+    }
+
+    #[test]
+    fn test_run_execute_respects_active_hours() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("target/out.bin"), "data").unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let mut config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        let log_path = temp.path().join("execution-log.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+
+        let now = chrono::Local::now().time();
+        let hour = chrono::Duration::hours(1);
+        config.executor.constraints.active_hours = Some(format!(
+            "{}-{}",
+            (now - hour).format("%H:%M"),
+            (now + hour).format("%H:%M")
+        ));
+        let options = ExecuteOptions {
+            plan_path: plan_path.clone(),
+            dry_run: false,
+            interactive: false,
+            backup_dir: None,
+            recycle_bin: false,
+            fail_fast: false,
+            skip_verify: false,
+            use_verification: None,
+            shard: None,
+            resume: None,
+            since_log: None,
+            only_failed: None,
+            largest_first: false,
+            allow_stale_plan: false,
+            empty_recycle_bin_after: false,
+            snapshot: false,
+            log_file: log_path.clone(),
+            parallel: false,
+            batch_size: None,
+            operation_timeout: None,
+            when_idle: None,
+            run_as: None,
+            assume_yes: true,
+        };
+
+        let error = run_execute(&config, options).unwrap_err();
+        assert!(error.to_string().starts_with("Not starting: "));
+        assert!(project.join("target").exists());
+        assert!(!log_path.exists());
+    }
+
+    #[test]
+    fn test_run_verify_watch_waits_until_clean() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+
+        // Drift appears, then the directory settles back to its planned state
+        fs::write(project.join("target/busy.tmp"), "in progress").unwrap();
+        let busy_file = project.join("target/busy.tmp");
+        let cleaner = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(1500));
+            fs::remove_file(busy_file).unwrap();
+        });
+
+        let options = VerifyOptions {
+            skip_mtime: true,
+            watch_interval: Some(Duration::from_secs(1)),
+            watch_timeout: Some(Duration::from_secs(30)),
+            ..verify_options(&plan_path)
+        };
+        run_verify(&config, options).unwrap();
+        cleaner.join().unwrap();
+    }
+
+    #[test]
+    fn test_run_verify_watch_times_out() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+        fs::remove_dir(project.join("target")).unwrap();
+
+        let options = VerifyOptions {
+            watch_interval: Some(Duration::from_secs(1)),
+            watch_timeout: Some(Duration::from_secs(2)),
+            ..verify_options(&plan_path)
+        };
+        let error = run_verify(&config, options).unwrap_err();
+        assert!(error.to_string().contains("watch timeout"));
+    }
+
+    #[test]
+    fn test_run_execute_shards_then_summarize_logs() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        for i in 0..6 {
+            let crate_dir = project.join(format!("crate_{}", i));
+            fs::create_dir_all(crate_dir.join("target")).unwrap();
+            fs::write(crate_dir.join("Cargo.toml"), "[package]").unwrap();
+        }
+
+        let config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+
+        let log_paths: Vec<_> = (1..=2)
+            .map(|index| temp.path().join(format!("log-{}.yaml", index)))
+            .collect();
+        for (index, log_file) in (1..=2).zip(&log_paths) {
+            let options = ExecuteOptions {
+                plan_path: plan_path.clone(),
+                dry_run: false,
+                interactive: false,
+                backup_dir: None,
+                recycle_bin: false,
+                fail_fast: false,
+                skip_verify: true,
+                use_verification: None,
+                shard: Some(Shard { index, count: 2 }),
+                resume: None,
+                since_log: None,
+                only_failed: None,
+                largest_first: false,
+                allow_stale_plan: false,
+                empty_recycle_bin_after: false,
+                snapshot: false,
+                log_file: log_file.clone(),
+                parallel: false,
+                batch_size: None,
+                operation_timeout: None,
+                when_idle: None,
+                run_as: None,
+                assume_yes: true,
+            };
+            run_execute(&config, options).unwrap();
+        }
+
+        // Both shards together removed every artifact exactly once
+        for i in 0..6 {
+            assert!(!project.join(format!("crate_{}/target", i)).exists());
+        }
+
+        let logs: Vec<_> = log_paths
+            .iter()
+            .filter(|p| p.exists())
+            .map(|p| TransactionLogger::read(p).unwrap())
+            .collect();
+        let summary = LogSummary::from_logs(&logs);
+        assert_eq!(summary.successful, 6);
+        assert!(logs.iter().all(|log| log
+            .confirmation
+            .as_ref()
+            .is_some_and(|c| c.method == ConfirmationMethod::Flag)));
+
+        run_log_summarize(
+            &log_paths
+                .into_iter()
+                .filter(|p| p.exists())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_volume_refuses_several_volumes() {
+        let volume_of = |root: &Path| root.iter().take(2).collect::<PathBuf>();
+        let roots = [PathBuf::from("/a/one"), PathBuf::from("/a/two")];
+        assert_eq!(
+            snapshot_volume(&roots, volume_of).unwrap(),
+            PathBuf::from("/a")
+        );
+
+        let roots = [PathBuf::from("/a/one"), PathBuf::from("/b/two")];
+        let err = snapshot_volume(&roots, volume_of).unwrap_err();
+        assert!(err.to_string().contains("several volumes"));
+    }
+
+    #[test]
+    fn test_check_plan_age() {
+        let mut plan = crate::models::CleanupPlan::new(PathBuf::from("/test"));
+        assert!(check_plan_age(&plan, Some(7), TimeDisplay::Utc).is_ok());
+
+        plan.created_at = chrono::Utc::now() - chrono::Duration::days(9);
+        let err = check_plan_age(&plan, Some(7), TimeDisplay::Utc).unwrap_err();
+        assert!(err.to_string().contains("--allow-stale-plan"));
+
+        assert!(check_plan_age(&plan, Some(10), TimeDisplay::Utc).is_ok());
+        assert!(check_plan_age(&plan, None, TimeDisplay::Utc).is_ok());
+    }
+
+    #[test]
+    fn test_rule_total_line() {
+        let stat = crate::models::RuleStats {
+            rule_name: "build_artifact".to_string(),
+            entries_flagged: 1204,
+            bytes_flagged: 44 * 1_073_741_824,
+            largest_entry: Some("app/target".to_string()),
+            largest_entry_bytes: 20 * 1_073_741_824 + 107_374_183,
+            ..Default::default()
+        };
+        assert_eq!(
+            rule_total_line(&stat),
+            "build_artifact: 44.0 GB in 1,204 entries (largest: app/target, 20.1 GB)"
+        );
+    }
+
+    #[test]
+    fn test_confirmation_prompt() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1204), "1,204");
+        assert_eq!(format_count(12_345_678), "12,345,678");
+
+        let prompt = confirmation_prompt(1204, 34 * 1_073_741_824 + 214_748_365, "delete");
+        assert!(prompt.contains("About to delete 1,204 entries totalling 34.2 GB"));
+        assert!(prompt.contains("type 'delete' to proceed"));
+    }
+
+    #[test]
+    fn test_run_execute_resume_from_paused_log() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        for i in 0..3 {
+            let crate_dir = project.join(format!("crate_{}", i));
+            fs::create_dir_all(crate_dir.join("target")).unwrap();
+            fs::write(crate_dir.join("Cargo.toml"), "[package]").unwrap();
+        }
+
+        let config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+
+        // Simulate a backup run that was paused after handling crate_0
+        let log_path = temp.path().join("execution-log.yaml");
+        let backup_dir = temp.path().join("backup");
+        let options = TransactionOptions {
+            dry_run: false,
+            backup_dir: Some(backup_dir.clone()),
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+        let mut logger = TransactionLogger::new(&plan_path, log_path.clone(), options);
+        fs::remove_dir_all(project.join("crate_0/target")).unwrap();
+        let op = crate::executor::OperationResult {
+            path: project.join("crate_0/target"),
+            entry_id: None,
+            action: crate::executor::OperationAction::Delete,
+            status: crate::executor::OperationStatus::Success,
+            size_freed: Some(0),
+            error: None,
+            timestamp: std::time::SystemTime::now(),
+            streams: Vec::new(),
+            backup_path: None,
+        };
+        logger.log_operation(&op);
+        let paused = crate::executor::ExecutionResult {
+            operations: vec![op],
+            summary: crate::executor::ExecutionSummary {
+                total_operations: 1,
+                successful: 1,
+                failed: 0,
+                skipped: 0,
+                space_freed: 0,
+                space_by_action: crate::executor::SpaceByAction::default(),
+                duration: Duration::from_secs(1),
+            },
+            paused: true,
+        };
+        logger.finalize(&paused, TransactionStatus::Paused).unwrap();
+
+        let options = |plan_path: &Path, dry_run: bool| ExecuteOptions {
+            plan_path: plan_path.to_path_buf(),
+            dry_run,
+            interactive: false,
+            backup_dir: None,
+            recycle_bin: false,
+            fail_fast: false,
+            skip_verify: false,
+            use_verification: None,
+            shard: None,
+            resume: Some(log_path.clone()),
+            since_log: None,
+            only_failed: None,
+            largest_first: false,
+            allow_stale_plan: false,
+            empty_recycle_bin_after: false,
+            snapshot: false,
+            log_file: PathBuf::from("unused.yaml"),
+            parallel: false,
+            batch_size: None,
+            operation_timeout: None,
+            when_idle: None,
+            run_as: None,
+            assume_yes: true,
+        };
+
+        // A real run can't resume another plan's log, nor a dry run's
+        let other_plan = temp.path().join("other.yaml");
+        fs::copy(&plan_path, &other_plan).unwrap();
+        let err = run_execute(&config, options(&other_plan, false)).unwrap_err();
+        assert!(err.to_string().contains("was recorded for plan"));
+        let err = run_execute(&config, options(&plan_path, true)).unwrap_err();
+        assert!(err.to_string().contains("real execution"));
+        assert!(project.join("crate_1/target").exists());
+
+        // Resuming verifies and executes only what is left, with the
+        // options the run started with
+        run_execute(&config, options(&plan_path, false)).unwrap();
+
+        for i in 0..3 {
+            assert!(!project.join(format!("crate_{}/target", i)).exists());
+        }
+        assert!(backup_dir.exists());
+        let log = TransactionLogger::read(&log_path).unwrap();
+        assert_eq!(log.status, TransactionStatus::Completed);
+        assert_eq!(log.operations.len(), 3);
+        assert!(log.operations[1..]
+            .iter()
+            .all(|op| op.action == "MoveToBackup"));
+        assert_eq!(log.summary.unwrap().successful, 3);
+    }
+
+    #[test]
+    fn test_run_execute_since_log_skips_processed_entries() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        let add_crate = |name: &str| {
+            let crate_dir = project.join(name);
+            fs::create_dir_all(crate_dir.join("target")).unwrap();
+            fs::write(crate_dir.join("Cargo.toml"), "[package]").unwrap();
+        };
+        add_crate("crate_0");
+
+        let config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        let log_path = temp.path().join("execution-log.yaml");
+        let options = |since_log: Option<PathBuf>, log_file: PathBuf| ExecuteOptions {
+            plan_path: plan_path.clone(),
+            dry_run: false,
+            interactive: false,
+            backup_dir: None,
+            recycle_bin: false,
+            fail_fast: false,
+            skip_verify: false,
+            use_verification: None,
+            shard: None,
+            resume: None,
+            since_log,
+            only_failed: None,
+            largest_first: false,
+            allow_stale_plan: false,
+            empty_recycle_bin_after: false,
+            snapshot: false,
+            log_file,
+            parallel: false,
+            batch_size: None,
+            operation_timeout: None,
+            when_idle: None,
+            run_as: None,
+            assume_yes: true,
+        };
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+        run_execute(&config, options(None, log_path.clone())).unwrap();
+
+        // A regenerated plan lists crate_0/target again alongside a new crate
+        add_crate("crate_0");
+        add_crate("crate_1");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+        assert_eq!(PlanReader::read(&plan_path).unwrap().entries.len(), 2);
+
+        let second_log = temp.path().join("second-log.yaml");
+        run_execute(&config, options(Some(log_path), second_log.clone())).unwrap();
+
+        assert!(project.join("crate_0/target").exists());
+        assert!(!project.join("crate_1/target").exists());
+        let log = TransactionLogger::read(&second_log).unwrap();
+        assert_eq!(log.summary.unwrap().successful, 1);
+    }
+
+    #[test]
+    fn test_run_scan_writes_and_reuses_scan_cache() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let config = MegamaidConfig::default();
+        let plan_path = temp.path().join("plan.yaml");
+        let cache_path = temp.path().join("scan-cache.json");
+        let options = || ScanOptions {
+            scan_cache: Some(cache_path.clone()),
+            ..scan_options(&project, &plan_path)
+        };
+
+        run_scan(&config, options()).unwrap();
+        let cache = ScanCache::load(&cache_path).unwrap();
+        assert_eq!(cache.entries().len(), 3);
+
+        // Second run uses the cache and produces the same plan
+        run_scan(&config, options()).unwrap();
+        let plan = PlanReader::read(&plan_path).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "target");
+
+        let full_walk = ScanOptions {
+            full_walk: true,
+            ..options()
+        };
+        run_scan(&config, full_walk).unwrap();
+        assert_eq!(ScanCache::load(&cache_path).unwrap().entries().len(), 3);
+    }
+
+    #[test]
+    fn test_run_scan_flags_growth_since_cached_scan() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("logs")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("logs/app.log"), "started").unwrap();
+        let plan_path = temp.path().join("plan.yaml");
+
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.growth.enabled = true;
+        config.detector.rules.growth.min_growth = ByteSize::from_bytes(1000);
+        let options = || ScanOptions {
+            scan_cache: Some(temp.path().join("scan-cache.json")),
+            full_walk: true,
+            ..scan_options(&root, &plan_path)
+        };
+
+        // The first scan has nothing to compare with
+        run_scan(&config, options()).unwrap();
+        assert!(PlanReader::read(&plan_path).unwrap().entries.is_empty());
+
+        fs::write(root.join("logs/app.log"), vec![b'x'; 4096]).unwrap();
+        run_scan(&config, options()).unwrap();
+        let plan = PlanReader::read(&plan_path).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "logs");
+        assert_eq!(plan.entries[0].rule_name, "rapid_growth");
+    }
+
+    #[test]
+    fn test_run_scan_excludes_own_artifacts() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        for name in ["app", "lib"] {
+            fs::create_dir_all(project.join(name).join("target")).unwrap();
+            fs::write(project.join(name).join("Cargo.toml"), "[package]").unwrap();
+        }
+
+        // The plan and the scan cache are written inside the scanned tree
+        let config = MegamaidConfig {
+            detector: crate::config::DetectorConfig {
+                rules: crate::config::BuiltInRulesConfig {
+                    size_threshold: crate::config::SizeThresholdConfig {
+                        threshold_mb: ByteSize::from_bytes(1),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let plan_path = project.join("cleanup-plan.yaml");
+        fs::write(&plan_path, "previous plan").unwrap();
+        let options = ScanOptions {
+            scan_cache: Some(project.join("lib/target/scan-cache.json")),
+            ..scan_options(&project, &plan_path)
+        };
+        run_scan(&config, options).unwrap();
+
+        let plan = PlanReader::read(&plan_path).unwrap();
+        let paths: Vec<_> = plan.entries.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"app/target"));
+        assert!(paths.contains(&"lib/Cargo.toml"));
+        assert!(!paths
+            .iter()
+            .any(|p| p.starts_with("lib/target") || p.ends_with(".yaml")));
+        assert!(plan
+            .protections
+            .iter()
+            .any(|e| e.path == "lib/target"
+                && e.protection == crate::models::Protection::OwnArtifact));
+    }
+
+    #[test]
+    fn test_run_scan_exports_entries() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.txt"), "a").unwrap();
+        let output = temp.path().join("plan.yaml");
+        let export = temp.path().join("entries.csv");
+
+        let mut options = scan_options(temp.path(), &output);
+        options.export_entries = Some(export.clone());
+        run_scan(&MegamaidConfig::default(), options).unwrap();
+
+        let csv = fs::read_to_string(&export).unwrap();
+        assert!(csv.starts_with("path,name,extension,"));
+        assert!(csv.lines().any(|l| l.contains(",a.txt,txt,file,1,")));
+    }
+
+    #[test]
+    fn test_run_detect_from_raw_scan() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("share");
+        fs::create_dir_all(root.join("videos")).unwrap();
+        fs::write(root.join("videos/a.mp4"), "x".repeat(2_048)).unwrap();
+        let scan_plan = temp.path().join("scan-plan.yaml");
+        let raw = temp.path().join("scan.jsonl");
+
+        let mut options = scan_options(&root, &scan_plan);
+        options.raw_output = Some(raw.clone());
+        run_scan(&MegamaidConfig::default(), options).unwrap();
+
+        // The share is gone; detection only needs the raw scan
+        fs::remove_dir_all(&root).unwrap();
+        let detect_plan = temp.path().join("detect-plan.yaml");
+        let options = PlanOptions {
+            output: &detect_plan,
+            large_file_threshold: Some(ByteSize::from_bytes(1_024)),
+            include_vetoed: false,
+            summary: false,
+            force: false,
+            tune: false,
+        };
+        run_detect(&MegamaidConfig::default(), &raw, options).unwrap();
+
+        let paths = |path: &Path| -> Vec<String> {
+            let plan = PlanReader::read(path).unwrap();
+            plan.entries.into_iter().map(|e| e.path).collect()
+        };
+        assert_eq!(paths(&detect_plan), vec!["videos/a.mp4"]);
     }
 
     #[test]
@@ -490,11 +5382,7 @@ mod tests {
         let config = MegamaidConfig::default();
         let result = run_scan(
             &config,
-            Path::new("/nonexistent/path"),
-            &output,
-            None,
-            true,
-            100,
+            scan_options(Path::new("/nonexistent/path"), &output),
         );
 
         assert!(result.is_err());
@@ -517,12 +5405,21 @@ entries:
     action: delete
     rule_name: test
     reason: Test
+protections:
+  - path: "clients"
+    size: 5000
+    rule_name: large_file
+    protection: keep_rule
+    outcome: suppressed
+    detail: clients
 "#;
 
         fs::write(&plan_path, plan_content).unwrap();
 
-        let result = run_stats(&plan_path);
+        let result = run_stats(&plan_path, false, false, TimeDisplay::Utc);
         assert!(result.is_ok());
+        assert!(run_stats(&plan_path, true, false, TimeDisplay::Utc).is_ok());
+        assert!(run_stats(&plan_path, false, true, TimeDisplay::Utc).is_ok());
     }
 
     #[test]
@@ -532,7 +5429,245 @@ entries:
 
         fs::write(&plan_path, "invalid: yaml: content: [[[").unwrap();
 
-        let result = run_stats(&plan_path);
+        let result = run_stats(&plan_path, false, false, TimeDisplay::Utc);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_run_estimate() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a/b")).unwrap();
+        fs::write(temp.path().join("a/b/file.bin"), "x".repeat(1000)).unwrap();
+
+        assert!(run_estimate(temp.path(), EstimateConfig::default(), 20).is_ok());
+        assert!(run_estimate(
+            &temp.path().join("a/b/file.bin"),
+            EstimateConfig::default(),
+            20
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_format_estimate_size() {
+        let exact = crate::scanner::Estimate {
+            bytes: 1.5 * 1_073_741_824.0,
+            exact: true,
+            ..Default::default()
+        };
+        assert_eq!(format_estimate_size(&exact), "1.5 GB");
+
+        let sampled = crate::scanner::Estimate {
+            bytes: 3.0 * 1_048_576.0,
+            bytes_margin: 0.5 * 1_048_576.0,
+            files: 1204.4,
+            ..Default::default()
+        };
+        assert_eq!(format_estimate_size(&sampled), "~3.0 MB ± 0.5 MB");
+        assert_eq!(format_estimate_files(&sampled), "~1,204 files");
+    }
+
+    #[test]
+    fn test_run_plan_from_paths() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("old.log"), "log").unwrap();
+        let list = temp.path().join("paths.txt");
+        fs::write(
+            &list,
+            format!(
+                "# ticket-1234\n{}\n{}\n",
+                temp.path().join("old.log").display(),
+                temp.path().join("gone.log").display()
+            ),
+        )
+        .unwrap();
+        let output = temp.path().join("plan.yaml");
+        let options = |skip_missing| FromPathsOptions {
+            list: list.clone(),
+            action: CleanupAction::Delete,
+            reason: "ticket-1234".to_string(),
+            base: None,
+            output: output.clone(),
+            skip_missing,
+        };
+
+        assert!(run_plan_from_paths(options(false), TimeDisplay::Utc).is_err());
+        assert!(!output.exists());
+
+        run_plan_from_paths(options(true), TimeDisplay::Utc).unwrap();
+        let plan = PlanReader::read(&output).unwrap();
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "old.log");
+        assert_eq!(plan.entries[0].reason, "ticket-1234");
+        assert_eq!(plan.delete_count(), 1);
+    }
+
+    #[test]
+    fn test_run_plan_import_ncdu() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("data");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("big.iso"), vec![0u8; 2 * 1_048_576]).unwrap();
+        fs::write(root.join("small.txt"), "small").unwrap();
+
+        let export = temp.path().join("export.json");
+        fs::write(
+            &export,
+            serde_json::json!([
+                1,
+                2,
+                {"progname": "ncdu"},
+                [
+                    {"name": root.to_string_lossy()},
+                    {"name": "big.iso", "asize": 2 * 1_048_576},
+                    {"name": "small.txt", "asize": 5}
+                ]
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+        let output = temp.path().join("plan.yaml");
+        run_plan_import(
+            &MegamaidConfig::default(),
+            ImportOptions {
+                file: export,
+                format: None,
+                du_bytes: false,
+                output: output.clone(),
+                large_file_threshold: Some(ByteSize::from_mb(1)),
+                force: false,
+            },
+        )
+        .unwrap();
+
+        let plan = PlanReader::read(&output).unwrap();
+        assert!(plan.entries.iter().any(|e| e.path == "big.iso"));
+        assert!(!plan.entries.iter().any(|e| e.path == "small.txt"));
+
+        // Mtimes were read from disk, so the plan verifies cleanly
+        let verifier = VerificationEngine::new(VerificationConfig::default());
+        assert!(verifier.verify(&plan).unwrap().is_safe_to_execute());
+    }
+
+    #[test]
+    fn test_run_plan_import_refuses_plan_deleting_most_of_the_tree() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("target")).unwrap();
+        fs::write(project.join("target/out.bin"), vec![0u8; 4096]).unwrap();
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let export = temp.path().join("export.json");
+        fs::write(
+            &export,
+            serde_json::json!([
+                1,
+                2,
+                {"progname": "ncdu"},
+                [
+                    {"name": project.to_string_lossy()},
+                    {"name": "Cargo.toml", "asize": 9},
+                    [{"name": "target"}, {"name": "out.bin", "asize": 4096}]
+                ]
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+        let output = temp.path().join("plan.yaml");
+        let options = |force| ImportOptions {
+            file: export.clone(),
+            format: None,
+            du_bytes: false,
+            output: output.clone(),
+            large_file_threshold: None,
+            force,
+        };
+        let config = MegamaidConfig::default();
+
+        let err = run_plan_import(&config, options(false)).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+        assert!(!output.exists());
+
+        run_plan_import(&config, options(true)).unwrap();
+        assert_eq!(PlanReader::read(&output).unwrap().delete_count(), 1);
+    }
+
+    #[test]
+    fn test_run_diagnostics_collect() {
+        let temp = TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        fs::create_dir_all(project.join("app/target")).unwrap();
+        fs::write(project.join("app/Cargo.toml"), "[package]").unwrap();
+
+        let mut config = MegamaidConfig::default();
+        config.diagnostics.crash_report_dir = Some(temp.path().join("crashes"));
+        config.executor.snapshot_command = Some("snap --password hunter2".to_string());
+        let plan_path = temp.path().join("plan.yaml");
+        let log_path = temp.path().join("execution-log.yaml");
+        run_scan(&config, scan_options(&project, &plan_path)).unwrap();
+        run_execute(
+            &config,
+            ExecuteOptions {
+                plan_path,
+                dry_run: true,
+                interactive: false,
+                backup_dir: None,
+                recycle_bin: false,
+                fail_fast: false,
+                skip_verify: false,
+                use_verification: None,
+                shard: None,
+                resume: None,
+                since_log: None,
+                only_failed: None,
+                largest_first: false,
+                allow_stale_plan: false,
+                empty_recycle_bin_after: false,
+                snapshot: false,
+                log_file: log_path.clone(),
+                parallel: false,
+                batch_size: None,
+                operation_timeout: None,
+                when_idle: None,
+                run_as: None,
+                assume_yes: true,
+            },
+        )
+        .unwrap();
+
+        let output = temp.path().join("diagnostics.zip");
+        run_diagnostics_collect(
+            &config,
+            None,
+            DiagnosticsOptions {
+                output: output.clone(),
+                logs: vec![log_path, temp.path().join("missing-log.yaml")],
+                max_logs: 3,
+                scan_paths: vec![project],
+            },
+        )
+        .unwrap();
+
+        let mut zip = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+        let mut names: Vec<_> = zip.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "config.yaml",
+                "environment.txt",
+                "logs/execution-log.yaml",
+                "logs/summary.txt",
+                "scan-errors.txt"
+            ]
+        );
+
+        let mut config_yaml = String::new();
+        io::Read::read_to_string(&mut zip.by_name("config.yaml").unwrap(), &mut config_yaml)
+            .unwrap();
+        assert!(config_yaml.contains("snapshot_command: <redacted>"));
+        assert!(!config_yaml.contains("hunter2"));
+    }
 }
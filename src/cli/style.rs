@@ -0,0 +1,188 @@
+//! Shared output styling for the CLI.
+//!
+//! Commands print through `outln!` and `out!` and create their spinners
+//! and progress bars here, so the global `--plain` flag applies to all of
+//! them. Plain output is line-oriented for screen readers and log scraping:
+//! no spinners or progress bars, whose redraws come out as noise, and no
+//! emoji or box-drawing characters. Warning and error markers become the
+//! words `Warning:` and `Error:` instead of disappearing.
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Turns plain output on or off for the rest of the process.
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+/// Returns true if output is plain.
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Returns `text` as it should be printed: unchanged, or without emoji and
+/// box drawing in plain mode.
+pub fn styled(text: &str) -> Cow<'_, str> {
+    if is_plain() {
+        Cow::Owned(plain_text(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Prints a line through [`styled`].
+macro_rules! outln {
+    () => {
+        println!()
+    };
+    ($($arg:tt)*) => {
+        println!("{}", $crate::cli::style::styled(&format!($($arg)*)))
+    };
+}
+
+/// Prints through [`styled`] without a newline, e.g. for prompts.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        print!("{}", $crate::cli::style::styled(&format!($($arg)*)))
+    };
+}
+
+pub(crate) use {out, outln};
+
+/// Creates a spinner showing `message`. In plain mode the spinner is hidden
+/// and the message printed as a line instead.
+pub fn spinner(message: impl Into<Cow<'static, str>>) -> ProgressBar {
+    let message = message.into();
+    if is_plain() {
+        outln!("{}", message);
+        return ProgressBar::hidden();
+    }
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} {msg}")
+            .unwrap(),
+    );
+    spinner.set_message(message);
+    spinner
+}
+
+/// Creates a progress bar counting to `len`, hidden in plain mode.
+pub fn progress_bar(len: u64) -> ProgressBar {
+    if is_plain() {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(len);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    bar
+}
+
+/// Prints a line above `bar`, or on its own in plain mode, where the bar is
+/// hidden and would swallow it.
+pub fn println_above(bar: &ProgressBar, line: impl AsRef<str>) {
+    if is_plain() {
+        outln!("{}", line.as_ref());
+    } else {
+        bar.println(line.as_ref());
+    }
+}
+
+/// Finishes `bar` with `message`, printed as a line in plain mode.
+pub fn finish(bar: &ProgressBar, message: impl Into<Cow<'static, str>>) {
+    let message = message.into();
+    if is_plain() {
+        outln!("{}", message);
+    } else {
+        bar.finish_with_message(message);
+    }
+}
+
+/// Stops `bar` where it is with `message`, printed as a line in plain mode.
+pub fn abandon(bar: &ProgressBar, message: impl Into<Cow<'static, str>>) {
+    let message = message.into();
+    if is_plain() {
+        outln!("{}", message);
+    } else {
+        bar.abandon_with_message(message);
+    }
+}
+
+/// Removes emoji and box drawing from `text`, spelling out warning and error
+/// markers and arrows.
+fn plain_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let replacement = match c {
+            '⚠' => "Warning:",
+            '❌' => "Error:",
+            'ℹ' => "Note:",
+            '→' => "->",
+            '─' | '━' | '═' => "-",
+            '│' | '┃' | '║' => "|",
+            _ if is_decoration(c) => "",
+            _ => {
+                plain.push(c);
+                continue;
+            }
+        };
+        while chars.next_if(|&c| c == '\u{fe0f}').is_some() {}
+        plain.push_str(replacement);
+
+        // Markers are followed by two spaces to line up after wide emoji;
+        // a dropped symbol takes its spaces with it
+        if replacement.is_empty() || replacement.ends_with(':') {
+            let spaced = chars.next_if_eq(&' ').is_some();
+            while chars.next_if_eq(&' ').is_some() {}
+            if spaced && !replacement.is_empty() {
+                plain.push(' ');
+            }
+        }
+    }
+    plain
+}
+
+/// Returns true for emoji, pictographs, dingbats and other box drawing.
+fn is_decoration(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x2190..=0x21ff     // arrows
+            | 0x2300..=0x23ff // technical symbols (⏸, ⏭)
+            | 0x2500..=0x259f // box drawing and blocks
+            | 0x25a0..=0x25ff // geometric shapes (▶)
+            | 0x2600..=0x27bf // symbols and dingbats (♻, ⚡, ✓, ✅)
+            | 0x2b00..=0x2bff
+            | 0x1f000..=0x1faff
+            | 0x200d          // zero-width joiner
+            | 0xfe0f // emoji presentation selector
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            plain_text("⚠️  Skipping /a: already covered"),
+            "Warning: Skipping /a: already covered"
+        );
+        assert_eq!(
+            plain_text("🔍 Scanning directory: /a"),
+            "Scanning directory: /a"
+        );
+        assert_eq!(plain_text("  ✓ Scanned 12 entries"), "  Scanned 12 entries");
+        assert_eq!(plain_text("❌ Failed"), "Error: Failed");
+        assert_eq!(plain_text("a → b ──"), "a -> b --");
+        assert_eq!(plain_text("ключ 12.0 GB — ok"), "ключ 12.0 GB — ok");
+    }
+}
@@ -1,9 +1,19 @@
 //! Configuration file loading and parsing.
 
-use super::schema::MegamaidConfig;
+use super::schema::{CustomRule, MegamaidConfig};
+use super::validation::validate_config;
 use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Default config file locations, in the order they are tried.
+const DEFAULT_PATHS: [&str; 4] = [
+    "megamaid.yaml",
+    "megamaid.yml",
+    ".megamaid.yaml",
+    ".megamaid.yml",
+];
 
 /// Loads configuration from a YAML file.
 pub fn load_config<P: AsRef<Path>>(path: P) -> Result<MegamaidConfig> {
@@ -27,23 +37,18 @@ pub fn parse_config(yaml: &str) -> Result<MegamaidConfig> {
     Ok(config)
 }
 
+/// Returns the first default location holding a config file.
+pub fn default_config_path() -> Option<PathBuf> {
+    DEFAULT_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+}
+
 /// Attempts to load config from default locations.
 /// Returns None if no config file is found.
 pub fn load_default_config() -> Result<Option<MegamaidConfig>> {
-    let default_paths = vec![
-        "megamaid.yaml",
-        "megamaid.yml",
-        ".megamaid.yaml",
-        ".megamaid.yml",
-    ];
-
-    for path in default_paths {
-        if Path::new(path).exists() {
-            return load_config(path).map(Some);
-        }
-    }
-
-    Ok(None)
+    default_config_path().map(load_config).transpose()
 }
 
 /// Writes a config to a YAML file.
@@ -57,6 +62,142 @@ pub fn write_config<P: AsRef<Path>>(config: &MegamaidConfig, path: P) -> Result<
     Ok(())
 }
 
+/// Replaces the custom rules in a config file, keeping its other settings.
+///
+/// The file is created if it doesn't exist. Only the `custom_rules` section
+/// under `detector` is rewritten, so comments and formatting elsewhere are
+/// kept; a file whose `detector` section is in flow style (`detector: {...}`)
+/// is rewritten whole instead, losing its comments. Nothing is written unless
+/// the updated configuration is valid, and the file is replaced atomically.
+pub fn write_custom_rules<P: AsRef<Path>>(path: P, rules: &[CustomRule]) -> Result<()> {
+    let path = path.as_ref();
+
+    let content = if path.exists() {
+        fs::read_to_string(path)
+            .context(format!("Failed to read config file: {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let mut document: Value = serde_yaml::from_str(&content)
+        .context(format!("Failed to parse config file: {}", path.display()))?;
+    let rules_value = serde_yaml::to_value(rules).context("Failed to serialize custom rules")?;
+    let detector = mapping(&mut document, path, "The config")?
+        .entry("detector".into())
+        .or_insert(Value::Null);
+    mapping(detector, path, "detector")?.insert("custom_rules".into(), rules_value);
+
+    let config: MegamaidConfig = serde_yaml::from_value(document.clone())
+        .context(format!("Failed to parse config file: {}", path.display()))?;
+    validate_config(&config)?;
+
+    // The section is edited in the text; if the result doesn't parse to the
+    // expected document, the document is written out instead
+    let yaml = match replace_custom_rules(&content, rules)? {
+        Some(edited) if serde_yaml::from_str::<Value>(&edited).ok().as_ref() == Some(&document) => {
+            edited
+        }
+        _ => serde_yaml::to_string(&document).context("Failed to serialize configuration")?,
+    };
+
+    let mut temp = path.to_path_buf();
+    temp.set_extension("tmp");
+    fs::write(&temp, yaml).context(format!("Failed to write config to: {}", temp.display()))?;
+    fs::rename(&temp, path).context(format!("Failed to write config to: {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Returns `content` with the block-style `detector.custom_rules` section
+/// set to `rules`, adding the section (and `detector`) if it is missing.
+///
+/// Returns `None` if `detector` isn't a block mapping.
+fn replace_custom_rules(content: &str, rules: &[CustomRule]) -> Result<Option<String>> {
+    let mut lines: Vec<&str> = content.lines().collect();
+    let is_content = |line: &str| !line.trim().is_empty() && !line.trim_start().starts_with('#');
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+    // Blank and comment lines at the end of a block belong to what follows.
+    // Sequence items may sit at the indent of their key.
+    let block_end = |lines: &[&str], start: usize, indent: usize| {
+        let mut end = start;
+        for (i, line) in lines.iter().enumerate().skip(start) {
+            if is_content(line) {
+                let item = indent_of(line) == indent && line.trim_start().starts_with('-');
+                if indent_of(line) <= indent && !item {
+                    break;
+                }
+                end = i + 1;
+            }
+        }
+        end
+    };
+
+    let detector = lines.iter().position(|line| {
+        line.strip_prefix("detector:")
+            .is_some_and(|rest| !rest.starts_with(|c: char| !c.is_whitespace()))
+    });
+    let Some(detector) = detector else {
+        let mut edited = content.to_string();
+        if !edited.is_empty() && !edited.ends_with('\n') {
+            edited.push('\n');
+        }
+        edited.push_str("detector:\n");
+        edited.push_str(&custom_rules_section(2, rules)?);
+        return Ok(Some(edited));
+    };
+    let value = lines[detector]["detector:".len()..].trim();
+    if !value.is_empty() && !value.starts_with('#') {
+        return Ok(None);
+    }
+
+    let detector_end = block_end(&lines, detector + 1, 0);
+    let indent = lines[detector + 1..detector_end]
+        .iter()
+        .find(|line| is_content(line))
+        .map_or(2, |line| indent_of(line));
+    let existing = (detector + 1..detector_end).find(|&i| {
+        indent_of(lines[i]) == indent
+            && lines[i]
+                .trim_start()
+                .strip_prefix("custom_rules")
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    });
+
+    let section = custom_rules_section(indent, rules)?;
+    let (start, end) = match existing {
+        Some(start) => (start, block_end(&lines, start + 1, indent)),
+        None => (detector_end, detector_end),
+    };
+    lines.splice(start..end, section.lines());
+
+    let mut edited = lines.join("\n");
+    edited.push('\n');
+    Ok(Some(edited))
+}
+
+/// Formats a `custom_rules:` section indented by `indent` spaces.
+fn custom_rules_section(indent: usize, rules: &[CustomRule]) -> Result<String> {
+    let pad = " ".repeat(indent);
+    if rules.is_empty() {
+        return Ok(format!("{}custom_rules: []\n", pad));
+    }
+    let yaml = serde_yaml::to_string(rules).context("Failed to serialize custom rules")?;
+    let mut section = format!("{}custom_rules:\n", pad);
+    for line in yaml.lines() {
+        section.push_str(&format!("{}  {}\n", pad, line));
+    }
+    Ok(section)
+}
+
+/// Returns `value` as a mapping, turning an empty value into one.
+fn mapping<'a>(value: &'a mut Value, path: &Path, what: &str) -> Result<&'a mut Mapping> {
+    if value.is_null() {
+        *value = Value::Mapping(Mapping::new());
+    }
+    value
+        .as_mapping_mut()
+        .with_context(|| format!("{} in {} is not a YAML mapping", what, path.display()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,7 +217,10 @@ detector:
 
         let config = parse_config(yaml).unwrap();
         assert_eq!(config.scanner.max_depth, Some(5));
-        assert_eq!(config.detector.rules.size_threshold.threshold_mb, 200);
+        assert_eq!(
+            config.detector.rules.size_threshold.threshold_mb,
+            crate::models::ByteSize::from_mb(200)
+        );
     }
 
     #[test]
@@ -137,6 +281,132 @@ executor:
         assert!(loaded.executor.parallel);
     }
 
+    #[test]
+    fn test_write_custom_rules_keeps_other_settings() {
+        let temp = TempDir::new().unwrap();
+        let config_path = temp.path().join("megamaid.yaml");
+        fs::write(&config_path, "scanner:\n  max_depth: 4\n").unwrap();
+
+        let rule = CustomRule {
+            name: "old_logs".to_string(),
+            description: "Old log files".to_string(),
+            pattern: Some("**/*.log".to_string()),
+            extensions: None,
+            min_age_days: Some(30),
+            min_size: None,
+            when: None,
+            action: crate::models::CleanupAction::Delete,
+        };
+        write_custom_rules(&config_path, std::slice::from_ref(&rule)).unwrap();
+
+        let loaded = load_config(&config_path).unwrap();
+        assert_eq!(loaded.scanner.max_depth, Some(4));
+        assert_eq!(loaded.detector.custom_rules, vec![rule.clone()]);
+        // Settings the file left out stay out
+        let content = fs::read_to_string(&config_path).unwrap();
+        assert!(!content.contains("executor"));
+
+        // An invalid rule leaves the file as it was
+        let invalid = CustomRule {
+            min_age_days: Some(0),
+            ..rule
+        };
+        assert!(write_custom_rules(&config_path, &[invalid]).is_err());
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), content);
+
+        // A missing file is created
+        let new_path = temp.path().join("new.yaml");
+        assert!(!temp.path().join("megamaid.tmp").exists());
+        write_custom_rules(&new_path, &[]).unwrap();
+        assert!(load_config(&new_path)
+            .unwrap()
+            .detector
+            .custom_rules
+            .is_empty());
+    }
+
+    #[test]
+    fn test_write_custom_rules_keeps_comments() {
+        let temp = TempDir::new().unwrap();
+        let config_path = temp.path().join("megamaid.yaml");
+        let original = "\
+# Scanner settings
+scanner:
+  max_depth: 4 # deep enough
+
+detector:
+    # Old rules
+    custom_rules:
+        - name: stale
+          description: Stale files
+          pattern: \"**/*.stale\"
+          action: delete
+
+    # Built-in rules
+    rules:
+        size_threshold:
+            enabled: false
+";
+        fs::write(&config_path, original).unwrap();
+
+        let rule = CustomRule {
+            name: "old_logs".to_string(),
+            description: "Old log files".to_string(),
+            pattern: Some("**/*.log".to_string()),
+            extensions: None,
+            min_age_days: Some(30),
+            min_size: None,
+            when: None,
+            action: crate::models::CleanupAction::Delete,
+        };
+        write_custom_rules(&config_path, std::slice::from_ref(&rule)).unwrap();
+
+        let content = fs::read_to_string(&config_path).unwrap();
+        for kept in [
+            "# Scanner settings\n",
+            "  max_depth: 4 # deep enough\n",
+            "\n    # Built-in rules\n    rules:\n",
+            "    custom_rules:\n      - name: old_logs\n",
+        ] {
+            assert!(content.contains(kept), "{:?} not in {}", kept, content);
+        }
+        assert!(!content.contains("stale"));
+        let loaded = load_config(&config_path).unwrap();
+        assert_eq!(loaded.detector.custom_rules, vec![rule.clone()]);
+        assert!(!loaded.detector.rules.size_threshold.enabled);
+
+        // Removing every rule leaves an empty section; a file without a
+        // detector section gets one
+        write_custom_rules(&config_path, &[]).unwrap();
+        let content = fs::read_to_string(&config_path).unwrap();
+        assert!(content.contains("\n    custom_rules: []\n\n    # Built-in rules\n"));
+
+        fs::write(&config_path, "# Only scanner\nscanner:\n  max_depth: 4\n").unwrap();
+        write_custom_rules(&config_path, &[]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "# Only scanner\nscanner:\n  max_depth: 4\ndetector:\n  custom_rules: []\n"
+        );
+
+        // Sequence items at the indent of their key are replaced too
+        fs::write(
+            &config_path,
+            "detector:\n  custom_rules:\n  - name: stale\n    description: Stale\n    pattern: '*.stale'\n  # Rules\n  rules: {}\n",
+        )
+        .unwrap();
+        write_custom_rules(&config_path, &[]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "detector:\n  custom_rules: []\n  # Rules\n  rules: {}\n"
+        );
+
+        // A flow-style detector section is rewritten whole
+        fs::write(&config_path, "detector: {custom_rules: []}\n").unwrap();
+        write_custom_rules(&config_path, std::slice::from_ref(&rule)).unwrap();
+        let loaded = load_config(&config_path).unwrap();
+        assert_eq!(loaded.detector.custom_rules, vec![rule]);
+    }
+
     #[test]
     fn test_load_default_config_not_found() {
         let temp = TempDir::new().unwrap();
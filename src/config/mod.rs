@@ -23,14 +23,23 @@
 //! ```
 
 pub mod loader;
+pub mod reload;
 pub mod schema;
+pub mod secrets;
 pub mod validation;
 
 // Re-export commonly used types
-pub use loader::{load_config, load_default_config, parse_config, write_config};
+pub use loader::{
+    default_config_path, load_config, load_default_config, parse_config, write_config,
+    write_custom_rules,
+};
+pub use reload::ConfigReloader;
 pub use schema::{
-    BuildArtifactsConfig, BuiltInRulesConfig, CustomRule, DetectorConfig, ExecutionModeConfig,
-    ExecutorConfig, MegamaidConfig, OutputConfig, ScannerConfig, SizeThresholdConfig,
-    VerifierConfig,
+    AlternateStreamsConfig, ArchiveConfig, ArtifactEcosystemsConfig, BuildArtifactsConfig,
+    BuiltInRulesConfig, CustomRule, DeleteMarkersConfig, DetectorConfig, DiagnosticsConfig,
+    DuplicatesConfig, EncryptionConfig, ExecutionModeConfig, ExecutorConfig, GrowthConfig,
+    IdleConfig, KeepRuleConfig, LastChanceConfig, MegamaidConfig, OrphanedEnvsConfig, OutputConfig,
+    PatternRuleConfig, ScannerConfig, SizeThresholdConfig, StaleReposConfig, VerifierConfig,
 };
+pub use secrets::{get_secret, remove_secret, set_secret, SecretError};
 pub use validation::validate_config;
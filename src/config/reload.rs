@@ -0,0 +1,86 @@
+//! Picking up edits to the config file while a long-running command runs.
+
+use super::loader::load_config;
+use super::schema::MegamaidConfig;
+use super::validation::validate_config;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Watches a config file for changes.
+///
+/// Each [`ConfigReloader::poll`] compares the file's modification time and
+/// size with the previous check, and only reads the file when either
+/// changed. A file that doesn't exist yet is picked up once it is created.
+#[derive(Debug)]
+pub struct ConfigReloader {
+    path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl ConfigReloader {
+    /// Starts watching `path`, taking its current contents as already
+    /// applied.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let stamp = stamp(&path);
+        Self { path, stamp }
+    }
+
+    /// Returns the watched file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the validated configuration if the file changed since the
+    /// last check, or why it can't be applied; None if it didn't change.
+    ///
+    /// A rejected edit isn't reported again until the file changes once
+    /// more.
+    pub fn poll(&mut self) -> Option<Result<MegamaidConfig>> {
+        let stamp = stamp(&self.path);
+        if stamp == self.stamp {
+            return None;
+        }
+        self.stamp = stamp;
+        Some(load_config(&self.path).and_then(|config| {
+            validate_config(&config)?;
+            Ok(config)
+        }))
+    }
+}
+
+/// Returns the modification time and size of `path`, if it exists.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_poll_reports_valid_and_invalid_edits() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("megamaid.yaml");
+        let mut reloader = ConfigReloader::new(&path);
+        assert!(reloader.poll().is_none());
+
+        // Created after watching started
+        fs::write(&path, "scanner:\n  max_depth: 3\n").unwrap();
+        let config = reloader.poll().unwrap().unwrap();
+        assert_eq!(config.scanner.max_depth, Some(3));
+        assert!(reloader.poll().is_none());
+
+        // Sizes differ, so the edits are seen even within the mtime resolution
+        fs::write(&path, "detector:\n  max_delete_fraction: 2.0\n").unwrap();
+        assert!(reloader.poll().unwrap().is_err());
+        assert!(reloader.poll().is_none());
+
+        fs::write(&path, "scanner: [[[\n").unwrap();
+        assert!(reloader.poll().unwrap().is_err());
+    }
+}
@@ -1,8 +1,13 @@
 //! Configuration schema definitions.
 
-use crate::models::CleanupAction;
+use crate::detector::{Comparison, EntryKinds, Expr, ExprError, PatternSyntax};
+use crate::executor::{IdlePolicy, LastChance, NoUserPolicy, RunConstraints, VolumeConcurrency};
+use crate::models::byte_size::{self, ByteSize};
+use crate::models::{CleanupAction, TimeDisplay};
+use crate::scanner::{DirSizeConfig, RemoteScanConfig, ScanBackend};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Root configuration structure.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -22,6 +27,12 @@ pub struct MegamaidConfig {
 
     /// Verifier configuration
     pub verifier: VerifierConfig,
+
+    /// Diagnostics configuration
+    pub diagnostics: DiagnosticsConfig,
+
+    /// Encryption of plans, logs and scan caches at rest
+    pub encryption: EncryptionConfig,
 }
 
 /// Scanner configuration.
@@ -39,6 +50,44 @@ pub struct ScannerConfig {
 
     /// Number of threads for parallel scanning (0 = auto-detect)
     pub thread_count: usize,
+
+    /// Enumerate NTFS alternate data streams of files (Windows only)
+    pub include_streams: bool,
+
+    /// Strategy for SMB/NFS shares and other network filesystems
+    pub remote: RemoteScanConfig,
+
+    /// Glob patterns of paths to leave out of scans (see
+    /// [`crate::scanner::exclude`])
+    pub exclude: Vec<String>,
+
+    /// Leave out what `.gitignore` and `.megamaidignore` files ignore
+    pub respect_ignore_files: bool,
+
+    /// Don't descend into other filesystems (network shares, USB drives,
+    /// bind mounts) below the scan root
+    pub same_filesystem: bool,
+
+    /// Parallelism of sizing directories at the depth limit in parallel
+    /// scans (see [`crate::scanner::dir_size`])
+    pub dir_size: DirSizeConfig,
+
+    /// How the CLI lists entries: walking the tree, or reading the NTFS
+    /// master file table (see [`crate::scanner::mft`])
+    pub backend: ScanBackend,
+
+    /// Record the owner and read-only/system attributes of entries (see
+    /// [`crate::scanner::owner`])
+    pub collect_owner: bool,
+
+    /// Walk the largest subdirectories of the scan root first, by a quick
+    /// estimate, reporting each as it finishes
+    pub largest_first: bool,
+
+    /// Maximum entries read per second, 0 for no limit; keeps background
+    /// scans of spinning disks and network shares from starving other work
+    /// (see [`crate::scanner::throttle`])
+    pub max_iops: u32,
 }
 
 impl Default for ScannerConfig {
@@ -48,6 +97,16 @@ impl Default for ScannerConfig {
             skip_hidden: true,
             follow_symlinks: false,
             thread_count: 0,
+            include_streams: false,
+            remote: RemoteScanConfig::default(),
+            exclude: Vec::new(),
+            respect_ignore_files: false,
+            same_filesystem: false,
+            dir_size: DirSizeConfig::default(),
+            backend: ScanBackend::Walk,
+            collect_owner: false,
+            largest_first: false,
+            max_iops: 0,
         }
     }
 }
@@ -58,10 +117,19 @@ impl From<ScannerConfig> for crate::scanner::traversal::ScanConfig {
             follow_links: config.follow_symlinks,
             max_depth: config.max_depth,
             skip_hidden: config.skip_hidden,
+            include_streams: config.include_streams,
+            remote: config.remote,
+            exclude_globs: config.exclude,
+            respect_ignore_files: config.respect_ignore_files,
+            same_filesystem: config.same_filesystem,
+            collect_owner: config.collect_owner,
+            largest_first: config.largest_first,
+            max_iops: config.max_iops,
         }
     }
 }
 
+#[cfg(feature = "parallel")]
 impl From<ScannerConfig> for crate::scanner::parallel::ScannerConfig {
     fn from(config: ScannerConfig) -> Self {
         Self {
@@ -69,12 +137,19 @@ impl From<ScannerConfig> for crate::scanner::parallel::ScannerConfig {
             skip_hidden: config.skip_hidden,
             follow_symlinks: config.follow_symlinks,
             thread_count: config.thread_count,
+            remote: config.remote,
+            exclude_globs: config.exclude,
+            respect_ignore_files: config.respect_ignore_files,
+            same_filesystem: config.same_filesystem,
+            collect_owner: config.collect_owner,
+            max_iops: config.max_iops,
+            dir_size: config.dir_size,
         }
     }
 }
 
 /// Detector configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 pub struct DetectorConfig {
     /// Built-in rules configuration
@@ -82,6 +157,32 @@ pub struct DetectorConfig {
 
     /// Custom detection rules
     pub custom_rules: Vec<CustomRule>,
+
+    /// Rules matching relative paths against glob or regex patterns
+    pub pattern_rules: Vec<PatternRuleConfig>,
+
+    /// Keep rules that veto detections under matching paths
+    pub keep_rules: Vec<KeepRuleConfig>,
+
+    /// Directories of deliberately archived material, kept like keep rules
+    pub archive: ArchiveConfig,
+
+    /// Share of the scanned bytes (0.0-1.0) a plan may mark Delete before
+    /// `scan` refuses to write it without `--force`
+    pub max_delete_fraction: f64,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> Self {
+        Self {
+            rules: BuiltInRulesConfig::default(),
+            custom_rules: Vec::new(),
+            pattern_rules: Vec::new(),
+            keep_rules: Vec::new(),
+            archive: ArchiveConfig::default(),
+            max_delete_fraction: 0.6,
+        }
+    }
 }
 
 /// Built-in rules configuration.
@@ -93,6 +194,33 @@ pub struct BuiltInRulesConfig {
 
     /// Build artifacts rule configuration
     pub build_artifacts: BuildArtifactsConfig,
+
+    /// Alternate data streams rule configuration
+    pub alternate_streams: AlternateStreamsConfig,
+
+    /// Orphaned Python environments rule configuration
+    pub orphaned_envs: OrphanedEnvsConfig,
+
+    /// Stale Git repositories rule configuration
+    pub stale_repos: StaleReposConfig,
+
+    /// Rapid directory growth rule configuration
+    pub growth: GrowthConfig,
+
+    /// Delete marker file rule configuration
+    pub delete_markers: DeleteMarkersConfig,
+
+    /// Duplicate files rule configuration
+    pub duplicates: DuplicatesConfig,
+
+    /// Empty files and directories rule configuration
+    pub empty_entries: EmptyEntriesConfig,
+
+    /// Package manager caches rule configuration
+    pub dev_caches: DevCachesConfig,
+
+    /// Temporary files, browser, thumbnail and crash dump rules configuration
+    pub system_caches: SystemCachesConfig,
 }
 
 /// Size threshold rule configuration.
@@ -102,19 +230,369 @@ pub struct SizeThresholdConfig {
     /// Enable this rule
     pub enabled: bool,
 
-    /// Threshold in megabytes
-    pub threshold_mb: u64,
+    /// Threshold (used for any kind without its own threshold); bare numbers are MB
+    #[serde(with = "byte_size::megabytes")]
+    pub threshold_mb: ByteSize,
+
+    /// File threshold (falls back to `threshold_mb`); bare numbers are MB
+    #[serde(
+        default,
+        with = "byte_size::megabytes::option",
+        skip_serializing_if = "Option::is_none",
+        alias = "file_threshold_mb"
+    )]
+    pub file_threshold: Option<ByteSize>,
+
+    /// Directory threshold (falls back to `threshold_mb`); bare numbers are MB
+    #[serde(
+        default,
+        with = "byte_size::megabytes::option",
+        skip_serializing_if = "Option::is_none",
+        alias = "directory_threshold_mb"
+    )]
+    pub directory_threshold: Option<ByteSize>,
 
     /// Default action for flagged files
     pub action: CleanupAction,
+
+    /// Entry kinds the rule applies to (files, directories, symlinks)
+    pub applies_to: EntryKinds,
+
+    /// Also flag cloud placeholders (e.g. OneDrive online-only files), which
+    /// report their full size but take up no disk space
+    pub include_cloud_placeholders: bool,
+}
+
+impl SizeThresholdConfig {
+    /// Returns the effective file threshold.
+    pub fn effective_file_threshold(&self) -> ByteSize {
+        self.file_threshold.unwrap_or(self.threshold_mb)
+    }
+
+    /// Returns the effective directory threshold.
+    pub fn effective_directory_threshold(&self) -> ByteSize {
+        self.directory_threshold.unwrap_or(self.threshold_mb)
+    }
 }
 
 impl Default for SizeThresholdConfig {
     fn default() -> Self {
         Self {
             enabled: true,
-            threshold_mb: 100,
+            threshold_mb: ByteSize::from_mb(100),
+            file_threshold: None,
+            directory_threshold: None,
             action: CleanupAction::Review,
+            applies_to: EntryKinds::ALL,
+            include_cloud_placeholders: false,
+        }
+    }
+}
+
+/// Alternate data streams rule configuration.
+///
+/// Only has an effect when the scanner enumerates streams
+/// (`scanner.include_streams`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AlternateStreamsConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Minimum combined stream size per file; bare numbers are MB
+    #[serde(with = "byte_size::megabytes", alias = "threshold_mb")]
+    pub threshold: ByteSize,
+}
+
+impl Default for AlternateStreamsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: ByteSize::from_mb(10),
+        }
+    }
+}
+
+/// Orphaned Python environments rule configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct OrphanedEnvsConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Flag environments whose project wasn't modified for this many days
+    pub stale_days: u64,
+}
+
+impl Default for OrphanedEnvsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            stale_days: 180,
+        }
+    }
+}
+
+/// Stale Git repositories rule configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StaleReposConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Flag repositories without a commit, checkout or fetch for this many days
+    pub stale_days: u64,
+}
+
+impl Default for StaleReposConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stale_days: 365,
+        }
+    }
+}
+
+/// Delete marker file rule configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DeleteMarkersConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Name of the marker file
+    pub marker: String,
+}
+
+impl Default for DeleteMarkersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            marker: ".megamaid-delete".to_string(),
+        }
+    }
+}
+
+/// Duplicate files rule configuration.
+///
+/// Hashes every file sharing its size with another, so it is off by default.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DuplicatesConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Leave smaller files out; bare numbers are MB
+    #[serde(with = "byte_size::megabytes", alias = "min_size_mb")]
+    pub min_size: ByteSize,
+}
+
+impl Default for DuplicatesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_size: ByteSize::from_mb(1),
+        }
+    }
+}
+
+/// Empty files and directories rule configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EmptyEntriesConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Names of zero-byte files that are never flagged
+    pub keep_names: Vec<String>,
+}
+
+impl Default for EmptyEntriesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keep_names: crate::detector::PLACEHOLDER_FILES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Package manager caches rule configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DevCachesConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Per-cache toggles
+    pub caches: DevCacheKindsConfig,
+}
+
+impl Default for DevCachesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            caches: DevCacheKindsConfig::default(),
+        }
+    }
+}
+
+impl From<&DevCachesConfig> for crate::detector::DevCacheRule {
+    fn from(config: &DevCachesConfig) -> Self {
+        Self::new(&config.caches.enabled_names())
+    }
+}
+
+/// Package manager caches that can be individually enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DevCacheKindsConfig {
+    /// Cargo `~/.cargo/registry/cache/`
+    pub cargo_registry: bool,
+
+    /// npm `~/.npm/_cacache/`
+    pub npm: bool,
+
+    /// pip `~/.cache/pip/` (`~/Library/Caches/pip/` on macOS)
+    pub pip: bool,
+
+    /// Gradle `~/.gradle/caches/`
+    pub gradle: bool,
+
+    /// NuGet `~/.nuget/packages/`
+    pub nuget: bool,
+}
+
+impl Default for DevCacheKindsConfig {
+    fn default() -> Self {
+        Self {
+            cargo_registry: true,
+            npm: true,
+            pip: true,
+            gradle: true,
+            nuget: true,
+        }
+    }
+}
+
+impl DevCacheKindsConfig {
+    /// Returns the names of all enabled caches.
+    pub fn enabled_names(&self) -> Vec<&'static str> {
+        [
+            ("cargo_registry", self.cargo_registry),
+            ("npm", self.npm),
+            ("pip", self.pip),
+            ("gradle", self.gradle),
+            ("nuget", self.nuget),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect()
+    }
+}
+
+/// Temporary files and system caches rules configuration.
+///
+/// The caches are flagged by four rules (`temp_files`, `browser_cache`,
+/// `thumbnail_cache` and `crash_dump`), each with a default action of its own.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SystemCachesConfig {
+    /// Enable these rules
+    pub enabled: bool,
+
+    /// Per-cache toggles
+    pub caches: SystemCacheKindsConfig,
+}
+
+impl Default for SystemCachesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            caches: SystemCacheKindsConfig::default(),
+        }
+    }
+}
+
+/// Temporary files and system caches that can be individually enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SystemCacheKindsConfig {
+    /// Contents of the user's `%TEMP%`
+    pub temp: bool,
+
+    /// Contents of `C:\Windows\Temp`
+    pub windows_temp: bool,
+
+    /// Chrome, Edge, Brave and Firefox caches
+    pub browser_caches: bool,
+
+    /// Explorer's `thumbcache_*.db` and `~/.cache/thumbnails/`
+    pub thumbnail_caches: bool,
+
+    /// `*.dmp` files and the contents of crash dump folders
+    pub crash_dumps: bool,
+}
+
+impl Default for SystemCacheKindsConfig {
+    fn default() -> Self {
+        Self {
+            temp: true,
+            windows_temp: true,
+            browser_caches: true,
+            thumbnail_caches: true,
+            crash_dumps: true,
+        }
+    }
+}
+
+impl SystemCacheKindsConfig {
+    /// Returns the names of all enabled caches.
+    pub fn enabled_names(&self) -> Vec<&'static str> {
+        [
+            ("temp", self.temp),
+            ("windows_temp", self.windows_temp),
+            ("browser_caches", self.browser_caches),
+            ("thumbnail_caches", self.thumbnail_caches),
+            ("crash_dumps", self.crash_dumps),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect()
+    }
+}
+
+/// Rapid directory growth rule configuration.
+///
+/// Compares directory sizes with the previous scan in the scan cache, so it
+/// only has an effect on scans using one (`--incremental` or `--scan-cache`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GrowthConfig {
+    /// Enable this rule
+    pub enabled: bool,
+
+    /// Flag directories that grew by at least this much; bare numbers are MB
+    #[serde(with = "byte_size::megabytes", alias = "min_growth_mb")]
+    pub min_growth: ByteSize,
+
+    /// Flag directories that grew by at least this percentage...
+    pub min_growth_percent: u64,
+
+    /// ...if they now hold at least this much; bare numbers are MB
+    #[serde(with = "byte_size::megabytes", alias = "min_size_mb")]
+    pub min_size: ByteSize,
+}
+
+impl Default for GrowthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_growth: ByteSize::from_mb(1024),
+            min_growth_percent: 50,
+            min_size: ByteSize::from_mb(100),
         }
     }
 }
@@ -129,8 +607,20 @@ pub struct BuildArtifactsConfig {
     /// Default action for flagged directories
     pub action: CleanupAction,
 
-    /// Custom patterns in addition to defaults
+    /// Glob patterns for additional artifact directory names, flagged
+    /// without a manifest check
     pub custom_patterns: Vec<String>,
+
+    /// Only flag artifact directories that have a matching manifest beside them
+    pub require_manifest: bool,
+
+    /// Plan the artifacts of projects touched within this many days (their
+    /// manifest or source files) for review instead of deletion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_project_days: Option<u64>,
+
+    /// Per-ecosystem toggles
+    pub ecosystems: ArtifactEcosystemsConfig,
 }
 
 impl Default for BuildArtifactsConfig {
@@ -139,11 +629,147 @@ impl Default for BuildArtifactsConfig {
             enabled: true,
             action: CleanupAction::Delete,
             custom_patterns: Vec::new(),
+            require_manifest: true,
+            active_project_days: None,
+            ecosystems: ArtifactEcosystemsConfig::default(),
+        }
+    }
+}
+
+impl From<&BuildArtifactsConfig> for crate::detector::BuildArtifactRule {
+    fn from(config: &BuildArtifactsConfig) -> Self {
+        Self::with_ecosystems(&config.ecosystems.enabled_names(), config.require_manifest)
+            .with_custom_patterns(&config.custom_patterns)
+            .with_active_project_days(config.active_project_days)
+    }
+}
+
+/// Build artifact ecosystems that can be individually enabled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ArtifactEcosystemsConfig {
+    /// Rust `target/` (requires `Cargo.toml`)
+    pub rust_target: bool,
+
+    /// Node.js `node_modules/` (requires `package.json`)
+    pub node_modules: bool,
+
+    /// Next.js `.next/` (requires `package.json`)
+    pub nextjs: bool,
+
+    /// Python `__pycache__/` and `.pytest_cache/`
+    pub python_pycache: bool,
+
+    /// .NET `bin/` and `obj/` (requires a project or solution file)
+    pub dotnet_bin_obj: bool,
+
+    /// Gradle `build/` and `.gradle/` (requires a Gradle build script)
+    pub gradle_build: bool,
+
+    /// Android NDK `.cxx/` and `.externalNativeBuild/` (requires a Gradle build script)
+    pub android_native: bool,
+
+    /// Xcode `DerivedData/` (in `~/Library/Developer/Xcode` or beside a project)
+    pub xcode_derived_data: bool,
+
+    /// Unity `Library/` and `Temp/` (always requires `ProjectSettings/`)
+    pub unity: bool,
+
+    /// JetBrains IDE caches (`~/.cache/JetBrains/`, `~/Library/Caches/JetBrains/`)
+    pub jetbrains_caches: bool,
+
+    /// Generic `build/` and `dist/` directories, anywhere (off by default:
+    /// there is no manifest to check them against)
+    pub generic_build: bool,
+}
+
+impl Default for ArtifactEcosystemsConfig {
+    fn default() -> Self {
+        Self {
+            rust_target: true,
+            node_modules: true,
+            nextjs: true,
+            python_pycache: true,
+            dotnet_bin_obj: true,
+            gradle_build: true,
+            android_native: true,
+            xcode_derived_data: true,
+            unity: true,
+            jetbrains_caches: true,
+            generic_build: false,
+        }
+    }
+}
+
+impl ArtifactEcosystemsConfig {
+    /// Returns the names of all enabled ecosystems.
+    pub fn enabled_names(&self) -> Vec<&'static str> {
+        [
+            ("rust_target", self.rust_target),
+            ("node_modules", self.node_modules),
+            ("nextjs", self.nextjs),
+            ("python_pycache", self.python_pycache),
+            ("dotnet_bin_obj", self.dotnet_bin_obj),
+            ("gradle_build", self.gradle_build),
+            ("android_native", self.android_native),
+            ("xcode_derived_data", self.xcode_derived_data),
+            ("unity", self.unity),
+            ("jetbrains_caches", self.jetbrains_caches),
+            ("generic_build", self.generic_build),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect()
+    }
+}
+
+/// Keep rule configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeepRuleConfig {
+    /// Rule name, recorded on every vetoed detection
+    pub name: String,
+
+    /// Glob patterns matched against each path and its ancestors
+    pub patterns: Vec<String>,
+}
+
+/// Archive directories, an in-filesystem veto on detections.
+///
+/// Everything inside a directory with one of `dir_names`, or holding the
+/// `marker` file, is kept whatever the rules flag: users can protect a tree
+/// by naming it `archive` or dropping an empty `.megamaid-keep` into it,
+/// without touching the configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Keep archive directories (default: true)
+    pub enabled: bool,
+
+    /// Directory name globs, matched ignoring case
+    pub dir_names: Vec<String>,
+
+    /// Name of the marker file; empty to not look for one
+    pub marker: String,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir_names: vec![
+                "archive".to_string(),
+                "keep".to_string(),
+                "do-not-delete*".to_string(),
+            ],
+            marker: ".megamaid-keep".to_string(),
         }
     }
 }
 
 /// Custom detection rule definition.
+///
+/// An entry is flagged when it meets every criterion given. Custom rules are
+/// checked before the built-in rules, in the order listed.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CustomRule {
     /// Rule name
@@ -164,14 +790,75 @@ pub struct CustomRule {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min_age_days: Option<u64>,
 
-    /// Minimum size in megabytes (optional)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub min_size_mb: Option<u64>,
+    /// Minimum size (optional); bare numbers are MB
+    #[serde(
+        default,
+        with = "byte_size::megabytes::option",
+        skip_serializing_if = "Option::is_none",
+        alias = "min_size_mb"
+    )]
+    pub min_size: Option<ByteSize>,
+
+    /// Expression combining rules and entry properties with AND, OR and NOT
+    /// (optional; see [`crate::detector::expr`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
 
     /// Action to apply
     pub action: CleanupAction,
 }
 
+impl CustomRule {
+    /// Combines the rule's criteria into a single expression.
+    pub fn expression(&self) -> Result<Expr, ExprError> {
+        let mut criteria = Vec::new();
+        if let Some(pattern) = &self.pattern {
+            criteria.push(Expr::glob(pattern)?);
+        }
+        if let Some(extensions) = &self.extensions {
+            criteria.extend(Expr::any(extensions.iter().map(|ext| Expr::ext(ext))));
+        }
+        if let Some(days) = self.min_age_days {
+            criteria.push(Expr::Age(
+                Comparison::GreaterOrEqual,
+                Duration::from_secs(days * 86_400),
+            ));
+        }
+        if let Some(size) = self.min_size {
+            criteria.push(Expr::Size(Comparison::GreaterOrEqual, size.as_bytes()));
+        }
+        if let Some(when) = &self.when {
+            criteria.push(Expr::parse(when)?);
+        }
+        Expr::all(criteria).ok_or(ExprError::Empty)
+    }
+}
+
+/// Pattern rule definition.
+///
+/// Flags entries whose path relative to the scan root matches one of the
+/// patterns (see [`crate::detector::pattern`]).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PatternRuleConfig {
+    /// Rule name
+    pub name: String,
+
+    /// Patterns matched against the whole relative path
+    pub patterns: Vec<String>,
+
+    /// Whether the patterns are globs or regular expressions
+    #[serde(default)]
+    pub syntax: PatternSyntax,
+
+    /// Action to apply
+    pub action: CleanupAction,
+
+    /// Reason template; `{pattern}`, `{path}` and `{name}` are replaced
+    /// (optional; default "Matches {pattern}")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
 /// Executor configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
@@ -193,6 +880,52 @@ pub struct ExecutorConfig {
 
     /// Default backup directory (None = no backup)
     pub backup_dir: Option<PathBuf>,
+
+    /// Plans older than this many days need `--allow-stale-plan` (None = no limit)
+    pub max_plan_age_days: Option<u64>,
+
+    /// Seconds before an operation is given up and logged as failed (None = no
+    /// limit)
+    pub operation_timeout_secs: Option<u64>,
+
+    /// Take a volume snapshot before deleting at least this much (None = only
+    /// with `--snapshot`); bare numbers are MB
+    #[serde(
+        default,
+        with = "byte_size::megabytes::option",
+        skip_serializing_if = "Option::is_none",
+        alias = "snapshot_threshold_mb"
+    )]
+    pub snapshot_threshold: Option<ByteSize>,
+
+    /// Shell command that takes the snapshot instead of the native mechanism;
+    /// `{volume}` and `{name}` are substituted and the command must print the
+    /// snapshot's directory as its last line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_command: Option<String>,
+
+    /// Desktop notice shown before `--yes` batch executions
+    pub last_chance: LastChanceConfig,
+
+    /// Operations running at once on each volume in parallel mode, by kind
+    /// of storage, with overrides for specific volumes
+    pub per_volume_concurrency: VolumeConcurrency,
+
+    /// When `execute --when-idle` counts the system as idle
+    pub idle: IdleConfig,
+
+    /// Account to delete as, `DOMAIN\user` (Windows only; see
+    /// [`crate::executor::run_as`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_as: Option<String>,
+
+    /// Keyring secret holding the password of the `run_as` account (None =
+    /// prompt for it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_as_secret: Option<String>,
+
+    /// Conditions batch executions must meet before they start
+    pub constraints: ConstraintsConfig,
 }
 
 impl Default for ExecutorConfig {
@@ -204,6 +937,144 @@ impl Default for ExecutorConfig {
             fail_fast: false,
             use_recycle_bin: false,
             backup_dir: None,
+            max_plan_age_days: Some(7),
+            operation_timeout_secs: None,
+            snapshot_threshold: None,
+            snapshot_command: None,
+            last_chance: LastChanceConfig::default(),
+            per_volume_concurrency: VolumeConcurrency::default(),
+            idle: IdleConfig::default(),
+            run_as: None,
+            run_as_secret: None,
+            constraints: ConstraintsConfig::default(),
+        }
+    }
+}
+
+/// Last-chance notice before unattended executions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LastChanceConfig {
+    /// Show the notice before `--yes` batch executions
+    pub enabled: bool,
+
+    /// Minutes the notice stays up before the run proceeds
+    pub delay_minutes: u64,
+
+    /// Minutes a postponed run waits before asking again
+    pub snooze_minutes: u64,
+
+    /// How many times a run can be postponed
+    pub max_snoozes: u32,
+
+    /// What to do when no user is logged on to see the notice
+    pub no_user: NoUserPolicy,
+}
+
+impl Default for LastChanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay_minutes: 10,
+            snooze_minutes: 30,
+            max_snoozes: 3,
+            no_user: NoUserPolicy::Abort,
+        }
+    }
+}
+
+impl LastChanceConfig {
+    /// Returns the notice timing.
+    pub fn to_last_chance(&self) -> LastChance {
+        LastChance {
+            delay: Duration::from_secs(self.delay_minutes * 60),
+            snooze: Duration::from_secs(self.snooze_minutes * 60),
+            max_snoozes: self.max_snoozes,
+            no_user: self.no_user,
+        }
+    }
+}
+
+/// Idle detection for executions that only run while nobody uses the system.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct IdleConfig {
+    /// Minutes without keyboard or mouse input
+    pub min_idle_minutes: u64,
+
+    /// Highest percentage of time the busiest disk may be busy before the
+    /// execution resumes
+    pub max_disk_busy_percent: u8,
+
+    /// Seconds between activity checks while waiting
+    pub poll_seconds: u64,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            min_idle_minutes: 5,
+            max_disk_busy_percent: 20,
+            poll_seconds: 10,
+        }
+    }
+}
+
+impl IdleConfig {
+    /// Returns the idle policy, with `min_idle_minutes` overridden if given.
+    pub fn to_policy(&self, min_idle_minutes: Option<u64>) -> IdlePolicy {
+        IdlePolicy {
+            min_idle: Duration::from_secs(min_idle_minutes.unwrap_or(self.min_idle_minutes) * 60),
+            max_disk_busy: f64::from(self.max_disk_busy_percent) / 100.0,
+            poll_interval: Duration::from_secs(self.poll_seconds),
+        }
+    }
+}
+
+/// Conditions checked before a batch execution starts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ConstraintsConfig {
+    /// Only start on AC power
+    pub require_ac_power: bool,
+
+    /// Only start once the disks have been quiet for this many minutes
+    /// (None = don't wait)
+    pub disk_idle_minutes: Option<u64>,
+
+    /// Highest percentage of time the busiest disk may be busy while the
+    /// disks are watched
+    pub max_disk_busy_percent: u8,
+
+    /// Never start during these local hours, `HH:MM-HH:MM` (None = any time)
+    pub active_hours: Option<String>,
+}
+
+impl Default for ConstraintsConfig {
+    fn default() -> Self {
+        Self {
+            require_ac_power: false,
+            disk_idle_minutes: None,
+            max_disk_busy_percent: 20,
+            active_hours: None,
+        }
+    }
+}
+
+impl ConstraintsConfig {
+    /// Returns the constraints to check.
+    ///
+    /// Invalid `active_hours` are left out; validation rejects them.
+    pub fn to_constraints(&self) -> RunConstraints {
+        RunConstraints {
+            require_ac_power: self.require_ac_power,
+            disk_idle: self
+                .disk_idle_minutes
+                .map(|minutes| Duration::from_secs(minutes * 60)),
+            max_disk_busy: f64::from(self.max_disk_busy_percent) / 100.0,
+            // Long enough samples that short bursts don't count as busy
+            poll_interval: Duration::from_secs(10),
+            active_hours: self.active_hours.as_deref().and_then(|h| h.parse().ok()),
         }
     }
 }
@@ -243,6 +1114,10 @@ impl ExecutorConfig {
             use_recycle_bin: self.use_recycle_bin,
             parallel: self.parallel,
             batch_size: self.batch_size,
+            operation_timeout: self
+                .operation_timeout_secs
+                .map(std::time::Duration::from_secs),
+            volume_concurrency: self.per_volume_concurrency.clone(),
         }
     }
 }
@@ -259,6 +1134,9 @@ pub struct OutputConfig {
 
     /// Default drift report filename
     pub drift_report: String,
+
+    /// Time zone of timestamps shown in reports (`utc` or `local`)
+    pub time_display: TimeDisplay,
 }
 
 impl Default for OutputConfig {
@@ -267,6 +1145,7 @@ impl Default for OutputConfig {
             plan_file: "cleanup-plan.yaml".to_string(),
             log_file: "execution-log.yaml".to_string(),
             drift_report: "drift-report.txt".to_string(),
+            time_display: TimeDisplay::Utc,
         }
     }
 }
@@ -283,6 +1162,12 @@ pub struct VerifierConfig {
 
     /// Stop verification on first drift detection
     pub fail_fast: bool,
+
+    /// Report entries the current user lacks permissions to delete
+    pub check_permissions: bool,
+
+    /// Maximum age of a verification record accepted by `execute --use-verification`
+    pub record_max_age_minutes: u64,
 }
 
 impl Default for VerifierConfig {
@@ -291,6 +1176,8 @@ impl Default for VerifierConfig {
             check_mtime: true,
             check_size: true,
             fail_fast: false,
+            check_permissions: true,
+            record_max_age_minutes: 60,
         }
     }
 }
@@ -301,10 +1188,49 @@ impl From<VerifierConfig> for crate::verifier::VerificationConfig {
             check_mtime: config.check_mtime,
             check_size: config.check_size,
             fail_fast: config.fail_fast,
+            check_permissions: config.check_permissions,
         }
     }
 }
 
+/// Diagnostics configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct DiagnosticsConfig {
+    /// Write a crash report file when megamaid panics
+    pub crash_reports: bool,
+
+    /// Directory for crash reports [default: megamaid/crash-reports in the
+    /// local data directory]
+    pub crash_report_dir: Option<PathBuf>,
+}
+
+/// Encryption configuration, see [`crate::planner::encryption`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    /// Encrypt new plans, transaction logs and scan caches
+    pub enabled: bool,
+
+    /// Name of the keyring secret holding the base64-encoded key (see
+    /// [`crate::config::secrets`])
+    pub key_secret: Option<String>,
+
+    /// File holding the base64-encoded 256-bit key, used without
+    /// `key_secret`; the `MEGAMAID_ENCRYPTION_KEY` environment variable takes
+    /// precedence over both
+    pub key_file: Option<PathBuf>,
+}
+
+impl DiagnosticsConfig {
+    /// Directory crash reports are written to and collected from.
+    pub fn crash_dir(&self) -> Option<PathBuf> {
+        self.crash_report_dir
+            .clone()
+            .or_else(crate::diagnostics::default_crash_dir)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -319,7 +1245,10 @@ mod tests {
         assert_eq!(config.scanner.max_depth, None);
 
         assert!(config.detector.rules.size_threshold.enabled);
-        assert_eq!(config.detector.rules.size_threshold.threshold_mb, 100);
+        assert_eq!(
+            config.detector.rules.size_threshold.threshold_mb,
+            ByteSize::from_mb(100)
+        );
         assert!(config.detector.rules.build_artifacts.enabled);
 
         assert!(!config.executor.parallel);
@@ -331,6 +1260,33 @@ mod tests {
 
         assert!(config.verifier.check_mtime);
         assert!(config.verifier.check_size);
+
+        assert!(!config.encryption.enabled);
+        assert_eq!(config.encryption.key_secret, None);
+        assert_eq!(config.encryption.key_file, None);
+    }
+
+    #[test]
+    fn test_constraints_config() {
+        let yaml = r#"
+executor:
+  constraints:
+    require_ac_power: true
+    disk_idle_minutes: 10
+    active_hours: "08:30-18:00"
+"#;
+
+        let config: MegamaidConfig = serde_yaml::from_str(yaml).unwrap();
+        let constraints = config.executor.constraints.to_constraints();
+        assert!(constraints.require_ac_power);
+        assert_eq!(constraints.disk_idle, Some(Duration::from_secs(600)));
+        assert_eq!(constraints.max_disk_busy, 0.2);
+        assert_eq!(constraints.active_hours.unwrap().to_string(), "08:30-18:00");
+
+        assert!(ExecutorConfig::default()
+            .constraints
+            .to_constraints()
+            .is_empty());
     }
 
     #[test]
@@ -367,7 +1323,10 @@ executor:
         assert_eq!(config.scanner.max_depth, Some(5));
         assert!(!config.scanner.skip_hidden);
         assert_eq!(config.scanner.thread_count, 4);
-        assert_eq!(config.detector.rules.size_threshold.threshold_mb, 200);
+        assert_eq!(
+            config.detector.rules.size_threshold.threshold_mb,
+            ByteSize::from_mb(200)
+        );
         assert!(config.executor.parallel);
         assert_eq!(config.executor.batch_size, 50);
     }
@@ -389,6 +1348,43 @@ action: delete
         assert_eq!(rule.pattern, Some("*.log".to_string()));
         assert_eq!(rule.min_age_days, Some(30));
         assert_eq!(rule.action, CleanupAction::Delete);
+        assert_eq!(rule.min_size, None);
+
+        // Older configs use min_size_mb
+        let rule: CustomRule =
+            serde_yaml::from_str("name: big\ndescription: Big\nmin_size_mb: 500\naction: review\n")
+                .unwrap();
+        assert_eq!(rule.min_size, Some(ByteSize::from_mb(500)));
+        let rule: CustomRule =
+            serde_yaml::from_str("name: big\ndescription: Big\nmin_size: 2GB\naction: review\n")
+                .unwrap();
+        assert_eq!(rule.min_size, Some(ByteSize::from_mb(2048)));
+    }
+
+    #[test]
+    fn test_custom_rule_when_expression() {
+        let yaml = r#"
+name: "stale_large"
+description: "Large files untouched for half a year"
+extensions: [".iso", ".zip"]
+when: "large_file AND age > 180d AND NOT under('D:\\Archive')"
+action: delete
+"#;
+
+        let rule: CustomRule = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(
+            rule.when.as_deref(),
+            Some("large_file AND age > 180d AND NOT under('D:\\Archive')")
+        );
+        assert_eq!(rule.expression().unwrap().rule_names(), vec!["large_file"]);
+
+        let empty = CustomRule {
+            when: None,
+            extensions: None,
+            ..rule
+        };
+        assert!(matches!(empty.expression(), Err(ExprError::Empty)));
     }
 
     #[test]
@@ -406,6 +1402,136 @@ scanner:
         assert_eq!(config.executor.batch_size, 100); // default
     }
 
+    #[test]
+    fn test_remote_scan_config() {
+        let yaml = r#"
+scanner:
+  remote:
+    mode: always
+    skip_dir_sizes: true
+"#;
+
+        let config: MegamaidConfig = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(
+            config.scanner.remote.mode,
+            crate::scanner::RemoteMode::Always
+        );
+        assert!(config.scanner.remote.skip_dir_sizes);
+        assert_eq!(config.scanner.remote.max_concurrency, 4); // default
+
+        let scan_config: crate::scanner::ScanConfig = config.scanner.into();
+        assert!(scan_config.remote.is_active(std::path::Path::new(".")));
+    }
+
+    #[test]
+    fn test_dir_size_config() {
+        let yaml = r#"
+scanner:
+  dir_size:
+    parallel_depth: 0
+"#;
+
+        let config: MegamaidConfig = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(config.scanner.dir_size.parallel_depth, 0);
+        assert_eq!(config.scanner.dir_size.min_parallel_entries, 32); // default
+    }
+
+    #[test]
+    fn test_scan_backend_config() {
+        let config: MegamaidConfig = serde_yaml::from_str("scanner:\n  backend: mft\n").unwrap();
+        assert_eq!(config.scanner.backend, ScanBackend::Mft);
+        assert_eq!(MegamaidConfig::default().scanner.backend, ScanBackend::Walk);
+    }
+
+    #[test]
+    fn test_idle_config() {
+        let yaml = r#"
+executor:
+  idle:
+    min_idle_minutes: 15
+    max_disk_busy_percent: 50
+"#;
+
+        let config: MegamaidConfig = serde_yaml::from_str(yaml).unwrap();
+        let idle = &config.executor.idle;
+        assert_eq!(idle.poll_seconds, 10); // default
+
+        let policy = idle.to_policy(None);
+        assert_eq!(policy.min_idle, Duration::from_secs(15 * 60));
+        assert_eq!(policy.max_disk_busy, 0.5);
+        assert_eq!(idle.to_policy(Some(1)).min_idle, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_snapshot_threshold_keeps_legacy_name() {
+        let config: ExecutorConfig = serde_yaml::from_str("snapshot_threshold: 10GB\n").unwrap();
+        assert_eq!(
+            config.snapshot_threshold,
+            Some(ByteSize::from_mb(10 * 1024))
+        );
+
+        let config: ExecutorConfig = serde_yaml::from_str("snapshot_threshold_mb: 512\n").unwrap();
+        assert_eq!(config.snapshot_threshold, Some(ByteSize::from_mb(512)));
+    }
+
+    #[test]
+    fn test_growth_config() {
+        let yaml = r#"
+detector:
+  rules:
+    growth:
+      enabled: true
+      min_growth: "2GB"
+      # Older configs use the _mb names
+      min_size_mb: 500
+"#;
+
+        let config: MegamaidConfig = serde_yaml::from_str(yaml).unwrap();
+        let growth = &config.detector.rules.growth;
+        assert!(growth.enabled);
+        assert_eq!(growth.min_growth, ByteSize::from_mb(2048));
+        assert_eq!(growth.min_size, ByteSize::from_mb(500));
+        assert_eq!(growth.min_growth_percent, 50); // default
+        assert!(!MegamaidConfig::default().detector.rules.growth.enabled);
+
+        let markers = &config.detector.rules.delete_markers;
+        assert!(markers.enabled);
+        assert_eq!(markers.marker, ".megamaid-delete");
+
+        let duplicates = &config.detector.rules.duplicates;
+        assert!(!duplicates.enabled);
+        assert_eq!(duplicates.min_size, ByteSize::from_mb(1));
+
+        let empty = &config.detector.rules.empty_entries;
+        assert!(!empty.enabled);
+        assert!(empty.keep_names.iter().any(|name| name == ".gitkeep"));
+    }
+
+    #[test]
+    fn test_scanner_exclude() {
+        let yaml = r#"
+scanner:
+  exclude:
+    - 'C:\Windows'
+    - node_modules
+"#;
+
+        let config: MegamaidConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.scanner.exclude, vec!["C:\\Windows", "node_modules"]);
+        assert!(MegamaidConfig::default().scanner.exclude.is_empty());
+
+        assert!(!config.scanner.respect_ignore_files);
+        assert!(!config.scanner.same_filesystem);
+        assert!(!config.scanner.collect_owner);
+        assert!(!config.scanner.largest_first);
+        assert_eq!(config.scanner.max_iops, 0);
+
+        let scan_config: crate::scanner::ScanConfig = config.scanner.into();
+        assert_eq!(scan_config.exclude_globs.len(), 2);
+    }
+
     #[test]
     fn test_execution_mode_serialization() {
         let mode = ExecutionModeConfig::DryRun;
@@ -441,4 +1567,173 @@ action: review
         let config: SizeThresholdConfig = serde_yaml::from_str(yaml).unwrap();
         assert_eq!(config.action, CleanupAction::Review);
     }
+
+    #[test]
+    fn test_size_threshold_applies_to() {
+        assert_eq!(SizeThresholdConfig::default().applies_to, EntryKinds::ALL);
+
+        let yaml = r#"
+threshold_mb: 100
+applies_to:
+  directories: false
+  symlinks: false
+"#;
+
+        let config: SizeThresholdConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.applies_to, EntryKinds::FILES);
+    }
+
+    #[test]
+    fn test_keep_rules_parse() {
+        let yaml = r#"
+keep_rules:
+  - name: clients
+    patterns: ["D:/Clients", "**/do-not-clean"]
+"#;
+
+        let config: DetectorConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.keep_rules.len(), 1);
+        assert_eq!(config.keep_rules[0].name, "clients");
+        assert_eq!(config.keep_rules[0].patterns.len(), 2);
+        assert_eq!(config.max_delete_fraction, 0.6);
+
+        let config: DetectorConfig = serde_yaml::from_str("max_delete_fraction: 0.9").unwrap();
+        assert_eq!(config.max_delete_fraction, 0.9);
+        assert!(config.archive.enabled);
+        assert_eq!(config.archive.marker, ".megamaid-keep");
+
+        let config: DetectorConfig =
+            serde_yaml::from_str("archive:\n  dir_names: [\"Archiv\"]").unwrap();
+        assert_eq!(config.archive.dir_names, vec!["Archiv"]);
+        assert!(config.archive.enabled);
+    }
+
+    #[test]
+    fn test_artifact_ecosystem_toggles() {
+        let yaml = r#"
+require_manifest: false
+ecosystems:
+  node_modules: false
+  generic_build: false
+"#;
+
+        let config: BuildArtifactsConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(!config.require_manifest);
+        let names = config.ecosystems.enabled_names();
+        assert!(names.contains(&"rust_target"));
+        assert!(!names.contains(&"node_modules"));
+        assert!(!names.contains(&"generic_build"));
+        assert_eq!(names.len(), 9);
+    }
+
+    #[test]
+    fn test_dev_cache_toggles() {
+        let yaml = r#"
+caches:
+  gradle: false
+"#;
+
+        let config: DevCachesConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(config.enabled);
+        let names = config.caches.enabled_names();
+        assert!(names.contains(&"nuget"));
+        assert!(!names.contains(&"gradle"));
+        assert_eq!(names.len(), 4);
+    }
+
+    #[test]
+    fn test_system_cache_toggles() {
+        let yaml = r#"
+caches:
+  temp: false
+  crash_dumps: false
+"#;
+
+        let config: SystemCachesConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(config.enabled);
+        assert_eq!(
+            config.caches.enabled_names(),
+            vec!["windows_temp", "browser_caches", "thumbnail_caches"]
+        );
+    }
+
+    #[test]
+    fn test_per_volume_concurrency() {
+        let yaml = r#"
+per_volume_concurrency:
+  rotational: 2
+  volumes:
+    /mnt/archive: 1
+"#;
+
+        let config: ExecutorConfig = serde_yaml::from_str(yaml).unwrap();
+        let concurrency = config.to_execution_config(None).volume_concurrency;
+        assert_eq!(concurrency.rotational, 2);
+        assert_eq!(
+            concurrency.solid_state, 16,
+            "unset kinds keep their default"
+        );
+        assert_eq!(
+            concurrency
+                .volumes
+                .get(std::path::Path::new("/mnt/archive")),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_size_threshold_accepts_human_sizes() {
+        let yaml = r#"
+threshold_mb: 1.5GiB
+file_threshold: "500MB"
+"#;
+        let config: SizeThresholdConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.threshold_mb, ByteSize::from_mb(1536));
+        assert_eq!(config.effective_file_threshold(), ByteSize::from_mb(500));
+
+        let err = serde_yaml::from_str::<SizeThresholdConfig>("threshold_mb: 1,5GB\n").unwrap_err();
+        assert!(err.to_string().contains("ambiguous"));
+    }
+
+    #[test]
+    fn test_size_threshold_legacy_migration() {
+        // A single legacy threshold applies to both files and directories
+        let yaml = "threshold_mb: 250\n";
+        let config: SizeThresholdConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.effective_file_threshold(), ByteSize::from_mb(250));
+        assert_eq!(
+            config.effective_directory_threshold(),
+            ByteSize::from_mb(250)
+        );
+
+        // Older configs name the overrides with an _mb suffix
+        let yaml = r#"
+threshold_mb: 250
+directory_threshold_mb: 2048
+"#;
+        let config: SizeThresholdConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.effective_file_threshold(), ByteSize::from_mb(250));
+        assert_eq!(
+            config.effective_directory_threshold(),
+            ByteSize::from_mb(2048)
+        );
+    }
+
+    #[test]
+    fn test_alternate_streams_threshold() {
+        let config: AlternateStreamsConfig = serde_yaml::from_str(
+            "threshold: 1MB
+",
+        )
+        .unwrap();
+        assert_eq!(config.threshold, ByteSize::from_mb(1));
+
+        // Older configs use threshold_mb
+        let config: AlternateStreamsConfig = serde_yaml::from_str(
+            "threshold_mb: 50
+",
+        )
+        .unwrap();
+        assert_eq!(config.threshold, ByteSize::from_mb(50));
+    }
 }
@@ -0,0 +1,228 @@
+//! Secrets kept in the OS keyring instead of the configuration file.
+//!
+//! Credentials in plaintext YAML end up in backups, dotfile repositories and
+//! bug reports. Settings that take a secret can instead name one stored with
+//! `megamaid secrets set`, which megamaid looks up when it needs it. Secrets
+//! are stored in the Windows Credential Manager as generic credentials named
+//! `megamaid:<name>`, and elsewhere in the Secret Service (GNOME Keyring,
+//! KWallet) through libsecret's `secret-tool`, under the attributes
+//! `service=megamaid name=<name>`.
+//!
+//! `encryption.key_secret` names the secret holding the encryption key.
+
+use std::io;
+use thiserror::Error;
+
+/// Errors accessing the keyring.
+#[derive(Debug, Error)]
+pub enum SecretError {
+    /// Secret names are letters, digits, `.`, `_` and `-`
+    #[error("invalid secret name '{0}': use letters, digits, '.', '_' and '-'")]
+    InvalidName(String),
+
+    /// No keyring is available on this system
+    #[error("no keyring available: {0}")]
+    Unavailable(String),
+
+    /// The keyring refused the operation
+    #[error("keyring error: {0}")]
+    Keyring(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Stores `value` as secret `name`, replacing any previous value.
+pub fn set_secret(name: &str, value: &str) -> Result<(), SecretError> {
+    validate_name(name)?;
+    platform::set(name, value)
+}
+
+/// Returns secret `name`, or `None` if it isn't stored.
+pub fn get_secret(name: &str) -> Result<Option<String>, SecretError> {
+    validate_name(name)?;
+    platform::get(name)
+}
+
+/// Removes secret `name`; returns false if it wasn't stored.
+pub fn remove_secret(name: &str) -> Result<bool, SecretError> {
+    validate_name(name)?;
+    platform::remove(name)
+}
+
+fn validate_name(name: &str) -> Result<(), SecretError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(SecretError::InvalidName(name.to_string()))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::SecretError;
+    use std::io;
+    use windows_sys::Win32::Foundation::ERROR_NOT_FOUND;
+    use windows_sys::Win32::Security::Credentials::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE,
+        CRED_TYPE_GENERIC,
+    };
+
+    fn target(name: &str) -> Vec<u16> {
+        format!("megamaid:{}", name)
+            .encode_utf16()
+            .chain(Some(0))
+            .collect()
+    }
+
+    fn keyring_error() -> SecretError {
+        SecretError::Keyring(io::Error::last_os_error().to_string())
+    }
+
+    fn not_found() -> bool {
+        io::Error::last_os_error().raw_os_error() == Some(ERROR_NOT_FOUND as i32)
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), SecretError> {
+        let mut target = target(name);
+        // SAFETY: every pointer in the credential outlives the call, and
+        // unused fields are null or zero
+        let mut credential: CREDENTIALW = unsafe { std::mem::zeroed() };
+        credential.Type = CRED_TYPE_GENERIC;
+        credential.TargetName = target.as_mut_ptr();
+        credential.CredentialBlobSize = value.len() as u32;
+        credential.CredentialBlob = value.as_ptr() as *mut u8;
+        credential.Persist = CRED_PERSIST_LOCAL_MACHINE;
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            return Err(keyring_error());
+        }
+        Ok(())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>, SecretError> {
+        let target = target(name);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        // SAFETY: the target is NUL-terminated; on success the credential
+        // is valid until freed below
+        if unsafe { CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) } == 0 {
+            return if not_found() {
+                Ok(None)
+            } else {
+                Err(keyring_error())
+            };
+        }
+        let value = unsafe {
+            let credential = &*credential;
+            std::slice::from_raw_parts(
+                credential.CredentialBlob,
+                credential.CredentialBlobSize as usize,
+            )
+            .to_vec()
+        };
+        unsafe { CredFree(credential as *const _) };
+        String::from_utf8(value)
+            .map(Some)
+            .map_err(|_| SecretError::Keyring(format!("secret '{}' is not text", name)))
+    }
+
+    pub fn remove(name: &str) -> Result<bool, SecretError> {
+        let target = target(name);
+        // SAFETY: the target is NUL-terminated
+        if unsafe { CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) } == 0 {
+            return if not_found() {
+                Ok(false)
+            } else {
+                Err(keyring_error())
+            };
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::SecretError;
+    use std::io::{self, Write};
+    use std::process::{Command, Output, Stdio};
+
+    /// Runs `secret-tool` with `args`, passing `input` on stdin.
+    fn secret_tool(args: &[&str], input: Option<&str>) -> Result<Output, SecretError> {
+        let mut child = Command::new("secret-tool")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => {
+                    SecretError::Unavailable("secret-tool (libsecret) is not installed".to_string())
+                }
+                _ => SecretError::Io(e),
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.unwrap_or_default().as_bytes())?;
+        }
+        Ok(child.wait_with_output()?)
+    }
+
+    fn keyring_error(output: &Output) -> SecretError {
+        SecretError::Keyring(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), SecretError> {
+        let label = format!("megamaid: {}", name);
+        let args = [
+            "store", "--label", &label, "service", "megamaid", "name", name,
+        ];
+        let output = secret_tool(&args, Some(value))?;
+        if !output.status.success() {
+            return Err(keyring_error(&output));
+        }
+        Ok(())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>, SecretError> {
+        let output = secret_tool(&["lookup", "service", "megamaid", "name", name], None)?;
+        // A missing secret fails without a message
+        if !output.status.success() {
+            return if output.stderr.is_empty() {
+                Ok(None)
+            } else {
+                Err(keyring_error(&output))
+            };
+        }
+        String::from_utf8(output.stdout)
+            .map(Some)
+            .map_err(|_| SecretError::Keyring(format!("secret '{}' is not text", name)))
+    }
+
+    pub fn remove(name: &str) -> Result<bool, SecretError> {
+        // Clearing succeeds whether or not the secret exists
+        if get(name)?.is_none() {
+            return Ok(false);
+        }
+        let output = secret_tool(&["clear", "service", "megamaid", "name", name], None)?;
+        if !output.status.success() {
+            return Err(keyring_error(&output));
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_names_are_validated() {
+        assert!(validate_name("smtp.password").is_ok());
+        assert!(validate_name("backup-key_2").is_ok());
+        for name in ["", "has space", "a/b", "ключ"] {
+            assert!(matches!(get_secret(name), Err(SecretError::InvalidName(_))));
+        }
+    }
+}
@@ -1,6 +1,9 @@
 //! Configuration validation.
 
-use super::schema::{CustomRule, MegamaidConfig};
+use super::schema::{CustomRule, KeepRuleConfig, MegamaidConfig, PatternRuleConfig};
+use crate::detector::{PatternRule, BUILTIN_RULES};
+use crate::models::ByteSize;
+use crate::scanner::ExcludeSet;
 use anyhow::{Context, Result};
 
 /// Validates a configuration.
@@ -9,6 +12,7 @@ pub fn validate_config(config: &MegamaidConfig) -> Result<()> {
     validate_detector(&config.detector)?;
     validate_executor(&config.executor)?;
     validate_output(&config.output)?;
+    validate_verifier(&config.verifier)?;
     Ok(())
 }
 
@@ -29,25 +33,132 @@ fn validate_scanner(scanner: &super::schema::ScannerConfig) -> Result<()> {
         );
     }
 
+    if scanner.remote.max_concurrency == 0 {
+        anyhow::bail!("scanner.remote.max_concurrency must be greater than 0");
+    }
+
+    for pattern in &scanner.exclude {
+        ExcludeSet::new(&[pattern])
+            .with_context(|| format!("Invalid scanner.exclude pattern '{}'", pattern))?;
+    }
+
     Ok(())
 }
 
 fn validate_detector(detector: &super::schema::DetectorConfig) -> Result<()> {
-    // Validate size threshold
-    if detector.rules.size_threshold.threshold_mb == 0 {
-        anyhow::bail!("detector.rules.size_threshold.threshold_mb must be greater than 0");
+    // Validate size thresholds
+    let size_threshold = &detector.rules.size_threshold;
+    validate_threshold_mb("threshold_mb", size_threshold.threshold_mb)?;
+    if let Some(size) = size_threshold.file_threshold {
+        validate_threshold_mb("file_threshold", size)?;
+    }
+    if let Some(size) = size_threshold.directory_threshold {
+        validate_threshold_mb("directory_threshold", size)?;
     }
 
-    if detector.rules.size_threshold.threshold_mb > 1_000_000 {
+    if detector.rules.size_threshold.applies_to.is_empty() {
         anyhow::bail!(
-            "detector.rules.size_threshold.threshold_mb cannot exceed 1,000,000 MB (got {})",
-            detector.rules.size_threshold.threshold_mb
+            "detector.rules.size_threshold.applies_to must include at least one entry kind"
         );
     }
 
-    // Validate custom rules
+    if detector.rules.alternate_streams.threshold.as_bytes() == 0 {
+        anyhow::bail!("detector.rules.alternate_streams.threshold must be greater than 0");
+    }
+
+    let marker = &detector.rules.delete_markers.marker;
+    if marker.is_empty() || marker.contains(['/', '\\']) {
+        anyhow::bail!("detector.rules.delete_markers.marker must be a file name");
+    }
+
+    if detector.rules.orphaned_envs.stale_days == 0 {
+        anyhow::bail!("detector.rules.orphaned_envs.stale_days must be greater than 0");
+    }
+
+    for pattern in &detector.rules.build_artifacts.custom_patterns {
+        glob::Pattern::new(pattern.trim_end_matches(['/', '\\'])).with_context(|| {
+            format!(
+                "Invalid detector.rules.build_artifacts.custom_patterns pattern '{}'",
+                pattern
+            )
+        })?;
+    }
+
+    if detector.rules.build_artifacts.active_project_days == Some(0) {
+        anyhow::bail!("detector.rules.build_artifacts.active_project_days must be greater than 0");
+    }
+
+    if detector.rules.stale_repos.stale_days == 0 {
+        anyhow::bail!("detector.rules.stale_repos.stale_days must be greater than 0");
+    }
+
+    let growth = &detector.rules.growth;
+    if growth.min_growth.as_bytes() == 0 {
+        anyhow::bail!("detector.rules.growth.min_growth must be greater than 0");
+    }
+    if growth.min_growth_percent == 0 {
+        anyhow::bail!("detector.rules.growth.min_growth_percent must be greater than 0");
+    }
+
+    if !(0.0..=1.0).contains(&detector.max_delete_fraction) {
+        anyhow::bail!("detector.max_delete_fraction must be between 0.0 and 1.0");
+    }
+
+    // Validate pattern rules, which custom rule expressions can reference
+    let mut known: Vec<&str> = BUILTIN_RULES.to_vec();
+    for rule in &detector.pattern_rules {
+        validate_pattern_rule(rule).context(format!("Invalid pattern rule: {}", rule.name))?;
+        if known.contains(&rule.name.as_str()) {
+            anyhow::bail!("Pattern rule name '{}' is already taken", rule.name);
+        }
+        known.push(&rule.name);
+    }
+
+    // Validate custom rules; expressions can reference built-in rules,
+    // pattern rules and custom rules listed earlier
     for rule in &detector.custom_rules {
         validate_custom_rule(rule).context(format!("Invalid custom rule: {}", rule.name))?;
+        if known.contains(&rule.name.as_str()) {
+            anyhow::bail!("Custom rule name '{}' is already taken", rule.name);
+        }
+        if let Ok(expr) = rule.expression() {
+            if let Some(unknown) = expr.rule_names().into_iter().find(|n| !known.contains(n)) {
+                anyhow::bail!(
+                    "Custom rule '{}' references unknown rule '{}' (only built-in rules, pattern rules and custom rules listed before it can be referenced)",
+                    rule.name,
+                    unknown
+                );
+            }
+        }
+        known.push(&rule.name);
+    }
+
+    // Validate keep rules
+    for rule in &detector.keep_rules {
+        validate_keep_rule(rule).context(format!("Invalid keep rule: {}", rule.name))?;
+    }
+    for name in &detector.archive.dir_names {
+        glob::Pattern::new(name)
+            .with_context(|| format!("Invalid detector.archive.dir_names pattern '{}'", name))?;
+    }
+
+    Ok(())
+}
+
+fn validate_threshold_mb(field: &str, value: ByteSize) -> Result<()> {
+    if value.as_bytes() == 0 {
+        anyhow::bail!(
+            "detector.rules.size_threshold.{} must be greater than 0",
+            field
+        );
+    }
+
+    if value > ByteSize::from_mb(1_000_000) {
+        anyhow::bail!(
+            "detector.rules.size_threshold.{} cannot exceed 1,000,000 MB (got {})",
+            field,
+            value
+        );
     }
 
     Ok(())
@@ -66,10 +177,11 @@ fn validate_custom_rule(rule: &CustomRule) -> Result<()> {
     if rule.pattern.is_none()
         && rule.extensions.is_none()
         && rule.min_age_days.is_none()
-        && rule.min_size_mb.is_none()
+        && rule.min_size.is_none()
+        && rule.when.is_none()
     {
         anyhow::bail!(
-            "Custom rule '{}' must specify at least one matching criterion (pattern, extensions, min_age_days, or min_size_mb)",
+            "Custom rule '{}' must specify at least one matching criterion (pattern, extensions, min_age_days, min_size, or when)",
             rule.name
         );
     }
@@ -85,10 +197,10 @@ fn validate_custom_rule(rule: &CustomRule) -> Result<()> {
         }
     }
 
-    // Validate min_size_mb
-    if let Some(size) = rule.min_size_mb {
-        if size == 0 {
-            anyhow::bail!("min_size_mb must be greater than 0");
+    // Validate min_size
+    if let Some(size) = rule.min_size {
+        if size.as_bytes() == 0 {
+            anyhow::bail!("min_size must be greater than 0");
         }
     }
 
@@ -104,10 +216,52 @@ fn validate_custom_rule(rule: &CustomRule) -> Result<()> {
         }
     }
 
+    // Validate the pattern and expression
+    rule.expression()?;
+
+    Ok(())
+}
+
+fn validate_pattern_rule(rule: &PatternRuleConfig) -> Result<()> {
+    if rule.name.is_empty() {
+        anyhow::bail!("Pattern rule name cannot be empty");
+    }
+    PatternRule::new(&rule.name, &rule.patterns, rule.syntax)?;
+    Ok(())
+}
+
+fn validate_keep_rule(rule: &KeepRuleConfig) -> Result<()> {
+    if rule.name.is_empty() {
+        anyhow::bail!("Keep rule name cannot be empty");
+    }
+
+    if rule.patterns.is_empty() {
+        anyhow::bail!(
+            "Keep rule '{}' must specify at least one pattern",
+            rule.name
+        );
+    }
+
+    for pattern in &rule.patterns {
+        glob::Pattern::new(pattern)
+            .with_context(|| format!("Invalid keep pattern '{}'", pattern))?;
+    }
+
     Ok(())
 }
 
 fn validate_executor(executor: &super::schema::ExecutorConfig) -> Result<()> {
+    if executor.idle.max_disk_busy_percent > 100 {
+        anyhow::bail!(
+            "executor.idle.max_disk_busy_percent cannot exceed 100 (got {})",
+            executor.idle.max_disk_busy_percent
+        );
+    }
+
+    if executor.idle.poll_seconds == 0 {
+        anyhow::bail!("executor.idle.poll_seconds must be greater than 0");
+    }
+
     if executor.batch_size == 0 {
         anyhow::bail!("executor.batch_size must be greater than 0");
     }
@@ -119,6 +273,49 @@ fn validate_executor(executor: &super::schema::ExecutorConfig) -> Result<()> {
         );
     }
 
+    let constraints = &executor.constraints;
+    if constraints.disk_idle_minutes == Some(0) {
+        anyhow::bail!(
+            "executor.constraints.disk_idle_minutes must be greater than 0 (use null to not wait)"
+        );
+    }
+
+    if constraints.max_disk_busy_percent > 100 {
+        anyhow::bail!(
+            "executor.constraints.max_disk_busy_percent cannot exceed 100 (got {})",
+            constraints.max_disk_busy_percent
+        );
+    }
+
+    if let Some(hours) = &constraints.active_hours {
+        if let Err(e) = hours.parse::<crate::executor::ActiveHours>() {
+            anyhow::bail!("executor.constraints.active_hours: {}", e);
+        }
+    }
+
+    if executor.max_plan_age_days == Some(0) {
+        anyhow::bail!("executor.max_plan_age_days must be greater than 0 (use null for no limit)");
+    }
+
+    if executor.operation_timeout_secs == Some(0) {
+        anyhow::bail!(
+            "executor.operation_timeout_secs must be greater than 0 (use null for no limit)"
+        );
+    }
+
+    let last_chance = &executor.last_chance;
+    if last_chance.enabled && (last_chance.delay_minutes == 0 || last_chance.snooze_minutes == 0) {
+        anyhow::bail!(
+            "executor.last_chance.delay_minutes and snooze_minutes must be greater than 0"
+        );
+    }
+
+    if let Some(account) = &executor.run_as {
+        account
+            .parse::<crate::executor::Account>()
+            .context("Invalid executor.run_as")?;
+    }
+
     Ok(())
 }
 
@@ -138,10 +335,19 @@ fn validate_output(output: &super::schema::OutputConfig) -> Result<()> {
     Ok(())
 }
 
+fn validate_verifier(verifier: &super::schema::VerifierConfig) -> Result<()> {
+    if verifier.record_max_age_minutes == 0 {
+        anyhow::bail!("verifier.record_max_age_minutes must be greater than 0");
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::schema::*;
+    use crate::detector::PatternSyntax;
     use crate::models::CleanupAction;
 
     #[test]
@@ -183,7 +389,7 @@ mod tests {
     #[test]
     fn test_validate_size_threshold_zero() {
         let mut config = MegamaidConfig::default();
-        config.detector.rules.size_threshold.threshold_mb = 0;
+        config.detector.rules.size_threshold.threshold_mb = ByteSize::from_mb(0);
 
         let result = validate_config(&config);
         assert!(result.is_err());
@@ -196,13 +402,115 @@ mod tests {
     #[test]
     fn test_validate_size_threshold_too_large() {
         let mut config = MegamaidConfig::default();
-        config.detector.rules.size_threshold.threshold_mb = 2_000_000;
+        config.detector.rules.size_threshold.threshold_mb = ByteSize::from_mb(2_000_000);
 
         let result = validate_config(&config);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("1,000,000"));
     }
 
+    #[test]
+    fn test_validate_directory_threshold_zero() {
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.size_threshold.directory_threshold = Some(ByteSize::from_mb(0));
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("directory_threshold must be greater than 0"));
+    }
+
+    #[test]
+    fn test_validate_size_threshold_applies_to_empty() {
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.size_threshold.applies_to = crate::detector::EntryKinds {
+            files: false,
+            directories: false,
+            symlinks: false,
+        };
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("at least one entry kind"));
+    }
+
+    #[test]
+    fn test_validate_growth_percent_zero() {
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.growth.min_growth_percent = 0;
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("min_growth_percent"));
+    }
+
+    #[test]
+    fn test_validate_orphaned_envs_stale_days_zero() {
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.orphaned_envs.stale_days = 0;
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("stale_days"));
+    }
+
+    #[test]
+    fn test_validate_build_artifacts_invalid_pattern() {
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.build_artifacts.custom_patterns = vec!["[".to_string()];
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("custom_patterns"));
+    }
+
+    #[test]
+    fn test_validate_active_project_days_zero() {
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.build_artifacts.active_project_days = Some(0);
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("active_project_days"));
+    }
+
+    #[test]
+    fn test_validate_stale_repos_stale_days_zero() {
+        let mut config = MegamaidConfig::default();
+        config.detector.rules.stale_repos.stale_days = 0;
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("stale_repos.stale_days"));
+    }
+
+    #[test]
+    fn test_validate_max_delete_fraction_out_of_range() {
+        let mut config = MegamaidConfig::default();
+        config.detector.max_delete_fraction = 60.0;
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("max_delete_fraction"));
+    }
+
     #[test]
     fn test_validate_batch_size_zero() {
         let mut config = MegamaidConfig::default();
@@ -226,6 +534,152 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("10000"));
     }
 
+    #[test]
+    fn test_validate_constraints() {
+        let mut config = MegamaidConfig::default();
+        config.executor.constraints.disk_idle_minutes = Some(0);
+        let result = validate_config(&config);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("disk_idle_minutes"));
+
+        config.executor.constraints.disk_idle_minutes = Some(10);
+        config.executor.constraints.max_disk_busy_percent = 150;
+        let result = validate_config(&config);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("max_disk_busy_percent"));
+
+        config.executor.constraints.max_disk_busy_percent = 20;
+        config.executor.constraints.active_hours = Some("9-17".to_string());
+        let result = validate_config(&config);
+        assert!(result.unwrap_err().to_string().contains("active_hours"));
+
+        config.executor.constraints.active_hours = Some("22:00-06:00".to_string());
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_validate_max_plan_age_zero() {
+        let mut config = MegamaidConfig::default();
+        config.executor.max_plan_age_days = Some(0);
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("max_plan_age_days"));
+
+        config.executor.max_plan_age_days = None;
+        assert!(validate_config(&config).is_ok());
+    }
+
+    #[test]
+    fn test_validate_remote_max_concurrency_zero() {
+        let mut config = MegamaidConfig::default();
+        config.scanner.remote.max_concurrency = 0;
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("remote.max_concurrency"));
+    }
+
+    #[test]
+    fn test_validate_idle_poll_seconds_zero() {
+        let mut config = MegamaidConfig::default();
+        config.executor.idle.poll_seconds = 0;
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("idle.poll_seconds"));
+    }
+
+    #[test]
+    fn test_validate_scanner_exclude_invalid_pattern() {
+        let mut config = MegamaidConfig::default();
+        config.scanner.exclude = vec!["node_modules".to_string(), "[".to_string()];
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("scanner.exclude"));
+    }
+
+    #[test]
+    fn test_validate_operation_timeout_zero() {
+        let mut config = MegamaidConfig::default();
+        config.executor.operation_timeout_secs = Some(0);
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("operation_timeout_secs"));
+
+        config.executor.operation_timeout_secs = Some(30);
+        assert!(validate_config(&config).is_ok());
+        assert_eq!(
+            config.executor.to_execution_config(None).operation_timeout,
+            Some(std::time::Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn test_validate_last_chance_delay_zero() {
+        let mut config = MegamaidConfig::default();
+        config.executor.last_chance.delay_minutes = 0;
+        assert!(validate_config(&config).is_ok());
+
+        config.executor.last_chance.enabled = true;
+        let result = validate_config(&config);
+        assert!(result.unwrap_err().to_string().contains("last_chance"));
+    }
+
+    #[test]
+    fn test_validate_run_as_account() {
+        let mut config = MegamaidConfig::default();
+        config.executor.run_as = Some("CORP\\jdoe".to_string());
+        assert!(validate_config(&config).is_ok());
+
+        config.executor.run_as = Some("CORP\\".to_string());
+        let result = validate_config(&config);
+        assert!(format!("{:#}", result.unwrap_err()).contains("executor.run_as"));
+    }
+
+    #[test]
+    fn test_validate_pattern_rules() {
+        let rule = |name: &str, pattern: &str, syntax| PatternRuleConfig {
+            name: name.to_string(),
+            patterns: vec![pattern.to_string()],
+            syntax,
+            action: CleanupAction::Delete,
+            reason: None,
+        };
+        let validate = |rules: Vec<PatternRuleConfig>| {
+            let mut config = MegamaidConfig::default();
+            config.detector.pattern_rules = rules;
+            validate_config(&config).map_err(|e| format!("{:#}", e))
+        };
+
+        assert!(validate(vec![rule("junk", "**/*.bak", PatternSyntax::Glob)]).is_ok());
+        assert!(validate(vec![rule("junk", "(", PatternSyntax::Regex)])
+            .unwrap_err()
+            .contains("Invalid regex"));
+        assert!(validate(vec![rule("large_file", "*", PatternSyntax::Glob)])
+            .unwrap_err()
+            .contains("already taken"));
+        assert!(validate(vec![rule("", "*", PatternSyntax::Glob)]).is_err());
+    }
+
     #[test]
     fn test_validate_custom_rule_valid() {
         let rule = CustomRule {
@@ -234,7 +688,8 @@ mod tests {
             pattern: Some("*.log".to_string()),
             extensions: None,
             min_age_days: Some(30),
-            min_size_mb: Some(10),
+            min_size: Some(ByteSize::from_mb(10)),
+            when: None,
             action: CleanupAction::Delete,
         };
 
@@ -249,7 +704,8 @@ mod tests {
             pattern: Some("*.log".to_string()),
             extensions: None,
             min_age_days: None,
-            min_size_mb: None,
+            min_size: None,
+            when: None,
             action: CleanupAction::Delete,
         };
 
@@ -269,7 +725,8 @@ mod tests {
             pattern: None,
             extensions: None,
             min_age_days: None,
-            min_size_mb: None,
+            min_size: None,
+            when: None,
             action: CleanupAction::Delete,
         };
 
@@ -289,7 +746,8 @@ mod tests {
             pattern: None,
             extensions: Some(vec!["txt".to_string()]), // Missing dot
             min_age_days: None,
-            min_size_mb: None,
+            min_size: None,
+            when: None,
             action: CleanupAction::Delete,
         };
 
@@ -309,7 +767,8 @@ mod tests {
             pattern: Some("*.log".to_string()),
             extensions: None,
             min_age_days: Some(50000),
-            min_size_mb: None,
+            min_size: None,
+            when: None,
             action: CleanupAction::Delete,
         };
 
@@ -318,6 +777,71 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("36500"));
     }
 
+    #[test]
+    fn test_validate_custom_rule_expressions() {
+        let rule = |name: &str, when: &str| CustomRule {
+            name: name.to_string(),
+            description: "Test".to_string(),
+            pattern: None,
+            extensions: None,
+            min_age_days: None,
+            min_size: None,
+            when: Some(when.to_string()),
+            action: CleanupAction::Review,
+        };
+        let validate = |rules: Vec<CustomRule>| {
+            let mut config = MegamaidConfig::default();
+            config.detector.custom_rules = rules;
+            validate_config(&config).map_err(|e| format!("{:#}", e))
+        };
+
+        assert!(validate(vec![
+            rule("old", "age > 1y"),
+            rule("old_large", "old AND large_file"),
+        ])
+        .is_ok());
+        assert!(validate(vec![
+            rule("old_large", "old AND large_file"),
+            rule("old", "age > 1y"),
+        ])
+        .unwrap_err()
+        .contains("unknown rule 'old'"));
+        assert!(validate(vec![rule("large_file", "age > 1y")])
+            .unwrap_err()
+            .contains("already taken"));
+        assert!(validate(vec![rule("old", "age >")])
+            .unwrap_err()
+            .contains("ends unexpectedly"));
+    }
+
+    #[test]
+    fn test_validate_keep_rule_no_patterns() {
+        let rule = KeepRuleConfig {
+            name: "clients".to_string(),
+            patterns: vec![],
+        };
+
+        let result = validate_keep_rule(&rule);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("at least one pattern"));
+    }
+
+    #[test]
+    fn test_validate_keep_rule_invalid_pattern() {
+        let mut config = MegamaidConfig::default();
+        config.detector.keep_rules.push(KeepRuleConfig {
+            name: "clients".to_string(),
+            patterns: vec!["[unclosed".to_string()],
+        });
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(format!("{:#}", result.unwrap_err()).contains("Invalid keep pattern"));
+    }
+
     #[test]
     fn test_validate_output_empty_filenames() {
         let mut config = MegamaidConfig::default();
@@ -327,4 +851,17 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("cannot be empty"));
     }
+
+    #[test]
+    fn test_validate_verifier_zero_record_age() {
+        let mut config = MegamaidConfig::default();
+        config.verifier.record_max_age_minutes = 0;
+
+        let result = validate_config(&config);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("record_max_age_minutes"));
+    }
 }
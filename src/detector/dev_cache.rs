@@ -0,0 +1,242 @@
+//! Package manager caches on developer machines.
+//!
+//! Cargo, npm, pip, Gradle and NuGet keep everything they ever downloaded in
+//! a cache under the user's home, which routinely grows to tens of GB. The
+//! `dev_cache` rule (`detector.rules.dev_caches`) flags these caches as a
+//! whole: each tool downloads what it needs again on the next build.
+//!
+//! A cache is recognized by where it lives: the default location relative
+//! to any home directory, so scanning another user's profile finds theirs
+//! too, or the location the tool's environment variable (`CARGO_HOME`,
+//! `npm_config_cache`, `PIP_CACHE_DIR`, `GRADLE_USER_HOME`,
+//! `NUGET_PACKAGES`) points megamaid's own user at.
+
+use crate::detector::engine::ScanContext;
+use crate::detector::rules::{DetectionRule, EntryKinds, PathPruneHints, VCS_DIRS};
+use crate::models::{paths_equal, FileEntry, PathCase};
+use std::path::{Path, PathBuf};
+
+/// A package manager cache that can be cleared without losing anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DevCache {
+    /// Identifier used in configuration (e.g. `cargo_registry`)
+    pub name: &'static str,
+
+    /// Description used in reasons
+    pub label: &'static str,
+
+    /// Trailing path components of the cache's default locations
+    pub suffixes: &'static [&'static [&'static str]],
+
+    /// Environment variable pointing at the tool's directory, and the path
+    /// of the cache below it
+    pub env: (&'static str, &'static [&'static str]),
+}
+
+/// Known package manager caches.
+pub const DEV_CACHES: &[DevCache] = &[
+    DevCache {
+        name: "cargo_registry",
+        label: "Cargo registry cache",
+        suffixes: &[&[".cargo", "registry", "cache"]],
+        env: ("CARGO_HOME", &["registry", "cache"]),
+    },
+    DevCache {
+        name: "npm",
+        label: "npm cache",
+        suffixes: &[
+            &[".npm", "_cacache"],
+            &["AppData", "Local", "npm-cache", "_cacache"],
+        ],
+        env: ("npm_config_cache", &["_cacache"]),
+    },
+    DevCache {
+        name: "pip",
+        label: "pip cache",
+        suffixes: &[
+            &[".cache", "pip"],
+            &["Library", "Caches", "pip"],
+            &["AppData", "Local", "pip", "Cache"],
+        ],
+        env: ("PIP_CACHE_DIR", &[]),
+    },
+    DevCache {
+        name: "gradle",
+        label: "Gradle cache",
+        suffixes: &[&[".gradle", "caches"]],
+        env: ("GRADLE_USER_HOME", &["caches"]),
+    },
+    DevCache {
+        name: "nuget",
+        label: "NuGet package cache",
+        suffixes: &[&[".nuget", "packages"]],
+        env: ("NUGET_PACKAGES", &[]),
+    },
+];
+
+impl DevCache {
+    /// Returns the known cache `path` is the root of, if any.
+    pub fn find(path: &Path) -> Option<&'static DevCache> {
+        DEV_CACHES.iter().find(|cache| cache.is_at(path))
+    }
+
+    /// Returns true if `path` is one of this cache's default locations.
+    fn is_at(&self, path: &Path) -> bool {
+        self.suffixes.iter().any(|suffix| ends_with(path, suffix))
+    }
+
+    /// Returns where the tool's environment variable puts this cache.
+    fn env_location(&self) -> Option<PathBuf> {
+        let (var, below) = self.env;
+        let dir = std::env::var_os(var).filter(|dir| !dir.is_empty())?;
+        Some(
+            below
+                .iter()
+                .fold(PathBuf::from(dir), |path, part| path.join(part)),
+        )
+    }
+}
+
+/// Returns true if the last components of `path` are `suffix`, compared as
+/// the platform's filesystem does.
+fn ends_with(path: &Path, suffix: &[&str]) -> bool {
+    let case = PathCase::native();
+    let mut components = path.components().rev();
+    suffix.iter().rev().all(|part| {
+        components
+            .next()
+            .is_some_and(|c| case.eq(Path::new(c.as_os_str()), Path::new(part)))
+    })
+}
+
+/// Rule that flags package manager caches.
+pub struct DevCacheRule {
+    caches: Vec<&'static DevCache>,
+    locations: Vec<(PathBuf, &'static DevCache)>,
+}
+
+impl DevCacheRule {
+    /// Creates a rule flagging the named caches.
+    ///
+    /// Unknown names are ignored. Locations set through the tools'
+    /// environment variables are read once, here.
+    pub fn new(names: &[&str]) -> Self {
+        let caches: Vec<&'static DevCache> = DEV_CACHES
+            .iter()
+            .filter(|cache| names.contains(&cache.name))
+            .collect();
+        let locations = caches
+            .iter()
+            .filter_map(|cache| Some((cache.env_location()?, *cache)))
+            .collect();
+        Self { caches, locations }
+    }
+
+    /// Returns the cache `path` is the root of, if it is one this rule flags.
+    fn cache_at(&self, path: &Path) -> Option<&'static DevCache> {
+        self.locations
+            .iter()
+            .find(|(location, _)| paths_equal(path, location))
+            .map(|(_, cache)| *cache)
+            .or_else(|| self.caches.iter().find(|cache| cache.is_at(path)).copied())
+    }
+}
+
+impl Default for DevCacheRule {
+    fn default() -> Self {
+        let names: Vec<&str> = DEV_CACHES.iter().map(|cache| cache.name).collect();
+        Self::new(&names)
+    }
+}
+
+impl DetectionRule for DevCacheRule {
+    fn name(&self) -> &str {
+        "dev_cache"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        self.cache_at(&entry.path).is_some()
+    }
+
+    fn reason(&self) -> String {
+        "Package manager cache, downloaded again when needed".to_string()
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        match self.cache_at(&entry.path) {
+            Some(cache) => format!(
+                "{} ({:.1} MB), downloaded again when needed",
+                cache.label,
+                entry.size as f64 / 1_048_576.0
+            ),
+            None => self.reason(),
+        }
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::DIRECTORIES
+    }
+
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::skip_dirs(VCS_DIRS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use std::time::SystemTime;
+
+    fn dir(path: &str) -> FileEntry {
+        FileEntry::new(
+            PathBuf::from(path),
+            3 * 1_048_576,
+            SystemTime::now(),
+            EntryType::Directory,
+        )
+    }
+
+    #[test]
+    fn test_caches_are_found_in_any_home() {
+        let rule = DevCacheRule::default();
+        let context = ScanContext::default();
+
+        for path in [
+            "/home/ana/.cargo/registry/cache",
+            "/home/ana/.npm/_cacache",
+            "/home/ana/.cache/pip",
+            "/Users/bo/Library/Caches/pip",
+            "/home/ana/.gradle/caches",
+            "/home/ana/.nuget/packages",
+        ] {
+            assert!(rule.should_flag(&dir(path), &context), "{}", path);
+        }
+        for path in [
+            "/home/ana/.cargo/registry",
+            "/home/ana/.cargo/registry/cache/index.crates.io-6f17d22bba15001f",
+            "/home/ana/project/packages",
+            "/home/ana/.gradle",
+        ] {
+            assert!(!rule.should_flag(&dir(path), &context), "{}", path);
+        }
+
+        assert_eq!(
+            rule.reason_for(&dir("/home/ana/.npm/_cacache")),
+            "npm cache (3.0 MB), downloaded again when needed"
+        );
+    }
+
+    #[test]
+    fn test_caches_can_be_left_out() {
+        let rule = DevCacheRule::new(&["pip", "unknown"]);
+        let context = ScanContext::default();
+
+        assert!(rule.should_flag(&dir("/home/ana/.cache/pip"), &context));
+        assert!(!rule.should_flag(&dir("/home/ana/.gradle/caches"), &context));
+        assert_eq!(
+            DevCache::find(Path::new("/home/ana/.gradle/caches")).map(|c| c.name),
+            Some("gradle")
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Duplicate file detection.
+//!
+//! Files are compared in three passes, each only over the candidates left by
+//! the one before: files of the same size, then of the same hash of their
+//! first 64 KB, then of the same BLAKE3 hash of their whole content. Most
+//! files have a size of their own and are never read; of those that don't,
+//! most differ in their first bytes, so only real duplicates are read whole.
+//!
+//! Within a group of identical files the newest copy is kept and the others
+//! are redundant. `megamaid duplicates` lists the groups, and the
+//! `duplicate` rule (`detector.rules.duplicates`) flags the redundant copies
+//! for review. Files that can't be read are left out, as are empty files,
+//! cloud placeholders and, on Unix, further hard links to a file already
+//! seen: deleting a hard link frees nothing.
+
+use crate::detector::engine::ScanContext;
+use crate::detector::rules::{DetectionRule, EntryKinds};
+use crate::models::FileEntry;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Bytes hashed by the prefilter pass.
+const PREFIX_BYTES: u64 = 64 * 1024;
+
+/// Identical files found by [`DuplicateFinder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    /// Size of each file in bytes
+    pub size: u64,
+
+    /// Hex-encoded BLAKE3 hash of the content
+    pub hash: String,
+
+    /// The identical files, newest first; the first is the one to keep
+    pub files: Vec<FileEntry>,
+}
+
+impl DuplicateGroup {
+    /// Returns the copy to keep, the newest one.
+    pub fn keeper(&self) -> &FileEntry {
+        &self.files[0]
+    }
+
+    /// Returns the copies besides the one to keep.
+    pub fn redundant(&self) -> &[FileEntry] {
+        &self.files[1..]
+    }
+
+    /// Returns the bytes freed by deleting the redundant copies.
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * self.redundant().len() as u64
+    }
+}
+
+/// Finds groups of identical files among scanned entries.
+#[derive(Debug, Clone)]
+pub struct DuplicateFinder {
+    min_size: u64,
+}
+
+impl Default for DuplicateFinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DuplicateFinder {
+    /// Creates a finder comparing files of at least 1 byte.
+    pub fn new() -> Self {
+        Self { min_size: 1 }
+    }
+
+    /// Leaves files smaller than `min_size` bytes out; empty files are
+    /// always left out.
+    pub fn with_min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size.max(1);
+        self
+    }
+
+    /// Returns the groups of identical files, those wasting the most bytes
+    /// first.
+    pub fn find(&self, entries: &[FileEntry]) -> Vec<DuplicateGroup> {
+        let mut by_size: HashMap<u64, Vec<&FileEntry>> = HashMap::new();
+        for entry in entries
+            .iter()
+            .filter(|e| e.is_file() && !e.cloud_placeholder && e.size >= self.min_size)
+        {
+            by_size.entry(entry.size).or_default().push(entry);
+        }
+
+        let mut groups = Vec::new();
+        for (size, candidates) in by_size {
+            if candidates.len() < 2 {
+                continue;
+            }
+            let candidates = distinct_files(candidates);
+            for (prefix_hash, files) in split_by_hash(candidates, Some(PREFIX_BYTES)) {
+                // Files no longer than the prefix were hashed whole already
+                let split = if size <= PREFIX_BYTES {
+                    vec![(prefix_hash, files)]
+                } else {
+                    split_by_hash(files, None)
+                };
+                for (hash, files) in split {
+                    let mut files: Vec<FileEntry> = files.into_iter().cloned().collect();
+                    files.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.path.cmp(&b.path)));
+                    groups.push(DuplicateGroup { size, hash, files });
+                }
+            }
+        }
+
+        groups.sort_by(|a, b| {
+            b.wasted_bytes()
+                .cmp(&a.wasted_bytes())
+                .then_with(|| a.keeper().path.cmp(&b.keeper().path))
+        });
+        groups
+    }
+}
+
+/// Groups `files` by the hash of their first `limit` bytes, or of their
+/// whole content; unreadable files and hashes of a single file are dropped.
+fn split_by_hash(files: Vec<&FileEntry>, limit: Option<u64>) -> Vec<(String, Vec<&FileEntry>)> {
+    let mut by_hash: HashMap<String, Vec<&FileEntry>> = HashMap::new();
+    for file in files {
+        if let Ok(hash) = hash_file(&file.path, limit) {
+            by_hash.entry(hash).or_default().push(file);
+        }
+    }
+    by_hash
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .collect()
+}
+
+/// Computes the hex-encoded BLAKE3 hash of a file's first `limit` bytes,
+/// or of all of them.
+fn hash_file(path: &Path, limit: Option<u64>) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    let file = File::open(path)?;
+    match limit {
+        Some(limit) => io::copy(&mut file.take(limit), &mut hasher)?,
+        None => io::copy(&mut { file }, &mut hasher)?,
+    };
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Drops further hard links to files already in `files`.
+fn distinct_files(files: Vec<&FileEntry>) -> Vec<&FileEntry> {
+    let mut seen = HashSet::new();
+    files
+        .into_iter()
+        .filter(|file| platform::file_id(&file.path).is_none_or(|id| seen.insert(id)))
+        .collect()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    /// Returns the device and inode of `path`, shared by its hard links.
+    pub fn file_id(path: &Path) -> Option<(u64, u64)> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some((metadata.dev(), metadata.ino()))
+    }
+}
+
+#[cfg(not(unix))]
+mod platform {
+    use std::path::Path;
+
+    pub fn file_id(_path: &Path) -> Option<(u64, u64)> {
+        None
+    }
+}
+
+/// Rule that flags redundant copies of duplicate files.
+///
+/// The groups are found before detection runs, since telling duplicates
+/// apart takes all entries; the newest copy of each group isn't flagged.
+pub struct DuplicateRule {
+    /// Copy to keep, by redundant copy
+    keepers: HashMap<PathBuf, PathBuf>,
+}
+
+impl DuplicateRule {
+    /// Creates a rule flagging the redundant copies of `groups`.
+    pub fn new(groups: &[DuplicateGroup]) -> Self {
+        let keepers = groups
+            .iter()
+            .flat_map(|group| {
+                let keeper = &group.keeper().path;
+                group
+                    .redundant()
+                    .iter()
+                    .map(move |file| (file.path.clone(), keeper.clone()))
+            })
+            .collect();
+        Self { keepers }
+    }
+}
+
+impl DetectionRule for DuplicateRule {
+    fn name(&self) -> &str {
+        "duplicate"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        self.keepers.contains_key(&entry.path)
+    }
+
+    fn reason(&self) -> String {
+        "Duplicate of a newer copy".to_string()
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        match self.keepers.get(&entry.path) {
+            Some(keeper) => format!("Duplicate of {}, keep newest", keeper.display()),
+            None => self.reason(),
+        }
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::FILES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &[u8], age_days: u64) -> FileEntry {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 86_400);
+        FileEntry::new(path, content.len() as u64, modified, EntryType::File)
+    }
+
+    #[test]
+    fn test_find_groups_identical_files() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        // Same size and first 64 KB, different tails
+        let big = vec![7u8; PREFIX_BYTES as usize + 10];
+        let mut tail = big.clone();
+        *tail.last_mut().unwrap() = 8;
+
+        let entries = vec![
+            write(dir, "a.txt", b"hello", 3),
+            write(dir, "b.txt", b"hello", 1),
+            write(dir, "c.txt", b"hellp", 2),
+            write(dir, "empty1", b"", 0),
+            write(dir, "empty2", b"", 0),
+            write(dir, "big1.bin", &big, 5),
+            write(dir, "big2.bin", &big, 4),
+            write(dir, "big3.bin", &tail, 4),
+        ];
+
+        let groups = DuplicateFinder::new().find(&entries);
+        assert_eq!(groups.len(), 2);
+
+        let large = &groups[0];
+        assert_eq!(large.size, PREFIX_BYTES + 10);
+        assert_eq!(large.files.len(), 2);
+        assert!(large.keeper().path.ends_with("big2.bin"));
+        assert_eq!(large.wasted_bytes(), PREFIX_BYTES + 10);
+
+        let small = &groups[1];
+        assert!(small.keeper().path.ends_with("b.txt"));
+        assert_eq!(small.redundant().len(), 1);
+        assert!(small.redundant()[0].path.ends_with("a.txt"));
+        assert_eq!(small.hash, blake3::hash(b"hello").to_hex().as_str());
+
+        let groups = DuplicateFinder::new().with_min_size(1024).find(&entries);
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_ignores_hard_links() {
+        let temp = TempDir::new().unwrap();
+        let original = write(temp.path(), "a.bin", b"data", 1);
+        let link = temp.path().join("b.bin");
+        fs::hard_link(&original.path, &link).unwrap();
+        let linked = FileEntry::new(link, 4, original.modified, EntryType::File);
+
+        assert!(DuplicateFinder::new().find(&[original, linked]).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_rule_flags_redundant_copies() {
+        let temp = TempDir::new().unwrap();
+        let old = write(temp.path(), "old.iso", b"image", 10);
+        let new = write(temp.path(), "new.iso", b"image", 1);
+        let groups = DuplicateFinder::new().find(&[old.clone(), new.clone()]);
+
+        let rule = DuplicateRule::new(&groups);
+        let context = ScanContext::default();
+        assert!(rule.should_flag(&old, &context));
+        assert!(!rule.should_flag(&new, &context));
+        assert_eq!(
+            rule.reason_for(&old),
+            format!("Duplicate of {}, keep newest", new.path.display())
+        );
+    }
+}
@@ -1,14 +1,22 @@
 //! Detection engine that orchestrates rules.
 
-use crate::detector::rules::{BuildArtifactRule, DetectionRule, SizeThresholdRule};
-use crate::models::FileEntry;
+use crate::detector::dev_cache::DevCache;
+use crate::detector::keep::{KeepRule, VetoedDetection};
+use crate::detector::rules::{BuildArtifactRule, DetectionRule, PathPruneHints, SizeThresholdRule};
+use crate::models::{
+    in_snapshot_dir, ArtifactKind, FileEntry, OwnArtifacts, Protection, ProtectionEvent,
+    ProtectionOutcome, RuleStats,
+};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::time::Instant;
 
 /// Context information for detection rules.
 #[derive(Debug, Default)]
 pub struct ScanContext {
-    // Future: could include statistics, parent directory info, etc.
+    /// megamaid's own files; entries that are, contain or lie inside one are
+    /// never flagged
+    pub own_artifacts: OwnArtifacts,
 }
 
 /// Result of applying detection rules to an entry.
@@ -22,11 +30,45 @@ pub struct DetectionResult {
 
     /// Reason it was flagged
     pub reason: String,
+
+    /// Protection the rule holds the entry back for review with, and its
+    /// detail (see [`DetectionRule::downgrade`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downgrade: Option<(Protection, String)>,
+}
+
+/// Detections together with per-rule coverage statistics.
+#[derive(Debug, Clone, Default)]
+pub struct DetectionReport {
+    /// Entries flagged by the rules
+    pub detections: Vec<DetectionResult>,
+
+    /// Statistics for each rule, in engine order
+    pub rule_stats: Vec<RuleStats>,
+
+    /// Detections suppressed by keep rules
+    pub vetoed: Vec<VetoedDetection>,
+
+    /// Detections suppressed by protections, including keep rules
+    pub protections: Vec<ProtectionEvent>,
+}
+
+/// What became of an entry during analysis.
+enum Outcome {
+    /// No rule has flagged it yet
+    Pending,
+    /// A protection kept it from being evaluated, with the detection it held back
+    Protected(Protection, Option<ProtectionEvent>),
+    /// Flagged, then vetoed by a keep rule
+    Vetoed(VetoedDetection),
+    /// Flagged
+    Flagged(DetectionResult),
 }
 
 /// Engine that applies multiple detection rules to identify cleanup candidates.
 pub struct DetectionEngine {
     rules: Vec<Box<dyn DetectionRule>>,
+    keep_rules: Vec<KeepRule>,
 }
 
 impl DetectionEngine {
@@ -34,17 +76,19 @@ impl DetectionEngine {
     pub fn new() -> Self {
         Self {
             rules: vec![
-                Box::new(SizeThresholdRule {
-                    threshold_bytes: 100 * 1_048_576, // 100MB
-                }),
+                Box::new(SizeThresholdRule::new(100 * 1_048_576)), // 100MB
                 Box::new(BuildArtifactRule::default()),
             ],
+            keep_rules: Vec::new(),
         }
     }
 
     /// Creates an empty DetectionEngine with no rules.
     pub fn empty() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            rules: Vec::new(),
+            keep_rules: Vec::new(),
+        }
     }
 
     /// Adds a rule to the engine.
@@ -52,38 +96,185 @@ impl DetectionEngine {
         self.rules.push(rule);
     }
 
+    /// Adds a keep rule that vetoes matching detections.
+    pub fn add_keep_rule(&mut self, rule: KeepRule) {
+        self.keep_rules.push(rule);
+    }
+
     /// Analyzes entries and returns those flagged by any rule.
     ///
     /// Each entry is flagged at most once (first matching rule wins).
     pub fn analyze(&self, entries: &[FileEntry], context: &ScanContext) -> Vec<DetectionResult> {
-        let mut results = Vec::new();
+        self.analyze_with_stats(entries, context).detections
+    }
 
-        for entry in entries {
-            // Protect common source code files and source root directories from being flagged.
-            if is_protected_source(entry) || is_repo_root(entry) || is_protected_manifest(entry) {
+    /// Analyzes entries and records how much work each rule did.
+    ///
+    /// Detection semantics are identical to [`DetectionEngine::analyze`]; the
+    /// report additionally carries evaluation counts, flagged counts, flagged
+    /// bytes and cumulative evaluation time for every rule.
+    ///
+    /// Each rule decides for all entries no earlier rule flagged in one
+    /// batch (see [`DetectionRule::evaluate_batch`]), leaving out what its
+    /// [`DetectionRule::prefilter`] prunes.
+    pub fn analyze_with_stats(
+        &self,
+        entries: &[FileEntry],
+        context: &ScanContext,
+    ) -> DetectionReport {
+        let mut stats: Vec<RuleStats> = self
+            .rules
+            .iter()
+            .map(|rule| RuleStats {
+                rule_name: rule.name().to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let mut elapsed = vec![std::time::Duration::ZERO; self.rules.len()];
+        let hints: Vec<PathPruneHints> = self
+            .rules
+            .iter()
+            .map(|rule| rule.prefilter(context))
+            .collect();
+
+        // Protect common source code files and source root directories from being flagged.
+        // Record which rule would have fired, so users can see what was held back.
+        let mut outcomes: Vec<Outcome> = entries
+            .iter()
+            .map(|entry| match protection_for(entry, context) {
+                Some((protection, detail)) => Outcome::Protected(
+                    protection,
+                    self.first_match(entry, context, &hints)
+                        .map(|rule| ProtectionEvent {
+                            path: entry.path.to_string_lossy().into_owned(),
+                            size: entry.size,
+                            rule_name: rule.name().to_string(),
+                            protection,
+                            outcome: ProtectionOutcome::Suppressed,
+                            detail,
+                        }),
+                ),
+                None => Outcome::Pending,
+            })
+            .collect();
+
+        // Apply rules in order; first match wins. Rules flagging whole
+        // repositories also see the repository roots protected from the rest
+        for (index, rule) in self.rules.iter().enumerate() {
+            let kinds = rule.applies_to();
+            let repositories = rule.flags_repositories();
+            let (positions, batch): (Vec<usize>, Vec<&FileEntry>) = entries
+                .iter()
+                .enumerate()
+                .filter(|(position, entry)| {
+                    let open = match outcomes[*position] {
+                        Outcome::Pending => true,
+                        Outcome::Protected(Protection::RepoRoot, _) => repositories,
+                        _ => false,
+                    };
+                    open && kinds.contains(entry.entry_type) && !hints[index].covers(&entry.path)
+                })
+                .unzip();
+            if batch.is_empty() {
                 continue;
             }
 
-            // Try each rule in order; first match wins
-            for rule in &self.rules {
-                if rule.should_flag(entry, context) {
-                    // Block delete-intent for protected patterns; downgrade to review
-                    let detection = DetectionResult {
-                        entry: entry.clone(),
-                        rule_name: rule.name().to_string(),
-                        reason: rule.reason(),
-                    };
-                    // If rule is build_artifact but path looks like repo root, skip
-                    if detection.rule_name == "build_artifact" && is_repo_root(entry) {
-                        continue;
+            let started = Instant::now();
+            let verdicts = rule.evaluate_batch(&batch, context);
+            elapsed[index] += started.elapsed();
+            stats[index].entries_evaluated += batch.len() as u64;
+
+            for ((position, entry), flagged) in positions.into_iter().zip(batch).zip(verdicts) {
+                if !flagged {
+                    continue;
+                }
+                // If rule is build_artifact but path looks like repo root, skip
+                if rule.name() == "build_artifact" && repo_root_marker(entry).is_some() {
+                    continue;
+                }
+                let mut detection = DetectionResult {
+                    entry: entry.clone(),
+                    rule_name: rule.name().to_string(),
+                    reason: rule.reason_for(entry),
+                    downgrade: None,
+                };
+                outcomes[position] = match self.keep_rules.iter().find(|k| k.matches(&entry.path)) {
+                    Some(keep) => Outcome::Vetoed(VetoedDetection {
+                        detection,
+                        keep_rule: keep.name().to_string(),
+                    }),
+                    None => {
+                        stats[index].record_flagged(&entry.path, entry.size);
+                        let started = Instant::now();
+                        detection.downgrade = rule.downgrade(entry);
+                        elapsed[index] += started.elapsed();
+                        Outcome::Flagged(detection)
                     }
-                    results.push(DetectionResult { ..detection });
-                    break; // Only flag once per entry
+                };
+            }
+        }
+
+        for (stat, duration) in stats.iter_mut().zip(elapsed) {
+            stat.evaluation_time_ms = duration.as_secs_f64() * 1000.0;
+        }
+
+        // Report in entry order
+        let mut results = Vec::new();
+        let mut vetoed = Vec::new();
+        let mut protections = Vec::new();
+        for outcome in outcomes {
+            match outcome {
+                Outcome::Pending => {}
+                Outcome::Protected(_, event) => protections.extend(event),
+                Outcome::Vetoed(veto) => {
+                    protections.push(ProtectionEvent {
+                        path: veto.detection.entry.path.to_string_lossy().into_owned(),
+                        size: veto.detection.entry.size,
+                        rule_name: veto.detection.rule_name.clone(),
+                        protection: Protection::KeepRule,
+                        outcome: ProtectionOutcome::Suppressed,
+                        detail: veto.keep_rule.clone(),
+                    });
+                    vetoed.push(veto);
                 }
+                Outcome::Flagged(detection) => results.push(detection),
             }
         }
 
-        results
+        DetectionReport {
+            detections: results,
+            rule_stats: stats,
+            vetoed,
+            protections,
+        }
+    }
+
+    /// Returns what no rule flags anything in, so a scan can leave it out.
+    ///
+    /// Only parts every rule prunes are returned; an engine without rules
+    /// prunes nothing.
+    pub fn prune_hints(&self, context: &ScanContext) -> PathPruneHints {
+        let mut hints = self.rules.iter().map(|rule| rule.prefilter(context));
+        let first = hints.next().unwrap_or_default();
+        hints.fold(first, |all, hints| all.intersect(&hints))
+    }
+
+    /// Returns the first rule that flags `entry`, without recording statistics.
+    fn first_match(
+        &self,
+        entry: &FileEntry,
+        context: &ScanContext,
+        hints: &[PathPruneHints],
+    ) -> Option<&dyn DetectionRule> {
+        self.rules
+            .iter()
+            .zip(hints)
+            .find(|(rule, hints)| {
+                rule.applies_to().contains(entry.entry_type)
+                    && !hints.covers(&entry.path)
+                    && rule.should_flag(entry, context)
+            })
+            .map(|(rule, _)| rule.as_ref())
     }
 
     /// Returns the number of rules in this engine.
@@ -92,7 +283,30 @@ impl DetectionEngine {
     }
 }
 
-fn is_protected_source(entry: &FileEntry) -> bool {
+/// Returns the protection that keeps `entry` from being evaluated, with details.
+fn protection_for(entry: &FileEntry, context: &ScanContext) -> Option<(Protection, String)> {
+    if let Some(artifact) = context.own_artifacts.affected_by(&entry.path) {
+        return Some((
+            Protection::OwnArtifact,
+            format!("{} {}", artifact.kind, artifact.path.display()),
+        ));
+    }
+    if in_snapshot_dir(&entry.path) {
+        return Some((
+            Protection::OwnArtifact,
+            format!("{} {}", ArtifactKind::Snapshot, entry.path.display()),
+        ));
+    }
+    if let Some(detail) = protected_source(entry) {
+        return Some((Protection::SourceFile, detail.to_string()));
+    }
+    if let Some(marker) = repo_root_marker(entry) {
+        return Some((Protection::RepoRoot, format!("contains {}", marker)));
+    }
+    project_manifest(entry).map(|manifest| (Protection::Manifest, format!("contains {}", manifest)))
+}
+
+fn protected_source(entry: &FileEntry) -> Option<&'static str> {
     // Skip obvious source code files
     if entry.path.is_file() {
         if let Some(ext) = entry.path.extension().and_then(|e| e.to_str()) {
@@ -102,13 +316,14 @@ fn is_protected_source(entry: &FileEntry) -> bool {
                 "cc", "cpp", "h", "hpp", "cs", "rb", "php",
             ];
             if SOURCE_EXTS.contains(&ext.as_str()) {
-                return true;
+                return Some("source code file");
             }
         }
     }
 
-    // Skip common source/config directories
-    if entry.path.is_dir() {
+    // Skip common source/config directories, except package manager caches
+    // such as `~/.nuget/packages`
+    if entry.path.is_dir() && DevCache::find(&entry.path).is_none() {
         if let Some(name) = entry.path.file_name().and_then(|n| n.to_str()) {
             let name = name.to_ascii_lowercase();
             const SOURCE_DIRS: &[&str] = &[
@@ -116,49 +331,40 @@ fn is_protected_source(entry: &FileEntry) -> bool {
                 ".vscode", "config", "configs", "docs",
             ];
             if SOURCE_DIRS.contains(&name.as_str()) {
-                return true;
+                return Some("source or config directory");
             }
         }
     }
 
-    false
+    None
 }
 
-fn is_repo_root(entry: &FileEntry) -> bool {
+/// Returns the marker that makes `entry` a repository or project root.
+fn repo_root_marker(entry: &FileEntry) -> Option<&'static str> {
     if entry.entry_type != crate::models::EntryType::Directory {
-        return false;
+        return None;
     }
     if is_known_junk_dir(&entry.path) {
-        return false;
+        return None;
     }
     let path = &entry.path;
     if path == Path::new(".") {
-        return true;
+        return Some("the working directory");
     }
     let candidates = [".git", ".hg", ".svn", "package.json", "Cargo.toml"];
-    for c in candidates {
-        if path.join(c).exists() {
-            return true;
-        }
-    }
-    false
+    candidates.into_iter().find(|c| path.join(c).exists())
 }
 
-fn is_protected_manifest(entry: &FileEntry) -> bool {
+fn project_manifest(entry: &FileEntry) -> Option<&'static str> {
     // Protect directories that contain obvious project manifests
     if entry.entry_type != crate::models::EntryType::Directory {
-        return false;
+        return None;
     }
     if is_known_junk_dir(&entry.path) {
-        return false;
+        return None;
     }
     let manifests = ["package.json", "Cargo.toml", "pyproject.toml"];
-    for m in manifests {
-        if entry.path.join(m).exists() {
-            return true;
-        }
-    }
-    false
+    manifests.into_iter().find(|m| entry.path.join(m).exists())
 }
 
 fn is_known_junk_dir(path: &Path) -> bool {
@@ -245,12 +451,8 @@ mod tests {
         let mut engine = DetectionEngine::empty();
 
         // Add two rules that would both match
-        engine.add_rule(Box::new(SizeThresholdRule {
-            threshold_bytes: 1000,
-        }));
-        engine.add_rule(Box::new(SizeThresholdRule {
-            threshold_bytes: 500,
-        }));
+        engine.add_rule(Box::new(SizeThresholdRule::new(1000)));
+        engine.add_rule(Box::new(SizeThresholdRule::new(500)));
 
         let large_file = create_test_entry("large.bin", 2000);
 
@@ -260,6 +462,78 @@ mod tests {
         assert_eq!(results.len(), 1, "Should only flag once per entry");
     }
 
+    #[test]
+    fn test_rules_decide_in_batches_and_skip_pruned_entries() {
+        use crate::detector::PathPruneHints;
+        use std::sync::Mutex;
+
+        // Flags everything outside .git, recording the batches it was given
+        struct BatchRule {
+            batches: Mutex<Vec<usize>>,
+        }
+        impl DetectionRule for BatchRule {
+            fn name(&self) -> &str {
+                "batch"
+            }
+            fn should_flag(&self, _: &FileEntry, _: &ScanContext) -> bool {
+                panic!("decided per entry")
+            }
+            fn reason(&self) -> String {
+                "batch".to_string()
+            }
+            fn prefilter(&self, _: &ScanContext) -> PathPruneHints {
+                PathPruneHints::skip_dirs([".git"])
+            }
+            fn evaluate_batch(&self, entries: &[&FileEntry], _: &ScanContext) -> Vec<bool> {
+                self.batches.lock().unwrap().push(entries.len());
+                vec![true; entries.len()]
+            }
+        }
+
+        let mut engine = DetectionEngine::empty();
+        engine.add_rule(Box::new(SizeThresholdRule::new(1000)));
+        engine.add_rule(Box::new(BatchRule {
+            batches: Mutex::default(),
+        }));
+
+        let entries = vec![
+            create_test_entry("a.txt", 10),
+            create_test_entry("big.bin", 2000),
+            create_test_entry("repo/.git/index", 10),
+            create_test_entry("b.txt", 10),
+        ];
+        let report = engine.analyze_with_stats(&entries, &ScanContext::default());
+
+        // Detections keep entry order; the size rule's match isn't re-evaluated
+        let flagged: Vec<_> = report
+            .detections
+            .iter()
+            .map(|d| (d.entry.path.to_str().unwrap(), d.rule_name.as_str()))
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                ("a.txt", "batch"),
+                ("big.bin", "large_file"),
+                ("b.txt", "batch")
+            ]
+        );
+        assert_eq!(report.rule_stats[0].entries_evaluated, 4);
+        assert_eq!(report.rule_stats[1].entries_evaluated, 2);
+
+        // Only what every rule prunes can be left out of a scan
+        assert!(engine.prune_hints(&ScanContext::default()).is_empty());
+        let mut pruning = DetectionEngine::empty();
+        pruning.add_rule(Box::new(crate::detector::EmptyEntryRule::default()));
+        pruning.add_rule(Box::new(crate::detector::DeleteMarkerRule::new(".del")));
+        assert!(pruning
+            .prune_hints(&ScanContext::default())
+            .covers(Path::new("repo/.git/objects")));
+        assert!(DetectionEngine::empty()
+            .prune_hints(&ScanContext::default())
+            .is_empty());
+    }
+
     #[test]
     fn test_custom_rule_integration() {
         struct TestRule;
@@ -369,4 +643,154 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].rule_name, "first"); // First rule wins
     }
+
+    #[test]
+    fn test_analyze_with_stats_counts_per_rule() {
+        let mut engine = DetectionEngine::empty();
+        engine.add_rule(Box::new(BuildArtifactRule::default()));
+        engine.add_rule(Box::new(SizeThresholdRule::new(1000)));
+
+        let entries = vec![
+            create_test_entry_dir("target"),
+            create_test_entry("big.bin", 5000),
+            create_test_entry("small.txt", 10),
+        ];
+
+        let report = engine.analyze_with_stats(&entries, &ScanContext::default());
+
+        assert_eq!(report.detections.len(), 2);
+        assert_eq!(report.rule_stats.len(), 2);
+
+        let artifact = &report.rule_stats[0];
+        assert_eq!(artifact.rule_name, "build_artifact");
+        // build_artifact only applies to directories
+        assert_eq!(artifact.entries_evaluated, 1);
+        assert_eq!(artifact.entries_flagged, 1);
+
+        // The size rule never sees the entry already claimed by build_artifact
+        let size = &report.rule_stats[1];
+        assert_eq!(size.rule_name, "large_file");
+        assert_eq!(size.entries_evaluated, 2);
+        assert_eq!(size.entries_flagged, 1);
+        assert_eq!(size.bytes_flagged, 5000);
+        assert_eq!(size.largest_entry.as_deref(), Some("big.bin"));
+        assert_eq!(size.largest_entry_bytes, 5000);
+    }
+
+    #[test]
+    fn test_engine_enforces_applicability_mask() {
+        use crate::detector::rules::{EntryKinds, ScopedRule};
+
+        let mut engine = DetectionEngine::empty();
+        engine.add_rule(Box::new(ScopedRule::new(
+            Box::new(SizeThresholdRule::new(1000)),
+            EntryKinds::FILES,
+        )));
+
+        let mut big_dir = create_test_entry_dir("cache");
+        big_dir.size = 50_000;
+        let entries = vec![big_dir, create_test_entry("big.bin", 5000)];
+
+        let report = engine.analyze_with_stats(&entries, &ScanContext::default());
+
+        assert_eq!(report.detections.len(), 1);
+        assert_eq!(report.detections[0].entry.path, PathBuf::from("big.bin"));
+        // Directories outside the mask are not counted as evaluated
+        assert_eq!(report.rule_stats[0].entries_evaluated, 1);
+    }
+
+    #[test]
+    fn test_keep_rules_veto_detections() {
+        let mut engine = DetectionEngine::empty();
+        engine.add_rule(Box::new(SizeThresholdRule::new(1000)));
+        engine.add_keep_rule(KeepRule::new("clients", &["/data/clients"]).unwrap());
+
+        let entries = vec![
+            create_test_entry("/data/clients/acme/big.bin", 5000),
+            create_test_entry("/data/other/big.bin", 5000),
+        ];
+
+        let report = engine.analyze_with_stats(&entries, &ScanContext::default());
+
+        assert_eq!(report.detections.len(), 1);
+        assert_eq!(
+            report.detections[0].entry.path,
+            PathBuf::from("/data/other/big.bin")
+        );
+        assert_eq!(report.vetoed.len(), 1);
+        assert_eq!(report.vetoed[0].keep_rule, "clients");
+        assert_eq!(report.vetoed[0].detection.rule_name, "large_file");
+        // Vetoed entries do not count towards flagged totals
+        assert_eq!(report.rule_stats[0].entries_flagged, 1);
+        assert_eq!(report.rule_stats[0].bytes_flagged, 5000);
+        assert_eq!(report.protections.len(), 1);
+        assert_eq!(report.protections[0].protection, Protection::KeepRule);
+        assert_eq!(report.protections[0].detail, "clients");
+    }
+
+    #[test]
+    fn test_rules_hold_back_what_they_flag() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let project = temp.path().join("app");
+        std::fs::create_dir_all(project.join("target")).unwrap();
+        std::fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let mut engine = DetectionEngine::empty();
+        engine.add_rule(Box::new(
+            BuildArtifactRule::default().with_active_project_days(Some(14)),
+        ));
+        let entries = vec![create_test_entry_dir(
+            project.join("target").to_str().unwrap(),
+        )];
+
+        let report = engine.analyze_with_stats(&entries, &ScanContext::default());
+
+        let (protection, detail) = report.detections[0].downgrade.clone().unwrap();
+        assert_eq!(protection, Protection::ActiveProject);
+        assert_eq!(detail, "project touched today");
+        // Still flagged; the plan decides what a downgrade means
+        assert_eq!(report.rule_stats[0].entries_flagged, 1);
+    }
+
+    #[test]
+    fn test_protected_entries_are_audited() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let project = temp.path().join("project");
+        std::fs::create_dir_all(project.join("docs")).unwrap();
+        std::fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+
+        let mut engine = DetectionEngine::empty();
+        engine.add_rule(Box::new(SizeThresholdRule::new(1000)));
+
+        let dir = |path: PathBuf, size| {
+            FileEntry::new(path, size, SystemTime::now(), EntryType::Directory)
+        };
+        let entries = vec![
+            dir(project.clone(), 40_000),
+            dir(project.join("docs"), 20_000),
+            dir(temp.path().join("small"), 10),
+        ];
+
+        let report = engine.analyze_with_stats(&entries, &ScanContext::default());
+
+        assert!(report.detections.is_empty());
+        assert_eq!(report.protections.len(), 2);
+        assert_eq!(report.protections[0].protection, Protection::RepoRoot);
+        assert_eq!(report.protections[0].detail, "contains Cargo.toml");
+        assert_eq!(report.protections[0].size, 40_000);
+        assert_eq!(report.protections[1].protection, Protection::SourceFile);
+        assert_eq!(report.protections[1].rule_name, "large_file");
+        // Protected entries aren't counted as evaluated
+        assert_eq!(report.rule_stats[0].entries_evaluated, 1);
+    }
+
+    #[test]
+    fn test_analyze_with_stats_empty_engine() {
+        let engine = DetectionEngine::empty();
+        let report =
+            engine.analyze_with_stats(&[create_test_entry("a.txt", 1)], &ScanContext::default());
+
+        assert!(report.detections.is_empty());
+        assert!(report.rule_stats.is_empty());
+    }
 }
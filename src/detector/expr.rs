@@ -0,0 +1,640 @@
+//! Boolean expressions combining rules and entry properties.
+//!
+//! Custom rules flag entries matching an expression such as
+//! `large_file AND age > 180d AND NOT under('D:\Archive')`. Operands are:
+//!
+//! - the name of a built-in rule, a pattern rule or an earlier custom rule,
+//!   true for entries that rule would flag (whether or not it is enabled on
+//!   its own)
+//! - `age` or `size` compared with `>`, `>=`, `<` or `<=` to a duration
+//!   (`36h`, `180d`, `8w`, `2y`) or a size with a unit (`500MB`, `1.5GiB`)
+//! - `under('path')`: the entry is the path or lies below it
+//! - `glob('pattern')`: a glob in `scanner.exclude` syntax matches the entry
+//! - `ext('.iso')`: the entry's extension, ignoring case
+//! - `owner('jdoe')`: the entry's owner, ignoring case; on Windows either
+//!   `DOMAIN\user` or just `user`. Needs `scanner.collect_owner`, without
+//!   which no entry has an owner
+//! - `readonly` and `system`: the entry's attributes, also collected with
+//!   `scanner.collect_owner`
+//! - `file`, `dir`, `symlink` and `junction`: the kind of entry
+//!
+//! Operands combine with `NOT`, `AND` and `OR`, binding in that order, and
+//! parentheses. Keywords ignore case; strings take single or double quotes
+//! and have no escapes, so backslashes in Windows paths are written once.
+
+use crate::detector::engine::ScanContext;
+use crate::detector::rules::DetectionRule;
+use crate::models::{strip_path_prefix, ByteSize, ByteSizeParseError, EntryType, FileEntry};
+use crate::scanner::exclude::{expand_vars, ExcludeSet};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors parsing an expression or resolving its rule references.
+#[derive(Debug, Error)]
+pub enum ExprError {
+    /// The expression has no operands
+    #[error("Expression is empty")]
+    Empty,
+
+    /// A token appears where it can't be used
+    #[error("Unexpected '{found}' at column {column}")]
+    Unexpected { column: usize, found: String },
+
+    /// The expression stops in the middle
+    #[error("Expression ends unexpectedly; expected {0}")]
+    UnexpectedEnd(&'static str),
+
+    /// A quoted string is never closed
+    #[error("Unterminated string starting at column {0}")]
+    UnterminatedString(usize),
+
+    /// An age doesn't have a number and unit
+    #[error("Invalid age '{0}' (use hours, days, weeks or years, e.g. 180d)")]
+    InvalidAge(String),
+
+    /// A size can't be parsed
+    #[error("Invalid size '{0}': {1}")]
+    InvalidSize(String, ByteSizeParseError),
+
+    /// A glob can't be compiled
+    #[error("Invalid glob '{0}': {1}")]
+    InvalidGlob(String, glob::PatternError),
+
+    /// A call names no known function
+    #[error("Unknown function '{0}' (expected under, glob, ext or owner)")]
+    UnknownFunction(String),
+
+    /// An operand names no available rule
+    #[error("Unknown rule '{0}' (custom rules can only reference built-in rules, pattern rules and custom rules listed before them)")]
+    UnknownRule(String),
+}
+
+/// How an entry property compares with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, value: T, bound: T) -> bool {
+        match self {
+            Self::Greater => value > bound,
+            Self::GreaterOrEqual => value >= bound,
+            Self::Less => value < bound,
+            Self::LessOrEqual => value <= bound,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+        };
+        f.write_str(symbol)
+    }
+}
+
+/// A parsed expression.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// Flagged by the named rule
+    Rule(String),
+    /// Time since the entry was last modified
+    Age(Comparison, Duration),
+    /// Size of the entry in bytes
+    Size(Comparison, u64),
+    /// The entry is this path or lies below it
+    Under(PathBuf),
+    /// The entry matches a glob
+    Glob(ExcludeSet),
+    /// Extension of the entry, without the dot
+    Ext(String),
+    /// Kind of entry
+    Kind(EntryType),
+    /// Owner of the entry
+    Owner(String),
+    /// The entry is read-only
+    ReadOnly,
+    /// The entry is a Windows system file
+    System,
+}
+
+impl Expr {
+    /// Parses an expression.
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err(ExprError::Empty);
+        }
+        let mut parser = Parser { tokens, next: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.next) {
+            Some((column, token)) => Err(ExprError::Unexpected {
+                column: *column,
+                found: token.to_string(),
+            }),
+            None => Ok(expr),
+        }
+    }
+
+    /// Matches entries by glob, in `scanner.exclude` syntax.
+    pub fn glob(pattern: &str) -> Result<Self, ExprError> {
+        ExcludeSet::new(&[pattern])
+            .map(Self::Glob)
+            .map_err(|e| ExprError::InvalidGlob(pattern.to_string(), e))
+    }
+
+    /// Matches entries by extension, with or without the leading dot.
+    pub fn ext(extension: &str) -> Self {
+        Self::Ext(extension.trim_start_matches('.').to_string())
+    }
+
+    /// Combines expressions with AND, or returns `None` if there are none.
+    pub fn all(exprs: impl IntoIterator<Item = Expr>) -> Option<Self> {
+        exprs
+            .into_iter()
+            .reduce(|a, b| Self::And(Box::new(a), Box::new(b)))
+    }
+
+    /// Combines expressions with OR, or returns `None` if there are none.
+    pub fn any(exprs: impl IntoIterator<Item = Expr>) -> Option<Self> {
+        exprs
+            .into_iter()
+            .reduce(|a, b| Self::Or(Box::new(a), Box::new(b)))
+    }
+
+    /// Returns the names of the rules the expression references.
+    pub fn rule_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_rule_names(&mut names);
+        names
+    }
+
+    fn collect_rule_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::And(a, b) | Self::Or(a, b) => {
+                a.collect_rule_names(names);
+                b.collect_rule_names(names);
+            }
+            Self::Not(inner) => inner.collect_rule_names(names),
+            Self::Rule(name) if !names.contains(&name.as_str()) => names.push(name),
+            _ => {}
+        }
+    }
+
+    /// Evaluates the expression for `entry`, looking referenced rules up in
+    /// `rules`; unknown rules never match.
+    pub fn matches(
+        &self,
+        entry: &FileEntry,
+        context: &ScanContext,
+        rules: &HashMap<String, Arc<dyn DetectionRule>>,
+    ) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(entry, context, rules) && b.matches(entry, context, rules),
+            Self::Or(a, b) => a.matches(entry, context, rules) || b.matches(entry, context, rules),
+            Self::Not(inner) => !inner.matches(entry, context, rules),
+            Self::Rule(name) => rules.get(name).is_some_and(|rule| {
+                rule.applies_to().contains(entry.entry_type) && rule.should_flag(entry, context)
+            }),
+            Self::Age(comparison, bound) => {
+                let age = entry.modified.elapsed().unwrap_or_default();
+                comparison.holds(age, *bound)
+            }
+            Self::Size(comparison, bound) => comparison.holds(entry.size, *bound),
+            Self::Under(dir) => strip_path_prefix(&entry.path, dir).is_some(),
+            Self::Glob(set) => set.matches(&entry.path),
+            Self::Ext(ext) => entry
+                .path
+                .extension()
+                .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case(ext)),
+            Self::Kind(kind) => entry.entry_type == *kind,
+            Self::Owner(name) => entry.owner.as_deref().is_some_and(|owner| {
+                owner.eq_ignore_ascii_case(name)
+                    || owner
+                        .rsplit_once('\\')
+                        .is_some_and(|(_, user)| user.eq_ignore_ascii_case(name))
+            }),
+            Self::ReadOnly => entry.readonly,
+            Self::System => entry.system,
+        }
+    }
+}
+
+/// Custom rule flagging entries that match an expression.
+pub struct ExprRule {
+    name: String,
+    description: String,
+    expr: Expr,
+    /// Rules the expression references
+    rules: HashMap<String, Arc<dyn DetectionRule>>,
+}
+
+impl ExprRule {
+    /// Creates a rule, resolving the rules `expr` references from `available`.
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        expr: Expr,
+        available: &HashMap<String, Arc<dyn DetectionRule>>,
+    ) -> Result<Self, ExprError> {
+        let mut rules = HashMap::new();
+        for referenced in expr.rule_names() {
+            let rule = available
+                .get(referenced)
+                .ok_or_else(|| ExprError::UnknownRule(referenced.to_string()))?;
+            rules.insert(referenced.to_string(), Arc::clone(rule));
+        }
+
+        Ok(Self {
+            name: name.into(),
+            description: description.into(),
+            expr,
+            rules,
+        })
+    }
+}
+
+impl DetectionRule for ExprRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn should_flag(&self, entry: &FileEntry, context: &ScanContext) -> bool {
+        self.expr.matches(entry, context, &self.rules)
+    }
+
+    fn reason(&self) -> String {
+        self.description.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Compare(Comparison),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => f.write_str(word),
+            Self::Str(text) => write!(f, "'{}'", text),
+            Self::Compare(comparison) => comparison.fmt(f),
+            Self::Open => f.write_str("("),
+            Self::Close => f.write_str(")"),
+        }
+    }
+}
+
+/// Splits `source` into tokens with their 1-based columns.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ExprError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+        let token = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '>' | '<' => {
+                let or_equal = chars.get(i + 1) == Some(&'=');
+                if or_equal {
+                    i += 1;
+                }
+                Token::Compare(match (c, or_equal) {
+                    ('>', false) => Comparison::Greater,
+                    ('>', true) => Comparison::GreaterOrEqual,
+                    ('<', false) => Comparison::Less,
+                    _ => Comparison::LessOrEqual,
+                })
+            }
+            '\'' | '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == c)
+                    .ok_or(ExprError::UnterminatedString(column))?;
+                let text = chars[i + 1..i + 1 + end].iter().collect();
+                i += end + 1;
+                Token::Str(text)
+            }
+            _ if is_word_char(c) => {
+                let len = chars[i..].iter().take_while(|&&c| is_word_char(c)).count();
+                let word = chars[i..i + len].iter().collect();
+                i += len - 1;
+                Token::Word(word)
+            }
+            _ => {
+                return Err(ExprError::Unexpected {
+                    column,
+                    found: c.to_string(),
+                })
+            }
+        };
+        tokens.push((column, token));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Recursive descent parser; `or` is the entry point.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Expr, ExprError> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ExprError> {
+        let mut expr = self.unary()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.operand()
+    }
+
+    fn operand(&mut self) -> Result<Expr, ExprError> {
+        let (column, token) = self.advance("an operand")?;
+        let word = match token {
+            Token::Open => {
+                let expr = self.or()?;
+                self.expect(Token::Close, "')'")?;
+                return Ok(expr);
+            }
+            Token::Word(word) if !is_keyword(&word) => word,
+            token => {
+                return Err(ExprError::Unexpected {
+                    column,
+                    found: token.to_string(),
+                })
+            }
+        };
+
+        let lower = word.to_ascii_lowercase();
+        match lower.as_str() {
+            "age" | "size" => {
+                let comparison = match self.advance("a comparison")? {
+                    (_, Token::Compare(comparison)) => comparison,
+                    (column, token) => {
+                        return Err(ExprError::Unexpected {
+                            column,
+                            found: token.to_string(),
+                        })
+                    }
+                };
+                let value = match self.advance("a value")? {
+                    (_, Token::Word(value)) => value,
+                    (column, token) => {
+                        return Err(ExprError::Unexpected {
+                            column,
+                            found: token.to_string(),
+                        })
+                    }
+                };
+                if lower == "age" {
+                    Ok(Expr::Age(comparison, parse_age(&value)?))
+                } else {
+                    let size = value
+                        .parse::<ByteSize>()
+                        .map_err(|e| ExprError::InvalidSize(value.clone(), e))?;
+                    Ok(Expr::Size(comparison, size.as_bytes()))
+                }
+            }
+            "file" => Ok(Expr::Kind(EntryType::File)),
+            "dir" => Ok(Expr::Kind(EntryType::Directory)),
+            "symlink" => Ok(Expr::Kind(EntryType::Symlink)),
+            "junction" => Ok(Expr::Kind(EntryType::Junction)),
+            "readonly" => Ok(Expr::ReadOnly),
+            "system" => Ok(Expr::System),
+            _ if self.peek() == Some(&Token::Open) => {
+                self.next += 1;
+                let argument = match self.advance("a quoted string")? {
+                    (_, Token::Str(text)) => text,
+                    (column, token) => {
+                        return Err(ExprError::Unexpected {
+                            column,
+                            found: token.to_string(),
+                        })
+                    }
+                };
+                self.expect(Token::Close, "')'")?;
+                match lower.as_str() {
+                    "under" => Ok(Expr::Under(PathBuf::from(expand_vars(&argument)))),
+                    "glob" => Expr::glob(&argument),
+                    "ext" => Ok(Expr::ext(&argument)),
+                    "owner" => Ok(Expr::Owner(argument)),
+                    _ => Err(ExprError::UnknownFunction(word)),
+                }
+            }
+            _ => Ok(Expr::Rule(word)),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn advance(&mut self, expected: &'static str) -> Result<(usize, Token), ExprError> {
+        let token = self
+            .tokens
+            .get(self.next)
+            .cloned()
+            .ok_or(ExprError::UnexpectedEnd(expected))?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token, description: &'static str) -> Result<(), ExprError> {
+        match self.advance(description)? {
+            (_, token) if token == expected => Ok(()),
+            (column, token) => Err(ExprError::Unexpected {
+                column,
+                found: token.to_string(),
+            }),
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found =
+            matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    ["and", "or", "not"]
+        .iter()
+        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+/// Parses `36h`, `180d`, `8w` or `2y` (365 days).
+fn parse_age(value: &str) -> Result<Duration, ExprError> {
+    let invalid = || ExprError::InvalidAge(value.to_string());
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let hours = match unit.to_ascii_lowercase().as_str() {
+        "h" => 1,
+        "d" => 24,
+        "w" => 24 * 7,
+        "y" => 24 * 365,
+        _ => return Err(invalid()),
+    };
+    number
+        .checked_mul(hours * 3600)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::SizeThresholdRule;
+    use std::time::SystemTime;
+
+    fn entry(path: &str, size: u64, age_days: u64) -> FileEntry {
+        FileEntry::new(
+            PathBuf::from(path),
+            size,
+            SystemTime::now() - Duration::from_secs(age_days * 86_400),
+            EntryType::File,
+        )
+    }
+
+    fn rules() -> HashMap<String, Arc<dyn DetectionRule>> {
+        let large: Arc<dyn DetectionRule> = Arc::new(SizeThresholdRule::new(1000));
+        HashMap::from([("large_file".to_string(), large)])
+    }
+
+    #[test]
+    fn test_parse_and_match() {
+        let expr =
+            Expr::parse("large_file AND age > 180d and not under('/data/archive') OR ext('.ISO')")
+                .unwrap();
+        let context = ScanContext::default();
+        let matches = |e: &FileEntry| expr.matches(e, &context, &rules());
+
+        assert!(matches(&entry("/data/old.bin", 5000, 200)));
+        assert!(!matches(&entry("/data/new.bin", 5000, 10)));
+        assert!(!matches(&entry("/data/old.txt", 10, 200)));
+        assert!(!matches(&entry("/data/archive/old.bin", 5000, 200)));
+        assert!(matches(&entry("/data/archive/disk.iso", 10, 0)));
+        assert_eq!(expr.rule_names(), vec!["large_file"]);
+    }
+
+    #[test]
+    fn test_precedence_and_operands() {
+        let context = ScanContext::default();
+        let old_log = entry("/logs/app.log", 2048, 40);
+        let check = |source: &str| {
+            Expr::parse(source)
+                .unwrap()
+                .matches(&old_log, &context, &HashMap::new())
+        };
+
+        assert!(check("NOT dir AND (size >= 2KB) AND glob('*.log')"));
+        assert!(check("size < 1KB OR age >= 4w"));
+        assert!(!check("NOT (file OR dir)"));
+        assert!(!check("age < 36h"));
+        assert!(check("glob(\"/logs/*\") and age <= 1y"));
+        assert!(!check("unknown_rule"), "unresolved rules never match");
+    }
+
+    #[test]
+    fn test_owner_and_attributes() {
+        let context = ScanContext::default();
+        let file = FileEntry {
+            owner: Some("CORP\\JDoe".to_string()),
+            readonly: true,
+            ..entry("/share/report.docx", 10, 0)
+        };
+        let check = |source: &str, entry: &FileEntry| {
+            Expr::parse(source)
+                .unwrap()
+                .matches(entry, &context, &HashMap::new())
+        };
+
+        assert!(check("owner('jdoe') AND readonly", &file));
+        assert!(check("owner('corp\\jdoe') AND NOT system", &file));
+        assert!(!check("owner('doe')", &file));
+        // Entries scanned without owners have none
+        assert!(!check("owner('jdoe')", &entry("/share/other.docx", 10, 0)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |source: &str| Expr::parse(source).unwrap_err();
+
+        assert!(matches!(error(""), ExprError::Empty));
+        assert!(matches!(
+            error("large_file AND"),
+            ExprError::UnexpectedEnd(_)
+        ));
+        assert!(matches!(
+            error("large_file OR OR dir"),
+            ExprError::Unexpected { column: 15, .. }
+        ));
+        assert!(matches!(error("(file"), ExprError::UnexpectedEnd("')'")));
+        assert!(matches!(error("age > 10"), ExprError::InvalidAge(_)));
+        assert!(matches!(error("size > 10"), ExprError::InvalidSize(..)));
+        assert!(matches!(
+            error("under('/tmp"),
+            ExprError::UnterminatedString(7)
+        ));
+        assert!(matches!(error("name('me')"), ExprError::UnknownFunction(_)));
+        assert!(matches!(error("file & dir"), ExprError::Unexpected { .. }));
+
+        let expr = Expr::parse("large_file AND missing").unwrap();
+        assert!(matches!(
+            ExprRule::new("r", "d", expr, &rules()),
+            Err(ExprError::UnknownRule(name)) if name == "missing"
+        ));
+    }
+}
@@ -0,0 +1,186 @@
+//! Keep rules that veto detections.
+
+use crate::detector::engine::DetectionResult;
+use glob::{MatchOptions, Pattern, PatternError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Rule that protects matching paths from being flagged.
+///
+/// Keep rules are evaluated after detection. An entry is vetoed when a
+/// pattern matches its path or any of its ancestors, so a single pattern such
+/// as `D:/Clients` protects the whole subtree. Rules can also match
+/// directories by name and directories holding a marker file, which is how
+/// `detector.archive` keeps consciously archived material.
+#[derive(Debug, Clone)]
+pub struct KeepRule {
+    name: String,
+    patterns: Vec<Pattern>,
+    dir_names: Vec<Pattern>,
+    marker: Option<String>,
+    /// Whether each directory checked holds the marker; flagged entries
+    /// mostly share their ancestors
+    marked: Arc<Mutex<HashMap<PathBuf, bool>>>,
+}
+
+impl KeepRule {
+    /// Creates a keep rule from glob patterns.
+    ///
+    /// Backslashes are treated as path separators so Windows-style patterns
+    /// work on every platform.
+    pub fn new<S: AsRef<str>>(
+        name: impl Into<String>,
+        patterns: &[S],
+    ) -> Result<Self, PatternError> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Pattern::new(&normalize(p.as_ref())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            name: name.into(),
+            patterns,
+            dir_names: Vec::new(),
+            marker: None,
+            marked: Arc::default(),
+        })
+    }
+
+    /// Also matches paths inside directories whose name matches one of the
+    /// glob patterns `names`, such as `archive` or `do-not-delete*`,
+    /// ignoring case.
+    pub fn with_dir_names<S: AsRef<str>>(mut self, names: &[S]) -> Result<Self, PatternError> {
+        self.dir_names = names
+            .iter()
+            .map(|n| Pattern::new(n.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self)
+    }
+
+    /// Also matches paths inside directories holding a file named `marker`,
+    /// checked on disk.
+    pub fn with_marker(mut self, marker: impl Into<String>) -> Self {
+        self.marker = Some(marker.into());
+        self
+    }
+
+    /// Returns the name of this rule.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true if the path or one of its ancestors matches a pattern,
+    /// has a matching directory name or holds the marker file.
+    pub fn matches(&self, path: &Path) -> bool {
+        path.ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .any(|ancestor| {
+                let candidate = normalize(&ancestor.to_string_lossy());
+                self.patterns.iter().any(|p| p.matches(&candidate))
+                    || self.has_dir_name(ancestor)
+                    || self.has_marker(ancestor)
+            })
+    }
+
+    fn has_dir_name(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+            return false;
+        };
+        self.dir_names
+            .iter()
+            .any(|p| p.matches_with(&name, options))
+    }
+
+    fn has_marker(&self, path: &Path) -> bool {
+        let Some(marker) = &self.marker else {
+            return false;
+        };
+        let mut marked = self.marked.lock().unwrap_or_else(|e| e.into_inner());
+        *marked
+            .entry(path.to_path_buf())
+            .or_insert_with(|| path.join(marker).is_file())
+    }
+}
+
+/// A detection that was suppressed by a keep rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VetoedDetection {
+    /// The detection that would otherwise have been reported
+    pub detection: DetectionResult,
+
+    /// Name of the keep rule that vetoed it
+    pub keep_rule: String,
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_rule_matches_path() {
+        let rule = KeepRule::new("clients", &["/data/clients"]).unwrap();
+
+        assert!(rule.matches(Path::new("/data/clients")));
+        assert!(!rule.matches(Path::new("/data/other")));
+    }
+
+    #[test]
+    fn test_keep_rule_matches_ancestors() {
+        let rule = KeepRule::new("clients", &["/data/clients"]).unwrap();
+
+        assert!(rule.matches(Path::new("/data/clients/acme/node_modules")));
+        assert!(!rule.matches(Path::new("/data")));
+    }
+
+    #[test]
+    fn test_keep_rule_glob_patterns() {
+        let rule = KeepRule::new("archives", &["**/archive", "*.keep"]).unwrap();
+
+        assert!(rule.matches(Path::new("/home/user/archive/old/build")));
+        assert!(rule.matches(Path::new("notes.keep")));
+        assert!(!rule.matches(Path::new("/home/user/build")));
+    }
+
+    #[test]
+    fn test_keep_rule_windows_separators() {
+        let rule = KeepRule::new("clients", &["D:\\Clients"]).unwrap();
+
+        assert!(rule.matches(Path::new("D:/Clients/acme/target")));
+    }
+
+    #[test]
+    fn test_keep_rule_dir_names_and_marker() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let kept = temp.path().join("photos/2019");
+        std::fs::create_dir_all(&kept).unwrap();
+        std::fs::write(kept.join(".megamaid-keep"), "").unwrap();
+
+        let rule = KeepRule::new("archive", &[] as &[&str])
+            .unwrap()
+            .with_dir_names(&["archive", "do-not-delete*"])
+            .unwrap()
+            .with_marker(".megamaid-keep");
+
+        assert!(rule.matches(Path::new("/home/user/Archive/old/build")));
+        assert!(rule.matches(Path::new("/data/DO-NOT-DELETE-taxes/scan.pdf")));
+        assert!(!rule.matches(Path::new("/data/archived/target")));
+        assert!(rule.matches(&kept));
+        assert!(rule.matches(&kept.join("raw/big.cr2")));
+        assert!(!rule.matches(&temp.path().join("photos/2020/big.cr2")));
+    }
+
+    #[test]
+    fn test_keep_rule_invalid_pattern() {
+        assert!(KeepRule::new("bad", &["[unclosed"]).is_err());
+    }
+}
@@ -1,7 +1,28 @@
 //! Cleanup candidate detection rules and engine.
 
+pub mod dev_cache;
+pub mod duplicates;
 pub mod engine;
+pub mod expr;
+pub mod keep;
+pub mod pattern;
+pub mod precious;
 pub mod rules;
+pub mod stale_repo;
+pub mod system_cache;
 
-pub use engine::{DetectionEngine, DetectionResult, ScanContext};
-pub use rules::{BuildArtifactRule, DetectionRule, SizeThresholdRule};
+pub use dev_cache::{DevCache, DevCacheRule, DEV_CACHES};
+pub use duplicates::{DuplicateFinder, DuplicateGroup, DuplicateRule};
+pub use engine::{DetectionEngine, DetectionReport, DetectionResult, ScanContext};
+pub use expr::{Comparison, Expr, ExprError, ExprRule};
+pub use keep::{KeepRule, VetoedDetection};
+pub use pattern::{PatternError, PatternRule, PatternSyntax};
+pub use precious::PreciousClass;
+pub use rules::{
+    AlternateStreamRule, ArtifactEcosystem, BuildArtifactRule, DeleteMarkerRule, DetectionRule,
+    EmptyEntryRule, EntryKinds, EnvStatus, GrowthRule, OrphanedEnvRule, PathPruneHints, PythonEnv,
+    PythonEnvKind, ScopedRule, SizeThresholdRule, ARTIFACT_ECOSYSTEMS, BUILTIN_RULES,
+    PLACEHOLDER_FILES,
+};
+pub use stale_repo::StaleRepoRule;
+pub use system_cache::{SystemCache, SystemCacheRule, SYSTEM_CACHES, SYSTEM_CACHE_RULES};
@@ -0,0 +1,263 @@
+//! Rules flagging entries by the shape of their path.
+//!
+//! A pattern rule (`detector.pattern_rules`) flags entries whose path
+//! relative to the scan root matches one of its patterns, so path-shaped
+//! junk such as `**/*.bak`, `**/Thumbs.db` or `**/~$*` gets cleaned up with
+//! an action of its own, without writing Rust. Relative paths use `/` as the
+//! separator on every platform.
+//!
+//! Patterns are globs by default and match the whole relative path: `*`
+//! stays within one directory, so `**/` is needed to match at any depth, and
+//! case follows the platform. With `syntax: regex` they are regular
+//! expressions, which also have to match the whole relative path; start one
+//! with `(?i)` to ignore case.
+//!
+//! The reason recorded for each entry comes from the rule's `reason`
+//! template, where `{pattern}`, `{path}` (relative) and `{name}` are
+//! replaced.
+
+use crate::detector::engine::ScanContext;
+use crate::detector::rules::DetectionRule;
+use crate::models::{FileEntry, PathCase};
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Reason template used when a rule has none.
+pub const DEFAULT_REASON: &str = "Matches {pattern}";
+
+/// Errors compiling a pattern rule.
+#[derive(Debug, Error)]
+pub enum PatternError {
+    /// The rule has no patterns
+    #[error("Pattern rule has no patterns")]
+    Empty,
+
+    /// A glob can't be compiled
+    #[error("Invalid glob '{0}': {1}")]
+    InvalidGlob(String, glob::PatternError),
+
+    /// A regular expression can't be compiled
+    #[error("Invalid regex '{0}': {1}")]
+    InvalidRegex(String, regex::Error),
+}
+
+/// How the patterns of a rule are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternSyntax {
+    /// Glob patterns such as `**/*.bak`
+    #[default]
+    Glob,
+
+    /// Regular expressions such as `(?i)(.*/)?~\$[^/]*`
+    Regex,
+}
+
+/// A compiled pattern.
+#[derive(Debug, Clone)]
+enum Matcher {
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn matches(&self, relative: &str) -> bool {
+        match self {
+            Self::Glob(pattern) => {
+                let options = MatchOptions {
+                    case_sensitive: PathCase::native() == PathCase::Sensitive,
+                    require_literal_separator: true,
+                    require_literal_leading_dot: false,
+                };
+                pattern.matches_with(relative, options)
+            }
+            Self::Regex(regex) => regex.is_match(relative),
+        }
+    }
+}
+
+/// Rule that flags entries whose path relative to the scan root matches a
+/// glob or regex pattern.
+#[derive(Debug, Clone)]
+pub struct PatternRule {
+    name: String,
+    patterns: Vec<(String, Matcher)>,
+    reason: String,
+    roots: Vec<PathBuf>,
+}
+
+impl PatternRule {
+    /// Compiles a rule flagging entries matching any of `patterns`.
+    pub fn new<S: AsRef<str>>(
+        name: impl Into<String>,
+        patterns: &[S],
+        syntax: PatternSyntax,
+    ) -> Result<Self, PatternError> {
+        if patterns.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        let patterns = patterns
+            .iter()
+            .map(|source| {
+                let source = source.as_ref();
+                let matcher = match syntax {
+                    PatternSyntax::Glob => Pattern::new(source)
+                        .map(Matcher::Glob)
+                        .map_err(|e| PatternError::InvalidGlob(source.to_string(), e))?,
+                    // Anchored, so the whole relative path has to match
+                    PatternSyntax::Regex => Regex::new(&format!("^(?:{})$", source))
+                        .map(Matcher::Regex)
+                        .map_err(|e| PatternError::InvalidRegex(source.to_string(), e))?,
+                };
+                Ok((source.to_string(), matcher))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            name: name.into(),
+            patterns,
+            reason: DEFAULT_REASON.to_string(),
+            roots: Vec::new(),
+        })
+    }
+
+    /// Uses `template` for reasons, replacing `{pattern}`, `{path}` and
+    /// `{name}`.
+    pub fn with_reason(mut self, template: impl Into<String>) -> Self {
+        self.reason = template.into();
+        self
+    }
+
+    /// Matches paths relative to the deepest of `roots` they lie under;
+    /// paths under none of them are matched as they are.
+    pub fn with_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = roots.to_vec();
+        self
+    }
+
+    /// Returns `path` relative to its scan root, with `/` separators.
+    fn relative(&self, path: &Path) -> String {
+        let relative = self
+            .roots
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .min_by_key(|relative| relative.components().count());
+        match relative {
+            Some(relative) => relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => path.to_string_lossy().replace('\\', "/"),
+        }
+    }
+
+    /// Returns the first pattern matching `entry` and its relative path.
+    fn matching(&self, entry: &FileEntry) -> Option<(&str, String)> {
+        let relative = self.relative(&entry.path);
+        let (source, _) = self
+            .patterns
+            .iter()
+            .find(|(_, matcher)| matcher.matches(&relative))?;
+        Some((source, relative))
+    }
+}
+
+impl DetectionRule for PatternRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        self.matching(entry).is_some()
+    }
+
+    fn reason(&self) -> String {
+        let sources: Vec<&str> = self.patterns.iter().map(|(s, _)| s.as_str()).collect();
+        format!("Matches {}", sources.join(", "))
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        let Some((pattern, relative)) = self.matching(entry) else {
+            return self.reason();
+        };
+        let name = entry
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        self.reason
+            .replace("{pattern}", pattern)
+            .replace("{path}", &relative)
+            .replace("{name}", &name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use std::time::SystemTime;
+
+    fn entry(path: &str) -> FileEntry {
+        FileEntry::new(PathBuf::from(path), 10, SystemTime::now(), EntryType::File)
+    }
+
+    #[test]
+    fn test_glob_patterns_match_relative_paths() {
+        let rule = PatternRule::new("junk", &["**/*.bak", "**/Thumbs.db"], PatternSyntax::Glob)
+            .unwrap()
+            .with_roots(&[PathBuf::from("/data"), PathBuf::from("/data/projects")]);
+        let context = ScanContext::default();
+
+        assert!(rule.should_flag(&entry("/data/report.bak"), &context));
+        assert!(rule.should_flag(&entry("/data/a/b/Thumbs.db"), &context));
+        assert!(!rule.should_flag(&entry("/data/report.bak.txt"), &context));
+
+        // Paths are relative to the deepest root holding them
+        let rule = PatternRule::new("logs", &["app/*.log"], PatternSyntax::Glob)
+            .unwrap()
+            .with_roots(&[PathBuf::from("/data"), PathBuf::from("/data/projects")]);
+        assert!(rule.should_flag(&entry("/data/projects/app/x.log"), &context));
+        assert!(rule.should_flag(&entry("/data/app/x.log"), &context));
+        assert!(!rule.should_flag(&entry("/data/app/sub/x.log"), &context));
+    }
+
+    #[test]
+    fn test_regex_patterns_match_whole_path() {
+        let rule = PatternRule::new("office_locks", &[r"(.*/)?~\$[^/]*"], PatternSyntax::Regex)
+            .unwrap()
+            .with_roots(&[PathBuf::from("/docs")]);
+        let context = ScanContext::default();
+
+        assert!(rule.should_flag(&entry("/docs/~$report.docx"), &context));
+        assert!(rule.should_flag(&entry("/docs/2024/~$budget.xlsx"), &context));
+        assert!(!rule.should_flag(&entry("/docs/not~$anchored.docx"), &context));
+
+        assert!(matches!(
+            PatternRule::new("bad", &["("], PatternSyntax::Regex),
+            Err(PatternError::InvalidRegex(..))
+        ));
+        assert!(matches!(
+            PatternRule::new("none", &[] as &[&str], PatternSyntax::Glob),
+            Err(PatternError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_reason_template() {
+        let rule = PatternRule::new("backups", &["**/*.bak", "**/*.old"], PatternSyntax::Glob)
+            .unwrap()
+            .with_roots(&[PathBuf::from("/data")])
+            .with_reason("Backup {name} at {path} ({pattern})");
+
+        assert_eq!(
+            rule.reason_for(&entry("/data/cfg/app.old")),
+            "Backup app.old at cfg/app.old (**/*.old)"
+        );
+        assert_eq!(rule.reason(), "Matches **/*.bak, **/*.old");
+    }
+}
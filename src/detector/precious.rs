@@ -0,0 +1,92 @@
+//! Classification of large files that are never deleted automatically.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// A class of file that is costly or impossible to recreate.
+///
+/// Plans mark entries of these classes as `never_delete`, and the executor
+/// refuses to remove such entries even if their action is edited to delete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreciousClass {
+    /// Outlook data files (`.pst`, `.ost`)
+    MailArchive,
+
+    /// Virtual machine disks (`.vmdk`, `.vdi`, `.vhd`, `.vhdx`, `.qcow2`)
+    DiskImage,
+
+    /// KeePass password databases (`.kdbx`, `.kdb`)
+    PasswordDatabase,
+
+    /// Database backups and dumps (`.bak`, `.bacpac`, `.sql`, `.dump`)
+    DatabaseBackup,
+}
+
+impl PreciousClass {
+    /// Every class, in display order.
+    pub const ALL: [Self; 4] = [
+        Self::MailArchive,
+        Self::DiskImage,
+        Self::PasswordDatabase,
+        Self::DatabaseBackup,
+    ];
+
+    /// Returns the lowercase extensions (without dot) of this class.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::MailArchive => &["pst", "ost"],
+            Self::DiskImage => &["vmdk", "vdi", "vhd", "vhdx", "qcow2"],
+            Self::PasswordDatabase => &["kdbx", "kdb"],
+            Self::DatabaseBackup => &["bak", "bacpac", "sql", "dump"],
+        }
+    }
+
+    /// Returns the class of the file at `path`, judged by its extension.
+    pub fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|class| class.extensions().contains(&extension.as_str()))
+    }
+}
+
+impl fmt::Display for PreciousClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::MailArchive => "mail archive",
+            Self::DiskImage => "virtual machine disk",
+            Self::PasswordDatabase => "password database",
+            Self::DatabaseBackup => "database backup",
+        };
+        f.write_str(label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_by_extension() {
+        assert_eq!(
+            PreciousClass::of(Path::new("Outlook/archive.PST")),
+            Some(PreciousClass::MailArchive)
+        );
+        assert_eq!(
+            PreciousClass::of(Path::new("vms/win11.vhdx")),
+            Some(PreciousClass::DiskImage)
+        );
+        assert_eq!(
+            PreciousClass::of(Path::new("Passwords.kdbx")),
+            Some(PreciousClass::PasswordDatabase)
+        );
+        assert_eq!(
+            PreciousClass::of(Path::new("db/nightly.bak")),
+            Some(PreciousClass::DatabaseBackup)
+        );
+        assert_eq!(PreciousClass::of(Path::new("video.mp4")), None);
+        assert_eq!(PreciousClass::of(Path::new("vmdk")), None);
+    }
+}
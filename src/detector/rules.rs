@@ -1,7 +1,12 @@
 //! Detection rule implementations.
 
 use crate::detector::engine::ScanContext;
-use crate::models::{EntryType, FileEntry};
+use crate::models::{EntryType, FileEntry, Protection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 /// Trait for detection rules that identify cleanup candidates.
 pub trait DetectionRule: Send + Sync {
@@ -13,12 +18,325 @@ pub trait DetectionRule: Send + Sync {
 
     /// Returns the reason why this entry was flagged.
     fn reason(&self) -> String;
+
+    /// Returns the reason for flagging a specific entry.
+    ///
+    /// Rules can override this to include entry details such as the actual size.
+    fn reason_for(&self, _entry: &FileEntry) -> String {
+        self.reason()
+    }
+
+    /// Returns the kinds of entries this rule applies to.
+    ///
+    /// The engine never evaluates a rule against entries outside this mask.
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::ALL
+    }
+
+    /// Returns the parts of the tree this rule never flags anything in.
+    ///
+    /// The engine never evaluates a rule against entries there, and a scan
+    /// can leave out what every rule prunes. The default prunes nothing.
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::default()
+    }
+
+    /// Returns true if the rule flags whole repositories.
+    ///
+    /// Repository roots are protected from every other rule; the engine
+    /// still evaluates this rule against them.
+    fn flags_repositories(&self) -> bool {
+        false
+    }
+
+    /// Returns the protection that keeps an entry this rule flagged for
+    /// review instead of deletion, with its detail.
+    ///
+    /// The engine asks only for entries the rule flagged. The default holds
+    /// nothing back.
+    fn downgrade(&self, _entry: &FileEntry) -> Option<(Protection, String)> {
+        None
+    }
+
+    /// Decides for a batch of entries at once, returning one verdict per
+    /// entry, in order.
+    ///
+    /// Rules that can share work across entries override this; the default
+    /// asks [`DetectionRule::should_flag`] for each entry. Either way the
+    /// verdicts must match what `should_flag` would say.
+    fn evaluate_batch(&self, entries: &[&FileEntry], context: &ScanContext) -> Vec<bool> {
+        entries
+            .iter()
+            .map(|entry| self.should_flag(entry, context))
+            .collect()
+    }
+}
+
+/// Parts of the tree a rule never flags anything in, see
+/// [`DetectionRule::prefilter`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathPruneHints {
+    /// Names of directories that are never flagged, nor is anything inside
+    /// them
+    pub skip_dir_names: Vec<String>,
+}
+
+impl PathPruneHints {
+    /// Prunes directories with any of `names`, wherever they are.
+    pub fn skip_dirs<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            skip_dir_names: names.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns true if nothing is pruned.
+    pub fn is_empty(&self) -> bool {
+        self.skip_dir_names.is_empty()
+    }
+
+    /// Returns true if `path` is, or lies inside, a pruned directory.
+    pub fn covers(&self, path: &Path) -> bool {
+        !self.is_empty()
+            && path.components().any(|c| {
+                self.skip_dir_names
+                    .iter()
+                    .any(|name| c.as_os_str() == name.as_str())
+            })
+    }
+
+    /// Returns what both `self` and `other` prune.
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            skip_dir_names: self
+                .skip_dir_names
+                .iter()
+                .filter(|name| other.skip_dir_names.contains(name))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Names of the built-in rules, which custom rule expressions can reference.
+pub const BUILTIN_RULES: [&str; 14] = [
+    "delete_marker",
+    "build_artifact",
+    "dev_cache",
+    "temp_files",
+    "browser_cache",
+    "thumbnail_cache",
+    "crash_dump",
+    "orphaned_env",
+    "stale_repo",
+    "rapid_growth",
+    "duplicate",
+    "empty_entry",
+    "large_file",
+    "alternate_streams",
+];
+
+/// Shared rules, such as those custom rule expressions also reference.
+impl<R: DetectionRule + ?Sized> DetectionRule for Arc<R> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn should_flag(&self, entry: &FileEntry, context: &ScanContext) -> bool {
+        (**self).should_flag(entry, context)
+    }
+
+    fn reason(&self) -> String {
+        (**self).reason()
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        (**self).reason_for(entry)
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        (**self).applies_to()
+    }
+
+    fn prefilter(&self, context: &ScanContext) -> PathPruneHints {
+        (**self).prefilter(context)
+    }
+
+    fn flags_repositories(&self) -> bool {
+        (**self).flags_repositories()
+    }
+
+    fn downgrade(&self, entry: &FileEntry) -> Option<(Protection, String)> {
+        (**self).downgrade(entry)
+    }
+
+    fn evaluate_batch(&self, entries: &[&FileEntry], context: &ScanContext) -> Vec<bool> {
+        (**self).evaluate_batch(entries, context)
+    }
+}
+
+/// Mask of entry kinds a detection rule applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EntryKinds {
+    /// Regular files
+    pub files: bool,
+
+    /// Directories
+    pub directories: bool,
+
+    /// Symbolic links and junctions
+    pub symlinks: bool,
+}
+
+impl EntryKinds {
+    /// Applies to every kind of entry.
+    pub const ALL: Self = Self {
+        files: true,
+        directories: true,
+        symlinks: true,
+    };
+
+    /// Applies to regular files only.
+    pub const FILES: Self = Self {
+        files: true,
+        directories: false,
+        symlinks: false,
+    };
+
+    /// Applies to directories only.
+    pub const DIRECTORIES: Self = Self {
+        files: false,
+        directories: true,
+        symlinks: false,
+    };
+
+    /// Returns true if entries of the given type are covered by this mask.
+    pub fn contains(&self, entry_type: EntryType) -> bool {
+        match entry_type {
+            EntryType::File => self.files,
+            EntryType::Directory => self.directories,
+            EntryType::Symlink | EntryType::Junction => self.symlinks,
+        }
+    }
+
+    /// Returns true if no entry kind is selected.
+    pub fn is_empty(&self) -> bool {
+        !self.files && !self.directories && !self.symlinks
+    }
+}
+
+impl Default for EntryKinds {
+    fn default() -> Self {
+        Self::ALL
+    }
 }
 
-/// Rule that flags files exceeding a size threshold.
+/// Restricts an existing rule to a subset of entry kinds.
+///
+/// Useful when a rule's natural scope is broader than desired, e.g. limiting
+/// `SizeThresholdRule` to individual files.
+pub struct ScopedRule {
+    inner: Box<dyn DetectionRule>,
+    kinds: EntryKinds,
+}
+
+impl ScopedRule {
+    /// Wraps `inner` so it only applies to `kinds`.
+    ///
+    /// The effective mask is the intersection with the inner rule's own mask.
+    pub fn new(inner: Box<dyn DetectionRule>, kinds: EntryKinds) -> Self {
+        let own = inner.applies_to();
+        let kinds = EntryKinds {
+            files: own.files && kinds.files,
+            directories: own.directories && kinds.directories,
+            symlinks: own.symlinks && kinds.symlinks,
+        };
+        Self { inner, kinds }
+    }
+}
+
+impl DetectionRule for ScopedRule {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn should_flag(&self, entry: &FileEntry, context: &ScanContext) -> bool {
+        self.inner.should_flag(entry, context)
+    }
+
+    fn reason(&self) -> String {
+        self.inner.reason()
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        self.inner.reason_for(entry)
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        self.kinds
+    }
+
+    fn prefilter(&self, context: &ScanContext) -> PathPruneHints {
+        self.inner.prefilter(context)
+    }
+
+    fn flags_repositories(&self) -> bool {
+        self.inner.flags_repositories()
+    }
+
+    fn downgrade(&self, entry: &FileEntry) -> Option<(Protection, String)> {
+        self.inner.downgrade(entry)
+    }
+
+    fn evaluate_batch(&self, entries: &[&FileEntry], context: &ScanContext) -> Vec<bool> {
+        self.inner.evaluate_batch(entries, context)
+    }
+}
+
+/// Rule that flags files and directories exceeding a size threshold.
+///
+/// Files and directories use separate thresholds, since a directory's size is
+/// the sum of its contents and routinely exceeds what is reasonable for a file.
 pub struct SizeThresholdRule {
-    /// Minimum size in bytes to flag
-    pub threshold_bytes: u64,
+    /// Minimum file size in bytes to flag
+    pub file_threshold_bytes: u64,
+
+    /// Minimum directory size in bytes to flag
+    pub directory_threshold_bytes: u64,
+
+    /// Also flag cloud placeholders, which take up no disk space
+    pub include_cloud_placeholders: bool,
+}
+
+impl SizeThresholdRule {
+    /// Creates a rule using the same threshold for files and directories.
+    pub fn new(threshold_bytes: u64) -> Self {
+        Self::with_thresholds(threshold_bytes, threshold_bytes)
+    }
+
+    /// Creates a rule with independent file and directory thresholds.
+    pub fn with_thresholds(file_threshold_bytes: u64, directory_threshold_bytes: u64) -> Self {
+        Self {
+            file_threshold_bytes,
+            directory_threshold_bytes,
+            include_cloud_placeholders: false,
+        }
+    }
+
+    /// Flag cloud placeholders too; by default they are never flagged, since
+    /// deleting one frees no disk space.
+    pub fn with_cloud_placeholders(mut self, include: bool) -> Self {
+        self.include_cloud_placeholders = include;
+        self
+    }
+
+    /// Returns the threshold that applies to the given entry type.
+    pub fn threshold_for(&self, entry_type: EntryType) -> u64 {
+        match entry_type {
+            EntryType::File | EntryType::Symlink => self.file_threshold_bytes,
+            EntryType::Directory | EntryType::Junction => self.directory_threshold_bytes,
+        }
+    }
 }
 
 impl DetectionRule for SizeThresholdRule {
@@ -27,20 +345,675 @@ impl DetectionRule for SizeThresholdRule {
     }
 
     fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
-        entry.size >= self.threshold_bytes
+        (self.include_cloud_placeholders || !entry.cloud_placeholder)
+            && entry.size >= self.threshold_for(entry.entry_type)
     }
 
     fn reason(&self) -> String {
         format!(
             "File exceeds size threshold of {} MB",
+            self.file_threshold_bytes / 1_048_576
+        )
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        let kind = match entry.entry_type {
+            EntryType::File => "File",
+            EntryType::Directory => "Directory",
+            EntryType::Symlink => "Symlink",
+            EntryType::Junction => "Junction",
+        };
+        format!(
+            "{} size {:.1} MB exceeds threshold of {} MB",
+            kind,
+            entry.size as f64 / 1_048_576.0,
+            self.threshold_for(entry.entry_type) / 1_048_576
+        )
+    }
+}
+
+/// Rule that flags files whose NTFS alternate data streams are large.
+///
+/// Streams such as `Zone.Identifier` are tiny; a large stream is usually data
+/// hidden from normal directory listings. Only entries scanned with stream
+/// enumeration enabled carry stream information.
+pub struct AlternateStreamRule {
+    /// Minimum combined stream size in bytes to flag
+    pub threshold_bytes: u64,
+}
+
+impl AlternateStreamRule {
+    /// Creates a rule flagging files with at least `threshold_bytes` of streams.
+    pub fn new(threshold_bytes: u64) -> Self {
+        Self { threshold_bytes }
+    }
+}
+
+impl DetectionRule for AlternateStreamRule {
+    fn name(&self) -> &str {
+        "alternate_streams"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        !entry.streams.is_empty() && entry.stream_size() >= self.threshold_bytes
+    }
+
+    fn reason(&self) -> String {
+        format!(
+            "Alternate data streams exceed {} MB",
             self.threshold_bytes / 1_048_576
         )
     }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        let largest = entry.streams.iter().max_by_key(|s| s.size);
+        match largest {
+            Some(largest) => format!(
+                "{} alternate data stream(s) total {:.1} MB (largest: '{}', {:.1} MB)",
+                entry.streams.len(),
+                entry.stream_size() as f64 / 1_048_576.0,
+                largest.name,
+                largest.size as f64 / 1_048_576.0
+            ),
+            None => self.reason(),
+        }
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::FILES
+    }
+}
+
+/// Rule that flags directories holding a delete marker file.
+///
+/// Dropping a `.megamaid-delete` file into a folder lets whoever owns it
+/// authorize its cleanup ahead of time, for whoever runs megamaid later. The
+/// first line of the marker, if any, is quoted in the reason as a note.
+pub struct DeleteMarkerRule {
+    /// File name of the marker
+    pub marker: String,
+}
+
+impl DeleteMarkerRule {
+    /// Creates a rule flagging directories that contain a file named `marker`.
+    pub fn new(marker: impl Into<String>) -> Self {
+        Self {
+            marker: marker.into(),
+        }
+    }
+}
+
+impl DetectionRule for DeleteMarkerRule {
+    fn name(&self) -> &str {
+        "delete_marker"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        entry.path.join(&self.marker).is_file()
+    }
+
+    fn reason(&self) -> String {
+        format!("Marked for deletion by a {} file", self.marker)
+    }
+
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::skip_dirs(VCS_DIRS)
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        let note = std::fs::read_to_string(entry.path.join(&self.marker))
+            .ok()
+            .and_then(|text| text.lines().next().map(|line| line.trim().to_string()))
+            .filter(|line| !line.is_empty());
+        match note {
+            Some(note) => format!("{}: \"{}\"", self.reason(), note),
+            None => self.reason(),
+        }
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::DIRECTORIES
+    }
+}
+
+/// Zero-byte files that mean something by existing, never flagged by
+/// [`EmptyEntryRule`] unless configured otherwise.
+pub const PLACEHOLDER_FILES: [&str; 5] =
+    [".gitkeep", ".keep", "__init__.py", "py.typed", ".nojekyll"];
+
+/// Version control metadata directories, whose empty directories the tools
+/// expect to exist.
+pub(crate) const VCS_DIRS: [&str; 3] = [".git", ".hg", ".svn"];
+
+/// Rule that flags zero-byte files and directories holding no files.
+///
+/// Empty directories are mostly what earlier cleanups leave behind. A
+/// directory is empty when nothing but directories lie beneath it, however
+/// deep; whatever can't be read counts as content. Zero-byte files named in
+/// `keep_names` are never flagged, and neither is anything inside version
+/// control metadata.
+pub struct EmptyEntryRule {
+    /// File names of zero-byte files that are never flagged
+    pub keep_names: Vec<String>,
+}
+
+impl EmptyEntryRule {
+    /// Creates a rule that leaves zero-byte files named in `keep_names` alone.
+    pub fn new<S: Into<String>>(keep_names: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            keep_names: keep_names.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns true if no files lie anywhere beneath `dir`.
+    fn is_empty_dir(dir: &Path) -> bool {
+        walkdir::WalkDir::new(dir)
+            .min_depth(1)
+            .into_iter()
+            .all(|entry| entry.is_ok_and(|entry| entry.file_type().is_dir()))
+    }
+}
+
+impl Default for EmptyEntryRule {
+    fn default() -> Self {
+        Self::new(PLACEHOLDER_FILES)
+    }
+}
+
+impl DetectionRule for EmptyEntryRule {
+    fn name(&self) -> &str {
+        "empty_entry"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, context: &ScanContext) -> bool {
+        if self.prefilter(context).covers(&entry.path) || entry.size > 0 {
+            return false;
+        }
+
+        match entry.entry_type {
+            EntryType::File => {
+                let name = entry.path.file_name().unwrap_or_default();
+                !self.keep_names.iter().any(|keep| name == keep.as_str())
+            }
+            EntryType::Directory => Self::is_empty_dir(&entry.path),
+            EntryType::Symlink | EntryType::Junction => false,
+        }
+    }
+
+    fn reason(&self) -> String {
+        "Empty file or directory".to_string()
+    }
+
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::skip_dirs(VCS_DIRS)
+    }
+
+    fn evaluate_batch(&self, entries: &[&FileEntry], context: &ScanContext) -> Vec<bool> {
+        // Directories holding a file of the batch aren't empty, without walking them
+        let mut occupied: HashSet<&Path> = HashSet::new();
+        for entry in entries
+            .iter()
+            .filter(|e| e.entry_type != EntryType::Directory)
+        {
+            for dir in entry.path.ancestors().skip(1) {
+                if !occupied.insert(dir) {
+                    break;
+                }
+            }
+        }
+
+        entries
+            .iter()
+            .map(|entry| {
+                let occupied = entry.entry_type == EntryType::Directory
+                    && occupied.contains(entry.path.as_path());
+                !occupied && self.should_flag(entry, context)
+            })
+            .collect()
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        match entry.entry_type {
+            EntryType::Directory => "Empty directory (no files inside)".to_string(),
+            _ => "Empty file (0 bytes)".to_string(),
+        }
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds {
+            files: true,
+            directories: true,
+            symlinks: false,
+        }
+    }
+}
+
+/// Rule that flags directories that grew quickly since the previous scan.
+///
+/// Catches runaway logs and caches before they cross a static size
+/// threshold. A directory is flagged when it grew by at least
+/// `min_growth_bytes`, or when it holds at least `min_size_bytes` and grew by
+/// at least `min_growth_percent`. Directories missing from the previous scan
+/// are never flagged.
+pub struct GrowthRule {
+    /// Directory sizes from the previous scan
+    previous: HashMap<PathBuf, u64>,
+    /// Absolute growth to flag
+    pub min_growth_bytes: u64,
+    /// Relative growth to flag
+    pub min_growth_percent: u64,
+    /// Size a directory must have for relative growth to count
+    pub min_size_bytes: u64,
+}
+
+impl GrowthRule {
+    /// Creates a rule comparing against the directory sizes of the previous scan.
+    pub fn new(
+        previous: HashMap<PathBuf, u64>,
+        min_growth_bytes: u64,
+        min_growth_percent: u64,
+        min_size_bytes: u64,
+    ) -> Self {
+        Self {
+            previous,
+            min_growth_bytes,
+            min_growth_percent,
+            min_size_bytes,
+        }
+    }
+
+    /// Returns the previous size of the entry and how much it grew since.
+    fn growth(&self, entry: &FileEntry) -> Option<(u64, u64)> {
+        let before = *self.previous.get(&entry.path)?;
+        let grown = entry.size.checked_sub(before).filter(|&grown| grown > 0)?;
+        Some((before, grown))
+    }
+}
+
+impl DetectionRule for GrowthRule {
+    fn name(&self) -> &str {
+        "rapid_growth"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        let Some((before, grown)) = self.growth(entry) else {
+            return false;
+        };
+        grown >= self.min_growth_bytes
+            || (entry.size >= self.min_size_bytes
+                && grown as u128 * 100 >= before as u128 * self.min_growth_percent as u128)
+    }
+
+    fn reason(&self) -> String {
+        format!(
+            "Directory grew by at least {} MB or {}% since the previous scan",
+            self.min_growth_bytes / 1_048_576,
+            self.min_growth_percent
+        )
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        let Some((before, grown)) = self.growth(entry) else {
+            return self.reason();
+        };
+        let percent = if before == 0 {
+            "from empty".to_string()
+        } else {
+            format!("{}%", grown as u128 * 100 / before as u128)
+        };
+        format!(
+            "Directory grew by {:.1} MB ({}) since the previous scan, to {:.1} MB",
+            grown as f64 / 1_048_576.0,
+            percent,
+            entry.size as f64 / 1_048_576.0
+        )
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::DIRECTORIES
+    }
+}
+
+/// Kind of Python environment recognized by [`OrphanedEnvRule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PythonEnvKind {
+    /// `venv` or `virtualenv` environment (has a `pyvenv.cfg`)
+    Virtualenv,
+
+    /// Conda environment (has a `conda-meta/` directory)
+    Conda,
+}
+
+/// Why a Python environment is considered orphaned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvStatus {
+    /// The project the environment belongs to no longer exists
+    ProjectMissing(PathBuf),
+
+    /// The interpreter the environment was created from no longer exists
+    InterpreterMissing(PathBuf),
+
+    /// Neither the environment nor its project was modified for this many days
+    Stale(u64),
+}
+
+/// A Python environment found on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonEnv {
+    /// Kind of environment
+    pub kind: PythonEnvKind,
+
+    /// Interpreter version, if recorded
+    pub version: Option<String>,
+
+    /// Base interpreter directory (`home` in `pyvenv.cfg`)
+    pub home: Option<PathBuf>,
+
+    /// Project directory the environment belongs to, if known
+    pub project: Option<PathBuf>,
+}
+
+/// Directory names that mark an environment living inside its project.
+const IN_PROJECT_ENV_NAMES: &[&str] = &[".venv", "venv", ".env", "env", "virtualenv"];
+
+impl PythonEnv {
+    /// Inspects `dir` and returns the environment it holds, if any.
+    ///
+    /// The project is the directory named in the environment's `.project` file
+    /// (written by virtualenvwrapper and pipenv) or, for environments with a
+    /// conventional name such as `.venv`, the directory containing it.
+    pub fn inspect(dir: &Path) -> Option<Self> {
+        let cfg_path = dir.join("pyvenv.cfg");
+        let mut env = if cfg_path.is_file() {
+            let cfg = std::fs::read_to_string(&cfg_path).unwrap_or_default();
+            let value = |key: &str| {
+                cfg.lines().find_map(|line| {
+                    let (k, v) = line.split_once('=')?;
+                    (k.trim() == key).then(|| v.trim().to_string())
+                })
+            };
+            Self {
+                kind: PythonEnvKind::Virtualenv,
+                version: value("version")
+                    .or_else(|| value("version_info").map(|v| short_version(&v))),
+                home: value("home").map(PathBuf::from),
+                project: None,
+            }
+        } else if dir.join("conda-meta").is_dir() {
+            Self {
+                kind: PythonEnvKind::Conda,
+                version: conda_python_version(&dir.join("conda-meta")),
+                home: None,
+                project: None,
+            }
+        } else {
+            return None;
+        };
+
+        env.project = match std::fs::read_to_string(dir.join(".project")) {
+            Ok(project) if !project.trim().is_empty() => Some(PathBuf::from(project.trim())),
+            _ => dir
+                .file_name()
+                .and_then(|n| n.to_str())
+                .filter(|name| IN_PROJECT_ENV_NAMES.contains(name))
+                .and_then(|_| dir.parent())
+                .map(Path::to_path_buf),
+        };
+
+        Some(env)
+    }
+
+    /// Returns why this environment is orphaned, or `None` if it's in use.
+    ///
+    /// `modified` is the environment directory's modification time; the
+    /// project's is used instead when the project is known.
+    pub fn status(&self, modified: SystemTime, stale_days: u64) -> Option<EnvStatus> {
+        if let Some(project) = &self.project {
+            if !project.exists() {
+                return Some(EnvStatus::ProjectMissing(project.clone()));
+            }
+        }
+        if let Some(home) = &self.home {
+            if !home.exists() {
+                return Some(EnvStatus::InterpreterMissing(home.clone()));
+            }
+        }
+
+        let touched = self
+            .project
+            .as_deref()
+            .and_then(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+            .map_or(modified, |project_modified| project_modified.max(modified));
+        let idle_days = SystemTime::now()
+            .duration_since(touched)
+            .map_or(0, |idle| idle.as_secs() / 86_400);
+
+        (idle_days >= stale_days).then_some(EnvStatus::Stale(idle_days))
+    }
+}
+
+/// Trims a `version_info` such as `3.11.4.final.0` to `3.11.4`.
+fn short_version(version_info: &str) -> String {
+    version_info
+        .splitn(4, '.')
+        .take(3)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Reads the Python version from a conda env's `python-<version>-<build>.json`.
+fn conda_python_version(conda_meta: &Path) -> Option<String> {
+    std::fs::read_dir(conda_meta)
+        .ok()?
+        .flatten()
+        .find_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let rest = name.strip_prefix("python-")?.strip_suffix(".json")?;
+            let version = rest.split('-').next()?;
+            version
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| version.to_string())
+        })
+}
+
+/// Rule that flags orphaned Python virtualenvs and conda environments.
+///
+/// An environment is orphaned when its project or base interpreter no longer
+/// exists, or when neither it nor its project was modified in `stale_days`.
+/// Environments are easy to forget and routinely hold gigabytes of packages.
+pub struct OrphanedEnvRule {
+    /// Days without modification after which an environment is flagged
+    pub stale_days: u64,
+}
+
+impl OrphanedEnvRule {
+    /// Creates a rule flagging environments idle for at least `stale_days`.
+    pub fn new(stale_days: u64) -> Self {
+        Self { stale_days }
+    }
+}
+
+impl DetectionRule for OrphanedEnvRule {
+    fn name(&self) -> &str {
+        "orphaned_env"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        entry.is_directory()
+            && PythonEnv::inspect(&entry.path)
+                .is_some_and(|env| env.status(entry.modified, self.stale_days).is_some())
+    }
+
+    fn reason(&self) -> String {
+        format!(
+            "Python environment orphaned or unused for {} days",
+            self.stale_days
+        )
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        let Some(env) = PythonEnv::inspect(&entry.path) else {
+            return self.reason();
+        };
+        let Some(status) = env.status(entry.modified, self.stale_days) else {
+            return self.reason();
+        };
+
+        let kind = match env.kind {
+            PythonEnvKind::Virtualenv => "virtualenv",
+            PythonEnvKind::Conda => "conda environment",
+        };
+        let python = match &env.version {
+            Some(version) => format!("Python {}", version),
+            None => "Python".to_string(),
+        };
+        let why = match status {
+            EnvStatus::ProjectMissing(project) => {
+                format!("project {} no longer exists", project.display())
+            }
+            EnvStatus::InterpreterMissing(home) => {
+                format!("interpreter {} no longer exists", home.display())
+            }
+            EnvStatus::Stale(days) => format!("unused for {} days", days),
+        };
+        format!(
+            "{} {} ({:.1} MB): {}",
+            python,
+            kind,
+            entry.size as f64 / 1_048_576.0,
+            why
+        )
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::DIRECTORIES
+    }
+
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::skip_dirs(VCS_DIRS)
+    }
+}
+
+/// A family of build artifact directories produced by one toolchain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtifactEcosystem {
+    /// Identifier used in configuration (e.g. `rust_target`)
+    pub name: &'static str,
+
+    /// Directory names produced by this toolchain
+    pub dir_names: &'static [&'static str],
+
+    /// Manifests expected next to the artifact directory (glob patterns).
+    ///
+    /// An empty list (with no `parent_names`) means the directory name alone
+    /// is conclusive.
+    pub manifests: &'static [&'static str],
+
+    /// Parent directory names that identify the artifact without a manifest
+    /// (e.g. `Xcode` for `~/Library/Developer/Xcode/DerivedData`).
+    pub parent_names: &'static [&'static str],
+
+    /// Check the manifests and parent names even when manifests aren't
+    /// required, for directory names too generic to go by alone.
+    pub strict: bool,
 }
 
+/// Known build artifact ecosystems.
+pub const ARTIFACT_ECOSYSTEMS: &[ArtifactEcosystem] = &[
+    ArtifactEcosystem {
+        name: "rust_target",
+        dir_names: &["target"],
+        manifests: &["Cargo.toml"],
+        parent_names: &[],
+        strict: false,
+    },
+    ArtifactEcosystem {
+        name: "node_modules",
+        dir_names: &["node_modules"],
+        manifests: &["package.json"],
+        parent_names: &[],
+        strict: false,
+    },
+    ArtifactEcosystem {
+        name: "nextjs",
+        dir_names: &[".next"],
+        manifests: &["package.json"],
+        parent_names: &[],
+        strict: false,
+    },
+    ArtifactEcosystem {
+        name: "python_pycache",
+        dir_names: &["__pycache__", ".pytest_cache"],
+        manifests: &[],
+        parent_names: &[],
+        strict: false,
+    },
+    ArtifactEcosystem {
+        name: "dotnet_bin_obj",
+        dir_names: &["bin", "obj"],
+        manifests: &["*.csproj", "*.fsproj", "*.vbproj", "*.sln"],
+        parent_names: &[],
+        strict: false,
+    },
+    ArtifactEcosystem {
+        name: "gradle_build",
+        dir_names: &["build", ".gradle"],
+        manifests: &[
+            "build.gradle",
+            "build.gradle.kts",
+            "settings.gradle",
+            "settings.gradle.kts",
+        ],
+        parent_names: &[],
+        strict: false,
+    },
+    ArtifactEcosystem {
+        name: "android_native",
+        dir_names: &[".cxx", ".externalNativeBuild"],
+        manifests: &["build.gradle", "build.gradle.kts"],
+        parent_names: &[],
+        strict: false,
+    },
+    ArtifactEcosystem {
+        name: "xcode_derived_data",
+        dir_names: &["DerivedData"],
+        manifests: &["*.xcodeproj", "*.xcworkspace"],
+        parent_names: &["Xcode"],
+        strict: true,
+    },
+    ArtifactEcosystem {
+        name: "unity",
+        dir_names: &["Library", "Temp"],
+        manifests: &["ProjectSettings"],
+        parent_names: &[],
+        strict: true,
+    },
+    ArtifactEcosystem {
+        name: "jetbrains_caches",
+        dir_names: &["JetBrains"],
+        manifests: &[],
+        parent_names: &[".cache", "Caches"],
+        strict: true,
+    },
+    ArtifactEcosystem {
+        name: "generic_build",
+        dir_names: &["build", "dist"],
+        manifests: &[],
+        parent_names: &[],
+        strict: false,
+    },
+];
+
+/// How deep [`BuildArtifactRule::project_touched`] looks into a project
+pub const PROJECT_FILE_DEPTH: usize = 4;
+
 /// Rule that flags common build artifact directories.
 pub struct BuildArtifactRule {
     patterns: Vec<&'static str>,
+    custom_patterns: Vec<glob::Pattern>,
+    ecosystems: Vec<&'static ArtifactEcosystem>,
+    require_manifest: bool,
+    active_project_days: Option<u64>,
 }
 
 impl Default for BuildArtifactRule {
@@ -57,6 +1030,10 @@ impl Default for BuildArtifactRule {
                 "bin",           // Binaries
                 "obj",           // C#/C++
             ],
+            custom_patterns: Vec::new(),
+            ecosystems: Vec::new(),
+            require_manifest: false,
+            active_project_days: None,
         }
     }
 }
@@ -69,10 +1046,165 @@ impl BuildArtifactRule {
 
     /// Creates a BuildArtifactRule with custom patterns.
     pub fn with_patterns(patterns: Vec<&'static str>) -> Self {
-        Self { patterns }
+        Self {
+            patterns,
+            custom_patterns: Vec::new(),
+            ecosystems: Vec::new(),
+            require_manifest: false,
+            active_project_days: None,
+        }
+    }
+
+    /// Creates a BuildArtifactRule for the named ecosystems.
+    ///
+    /// Unknown names are ignored. When `require_manifest` is set, directories
+    /// are only flagged if one of the ecosystem's manifests sits next to them
+    /// (e.g. `Cargo.toml` beside `target/`) or their parent has one of the
+    /// ecosystem's parent names. Strict ecosystems are always checked.
+    pub fn with_ecosystems(names: &[&str], require_manifest: bool) -> Self {
+        Self {
+            patterns: Vec::new(),
+            custom_patterns: Vec::new(),
+            ecosystems: ARTIFACT_ECOSYSTEMS
+                .iter()
+                .filter(|eco| names.contains(&eco.name))
+                .collect(),
+            require_manifest,
+            active_project_days: None,
+        }
+    }
+
+    /// Also flags directories whose name matches one of the glob `patterns`,
+    /// such as `.tox` or `*.egg-info`, without any manifest check. A trailing
+    /// separator is ignored, and so are invalid patterns.
+    pub fn with_custom_patterns<S: AsRef<str>>(mut self, patterns: &[S]) -> Self {
+        self.custom_patterns = patterns
+            .iter()
+            .filter_map(|p| glob::Pattern::new(p.as_ref().trim_end_matches(['/', '\\'])).ok())
+            .collect();
+        self
+    }
+
+    /// Holds back the artifacts of projects touched within `days` for review
+    /// instead of deletion (see [`BuildArtifactRule::project_touched`]).
+    ///
+    /// None, the default, holds back nothing.
+    pub fn with_active_project_days(mut self, days: Option<u64>) -> Self {
+        self.active_project_days = days;
+        self
+    }
+
+    /// Returns when the project owning the artifact directory `dir` was last
+    /// touched: the newest modification time of the manifests beside `dir`
+    /// of the ecosystems using its name, and of the project's files up to
+    /// [`PROJECT_FILE_DEPTH`] levels down.
+    ///
+    /// Build artifact directories, hidden directories and their contents
+    /// don't count, since builds and fetches touch them on their own; neither
+    /// does the project directory itself. The walk stops at the first file
+    /// touched within the last day.
+    ///
+    /// Returns None if the project has no such files.
+    pub fn project_touched(dir: &Path) -> Option<SystemTime> {
+        let project = dir.parent()?;
+        let dir_name = dir.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let patterns: Vec<glob::Pattern> = ARTIFACT_ECOSYSTEMS
+            .iter()
+            .filter(|eco| eco.dir_names.contains(&dir_name))
+            .flat_map(|eco| eco.manifests)
+            .filter_map(|m| glob::Pattern::new(m).ok())
+            .collect();
+
+        let manifests = std::fs::read_dir(project)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                patterns.iter().any(|p| p.matches(&name))
+            })
+            .filter_map(|entry| entry.metadata().ok()?.modified().ok());
+        let mut newest = manifests.max();
+
+        let recent = SystemTime::now().checked_sub(Duration::from_secs(86_400));
+        let files = walkdir::WalkDir::new(project)
+            .max_depth(PROJECT_FILE_DEPTH)
+            .into_iter()
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                entry.depth() == 0
+                    || !entry.file_type().is_dir()
+                    || !(name.starts_with('.')
+                        || ARTIFACT_ECOSYSTEMS
+                            .iter()
+                            .any(|eco| eco.dir_names.contains(&name.as_ref())))
+            })
+            .flatten()
+            .filter(|entry| entry.file_type().is_file());
+        for file in files {
+            let Some(modified) = file.metadata().ok().and_then(|m| m.modified().ok()) else {
+                continue;
+            };
+            newest = newest.max(Some(modified));
+            if recent.is_some_and(|recent| modified >= recent) {
+                break;
+            }
+        }
+        newest
+    }
+
+    /// Returns how many days ago the project of the artifact directory `dir`
+    /// was touched, if that is within the active project window.
+    fn idle_days_if_active(&self, dir: &Path) -> Option<u64> {
+        let window = self.active_project_days?;
+        let touched = Self::project_touched(dir)?;
+        let days = SystemTime::now()
+            .duration_since(touched)
+            .map_or(0, |idle| idle.as_secs() / 86_400);
+        (days < window).then_some(days)
+    }
+
+    fn ecosystem_matches(
+        &self,
+        eco: &ArtifactEcosystem,
+        entry: &FileEntry,
+        dir_name: &str,
+    ) -> bool {
+        if !eco.dir_names.contains(&dir_name) {
+            return false;
+        }
+
+        let checked = self.require_manifest || eco.strict;
+        if !checked || (eco.manifests.is_empty() && eco.parent_names.is_empty()) {
+            return true;
+        }
+
+        entry.path.parent().is_some_and(|parent| {
+            let parent_name = parent.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            eco.parent_names.contains(&parent_name) || has_manifest(parent, eco.manifests)
+        })
     }
 }
 
+/// Returns true if `dir` contains a file matching any of the manifest patterns.
+fn has_manifest(dir: &Path, manifests: &[&str]) -> bool {
+    let patterns: Vec<glob::Pattern> = manifests
+        .iter()
+        .filter_map(|m| glob::Pattern::new(m).ok())
+        .collect();
+
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return false;
+    };
+
+    read_dir.flatten().any(|entry| {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        patterns.iter().any(|p| p.matches(&name))
+    })
+}
+
 impl DetectionRule for BuildArtifactRule {
     fn name(&self) -> &str {
         "build_artifact"
@@ -91,18 +1223,40 @@ impl DetectionRule for BuildArtifactRule {
             .unwrap_or("");
 
         self.patterns.contains(&dir_name)
+            || self.custom_patterns.iter().any(|p| p.matches(dir_name))
+            || self
+                .ecosystems
+                .iter()
+                .any(|eco| self.ecosystem_matches(eco, entry, dir_name))
     }
 
     fn reason(&self) -> String {
         "Common build artifact directory".to_string()
     }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::DIRECTORIES
+    }
+
+    // Artifacts of a project still being worked on are rebuilt right away
+    fn downgrade(&self, entry: &FileEntry) -> Option<(Protection, String)> {
+        let detail = match self.idle_days_if_active(&entry.path)? {
+            0 => "project touched today".to_string(),
+            1 => "project touched yesterday".to_string(),
+            days => format!("project touched {} days ago", days),
+        };
+        Some((Protection::ActiveProject, detail))
+    }
+
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::skip_dirs(VCS_DIRS)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
-    use std::time::SystemTime;
+    use std::time::Duration;
 
     fn create_test_entry(path: &str, size: u64) -> FileEntry {
         FileEntry::new(
@@ -124,9 +1278,7 @@ mod tests {
 
     #[test]
     fn test_size_threshold_rule_flags_large_files() {
-        let rule = SizeThresholdRule {
-            threshold_bytes: 1_048_576,
-        }; // 1MB
+        let rule = SizeThresholdRule::new(1_048_576); // 1MB
 
         let small_file = create_test_entry("small.txt", 1024);
         let large_file = create_test_entry("large.bin", 2_097_152);
@@ -139,18 +1291,14 @@ mod tests {
 
     #[test]
     fn test_size_threshold_rule_name() {
-        let rule = SizeThresholdRule {
-            threshold_bytes: 100_000_000,
-        };
+        let rule = SizeThresholdRule::new(100_000_000);
 
         assert_eq!(rule.name(), "large_file");
     }
 
     #[test]
     fn test_size_threshold_rule_reason() {
-        let rule = SizeThresholdRule {
-            threshold_bytes: 100 * 1_048_576, // Exactly 100 MB
-        };
+        let rule = SizeThresholdRule::new(100 * 1_048_576); // Exactly 100 MB
 
         let reason = rule.reason();
         assert!(reason.contains("100"));
@@ -236,4 +1384,477 @@ mod tests {
         assert!(rule.should_flag(&custom_dir, &context));
         assert!(!rule.should_flag(&target_dir, &context)); // Not in custom patterns
     }
+
+    #[test]
+    fn test_build_artifact_rule_configured_patterns() {
+        let config = crate::config::BuildArtifactsConfig {
+            custom_patterns: vec![".tox/".to_string(), "*.egg-info".to_string()],
+            ..Default::default()
+        };
+        let rule = BuildArtifactRule::from(&config);
+        let context = ScanContext::default();
+
+        assert!(rule.should_flag(&create_test_entry_dir("/project/.tox"), &context));
+        assert!(rule.should_flag(&create_test_entry_dir("/project/pkg.egg-info"), &context));
+        assert!(!rule.should_flag(&create_test_entry_dir("/project/egg-info"), &context));
+        // Generic build/ and dist/ directories need opting in
+        assert!(!rule.should_flag(&create_test_entry_dir("/project/dist"), &context));
+    }
+
+    #[test]
+    fn test_build_artifact_rule_requires_manifest() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let rust_project = temp.path().join("rust");
+        let plain = temp.path().join("plain");
+        std::fs::create_dir_all(rust_project.join("target")).unwrap();
+        std::fs::create_dir_all(plain.join("target")).unwrap();
+        std::fs::write(rust_project.join("Cargo.toml"), "[package]").unwrap();
+
+        let rule = BuildArtifactRule::with_ecosystems(&["rust_target"], true);
+        let context = ScanContext::default();
+
+        let with_manifest = create_test_entry_dir(rust_project.join("target").to_str().unwrap());
+        let without_manifest = create_test_entry_dir(plain.join("target").to_str().unwrap());
+
+        assert!(rule.should_flag(&with_manifest, &context));
+        assert!(!rule.should_flag(&without_manifest, &context));
+
+        let lenient = BuildArtifactRule::with_ecosystems(&["rust_target"], false);
+        assert!(lenient.should_flag(&without_manifest, &context));
+    }
+
+    #[test]
+    fn test_build_artifact_rule_manifest_glob() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("bin")).unwrap();
+        std::fs::write(temp.path().join("App.csproj"), "<Project/>").unwrap();
+
+        let rule = BuildArtifactRule::with_ecosystems(&["dotnet_bin_obj"], true);
+        let bin = create_test_entry_dir(temp.path().join("bin").to_str().unwrap());
+
+        assert!(rule.should_flag(&bin, &ScanContext::default()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_artifact_rule_holds_back_active_projects() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let project = temp.path().join("app");
+        std::fs::create_dir_all(project.join("node_modules/dep")).unwrap();
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("package.json"), "{}").unwrap();
+        std::fs::write(project.join("src/index.js"), "").unwrap();
+        std::fs::write(project.join("node_modules/dep/index.js"), "").unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(30 * 86_400);
+        for path in [project.join("package.json"), project.join("src/index.js")] {
+            std::fs::File::open(path)
+                .unwrap()
+                .set_modified(long_ago)
+                .unwrap();
+        }
+
+        // The fresh project directory and artifacts don't make it active
+        let node_modules = create_test_entry_dir(project.join("node_modules").to_str().unwrap());
+        let rule = BuildArtifactRule::default().with_active_project_days(Some(14));
+        assert_eq!(
+            BuildArtifactRule::project_touched(&node_modules.path),
+            Some(long_ago)
+        );
+        assert_eq!(rule.downgrade(&node_modules), None);
+
+        // Editing a source file does
+        std::fs::write(project.join("src/index.js"), "edit").unwrap();
+        assert_eq!(
+            rule.downgrade(&node_modules),
+            Some((
+                Protection::ActiveProject,
+                "project touched today".to_string()
+            ))
+        );
+        assert_eq!(BuildArtifactRule::default().downgrade(&node_modules), None);
+    }
+
+    #[test]
+    fn test_build_artifact_rule_disabled_ecosystem() {
+        let rule = BuildArtifactRule::with_ecosystems(&["python_pycache"], true);
+        let context = ScanContext::default();
+
+        // No manifest needed for __pycache__
+        assert!(rule.should_flag(&create_test_entry_dir("/project/__pycache__"), &context));
+        // node_modules ecosystem is not enabled
+        assert!(!rule.should_flag(&create_test_entry_dir("/project/node_modules"), &context));
+    }
+
+    #[test]
+    fn test_build_artifact_rule_ide_presets() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let xcode = temp.path().join("Developer").join("Xcode");
+        let game = temp.path().join("game");
+        let caches = temp.path().join(".cache");
+        std::fs::create_dir_all(xcode.join("DerivedData")).unwrap();
+        std::fs::create_dir_all(game.join("ProjectSettings")).unwrap();
+        std::fs::create_dir_all(game.join("Library")).unwrap();
+        std::fs::create_dir_all(caches.join("JetBrains")).unwrap();
+
+        let names = ["xcode_derived_data", "unity", "jetbrains_caches"];
+        let context = ScanContext::default();
+        let entry = |path: PathBuf| create_test_entry_dir(path.to_str().unwrap());
+
+        for require_manifest in [true, false] {
+            let rule = BuildArtifactRule::with_ecosystems(&names, require_manifest);
+            assert!(rule.should_flag(&entry(xcode.join("DerivedData")), &context));
+            assert!(rule.should_flag(&entry(game.join("Library")), &context));
+            assert!(rule.should_flag(&entry(game.join("Temp")), &context));
+            assert!(rule.should_flag(&entry(caches.join("JetBrains")), &context));
+
+            // Generic names outside their context are left alone
+            assert!(!rule.should_flag(&entry(temp.path().join("Library")), &context));
+            assert!(!rule.should_flag(&entry(temp.path().join("JetBrains")), &context));
+            assert!(!rule.should_flag(&entry(temp.path().join("DerivedData")), &context));
+        }
+
+        std::fs::create_dir_all(temp.path().join("App.xcodeproj")).unwrap();
+        let rule = BuildArtifactRule::with_ecosystems(&names, true);
+        assert!(rule.should_flag(&entry(temp.path().join("DerivedData")), &context));
+    }
+
+    #[test]
+    fn test_size_threshold_rule_separate_directory_threshold() {
+        let rule = SizeThresholdRule::with_thresholds(1000, 10_000);
+        let context = ScanContext::default();
+
+        let mut dir = create_test_entry_dir("cache");
+        dir.size = 5000;
+        assert!(!rule.should_flag(&dir, &context));
+        assert!(rule.should_flag(&create_test_entry("big.bin", 5000), &context));
+
+        dir.size = 10_000;
+        assert!(rule.should_flag(&dir, &context));
+    }
+
+    #[test]
+    fn test_size_threshold_rule_skips_cloud_placeholders() {
+        let rule = SizeThresholdRule::new(1000);
+        let context = ScanContext::default();
+        let placeholder = FileEntry {
+            cloud_placeholder: true,
+            ..create_test_entry("OneDrive/video.mp4", 50_000)
+        };
+
+        assert!(!rule.should_flag(&placeholder, &context));
+        assert!(rule
+            .with_cloud_placeholders(true)
+            .should_flag(&placeholder, &context));
+    }
+
+    #[test]
+    fn test_size_threshold_rule_reason_includes_actual_size() {
+        let rule = SizeThresholdRule::with_thresholds(100 * 1_048_576, 1024 * 1_048_576);
+
+        let reason = rule.reason_for(&create_test_entry("big.iso", 150 * 1_048_576));
+        assert_eq!(reason, "File size 150.0 MB exceeds threshold of 100 MB");
+
+        let mut dir = create_test_entry_dir("cache");
+        dir.size = 2048 * 1_048_576;
+        let reason = rule.reason_for(&dir);
+        assert_eq!(
+            reason,
+            "Directory size 2048.0 MB exceeds threshold of 1024 MB"
+        );
+    }
+
+    #[test]
+    fn test_growth_rule_flags_absolute_or_relative_growth() {
+        let mb = 1_048_576;
+        let previous = HashMap::from([
+            (PathBuf::from("/logs"), 100 * mb),
+            (PathBuf::from("/cache"), 10 * mb),
+            (PathBuf::from("/tiny"), 1024),
+        ]);
+        let rule = GrowthRule::new(previous, 1024 * mb, 50, 100 * mb);
+        let context = ScanContext::default();
+        let dir = |path: &str, size: u64| FileEntry {
+            size,
+            ..create_test_entry_dir(path)
+        };
+
+        let logs = dir("/logs", 1124 * mb);
+        assert!(rule.should_flag(&logs, &context));
+        assert_eq!(
+            rule.reason_for(&logs),
+            "Directory grew by 1024.0 MB (1024%) since the previous scan, to 1124.0 MB"
+        );
+
+        // 50% growth counts once the directory holds 100 MB
+        assert!(rule.should_flag(&dir("/cache", 150 * mb), &context));
+        assert!(!rule.should_flag(&dir("/cache", 90 * mb), &context));
+        assert!(!rule.should_flag(&dir("/tiny", 4096), &context));
+        assert!(!rule.should_flag(&dir("/new", 5000 * mb), &context));
+        assert_eq!(rule.applies_to(), EntryKinds::DIRECTORIES);
+    }
+
+    #[test]
+    fn test_delete_marker_rule_flags_marked_directories() {
+        use tempfile::TempDir;
+
+        let temp = TempDir::new().unwrap();
+        let (marked, noted, plain) = (
+            temp.path().join("old-renders"),
+            temp.path().join("scratch"),
+            temp.path().join("project"),
+        );
+        for dir in [&marked, &noted, &plain] {
+            std::fs::create_dir(dir).unwrap();
+        }
+        std::fs::write(marked.join(".megamaid-delete"), "").unwrap();
+        std::fs::write(
+            noted.join(".megamaid-delete"),
+            "  Regenerated nightly - jdoe  \nmore\n",
+        )
+        .unwrap();
+
+        let rule = DeleteMarkerRule::new(".megamaid-delete");
+        let context = ScanContext::default();
+        let dir = |path: &Path| create_test_entry_dir(path.to_str().unwrap());
+        assert!(rule.should_flag(&dir(&marked), &context));
+        assert!(rule.should_flag(&dir(&noted), &context));
+        assert!(!rule.should_flag(&dir(&plain), &context));
+        assert_eq!(rule.applies_to(), EntryKinds::DIRECTORIES);
+
+        assert_eq!(
+            rule.reason_for(&dir(&marked)),
+            "Marked for deletion by a .megamaid-delete file"
+        );
+        assert_eq!(
+            rule.reason_for(&dir(&noted)),
+            "Marked for deletion by a .megamaid-delete file: \"Regenerated nightly - jdoe\""
+        );
+    }
+
+    #[test]
+    fn test_empty_entry_rule_flags_empty_files_and_directories() {
+        use tempfile::TempDir;
+
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("left/behind/deeper")).unwrap();
+        std::fs::create_dir_all(root.join("holds/empty-file")).unwrap();
+        std::fs::create_dir_all(root.join("repo/.git/refs/tags")).unwrap();
+        std::fs::write(root.join("holds/empty-file/touched"), "").unwrap();
+        std::fs::write(root.join("notes.txt"), "text").unwrap();
+
+        let rule = EmptyEntryRule::default();
+        let context = ScanContext::default();
+        let dir = |path: &str| create_test_entry_dir(root.join(path).to_str().unwrap());
+        let file = |path: &str, size| create_test_entry(root.join(path).to_str().unwrap(), size);
+
+        assert!(rule.should_flag(&dir("left"), &context));
+        assert!(rule.should_flag(&dir("left/behind/deeper"), &context));
+        assert!(!rule.should_flag(&dir("holds"), &context));
+        assert!(!rule.should_flag(&dir("repo/.git/refs"), &context));
+
+        assert!(rule.should_flag(&file("holds/empty-file/touched", 0), &context));
+        assert!(!rule.should_flag(&file("notes.txt", 4), &context));
+        assert!(!rule.should_flag(&file("pkg/__init__.py", 0), &context));
+        assert!(!rule.should_flag(&file("repo/.git/index.lock", 0), &context));
+
+        // Deciding in a batch agrees with deciding per entry
+        let entries = [
+            dir("left"),
+            dir("holds"),
+            file("holds/empty-file/touched", 0),
+        ];
+        let batch: Vec<&FileEntry> = entries.iter().collect();
+        assert_eq!(
+            rule.evaluate_batch(&batch, &context),
+            vec![true, false, true]
+        );
+
+        assert_eq!(
+            rule.reason_for(&dir("left")),
+            "Empty directory (no files inside)"
+        );
+        assert_eq!(
+            rule.reason_for(&file("holds/empty-file/touched", 0)),
+            "Empty file (0 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_alternate_stream_rule_flags_large_streams() {
+        use crate::models::DataStream;
+
+        let rule = AlternateStreamRule::new(1_048_576);
+        let context = ScanContext::default();
+
+        let mut downloaded = create_test_entry("setup.exe", 10);
+        downloaded.streams = vec![DataStream {
+            name: "Zone.Identifier".to_string(),
+            size: 26,
+        }];
+        assert!(!rule.should_flag(&downloaded, &context));
+
+        let mut hiding = create_test_entry("notes.txt", 10);
+        hiding.streams = vec![
+            DataStream {
+                name: "Zone.Identifier".to_string(),
+                size: 26,
+            },
+            DataStream {
+                name: "hidden".to_string(),
+                size: 2_097_152,
+            },
+        ];
+        assert!(rule.should_flag(&hiding, &context));
+        assert_eq!(
+            rule.reason_for(&hiding),
+            "2 alternate data stream(s) total 2.0 MB (largest: 'hidden', 2.0 MB)"
+        );
+
+        // Entries without streams are never flagged, even with a zero threshold
+        let plain = create_test_entry("plain.txt", 10);
+        assert!(!AlternateStreamRule::new(0).should_flag(&plain, &context));
+    }
+
+    fn create_env_entry(path: &Path, size: u64, days_old: u64) -> FileEntry {
+        FileEntry::new(
+            path.to_path_buf(),
+            size,
+            SystemTime::now() - Duration::from_secs(days_old * 86_400),
+            EntryType::Directory,
+        )
+    }
+
+    #[test]
+    fn test_python_env_inspect() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let venv = temp.path().join("project").join(".venv");
+        std::fs::create_dir_all(&venv).unwrap();
+        std::fs::write(
+            venv.join("pyvenv.cfg"),
+            "home = /usr/bin\ninclude-system-site-packages = false\nversion_info = 3.11.4.final.0\n",
+        )
+        .unwrap();
+
+        let env = PythonEnv::inspect(&venv).unwrap();
+        assert_eq!(env.kind, PythonEnvKind::Virtualenv);
+        assert_eq!(env.version.as_deref(), Some("3.11.4"));
+        assert_eq!(env.home, Some(PathBuf::from("/usr/bin")));
+        assert_eq!(env.project, Some(temp.path().join("project")));
+
+        let conda = temp.path().join("envs").join("analysis");
+        std::fs::create_dir_all(conda.join("conda-meta")).unwrap();
+        std::fs::write(
+            conda
+                .join("conda-meta")
+                .join("python-3.10.12-h955ad1f_0.json"),
+            "{}",
+        )
+        .unwrap();
+        std::fs::write(
+            conda.join("conda-meta").join("numpy-1.26.0-py310_0.json"),
+            "{}",
+        )
+        .unwrap();
+
+        let env = PythonEnv::inspect(&conda).unwrap();
+        assert_eq!(env.kind, PythonEnvKind::Conda);
+        assert_eq!(env.version.as_deref(), Some("3.10.12"));
+        assert_eq!(env.project, None);
+
+        assert!(PythonEnv::inspect(temp.path()).is_none());
+    }
+
+    #[test]
+    fn test_orphaned_env_rule_flags_missing_project() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let env_dir = temp.path().join(".virtualenvs").join("webapp");
+        std::fs::create_dir_all(&env_dir).unwrap();
+        std::fs::write(env_dir.join("pyvenv.cfg"), "version = 3.12.1\n").unwrap();
+        let context = ScanContext::default();
+        let rule = OrphanedEnvRule::new(180);
+
+        let entry = create_env_entry(&env_dir, 2 * 1_073_741_824, 1);
+        assert!(!rule.should_flag(&entry, &context));
+
+        let project = temp.path().join("webapp");
+        std::fs::write(env_dir.join(".project"), project.to_str().unwrap()).unwrap();
+        assert!(rule.should_flag(&entry, &context));
+        assert_eq!(
+            rule.reason_for(&entry),
+            format!(
+                "Python 3.12.1 virtualenv (2048.0 MB): project {} no longer exists",
+                project.display()
+            )
+        );
+
+        std::fs::create_dir(&project).unwrap();
+        assert!(!rule.should_flag(&entry, &context));
+    }
+
+    #[test]
+    fn test_orphaned_env_rule_flags_stale_and_broken_envs() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let conda = temp.path().join("analysis");
+        std::fs::create_dir_all(conda.join("conda-meta")).unwrap();
+        let context = ScanContext::default();
+        let rule = OrphanedEnvRule::new(180);
+
+        assert!(!rule.should_flag(&create_env_entry(&conda, 1024, 30), &context));
+        let stale = create_env_entry(&conda, 10 * 1_048_576, 400);
+        assert!(rule.should_flag(&stale, &context));
+        assert_eq!(
+            rule.reason_for(&stale),
+            "Python conda environment (10.0 MB): unused for 400 days"
+        );
+
+        let venv = temp.path().join("tool-env");
+        std::fs::create_dir_all(&venv).unwrap();
+        let home = temp.path().join("python3.8").join("bin");
+        std::fs::write(
+            venv.join("pyvenv.cfg"),
+            format!("home = {}\nversion = 3.8.10\n", home.display()),
+        )
+        .unwrap();
+        assert!(rule.should_flag(&create_env_entry(&venv, 0, 1), &context));
+
+        // Plain directories and files are never flagged
+        let plain = create_env_entry(temp.path(), 0, 400);
+        assert!(!rule.should_flag(&plain, &context));
+        assert_eq!(rule.applies_to(), EntryKinds::DIRECTORIES);
+    }
+
+    #[test]
+    fn test_entry_kinds_contains() {
+        assert!(EntryKinds::ALL.contains(EntryType::File));
+        assert!(EntryKinds::ALL.contains(EntryType::Directory));
+        assert!(EntryKinds::FILES.contains(EntryType::File));
+        assert!(!EntryKinds::FILES.contains(EntryType::Directory));
+        assert!(!EntryKinds::DIRECTORIES.contains(EntryType::File));
+        assert!(EntryKinds {
+            files: false,
+            directories: false,
+            symlinks: false
+        }
+        .is_empty());
+    }
+
+    #[test]
+    fn test_build_artifact_rule_applies_to_directories() {
+        assert_eq!(
+            BuildArtifactRule::default().applies_to(),
+            EntryKinds::DIRECTORIES
+        );
+    }
+
+    #[test]
+    fn test_scoped_rule_intersects_masks() {
+        let scoped = ScopedRule::new(Box::new(BuildArtifactRule::default()), EntryKinds::FILES);
+        assert!(!scoped.applies_to().files);
+        assert!(!scoped.applies_to().directories);
+
+        let scoped = ScopedRule::new(Box::new(SizeThresholdRule::new(10)), EntryKinds::FILES);
+        assert_eq!(scoped.applies_to(), EntryKinds::FILES);
+        assert_eq!(scoped.name(), "large_file");
+    }
 }
@@ -0,0 +1,241 @@
+//! Git repositories nobody has worked on in a long time.
+//!
+//! Old clones and abandoned experiments pile up in project folders, each
+//! with its history, dependencies and build output. The `stale_repo` rule
+//! (`detector.rules.stale_repos`, off by default) flags a whole repository
+//! for review once its last activity is older than `stale_days`.
+//!
+//! Last activity is the newest of the last entry in the `HEAD` reflog,
+//! which Git writes on every commit, checkout, merge and pull, and the
+//! modification times of `HEAD`, `index` and `FETCH_HEAD` in the Git
+//! directory, which cover clones without a reflog. Worktrees and submodules,
+//! whose `.git` is a file, are followed to their Git directory.
+//!
+//! Repository roots are protected from every other rule (see
+//! [`DetectionRule::flags_repositories`]).
+
+use crate::detector::engine::ScanContext;
+use crate::detector::rules::{DetectionRule, EntryKinds, PathPruneHints, VCS_DIRS};
+use crate::models::FileEntry;
+use chrono::{DateTime, Utc};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Files in the Git directory whose modification time counts as activity.
+const ACTIVITY_FILES: [&str; 3] = ["HEAD", "index", "FETCH_HEAD"];
+
+/// Bytes read from the end of the reflog to find its last entry.
+const REFLOG_TAIL: u64 = 4096;
+
+/// Returns the Git directory of the repository rooted at `dir`, if it is one.
+pub fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    let metadata = fs::metadata(&dot_git).ok()?;
+    if metadata.is_dir() {
+        return Some(dot_git);
+    }
+    // Worktrees and submodules point at their Git directory
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let target = contents.trim().strip_prefix("gitdir:")?.trim();
+    let target = dir.join(target);
+    target.is_dir().then_some(target)
+}
+
+/// Returns when the repository rooted at `dir` was last worked on, or None
+/// if it isn't a Git repository.
+pub fn last_activity(dir: &Path) -> Option<SystemTime> {
+    let git_dir = git_dir(dir)?;
+    let modified = ACTIVITY_FILES
+        .iter()
+        .filter_map(|name| fs::metadata(git_dir.join(name)).ok()?.modified().ok());
+    let logged = last_reflog_time(&git_dir.join("logs").join("HEAD"));
+    modified.chain(logged).max()
+}
+
+/// Returns the time of the last entry in the reflog at `path`.
+fn last_reflog_time(path: &Path) -> Option<SystemTime> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(REFLOG_TAIL)))
+        .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    String::from_utf8_lossy(&tail)
+        .lines()
+        .rev()
+        .find_map(reflog_time)
+}
+
+/// Parses the time of a reflog line:
+/// `<old> <new> <name> <email> <seconds> <offset>\t<message>`.
+fn reflog_time(line: &str) -> Option<SystemTime> {
+    let header = line.split('\t').next()?;
+    let mut fields = header.split_whitespace().rev();
+    let _offset = fields.next()?;
+    let seconds: u64 = fields.next()?.parse().ok()?;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Returns the number of whole days between `time` and now.
+fn days_since(time: SystemTime) -> u64 {
+    SystemTime::now()
+        .duration_since(time)
+        .map_or(0, |idle| idle.as_secs() / 86_400)
+}
+
+/// Rule that flags Git repositories without activity for `stale_days`.
+pub struct StaleRepoRule {
+    /// Days without activity after which a repository is flagged
+    pub stale_days: u64,
+}
+
+impl StaleRepoRule {
+    /// Creates a rule flagging repositories idle for at least `stale_days`.
+    pub fn new(stale_days: u64) -> Self {
+        Self { stale_days }
+    }
+
+    /// Returns when the repository at `entry` was last worked on, if it is
+    /// one that is stale.
+    fn stale_since(&self, entry: &FileEntry) -> Option<SystemTime> {
+        // The working directory stays protected
+        if entry.path == Path::new(".") {
+            return None;
+        }
+        last_activity(&entry.path).filter(|time| days_since(*time) >= self.stale_days)
+    }
+}
+
+impl DetectionRule for StaleRepoRule {
+    fn name(&self) -> &str {
+        "stale_repo"
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        self.stale_since(entry).is_some()
+    }
+
+    fn reason(&self) -> String {
+        format!(
+            "Git repository without activity for {} days",
+            self.stale_days
+        )
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        match self.stale_since(entry) {
+            Some(time) => format!(
+                "Git repository ({:.1} MB) without activity for {} days, last on {}",
+                entry.size as f64 / 1_048_576.0,
+                days_since(time),
+                DateTime::<Utc>::from(time).format("%Y-%m-%d")
+            ),
+            None => self.reason(),
+        }
+    }
+
+    fn applies_to(&self) -> EntryKinds {
+        EntryKinds::DIRECTORIES
+    }
+
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::skip_dirs(VCS_DIRS)
+    }
+
+    fn flags_repositories(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use tempfile::TempDir;
+
+    fn dir(path: &Path) -> FileEntry {
+        FileEntry::new(
+            path.to_path_buf(),
+            2 * 1_048_576,
+            SystemTime::now(),
+            EntryType::Directory,
+        )
+    }
+
+    /// Creates a repository whose last activity is `days` ago.
+    fn repo(root: &Path, name: &str, days: u64) -> PathBuf {
+        let repo = root.join(name);
+        let git = repo.join(".git");
+        fs::create_dir_all(git.join("logs")).unwrap();
+        let then = SystemTime::now() - Duration::from_secs(days * 86_400);
+        for name in ["HEAD", "index"] {
+            let file = File::create(git.join(name)).unwrap();
+            file.set_modified(then).unwrap();
+        }
+        let seconds = then
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        fs::write(
+            git.join("logs/HEAD"),
+            format!(
+                "0000 1111 Ana <ana@example.com> {} +0100\tcommit (initial): Start\n",
+                seconds
+            ),
+        )
+        .unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_reflog_time_parses_last_field_before_message() {
+        let time = reflog_time(
+            "a1b2 c3d4 Ana Lima <ana@example.com> 1700000000 -0300\tcheckout: moving from main to dev",
+        )
+        .unwrap();
+        assert_eq!(
+            time,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        assert!(reflog_time("").is_none());
+    }
+
+    #[test]
+    fn test_flags_repositories_without_recent_activity() {
+        let temp = TempDir::new().unwrap();
+        let rule = StaleRepoRule::new(365);
+        let context = ScanContext::default();
+
+        let old = repo(temp.path(), "old", 400);
+        let active = repo(temp.path(), "active", 400);
+        // A fetch counts as activity even without a reflog entry
+        File::create(active.join(".git/FETCH_HEAD")).unwrap();
+        let plain = temp.path().join("plain");
+        fs::create_dir(&plain).unwrap();
+
+        assert!(rule.should_flag(&dir(&old), &context));
+        assert!(!rule.should_flag(&dir(&active), &context));
+        assert!(!rule.should_flag(&dir(&plain), &context));
+        assert!(rule
+            .reason_for(&dir(&old))
+            .starts_with("Git repository (2.0 MB) without activity for 400 days, last on "));
+    }
+
+    #[test]
+    fn test_worktrees_are_followed_to_their_git_dir() {
+        let temp = TempDir::new().unwrap();
+        let main = repo(temp.path(), "main", 400);
+        let worktree = temp.path().join("feature");
+        fs::create_dir(&worktree).unwrap();
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", main.join(".git").display()),
+        )
+        .unwrap();
+
+        assert_eq!(git_dir(&worktree), Some(main.join(".git")));
+        assert!(StaleRepoRule::new(365).should_flag(&dir(&worktree), &ScanContext::default()));
+    }
+}
@@ -0,0 +1,337 @@
+//! Temporary files and caches kept by the operating system and browsers.
+//!
+//! Four built-in rules, toggled under `detector.rules.system_caches`, flag
+//! what Windows, the browsers and file managers leave behind:
+//!
+//! - `temp_files`: the contents of the user's `%TEMP%` and of
+//!   `C:\Windows\Temp`, which may still be in use, so they default to Review
+//! - `browser_cache`: the HTTP, code and GPU caches of Chrome, Edge, Brave
+//!   and Firefox profiles, which also hold offline data of web apps, so they
+//!   default to Review
+//! - `thumbnail_cache`: Explorer's `thumbcache_*.db` files and the freedesktop
+//!   `~/.cache/thumbnails`, rebuilt on demand, so they default to Delete
+//! - `crash_dump`: `*.dmp` files and the contents of the folders crash dumps
+//!   are written to (`%LOCALAPPDATA%\CrashDumps`, where Windows Error
+//!   Reporting's `LocalDumps` puts them, and `C:\Windows\Minidump`), only
+//!   needed to debug a crash, so they default to Review
+//!
+//! Locations are `/`-separated path patterns whose components are globs
+//! matched against the last components of a path, in the platform's case;
+//! a leading `/` anchors the pattern at the root of the path instead.
+
+use crate::detector::engine::ScanContext;
+use crate::detector::rules::{DetectionRule, PathPruneHints, VCS_DIRS};
+use crate::models::{paths_equal, FileEntry, PathCase};
+use glob::{MatchOptions, Pattern};
+use std::path::{Component, Path, PathBuf};
+
+/// Names of the rules flagging system caches, in the order they are checked.
+pub const SYSTEM_CACHE_RULES: [&str; 4] = [
+    "temp_files",
+    "browser_cache",
+    "thumbnail_cache",
+    "crash_dump",
+];
+
+/// A kind of temporary file or cache that can be toggled in configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemCache {
+    /// Identifier used in configuration (e.g. `browser_caches`)
+    pub name: &'static str,
+
+    /// Rule flagging it
+    pub rule: &'static str,
+
+    /// Description used in reasons
+    pub label: &'static str,
+
+    /// What deleting it means, used in reasons
+    pub note: &'static str,
+
+    /// Path patterns of its locations
+    pub locations: &'static [&'static str],
+
+    /// Environment variables naming a directory whose contents are flagged
+    pub env: &'static [&'static str],
+}
+
+/// Known temporary files and caches.
+pub const SYSTEM_CACHES: &[SystemCache] = &[
+    SystemCache {
+        name: "temp",
+        rule: "temp_files",
+        label: "Temporary file",
+        note: "may still be in use",
+        locations: &["AppData/Local/Temp/*"],
+        env: &["TEMP", "TMP"],
+    },
+    SystemCache {
+        name: "windows_temp",
+        rule: "temp_files",
+        label: "Windows temporary file",
+        note: "may still be in use",
+        locations: &["/Windows/Temp/*"],
+        env: &[],
+    },
+    SystemCache {
+        name: "browser_caches",
+        rule: "browser_cache",
+        label: "Browser cache",
+        note: "rebuilt by the browser, which should be closed first",
+        locations: &[
+            "AppData/Local/Google/Chrome/User Data/*/Cache",
+            "AppData/Local/Google/Chrome/User Data/*/Code Cache",
+            "AppData/Local/Google/Chrome/User Data/*/GPUCache",
+            "AppData/Local/Microsoft/Edge/User Data/*/Cache",
+            "AppData/Local/Microsoft/Edge/User Data/*/Code Cache",
+            "AppData/Local/Microsoft/Edge/User Data/*/GPUCache",
+            "AppData/Local/BraveSoftware/Brave-Browser/User Data/*/Cache",
+            "AppData/Local/Mozilla/Firefox/Profiles/*/cache2",
+            ".cache/google-chrome/*/Cache",
+            ".cache/chromium/*/Cache",
+            ".cache/mozilla/firefox/*/cache2",
+            "Library/Caches/Google/Chrome/*/Cache",
+            "Library/Caches/Firefox/Profiles/*/cache2",
+        ],
+        env: &[],
+    },
+    SystemCache {
+        name: "thumbnail_caches",
+        rule: "thumbnail_cache",
+        label: "Thumbnail cache",
+        note: "rebuilt on demand",
+        locations: &[
+            "AppData/Local/Microsoft/Windows/Explorer/thumbcache_*.db",
+            ".cache/thumbnails",
+        ],
+        env: &[],
+    },
+    SystemCache {
+        name: "crash_dumps",
+        rule: "crash_dump",
+        label: "Crash dump",
+        note: "only needed to debug the crash",
+        locations: &["*.dmp", "AppData/Local/CrashDumps/*", "/Windows/Minidump/*"],
+        env: &[],
+    },
+];
+
+/// A compiled location pattern.
+#[derive(Debug, Clone)]
+struct Location {
+    components: Vec<Pattern>,
+    anchored: bool,
+}
+
+impl Location {
+    fn new(source: &str) -> Self {
+        let anchored = source.starts_with('/');
+        let components = source
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .map(|part| Pattern::new(part).expect("built-in location patterns are valid"))
+            .collect();
+        Self {
+            components,
+            anchored,
+        }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            case_sensitive: PathCase::native() == PathCase::Sensitive,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let names: Vec<&str> = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+        if names.len() < self.components.len()
+            || (self.anchored && names.len() != self.components.len())
+        {
+            return false;
+        }
+        let tail = &names[names.len() - self.components.len()..];
+        self.components
+            .iter()
+            .zip(tail)
+            .all(|(pattern, name)| pattern.matches_with(name, options))
+    }
+}
+
+/// Rule that flags the temporary files and caches of one of
+/// [`SYSTEM_CACHE_RULES`].
+pub struct SystemCacheRule {
+    name: &'static str,
+    caches: Vec<(&'static SystemCache, Vec<Location>)>,
+    env_dirs: Vec<(PathBuf, &'static SystemCache)>,
+}
+
+impl SystemCacheRule {
+    /// Creates the rule `rule` (one of [`SYSTEM_CACHE_RULES`]), flagging
+    /// the named caches it covers.
+    ///
+    /// Unknown names are ignored. Directories named by the caches'
+    /// environment variables are read once, here.
+    pub fn new(rule: &'static str, names: &[&str]) -> Self {
+        let enabled: Vec<&'static SystemCache> = SYSTEM_CACHES
+            .iter()
+            .filter(|cache| cache.rule == rule && names.contains(&cache.name))
+            .collect();
+        let caches = enabled
+            .iter()
+            .map(|cache| {
+                (
+                    *cache,
+                    cache.locations.iter().map(|l| Location::new(l)).collect(),
+                )
+            })
+            .collect();
+        let mut env_dirs: Vec<(PathBuf, &'static SystemCache)> = Vec::new();
+        for cache in enabled {
+            for var in cache.env {
+                let Some(dir) = std::env::var_os(var).filter(|dir| !dir.is_empty()) else {
+                    continue;
+                };
+                let dir = PathBuf::from(dir);
+                if !env_dirs.iter().any(|(known, _)| paths_equal(known, &dir)) {
+                    env_dirs.push((dir, cache));
+                }
+            }
+        }
+        Self {
+            name: rule,
+            caches,
+            env_dirs,
+        }
+    }
+
+    /// Returns true if the rule covers none of the enabled caches.
+    pub fn is_empty(&self) -> bool {
+        self.caches.is_empty()
+    }
+
+    /// Returns the cache `path` belongs to, if it is one this rule flags.
+    fn cache_for(&self, path: &Path) -> Option<&'static SystemCache> {
+        let parent = path.parent();
+        self.env_dirs
+            .iter()
+            .find(|(dir, _)| parent.is_some_and(|parent| paths_equal(parent, dir)))
+            .map(|(_, cache)| *cache)
+            .or_else(|| {
+                self.caches
+                    .iter()
+                    .find(|(_, locations)| locations.iter().any(|l| l.matches(path)))
+                    .map(|(cache, _)| *cache)
+            })
+    }
+}
+
+impl DetectionRule for SystemCacheRule {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn should_flag(&self, entry: &FileEntry, _context: &ScanContext) -> bool {
+        self.cache_for(&entry.path).is_some()
+    }
+
+    fn reason(&self) -> String {
+        let labels: Vec<&str> = self.caches.iter().map(|(cache, _)| cache.label).collect();
+        labels.join(" or ")
+    }
+
+    fn reason_for(&self, entry: &FileEntry) -> String {
+        match self.cache_for(&entry.path) {
+            Some(cache) => format!(
+                "{} ({:.1} MB), {}",
+                cache.label,
+                entry.size as f64 / 1_048_576.0,
+                cache.note
+            ),
+            None => self.reason(),
+        }
+    }
+
+    fn prefilter(&self, _context: &ScanContext) -> PathPruneHints {
+        PathPruneHints::skip_dirs(VCS_DIRS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use std::time::SystemTime;
+
+    fn entry(path: &str, entry_type: EntryType) -> FileEntry {
+        FileEntry::new(
+            PathBuf::from(path),
+            2 * 1_048_576,
+            SystemTime::now(),
+            entry_type,
+        )
+    }
+
+    fn all(rule: &'static str) -> SystemCacheRule {
+        let names: Vec<&str> = SYSTEM_CACHES.iter().map(|cache| cache.name).collect();
+        SystemCacheRule::new(rule, &names)
+    }
+
+    #[test]
+    fn test_locations_match_path_tails() {
+        let context = ScanContext::default();
+        let flags = |rule: &SystemCacheRule, path: &str| {
+            rule.should_flag(&entry(path, EntryType::Directory), &context)
+        };
+
+        let temp = all("temp_files");
+        assert!(flags(&temp, "/Users/ana/AppData/Local/Temp/setup-1234"));
+        assert!(flags(&temp, "/Windows/Temp/cab_5678"));
+        assert!(!flags(&temp, "/Users/ana/AppData/Local/Temp"));
+        assert!(!flags(
+            &temp,
+            "/Users/ana/AppData/Local/Temp/setup-1234/data"
+        ));
+        // C:\Windows\Temp only counts at the root
+        assert!(!flags(&temp, "/backup/Windows/Temp/cab_5678"));
+
+        let browser = all("browser_cache");
+        assert!(flags(
+            &browser,
+            "/Users/ana/AppData/Local/Google/Chrome/User Data/Profile 1/Cache"
+        ));
+        assert!(flags(
+            &browser,
+            "/home/ana/.cache/mozilla/firefox/x1y2.default/cache2"
+        ));
+        assert!(!flags(
+            &browser,
+            "/home/ana/.cache/mozilla/firefox/x1y2.default"
+        ));
+
+        let dumps = all("crash_dump");
+        assert!(dumps.should_flag(&entry("/data/app.dmp", EntryType::File), &context));
+        assert_eq!(
+            dumps.reason_for(&entry("/data/app.dmp", EntryType::File)),
+            "Crash dump (2.0 MB), only needed to debug the crash"
+        );
+    }
+
+    #[test]
+    fn test_caches_can_be_left_out() {
+        let context = ScanContext::default();
+        let temp = SystemCacheRule::new("temp_files", &["windows_temp", "browser_caches"]);
+
+        assert!(temp.should_flag(&entry("/Windows/Temp/x", EntryType::File), &context));
+        assert!(!temp.should_flag(
+            &entry("/Users/ana/AppData/Local/Temp/x", EntryType::File),
+            &context
+        ));
+        assert!(SystemCacheRule::new("thumbnail_cache", &["temp"]).is_empty());
+    }
+}
@@ -0,0 +1,549 @@
+//! Zip bundles of diagnostic information.
+//!
+//! A bundle holds plain text and YAML files so it can be read without
+//! megamaid:
+//!
+//! - `environment.txt`: version, build features, platform and paths
+//! - `config.yaml`: the effective configuration, see [`redact_config`]
+//! - `logs/`: copies of transaction logs, and `logs/summary.txt` with their
+//!   totals and failed operations grouped by error kind
+//! - `scan-errors.txt`: errors met walking the requested directories
+//! - `crash-reports/`: the newest crash reports
+//!
+//! Transaction logs and scan error examples contain file paths. The user's
+//! home directory is shortened to `~` in the files the bundle generates, but
+//! log copies are included as they are.
+
+use crate::config::MegamaidConfig;
+use crate::diagnostics::crash::crash_reports;
+use crate::executor::{LogSummary, OperationStatus, TransactionLog, TransactionLogger};
+use crate::models::timestamp;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Replacement for redacted configuration values.
+const REDACTED: &str = "<redacted>";
+
+/// Key fragments marking configuration values as secret.
+const SECRET_KEYS: [&str; 6] = [
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "credential",
+    "api_key",
+];
+
+/// Keys holding shell commands, which often carry credentials.
+const COMMAND_KEYS: [&str; 1] = ["snapshot_command"];
+
+/// Example paths kept per kind of scan error.
+const MAX_EXAMPLES: usize = 5;
+
+/// Errors writing a diagnostic bundle.
+#[derive(Debug, Error)]
+pub enum DiagnosticsError {
+    /// Reading an input or writing the bundle failed
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The zip archive couldn't be written
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    /// The configuration couldn't be serialized
+    #[error("configuration error: {0}")]
+    Config(#[from] serde_yaml::Error),
+}
+
+/// What to put in a bundle besides the environment and configuration.
+#[derive(Debug, Clone, Default)]
+pub struct BundleOptions {
+    /// Where the configuration came from, e.g. its file path
+    pub config_source: String,
+
+    /// Transaction logs to include
+    pub logs: Vec<PathBuf>,
+
+    /// Directories to walk for scan errors
+    pub scan_paths: Vec<PathBuf>,
+
+    /// Directory holding crash reports
+    pub crash_dir: Option<PathBuf>,
+
+    /// Number of crash reports to include, newest first
+    pub max_crash_reports: usize,
+}
+
+/// What a written bundle contains.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleSummary {
+    /// Names of the files in the bundle
+    pub files: Vec<String>,
+
+    /// Scan errors found across the walked directories
+    pub scan_errors: u64,
+
+    /// Inputs that couldn't be read, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// Version, build and platform details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    /// megamaid version
+    pub version: &'static str,
+
+    /// Cargo features megamaid was built with
+    pub features: Vec<&'static str>,
+
+    /// Operating system, e.g. `windows`
+    pub os: &'static str,
+
+    /// CPU architecture, e.g. `x86_64`
+    pub arch: &'static str,
+
+    /// Available parallelism
+    pub cpus: usize,
+
+    /// Working directory of the process
+    pub current_dir: Option<PathBuf>,
+
+    /// Path of the running executable
+    pub executable: Option<PathBuf>,
+}
+
+impl Environment {
+    /// Describes the running process.
+    pub fn current() -> Self {
+        let features = [
+            ("cli", cfg!(feature = "cli")),
+            ("trash", cfg!(feature = "trash")),
+            ("parallel", cfg!(feature = "parallel")),
+            ("ui-support", cfg!(feature = "ui-support")),
+            ("parquet", cfg!(feature = "parquet")),
+            ("vss", cfg!(feature = "vss")),
+            ("notice", cfg!(feature = "notice")),
+            ("mft", cfg!(feature = "mft")),
+        ];
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            features: features
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name)
+                .collect(),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            cpus: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            current_dir: std::env::current_dir().ok(),
+            executable: std::env::current_exe().ok(),
+        }
+    }
+
+    /// Formats the details as `key: value` lines.
+    pub fn to_text(&self) -> String {
+        let path = |path: &Option<PathBuf>| {
+            path.as_ref().map_or("(unknown)".to_string(), |p| {
+                shorten_home(&p.display().to_string())
+            })
+        };
+        format!(
+            "Version: {}\nFeatures: {}\nOS: {} ({})\nArchitecture: {}\nCPUs: {}\n\
+             Working directory: {}\nExecutable: {}\n",
+            self.version,
+            self.features.join(", "),
+            self.os,
+            std::env::consts::FAMILY,
+            self.arch,
+            self.cpus,
+            path(&self.current_dir),
+            path(&self.executable),
+        )
+    }
+}
+
+/// Errors met walking a directory, grouped by kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanErrorSummary {
+    /// Walked directory
+    pub root: PathBuf,
+
+    /// Entries walked without error
+    pub entries: u64,
+
+    /// Error count and example paths per kind of error
+    pub by_kind: BTreeMap<String, (u64, Vec<PathBuf>)>,
+}
+
+impl ScanErrorSummary {
+    /// Walks `root` like a scan would and records every error instead of
+    /// stopping at the first.
+    pub fn collect(root: &Path, config: &MegamaidConfig) -> Self {
+        let mut summary = Self {
+            root: root.to_path_buf(),
+            ..Self::default()
+        };
+        let walker = WalkDir::new(root)
+            .follow_links(config.scanner.follow_symlinks)
+            .max_depth(config.scanner.max_depth.unwrap_or(usize::MAX));
+
+        for entry in walker {
+            match entry {
+                Ok(entry) => match entry.metadata() {
+                    Ok(_) => summary.entries += 1,
+                    Err(e) => summary.record(&e, entry.path()),
+                },
+                Err(e) => {
+                    let path = e.path().unwrap_or(root).to_path_buf();
+                    summary.record(&e, &path);
+                }
+            }
+        }
+        summary
+    }
+
+    /// Total number of errors.
+    pub fn total(&self) -> u64 {
+        self.by_kind.values().map(|(count, _)| count).sum()
+    }
+
+    fn record(&mut self, error: &walkdir::Error, path: &Path) {
+        let kind = if error.loop_ancestor().is_some() {
+            "symlink loop".to_string()
+        } else {
+            match error.io_error().map(io::Error::kind) {
+                Some(io::ErrorKind::PermissionDenied) => "permission denied".to_string(),
+                Some(io::ErrorKind::NotFound) => "removed during scan".to_string(),
+                Some(kind) => kind.to_string(),
+                None => "other".to_string(),
+            }
+        };
+        let (count, examples) = self.by_kind.entry(kind).or_default();
+        *count += 1;
+        if examples.len() < MAX_EXAMPLES {
+            examples.push(path.to_path_buf());
+        }
+    }
+
+    /// Formats the summary for `scan-errors.txt`.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}: {} entries, {} errors\n",
+            shorten_home(&self.root.display().to_string()),
+            self.entries,
+            self.total()
+        );
+        for (kind, (count, examples)) in &self.by_kind {
+            let _ = writeln!(text, "  {}: {}", kind, count);
+            for path in examples {
+                let _ = writeln!(text, "    {}", shorten_home(&path.display().to_string()));
+            }
+        }
+        text
+    }
+}
+
+/// Returns `config` as YAML with secrets redacted.
+///
+/// Values whose keys mention passwords, secrets, tokens, credentials or API
+/// keys are replaced, as are shell commands such as
+/// `executor.snapshot_command`. The user's home directory is shortened to
+/// `~` everywhere.
+pub fn redact_config(config: &MegamaidConfig) -> Result<String, DiagnosticsError> {
+    let mut value = serde_yaml::to_value(config)?;
+    redact_value(&mut value);
+    Ok(serde_yaml::to_string(&value)?)
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping.iter_mut() {
+                let key = key.as_str().unwrap_or_default().to_ascii_lowercase();
+                let secret = SECRET_KEYS.iter().any(|k| key.contains(k))
+                    || COMMAND_KEYS.contains(&key.as_str());
+                if secret && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Sequence(items) => items.iter_mut().for_each(redact_value),
+        Value::String(s) => *s = shorten_home(s),
+        _ => {}
+    }
+}
+
+/// Replaces a leading home directory in `text` with `~`.
+fn shorten_home(text: &str) -> String {
+    match dirs::home_dir() {
+        Some(home) if !home.as_os_str().is_empty() => {
+            let home = home.display().to_string();
+            match text.strip_prefix(&home) {
+                Some(rest) if rest.is_empty() || rest.starts_with(std::path::is_separator) => {
+                    format!("~{}", rest)
+                }
+                _ => text.to_string(),
+            }
+        }
+        _ => text.to_string(),
+    }
+}
+
+/// Writes diagnostic bundles.
+pub struct DiagnosticBundle;
+
+impl DiagnosticBundle {
+    /// Writes a bundle for `config` to `output`.
+    ///
+    /// Logs and crash reports that can't be read are listed in the summary
+    /// instead of failing the bundle.
+    pub fn write(
+        config: &MegamaidConfig,
+        options: &BundleOptions,
+        output: &Path,
+    ) -> Result<BundleSummary, DiagnosticsError> {
+        let mut zip = ZipWriter::new(File::create(output)?);
+        let mut summary = BundleSummary::default();
+        let mut add = |zip: &mut ZipWriter<File>, name: String, bytes: &[u8]| {
+            let file_options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+            zip.start_file(name.as_str(), file_options)?;
+            zip.write_all(bytes)?;
+            summary.files.push(name);
+            Ok::<_, DiagnosticsError>(())
+        };
+
+        add(
+            &mut zip,
+            "environment.txt".to_string(),
+            format!(
+                "{}Configuration: {}\n",
+                Environment::current().to_text(),
+                options.config_source
+            )
+            .as_bytes(),
+        )?;
+        add(
+            &mut zip,
+            "config.yaml".to_string(),
+            redact_config(config)?.as_bytes(),
+        )?;
+
+        let mut skipped = Vec::new();
+        if !options.logs.is_empty() {
+            let (mut names, mut logs) = (Vec::new(), Vec::new());
+            for path in &options.logs {
+                let name = file_name(path);
+                match fs::read(path) {
+                    Ok(bytes) => add(&mut zip, format!("logs/{}", name), &bytes)?,
+                    Err(e) => {
+                        skipped.push(format!("{}: {}", path.display(), e));
+                        continue;
+                    }
+                }
+                match TransactionLogger::read(path) {
+                    Ok(log) => {
+                        names.push(name);
+                        logs.push(log);
+                    }
+                    Err(e) => skipped.push(format!("{}: {}", path.display(), e)),
+                }
+            }
+            add(
+                &mut zip,
+                "logs/summary.txt".to_string(),
+                log_summary_text(&names, &logs).as_bytes(),
+            )?;
+        }
+
+        if !options.scan_paths.is_empty() {
+            let mut text = String::new();
+            for root in &options.scan_paths {
+                let errors = ScanErrorSummary::collect(root, config);
+                summary.scan_errors += errors.total();
+                text.push_str(&errors.to_text());
+            }
+            add(&mut zip, "scan-errors.txt".to_string(), text.as_bytes())?;
+        }
+
+        if let Some(dir) = &options.crash_dir {
+            for path in crash_reports(dir)
+                .into_iter()
+                .take(options.max_crash_reports)
+            {
+                match fs::read(&path) {
+                    Ok(bytes) => add(
+                        &mut zip,
+                        format!("crash-reports/{}", file_name(&path)),
+                        &bytes,
+                    )?,
+                    Err(e) => skipped.push(format!("{}: {}", path.display(), e)),
+                }
+            }
+        }
+
+        zip.finish()?;
+        summary.skipped = skipped;
+        Ok(summary)
+    }
+
+    /// Returns up to `max` transaction logs in `dir`, most recently modified
+    /// first.
+    ///
+    /// Logs are recognized by name: YAML files, compressed or not, with
+    /// `log` in their name (such as the default `execution-log.yaml`).
+    pub fn recent_logs(dir: &Path, max: usize) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut logs: Vec<_> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_lowercase();
+                name.contains("log") && (name.ends_with(".yaml") || name.ends_with(".yaml.zst"))
+            })
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+                Some((metadata.modified().ok(), entry.path()))
+            })
+            .collect();
+        logs.sort_by(|a, b| b.cmp(a));
+        logs.into_iter().take(max).map(|(_, path)| path).collect()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || "unnamed".to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
+
+/// Formats totals and failed operations of the bundled logs.
+fn log_summary_text(names: &[String], logs: &[TransactionLog]) -> String {
+    let summary = LogSummary::from_logs(logs);
+    let failed = format!("{:?}", OperationStatus::Failed);
+
+    let mut text = format!(
+        "Logs: {}\nRuns: {} completed, {} failed, {} incomplete, {} paused\n\
+         Operations: {} total, {} successful, {} failed, {} skipped\n",
+        summary.logs,
+        summary.completed,
+        summary.failed_runs,
+        summary.incomplete,
+        summary.paused,
+        summary.total_operations,
+        summary.successful,
+        summary.failed,
+        summary.skipped
+    );
+
+    for (name, log) in names.iter().zip(logs) {
+        let mut kinds: BTreeMap<&str, (usize, &str)> = BTreeMap::new();
+        for op in log.operations.iter().filter(|op| op.status == failed) {
+            let kind = op.error.as_ref().map_or("Other errors", |e| e.label());
+            let (count, _) = kinds
+                .entry(kind)
+                .or_insert((0, op.error.as_ref().map_or("", |e| e.message())));
+            *count += 1;
+        }
+        let _ = writeln!(
+            text,
+            "\n{}: {:?}, started {}",
+            name,
+            log.status,
+            timestamp::format(log.started_at)
+        );
+        for (kind, (count, example)) in kinds {
+            let _ = writeln!(text, "  {} failed ({}): {}", count, kind, example);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_redacts_secrets_and_commands() {
+        let mut value: Value = serde_yaml::from_str(
+            "remote:\n  Password: hunter2\n  api_key_file: null\n  hosts: [a]\n\
+             executor:\n  snapshot_command: snap --token abc\n  batch_size: 100\n",
+        )
+        .unwrap();
+        redact_value(&mut value);
+
+        assert_eq!(value["remote"]["Password"], Value::from(REDACTED));
+        assert_eq!(value["remote"]["api_key_file"], Value::Null);
+        assert_eq!(value["remote"]["hosts"][0], Value::from("a"));
+        assert_eq!(value["executor"]["snapshot_command"], Value::from(REDACTED));
+        assert_eq!(value["executor"]["batch_size"], Value::from(100));
+
+        if let Some(home) = dirs::home_dir() {
+            let inside = home.join("plans").display().to_string();
+            assert!(shorten_home(&inside).starts_with('~'));
+            assert_eq!(shorten_home("/elsewhere"), "/elsewhere");
+        }
+    }
+
+    #[test]
+    fn test_scan_error_summary() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.txt"), "a").unwrap();
+        let config = MegamaidConfig::default();
+
+        let summary = ScanErrorSummary::collect(temp.path(), &config);
+        assert_eq!(summary.entries, 2);
+        assert_eq!(summary.total(), 0);
+
+        let missing = temp.path().join("missing");
+        let summary = ScanErrorSummary::collect(&missing, &config);
+        assert_eq!(summary.total(), 1);
+        assert_eq!(
+            summary.by_kind["removed during scan"],
+            (1, vec![missing.clone()])
+        );
+        assert!(summary.to_text().contains("removed during scan: 1"));
+    }
+
+    #[test]
+    fn test_recent_logs_newest_first() {
+        let temp = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        for (name, age) in [
+            ("execution-log.yaml", 30),
+            ("shard2-log.yaml.zst", 10),
+            ("old-log.yaml", 60),
+            ("cleanup-plan.yaml", 0),
+        ] {
+            let path = temp.path().join(name);
+            fs::write(&path, "").unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+        }
+
+        let names: Vec<_> = DiagnosticBundle::recent_logs(temp.path(), 2)
+            .iter()
+            .map(|path| file_name(path))
+            .collect();
+        assert_eq!(names, vec!["shard2-log.yaml.zst", "execution-log.yaml"]);
+    }
+}
@@ -0,0 +1,122 @@
+//! Crash report files written by a panic hook.
+
+use crate::models::timestamp;
+use chrono::Utc;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+
+/// File name prefix of crash reports.
+const REPORT_PREFIX: &str = "megamaid-crash-";
+
+/// Directory crash reports go to when none is configured.
+pub fn default_crash_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("megamaid").join("crash-reports"))
+}
+
+/// Installs a panic hook that writes a crash report to `dir`.
+///
+/// The previous hook still runs afterwards, so the panic message is printed
+/// as usual. Failing to write the report is not an error; the path of a
+/// written report is printed to stderr.
+pub fn install_panic_hook(dir: PathBuf) {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let report = format_report(info, &Backtrace::force_capture());
+        if let Some(path) = write_report(&dir, &report) {
+            eprintln!("Crash report written to {}", path.display());
+        }
+        previous(info);
+    }));
+}
+
+/// Lists the crash reports in `dir`, newest first.
+pub fn crash_reports(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(REPORT_PREFIX))
+        })
+        .collect();
+    // Names start with a sortable timestamp
+    reports.sort_by(|a, b| b.cmp(a));
+    reports
+}
+
+/// Writes `report` to a new file in `dir`.
+fn write_report(dir: &Path, report: &str) -> Option<PathBuf> {
+    fs::create_dir_all(dir).ok()?;
+    let name = format!(
+        "{}{}-{}.txt",
+        REPORT_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        std::process::id()
+    );
+    let path = dir.join(name);
+    fs::write(&path, report).ok()?;
+    Some(path)
+}
+
+/// Formats the crash report for a panic.
+fn format_report(info: &PanicHookInfo, backtrace: &Backtrace) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string());
+    let location = info
+        .location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "(unknown)".to_string());
+    let thread = std::thread::current();
+
+    format!(
+        "megamaid {} crash report\n\
+         Time: {}\n\
+         Thread: {}\n\
+         Location: {}\n\
+         Message: {}\n\
+         Command line: {}\n\n\
+         Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        timestamp::format(Utc::now()),
+        thread.name().unwrap_or("(unnamed)"),
+        location,
+        message,
+        std::env::args().collect::<Vec<_>>().join(" "),
+        backtrace
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reports_are_listed_newest_first() {
+        let temp = TempDir::new().unwrap();
+        assert!(crash_reports(&temp.path().join("missing")).is_empty());
+
+        let older = temp
+            .path()
+            .join("megamaid-crash-20250101T000000.000Z-1.txt");
+        let newer = temp
+            .path()
+            .join("megamaid-crash-20250102T000000.000Z-1.txt");
+        fs::write(&older, "").unwrap();
+        fs::write(&newer, "").unwrap();
+        fs::write(temp.path().join("notes.txt"), "").unwrap();
+
+        let written = write_report(temp.path(), "report").unwrap();
+        assert_eq!(fs::read_to_string(&written).unwrap(), "report");
+        assert_eq!(crash_reports(temp.path()), vec![written, newer, older]);
+    }
+}
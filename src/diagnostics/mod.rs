@@ -0,0 +1,19 @@
+//! Diagnostic bundles and crash reports for bug reports.
+//!
+//! A [`DiagnosticBundle`](crate::diagnostics::DiagnosticBundle) gathers what
+//! a maintainer needs to look into a problem (version and build features, the
+//! effective configuration with secrets redacted, recent transaction logs,
+//! summaries of the errors a scan runs into, environment details and crash
+//! reports) into one zip file.
+//!
+//! [`install_panic_hook`](crate::diagnostics::install_panic_hook) makes a
+//! crash write a report file that later bundles pick up.
+
+pub mod bundle;
+pub mod crash;
+
+pub use bundle::{
+    redact_config, BundleOptions, BundleSummary, DiagnosticBundle, DiagnosticsError, Environment,
+    ScanErrorSummary,
+};
+pub use crash::{crash_reports, default_crash_dir, install_panic_hook};
@@ -0,0 +1,465 @@
+//! Serializable summaries shared by the CLI's JSON output and the desktop UI.
+//!
+//! These are the shapes front ends consume, so they serialize with camelCase
+//! field names and are kept stable: fields may be added, but are not renamed
+//! or removed. Plan files and scan caches keep their own snake_case formats.
+
+use crate::analysis::UsageAnalyzer;
+use crate::executor::recycle_bin::volume_of;
+use crate::executor::volumes::detect_kind;
+use crate::executor::{ExecutionResult, VolumeKind};
+use crate::models::{timestamp, AggregateStats, CleanupPlan, FileEntry, Protection, RuleStats};
+use crate::scanner::ScanErrorReport;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+/// File name of the scan summary `scan --summary-file` writes by default,
+/// next to the plan.
+pub const SCAN_SUMMARY_FILE: &str = "scan-summary.json";
+
+/// Event emitted when a scan starts; the payload is the scanned path.
+pub const SCAN_STARTED_EVENT: &str = "scan:started";
+
+/// Event emitted while a scan runs, with a [`ScanProgressEvent`] payload.
+pub const SCAN_PROGRESS_EVENT: &str = "scan:progress";
+
+/// Event emitted when a scan finishes, with a [`ScanSummary`] payload.
+pub const SCAN_COMPLETE_EVENT: &str = "scan:complete";
+
+/// Event emitted when a scan fails; the payload is the error message.
+pub const SCAN_ERROR_EVENT: &str = "scan:error";
+
+/// Totals of a finished scan.
+///
+/// Besides the totals the UI has always shown, a summary can describe the
+/// whole scan: when it ran, what each rule found and where the space goes.
+/// `scan --summary-file` writes it as `scan-summary.json` for external
+/// dashboards and monitoring to pick up.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanSummary {
+    /// Scanned directory
+    pub path: String,
+
+    /// Number of entries found, files and directories
+    pub total_entries: usize,
+
+    /// Number of files found
+    pub total_files: usize,
+
+    /// Combined size of the files in bytes
+    pub total_size: u64,
+
+    /// Errors that didn't stop the scan
+    pub errors: Vec<String>,
+
+    /// Errors that were for lack of permission
+    #[serde(default)]
+    pub permission_denied: usize,
+
+    /// When the scan started (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+
+    /// Wall-clock time of the scan and detection, in milliseconds
+    #[serde(default)]
+    pub duration_ms: u64,
+
+    /// Scanned roots, below `path`
+    #[serde(default)]
+    pub roots: Vec<String>,
+
+    /// Volumes the roots are on
+    #[serde(default)]
+    pub volumes: Vec<VolumeSummary>,
+
+    /// Per-rule statistics of detection
+    #[serde(default)]
+    pub rule_stats: Vec<RuleCoverage>,
+
+    /// Largest directories, biggest first
+    #[serde(default)]
+    pub top_directories: Vec<DirectorySize>,
+
+    /// Cleanup plan written from the scan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_file: Option<String>,
+}
+
+impl ScanSummary {
+    /// Summarizes the entries of a scan of `path`.
+    pub fn from_entries(path: &Path, entries: &[FileEntry], errors: Vec<String>) -> Self {
+        let files = entries.iter().filter(|e| e.is_file());
+        Self {
+            path: path.to_string_lossy().into_owned(),
+            total_entries: entries.len(),
+            total_files: files.clone().count(),
+            total_size: files.map(|e| e.size).sum(),
+            errors,
+            ..Default::default()
+        }
+    }
+
+    /// Takes the errors from the scanner's report.
+    pub fn with_errors(mut self, report: &ScanErrorReport) -> Self {
+        self.errors = report
+            .entries
+            .iter()
+            .map(|e| format!("{}: {}", e.path.display(), e.message))
+            .collect();
+        self.permission_denied = report.permission_denied();
+        self
+    }
+
+    /// Records when the scan started and how long it took.
+    pub fn with_timing(mut self, started_at: SystemTime, duration: Duration) -> Self {
+        self.started_at = Some(timestamp::format(DateTime::<Utc>::from(started_at)));
+        self.duration_ms = duration.as_millis() as u64;
+        self
+    }
+
+    /// Lists the scanned roots and the volumes they are on.
+    pub fn with_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots = roots.iter().map(|r| r.display().to_string()).collect();
+        for root in roots {
+            let absolute = std::path::absolute(root).unwrap_or_else(|_| root.clone());
+            let volume = volume_of(&absolute).display().to_string();
+            match self.volumes.iter_mut().find(|v| v.root == volume) {
+                Some(known) => known.roots.push(root.display().to_string()),
+                None => self.volumes.push(VolumeSummary {
+                    kind: detect_kind(Path::new(&volume)),
+                    root: volume,
+                    roots: vec![root.display().to_string()],
+                }),
+            }
+        }
+        self
+    }
+
+    /// Adds the per-rule statistics of the plan made from the scan.
+    pub fn with_plan(mut self, plan: &CleanupPlan, plan_file: &Path) -> Self {
+        self.rule_stats = plan
+            .detection_stats
+            .iter()
+            .map(RuleCoverage::from)
+            .collect();
+        self.plan_file = Some(plan_file.display().to_string());
+        self
+    }
+
+    /// Lists the `top` largest directories among `entries`.
+    pub fn with_top_directories(mut self, entries: &[FileEntry], top: usize) -> Self {
+        self.top_directories = UsageAnalyzer::new()
+            .with_top(top)
+            .analyze(entries)
+            .largest_directories
+            .into_iter()
+            .map(|dir| DirectorySize {
+                path: dir.path.display().to_string(),
+                bytes: dir.bytes,
+            })
+            .collect();
+        self
+    }
+
+    /// Writes the summary to `path` as JSON, replacing it at once so readers
+    /// never see half of it.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(temp, path)
+    }
+}
+
+/// A volume holding scanned roots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeSummary {
+    /// Root of the volume (e.g. `C:\` or `/home`)
+    pub root: String,
+
+    /// Kind of storage the volume is on
+    pub kind: VolumeKind,
+
+    /// Scanned roots on the volume
+    pub roots: Vec<String>,
+}
+
+/// A directory and its size.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectorySize {
+    /// Path of the directory
+    pub path: String,
+
+    /// Combined size of its contents in bytes
+    pub bytes: u64,
+}
+
+/// A finished scan: its entries and their totals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    /// Scanned entries
+    pub entries: Vec<FileEntry>,
+
+    /// Totals of the scan
+    #[serde(flatten)]
+    pub summary: ScanSummary,
+}
+
+/// Progress of a running scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanProgressEvent {
+    /// Directory being scanned
+    pub path: String,
+
+    /// Entries scanned so far
+    pub files_scanned: usize,
+}
+
+/// Statistics of a cleanup plan.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanStats {
+    /// Number of entries in the plan
+    pub total_entries: usize,
+
+    /// Entries marked for deletion
+    pub delete_count: usize,
+
+    /// Entries marked for review
+    pub review_count: usize,
+
+    /// Entries marked to keep
+    pub keep_count: usize,
+
+    /// Combined size of the entries in bytes
+    pub total_size: u64,
+
+    /// Per-rule statistics recorded when the plan was generated
+    pub detection_stats: Vec<RuleCoverage>,
+
+    /// Entry count and size per rule
+    pub by_rule: BTreeMap<String, AggregateStats>,
+
+    /// Entry count and size per lowercase extension
+    pub by_extension: BTreeMap<String, AggregateStats>,
+
+    /// Count and size of the detections each protection held back
+    pub by_protection: BTreeMap<Protection, AggregateStats>,
+}
+
+impl From<&CleanupPlan> for PlanStats {
+    fn from(plan: &CleanupPlan) -> Self {
+        Self {
+            total_entries: plan.entries.len(),
+            delete_count: plan.delete_count(),
+            review_count: plan.review_count(),
+            keep_count: plan.keep_count(),
+            total_size: plan.total_size(),
+            detection_stats: plan
+                .detection_stats
+                .iter()
+                .map(RuleCoverage::from)
+                .collect(),
+            by_rule: plan.aggregate_by_rule(),
+            by_extension: plan.aggregate_by_extension(),
+            by_protection: plan.aggregate_by_protection(),
+        }
+    }
+}
+
+/// How much of a scan a detection rule flagged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleCoverage {
+    /// Name of the detection rule
+    pub rule_name: String,
+
+    /// Entries the rule was evaluated against
+    pub entries_evaluated: u64,
+
+    /// Entries attributed to the rule
+    pub entries_flagged: u64,
+
+    /// Combined size of the flagged entries in bytes
+    pub bytes_flagged: u64,
+
+    /// Time spent evaluating the rule, in milliseconds
+    pub evaluation_time_ms: f64,
+
+    /// Path of the largest flagged entry, relative to the plan's base path
+    pub largest_entry: Option<String>,
+
+    /// Size of the largest flagged entry in bytes
+    pub largest_entry_bytes: u64,
+}
+
+impl From<&RuleStats> for RuleCoverage {
+    fn from(stats: &RuleStats) -> Self {
+        Self {
+            rule_name: stats.rule_name.clone(),
+            entries_evaluated: stats.entries_evaluated,
+            entries_flagged: stats.entries_flagged,
+            bytes_flagged: stats.bytes_flagged,
+            evaluation_time_ms: stats.evaluation_time_ms,
+            largest_entry: stats.largest_entry.clone(),
+            largest_entry_bytes: stats.largest_entry_bytes,
+        }
+    }
+}
+
+/// Outcome of executing a plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionSummary {
+    /// Operations attempted
+    pub total_operations: usize,
+
+    /// Operations that succeeded
+    pub successful: usize,
+
+    /// Operations that failed
+    pub failed: usize,
+
+    /// Entries skipped (kept, reviewed or outside the shard)
+    pub skipped: usize,
+
+    /// Bytes freed
+    pub space_freed: u64,
+
+    /// Bytes deleted outright
+    pub bytes_deleted: u64,
+
+    /// Bytes moved to the recycle bin
+    pub bytes_recycled: u64,
+
+    /// Bytes moved to the backup directory
+    pub bytes_backed_up: u64,
+
+    /// Bytes of skipped entries
+    pub bytes_skipped: u64,
+
+    /// Wall-clock time of the run, in milliseconds
+    pub duration_ms: u64,
+
+    /// The run stopped early because a pause was requested
+    pub paused: bool,
+}
+
+impl From<&ExecutionResult> for ExecutionSummary {
+    fn from(result: &ExecutionResult) -> Self {
+        let summary = &result.summary;
+        Self {
+            total_operations: summary.total_operations,
+            successful: summary.successful,
+            failed: summary.failed,
+            skipped: summary.skipped,
+            space_freed: summary.space_freed,
+            bytes_deleted: summary.space_by_action.deleted,
+            bytes_recycled: summary.space_by_action.recycled,
+            bytes_backed_up: summary.space_by_action.backed_up,
+            bytes_skipped: summary.space_by_action.skipped,
+            duration_ms: summary.duration.as_millis() as u64,
+            paused: result.paused,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CleanupAction, CleanupEntry, EntryType};
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_scan_summary_counts_files() {
+        let entries = vec![
+            FileEntry::new(
+                PathBuf::from("/r"),
+                300,
+                SystemTime::now(),
+                EntryType::Directory,
+            ),
+            FileEntry::new(
+                PathBuf::from("/r/a"),
+                100,
+                SystemTime::now(),
+                EntryType::File,
+            ),
+            FileEntry::new(
+                PathBuf::from("/r/b"),
+                200,
+                SystemTime::now(),
+                EntryType::File,
+            ),
+        ];
+
+        let summary = ScanSummary::from_entries(Path::new("/r"), &entries, Vec::new());
+
+        assert_eq!(summary.total_entries, 3);
+        assert_eq!(summary.total_files, 2);
+        assert_eq!(summary.total_size, 300);
+    }
+
+    #[test]
+    fn test_serializes_camel_case() {
+        let result = ScanResult {
+            entries: Vec::new(),
+            summary: ScanSummary {
+                path: "/r".to_string(),
+                total_files: 2,
+                ..Default::default()
+            },
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["totalFiles"], 2);
+        assert_eq!(json["path"], "/r");
+        assert!(json.get("summary").is_none());
+
+        let progress = ScanProgressEvent {
+            path: "/r".to_string(),
+            files_scanned: 10,
+        };
+        assert_eq!(
+            serde_json::to_string(&progress).unwrap(),
+            r#"{"path":"/r","filesScanned":10}"#
+        );
+    }
+
+    #[test]
+    fn test_plan_stats_from_plan() {
+        let mut plan = CleanupPlan::new(PathBuf::from("/r"));
+        plan.add_entry(CleanupEntry::new(
+            "target".to_string(),
+            500,
+            "2025-01-01T00:00:00Z".to_string(),
+            CleanupAction::Delete,
+            "build_artifact".to_string(),
+            "Build output".to_string(),
+        ));
+        plan.detection_stats.push(RuleStats {
+            rule_name: "build_artifact".to_string(),
+            entries_flagged: 1,
+            ..Default::default()
+        });
+
+        let stats = PlanStats::from(&plan);
+        assert_eq!(stats.total_entries, 1);
+        assert_eq!(stats.delete_count, 1);
+        assert_eq!(stats.total_size, 500);
+        assert_eq!(stats.by_rule["build_artifact"].size, 500);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["deleteCount"], 1);
+        assert_eq!(json["detectionStats"][0]["ruleName"], "build_artifact");
+        assert_eq!(json["byExtension"][""]["count"], 1);
+    }
+}
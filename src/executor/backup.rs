@@ -0,0 +1,332 @@
+//! Verifying and pruning the backups left by backup-mode executions.
+//!
+//! Backup mode moves entries into a backup directory instead of deleting
+//! them, and the transaction log records where each one went. Before those
+//! backups are deleted for good, [`verify`] checks every one against its
+//! original path. Something there again means the user copied the backup
+//! back or a tool regenerated the entry; either way they may still be relying
+//! on the backup (if only to compare against), so [`prune`] refuses to remove
+//! any backup until forced. Verification only reads the filesystem.
+
+use crate::executor::engine::{OperationAction, OperationStatus};
+use crate::executor::transaction::TransactionLog;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors pruning backups.
+#[derive(Debug, Error)]
+pub enum BackupError {
+    /// Backed-up entries exist again at their original path
+    #[error("{0} backed-up entries exist again at their original path; pass --force to prune their backups anyway")]
+    Reappeared(usize),
+}
+
+/// What verification found for one backup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupState {
+    /// The original path is still empty, so the backup is its only copy
+    Sole,
+    /// The original path holds a file identical to the backup again
+    Restored,
+    /// Something different exists at the original path again
+    Regenerated,
+    /// The backup itself is gone
+    Missing,
+}
+
+impl fmt::Display for BackupState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Sole => "only copy",
+            Self::Restored => "restored",
+            Self::Regenerated => "regenerated",
+            Self::Missing => "missing",
+        };
+        f.write_str(label)
+    }
+}
+
+/// A backup recorded in a transaction log and its verified state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupCheck {
+    /// Path the entry was moved from
+    pub original: PathBuf,
+    /// Path the entry was moved to
+    pub backup: PathBuf,
+    /// Backup directory of the execution, if logged
+    pub backup_dir: Option<PathBuf>,
+    /// Size recorded when the entry was moved
+    pub bytes: u64,
+    pub state: BackupState,
+}
+
+/// Verified backups of one or more executions.
+#[derive(Debug, Clone, Default)]
+pub struct BackupReport {
+    pub checks: Vec<BackupCheck>,
+
+    /// Successful backup moves logged without their backup path (by older
+    /// versions); these can't be verified and are never pruned
+    pub untracked: usize,
+}
+
+impl BackupReport {
+    /// Returns the backups whose original path exists again.
+    pub fn reappeared(&self) -> impl Iterator<Item = &BackupCheck> {
+        self.checks.iter().filter(|check| {
+            matches!(
+                check.state,
+                BackupState::Restored | BackupState::Regenerated
+            )
+        })
+    }
+
+    /// Returns the backups that still exist.
+    pub fn present(&self) -> impl Iterator<Item = &BackupCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.state != BackupState::Missing)
+    }
+}
+
+/// Outcome of pruning backups.
+#[derive(Debug, Default)]
+pub struct PruneSummary {
+    /// Backups deleted
+    pub removed: usize,
+
+    /// Recorded size of the deleted backups
+    pub bytes: u64,
+
+    /// Backups that couldn't be deleted, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Checks every successful backup move recorded in `logs`.
+pub fn verify(logs: &[TransactionLog]) -> BackupReport {
+    let action = format!("{:?}", OperationAction::MoveToBackup);
+    let success = format!("{:?}", OperationStatus::Success);
+    let mut report = BackupReport::default();
+    let mut seen = HashSet::new();
+
+    for log in logs {
+        for op in &log.operations {
+            if op.action != action || op.status != success {
+                continue;
+            }
+            let Some(backup) = &op.backup_path else {
+                report.untracked += 1;
+                continue;
+            };
+            if !seen.insert(backup.clone()) {
+                continue;
+            }
+
+            let original = PathBuf::from(&op.path);
+            report.checks.push(BackupCheck {
+                state: state_of(&original, backup),
+                original,
+                backup: backup.clone(),
+                backup_dir: log.options.backup_dir.clone(),
+                bytes: op.size_freed.unwrap_or(0),
+            });
+        }
+    }
+
+    report
+}
+
+/// Deletes the backups in `report` that still exist.
+///
+/// Fails without deleting anything if an original path exists again, unless
+/// `force` is set. Directories emptied by the deletion are removed up to the
+/// backup directory.
+pub fn prune(report: &BackupReport, force: bool) -> Result<PruneSummary, BackupError> {
+    let reappeared = report.reappeared().count();
+    if reappeared > 0 && !force {
+        return Err(BackupError::Reappeared(reappeared));
+    }
+
+    let mut summary = PruneSummary::default();
+    for check in report.present() {
+        let result = if check.backup.is_dir() {
+            fs::remove_dir_all(&check.backup)
+        } else {
+            fs::remove_file(&check.backup)
+        };
+        match result {
+            Ok(()) => {
+                summary.removed += 1;
+                summary.bytes += check.bytes;
+                if let Some(dir) = &check.backup_dir {
+                    remove_empty_parents(&check.backup, dir);
+                }
+            }
+            Err(e) => summary.failed.push((check.backup.clone(), e.to_string())),
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Compares the original path of a backup with the backup itself.
+fn state_of(original: &Path, backup: &Path) -> BackupState {
+    let Ok(backup_meta) = backup.symlink_metadata() else {
+        return BackupState::Missing;
+    };
+    let Ok(original_meta) = original.symlink_metadata() else {
+        return BackupState::Sole;
+    };
+
+    // Only files are compared; a directory tree would take a full walk
+    let same = original_meta.is_file()
+        && backup_meta.is_file()
+        && original_meta.len() == backup_meta.len()
+        && matches!((hash_file(original), hash_file(backup)), (Ok(a), Ok(b)) if a == b);
+    if same {
+        BackupState::Restored
+    } else {
+        BackupState::Regenerated
+    }
+}
+
+/// Computes the SHA-256 hash of a file's contents.
+fn hash_file(path: &Path) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Removes the empty directories between `path` and `root`, exclusive.
+fn remove_empty_parents(path: &Path, root: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::transaction::{LoggedOperation, TransactionOptions, TransactionStatus};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn backup_log(backup_dir: &Path, moves: &[(&Path, Option<&Path>)]) -> TransactionLog {
+        TransactionLog {
+            version: "0.1.0".to_string(),
+            execution_id: "test".to_string(),
+            plan_file: PathBuf::from("plan.yaml"),
+            started_at: Utc::now(),
+            completed_at: None,
+            status: TransactionStatus::Completed,
+            mode: "Batch".to_string(),
+            options: TransactionOptions {
+                dry_run: false,
+                backup_dir: Some(backup_dir.to_path_buf()),
+                use_recycle_bin: false,
+                fail_fast: false,
+            },
+            operations: moves
+                .iter()
+                .map(|(original, backup)| LoggedOperation {
+                    path: original.to_string_lossy().to_string(),
+                    entry_id: None,
+                    action: format!("{:?}", OperationAction::MoveToBackup),
+                    status: format!("{:?}", OperationStatus::Success),
+                    size_freed: Some(4),
+                    error: None,
+                    timestamp: Utc::now(),
+                    streams: Vec::new(),
+                    backup_path: backup.map(Path::to_path_buf),
+                })
+                .collect(),
+            summary: None,
+            shard: None,
+            confirmation: None,
+            snapshot: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_classifies_backups() {
+        let temp = TempDir::new().unwrap();
+        let (project, backups) = (temp.path().join("project"), temp.path().join("backups"));
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(backups.join("logs")).unwrap();
+        for name in ["sole.txt", "restored.txt", "regenerated.txt"] {
+            fs::write(backups.join("logs").join(name), "data").unwrap();
+        }
+        fs::write(project.join("restored.txt"), "data").unwrap();
+        fs::write(project.join("regenerated.txt"), "new data").unwrap();
+
+        let moved = |name: &str| (project.join(name), backups.join("logs").join(name));
+        let names = ["sole.txt", "restored.txt", "regenerated.txt", "gone.txt"];
+        let moves: Vec<_> = names.iter().map(|name| moved(name)).collect();
+        let mut entries: Vec<(&Path, Option<&Path>)> = moves
+            .iter()
+            .map(|(original, backup)| (original.as_path(), Some(backup.as_path())))
+            .collect();
+        entries.push((moves[0].0.as_path(), None));
+
+        let report = verify(&[backup_log(&backups, &entries)]);
+        let states: Vec<_> = report.checks.iter().map(|c| c.state).collect();
+        assert_eq!(
+            states,
+            vec![
+                BackupState::Sole,
+                BackupState::Restored,
+                BackupState::Regenerated,
+                BackupState::Missing
+            ]
+        );
+        assert_eq!(report.untracked, 1);
+        assert_eq!(report.reappeared().count(), 2);
+    }
+
+    #[test]
+    fn test_prune_requires_force_when_entries_reappeared() {
+        let temp = TempDir::new().unwrap();
+        let (project, backups) = (temp.path().join("project"), temp.path().join("backups"));
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(backups.join("a/b")).unwrap();
+        fs::write(backups.join("a/b/cache.bin"), "data").unwrap();
+        fs::write(backups.join("kept.bin"), "data").unwrap();
+        fs::write(project.join("kept.bin"), "rebuilt").unwrap();
+
+        let (cache, kept) = (project.join("a/b/cache.bin"), project.join("kept.bin"));
+        let (cache_backup, kept_backup) = (backups.join("a/b/cache.bin"), backups.join("kept.bin"));
+        let log = backup_log(
+            &backups,
+            &[
+                (&cache, Some(cache_backup.as_path())),
+                (&kept, Some(kept_backup.as_path())),
+            ],
+        );
+        let report = verify(&[log]);
+
+        assert!(matches!(
+            prune(&report, false),
+            Err(BackupError::Reappeared(1))
+        ));
+        assert!(cache_backup.exists(), "nothing is deleted without --force");
+
+        let summary = prune(&report, true).unwrap();
+        assert_eq!(summary.removed, 2);
+        assert_eq!(summary.bytes, 8);
+        assert!(
+            !backups.join("a").exists(),
+            "emptied directories are removed"
+        );
+        assert!(backups.exists());
+        assert!(kept.exists(), "originals are never touched");
+    }
+}
@@ -1,12 +1,28 @@
 //! Execution engine for safe deletion operations.
 
-use crate::models::{CleanupAction, CleanupEntry, CleanupPlan};
+use crate::executor::idle::IdleGate;
+use crate::executor::operation_error::OperationError;
+use crate::executor::run_as::UserToken;
+#[cfg(feature = "parallel")]
+use crate::executor::volumes::VolumeCache;
+use crate::executor::volumes::VolumeConcurrency;
+use crate::models::paths::find_on_disk;
+use crate::models::{
+    in_snapshot_dir, ArtifactKind, CleanupAction, CleanupEntry, CleanupPlan, DataStream,
+    OwnArtifacts, PathKey,
+};
 use crate::scanner::progress::AdvancedProgress;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
-use std::time::{Instant, SystemTime};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant, SystemTime};
 
 /// Configuration for execution behavior.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +35,15 @@ pub struct ExecutionConfig {
     pub parallel: bool,
     /// Batch size for parallel processing (default: 100)
     pub batch_size: usize,
+    /// Stop waiting for an operation that hasn't finished after this long
+    /// and move on to the next one (None = no limit). A deletion stops at its
+    /// next entry; a call blocked on a dying disk or share, or a move, may
+    /// still finish in the background.
+    #[serde(default)]
+    pub operation_timeout: Option<Duration>,
+    /// Operations running at once on each volume in parallel mode
+    #[serde(default)]
+    pub volume_concurrency: VolumeConcurrency,
 }
 
 impl Default for ExecutionConfig {
@@ -30,6 +55,8 @@ impl Default for ExecutionConfig {
             use_recycle_bin: false,
             parallel: false,
             batch_size: 100,
+            operation_timeout: None,
+            volume_concurrency: VolumeConcurrency::default(),
         }
     }
 }
@@ -50,6 +77,11 @@ pub enum ExecutionMode {
 pub struct ExecutionEngine {
     config: ExecutionConfig,
     progress: Arc<AdvancedProgress>,
+    pause: Arc<AtomicBool>,
+    largest_first: bool,
+    protected: OwnArtifacts,
+    idle: Option<IdleGate>,
+    run_as: Option<Arc<UserToken>>,
 }
 
 /// Result of execution operation.
@@ -57,6 +89,21 @@ pub struct ExecutionEngine {
 pub struct ExecutionResult {
     pub operations: Vec<OperationResult>,
     pub summary: ExecutionSummary,
+    /// Execution stopped early because a pause was requested
+    pub paused: bool,
+}
+
+impl ExecutionResult {
+    /// Groups failed operations by the label of their error.
+    pub fn failures_by_cause(&self) -> BTreeMap<&'static str, Vec<&OperationResult>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for op in &self.operations {
+            if let (OperationStatus::Failed, Some(error)) = (&op.status, &op.error) {
+                groups.entry(error.label()).or_default().push(op);
+            }
+        }
+        groups
+    }
 }
 
 /// Summary of execution.
@@ -67,18 +114,54 @@ pub struct ExecutionSummary {
     pub failed: usize,
     pub skipped: usize,
     pub space_freed: u64,
+    /// Where the bytes of the processed entries went
+    pub space_by_action: SpaceByAction,
     pub duration: std::time::Duration,
 }
 
+/// Bytes of the processed entries, by what happened to them.
+///
+/// Deleted, recycled and backed-up bytes add up to `space_freed`; recycled
+/// and backed-up bytes still take up space until the bin or backup is
+/// emptied. Dry runs count what would have been deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpaceByAction {
+    /// Bytes deleted outright
+    pub deleted: u64,
+    /// Bytes moved to the recycle bin
+    pub recycled: u64,
+    /// Bytes moved to the backup directory
+    pub backed_up: u64,
+    /// Bytes of entries skipped (declined, never_delete or protected)
+    pub skipped: u64,
+}
+
+impl SpaceByAction {
+    /// Adds `other` to these totals.
+    pub fn add(&mut self, other: &Self) {
+        self.deleted = self.deleted.saturating_add(other.deleted);
+        self.recycled = self.recycled.saturating_add(other.recycled);
+        self.backed_up = self.backed_up.saturating_add(other.backed_up);
+        self.skipped = self.skipped.saturating_add(other.skipped);
+    }
+}
+
 /// Result of a single operation.
 #[derive(Debug, Clone)]
 pub struct OperationResult {
     pub path: PathBuf,
+    /// Stable ID of the plan entry (see [`CleanupEntry::id`])
+    pub entry_id: Option<String>,
     pub action: OperationAction,
     pub status: OperationStatus,
     pub size_freed: Option<u64>,
-    pub error: Option<String>,
+    /// Why the operation failed, or why the entry was skipped
+    pub error: Option<OperationError>,
     pub timestamp: SystemTime,
+    /// Alternate data streams of the entry, removed along with it
+    pub streams: Vec<DataStream>,
+    /// Where the entry was moved to, for a successful backup move
+    pub backup_path: Option<PathBuf>,
 }
 
 /// Action performed on an entry.
@@ -105,16 +188,118 @@ impl ExecutionEngine {
         Self {
             config,
             progress: Arc::new(AdvancedProgress::new()),
+            pause: Arc::new(AtomicBool::new(false)),
+            largest_first: false,
+            protected: OwnArtifacts::default(),
+            idle: None,
+            run_as: None,
         }
     }
 
+    /// Never touch megamaid's own files, such as this run's transaction log.
+    ///
+    /// Entries that are, contain or lie inside one of `artifacts` are skipped
+    /// whatever the plan says.
+    pub fn with_protected(mut self, artifacts: OwnArtifacts) -> Self {
+        self.protected = artifacts;
+        self
+    }
+
+    /// Process the largest entries first so an interrupted run frees the most space.
+    pub fn with_largest_first(mut self, largest_first: bool) -> Self {
+        self.largest_first = largest_first;
+        self
+    }
+
+    /// Only process entries while the system is idle (see
+    /// [`crate::executor::idle`]).
+    ///
+    /// The gate is checked before each entry, or each batch in parallel
+    /// mode. A pause requested while waiting for idle stops the execution
+    /// like any other pause.
+    pub fn with_idle_gate(mut self, gate: IdleGate) -> Self {
+        self.idle = Some(gate);
+        self
+    }
+
+    /// Perform operations as another user (see [`crate::executor::run_as`]).
+    ///
+    /// Each delete, move or recycle impersonates the account on the thread
+    /// running it; an operation whose impersonation fails fails with it.
+    pub fn with_run_as(mut self, token: Arc<UserToken>) -> Self {
+        self.run_as = Some(token);
+        self
+    }
+
     /// Get a reference to the progress tracker.
     pub fn progress(&self) -> &AdvancedProgress {
         &self.progress
     }
 
+    /// Get a handle that pauses execution when set to `true`.
+    ///
+    /// The engine finishes the entry (or parallel batch) in flight and returns
+    /// with [`ExecutionResult::paused`] set. Entries not yet processed can be
+    /// run later by resuming from the transaction log.
+    pub fn pause_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.pause)
+    }
+
+    fn pause_requested(&self) -> bool {
+        self.pause.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if processing must stop before the next entry, waiting
+    /// for the system to go idle first if gated.
+    fn should_pause(&self) -> bool {
+        if self.pause_requested() {
+            return true;
+        }
+        self.idle
+            .as_ref()
+            .is_some_and(|gate| !gate.wait(&self.pause))
+    }
+
+    /// Delete entries in the order they will be processed.
+    ///
+    /// Entries naming the same path as an earlier one under the platform's
+    /// case rules are dropped; the second would only fail as missing.
+    fn queue<'a>(&self, plan: &'a CleanupPlan) -> Vec<&'a CleanupEntry> {
+        let mut seen = HashSet::new();
+        let mut entries: Vec<_> = plan
+            .entries
+            .iter()
+            .filter(|e| e.action == CleanupAction::Delete)
+            .filter(|e| seen.insert(PathKey::new(&plan.entry_path(e))))
+            .collect();
+
+        if self.largest_first {
+            // Stable sort keeps plan order among equal sizes
+            entries.sort_by_key(|e| std::cmp::Reverse(e.size));
+        }
+
+        entries
+    }
+
     /// Execute a cleanup plan.
     pub fn execute(&self, plan: &CleanupPlan) -> Result<ExecutionResult, ExecutionError> {
+        self.execute_with(plan, |_| Ok(()))
+    }
+
+    /// Execute a cleanup plan, passing each operation to `on_operation` as
+    /// soon as it's done, e.g. to append it to the transaction log.
+    ///
+    /// `on_operation` runs on the calling thread, in plan order: after each
+    /// entry, or after each batch in parallel mode. An error from it stops
+    /// the execution before the next entry.
+    pub fn execute_with<F>(
+        &self,
+        plan: &CleanupPlan,
+        on_operation: F,
+    ) -> Result<ExecutionResult, ExecutionError>
+    where
+        F: FnMut(&OperationResult) -> std::io::Result<()>,
+    {
         // Validate: parallel mode not compatible with interactive
         if self.config.parallel && self.config.mode == ExecutionMode::Interactive {
             return Err(ExecutionError::InvalidConfiguration(
@@ -122,47 +307,73 @@ impl ExecutionEngine {
             ));
         }
 
+        if self.config.operation_timeout == Some(Duration::ZERO) {
+            return Err(ExecutionError::InvalidConfiguration(
+                "Operation timeout must be greater than zero".to_string(),
+            ));
+        }
+
+        if self.config.parallel && !cfg!(feature = "parallel") {
+            return Err(ExecutionError::InvalidConfiguration(
+                "Parallel execution is not enabled in this build (feature `parallel`)".to_string(),
+            ));
+        }
+
         // Dispatch to parallel or sequential execution
+        #[cfg(feature = "parallel")]
         if self.config.parallel {
-            self.execute_parallel(plan)
-        } else {
-            self.execute_sequential(plan)
+            return self.execute_parallel(plan, on_operation);
         }
+        self.execute_sequential(plan, on_operation)
     }
 
     /// Execute plan sequentially (original implementation).
-    fn execute_sequential(&self, plan: &CleanupPlan) -> Result<ExecutionResult, ExecutionError> {
+    fn execute_sequential<F>(
+        &self,
+        plan: &CleanupPlan,
+        mut on_operation: F,
+    ) -> Result<ExecutionResult, ExecutionError>
+    where
+        F: FnMut(&OperationResult) -> std::io::Result<()>,
+    {
         let start_time = Instant::now();
         let mut operations = Vec::new();
+        let mut paused = false;
 
         // Filter entries to process
-        let entries_to_process: Vec<_> = plan
-            .entries
-            .iter()
-            .filter(|e| e.action == CleanupAction::Delete)
-            .collect();
+        let entries_to_process = self.queue(plan);
 
         self.progress.set_total(entries_to_process.len() as u64);
 
         for entry in entries_to_process {
-            let full_path = plan.base_path.join(&entry.path);
+            if self.should_pause() {
+                paused = true;
+                break;
+            }
 
-            // Interactive mode: prompt user
-            if self.config.mode == ExecutionMode::Interactive {
+            let full_path = plan.entry_path(entry);
+
+            // Interactive mode: prompt user (never_delete entries are skipped anyway)
+            if self.config.mode == ExecutionMode::Interactive && !entry.never_delete {
                 match self.prompt_user(entry)? {
                     UserChoice::Yes => {
                         // Continue to execute
                     }
                     UserChoice::No => {
-                        operations.push(OperationResult {
+                        let declined = OperationResult {
                             path: full_path,
+                            entry_id: Some(entry.id()),
                             action: OperationAction::Skip,
                             status: OperationStatus::Skipped,
                             size_freed: None,
                             error: None,
                             timestamp: SystemTime::now(),
-                        });
+                            streams: entry.streams.clone(),
+                            backup_path: None,
+                        };
                         self.progress.increment();
+                        on_operation(&declined)?;
+                        operations.push(declined);
                         continue;
                     }
                     UserChoice::Abort => {
@@ -172,8 +383,9 @@ impl ExecutionEngine {
             }
 
             // Execute operation
-            let result = self.execute_single(&full_path, entry);
+            let result = self.execute_guarded(&full_path, entry);
             self.progress.increment();
+            on_operation(&result)?;
 
             // Fail-fast check
             if self.config.fail_fast && result.status == OperationStatus::Failed {
@@ -185,158 +397,282 @@ impl ExecutionEngine {
         }
 
         let duration = start_time.elapsed();
-        let summary = self.compute_summary(&operations, duration);
+        let summary = self.compute_summary(plan, &operations, duration);
 
         Ok(ExecutionResult {
             operations,
             summary,
+            paused,
         })
     }
 
     /// Execute plan in parallel using rayon.
-    fn execute_parallel(&self, plan: &CleanupPlan) -> Result<ExecutionResult, ExecutionError> {
+    ///
+    /// Each batch is split by volume, and each volume runs on its own pool
+    /// sized by [`ExecutionConfig::volume_concurrency`], so a spinning disk
+    /// isn't slowed down by parallel seeks while SSDs run at full speed.
+    #[cfg(feature = "parallel")]
+    fn execute_parallel<F>(
+        &self,
+        plan: &CleanupPlan,
+        mut on_operation: F,
+    ) -> Result<ExecutionResult, ExecutionError>
+    where
+        F: FnMut(&OperationResult) -> std::io::Result<()>,
+    {
         let start_time = Instant::now();
 
         // Filter entries to process
-        let entries_to_process: Vec<_> = plan
-            .entries
-            .iter()
-            .filter(|e| e.action == CleanupAction::Delete)
-            .collect();
+        let entries_to_process = self.queue(plan);
 
         self.progress.set_total(entries_to_process.len() as u64);
 
-        // Shared state for collecting results
-        let results = Arc::new(Mutex::new(Vec::new()));
-        let should_abort = Arc::new(Mutex::new(false));
+        let mut operations = Vec::with_capacity(entries_to_process.len());
+        let mut paused = false;
+        let mut volumes = VolumeCache::default();
+        let mut pools: BTreeMap<PathBuf, rayon::ThreadPool> = BTreeMap::new();
 
         // Process in batches for better error handling
-        let batches: Vec<_> = entries_to_process.chunks(self.config.batch_size).collect();
-
-        for batch in batches {
-            // Check abort signal
-            if *should_abort.lock().unwrap() {
+        for batch in entries_to_process.chunks(self.config.batch_size) {
+            // Pause between batches
+            if self.should_pause() {
+                paused = true;
                 break;
             }
 
-            // Process batch in parallel
-            let batch_results: Vec<OperationResult> = batch
+            // Group the batch by volume, keeping each entry's position
+            let mut groups: BTreeMap<PathBuf, Vec<(usize, PathBuf, &CleanupEntry)>> =
+                BTreeMap::new();
+            for (index, entry) in batch.iter().enumerate() {
+                let full_path = plan.entry_path(entry);
+                let volume = volumes.volume(&full_path);
+                if !pools.contains_key(&volume.root) {
+                    let pool = rayon::ThreadPoolBuilder::new()
+                        .num_threads(self.config.volume_concurrency.limit(volume))
+                        .build()
+                        .map_err(|e| ExecutionError::Io(std::io::Error::other(e)))?;
+                    pools.insert(volume.root.clone(), pool);
+                }
+                groups
+                    .entry(volume.root.clone())
+                    .or_default()
+                    .push((index, full_path, *entry));
+            }
+
+            // Process volumes side by side, each within its limit; results are
+            // collected on this thread, so a panicking worker can't poison any
+            // shared state
+            let mut indexed: Vec<(usize, OperationResult)> = groups
                 .par_iter()
-                .map(|entry| {
-                    let full_path = plan.base_path.join(&entry.path);
-                    let result = self.execute_single(&full_path, entry);
-                    self.progress.increment();
-                    result
+                .flat_map_iter(|(root, group)| {
+                    pools[root].install(|| {
+                        group
+                            .par_iter()
+                            .map(|(index, full_path, entry)| {
+                                let result = self.execute_guarded(full_path, entry);
+                                self.progress.increment();
+                                (*index, result)
+                            })
+                            .collect::<Vec<_>>()
+                    })
                 })
                 .collect();
+            indexed.sort_by_key(|(index, _)| *index);
+            let batch_results: Vec<OperationResult> =
+                indexed.into_iter().map(|(_, result)| result).collect();
+            for result in &batch_results {
+                on_operation(result)?;
+            }
 
-            // Collect results
-            {
-                let mut results_guard = results.lock().unwrap();
-                results_guard.extend(batch_results);
-
-                // Check for fail-fast condition
-                if self.config.fail_fast
-                    && results_guard
-                        .iter()
-                        .any(|r| r.status == OperationStatus::Failed)
-                {
-                    *should_abort.lock().unwrap() = true;
-                }
+            let failed = batch_results
+                .iter()
+                .any(|r| r.status == OperationStatus::Failed);
+            operations.extend(batch_results);
+
+            // Check for fail-fast condition
+            if self.config.fail_fast && failed {
+                break;
             }
         }
 
         let duration = start_time.elapsed();
-        let operations = Arc::try_unwrap(results)
-            .map(|mutex| mutex.into_inner().unwrap())
-            .unwrap_or_else(|arc| arc.lock().unwrap().clone());
-        let summary = self.compute_summary(&operations, duration);
+        let summary = self.compute_summary(plan, &operations, duration);
 
         Ok(ExecutionResult {
             operations,
             summary,
+            paused,
+        })
+    }
+
+    /// Executes one entry, recording a panic as a failed operation.
+    fn execute_guarded(&self, path: &Path, entry: &CleanupEntry) -> OperationResult {
+        self.catch_panic(path, entry, || self.execute_single(path, entry))
+    }
+
+    /// Runs `op`, turning a panic into a `Failed` result for `path` so one bad
+    /// entry can't take down the whole run.
+    fn catch_panic(
+        &self,
+        path: &Path,
+        entry: &CleanupEntry,
+        op: impl FnOnce() -> OperationResult,
+    ) -> OperationResult {
+        panic::catch_unwind(AssertUnwindSafe(op)).unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            OperationResult {
+                path: path.to_path_buf(),
+                entry_id: Some(entry.id()),
+                action: self.action(),
+                status: OperationStatus::Failed,
+                size_freed: None,
+                error: Some(OperationError::Other(format!(
+                    "Operation panicked: {}",
+                    message
+                ))),
+                timestamp: SystemTime::now(),
+                streams: entry.streams.clone(),
+                backup_path: None,
+            }
         })
     }
 
+    /// Returns the action applied to each entry under this configuration.
+    fn action(&self) -> OperationAction {
+        if self.config.use_recycle_bin {
+            OperationAction::MoveToRecycleBin
+        } else if self.config.backup_dir.is_some() {
+            OperationAction::MoveToBackup
+        } else {
+            OperationAction::Delete
+        }
+    }
+
     fn execute_single(&self, path: &Path, entry: &CleanupEntry) -> OperationResult {
         let timestamp = SystemTime::now();
 
+        // Precious entries stay put even if the plan was edited to delete them
+        if entry.never_delete {
+            return OperationResult {
+                path: path.to_path_buf(),
+                entry_id: Some(entry.id()),
+                action: OperationAction::Skip,
+                status: OperationStatus::Skipped,
+                size_freed: None,
+                error: Some(OperationError::Other(
+                    "Entry is marked never_delete".to_string(),
+                )),
+                timestamp,
+                streams: entry.streams.clone(),
+                backup_path: None,
+            };
+        }
+
+        // Removing the running transaction log (or the plan, or the backup
+        // directory) would lose the record of this very run, and volume
+        // snapshots are read-only copies of the tree
+        let own = match self.protected.affected_by(path) {
+            Some(artifact) => Some((artifact.kind, artifact.path.clone())),
+            None => in_snapshot_dir(path).then(|| (ArtifactKind::Snapshot, path.to_path_buf())),
+        };
+        if let Some((kind, artifact)) = own {
+            return OperationResult {
+                path: path.to_path_buf(),
+                entry_id: Some(entry.id()),
+                action: OperationAction::Skip,
+                status: OperationStatus::Skipped,
+                size_freed: None,
+                error: Some(OperationError::Other(format!(
+                    "Entry holds megamaid's {}: {}",
+                    kind,
+                    artifact.display()
+                ))),
+                timestamp,
+                streams: entry.streams.clone(),
+                backup_path: None,
+            };
+        }
+
+        // Act on the on-disk name if it differs only in Unicode normalization
+        let resolved = find_on_disk(path);
+        let path = resolved.as_deref().unwrap_or(path);
+        // Deleting or moving a file takes its alternate data streams with it
+        let size_freed = entry.size.saturating_add(entry.stream_size());
+
         // Dry-run mode
         if self.config.mode == ExecutionMode::DryRun {
             return OperationResult {
                 path: path.to_path_buf(),
+                entry_id: Some(entry.id()),
                 action: OperationAction::Delete,
                 status: OperationStatus::DryRun,
-                size_freed: Some(entry.size),
+                size_freed: Some(size_freed),
                 error: None,
                 timestamp,
+                streams: entry.streams.clone(),
+                backup_path: None,
             };
         }
 
         // Determine action type
-        let action = if self.config.use_recycle_bin {
-            OperationAction::MoveToRecycleBin
-        } else if self.config.backup_dir.is_some() {
-            OperationAction::MoveToBackup
-        } else {
-            OperationAction::Delete
-        };
+        let action = self.action();
 
         // Execute the operation
-        let result = match action {
-            OperationAction::Delete => self.delete_path(path),
-            OperationAction::MoveToBackup => self.move_to_backup(path, entry),
-            OperationAction::MoveToRecycleBin => self.move_to_recycle_bin(path),
-            OperationAction::Skip => Ok(()),
+        let backup_dest = self
+            .config
+            .backup_dir
+            .as_ref()
+            .map(|dir| dir.join(entry.backup_path()));
+        let deadline = self
+            .config
+            .operation_timeout
+            .map(|timeout| Instant::now() + timeout);
+        let operation = {
+            let (action, path, dest) = (action.clone(), path.to_path_buf(), backup_dest.clone());
+            let run_as = self.run_as.clone();
+            move || {
+                run_as
+                    .as_deref()
+                    .map(UserToken::impersonate)
+                    .transpose()
+                    .and_then(|_impersonation| perform(&action, &path, dest.as_deref(), deadline))
+            }
+        };
+        let result = match deadline {
+            Some(deadline) => run_until(operation, deadline),
+            None => operation(),
         };
 
         match result {
             Ok(()) => OperationResult {
                 path: path.to_path_buf(),
+                entry_id: Some(entry.id()),
+                backup_path: backup_dest.filter(|_| action == OperationAction::MoveToBackup),
                 action,
                 status: OperationStatus::Success,
-                size_freed: Some(entry.size),
+                size_freed: Some(size_freed),
                 error: None,
                 timestamp,
+                streams: entry.streams.clone(),
             },
             Err(e) => OperationResult {
                 path: path.to_path_buf(),
+                entry_id: Some(entry.id()),
+                error: Some(OperationError::from_io(&e, &action)),
                 action,
                 status: OperationStatus::Failed,
                 size_freed: None,
-                error: Some(e.to_string()),
                 timestamp,
+                streams: entry.streams.clone(),
+                backup_path: None,
             },
         }
     }
 
-    fn delete_path(&self, path: &Path) -> Result<(), std::io::Error> {
-        if path.is_dir() {
-            std::fs::remove_dir_all(path)
-        } else {
-            std::fs::remove_file(path)
-        }
-    }
-
-    fn move_to_backup(&self, path: &Path, entry: &CleanupEntry) -> Result<(), std::io::Error> {
-        let backup_dir = self.config.backup_dir.as_ref().unwrap();
-        let dest = backup_dir.join(&entry.path);
-
-        // Create parent directories
-        if let Some(parent) = dest.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        // Move the file/directory
-        std::fs::rename(path, &dest)?;
-        Ok(())
-    }
-
-    fn move_to_recycle_bin(&self, path: &Path) -> Result<(), std::io::Error> {
-        // Use trash crate for cross-platform recycle bin support
-        trash::delete(path).map_err(std::io::Error::other)
-    }
-
     fn prompt_user(&self, entry: &CleanupEntry) -> Result<UserChoice, ExecutionError> {
         use std::io::{self, Write};
 
@@ -364,6 +700,7 @@ impl ExecutionEngine {
 
     fn compute_summary(
         &self,
+        plan: &CleanupPlan,
         operations: &[OperationResult],
         duration: std::time::Duration,
     ) -> ExecutionSummary {
@@ -381,6 +718,7 @@ impl ExecutionEngine {
             .filter(|o| o.status == OperationStatus::Skipped)
             .count();
         let space_freed = operations.iter().filter_map(|o| o.size_freed).sum();
+        let space_by_action = space_by_action(plan, operations);
 
         ExecutionSummary {
             total_operations,
@@ -388,11 +726,47 @@ impl ExecutionEngine {
             failed,
             skipped,
             space_freed,
+            space_by_action,
             duration,
         }
     }
 }
 
+/// Splits the bytes of `operations` by action.
+///
+/// Skipped operations free nothing, so their bytes are the sizes of their
+/// plan entries.
+fn space_by_action(plan: &CleanupPlan, operations: &[OperationResult]) -> SpaceByAction {
+    let mut space = SpaceByAction::default();
+    let mut skipped_ids = HashSet::new();
+    for op in operations {
+        if op.status == OperationStatus::Skipped {
+            skipped_ids.extend(op.entry_id.as_deref());
+            continue;
+        }
+        let Some(size) = op.size_freed else {
+            continue;
+        };
+        let total = match op.action {
+            OperationAction::Delete => &mut space.deleted,
+            OperationAction::MoveToRecycleBin => &mut space.recycled,
+            OperationAction::MoveToBackup => &mut space.backed_up,
+            OperationAction::Skip => continue,
+        };
+        *total = total.saturating_add(size);
+    }
+
+    if !skipped_ids.is_empty() {
+        space.skipped = plan
+            .entries
+            .iter()
+            .filter(|e| skipped_ids.contains(e.id().as_str()))
+            .map(|e| e.size.saturating_add(e.stream_size()))
+            .fold(0, u64::saturating_add);
+    }
+    space
+}
+
 enum UserChoice {
     Yes,
     No,
@@ -412,9 +786,179 @@ pub enum ExecutionError {
     InvalidConfiguration(String),
 }
 
+/// Work handed to an operation worker.
+type Job = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    /// Queue of the worker running this thread's operations with a timeout
+    static WORKER: RefCell<Option<mpsc::Sender<Job>>> = const { RefCell::new(None) };
+}
+
+/// Runs `operation` on this thread's operation worker and waits for it
+/// until `deadline`, failing with `TimedOut` after that.
+///
+/// A call blocked on a dying disk or unreachable share can't be interrupted,
+/// so a timed-out worker is abandoned: its operation may still finish in the
+/// background, and the next operation starts a new worker. A panic in
+/// `operation` is raised again on this thread.
+fn run_until<F>(operation: F, deadline: Instant) -> Result<(), std::io::Error>
+where
+    F: FnOnce() -> Result<(), std::io::Error> + Send + 'static,
+{
+    let (done, outcome) = mpsc::channel();
+    let job: Job = Box::new(move || {
+        let _ = done.send(panic::catch_unwind(AssertUnwindSafe(operation)));
+    });
+
+    WORKER.with(|worker| {
+        let mut worker = worker.borrow_mut();
+        let jobs = match worker.take() {
+            Some(jobs) => jobs,
+            None => spawn_worker()?,
+        };
+        jobs.send(job)
+            .map_err(|_| std::io::Error::other("operation worker stopped"))?;
+
+        match outcome.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Ok(result)) => {
+                *worker = Some(jobs);
+                result
+            }
+            Ok(Err(payload)) => {
+                *worker = Some(jobs);
+                panic::resume_unwind(payload)
+            }
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "operation timed out; it may still finish in the background",
+            )),
+        }
+    })
+}
+
+/// Starts a thread running the jobs sent to it, in order, until the sender
+/// is dropped.
+fn spawn_worker() -> Result<mpsc::Sender<Job>, std::io::Error> {
+    let (jobs, queue) = mpsc::channel::<Job>();
+    std::thread::Builder::new()
+        .name("megamaid-operation".to_string())
+        .spawn(move || queue.into_iter().for_each(|job| job()))?;
+    Ok(jobs)
+}
+
+/// Applies `action` to `path`; `backup_dest` is where a backup move goes.
+///
+/// A deletion still running at `deadline` stops there, see
+/// [`remove_dir_until`].
+fn perform(
+    action: &OperationAction,
+    path: &Path,
+    backup_dest: Option<&Path>,
+    deadline: Option<Instant>,
+) -> Result<(), std::io::Error> {
+    match (action, backup_dest) {
+        (OperationAction::Delete, _) => delete_path(path, deadline),
+        (OperationAction::MoveToBackup, Some(dest)) => move_to_backup(path, dest),
+        (OperationAction::MoveToBackup, None) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "no backup directory configured",
+        )),
+        (OperationAction::MoveToRecycleBin, _) => move_to_recycle_bin(path),
+        (OperationAction::Skip, _) => Ok(()),
+    }
+}
+
+/// Deletes `path`; a symlink or junction is removed itself, never what it
+/// points to.
+fn delete_path(path: &Path, deadline: Option<Instant>) -> Result<(), std::io::Error> {
+    let file_type = std::fs::symlink_metadata(path)?.file_type();
+    if file_type.is_symlink() {
+        remove_link(path, file_type)
+    } else if file_type.is_dir() {
+        match deadline {
+            Some(deadline) => remove_dir_until(path, deadline),
+            None => std::fs::remove_dir_all(path),
+        }
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Removes the directory `dir` and its contents, failing with `TimedOut`
+/// once `deadline` passes.
+///
+/// The deadline is checked before each entry, so a walk abandoned by
+/// [`run_until`] stops deleting at its next entry: a timed-out directory is
+/// left partly removed. A single call blocked on a dying disk or share can
+/// still run past the deadline.
+fn remove_dir_until(dir: &Path, deadline: Instant) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        if Instant::now() >= deadline {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("operation timed out, {} was partly removed", dir.display()),
+            ));
+        }
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            remove_link(&entry.path(), file_type)?;
+        } else if file_type.is_dir() {
+            remove_dir_until(&entry.path(), deadline)?;
+        } else {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    std::fs::remove_dir(dir)
+}
+
+#[cfg(windows)]
+fn remove_link(path: &Path, file_type: std::fs::FileType) -> Result<(), std::io::Error> {
+    use std::os::windows::fs::FileTypeExt;
+
+    // Directory symlinks and junctions are directories to the filesystem,
+    // whatever they point to
+    if file_type.is_symlink_dir() {
+        std::fs::remove_dir(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+#[cfg(not(windows))]
+fn remove_link(path: &Path, _file_type: std::fs::FileType) -> Result<(), std::io::Error> {
+    std::fs::remove_file(path)
+}
+
+fn move_to_backup(path: &Path, dest: &Path) -> Result<(), std::io::Error> {
+    // Create parent directories
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Move the file/directory
+    std::fs::rename(path, dest)?;
+    Ok(())
+}
+
+#[cfg(feature = "trash")]
+fn move_to_recycle_bin(path: &Path) -> Result<(), std::io::Error> {
+    // Use trash crate for cross-platform recycle bin support
+    trash::delete(path).map_err(std::io::Error::other)
+}
+
+#[cfg(not(feature = "trash"))]
+fn move_to_recycle_bin(_path: &Path) -> Result<(), std::io::Error> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "recycle bin support is not enabled in this build (feature `trash`)",
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::PathCase;
     use chrono::Utc;
     use std::fs;
     use tempfile::TempDir;
@@ -424,18 +968,26 @@ mod tests {
             version: "0.1.0".to_string(),
             created_at: Utc::now(),
             base_path: base_path.to_path_buf(),
+            roots: Vec::new(),
             entries,
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         }
     }
 
     fn create_cleanup_entry(path: &str, size: u64, action: CleanupAction) -> CleanupEntry {
         CleanupEntry {
             path: path.to_string(),
+            root: 0,
             size,
             modified: Utc::now().to_rfc3339(),
             action,
             rule_name: "test".to_string(),
             reason: "test reason".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         }
     }
 
@@ -485,6 +1037,112 @@ mod tests {
         assert!(!file_path.exists(), "File should be deleted");
     }
 
+    #[test]
+    fn test_delete_counts_alternate_streams_as_freed() {
+        let temp = TempDir::new().unwrap();
+        let file_path = temp.path().join("download.exe");
+        fs::write(&file_path, "content").unwrap();
+
+        let mut entry = create_cleanup_entry("download.exe", 7, CleanupAction::Delete);
+        entry.streams = vec![DataStream {
+            name: "Zone.Identifier".to_string(),
+            size: 26,
+        }];
+        let plan = create_test_plan(temp.path(), vec![entry]);
+
+        let config = ExecutionConfig {
+            mode: ExecutionMode::Batch,
+            ..Default::default()
+        };
+
+        let executor = ExecutionEngine::new(config);
+        let result = executor.execute(&plan).unwrap();
+
+        assert_eq!(result.operations[0].size_freed, Some(33));
+        assert_eq!(result.operations[0].streams[0].name, "Zone.Identifier");
+        assert_eq!(result.summary.space_freed, 33);
+        assert_eq!(result.summary.space_by_action.deleted, 33);
+        assert!(!file_path.exists(), "File should be deleted");
+    }
+
+    #[test]
+    fn test_never_delete_entries_are_skipped() {
+        let temp = TempDir::new().unwrap();
+        let file_path = temp.path().join("archive.pst");
+        fs::write(&file_path, "content").unwrap();
+
+        let mut entry = create_cleanup_entry("archive.pst", 7, CleanupAction::Delete);
+        entry.never_delete = true;
+        let plan = create_test_plan(temp.path(), vec![entry]);
+
+        let config = ExecutionConfig {
+            mode: ExecutionMode::Batch,
+            ..Default::default()
+        };
+
+        let result = ExecutionEngine::new(config).execute(&plan).unwrap();
+
+        assert_eq!(result.operations[0].status, OperationStatus::Skipped);
+        assert_eq!(result.operations[0].action, OperationAction::Skip);
+        assert_eq!(result.summary.skipped, 1);
+        assert_eq!(result.summary.space_freed, 0);
+        assert_eq!(result.summary.space_by_action.skipped, 7);
+        assert!(file_path.exists(), "Precious file must not be deleted");
+    }
+
+    #[test]
+    fn test_own_artifacts_are_never_removed() {
+        let temp = TempDir::new().unwrap();
+        let logs = temp.path().join("logs");
+        fs::create_dir(&logs).unwrap();
+        fs::write(logs.join("execution-log.yaml"), "log").unwrap();
+        fs::write(temp.path().join("junk.bin"), "junk").unwrap();
+        let snapshot = temp.path().join(".megamaid-snapshots/megamaid-1/target");
+        fs::create_dir_all(&snapshot).unwrap();
+
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("logs", 3, CleanupAction::Delete),
+                create_cleanup_entry("junk.bin", 4, CleanupAction::Delete),
+                create_cleanup_entry(
+                    ".megamaid-snapshots/megamaid-1/target",
+                    5,
+                    CleanupAction::Delete,
+                ),
+            ],
+        );
+        let protected = OwnArtifacts::new().with(
+            crate::models::ArtifactKind::TransactionLog,
+            logs.join("execution-log.yaml"),
+        );
+
+        let result = ExecutionEngine::new(ExecutionConfig::default())
+            .with_protected(protected)
+            .execute(&plan)
+            .unwrap();
+
+        assert_eq!(result.operations[0].status, OperationStatus::Skipped);
+        assert!(result.operations[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .message()
+            .contains("transaction log"));
+        assert_eq!(result.operations[1].status, OperationStatus::Success);
+        assert!(logs.join("execution-log.yaml").exists());
+        assert!(!temp.path().join("junk.bin").exists());
+
+        assert_eq!(result.operations[2].status, OperationStatus::Skipped);
+        assert!(result.operations[2]
+            .error
+            .as_ref()
+            .unwrap()
+            .message()
+            .contains("volume snapshot"));
+        assert!(snapshot.exists());
+    }
+
     #[test]
     fn test_batch_delete_directory() {
         let temp = TempDir::new().unwrap();
@@ -507,6 +1165,27 @@ mod tests {
         assert!(!dir_path.exists(), "Directory should be deleted");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_symlink_keeps_target() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("shared");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("file.txt"), "content").unwrap();
+        let link = temp.path().join("node_modules");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let entry = create_cleanup_entry("node_modules", 0, CleanupAction::Delete);
+        let plan = create_test_plan(temp.path(), vec![entry]);
+        let result = ExecutionEngine::new(ExecutionConfig::default())
+            .execute(&plan)
+            .unwrap();
+
+        assert_eq!(result.summary.successful, 1);
+        assert!(link.symlink_metadata().is_err(), "link should be removed");
+        assert!(target.join("file.txt").exists(), "target should be kept");
+    }
+
     #[test]
     fn test_backup_mode() {
         let temp = TempDir::new().unwrap();
@@ -534,6 +1213,10 @@ mod tests {
             backup_dir.join("move_me.txt").exists(),
             "File should be in backup"
         );
+        assert_eq!(
+            result.operations[0].backup_path,
+            Some(backup_dir.join("move_me.txt"))
+        );
     }
 
     #[test]
@@ -700,6 +1383,7 @@ mod tests {
         assert_eq!(result.operations.len(), 2);
     }
 
+    #[cfg(feature = "parallel")]
     #[test]
     fn test_parallel_execution() {
         let temp = TempDir::new().unwrap();
@@ -768,6 +1452,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "parallel")]
     #[test]
     fn test_parallel_fail_fast() {
         let temp = TempDir::new().unwrap();
@@ -806,6 +1491,7 @@ mod tests {
         assert!(result.operations.len() < 10);
     }
 
+    #[cfg(feature = "parallel")]
     #[test]
     fn test_parallel_dry_run() {
         let temp = TempDir::new().unwrap();
@@ -854,6 +1540,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "parallel")]
     #[test]
     fn test_parallel_progress_tracking() {
         let temp = TempDir::new().unwrap();
@@ -894,4 +1581,416 @@ mod tests {
         assert_eq!(executor.progress().get_processed(), 50);
         assert_eq!(result.summary.successful, 50);
     }
+
+    #[test]
+    fn test_pause_stops_before_next_entry() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.txt"), "a").unwrap();
+        fs::write(temp.path().join("b.txt"), "b").unwrap();
+
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("a.txt", 1, CleanupAction::Delete),
+                create_cleanup_entry("b.txt", 1, CleanupAction::Delete),
+            ],
+        );
+
+        let engine = ExecutionEngine::new(ExecutionConfig::default());
+        engine.pause_handle().store(true, Ordering::SeqCst);
+        let result = engine.execute(&plan).unwrap();
+
+        assert!(result.paused);
+        assert!(result.operations.is_empty());
+        assert!(temp.path().join("a.txt").exists());
+        assert!(temp.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_idle_gate_pauses_on_user_input() {
+        use crate::executor::idle::{ActivityMonitor, IdlePolicy};
+
+        /// Idle once, then reports input and asks to pause, as Ctrl+C would
+        struct UserReturns {
+            calls: usize,
+            pause: Arc<AtomicBool>,
+        }
+        impl ActivityMonitor for UserReturns {
+            fn input_idle(&mut self) -> Option<Duration> {
+                self.calls += 1;
+                if self.calls == 1 {
+                    return Some(Duration::from_secs(3600));
+                }
+                self.pause.store(true, Ordering::SeqCst);
+                Some(Duration::ZERO)
+            }
+            fn disk_busy(&mut self) -> Option<f64> {
+                None
+            }
+        }
+
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.txt"), "a").unwrap();
+        fs::write(temp.path().join("b.txt"), "b").unwrap();
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("a.txt", 1, CleanupAction::Delete),
+                create_cleanup_entry("b.txt", 1, CleanupAction::Delete),
+            ],
+        );
+
+        let engine = ExecutionEngine::new(ExecutionConfig::default());
+        let policy = IdlePolicy {
+            poll_interval: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let monitor = UserReturns {
+            calls: 0,
+            pause: engine.pause_handle(),
+        };
+        let engine = engine.with_idle_gate(IdleGate::with_monitor(policy, Box::new(monitor)));
+        let result = engine.execute(&plan).unwrap();
+
+        assert!(result.paused);
+        assert_eq!(result.operations.len(), 1);
+        assert!(!temp.path().join("a.txt").exists());
+        assert!(temp.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_largest_first_ordering() {
+        let temp = TempDir::new().unwrap();
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("small.txt", 10, CleanupAction::Delete),
+                create_cleanup_entry("large.txt", 1000, CleanupAction::Delete),
+                create_cleanup_entry("medium.txt", 100, CleanupAction::Delete),
+            ],
+        );
+
+        let config = ExecutionConfig {
+            mode: ExecutionMode::DryRun,
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config)
+            .with_largest_first(true)
+            .execute(&plan)
+            .unwrap();
+
+        let order: Vec<_> = result
+            .operations
+            .iter()
+            .map(|o| o.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(order, vec!["large.txt", "medium.txt", "small.txt"]);
+        assert!(!result.paused);
+    }
+
+    #[test]
+    fn test_delete_matches_decomposed_name_on_disk() {
+        let temp = TempDir::new().unwrap();
+        let file_path = temp.path().join("cafe\u{301}.txt");
+        fs::write(&file_path, "content").unwrap();
+
+        let entry = create_cleanup_entry("caf\u{e9}.txt", 7, CleanupAction::Delete);
+        let plan = create_test_plan(temp.path(), vec![entry]);
+
+        let config = ExecutionConfig {
+            mode: ExecutionMode::Batch,
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config).execute(&plan).unwrap();
+
+        assert_eq!(result.summary.successful, 1);
+        assert!(!file_path.exists(), "Decomposed file should be deleted");
+    }
+
+    #[test]
+    fn test_panicking_operation_recorded_as_failed() {
+        let temp = TempDir::new().unwrap();
+        let entry = create_cleanup_entry("boom.txt", 10, CleanupAction::Delete);
+        let path = temp.path().join(&entry.path);
+        let executor = ExecutionEngine::new(ExecutionConfig::default());
+
+        let result = executor.catch_panic(&path, &entry, || panic!("injected failure"));
+
+        assert_eq!(result.status, OperationStatus::Failed);
+        assert_eq!(result.action, OperationAction::Delete);
+        assert_eq!(result.path, path);
+        assert!(result.size_freed.is_none());
+        let error = result.error.unwrap();
+        assert!(matches!(error, OperationError::Other(_)));
+        assert!(error.message().contains("injected failure"));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_worker_panic_does_not_cascade() {
+        let temp = TempDir::new().unwrap();
+        let entries: Vec<CleanupEntry> = (0..8)
+            .map(|i| {
+                fs::write(temp.path().join(format!("file{}.txt", i)), "content").unwrap();
+                create_cleanup_entry(&format!("file{}.txt", i), 7, CleanupAction::Delete)
+            })
+            .collect();
+        let executor = ExecutionEngine::new(ExecutionConfig::default());
+
+        // Inject a panic into one worker of a parallel batch
+        let results: Vec<OperationResult> = entries
+            .par_iter()
+            .map(|entry| {
+                let path = temp.path().join(&entry.path);
+                executor.catch_panic(&path, entry, || {
+                    if entry.path == "file3.txt" {
+                        panic!("injected failure");
+                    }
+                    executor.execute_single(&path, entry)
+                })
+            })
+            .collect();
+
+        let failed: Vec<_> = results
+            .iter()
+            .filter(|r| r.status == OperationStatus::Failed)
+            .collect();
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].path.ends_with("file3.txt"));
+        assert_eq!(
+            results
+                .iter()
+                .filter(|r| r.status == OperationStatus::Success)
+                .count(),
+            7
+        );
+        assert!(temp.path().join("file3.txt").exists());
+
+        // The engine stays usable for the rest of the plan
+        let plan = create_test_plan(
+            temp.path(),
+            vec![create_cleanup_entry("file3.txt", 7, CleanupAction::Delete)],
+        );
+        let config = ExecutionConfig {
+            parallel: true,
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config).execute(&plan).unwrap();
+        assert_eq!(result.summary.successful, 1);
+    }
+
+    #[test]
+    fn test_remove_dir_until_stops_at_the_deadline() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("dir");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested/a.txt"), "a").unwrap();
+        fs::write(dir.join("b.txt"), "b").unwrap();
+
+        // Nothing is removed once the deadline has passed
+        let err = remove_dir_until(&dir, Instant::now()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(dir.join("nested/a.txt").exists());
+        assert!(dir.join("b.txt").exists());
+
+        remove_dir_until(&dir, Instant::now() + Duration::from_secs(30)).unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_run_until_abandons_a_blocked_operation() {
+        let (release, blocked) = mpsc::channel::<()>();
+        let started = Instant::now();
+        let err = run_until(
+            move || {
+                let _ = blocked.recv();
+                Ok(())
+            },
+            Instant::now() + Duration::from_millis(50),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(err.to_string().contains("in the background"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // The next operation runs on a new worker while the old one is stuck
+        let result = run_until(|| Ok(()), Instant::now() + Duration::from_secs(30));
+        assert!(result.is_ok());
+        drop(release);
+
+        let result = panic::catch_unwind(|| {
+            run_until(
+                || panic!("injected failure"),
+                Instant::now() + Duration::from_secs(30),
+            )
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_panicking_operation_is_classified() {
+        let temp = TempDir::new().unwrap();
+        let entry = create_cleanup_entry("boom.txt", 10, CleanupAction::Delete);
+        let path = temp.path().join(&entry.path);
+        let executor = ExecutionEngine::new(ExecutionConfig::default());
+
+        let result = executor.catch_panic(&path, &entry, || panic!("injected failure"));
+
+        assert_eq!(result.status, OperationStatus::Failed);
+        assert!(result.error.unwrap().message().contains("panicked"));
+    }
+
+    #[test]
+    fn test_execution_with_operation_timeout() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("file.txt"), "content").unwrap();
+        fs::create_dir(temp.path().join("dir")).unwrap();
+        fs::write(temp.path().join("dir/nested.txt"), "content").unwrap();
+
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("file.txt", 7, CleanupAction::Delete),
+                create_cleanup_entry("dir", 7, CleanupAction::Delete),
+                create_cleanup_entry("missing.txt", 7, CleanupAction::Delete),
+            ],
+        );
+        let config = ExecutionConfig {
+            operation_timeout: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config).execute(&plan).unwrap();
+
+        assert_eq!(result.summary.successful, 2);
+        assert_eq!(result.summary.failed, 1);
+        assert!(!temp.path().join("dir").exists());
+        let failed = &result.operations[2];
+        assert!(matches!(failed.error, Some(OperationError::NotFound(_))));
+        let groups = result.failures_by_cause();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups["Already gone"][0].path, failed.path);
+    }
+
+    #[test]
+    fn test_execute_with_reports_each_operation() {
+        let temp = TempDir::new().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(temp.path().join(name), "content").unwrap();
+        }
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("a.txt", 7, CleanupAction::Delete),
+                create_cleanup_entry("b.txt", 7, CleanupAction::Delete),
+                create_cleanup_entry("c.txt", 7, CleanupAction::Delete),
+            ],
+        );
+
+        // A failing observer stops the run before the next entry
+        let mut seen = Vec::new();
+        let result = ExecutionEngine::new(ExecutionConfig::default()).execute_with(&plan, |op| {
+            seen.push(op.path.clone());
+            if seen.len() == 2 {
+                return Err(std::io::Error::other("disk full"));
+            }
+            Ok(())
+        });
+
+        assert!(matches!(result, Err(ExecutionError::Io(_))));
+        assert_eq!(
+            seen,
+            vec![temp.path().join("a.txt"), temp.path().join("b.txt")]
+        );
+        assert!(temp.path().join("c.txt").exists());
+    }
+
+    #[test]
+    fn test_timed_out_deletion_is_left_as_logged() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("dir")).unwrap();
+        for i in 0..10 {
+            fs::write(temp.path().join(format!("dir/{}.txt", i)), "content").unwrap();
+        }
+
+        let plan = create_test_plan(
+            temp.path(),
+            vec![create_cleanup_entry("dir", 70, CleanupAction::Delete)],
+        );
+        let config = ExecutionConfig {
+            operation_timeout: Some(Duration::from_nanos(1)),
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config).execute(&plan).unwrap();
+
+        assert_eq!(result.summary.failed, 1);
+        assert!(matches!(
+            result.operations[0].error,
+            Some(OperationError::Timeout(_))
+        ));
+        // The abandoned walk stops at its next entry
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(temp.path().join("dir").exists());
+    }
+
+    #[test]
+    fn test_zero_operation_timeout_rejected() {
+        let temp = TempDir::new().unwrap();
+        let plan = create_test_plan(temp.path(), vec![]);
+
+        let config = ExecutionConfig {
+            operation_timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config).execute(&plan);
+
+        assert!(matches!(
+            result,
+            Err(ExecutionError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    #[test]
+    fn test_parallel_rejected_without_feature() {
+        let temp = TempDir::new().unwrap();
+        let plan = create_test_plan(temp.path(), vec![]);
+
+        let config = ExecutionConfig {
+            parallel: true,
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config).execute(&plan);
+
+        assert!(matches!(
+            result,
+            Err(ExecutionError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_entries_are_processed_once() {
+        let temp = TempDir::new().unwrap();
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("dup.txt", 10, CleanupAction::Delete),
+                create_cleanup_entry("./dup.txt", 10, CleanupAction::Delete),
+                create_cleanup_entry("DUP.txt", 10, CleanupAction::Delete),
+            ],
+        );
+
+        let config = ExecutionConfig {
+            mode: ExecutionMode::DryRun,
+            ..Default::default()
+        };
+        let result = ExecutionEngine::new(config).execute(&plan).unwrap();
+
+        // `DUP.txt` is a different file where the filesystem is case-sensitive
+        let expected = match PathCase::native() {
+            PathCase::Insensitive => 1,
+            PathCase::Sensitive => 2,
+        };
+        assert_eq!(result.operations.len(), expected);
+        assert!(result.operations[0].path.ends_with("dup.txt"));
+    }
 }
@@ -0,0 +1,728 @@
+//! Running executions only while the machine is idle.
+//!
+//! A large approved plan can be drained over a workday without anyone
+//! noticing: an [`IdleGate`] holds the execution back until nobody has
+//! touched the keyboard or mouse for a while and the disks are quiet, and
+//! pauses it again as soon as input resumes. Input is checked before every
+//! entry (every batch in parallel mode); disk activity only before resuming,
+//! since the execution's own deletions keep the disk busy while it runs.
+//!
+//! Input idle time comes from `GetLastInputInfo` on Windows, which only sees
+//! the session megamaid runs in, and from terminal devices on Linux, which
+//! covers console and SSH sessions but not every graphical one. Disk activity
+//! is read from `/proc/diskstats` on Linux. Whatever can't be measured on a
+//! platform counts as idle.
+//!
+//! [`RunConstraints`] are checked once, before a batch run starts: only on
+//! AC power, only once the disks have been quiet for a while, and never
+//! during active hours. A run that doesn't meet them doesn't start, so the
+//! scheduler that launched it tries again later. The power source comes from
+//! `GetSystemPowerStatus` on Windows and `/sys/class/power_supply` on Linux;
+//! a machine whose power source can't be read counts as on AC power.
+
+use chrono::NaiveTime;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// When the system counts as idle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdlePolicy {
+    /// Time without keyboard or mouse input
+    pub min_idle: Duration,
+    /// Highest fraction of time (0.0-1.0) the busiest disk may be busy
+    pub max_disk_busy: f64,
+    /// How often activity is sampled while waiting
+    pub poll_interval: Duration,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self {
+            min_idle: Duration::from_secs(5 * 60),
+            max_disk_busy: 0.2,
+            poll_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Whether a gated execution is running or waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleState {
+    /// Waiting for the system to go idle
+    Waiting,
+    /// Processing entries
+    Running,
+}
+
+impl fmt::Display for IdleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Waiting => "waiting for idle",
+            Self::Running => "running",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Source of user and disk activity.
+pub trait ActivityMonitor: Send {
+    /// Returns the time since the last keyboard or mouse input, if known.
+    fn input_idle(&mut self) -> Option<Duration>;
+
+    /// Returns the fraction of time the busiest disk was busy since the
+    /// previous call, if known. The first call only takes a baseline.
+    fn disk_busy(&mut self) -> Option<f64>;
+
+    /// Returns whether the machine runs on AC power, if known.
+    fn on_ac_power(&mut self) -> Option<bool> {
+        None
+    }
+}
+
+/// Reads activity from the operating system.
+#[derive(Debug, Default)]
+pub struct SystemMonitor {
+    /// Busy milliseconds per disk at the previous sample
+    last_disk: Option<(Instant, Vec<(String, u64)>)>,
+}
+
+impl ActivityMonitor for SystemMonitor {
+    fn input_idle(&mut self) -> Option<Duration> {
+        platform::input_idle()
+    }
+
+    fn disk_busy(&mut self) -> Option<f64> {
+        let now = Instant::now();
+        let sample = platform::disk_busy_ms()?;
+        let previous = self.last_disk.replace((now, sample.clone()));
+        let (then, before) = previous?;
+
+        let elapsed = now.duration_since(then).as_millis().max(1) as f64;
+        let busiest = sample
+            .iter()
+            .filter_map(|(name, ms)| {
+                let (_, old) = before.iter().find(|(old_name, _)| old_name == name)?;
+                Some(ms.saturating_sub(*old))
+            })
+            .max()
+            .unwrap_or(0);
+        Some((busiest as f64 / elapsed).min(1.0))
+    }
+
+    fn on_ac_power(&mut self) -> Option<bool> {
+        platform::on_ac_power()
+    }
+}
+
+/// Holds an execution back while the system is in use.
+pub struct IdleGate {
+    policy: IdlePolicy,
+    monitor: Mutex<Box<dyn ActivityMonitor>>,
+    running: AtomicBool,
+    listener: Option<Box<dyn Fn(IdleState) + Send + Sync>>,
+}
+
+impl IdleGate {
+    /// Creates a gate reading activity from the operating system.
+    pub fn new(policy: IdlePolicy) -> Self {
+        Self::with_monitor(policy, Box::new(SystemMonitor::default()))
+    }
+
+    /// Creates a gate reading activity from `monitor`.
+    pub fn with_monitor(policy: IdlePolicy, monitor: Box<dyn ActivityMonitor>) -> Self {
+        Self {
+            policy,
+            monitor: Mutex::new(monitor),
+            running: AtomicBool::new(false),
+            listener: None,
+        }
+    }
+
+    /// Calls `listener` whenever the execution starts waiting or resumes.
+    pub fn with_listener(mut self, listener: impl Fn(IdleState) + Send + Sync + 'static) -> Self {
+        self.listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Returns true if keyboard and mouse idle time can be read here.
+    pub fn can_read_input(&self) -> bool {
+        self.lock().input_idle().is_some()
+    }
+
+    /// Blocks until the next entry may be processed.
+    ///
+    /// Returns right away while the user stays away; otherwise waits until
+    /// the system is idle again. Returns false if `stop` is set while
+    /// waiting.
+    pub fn wait(&self, stop: &AtomicBool) -> bool {
+        let mut monitor = self.lock();
+        if self.running.load(Ordering::SeqCst) && self.input_idle(monitor.as_mut()) {
+            return true;
+        }
+
+        self.running.store(false, Ordering::SeqCst);
+        self.notify(IdleState::Waiting);
+        monitor.disk_busy();
+        loop {
+            if !sleep_unless(stop, self.policy.poll_interval) {
+                return false;
+            }
+            let disk_quiet = monitor
+                .disk_busy()
+                .is_none_or(|busy| busy <= self.policy.max_disk_busy);
+            if self.input_idle(monitor.as_mut()) && disk_quiet {
+                break;
+            }
+        }
+
+        self.running.store(true, Ordering::SeqCst);
+        self.notify(IdleState::Running);
+        true
+    }
+
+    fn input_idle(&self, monitor: &mut dyn ActivityMonitor) -> bool {
+        monitor
+            .input_idle()
+            .is_none_or(|idle| idle >= self.policy.min_idle)
+    }
+
+    fn notify(&self, state: IdleState) {
+        if let Some(listener) = &self.listener {
+            listener(state);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Box<dyn ActivityMonitor>> {
+        self.monitor.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Daily hours, in local time, during which no run starts.
+///
+/// Written `HH:MM-HH:MM`; a range ending before it starts runs past
+/// midnight, e.g. `22:00-06:00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl ActiveHours {
+    /// Returns true if `time` falls within these hours; the end is excluded.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for ActiveHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid hours '{}': expected HH:MM-HH:MM", s);
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+        let hours = Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        };
+        if hours.start == hours.end {
+            return Err(format!("hours '{}' start and end at the same time", s));
+        }
+        Ok(hours)
+    }
+}
+
+impl fmt::Display for ActiveHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Conditions an unattended run must meet before it starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunConstraints {
+    /// Only start on AC power
+    pub require_ac_power: bool,
+    /// Only start once the busiest disk has been busy at most
+    /// `max_disk_busy` of the time for this long
+    pub disk_idle: Option<Duration>,
+    /// Highest fraction of time (0.0-1.0) the busiest disk may be busy
+    pub max_disk_busy: f64,
+    /// How often disk activity is sampled while watching it
+    pub poll_interval: Duration,
+    /// Never start during these hours
+    pub active_hours: Option<ActiveHours>,
+}
+
+/// Why a run didn't start.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnmetConstraint {
+    /// The machine runs on battery
+    OnBattery,
+    /// The local time falls within the active hours
+    ActiveHours(ActiveHours),
+    /// The busiest disk was busier than allowed while being watched
+    DiskBusy {
+        /// Fraction of time (0.0-1.0) it was busy
+        busy: f64,
+    },
+    /// The check was stopped while watching the disks
+    Interrupted,
+}
+
+impl fmt::Display for UnmetConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OnBattery => f.write_str("the machine is on battery power"),
+            Self::ActiveHours(hours) => write!(f, "it is within the active hours {}", hours),
+            Self::DiskBusy { busy } => {
+                write!(f, "a disk was busy {:.0}% of the time", busy * 100.0)
+            }
+            Self::Interrupted => f.write_str("the check was interrupted"),
+        }
+    }
+}
+
+impl RunConstraints {
+    /// Returns true if no constraint is set.
+    pub fn is_empty(&self) -> bool {
+        !self.require_ac_power && self.disk_idle.is_none() && self.active_hours.is_none()
+    }
+
+    /// Checks the constraints at local time `now`, returning the first one
+    /// not met.
+    ///
+    /// Active hours and the power source are checked first; the disks are
+    /// then watched for `disk_idle`, failing as soon as a sample is too
+    /// busy. Setting `stop` ends the watch with
+    /// [`UnmetConstraint::Interrupted`].
+    pub fn check(
+        &self,
+        monitor: &mut dyn ActivityMonitor,
+        now: NaiveTime,
+        stop: &AtomicBool,
+    ) -> Result<(), UnmetConstraint> {
+        if let Some(hours) = self.active_hours.filter(|hours| hours.contains(now)) {
+            return Err(UnmetConstraint::ActiveHours(hours));
+        }
+        if self.require_ac_power && monitor.on_ac_power() == Some(false) {
+            return Err(UnmetConstraint::OnBattery);
+        }
+
+        let Some(window) = self.disk_idle else {
+            return Ok(());
+        };
+        let started = Instant::now();
+        monitor.disk_busy();
+        while started.elapsed() < window {
+            if !sleep_unless(stop, self.poll_interval.min(window)) {
+                return Err(UnmetConstraint::Interrupted);
+            }
+            match monitor.disk_busy() {
+                Some(busy) if busy > self.max_disk_busy => {
+                    return Err(UnmetConstraint::DiskBusy { busy });
+                }
+                Some(_) => {}
+                // Disk activity can't be read here
+                None => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the power supplies listed in `/sys/class/power_supply`
+/// (as their `type`, `online` and `status` contents) feed the machine from
+/// the mains, or None if there is neither a mains supply nor a battery.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_power_supplies(supplies: &[(String, Option<String>, Option<String>)]) -> Option<bool> {
+    fn field(value: &Option<String>) -> &str {
+        value.as_deref().map(str::trim).unwrap_or_default()
+    }
+    let mains = || {
+        supplies
+            .iter()
+            .filter(|(kind, _, _)| matches!(kind.trim(), "Mains" | "USB"))
+    };
+    let batteries = || {
+        supplies
+            .iter()
+            .filter(|(kind, _, _)| kind.trim() == "Battery")
+    };
+
+    if mains().any(|(_, online, _)| field(online) == "1") {
+        Some(true)
+    } else if batteries().any(|(_, _, status)| field(status) == "Discharging")
+        || mains().next().is_some()
+    {
+        // Offline mains adapters mean the machine runs on battery
+        Some(false)
+    } else {
+        // A battery that isn't discharging is charging or full
+        batteries().next().map(|_| true)
+    }
+}
+
+/// Sleeps for `duration` in short steps; returns false if `stop` gets set.
+fn sleep_unless(stop: &AtomicBool, duration: Duration) -> bool {
+    const STEP: Duration = Duration::from_millis(100);
+    let deadline = Instant::now() + duration;
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            return false;
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(STEP));
+    }
+}
+
+/// Parses the busy milliseconds (`io_ticks`) of each device in
+/// `/proc/diskstats` that `is_disk` accepts.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_diskstats(content: &str, is_disk: impl Fn(&str) -> bool) -> Vec<(String, u64)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let name = *fields.get(2)?;
+            let busy = fields.get(12)?.parse().ok()?;
+            is_disk(name).then(|| (name.to_string(), busy))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use std::path::Path;
+    use std::time::SystemTime;
+
+    /// Whether the mains feed the machine, from the kernel's power supplies.
+    pub fn on_ac_power() -> Option<bool> {
+        let read = |path: &Path| std::fs::read_to_string(path).ok();
+        let supplies: Vec<_> = std::fs::read_dir("/sys/class/power_supply")
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let dir = entry.path();
+                Some((
+                    read(&dir.join("type"))?,
+                    read(&dir.join("online")),
+                    read(&dir.join("status")),
+                ))
+            })
+            .collect();
+        parse_power_supplies(&supplies)
+    }
+
+    /// Time since the most recent input on any terminal; the kernel updates
+    /// a terminal's access time when it's typed on.
+    pub fn input_idle() -> Option<Duration> {
+        let mut ttys: Vec<_> = std::fs::read_dir("/dev/pts")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        ttys.extend((1..=12).map(|n| Path::new("/dev").join(format!("tty{}", n))));
+
+        let last_input = ttys
+            .iter()
+            .filter_map(|tty| tty.metadata().ok()?.accessed().ok())
+            .max()?;
+        Some(
+            SystemTime::now()
+                .duration_since(last_input)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Busy milliseconds of each whole disk, leaving out partitions and
+    /// loop and RAM devices.
+    pub fn disk_busy_ms() -> Option<Vec<(String, u64)>> {
+        let content = std::fs::read_to_string("/proc/diskstats").ok()?;
+        Some(parse_diskstats(&content, |name| {
+            !name.starts_with("loop")
+                && !name.starts_with("ram")
+                && Path::new("/sys/block").join(name).exists()
+        }))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    /// Time since the last input in this session.
+    pub fn input_idle() -> Option<Duration> {
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        // SAFETY: `info` is a valid LASTINPUTINFO with cbSize set
+        if unsafe { GetLastInputInfo(&mut info) } == 0 {
+            return None;
+        }
+        // SAFETY: GetTickCount has no preconditions
+        let now = unsafe { GetTickCount() };
+        Some(Duration::from_millis(now.wrapping_sub(info.dwTime) as u64))
+    }
+
+    /// Disk activity isn't read on Windows.
+    pub fn disk_busy_ms() -> Option<Vec<(String, u64)>> {
+        None
+    }
+
+    /// Whether the AC line is online; None if Windows doesn't know.
+    pub fn on_ac_power() -> Option<bool> {
+        // SAFETY: SYSTEM_POWER_STATUS is plain data, valid when zeroed
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        // SAFETY: `status` is a valid SYSTEM_POWER_STATUS to fill in
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+            return None;
+        }
+        match status.ACLineStatus {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    use super::*;
+
+    /// Input idle time isn't read on this platform.
+    pub fn input_idle() -> Option<Duration> {
+        None
+    }
+
+    /// Disk activity isn't read on this platform.
+    pub fn disk_busy_ms() -> Option<Vec<(String, u64)>> {
+        None
+    }
+
+    /// The power source isn't read on this platform.
+    pub fn on_ac_power() -> Option<bool> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+
+    /// Replays scripted samples, repeating the last one.
+    struct Scripted {
+        input: VecDeque<u64>,
+        disk: VecDeque<f64>,
+        ac_power: Option<bool>,
+    }
+
+    impl ActivityMonitor for Scripted {
+        fn input_idle(&mut self) -> Option<Duration> {
+            let secs = match self.input.len() {
+                1 => self.input[0],
+                _ => self.input.pop_front()?,
+            };
+            Some(Duration::from_secs(secs))
+        }
+
+        fn disk_busy(&mut self) -> Option<f64> {
+            match self.disk.len() {
+                1 => Some(self.disk[0]),
+                _ => self.disk.pop_front(),
+            }
+        }
+
+        fn on_ac_power(&mut self) -> Option<bool> {
+            self.ac_power
+        }
+    }
+
+    fn gate(input: &[u64], disk: &[f64]) -> IdleGate {
+        let policy = IdlePolicy {
+            min_idle: Duration::from_secs(60),
+            max_disk_busy: 0.2,
+            poll_interval: Duration::from_millis(1),
+        };
+        IdleGate::with_monitor(
+            policy,
+            Box::new(Scripted {
+                input: input.iter().copied().collect(),
+                disk: disk.iter().copied().collect(),
+                ac_power: None,
+            }),
+        )
+    }
+
+    fn constraints() -> RunConstraints {
+        RunConstraints {
+            require_ac_power: true,
+            disk_idle: Some(Duration::from_millis(5)),
+            max_disk_busy: 0.2,
+            poll_interval: Duration::from_millis(1),
+            active_hours: Some("08:00-18:00".parse().unwrap()),
+        }
+    }
+
+    fn time(text: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(text, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_waits_for_input_and_disk_to_settle() {
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&states);
+        // Idle input but a busy disk, then active input, then idle
+        let gate = gate(&[120, 5, 120], &[0.0, 0.9, 0.1]).with_listener(move |state| {
+            recorded.lock().unwrap().push(state);
+        });
+        let stop = AtomicBool::new(false);
+
+        assert!(gate.wait(&stop));
+        assert!(gate.running.load(Ordering::SeqCst));
+        // Still idle, so the next entry goes straight through
+        assert!(gate.wait(&stop));
+        assert_eq!(
+            *states.lock().unwrap(),
+            vec![IdleState::Waiting, IdleState::Running]
+        );
+    }
+
+    #[test]
+    fn test_input_pauses_and_stop_ends_the_wait() {
+        let gate = gate(&[120, 5], &[0.0]);
+        let stop = AtomicBool::new(false);
+        assert!(gate.wait(&stop));
+
+        // Input resumed: the gate waits again until stopped
+        stop.store(true, Ordering::SeqCst);
+        assert!(!gate.wait(&stop));
+        assert!(!gate.running.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_active_hours() {
+        let office: ActiveHours = "08:00-18:00".parse().unwrap();
+        assert!(office.contains(time("08:00")));
+        assert!(office.contains(time("12:30")));
+        assert!(!office.contains(time("18:00")));
+        assert!(!office.contains(time("07:59")));
+        assert_eq!(office.to_string(), "08:00-18:00");
+
+        let night: ActiveHours = "22:00 - 06:00".parse().unwrap();
+        assert!(night.contains(time("23:00")));
+        assert!(night.contains(time("05:59")));
+        assert!(!night.contains(time("12:00")));
+
+        assert!("8-18".parse::<ActiveHours>().is_err());
+        assert!("09:00".parse::<ActiveHours>().is_err());
+        assert!("09:00-09:00".parse::<ActiveHours>().is_err());
+    }
+
+    #[test]
+    fn test_run_constraints() {
+        let stop = AtomicBool::new(false);
+        let monitor = |disk: &[f64], ac_power| Scripted {
+            input: VecDeque::from([0]),
+            disk: disk.iter().copied().collect(),
+            ac_power,
+        };
+
+        let evening = time("20:00");
+        assert_eq!(
+            constraints().check(&mut monitor(&[0.0], Some(true)), evening, &stop),
+            Ok(())
+        );
+        assert_eq!(
+            constraints().check(&mut monitor(&[0.0], Some(true)), time("09:00"), &stop),
+            Err(UnmetConstraint::ActiveHours("08:00-18:00".parse().unwrap()))
+        );
+        assert_eq!(
+            constraints().check(&mut monitor(&[0.0], Some(false)), evening, &stop),
+            Err(UnmetConstraint::OnBattery)
+        );
+        // An unknown power source counts as AC power
+        assert!(constraints()
+            .check(&mut monitor(&[0.0], None), evening, &stop)
+            .is_ok());
+        // A busy sample ends the watch early
+        let watchful = RunConstraints {
+            disk_idle: Some(Duration::from_secs(60)),
+            ..constraints()
+        };
+        assert_eq!(
+            watchful.check(&mut monitor(&[0.0, 0.1, 0.7], Some(true)), evening, &stop),
+            Err(UnmetConstraint::DiskBusy { busy: 0.7 })
+        );
+
+        stop.store(true, Ordering::SeqCst);
+        assert_eq!(
+            constraints().check(&mut monitor(&[0.0], Some(true)), evening, &stop),
+            Err(UnmetConstraint::Interrupted)
+        );
+    }
+
+    #[test]
+    fn test_parse_power_supplies() {
+        let supply = |kind: &str, online: Option<&str>, status: Option<&str>| {
+            (
+                format!("{}\n", kind),
+                online.map(str::to_string),
+                status.map(str::to_string),
+            )
+        };
+
+        let laptop_plugged = [
+            supply("Mains", Some("1\n"), None),
+            supply("Battery", None, Some("Charging\n")),
+        ];
+        assert_eq!(parse_power_supplies(&laptop_plugged), Some(true));
+        let laptop_unplugged = [
+            supply("Mains", Some("0\n"), None),
+            supply("Battery", None, Some("Discharging\n")),
+        ];
+        assert_eq!(parse_power_supplies(&laptop_unplugged), Some(false));
+        // Only a full battery is listed
+        assert_eq!(
+            parse_power_supplies(&[supply("Battery", None, Some("Full"))]),
+            Some(true)
+        );
+        // A desktop lists no supply at all
+        assert_eq!(parse_power_supplies(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_diskstats() {
+        let content = "\
+   7       0 loop0 5 0 10 0 0 0 0 0 0 7 0 0 0 0 0
+ 253       0 vda 100 0 800 50 20 0 160 30 0 1234 80 0 0 0 0
+ 253       1 vda1 90 0 700 40 20 0 160 30 0 1200 70 0 0 0 0
+";
+        let disks = parse_diskstats(content, |name| name == "vda");
+        assert_eq!(disks, vec![("vda".to_string(), 1234)]);
+    }
+}
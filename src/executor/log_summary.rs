@@ -0,0 +1,178 @@
+//! Merging of transaction logs, e.g. from sharded executions.
+
+use crate::executor::engine::SpaceByAction;
+use crate::executor::transaction::{TransactionLog, TransactionStatus};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Combined totals across one or more transaction logs.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LogSummary {
+    /// Number of logs merged
+    pub logs: usize,
+
+    /// Distinct plan files the logs refer to
+    pub plan_files: BTreeSet<PathBuf>,
+
+    /// Logs that finished with status `completed`
+    pub completed: usize,
+
+    /// Logs that finished with status `failed` or `aborted`
+    pub failed_runs: usize,
+
+    /// Logs without a summary (still running or interrupted)
+    pub incomplete: usize,
+
+    /// Logs that were paused and can be resumed
+    pub paused: usize,
+
+    /// Operations across all logs
+    pub total_operations: usize,
+
+    /// Successful operations
+    pub successful: usize,
+
+    /// Failed operations
+    pub failed: usize,
+
+    /// Skipped operations
+    pub skipped: usize,
+
+    /// Bytes freed
+    pub space_freed: u64,
+
+    /// Bytes by what happened to them
+    pub space_by_action: SpaceByAction,
+
+    /// Longest single execution, i.e. wall time when shards ran in parallel
+    pub max_duration_seconds: f64,
+
+    /// Shard count shared by the sharded logs, if any
+    pub shard_count: Option<usize>,
+
+    /// Shard indices (1-based) with no log among the inputs
+    pub missing_shards: Vec<usize>,
+}
+
+impl LogSummary {
+    /// Merges the given logs into a single summary.
+    pub fn from_logs(logs: &[TransactionLog]) -> Self {
+        let mut summary = Self {
+            logs: logs.len(),
+            ..Self::default()
+        };
+        let mut seen_shards = BTreeSet::new();
+
+        for log in logs {
+            summary.plan_files.insert(log.plan_file.clone());
+
+            match log.status {
+                TransactionStatus::Completed => summary.completed += 1,
+                TransactionStatus::Failed | TransactionStatus::Aborted => summary.failed_runs += 1,
+                TransactionStatus::Paused => summary.paused += 1,
+                TransactionStatus::InProgress => {}
+            }
+
+            match &log.summary {
+                Some(s) => {
+                    summary.total_operations += s.total_operations;
+                    summary.successful += s.successful;
+                    summary.failed += s.failed;
+                    summary.skipped += s.skipped;
+                    summary.space_freed = summary.space_freed.saturating_add(s.space_freed);
+                    summary.space_by_action.add(&s.space_by_action);
+                    summary.max_duration_seconds =
+                        summary.max_duration_seconds.max(s.duration_seconds);
+                }
+                None => summary.incomplete += 1,
+            }
+
+            if let Some(shard) = log.shard {
+                summary.shard_count = Some(summary.shard_count.unwrap_or(0).max(shard.count));
+                seen_shards.insert(shard.index);
+            }
+        }
+
+        if let Some(count) = summary.shard_count {
+            summary.missing_shards = (1..=count).filter(|i| !seen_shards.contains(i)).collect();
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::shard::Shard;
+    use crate::executor::transaction::{ExecutionSummaryLog, TransactionOptions};
+    use chrono::Utc;
+
+    fn log(shard: Option<Shard>, successful: usize, space_freed: u64) -> TransactionLog {
+        TransactionLog {
+            version: "0.1.0".to_string(),
+            execution_id: "id".to_string(),
+            plan_file: PathBuf::from("plan.yaml"),
+            started_at: Utc::now(),
+            completed_at: Some(Utc::now()),
+            status: TransactionStatus::Completed,
+            mode: "batch".to_string(),
+            options: TransactionOptions {
+                dry_run: false,
+                backup_dir: None,
+                use_recycle_bin: false,
+                fail_fast: false,
+            },
+            operations: vec![],
+            summary: Some(ExecutionSummaryLog {
+                total_operations: successful,
+                successful,
+                failed: 0,
+                skipped: 0,
+                space_freed,
+                space_by_action: SpaceByAction {
+                    recycled: space_freed,
+                    ..SpaceByAction::default()
+                },
+                duration_seconds: successful as f64,
+            }),
+            shard,
+            confirmation: None,
+            snapshot: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_sharded_logs() {
+        let logs = vec![
+            log(Some(Shard { index: 1, count: 3 }), 2, 100),
+            log(Some(Shard { index: 3, count: 3 }), 5, 400),
+        ];
+
+        let summary = LogSummary::from_logs(&logs);
+
+        assert_eq!(summary.logs, 2);
+        assert_eq!(summary.completed, 2);
+        assert_eq!(summary.successful, 7);
+        assert_eq!(summary.space_freed, 500);
+        assert_eq!(summary.space_by_action.recycled, 500);
+        assert_eq!(summary.max_duration_seconds, 5.0);
+        assert_eq!(summary.shard_count, Some(3));
+        assert_eq!(summary.missing_shards, vec![2]);
+        assert_eq!(summary.plan_files.len(), 1);
+    }
+
+    #[test]
+    fn test_incomplete_log() {
+        let mut running = log(None, 0, 0);
+        running.status = TransactionStatus::InProgress;
+        running.summary = None;
+
+        let summary = LogSummary::from_logs(&[running, log(None, 1, 10)]);
+
+        assert_eq!(summary.incomplete, 1);
+        assert_eq!(summary.completed, 1);
+        assert_eq!(summary.shard_count, None);
+        assert!(summary.missing_shards.is_empty());
+    }
+}
@@ -3,14 +3,44 @@
 //! This module provides functionality to safely execute cleanup plans with
 //! multiple execution modes, backup support, and comprehensive error handling.
 
+pub mod backup;
 pub mod engine;
+pub mod idle;
+pub mod log_summary;
+pub mod notice;
+pub mod operation_error;
+pub mod recycle_bin;
+pub mod run_as;
+pub mod shard;
+pub mod snapshot;
 pub mod transaction;
+pub mod trash_index;
+pub mod volumes;
 
+pub use backup::{BackupCheck, BackupError, BackupReport, BackupState, PruneSummary};
 pub use engine::{
     ExecutionConfig, ExecutionEngine, ExecutionError, ExecutionMode, ExecutionResult,
-    ExecutionSummary, OperationAction, OperationResult, OperationStatus,
+    ExecutionSummary, OperationAction, OperationResult, OperationStatus, SpaceByAction,
 };
+pub use idle::{
+    ActiveHours, ActivityMonitor, IdleGate, IdlePolicy, IdleState, RunConstraints, SystemMonitor,
+    UnmetConstraint,
+};
+pub use log_summary::LogSummary;
+pub use notice::{
+    DesktopNotifier, LastChance, LastChanceOutcome, NoUserPolicy, NoticeError, NoticeResponse,
+    Notifier,
+};
+pub use operation_error::OperationError;
+pub use recycle_bin::{
+    PurgeSummary, RecycleBinError, RecycleBinReport, RecycledDuplicate, RecycledItem, VolumeUsage,
+};
+pub use run_as::{Account, Impersonation, RunAsError, UserToken};
+pub use shard::{Shard, ShardParseError};
+pub use snapshot::{RollbackSummary, SnapshotError, VolumeSnapshot};
 pub use transaction::{
-    ExecutionSummaryLog, LoggedOperation, TransactionLog, TransactionLogger, TransactionOptions,
-    TransactionStatus,
+    Confirmation, ConfirmationMethod, ExecutionSummaryLog, LoggedOperation, ProcessedEntries,
+    TransactionLog, TransactionLogger, TransactionOptions, TransactionStatus,
 };
+pub use trash_index::{TrashIndex, TrashRecord};
+pub use volumes::{Volume, VolumeCache, VolumeConcurrency, VolumeKind};
@@ -0,0 +1,382 @@
+//! Last-chance notices before unattended executions.
+//!
+//! A scheduled `execute --yes` run can first tell the logged-on user what it
+//! is about to do ("Megamaid will free 23.0 GB in 10 minutes") and let them
+//! run it now, postpone it or cancel it. A notice nobody answers closes when
+//! its delay is up, and the run proceeds.
+//!
+//! When no user can see a notice (nobody is logged on, or the run is in a
+//! non-interactive session such as a task set to run whether or not a user
+//! is logged on) the configured [`NoUserPolicy`] decides instead.
+//!
+//! [`DesktopNotifier`] shows a system-modal dialog on Windows when built with
+//! the `notice` feature. Elsewhere it never finds a user, so the policy
+//! always applies.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors showing a notice.
+#[derive(Debug, Error)]
+pub enum NoticeError {
+    /// Notices aren't available on this platform or build
+    #[error("last-chance notices need Windows with the notice feature")]
+    Unsupported,
+
+    /// The dialog couldn't be shown
+    #[error("failed to show notice: {0}")]
+    Show(String),
+}
+
+/// How the user answered a notice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoticeResponse {
+    /// Run now
+    Proceed,
+    /// Ask again after the snooze interval
+    Postpone,
+    /// Skip this run
+    Cancel,
+    /// Nobody answered before the notice closed
+    TimedOut,
+}
+
+/// What an unattended run does when no user can be asked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoUserPolicy {
+    /// Run anyway
+    Proceed,
+    /// Skip the run
+    #[default]
+    Abort,
+}
+
+/// Shows notices to the logged-on user.
+pub trait Notifier {
+    /// Returns true if a logged-on user can see a notice.
+    fn user_present(&self) -> bool;
+
+    /// Shows `message` until the user answers or `timeout` elapses.
+    ///
+    /// The postpone choice is only offered if `can_postpone` is true.
+    fn show(
+        &self,
+        message: &str,
+        can_postpone: bool,
+        timeout: Duration,
+    ) -> Result<NoticeResponse, NoticeError>;
+}
+
+/// Result of giving the user a last chance to stop a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastChanceOutcome {
+    /// The user let the run proceed, after postponing it `snoozes` times
+    Proceed { snoozes: u32 },
+    /// The user cancelled the run
+    Cancelled,
+    /// No user could be asked, so the policy decided
+    NoUser(NoUserPolicy),
+}
+
+impl LastChanceOutcome {
+    /// Returns true if the run should go ahead.
+    pub fn proceeds(self) -> bool {
+        matches!(
+            self,
+            Self::Proceed { .. } | Self::NoUser(NoUserPolicy::Proceed)
+        )
+    }
+}
+
+/// Timing of a last-chance notice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastChance {
+    /// How long the notice stays up before the run proceeds
+    pub delay: Duration,
+    /// How long a postponed run waits before asking again
+    pub snooze: Duration,
+    /// How many times the run can be postponed
+    pub max_snoozes: u32,
+    /// What to do when no user can be asked
+    pub no_user: NoUserPolicy,
+}
+
+impl LastChance {
+    /// Asks the user whether a run freeing `bytes` in `entries` may go ahead.
+    ///
+    /// Blocks until the run may proceed or is cancelled, calling `wait`
+    /// between a postponement and the next notice. A notice that fails to
+    /// show is treated like an absent user.
+    pub fn ask(
+        &self,
+        notifier: &dyn Notifier,
+        entries: usize,
+        bytes: u64,
+        wait: &mut dyn FnMut(Duration),
+    ) -> LastChanceOutcome {
+        let mut snoozes = 0;
+        loop {
+            if !notifier.user_present() {
+                return LastChanceOutcome::NoUser(self.no_user);
+            }
+
+            let can_postpone = snoozes < self.max_snoozes;
+            let message = self.message(entries, bytes, can_postpone);
+            match notifier.show(&message, can_postpone, self.delay) {
+                Ok(NoticeResponse::Proceed | NoticeResponse::TimedOut) => {
+                    return LastChanceOutcome::Proceed { snoozes };
+                }
+                Ok(NoticeResponse::Postpone) if can_postpone => {
+                    snoozes += 1;
+                    wait(self.snooze);
+                }
+                Ok(NoticeResponse::Postpone | NoticeResponse::Cancel) => {
+                    return LastChanceOutcome::Cancelled;
+                }
+                Err(_) => return LastChanceOutcome::NoUser(self.no_user),
+            }
+        }
+    }
+
+    /// Formats the notice text.
+    fn message(&self, entries: usize, bytes: u64, can_postpone: bool) -> String {
+        let mut message = format!(
+            "Megamaid will free {:.1} GB ({} entries) in {} — ",
+            bytes as f64 / 1_073_741_824.0,
+            entries,
+            format_minutes(self.delay)
+        );
+        if can_postpone {
+            message.push_str(&format!("postpone it by {}?", format_minutes(self.snooze)));
+        } else {
+            message.push_str("cancel it?");
+        }
+        message
+    }
+}
+
+/// Formats a duration in whole minutes (e.g. "10 minutes").
+fn format_minutes(duration: Duration) -> String {
+    match duration.as_secs().div_ceil(60) {
+        1 => "1 minute".to_string(),
+        minutes => format!("{} minutes", minutes),
+    }
+}
+
+/// Shows notices with the platform's native dialog.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DesktopNotifier;
+
+impl Notifier for DesktopNotifier {
+    fn user_present(&self) -> bool {
+        platform::user_present()
+    }
+
+    fn show(
+        &self,
+        message: &str,
+        can_postpone: bool,
+        timeout: Duration,
+    ) -> Result<NoticeResponse, NoticeError> {
+        platform::show(message, can_postpone, timeout)
+    }
+}
+
+#[cfg(all(windows, feature = "notice"))]
+mod platform {
+    use super::*;
+    use std::process::Command;
+
+    /// `WScript.Shell.Popup` button sets, icon and modality flags
+    const YES_NO_CANCEL: u32 = 3;
+    const OK_CANCEL: u32 = 1;
+    const WARNING_ICON: u32 = 0x30;
+    const SYSTEM_MODAL: u32 = 0x1000;
+
+    /// Runs a PowerShell script and returns its trimmed standard output.
+    fn powershell(script: &str) -> Result<String, NoticeError> {
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .output()
+            .map_err(|e| NoticeError::Show(format!("failed to run PowerShell: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(NoticeError::Show(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Session 0 runs services and tasks nobody is logged on for; dialogs
+    /// shown there are never seen.
+    pub fn user_present() -> bool {
+        powershell(
+            "[Environment]::UserInteractive -and (Get-Process -Id $PID).SessionId -ne 0 \
+             -and [bool](Get-Process explorer -ErrorAction SilentlyContinue | \
+             Where-Object { $_.SessionId -eq (Get-Process -Id $PID).SessionId })",
+        )
+        .is_ok_and(|output| output.eq_ignore_ascii_case("true"))
+    }
+
+    pub fn show(
+        message: &str,
+        can_postpone: bool,
+        timeout: Duration,
+    ) -> Result<NoticeResponse, NoticeError> {
+        let (buttons, choices) = if can_postpone {
+            (
+                YES_NO_CANCEL,
+                "Yes: run now    No: postpone    Cancel: skip this run",
+            )
+        } else {
+            (OK_CANCEL, "OK: run now    Cancel: skip this run")
+        };
+        let text = format!("{}\n\n{}", message, choices).replace('\'', "''");
+        let script = format!(
+            "(New-Object -ComObject WScript.Shell).Popup('{}', {}, 'Megamaid', {})",
+            text,
+            timeout.as_secs().max(1),
+            buttons | WARNING_ICON | SYSTEM_MODAL
+        );
+
+        parse_popup_result(&powershell(&script)?)
+    }
+}
+
+#[cfg(not(all(windows, feature = "notice")))]
+mod platform {
+    use super::*;
+
+    pub fn user_present() -> bool {
+        false
+    }
+
+    pub fn show(
+        _message: &str,
+        _can_postpone: bool,
+        _timeout: Duration,
+    ) -> Result<NoticeResponse, NoticeError> {
+        Err(NoticeError::Unsupported)
+    }
+}
+
+/// Maps the button code returned by `WScript.Shell.Popup`.
+#[cfg_attr(not(all(windows, feature = "notice")), allow(dead_code))]
+fn parse_popup_result(output: &str) -> Result<NoticeResponse, NoticeError> {
+    match output.trim() {
+        "-1" => Ok(NoticeResponse::TimedOut),
+        "1" | "6" => Ok(NoticeResponse::Proceed),
+        "7" => Ok(NoticeResponse::Postpone),
+        "2" => Ok(NoticeResponse::Cancel),
+        other => Err(NoticeError::Show(format!("unexpected answer: {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Answers notices from a script and records what was shown.
+    struct ScriptedNotifier {
+        present: bool,
+        answers: RefCell<Vec<NoticeResponse>>,
+        shown: RefCell<Vec<(String, bool)>>,
+    }
+
+    impl ScriptedNotifier {
+        fn new(present: bool, answers: &[NoticeResponse]) -> Self {
+            Self {
+                present,
+                answers: RefCell::new(answers.iter().rev().copied().collect()),
+                shown: RefCell::default(),
+            }
+        }
+    }
+
+    impl Notifier for ScriptedNotifier {
+        fn user_present(&self) -> bool {
+            self.present
+        }
+
+        fn show(
+            &self,
+            message: &str,
+            can_postpone: bool,
+            _timeout: Duration,
+        ) -> Result<NoticeResponse, NoticeError> {
+            self.shown
+                .borrow_mut()
+                .push((message.to_string(), can_postpone));
+            self.answers
+                .borrow_mut()
+                .pop()
+                .ok_or(NoticeError::Unsupported)
+        }
+    }
+
+    fn last_chance(no_user: NoUserPolicy) -> LastChance {
+        LastChance {
+            delay: Duration::from_secs(600),
+            snooze: Duration::from_secs(1800),
+            max_snoozes: 2,
+            no_user,
+        }
+    }
+
+    #[test]
+    fn test_snoozes_until_limit_then_cancels() {
+        let notifier = ScriptedNotifier::new(true, &[NoticeResponse::Postpone; 3]);
+        let mut waited = Vec::new();
+        let outcome =
+            last_chance(NoUserPolicy::Proceed)
+                .ask(&notifier, 12, 23 * 1_073_741_824, &mut |d| waited.push(d));
+
+        assert_eq!(outcome, LastChanceOutcome::Cancelled);
+        assert_eq!(waited, vec![Duration::from_secs(1800); 2]);
+        let shown = notifier.shown.borrow();
+        assert_eq!(
+            shown[0].0,
+            "Megamaid will free 23.0 GB (12 entries) in 10 minutes — postpone it by 30 minutes?"
+        );
+        let offered: Vec<_> = shown.iter().map(|(_, postpone)| *postpone).collect();
+        assert_eq!(offered, vec![true, true, false]);
+    }
+
+    #[test]
+    fn test_unanswered_notice_proceeds() {
+        let notifier =
+            ScriptedNotifier::new(true, &[NoticeResponse::Postpone, NoticeResponse::TimedOut]);
+        let outcome = last_chance(NoUserPolicy::Abort).ask(&notifier, 1, 0, &mut |_| {});
+        assert_eq!(outcome, LastChanceOutcome::Proceed { snoozes: 1 });
+        assert!(outcome.proceeds());
+    }
+
+    #[test]
+    fn test_no_user_falls_back_to_policy() {
+        let absent = ScriptedNotifier::new(false, &[]);
+        let outcome = last_chance(NoUserPolicy::Abort).ask(&absent, 1, 0, &mut |_| {});
+        assert_eq!(outcome, LastChanceOutcome::NoUser(NoUserPolicy::Abort));
+        assert!(!outcome.proceeds());
+
+        // A notice that can't be shown counts as no user
+        let failing = ScriptedNotifier::new(true, &[]);
+        let outcome = last_chance(NoUserPolicy::Proceed).ask(&failing, 1, 0, &mut |_| {});
+        assert!(outcome.proceeds());
+    }
+
+    #[test]
+    fn test_parse_popup_result() {
+        assert_eq!(
+            parse_popup_result("-1\r\n").unwrap(),
+            NoticeResponse::TimedOut
+        );
+        assert_eq!(parse_popup_result("6").unwrap(), NoticeResponse::Proceed);
+        assert_eq!(parse_popup_result("7").unwrap(), NoticeResponse::Postpone);
+        assert_eq!(parse_popup_result("2").unwrap(), NoticeResponse::Cancel);
+        assert!(parse_popup_result("").is_err());
+    }
+}
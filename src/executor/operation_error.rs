@@ -0,0 +1,190 @@
+//! Typed failures of cleanup operations.
+//!
+//! A failed operation records why it failed as an [`OperationError`], and the
+//! transaction log keeps it, so reports can group failures by cause and tell
+//! the transient ones (a file briefly locked, a stalled share) from those that
+//! need the user's attention first. `megamaid execute --only-failed <LOG>`
+//! retries just the entries a log records as failed.
+
+use crate::executor::engine::OperationAction;
+use crate::verifier::access::AccessErrorCause;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io;
+use thiserror::Error;
+
+/// Why an operation failed, with the error message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum OperationError {
+    /// The entry was already gone
+    #[error("{0}")]
+    NotFound(String),
+
+    /// The entry's ACL or mode denies access to the current user
+    #[error("{0}")]
+    PermissionDenied(String),
+
+    /// Another process holds the entry open or locked
+    #[error("{0}")]
+    SharingViolation(String),
+
+    /// The path or one of its names is longer than the platform allows
+    #[error("{0}")]
+    PathTooLong(String),
+
+    /// The backup directory is on another volume than the entry
+    #[error("{0}")]
+    CrossDevice(String),
+
+    /// The recycle bin refused the entry or isn't available
+    #[error("{0}")]
+    TrashUnavailable(String),
+
+    /// An operation didn't finish within `ExecutionConfig::operation_timeout`
+    #[error("{0}")]
+    Timeout(String),
+
+    /// Any other failure, including a panicking operation, or the reason an
+    /// entry was skipped
+    #[error("{0}")]
+    Other(String),
+}
+
+impl OperationError {
+    /// Classifies an I/O error from performing `action`.
+    ///
+    /// Errors the recycle bin reports without a more specific cause are
+    /// [`OperationError::TrashUnavailable`].
+    pub fn from_io(err: &io::Error, action: &OperationAction) -> Self {
+        let message = err.to_string();
+        if AccessErrorCause::of(err) == AccessErrorCause::SharingViolation {
+            return Self::SharingViolation(message);
+        }
+        match err.kind() {
+            io::ErrorKind::NotFound => Self::NotFound(message),
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied(message),
+            io::ErrorKind::InvalidFilename => Self::PathTooLong(message),
+            io::ErrorKind::CrossesDevices => Self::CrossDevice(message),
+            io::ErrorKind::TimedOut => Self::Timeout(message),
+            _ if *action == OperationAction::MoveToRecycleBin => Self::TrashUnavailable(message),
+            _ => Self::Other(message),
+        }
+    }
+
+    /// The error message.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(message)
+            | Self::PermissionDenied(message)
+            | Self::SharingViolation(message)
+            | Self::PathTooLong(message)
+            | Self::CrossDevice(message)
+            | Self::TrashUnavailable(message)
+            | Self::Timeout(message)
+            | Self::Other(message) => message,
+        }
+    }
+
+    /// Returns true if retrying the operation may succeed without the user
+    /// doing anything first.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::SharingViolation(_) | Self::Timeout(_))
+    }
+
+    /// Returns true if retrying the operation can succeed at all, possibly
+    /// after following [`OperationError::recommendation`].
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Self::NotFound(_))
+    }
+
+    /// Human-readable heading for failures of this kind.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "Already gone",
+            Self::PermissionDenied(_) => "Access denied",
+            Self::SharingViolation(_) => "Locked by another process",
+            Self::PathTooLong(_) => "Path too long",
+            Self::CrossDevice(_) => "Backup on another volume",
+            Self::TrashUnavailable(_) => "Recycle bin unavailable",
+            Self::Timeout(_) => "Timed out",
+            Self::Other(_) => "Other errors",
+        }
+    }
+
+    /// What the user can do about entries failing this way.
+    pub fn recommendation(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "nothing to do, something else removed them",
+            Self::PermissionDenied(_) => {
+                "rerun elevated (as Administrator or root) or as the entries' owner"
+            }
+            Self::SharingViolation(_) => "close the programs using them, then retry",
+            Self::PathTooLong(_) => {
+                "enable long-path support (Windows) or shorten the parent directories' names"
+            }
+            Self::CrossDevice(_) => "retry with a backup directory on the same volume",
+            Self::TrashUnavailable(_) => "retry without --recycle-bin, or after emptying the bin",
+            Self::Timeout(_) => "check the disk or share, then retry",
+            Self::Other(_) => "check these paths manually, or skip them",
+        }
+    }
+}
+
+/// Reads a logged error, accepting the plain messages of logs written before
+/// errors were typed as [`OperationError::Other`].
+pub(crate) fn deserialize_logged<'de, D>(
+    deserializer: D,
+) -> Result<Option<OperationError>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Logged {
+        Typed(OperationError),
+        Message(String),
+    }
+
+    Ok(
+        Option::<Logged>::deserialize(deserializer)?.map(|logged| match logged {
+            Logged::Typed(error) => error,
+            Logged::Message(message) => OperationError::Other(message),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_io_errors() {
+        let delete = OperationAction::Delete;
+        let recycle = OperationAction::MoveToRecycleBin;
+
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert!(matches!(
+            OperationError::from_io(&missing, &delete),
+            OperationError::NotFound(_)
+        ));
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            OperationError::from_io(&denied, &recycle),
+            OperationError::PermissionDenied(_)
+        ));
+        let timeout = io::Error::new(io::ErrorKind::TimedOut, "operation timed out");
+        let error = OperationError::from_io(&timeout, &delete);
+        assert!(error.is_transient());
+        assert_eq!(error.message(), "operation timed out");
+
+        let refused = io::Error::other("no trash folder");
+        assert!(matches!(
+            OperationError::from_io(&refused, &recycle),
+            OperationError::TrashUnavailable(_)
+        ));
+        assert!(matches!(
+            OperationError::from_io(&refused, &delete),
+            OperationError::Other(_)
+        ));
+    }
+}
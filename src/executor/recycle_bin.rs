@@ -0,0 +1,503 @@
+//! Recycle bin occupancy reporting and emptying.
+//!
+//! Moving entries to the recycle bin does not free any space until the bin is
+//! emptied. After a recycle-bin execution, [`occupancy`] reports how much the
+//! bin holds on each volume, and [`purge_recycled`] permanently deletes the
+//! items a given run recycled (and nothing else).
+//!
+//! Before a recycle-bin execution, [`find_duplicates`] reports entries that
+//! look already recycled by an earlier run, by name and size: recycling them
+//! again keeps two copies in the bin. The items megamaid recycled are
+//! remembered in a [`TrashIndex`](crate::executor::TrashIndex), so they can
+//! be listed and purged apart from the rest of the bin.
+//!
+//! Listing and purging use the platform shell APIs via the `trash` crate and
+//! are only available on Windows and freedesktop.org-compliant Unix systems
+//! with the `trash` feature enabled; otherwise these functions return
+//! [`RecycleBinError::Unsupported`].
+
+use crate::models::PathKey;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Errors accessing the recycle bin.
+#[derive(Debug, Error)]
+pub enum RecycleBinError {
+    /// The platform does not support listing the recycle bin
+    #[error("listing the recycle bin is not supported on this platform")]
+    Unsupported,
+
+    /// The shell API reported an error
+    #[error("recycle bin error: {0}")]
+    Trash(String),
+}
+
+/// Recycle bin contents originating from one volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeUsage {
+    /// Root of the volume the items were deleted from
+    pub volume: PathBuf,
+
+    /// Number of items in the bin
+    pub items: usize,
+
+    /// Total size of the items whose size could be determined
+    pub bytes: u64,
+
+    /// Items whose size could not be determined (e.g. folders on Windows)
+    pub unsized_items: usize,
+}
+
+/// Recycle bin occupancy per volume, sorted by volume.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecycleBinReport {
+    pub volumes: Vec<VolumeUsage>,
+}
+
+impl RecycleBinReport {
+    /// Builds a report from `(volume, size)` pairs, one per item.
+    pub fn from_items(items: impl IntoIterator<Item = (PathBuf, Option<u64>)>) -> Self {
+        let mut volumes: BTreeMap<PathBuf, VolumeUsage> = BTreeMap::new();
+        for (volume, size) in items {
+            let usage = volumes
+                .entry(volume.clone())
+                .or_insert_with(|| VolumeUsage {
+                    volume,
+                    items: 0,
+                    bytes: 0,
+                    unsized_items: 0,
+                });
+            usage.items += 1;
+            match size {
+                Some(bytes) => usage.bytes = usage.bytes.saturating_add(bytes),
+                None => usage.unsized_items += 1,
+            }
+        }
+
+        Self {
+            volumes: volumes.into_values().collect(),
+        }
+    }
+
+    /// Total number of items across all volumes.
+    pub fn total_items(&self) -> usize {
+        self.volumes.iter().map(|v| v.items).sum()
+    }
+
+    /// Total known size across all volumes.
+    pub fn total_bytes(&self) -> u64 {
+        self.volumes.iter().map(|v| v.bytes).sum()
+    }
+}
+
+/// Summary of a purge.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PurgeSummary {
+    /// Items permanently deleted
+    pub items: usize,
+
+    /// Known size of the deleted items
+    pub bytes: u64,
+}
+
+/// A recycled item as needed for selecting and sizing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecycledItem {
+    /// Where the item lived before it was recycled
+    pub original_path: PathBuf,
+
+    /// When it was recycled, in seconds since the Unix epoch
+    pub time_deleted: i64,
+
+    /// Size of the item, if it could be determined
+    pub size: Option<u64>,
+}
+
+/// A plan entry matching an item already in the recycle bin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecycledDuplicate {
+    /// Path of the entry about to be recycled
+    pub path: PathBuf,
+
+    /// The item in the bin with the same name and size
+    pub item: RecycledItem,
+}
+
+/// Returns the `(path, size)` candidates whose name and size match an item in
+/// the bin; a candidate matching several items is reported with the latest.
+pub fn find_duplicates(
+    candidates: &[(PathBuf, u64)],
+    items: &[RecycledItem],
+) -> Vec<RecycledDuplicate> {
+    let mut by_name: HashMap<(PathKey, u64), &RecycledItem> = HashMap::new();
+    for item in items {
+        let (Some(name), Some(size)) = (item.original_path.file_name(), item.size) else {
+            continue;
+        };
+        let latest = by_name
+            .entry((PathKey::new(Path::new(name)), size))
+            .or_insert(item);
+        if item.time_deleted > latest.time_deleted {
+            *latest = item;
+        }
+    }
+
+    candidates
+        .iter()
+        .filter_map(|(path, size)| {
+            let name = path.file_name()?;
+            let item = by_name.get(&(PathKey::new(Path::new(name)), *size))?;
+            Some(RecycledDuplicate {
+                path: path.clone(),
+                item: (*item).clone(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the indices of `items` that were recycled from `paths` at or after `since`.
+pub fn select_recycled(items: &[RecycledItem], paths: &[PathBuf], since: SystemTime) -> Vec<usize> {
+    let paths: HashSet<PathKey> = paths.iter().map(|p| PathKey::new(p)).collect();
+    // Allow for the one-second resolution of deletion times
+    let since = since
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64 - 1)
+        .unwrap_or(0);
+
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.time_deleted >= since)
+        .filter(|(_, item)| paths.contains(&PathKey::new(&item.original_path)))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the root of the volume holding `path`.
+///
+/// On Windows this is the path prefix (e.g. `C:\`). On Unix it is the mount
+/// point, found by walking up from the nearest existing ancestor until the
+/// device changes.
+pub fn volume_of(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        use std::path::Component;
+        match path.components().next() {
+            Some(Component::Prefix(prefix)) => {
+                let mut root = PathBuf::from(prefix.as_os_str());
+                root.push(std::path::MAIN_SEPARATOR_STR);
+                root
+            }
+            _ => PathBuf::from(std::path::MAIN_SEPARATOR_STR),
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let Some((mut mount, dev)) = path
+            .ancestors()
+            .find_map(|p| std::fs::metadata(p).ok().map(|m| (p, m.dev())))
+        else {
+            return PathBuf::from("/");
+        };
+        while let Some(parent) = mount.parent() {
+            match std::fs::metadata(parent) {
+                Ok(meta) if meta.dev() == dev => mount = parent,
+                _ => break,
+            }
+        }
+        mount.to_path_buf()
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        path.ancestors().last().unwrap_or(path).to_path_buf()
+    }
+}
+
+#[cfg(all(
+    feature = "trash",
+    any(
+        target_os = "windows",
+        all(
+            unix,
+            not(target_os = "macos"),
+            not(target_os = "ios"),
+            not(target_os = "android")
+        )
+    )
+))]
+mod platform {
+    use super::*;
+    use trash::os_limited;
+    use trash::{TrashItem, TrashItemSize};
+
+    fn trash_error(e: trash::Error) -> RecycleBinError {
+        RecycleBinError::Trash(e.to_string())
+    }
+
+    pub fn list() -> Result<Vec<TrashItem>, RecycleBinError> {
+        os_limited::list().map_err(trash_error)
+    }
+
+    pub fn item_size(item: &TrashItem) -> Option<u64> {
+        match os_limited::metadata(item).ok()?.size {
+            TrashItemSize::Bytes(bytes) => Some(bytes),
+            TrashItemSize::Entries(_) => trashed_dir_size(item),
+        }
+    }
+
+    /// Sums a trashed folder's contents; freedesktop trash keeps them in
+    /// `files/<name>` next to `info/<name>.trashinfo`.
+    #[cfg(unix)]
+    fn trashed_dir_size(item: &TrashItem) -> Option<u64> {
+        let info = Path::new(&item.id);
+        let stem = info.file_stem()?;
+        let files = info.parent()?.parent()?.join("files").join(stem);
+        Some(
+            walkdir::WalkDir::new(files)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum(),
+        )
+    }
+
+    #[cfg(windows)]
+    fn trashed_dir_size(_item: &TrashItem) -> Option<u64> {
+        None
+    }
+
+    pub fn contents() -> Result<Vec<RecycledItem>, RecycleBinError> {
+        Ok(list()?
+            .iter()
+            .map(|item| RecycledItem {
+                original_path: item.original_path(),
+                time_deleted: item.time_deleted,
+                size: item_size(item),
+            })
+            .collect())
+    }
+
+    pub fn purge_items(wanted: &[RecycledItem]) -> Result<PurgeSummary, RecycleBinError> {
+        let wanted: HashSet<(PathKey, i64)> = wanted
+            .iter()
+            .map(|item| (PathKey::new(&item.original_path), item.time_deleted))
+            .collect();
+        let selected: Vec<TrashItem> = list()?
+            .into_iter()
+            .filter(|item| {
+                wanted.contains(&(PathKey::new(&item.original_path()), item.time_deleted))
+            })
+            .collect();
+        let summary = PurgeSummary {
+            items: selected.len(),
+            bytes: selected.iter().filter_map(item_size).sum(),
+        };
+
+        if !selected.is_empty() {
+            os_limited::purge_all(selected).map_err(trash_error)?;
+        }
+        Ok(summary)
+    }
+
+    pub fn occupancy() -> Result<RecycleBinReport, RecycleBinError> {
+        let items = list()?;
+        Ok(RecycleBinReport::from_items(items.iter().map(|item| {
+            (volume_of(&item.original_parent), item_size(item))
+        })))
+    }
+
+    pub fn purge_recycled(
+        paths: &[PathBuf],
+        since: SystemTime,
+    ) -> Result<PurgeSummary, RecycleBinError> {
+        let items = list()?;
+        let candidates: Vec<RecycledItem> = items
+            .iter()
+            .map(|item| RecycledItem {
+                original_path: item.original_path(),
+                time_deleted: item.time_deleted,
+                size: None,
+            })
+            .collect();
+
+        let selected: Vec<&TrashItem> = select_recycled(&candidates, paths, since)
+            .into_iter()
+            .map(|i| &items[i])
+            .collect();
+        let summary = PurgeSummary {
+            items: selected.len(),
+            bytes: selected.iter().filter_map(|item| item_size(item)).sum(),
+        };
+
+        if !selected.is_empty() {
+            os_limited::purge_all(selected).map_err(trash_error)?;
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(not(all(
+    feature = "trash",
+    any(
+        target_os = "windows",
+        all(
+            unix,
+            not(target_os = "macos"),
+            not(target_os = "ios"),
+            not(target_os = "android")
+        )
+    )
+)))]
+mod platform {
+    use super::*;
+
+    pub fn contents() -> Result<Vec<RecycledItem>, RecycleBinError> {
+        Err(RecycleBinError::Unsupported)
+    }
+
+    pub fn purge_items(_items: &[RecycledItem]) -> Result<PurgeSummary, RecycleBinError> {
+        Err(RecycleBinError::Unsupported)
+    }
+
+    pub fn occupancy() -> Result<RecycleBinReport, RecycleBinError> {
+        Err(RecycleBinError::Unsupported)
+    }
+
+    pub fn purge_recycled(
+        _paths: &[PathBuf],
+        _since: SystemTime,
+    ) -> Result<PurgeSummary, RecycleBinError> {
+        Err(RecycleBinError::Unsupported)
+    }
+}
+
+/// Lists the items in the recycle bin, with their sizes.
+pub fn contents() -> Result<Vec<RecycledItem>, RecycleBinError> {
+    platform::contents()
+}
+
+/// Permanently deletes the items in the bin with the original path and
+/// deletion time of one of `items`.
+pub fn purge_items(items: &[RecycledItem]) -> Result<PurgeSummary, RecycleBinError> {
+    platform::purge_items(items)
+}
+
+/// Reports what the recycle bin currently holds, per volume.
+pub fn occupancy() -> Result<RecycleBinReport, RecycleBinError> {
+    platform::occupancy()
+}
+
+/// Permanently deletes the items recycled from `paths` at or after `since`.
+///
+/// Other items in the recycle bin are left alone.
+pub fn purge_recycled(
+    paths: &[PathBuf],
+    since: SystemTime,
+) -> Result<PurgeSummary, RecycleBinError> {
+    platform::purge_recycled(paths, since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_report_groups_by_volume() {
+        let report = RecycleBinReport::from_items(vec![
+            (PathBuf::from("/data"), Some(100)),
+            (PathBuf::from("/"), Some(5)),
+            (PathBuf::from("/data"), None),
+            (PathBuf::from("/data"), Some(50)),
+        ]);
+
+        assert_eq!(report.volumes.len(), 2);
+        assert_eq!(report.volumes[0].volume, PathBuf::from("/"));
+        assert_eq!(
+            report.volumes[1],
+            VolumeUsage {
+                volume: PathBuf::from("/data"),
+                items: 3,
+                bytes: 150,
+                unsized_items: 1,
+            }
+        );
+        assert_eq!(report.total_items(), 4);
+        assert_eq!(report.total_bytes(), 155);
+    }
+
+    #[test]
+    fn test_select_recycled_matches_run_paths_and_time() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let items = vec![
+            RecycledItem {
+                original_path: PathBuf::from("/p/target"),
+                time_deleted: 1_000,
+                size: None,
+            },
+            // Recycled by an earlier run
+            RecycledItem {
+                original_path: PathBuf::from("/p/target"),
+                time_deleted: 500,
+                size: None,
+            },
+            // Not part of this run
+            RecycledItem {
+                original_path: PathBuf::from("/p/keep.txt"),
+                time_deleted: 1_001,
+                size: None,
+            },
+            RecycledItem {
+                original_path: PathBuf::from("/p/node_modules"),
+                time_deleted: 1_002,
+                size: None,
+            },
+        ];
+        let paths = vec![PathBuf::from("/p/target"), PathBuf::from("/p/node_modules")];
+
+        assert_eq!(select_recycled(&items, &paths, start), vec![0, 3]);
+    }
+
+    #[test]
+    fn test_find_duplicates_by_name_and_size() {
+        let item = |path: &str, time_deleted, size| RecycledItem {
+            original_path: PathBuf::from(path),
+            time_deleted,
+            size,
+        };
+        let items = vec![
+            item("/old/app.iso", 100, Some(4096)),
+            item("/older/app.iso", 50, Some(4096)),
+            item("/old/other.iso", 100, Some(10)),
+            item("/old/unsized", 100, None),
+        ];
+        let candidates = vec![
+            (PathBuf::from("/p/app.iso"), 4096),
+            (PathBuf::from("/p/other.iso"), 11),
+            (PathBuf::from("/p/unsized"), 0),
+        ];
+
+        let duplicates = find_duplicates(&candidates, &items);
+
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].path, PathBuf::from("/p/app.iso"));
+        assert_eq!(duplicates[0].item, items[0], "the latest match is reported");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_volume_of_missing_path_uses_existing_ancestor() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let missing = temp.path().join("gone/deeper");
+
+        assert_eq!(volume_of(&missing), volume_of(temp.path()));
+    }
+}
@@ -0,0 +1,301 @@
+//! Running deletions under another user's account.
+//!
+//! An administrator cleaning up another user's profile deletes with their own
+//! rights: recycled items land in the administrator's recycle bin rather than
+//! the profile owner's, and files the owner couldn't delete go anyway. With
+//! `execute --run-as DOMAIN\user` (or `executor.run_as`), megamaid logs that
+//! account on and performs every delete, move and recycle impersonating it,
+//! so recycled items go to that user's bin and access checks are the ones
+//! the user gets. The password comes from the keyring secret named by
+//! `executor.run_as_secret`, or is prompted for.
+//!
+//! Impersonation is a Windows mechanism. Elsewhere `--run-as` is refused;
+//! run megamaid as the user (e.g. with `sudo -u`) instead.
+
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Whether this platform can run operations as another user.
+pub const SUPPORTED: bool = cfg!(windows);
+
+/// Errors logging an account on.
+#[derive(Debug, Error)]
+pub enum RunAsError {
+    /// The account name is empty or malformed
+    #[error("invalid account '{0}': use DOMAIN\\user, user@domain or user")]
+    InvalidAccount(String),
+
+    /// Impersonation isn't available on this platform
+    #[error("running as another user is only supported on Windows")]
+    Unsupported,
+
+    /// The logon was refused, e.g. for a wrong password
+    #[error("couldn't log on as {account}: {source}")]
+    Logon { account: Account, source: io::Error },
+}
+
+/// A Windows account: `DOMAIN\user`, `.\user` for a local account, or a
+/// `user@domain` principal name without a separate domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// Domain or computer name; `None` for a principal name
+    pub domain: Option<String>,
+
+    /// User name
+    pub user: String,
+}
+
+impl FromStr for Account {
+    type Err = RunAsError;
+
+    /// Parses `DOMAIN\user`, `user@domain`, or a bare local `user`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || RunAsError::InvalidAccount(text.to_string());
+        let (domain, user) = match text.split_once('\\') {
+            Some((domain, user)) if !domain.is_empty() => (Some(domain), user),
+            Some(_) => return Err(invalid()),
+            None if text.contains('@') => (None, text),
+            None => (Some("."), text),
+        };
+        if user.is_empty() || user.contains(['\\', '/']) || user.starts_with('@') {
+            return Err(invalid());
+        }
+        Ok(Self {
+            domain: domain.map(str::to_string),
+            user: user.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.domain {
+            Some(domain) => write!(f, "{}\\{}", domain, self.user),
+            None => f.write_str(&self.user),
+        }
+    }
+}
+
+/// A logged-on account whose rights operations can take up.
+pub struct UserToken {
+    account: Account,
+    token: platform::Token,
+}
+
+impl UserToken {
+    /// Logs `account` on with `password`.
+    pub fn logon(account: &Account, password: &str) -> Result<Self, RunAsError> {
+        if !SUPPORTED {
+            return Err(RunAsError::Unsupported);
+        }
+        let token = platform::logon(&account.user, account.domain.as_deref(), password).map_err(
+            |source| RunAsError::Logon {
+                account: account.clone(),
+                source,
+            },
+        )?;
+        Ok(Self {
+            account: account.clone(),
+            token,
+        })
+    }
+
+    /// Returns the logged-on account.
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    /// Makes the current thread act as the account until the returned guard
+    /// is dropped.
+    ///
+    /// Impersonation applies to one thread only, so each thread running
+    /// operations has to take it up itself.
+    pub fn impersonate(&self) -> io::Result<Impersonation> {
+        self.token.impersonate()?;
+        Ok(Impersonation {
+            _thread: PhantomData,
+        })
+    }
+}
+
+impl fmt::Debug for UserToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserToken")
+            .field("account", &self.account)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Impersonation of the current thread, reverted on drop.
+pub struct Impersonation {
+    /// Reverting has to happen on the impersonating thread
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for Impersonation {
+    fn drop(&mut self) {
+        platform::revert();
+    }
+}
+
+/// Reads a password from the terminal, without echoing it where the console
+/// allows.
+pub fn read_password() -> io::Result<String> {
+    platform::read_password()
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::{
+        ImpersonateLoggedOnUser, LogonUserW, RevertToSelf, LOGON32_LOGON_INTERACTIVE,
+        LOGON32_PROVIDER_DEFAULT,
+    };
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_ECHO_INPUT, STD_INPUT_HANDLE,
+    };
+
+    pub struct Token(HANDLE);
+
+    // SAFETY: a token handle isn't tied to the thread that opened it
+    unsafe impl Send for Token {}
+    unsafe impl Sync for Token {}
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(Some(0)).collect()
+    }
+
+    pub fn logon(user: &str, domain: Option<&str>, password: &str) -> io::Result<Token> {
+        let user = wide(user);
+        let domain = domain.map(wide);
+        let mut password = wide(password);
+        let mut handle: HANDLE = std::ptr::null_mut();
+        // SAFETY: the strings are NUL-terminated; a null domain means the
+        // user is a principal name
+        let ok = unsafe {
+            LogonUserW(
+                user.as_ptr(),
+                domain.as_ref().map_or(std::ptr::null(), |d| d.as_ptr()),
+                password.as_ptr(),
+                LOGON32_LOGON_INTERACTIVE,
+                LOGON32_PROVIDER_DEFAULT,
+                &mut handle,
+            )
+        };
+        let result = if ok == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Token(handle))
+        };
+        password.fill(0);
+        result
+    }
+
+    impl Token {
+        pub fn impersonate(&self) -> io::Result<()> {
+            // SAFETY: the handle is a logon token owned by self
+            if unsafe { ImpersonateLoggedOnUser(self.0) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for Token {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    pub fn revert() {
+        unsafe { RevertToSelf() };
+    }
+
+    pub fn read_password() -> io::Result<String> {
+        // SAFETY: the standard input handle is only queried and restored
+        let input = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+        let mut mode = 0;
+        let console = unsafe { GetConsoleMode(input, &mut mode) } != 0;
+        if console {
+            unsafe { SetConsoleMode(input, mode & !ENABLE_ECHO_INPUT) };
+        }
+        let mut line = String::new();
+        let result = io::stdin().read_line(&mut line);
+        if console {
+            unsafe { SetConsoleMode(input, mode) };
+            println!();
+        }
+        result?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::io;
+
+    /// No token can be obtained here
+    pub enum Token {}
+
+    pub fn logon(_user: &str, _domain: Option<&str>, _password: &str) -> io::Result<Token> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    impl Token {
+        pub fn impersonate(&self) -> io::Result<()> {
+            match *self {}
+        }
+    }
+
+    pub fn revert() {}
+
+    pub fn read_password() -> io::Result<String> {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account() {
+        let account: Account = "CORP\\jdoe".parse().unwrap();
+        assert_eq!(account.domain.as_deref(), Some("CORP"));
+        assert_eq!(account.user, "jdoe");
+        assert_eq!(account.to_string(), "CORP\\jdoe");
+
+        let account: Account = "jdoe@corp.example.com".parse().unwrap();
+        assert_eq!(account.domain, None);
+        assert_eq!(account.to_string(), "jdoe@corp.example.com");
+
+        let account: Account = "jdoe".parse().unwrap();
+        assert_eq!(account.to_string(), ".\\jdoe");
+
+        for invalid in ["", "CORP\\", "\\jdoe", "CORP\\a\\b", "@corp"] {
+            assert!(
+                matches!(
+                    invalid.parse::<Account>(),
+                    Err(RunAsError::InvalidAccount(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_logon_unsupported() {
+        let account: Account = "jdoe".parse().unwrap();
+        assert!(matches!(
+            UserToken::logon(&account, "secret"),
+            Err(RunAsError::Unsupported)
+        ));
+    }
+}
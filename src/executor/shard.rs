@@ -0,0 +1,168 @@
+//! Deterministic partitioning of plan entries for sharded execution.
+//!
+//! Running `execute --shard 1/4` through `--shard 4/4` as separate processes
+//! (possibly on different machines) processes every entry exactly once. The
+//! assignment depends only on the entry's relative path, so all invocations
+//! agree without coordinating.
+
+use crate::models::paths::fnv1a;
+use crate::models::CleanupPlan;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// One shard out of `count`, numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shard {
+    /// 1-based shard number
+    pub index: usize,
+
+    /// Total number of shards
+    pub count: usize,
+}
+
+/// Errors parsing a `K/N` shard specification.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ShardParseError {
+    /// The value is not of the form `K/N`
+    #[error("expected shard as K/N (e.g. 2/4), got '{0}'")]
+    InvalidFormat(String),
+
+    /// K or N is out of range
+    #[error("shard index must be between 1 and {count} (got {index})")]
+    OutOfRange { index: usize, count: usize },
+}
+
+impl Shard {
+    /// Returns true if the entry with the given relative path belongs to this shard.
+    pub fn contains(&self, entry_path: &str) -> bool {
+        (fnv1a(&entry_path.replace('\\', "/")) % self.count as u64) as usize == self.index - 1
+    }
+
+    /// Returns a copy of the plan containing only this shard's entries.
+    pub fn apply(&self, plan: &CleanupPlan) -> CleanupPlan {
+        CleanupPlan {
+            version: plan.version.clone(),
+            created_at: plan.created_at,
+            base_path: plan.base_path.clone(),
+            roots: plan.roots.clone(),
+            entries: plan
+                .entries
+                .iter()
+                .filter(|e| self.contains(&e.path))
+                .cloned()
+                .collect(),
+            detection_stats: plan.detection_stats.clone(),
+            protections: plan.protections.clone(),
+        }
+    }
+}
+
+impl FromStr for Shard {
+    type Err = ShardParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ShardParseError::InvalidFormat(s.to_string());
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index: usize = index.trim().parse().map_err(|_| invalid())?;
+        let count: usize = count.trim().parse().map_err(|_| invalid())?;
+
+        if count == 0 || index == 0 || index > count {
+            return Err(ShardParseError::OutOfRange { index, count });
+        }
+
+        Ok(Self { index, count })
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CleanupAction, CleanupEntry};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_shard() {
+        assert_eq!("2/4".parse(), Ok(Shard { index: 2, count: 4 }));
+        assert_eq!("1/1".parse(), Ok(Shard { index: 1, count: 1 }));
+        assert!(matches!(
+            "5/4".parse::<Shard>(),
+            Err(ShardParseError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            "0/4".parse::<Shard>(),
+            Err(ShardParseError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            "2".parse::<Shard>(),
+            Err(ShardParseError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            "a/b".parse::<Shard>(),
+            Err(ShardParseError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_shards_partition_entries() {
+        let paths: Vec<String> = (0..200).map(|i| format!("project_{}/target", i)).collect();
+
+        for count in [1, 2, 3, 7] {
+            for path in &paths {
+                let owners = (1..=count)
+                    .filter(|&index| Shard { index, count }.contains(path))
+                    .count();
+                assert_eq!(owners, 1, "{} owned by {} shards", path, owners);
+            }
+        }
+
+        // Every shard gets some work
+        for index in 1..=3 {
+            let shard = Shard { index, count: 3 };
+            assert!(paths.iter().any(|p| shard.contains(p)));
+        }
+    }
+
+    #[test]
+    fn test_separators_do_not_change_assignment() {
+        for index in 1..=4 {
+            let shard = Shard { index, count: 4 };
+            assert_eq!(
+                shard.contains("a/b/node_modules"),
+                shard.contains("a\\b\\node_modules")
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_filters_plan() {
+        let mut plan = CleanupPlan::new(PathBuf::from("/test"));
+        for i in 0..20 {
+            plan.add_entry(CleanupEntry::new(
+                format!("dir_{}", i),
+                10,
+                "2025-11-19T12:00:00Z".to_string(),
+                CleanupAction::Delete,
+                "build_artifact".to_string(),
+                "Build artifact".to_string(),
+            ));
+        }
+
+        let total: usize = (1..=2)
+            .map(|index| Shard { index, count: 2 }.apply(&plan).entries.len())
+            .sum();
+        assert_eq!(total, 20);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Shard { index: 2, count: 4 }.to_string(), "2/4");
+    }
+}
@@ -0,0 +1,775 @@
+//! Filesystem snapshots taken before an execution.
+//!
+//! An execution can first snapshot the volume holding the plan's base path.
+//! The snapshot is recorded in the transaction log, and [`rollback`] later
+//! copies every entry the execution deleted back from it. Only the
+//! execution's own deletions are restored; other changes made to the volume
+//! since the snapshot are left alone.
+//!
+//! [`create`] picks the native mechanism for the volume:
+//!
+//! - Windows (with the `vss` feature): a Volume Shadow Copy, created through
+//!   WMI (`Win32_ShadowCopy`) by an elevated (administrator) process
+//! - Linux btrfs: a read-only snapshot of the mounted subvolume, kept in its
+//!   hidden `.megamaid-snapshots` directory (nested subvolumes aren't included),
+//!   which scans never walk into
+//! - Linux ZFS: a snapshot of the mounted dataset, read through `.zfs/snapshot`
+//!
+//! [`create_with_command`] runs a user-supplied command instead, for other
+//! snapshot tools. Snapshots are never deleted by megamaid; see
+//! [`VolumeSnapshot::instructions`]. Where no mechanism applies, [`create`]
+//! returns [`SnapshotError::Unsupported`].
+
+use crate::executor::engine::{OperationAction, OperationStatus};
+use crate::executor::transaction::TransactionLog;
+use crate::models::{strip_path_prefix, SNAPSHOT_DIR};
+use crate::scanner::mounts::parse_mount_table;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+use walkdir::WalkDir;
+
+/// Errors creating, listing or restoring from snapshots.
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    /// No snapshot mechanism applies to the volume
+    #[error(
+        "volume snapshots need Windows with the vss feature, btrfs or ZFS on Linux, \
+         or executor.snapshot_command"
+    )]
+    Unsupported,
+
+    /// The process isn't elevated
+    #[error("creating a volume snapshot requires administrator rights")]
+    AccessDenied,
+
+    /// The shadow copy service reported an error
+    #[error("volume shadow copy error: {0}")]
+    Vss(String),
+
+    /// A snapshot command failed or printed something unexpected
+    #[error("snapshot command failed: {0}")]
+    Command(String),
+
+    /// The transaction log has no snapshot to roll back to
+    #[error("transaction {0} has no snapshot")]
+    NoSnapshot(String),
+
+    /// The snapshot was deleted since the execution
+    #[error("snapshot {0} no longer exists")]
+    Missing(String),
+}
+
+/// How a snapshot was taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotKind {
+    /// Windows Volume Shadow Copy
+    #[default]
+    Vss,
+    /// btrfs read-only subvolume snapshot
+    Btrfs,
+    /// ZFS dataset snapshot
+    Zfs,
+    /// `executor.snapshot_command`
+    Command,
+}
+
+/// A snapshot of a volume, as recorded in the transaction log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeSnapshot {
+    #[serde(default)]
+    pub kind: SnapshotKind,
+
+    /// Snapshot identifier: the shadow copy GUID, the btrfs snapshot path,
+    /// the ZFS `dataset@name`, or the name given to the snapshot command
+    pub id: String,
+
+    /// Root of the snapshotted volume (e.g. `C:\` or a mount point)
+    pub volume: PathBuf,
+
+    /// Where the volume's contents can be read in the snapshot (e.g.
+    /// `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3` or
+    /// `/tank/.zfs/snapshot/megamaid-20250101-120000`)
+    #[serde(alias = "device_object")]
+    pub root: PathBuf,
+
+    #[serde(with = "crate::models::timestamp::tolerant")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl VolumeSnapshot {
+    /// Returns where `path` can be read from in the snapshot, or None if it
+    /// isn't on the snapshotted volume.
+    pub fn shadow_path(&self, path: &Path) -> Option<PathBuf> {
+        let relative = strip_path_prefix(path, &self.volume)?;
+        Some(self.root.join(relative))
+    }
+
+    /// Returns how to roll back the whole volume or free the snapshot with
+    /// the platform's own tools, one instruction per line.
+    pub fn instructions(&self) -> Vec<String> {
+        match self.kind {
+            SnapshotKind::Vss => vec![format!(
+                "Delete with: vssadmin delete shadows /shadow={}",
+                self.id
+            )],
+            SnapshotKind::Btrfs => vec![format!(
+                "Delete with: btrfs subvolume delete {}",
+                self.root.display()
+            )],
+            SnapshotKind::Zfs => vec![
+                format!(
+                    "Revert the whole dataset (discarding every later change) with: zfs rollback -r {}",
+                    self.id
+                ),
+                format!("Delete with: zfs destroy {}", self.id),
+            ],
+            SnapshotKind::Command => Vec::new(),
+        }
+    }
+}
+
+/// Outcome of a rollback.
+#[derive(Debug, Default)]
+pub struct RollbackSummary {
+    /// Entries copied back from the snapshot
+    pub restored: usize,
+
+    /// Bytes copied back
+    pub bytes: u64,
+
+    /// Entries left alone because something exists at their path again
+    pub existing: Vec<PathBuf>,
+
+    /// Entries that couldn't be restored, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Snapshots the volume whose root is `volume` with the platform's native mechanism.
+pub fn create(volume: &Path) -> Result<VolumeSnapshot, SnapshotError> {
+    platform::create(volume)
+}
+
+/// Snapshots `volume` by running `template` with the shell.
+///
+/// `{volume}` in the template is replaced by the quoted volume root and
+/// `{name}` by a snapshot name such as `megamaid-20250101-120000`. The
+/// command must print, as its last line, the directory where the snapshot's
+/// copy of the volume root can be read.
+pub fn create_with_command(template: &str, volume: &Path) -> Result<VolumeSnapshot, SnapshotError> {
+    let created_at = Utc::now();
+    let name = snapshot_name(created_at);
+    let command = template
+        .replace("{volume}", &shell_quote(&volume.to_string_lossy()))
+        .replace("{name}", &name);
+
+    #[cfg(windows)]
+    let output = run(Command::new("cmd").args(["/C", &command]))?;
+    #[cfg(not(windows))]
+    let output = run(Command::new("sh").args(["-c", &command]))?;
+
+    let root = output
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .filter(|root| root.is_dir())
+        .ok_or_else(|| {
+            SnapshotError::Command(format!(
+                "expected the snapshot directory as the last line of output, got: {}",
+                output.trim()
+            ))
+        })?;
+
+    Ok(VolumeSnapshot {
+        kind: SnapshotKind::Command,
+        id: name,
+        volume: volume.to_path_buf(),
+        root,
+        created_at,
+    })
+}
+
+/// Returns whether `snapshot` still exists.
+pub fn exists(snapshot: &VolumeSnapshot) -> Result<bool, SnapshotError> {
+    match snapshot.kind {
+        SnapshotKind::Vss => Ok(existing_ids()?.contains(&snapshot.id)),
+        SnapshotKind::Btrfs | SnapshotKind::Zfs | SnapshotKind::Command => {
+            Ok(snapshot.root.is_dir())
+        }
+    }
+}
+
+/// Returns the IDs of the shadow copies that currently exist.
+pub fn existing_ids() -> Result<Vec<String>, SnapshotError> {
+    platform::existing_ids()
+}
+
+/// Restores every entry the logged execution deleted from its snapshot.
+///
+/// Entries whose path exists again are skipped rather than overwritten.
+/// Moves to a backup directory or the recycle bin are not touched; they can
+/// be undone from there.
+pub fn rollback(log: &TransactionLog) -> Result<RollbackSummary, SnapshotError> {
+    let snapshot = log
+        .snapshot
+        .as_ref()
+        .ok_or_else(|| SnapshotError::NoSnapshot(log.execution_id.clone()))?;
+    if !exists(snapshot)? {
+        return Err(SnapshotError::Missing(snapshot.id.clone()));
+    }
+
+    Ok(restore_deleted(snapshot, log))
+}
+
+/// Copies the log's deleted entries back from `snapshot`.
+fn restore_deleted(snapshot: &VolumeSnapshot, log: &TransactionLog) -> RollbackSummary {
+    let delete = format!("{:?}", OperationAction::Delete);
+    let success = format!("{:?}", OperationStatus::Success);
+    let mut summary = RollbackSummary::default();
+
+    for op in &log.operations {
+        if op.action != delete || op.status != success {
+            continue;
+        }
+
+        let path = PathBuf::from(&op.path);
+        if path.symlink_metadata().is_ok() {
+            summary.existing.push(path);
+            continue;
+        }
+        let Some(source) = snapshot.shadow_path(&path) else {
+            summary
+                .failed
+                .push((path, "not on the snapshotted volume".to_string()));
+            continue;
+        };
+
+        match copy_tree(&source, &path) {
+            Ok(bytes) => {
+                summary.restored += 1;
+                summary.bytes += bytes;
+            }
+            Err(e) => summary.failed.push((path, e.to_string())),
+        }
+    }
+
+    summary
+}
+
+/// Copies the file or directory tree at `source` to `target`; returns the bytes copied.
+///
+/// Symlinks and junctions are recreated as links to the same target, never
+/// followed.
+fn copy_tree(source: &Path, target: &Path) -> io::Result<u64> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut bytes = 0u64;
+    for entry in WalkDir::new(source).follow_links(false) {
+        let entry = entry?;
+        let relative = entry
+            .path()
+            .strip_prefix(source)
+            .map_err(io::Error::other)?;
+        let destination = if relative.as_os_str().is_empty() {
+            target.to_path_buf()
+        } else {
+            target.join(relative)
+        };
+
+        if entry.file_type().is_symlink() {
+            copy_link(entry.path(), entry.file_type(), &destination)?;
+        } else if entry.file_type().is_dir() {
+            fs::create_dir_all(&destination)?;
+        } else if entry.file_type().is_file() {
+            bytes += fs::copy(entry.path(), &destination)?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Creates a link at `destination` pointing where the link `source` points.
+#[cfg(unix)]
+fn copy_link(source: &Path, _file_type: fs::FileType, destination: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, destination)
+}
+
+/// Creates a link at `destination` pointing where the link `source` points.
+///
+/// Junctions come back as directory symlinks, which need the
+/// `SeCreateSymbolicLinkPrivilege` (or developer mode) like any symlink.
+#[cfg(windows)]
+fn copy_link(source: &Path, file_type: fs::FileType, destination: &Path) -> io::Result<()> {
+    use std::os::windows::fs::FileTypeExt;
+
+    let target = fs::read_link(source)?;
+    if file_type.is_symlink_dir() {
+        std::os::windows::fs::symlink_dir(target, destination)
+    } else {
+        std::os::windows::fs::symlink_file(target, destination)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_link(source: &Path, _file_type: fs::FileType, _destination: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("can't recreate the link {}", source.display()),
+    ))
+}
+
+#[cfg(all(windows, feature = "vss"))]
+mod platform {
+    use super::*;
+
+    /// Offset added to `Create` return codes used as the script's exit code,
+    /// so they can't be confused with PowerShell's own exit code 1
+    const EXIT_CODE_BASE: i32 = 100;
+
+    /// Runs a PowerShell script and returns its standard output.
+    fn powershell(script: &str) -> Result<String, SnapshotError> {
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .output()
+            .map_err(|e| SnapshotError::Vss(format!("failed to run PowerShell: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(match output.status.code() {
+                Some(code) if code == EXIT_CODE_BASE + 1 => SnapshotError::AccessDenied,
+                Some(code) if code > EXIT_CODE_BASE => {
+                    SnapshotError::Vss(create_error(code - EXIT_CODE_BASE))
+                }
+                _ => SnapshotError::Vss(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    pub fn create(volume: &Path) -> Result<VolumeSnapshot, SnapshotError> {
+        let volume_arg = volume.to_string_lossy().replace('\'', "''");
+        let script = format!(
+            "$r = Invoke-CimMethod -ClassName Win32_ShadowCopy -MethodName Create \
+             -Arguments @{{Volume='{volume_arg}'; Context='ClientAccessible'}}; \
+             if ($r.ReturnValue -ne 0) {{ exit ({EXIT_CODE_BASE} + $r.ReturnValue) }}; \
+             $s = Get-CimInstance Win32_ShadowCopy | Where-Object {{ $_.ID -eq $r.ShadowID }}; \
+             Write-Output \"$($s.ID)|$($s.DeviceObject)\""
+        );
+        let output = powershell(&script)?;
+        let (id, device_object) = parse_created(&output)?;
+
+        Ok(VolumeSnapshot {
+            kind: SnapshotKind::Vss,
+            id,
+            volume: volume.to_path_buf(),
+            root: PathBuf::from(device_object),
+            created_at: Utc::now(),
+        })
+    }
+
+    pub fn existing_ids() -> Result<Vec<String>, SnapshotError> {
+        let output = powershell("Get-CimInstance Win32_ShadowCopy | ForEach-Object { $_.ID }")?;
+        Ok(output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    pub fn create(volume: &Path) -> Result<VolumeSnapshot, SnapshotError> {
+        let mounts = fs::read_to_string("/proc/self/mounts")
+            .map_err(|e| SnapshotError::Command(format!("failed to read mounts: {}", e)))?;
+        let created_at = Utc::now();
+        let name = snapshot_name(created_at);
+
+        match mount_entry(&mounts, volume) {
+            Some((fs_type, _)) if fs_type == "btrfs" => {
+                // Scans never walk into this directory
+                let dir = volume.join(SNAPSHOT_DIR);
+                fs::create_dir_all(&dir).map_err(|e| {
+                    SnapshotError::Command(format!("failed to create {}: {}", dir.display(), e))
+                })?;
+                let root = dir.join(&name);
+                run(Command::new("btrfs")
+                    .args(["subvolume", "snapshot", "-r"])
+                    .arg(volume)
+                    .arg(&root))?;
+
+                Ok(VolumeSnapshot {
+                    kind: SnapshotKind::Btrfs,
+                    id: root.to_string_lossy().into_owned(),
+                    volume: volume.to_path_buf(),
+                    root,
+                    created_at,
+                })
+            }
+            Some((fs_type, dataset)) if fs_type == "zfs" => {
+                let id = format!("{}@{}", dataset, name);
+                run(Command::new("zfs").args(["snapshot", &id]))?;
+
+                Ok(VolumeSnapshot {
+                    kind: SnapshotKind::Zfs,
+                    id,
+                    volume: volume.to_path_buf(),
+                    root: volume.join(".zfs").join("snapshot").join(&name),
+                    created_at,
+                })
+            }
+            _ => Err(SnapshotError::Unsupported),
+        }
+    }
+
+    pub fn existing_ids() -> Result<Vec<String>, SnapshotError> {
+        Err(SnapshotError::Unsupported)
+    }
+}
+
+#[cfg(not(any(all(windows, feature = "vss"), target_os = "linux")))]
+mod platform {
+    use super::*;
+
+    pub fn create(_volume: &Path) -> Result<VolumeSnapshot, SnapshotError> {
+        Err(SnapshotError::Unsupported)
+    }
+
+    pub fn existing_ids() -> Result<Vec<String>, SnapshotError> {
+        Err(SnapshotError::Unsupported)
+    }
+}
+
+/// Runs a snapshot command and returns its standard output.
+fn run(command: &mut Command) -> Result<String, SnapshotError> {
+    let output = command
+        .output()
+        .map_err(|e| SnapshotError::Command(format!("{:?}: {}", command.get_program(), e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SnapshotError::Command(format!(
+            "{:?} exited with {}: {}",
+            command.get_program(),
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Names a snapshot after its creation time.
+fn snapshot_name(created_at: DateTime<Utc>) -> String {
+    format!("megamaid-{}", created_at.format("%Y%m%d-%H%M%S"))
+}
+
+/// Quotes `value` as a single shell word.
+fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", value)
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Finds the filesystem type and source (device or dataset) mounted at
+/// `mount_point` in `/proc/mounts` contents; the last mount wins.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn mount_entry(mounts: &str, mount_point: &Path) -> Option<(String, String)> {
+    parse_mount_table(mounts)
+        .filter(|mount| mount.mount_point == mount_point)
+        .last()
+        .map(|mount| {
+            let source = mount.source.to_string_lossy().into_owned();
+            (mount.fs_type.to_string(), source)
+        })
+}
+
+/// Parses the `ID|DeviceObject` line printed after creating a snapshot.
+#[cfg_attr(not(all(windows, feature = "vss")), allow(dead_code))]
+fn parse_created(output: &str) -> Result<(String, String), SnapshotError> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().split_once('|'))
+        .find(|(id, device)| !id.is_empty() && !device.is_empty())
+        .map(|(id, device)| (id.to_string(), device.to_string()))
+        .ok_or_else(|| SnapshotError::Vss(format!("unexpected output: {}", output.trim())))
+}
+
+/// Describes a `Win32_ShadowCopy.Create` return code.
+#[cfg_attr(not(all(windows, feature = "vss")), allow(dead_code))]
+fn create_error(code: i32) -> String {
+    let reason = match code {
+        2 => "invalid argument",
+        3 => "volume not found",
+        4 => "volume not supported",
+        5 => "unsupported shadow copy context",
+        6 => "insufficient shadow copy storage",
+        7 => "volume is in use",
+        8 => "maximum number of shadow copies reached",
+        9 => "another shadow copy operation is in progress",
+        10 => "shadow copy provider vetoed the operation",
+        11 => "shadow copy provider not registered",
+        12 => "shadow copy provider failure",
+        _ => "unknown error",
+    };
+    format!("snapshot creation failed ({}): {}", code, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::transaction::{LoggedOperation, TransactionLogger, TransactionOptions};
+    use tempfile::TempDir;
+
+    fn logged(path: &Path, action: OperationAction, status: OperationStatus) -> LoggedOperation {
+        LoggedOperation {
+            path: path.to_string_lossy().to_string(),
+            entry_id: None,
+            action: format!("{:?}", action),
+            status: format!("{:?}", status),
+            size_freed: None,
+            error: None,
+            timestamp: Utc::now(),
+            streams: Vec::new(),
+            backup_path: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_created() {
+        let output = "\r\n{A1B2}|\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3\r\n";
+        let (id, device) = parse_created(output).unwrap();
+        assert_eq!(id, "{A1B2}");
+        assert_eq!(
+            device,
+            "\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3"
+        );
+
+        assert!(matches!(parse_created("|\n"), Err(SnapshotError::Vss(_))));
+        assert!(create_error(6).contains("insufficient"));
+    }
+
+    #[test]
+    fn test_restore_deleted_entries() {
+        // A directory stands in for the snapshot's device path
+        let live = TempDir::new().unwrap();
+        let shadow = TempDir::new().unwrap();
+        fs::create_dir_all(shadow.path().join("cache/sub")).unwrap();
+        fs::write(shadow.path().join("cache/sub/a.bin"), "a".repeat(100)).unwrap();
+        fs::write(shadow.path().join("old.log"), "b".repeat(20)).unwrap();
+        fs::write(shadow.path().join("kept.txt"), "c").unwrap();
+        fs::write(live.path().join("kept.txt"), "new").unwrap();
+
+        let snapshot = VolumeSnapshot {
+            kind: SnapshotKind::Vss,
+            id: "{1}".to_string(),
+            volume: live.path().to_path_buf(),
+            root: shadow.path().to_path_buf(),
+            created_at: Utc::now(),
+        };
+        assert_eq!(
+            snapshot.shadow_path(&live.path().join("old.log")),
+            Some(shadow.path().join("old.log"))
+        );
+
+        let temp = TempDir::new().unwrap();
+        let mut logger = TransactionLogger::new(
+            &temp.path().join("plan.yaml"),
+            temp.path().join("log.yaml"),
+            TransactionOptions {
+                dry_run: false,
+                backup_dir: None,
+                use_recycle_bin: false,
+                fail_fast: false,
+            },
+        );
+        logger.record_snapshot(snapshot.clone());
+        logger.write().unwrap();
+        let mut log = TransactionLogger::read(&temp.path().join("log.yaml")).unwrap();
+        assert_eq!(log.snapshot.as_ref(), Some(&snapshot));
+
+        log.operations = vec![
+            logged(
+                &live.path().join("cache"),
+                OperationAction::Delete,
+                OperationStatus::Success,
+            ),
+            logged(
+                &live.path().join("old.log"),
+                OperationAction::Delete,
+                OperationStatus::Success,
+            ),
+            logged(
+                &live.path().join("kept.txt"),
+                OperationAction::Delete,
+                OperationStatus::Success,
+            ),
+            logged(
+                &live.path().join("recycled.txt"),
+                OperationAction::MoveToRecycleBin,
+                OperationStatus::Success,
+            ),
+            logged(
+                &live.path().join("failed.txt"),
+                OperationAction::Delete,
+                OperationStatus::Failed,
+            ),
+        ];
+
+        let summary = restore_deleted(&snapshot, &log);
+
+        assert_eq!(summary.restored, 2);
+        assert_eq!(summary.bytes, 120);
+        assert_eq!(summary.existing, vec![live.path().join("kept.txt")]);
+        assert!(summary.failed.is_empty());
+        assert_eq!(
+            fs::read_to_string(live.path().join("cache/sub/a.bin")).unwrap(),
+            "a".repeat(100)
+        );
+        assert_eq!(
+            fs::read_to_string(live.path().join("kept.txt")).unwrap(),
+            "new"
+        );
+        assert!(!live.path().join("recycled.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_recreates_links() {
+        let live = TempDir::new().unwrap();
+        let shadow = TempDir::new().unwrap();
+        fs::create_dir_all(shadow.path().join("cache")).unwrap();
+        fs::write(shadow.path().join("cache/a.bin"), "a").unwrap();
+        std::os::unix::fs::symlink("a.bin", shadow.path().join("cache/link")).unwrap();
+        std::os::unix::fs::symlink("/nowhere", shadow.path().join("cache/dangling")).unwrap();
+
+        let snapshot = VolumeSnapshot {
+            kind: SnapshotKind::Btrfs,
+            id: "1".to_string(),
+            volume: live.path().to_path_buf(),
+            root: shadow.path().to_path_buf(),
+            created_at: Utc::now(),
+        };
+        let temp = TempDir::new().unwrap();
+        let logger = TransactionLogger::new(
+            &temp.path().join("plan.yaml"),
+            temp.path().join("log.yaml"),
+            TransactionOptions {
+                dry_run: false,
+                backup_dir: None,
+                use_recycle_bin: false,
+                fail_fast: false,
+            },
+        );
+        logger.write().unwrap();
+        let mut log = TransactionLogger::read(&temp.path().join("log.yaml")).unwrap();
+        log.operations = vec![logged(
+            &live.path().join("cache"),
+            OperationAction::Delete,
+            OperationStatus::Success,
+        )];
+
+        let summary = restore_deleted(&snapshot, &log);
+
+        assert_eq!(summary.restored, 1);
+        assert!(summary.failed.is_empty());
+        let link = live.path().join("cache/link");
+        assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("a.bin"));
+        assert_eq!(fs::read_to_string(&link).unwrap(), "a");
+        assert_eq!(
+            fs::read_link(live.path().join("cache/dangling")).unwrap(),
+            PathBuf::from("/nowhere")
+        );
+    }
+
+    #[test]
+    fn test_snapshot_recorded_before_kinds_existed() {
+        let yaml = "id: '{A1B2}'\nvolume: 'C:\\'\ndevice_object: '\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3'\ncreated_at: 2025-01-01T00:00:00Z\n";
+        let snapshot: VolumeSnapshot = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(snapshot.kind, SnapshotKind::Vss);
+        assert_eq!(
+            snapshot.root,
+            PathBuf::from("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3")
+        );
+        assert!(snapshot.instructions()[0].contains("/shadow={A1B2}"));
+    }
+
+    #[test]
+    fn test_mount_entry() {
+        let mounts = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+tank/home /home zfs rw,xattr 0 0
+tank /tank zfs rw,xattr 0 0
+/dev/sdb1 /mnt/my\\040data btrfs rw,subvol=/data 0 0
+/dev/sdc1 /home ext4 rw 0 0
+";
+        assert_eq!(
+            mount_entry(mounts, Path::new("/mnt/my data")),
+            Some(("btrfs".to_string(), "/dev/sdb1".to_string()))
+        );
+        // Later mounts hide earlier ones at the same point
+        assert_eq!(
+            mount_entry(mounts, Path::new("/home")),
+            Some(("ext4".to_string(), "/dev/sdc1".to_string()))
+        );
+        assert_eq!(
+            mount_entry(mounts, Path::new("/tank")),
+            Some(("zfs".to_string(), "tank".to_string()))
+        );
+        assert_eq!(mount_entry(mounts, Path::new("/home/user")), None);
+    }
+
+    #[test]
+    fn test_not_a_snapshot_volume() {
+        // A plain directory is never a mount point with a snapshot mechanism
+        let temp = TempDir::new().unwrap();
+        assert!(matches!(
+            create(temp.path()),
+            Err(SnapshotError::Unsupported)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_with_command() {
+        let volume = TempDir::new().unwrap();
+        let store = TempDir::new().unwrap();
+        fs::write(volume.path().join("file.txt"), "data").unwrap();
+
+        let template = format!(
+            "cp -R {{volume}} {store}/{{name}} && echo created && echo {store}/{{name}}",
+            store = store.path().display()
+        );
+        let snapshot = create_with_command(&template, volume.path()).unwrap();
+
+        assert_eq!(snapshot.kind, SnapshotKind::Command);
+        assert!(snapshot.id.starts_with("megamaid-"));
+        assert_eq!(snapshot.root, store.path().join(&snapshot.id));
+        assert!(exists(&snapshot).unwrap());
+        assert_eq!(
+            snapshot.shadow_path(&volume.path().join("file.txt")),
+            Some(snapshot.root.join("file.txt"))
+        );
+        assert!(snapshot.instructions().is_empty());
+
+        assert!(matches!(
+            create_with_command("echo not-a-directory", volume.path()),
+            Err(SnapshotError::Command(_))
+        ));
+        assert!(matches!(
+            create_with_command("exit 3", volume.path()),
+            Err(SnapshotError::Command(_))
+        ));
+    }
+}
@@ -1,9 +1,19 @@
 //! Transaction logging for execution audit trails.
 
-use crate::executor::engine::{ExecutionResult, OperationResult};
+use crate::executor::engine::{ExecutionResult, OperationResult, OperationStatus, SpaceByAction};
+use crate::executor::operation_error::{self, OperationError};
+use crate::executor::shard::Shard;
+use crate::executor::snapshot::VolumeSnapshot;
+use crate::models::{timestamp, CleanupEntry, DataStream, PathKey};
+use crate::planner::compression::{is_compressed, open_reader, CompressedWriter};
+use crate::planner::encryption::{self, EncryptingWriter, EncryptionKey};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::io::Write;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
@@ -13,13 +23,46 @@ pub struct TransactionLog {
     pub version: String,
     pub execution_id: String,
     pub plan_file: PathBuf,
+    #[serde(with = "timestamp::tolerant")]
     pub started_at: DateTime<Utc>,
+    #[serde(default, with = "timestamp::tolerant::option")]
     pub completed_at: Option<DateTime<Utc>>,
     pub status: TransactionStatus,
     pub mode: String,
     pub options: TransactionOptions,
     pub operations: Vec<LoggedOperation>,
     pub summary: Option<ExecutionSummaryLog>,
+    /// Shard of the plan this execution covered, if sharded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+    /// How the user approved a batch execution, if approval was required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation: Option<Confirmation>,
+    /// Volume snapshot taken before the execution, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<VolumeSnapshot>,
+}
+
+/// Approval given before a batch execution started.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Confirmation {
+    pub method: ConfirmationMethod,
+    #[serde(with = "timestamp::tolerant")]
+    pub confirmed_at: DateTime<Utc>,
+    /// Entries the user approved for deletion
+    pub entries: usize,
+    /// Total size of the approved entries in bytes
+    pub bytes: u64,
+}
+
+/// How an execution was confirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmationMethod {
+    /// The user typed the confirmation word at the prompt
+    Prompt,
+    /// The prompt was skipped with `--yes`
+    Flag,
 }
 
 /// Status of a transaction.
@@ -30,6 +73,8 @@ pub enum TransactionStatus {
     Completed,
     Failed,
     Aborted,
+    /// Stopped on request; can be resumed from the log
+    Paused,
 }
 
 /// Options used for execution.
@@ -45,11 +90,23 @@ pub struct TransactionOptions {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LoggedOperation {
     pub path: String,
+    /// Stable ID of the plan entry (see [`CleanupEntry::id`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_id: Option<String>,
     pub action: String,
     pub status: String,
     pub size_freed: Option<u64>,
-    pub error: Option<String>,
+    /// Why the operation failed, or why the entry was skipped
+    #[serde(default, deserialize_with = "operation_error::deserialize_logged")]
+    pub error: Option<OperationError>,
+    #[serde(with = "timestamp::tolerant")]
     pub timestamp: DateTime<Utc>,
+    /// Alternate data streams removed along with the entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub streams: Vec<DataStream>,
+    /// Where the entry was moved to in backup mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<PathBuf>,
 }
 
 /// Summary of execution in the log.
@@ -60,13 +117,78 @@ pub struct ExecutionSummaryLog {
     pub failed: usize,
     pub skipped: usize,
     pub space_freed: u64,
+    /// Bytes by what happened to them; zero in logs written before it was
+    /// recorded
+    #[serde(default)]
+    pub space_by_action: SpaceByAction,
     pub duration_seconds: f64,
 }
 
+/// Entries an earlier execution removed, for skipping them in a later plan,
+/// or failed to remove, for retrying only those.
+///
+/// Entries are matched by their stable ID; operations logged before IDs were
+/// recorded are matched by path instead.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessedEntries {
+    ids: HashSet<String>,
+    paths: HashSet<PathKey>,
+}
+
+impl ProcessedEntries {
+    /// Collects the successful operations of `log`.
+    ///
+    /// Failed, skipped and dry-run operations are left out, so their entries
+    /// are processed again.
+    pub fn from_log(log: &TransactionLog) -> Self {
+        Self::with_status(log, OperationStatus::Success)
+    }
+
+    /// Collects the failed operations of `log`.
+    pub fn failed(log: &TransactionLog) -> Self {
+        Self::with_status(log, OperationStatus::Failed)
+    }
+
+    fn with_status(log: &TransactionLog, status: OperationStatus) -> Self {
+        let mut processed = Self::default();
+        let status = format!("{:?}", status);
+        for op in log.operations.iter().filter(|op| op.status == status) {
+            match &op.entry_id {
+                Some(id) => {
+                    processed.ids.insert(id.clone());
+                }
+                None => {
+                    processed.paths.insert(PathKey::new(Path::new(&op.path)));
+                }
+            }
+        }
+        processed
+    }
+
+    /// Returns true if `entry`, relative to `root`, was processed.
+    pub fn contains(&self, root: &Path, entry: &CleanupEntry) -> bool {
+        self.ids.contains(&entry.id())
+            || (!self.paths.is_empty()
+                && self.paths.contains(&PathKey::new(&root.join(&entry.path))))
+    }
+
+    /// Returns the number of processed entries.
+    pub fn len(&self) -> usize {
+        self.ids.len() + self.paths.len()
+    }
+
+    /// Returns true if no entry was processed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// Logger for transaction operations.
 pub struct TransactionLogger {
     log_path: PathBuf,
     log: TransactionLog,
+    /// Journal of the operations appended since the log was last written
+    journal: Option<Journal>,
 }
 
 impl TransactionLogger {
@@ -87,9 +209,98 @@ impl TransactionLogger {
             options,
             operations: Vec::new(),
             summary: None,
+            shard: None,
+            confirmation: None,
+            snapshot: None,
         };
 
-        Self { log_path, log }
+        Self {
+            log_path,
+            log,
+            journal: None,
+        }
+    }
+
+    /// Continue an interrupted execution recorded in an existing log.
+    ///
+    /// New operations are appended to the same log and the final summary
+    /// includes the earlier runs. Completed logs cannot be resumed.
+    pub fn resume(log_path: PathBuf) -> std::io::Result<Self> {
+        let mut log = Self::read(&log_path)?;
+        if log.status == TransactionStatus::Completed {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "transaction already completed; nothing to resume",
+            ));
+        }
+
+        log.status = TransactionStatus::InProgress;
+        log.completed_at = None;
+        Ok(Self {
+            log_path,
+            log,
+            journal: None,
+        })
+    }
+
+    /// Get the shard this transaction covers, if sharded.
+    pub fn shard(&self) -> Option<Shard> {
+        self.log.shard
+    }
+
+    /// Get the plan file this log belongs to.
+    pub fn plan_file(&self) -> &Path {
+        &self.log.plan_file
+    }
+
+    /// Get the options the transaction was started with.
+    pub fn options(&self) -> &TransactionOptions {
+        &self.log.options
+    }
+
+    /// Paths already handled by this transaction, keyed by [`PathKey`] so a
+    /// resumed plan matches them under the platform's case rules.
+    ///
+    /// Failed operations are excluded so a resumed run retries them, and
+    /// dry-run operations only count in a dry-run transaction.
+    pub fn processed_paths(&self) -> HashSet<PathKey> {
+        let done = |status: &str| match status {
+            "Success" | "Skipped" => true,
+            "DryRun" => self.log.options.dry_run,
+            _ => false,
+        };
+        self.log
+            .operations
+            .iter()
+            .filter(|op| done(&op.status))
+            .map(|op| PathKey::new(Path::new(&op.path)))
+            .collect()
+    }
+
+    /// Record that this execution covers a single shard of the plan.
+    pub fn with_shard(mut self, shard: Shard) -> Self {
+        self.log.shard = Some(shard);
+        self
+    }
+
+    /// Record how the execution was approved.
+    pub fn record_confirmation(&mut self, method: ConfirmationMethod, entries: usize, bytes: u64) {
+        self.log.confirmation = Some(Confirmation {
+            method,
+            confirmed_at: Utc::now(),
+            entries,
+            bytes,
+        });
+    }
+
+    /// Record the volume snapshot taken before the execution.
+    pub fn record_snapshot(&mut self, snapshot: VolumeSnapshot) {
+        self.log.snapshot = Some(snapshot);
+    }
+
+    /// Get the volume snapshot taken before the execution, if any.
+    pub fn snapshot(&self) -> Option<&VolumeSnapshot> {
+        self.log.snapshot.as_ref()
     }
 
     /// Get the execution ID.
@@ -101,60 +312,274 @@ impl TransactionLogger {
     pub fn log_operation(&mut self, operation: &OperationResult) {
         self.log.operations.push(LoggedOperation {
             path: operation.path.to_string_lossy().to_string(),
+            entry_id: operation.entry_id.clone(),
             action: format!("{:?}", operation.action),
             status: format!("{:?}", operation.status),
             size_freed: operation.size_freed,
             error: operation.error.clone(),
             timestamp: operation.timestamp.into(),
+            streams: operation.streams.clone(),
+            backup_path: operation.backup_path.clone(),
         });
     }
 
+    /// Log an operation of a running execution and record it on disk.
+    ///
+    /// The first operation writes the whole log; later ones are appended to
+    /// its journal (see [`Self::journal_path`]), one line each, which
+    /// [`Self::read`] merges back in. A run that is killed or crashes keeps
+    /// the record of everything done, so it can be resumed from the log.
+    pub fn append_operation(&mut self, operation: &OperationResult) -> std::io::Result<()> {
+        self.log_operation(operation);
+        let index = self.log.operations.len() - 1;
+        match &mut self.journal {
+            Some(journal) => journal.append(index, &self.log.operations[index]),
+            None => {
+                // Whatever an earlier run left in the journal is in the log now
+                self.write()?;
+                self.journal = Some(Journal::create(
+                    &Self::journal_path(&self.log_path),
+                    &self.log.execution_id,
+                    encryption::write_key(),
+                )?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Save the log of an execution that stopped without a result, such as
+    /// one forced to exit; it can still be resumed.
+    pub fn abort(&mut self) -> std::io::Result<()> {
+        self.log.completed_at = Some(Utc::now());
+        self.log.status = TransactionStatus::Aborted;
+        self.write_final()
+    }
+
     /// Finalize the transaction log with execution results.
+    ///
+    /// When resuming, the totals cover the earlier runs too: each entry
+    /// counts once, with the result of its last attempt.
     pub fn finalize(
         &mut self,
         result: &ExecutionResult,
         status: TransactionStatus,
     ) -> std::io::Result<()> {
+        let previous = self.log.summary.take();
+        let mut last = HashMap::new();
+        for op in &self.log.operations {
+            last.insert(PathKey::new(Path::new(&op.path)), op);
+        }
+
+        let count = |status: &str| last.values().filter(|op| op.status == status).count();
+        let mut space_by_action = SpaceByAction::default();
+        for op in last.values() {
+            let Some(size) = op.size_freed.filter(|_| op.status != "Skipped") else {
+                continue;
+            };
+            let total = match op.action.as_str() {
+                "Delete" => &mut space_by_action.deleted,
+                "MoveToRecycleBin" => &mut space_by_action.recycled,
+                "MoveToBackup" => &mut space_by_action.backed_up,
+                _ => continue,
+            };
+            *total = total.saturating_add(size);
+        }
+        // The log doesn't hold the sizes of skipped entries, and resumed runs
+        // never attempt those again, so their bytes add up
+        space_by_action.skipped = previous
+            .as_ref()
+            .map_or(0, |p| p.space_by_action.skipped)
+            .saturating_add(result.summary.space_by_action.skipped);
+
         self.log.completed_at = Some(Utc::now());
         self.log.status = status;
         self.log.summary = Some(ExecutionSummaryLog {
-            total_operations: result.summary.total_operations,
-            successful: result.summary.successful,
-            failed: result.summary.failed,
-            skipped: result.summary.skipped,
-            space_freed: result.summary.space_freed,
-            duration_seconds: result.summary.duration.as_secs_f64(),
+            total_operations: last.len(),
+            successful: count("Success") + count("DryRun"),
+            failed: count("Failed"),
+            skipped: count("Skipped"),
+            space_freed: last
+                .values()
+                .filter_map(|op| op.size_freed)
+                .fold(0, u64::saturating_add),
+            space_by_action,
+            duration_seconds: previous.map_or(0.0, |p| p.duration_seconds)
+                + result.summary.duration.as_secs_f64(),
         });
 
-        self.write()
+        self.write_final()
+    }
+
+    /// Write the whole log, which then holds everything in the journal, and
+    /// remove the journal.
+    fn write_final(&mut self) -> std::io::Result<()> {
+        self.write()?;
+        self.journal = None;
+        match std::fs::remove_file(Self::journal_path(&self.log_path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Path of the journal a running execution appends its operations to,
+    /// beside the log: `<log>.journal`.
+    pub fn journal_path(log_path: &Path) -> PathBuf {
+        let mut name = log_path.as_os_str().to_owned();
+        name.push(".journal");
+        PathBuf::from(name)
     }
 
     /// Write the transaction log to disk.
+    ///
+    /// Logs whose path ends in `.zst` are zstd-compressed.
     pub fn write(&self) -> std::io::Result<()> {
-        let yaml_content = serde_yaml::to_string(&self.log).map_err(std::io::Error::other)?;
-
-        // Atomic write: write to temp file, then rename
+        // Atomic write: stream to temp file, then rename
         let temp_path = self.log_path.with_extension("tmp");
-        let mut file = std::fs::File::create(&temp_path)?;
-        file.write_all(yaml_content.as_bytes())?;
-        file.sync_all()?;
+        let mut writer = CompressedWriter::create(&temp_path, is_compressed(&self.log_path))?;
+        serde_yaml::to_writer(&mut writer, &self.log).map_err(std::io::Error::other)?;
+        writer.finish()?;
 
         std::fs::rename(temp_path, &self.log_path)?;
         Ok(())
     }
 
-    /// Read a transaction log from disk.
+    /// Read a transaction log from disk, decompressing `.zst` logs.
+    ///
+    /// Operations in the journal of a run that didn't finish are included.
     pub fn read(path: &Path) -> std::io::Result<TransactionLog> {
-        let content = std::fs::read_to_string(path)?;
-        serde_yaml::from_str(&content)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        let reader = open_reader(path)?;
+        let mut log = serde_yaml::from_reader(reader)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        merge_journal(
+            &mut log,
+            &Self::journal_path(path),
+            encryption::read_key().as_ref(),
+        )?;
+        Ok(log)
     }
 }
 
+/// First line of a journal, naming the execution it belongs to.
+#[derive(Serialize, Deserialize)]
+struct JournalHeader {
+    execution_id: String,
+}
+
+/// Line of a journal: an operation and its position in the log.
+#[derive(Serialize, Deserialize)]
+struct JournalRecord {
+    index: usize,
+    operation: LoggedOperation,
+}
+
+/// File a running execution appends its operations to as JSON lines, each
+/// written in full as soon as it's done.
+///
+/// With encryption on, each line is encrypted on its own and base64-encoded.
+struct Journal {
+    file: File,
+    key: Option<EncryptionKey>,
+}
+
+impl Journal {
+    /// Creates (or truncates) the journal at `path` for `execution_id`.
+    fn create(
+        path: &Path,
+        execution_id: &str,
+        key: Option<EncryptionKey>,
+    ) -> std::io::Result<Self> {
+        let mut journal = Self {
+            file: File::create(path)?,
+            key,
+        };
+        journal.write_line(&JournalHeader {
+            execution_id: execution_id.to_string(),
+        })?;
+        Ok(journal)
+    }
+
+    /// Appends the operation at `index` of the log.
+    fn append(&mut self, index: usize, operation: &LoggedOperation) -> std::io::Result<()> {
+        self.write_line(&JournalRecord {
+            index,
+            operation: operation.clone(),
+        })
+    }
+
+    fn write_line(&mut self, value: &impl Serialize) -> std::io::Result<()> {
+        let json = serde_json::to_vec(value).map_err(std::io::Error::other)?;
+        let mut line = match &self.key {
+            Some(key) => {
+                let mut writer = EncryptingWriter::new(Vec::new(), key)?;
+                writer.write_all(&json)?;
+                STANDARD.encode(writer.finish()?).into_bytes()
+            }
+            None => json,
+        };
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.flush()
+    }
+}
+
+/// Adds the operations recorded in the journal at `path` to `log`, if the
+/// journal belongs to it.
+///
+/// Operations already in the log are skipped. Reading stops at the first
+/// line that can't be read, such as one cut short by a crash.
+fn merge_journal(
+    log: &mut TransactionLog,
+    path: &Path,
+    key: Option<&EncryptionKey>,
+) -> std::io::Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let mut lines = BufReader::new(file).lines();
+
+    let header = lines.next().transpose()?;
+    let header: Option<JournalHeader> = header.and_then(|line| decode_line(&line, key));
+    if header.is_none_or(|h| h.execution_id != log.execution_id) {
+        return Ok(());
+    }
+
+    for line in lines {
+        let Some(record) = decode_line::<JournalRecord>(&line?, key) else {
+            break;
+        };
+        if record.index > log.operations.len() {
+            break;
+        }
+        if record.index == log.operations.len() {
+            log.operations.push(record.operation);
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a journal line, decrypting it if it isn't plain JSON.
+fn decode_line<T: serde::de::DeserializeOwned>(
+    line: &str,
+    key: Option<&EncryptionKey>,
+) -> Option<T> {
+    if line.starts_with('{') {
+        return serde_json::from_str(line).ok();
+    }
+    let bytes = STANDARD.decode(line.trim()).ok()?;
+    let mut json = Vec::new();
+    encryption::decrypting(std::io::Cursor::new(bytes), key)
+        .ok()?
+        .read_to_end(&mut json)
+        .ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::executor::engine::{ExecutionSummary, OperationAction, OperationStatus};
+    use crate::executor::engine::{ExecutionSummary, OperationAction};
     use std::time::SystemTime;
     use tempfile::TempDir;
 
@@ -165,11 +590,14 @@ mod tests {
     ) -> OperationResult {
         OperationResult {
             path: PathBuf::from(path),
+            entry_id: None,
             action: OperationAction::Delete,
             status,
             size_freed,
             error: None,
             timestamp: SystemTime::now(),
+            streams: Vec::new(),
+            backup_path: None,
         }
     }
 
@@ -185,8 +613,13 @@ mod tests {
                 failed: 0,
                 skipped: 0,
                 space_freed: 3000,
+                space_by_action: SpaceByAction {
+                    deleted: 3000,
+                    ..SpaceByAction::default()
+                },
                 duration: std::time::Duration::from_secs(5),
             },
+            paused: false,
         }
     }
 
@@ -231,6 +664,40 @@ mod tests {
         assert_eq!(logger.log.operations[0].path, "test.txt");
     }
 
+    #[test]
+    fn test_log_operation_streams() {
+        let temp = TempDir::new().unwrap();
+        let log_path = temp.path().join("transaction.yaml");
+        let plan_path = temp.path().join("plan.yaml");
+
+        let options = TransactionOptions {
+            dry_run: false,
+            backup_dir: None,
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+
+        let mut logger = TransactionLogger::new(&plan_path, log_path.clone(), options);
+
+        let plain = create_test_operation("plain.txt", OperationStatus::Success, Some(10));
+        let mut with_streams =
+            create_test_operation("setup.exe", OperationStatus::Success, Some(1026));
+        with_streams.streams = vec![DataStream {
+            name: "Zone.Identifier".to_string(),
+            size: 26,
+        }];
+        logger.log_operation(&plain);
+        logger.log_operation(&with_streams);
+        logger.write().unwrap();
+
+        let content = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(content.matches("streams:").count(), 1);
+
+        let loaded = TransactionLogger::read(&log_path).unwrap();
+        assert!(loaded.operations[0].streams.is_empty());
+        assert_eq!(loaded.operations[1].streams, with_streams.streams);
+    }
+
     #[test]
     fn test_finalize_transaction() {
         let temp = TempDir::new().unwrap();
@@ -246,6 +713,9 @@ mod tests {
 
         let mut logger = TransactionLogger::new(&plan_path, log_path, options);
         let result = create_test_execution_result();
+        for op in &result.operations {
+            logger.log_operation(op);
+        }
 
         logger
             .finalize(&result, TransactionStatus::Completed)
@@ -259,6 +729,7 @@ mod tests {
         assert_eq!(summary.total_operations, 2);
         assert_eq!(summary.successful, 2);
         assert_eq!(summary.space_freed, 3000);
+        assert_eq!(summary.space_by_action.deleted, 3000);
     }
 
     #[test]
@@ -308,8 +779,12 @@ mod tests {
                 failed: 0,
                 skipped: 0,
                 space_freed: 1000,
+                space_by_action: SpaceByAction::default(),
                 duration_seconds: 1.5,
             }),
+            shard: None,
+            confirmation: None,
+            snapshot: None,
         };
 
         let yaml = serde_yaml::to_string(&log).unwrap();
@@ -340,6 +815,28 @@ mod tests {
         assert!(log_path.exists());
     }
 
+    #[test]
+    fn test_compressed_transaction_log() {
+        let temp = TempDir::new().unwrap();
+        let log_path = temp.path().join("transaction.yaml.zst");
+        let plan_path = temp.path().join("plan.yaml");
+
+        let options = TransactionOptions {
+            dry_run: false,
+            backup_dir: None,
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+
+        let logger = TransactionLogger::new(&plan_path, log_path.clone(), options);
+        logger.write().unwrap();
+
+        let raw = std::fs::read(&log_path).unwrap();
+        assert!(!raw.starts_with(b"version"));
+        let loaded = TransactionLogger::read(&log_path).unwrap();
+        assert_eq!(loaded.execution_id, logger.log.execution_id);
+    }
+
     #[test]
     fn test_execution_id_generation() {
         let temp = TempDir::new().unwrap();
@@ -376,15 +873,30 @@ mod tests {
         let mut logger = TransactionLogger::new(&plan_path, log_path, options);
 
         let mut op = create_test_operation("test.txt", OperationStatus::Failed, None);
-        op.error = Some("Permission denied".to_string());
+        op.error = Some(OperationError::PermissionDenied(
+            "Permission denied".to_string(),
+        ));
 
         logger.log_operation(&op);
 
         assert_eq!(logger.log.operations.len(), 1);
         assert_eq!(logger.log.operations[0].status, "Failed");
+        assert_eq!(logger.log.operations[0].error, op.error);
+
+        // The kind of failure survives the round trip through the log
+        logger.write().unwrap();
+        let loaded = TransactionLogger::read(&logger.log_path).unwrap();
+        assert_eq!(loaded.operations[0].error, op.error);
+
+        // Older logs recorded only the message
+        let legacy: LoggedOperation = serde_yaml::from_str(
+            "path: a\naction: Delete\nstatus: Failed\nsize_freed: null\n\
+             error: Access is denied.\nerror_kind: Io\ntimestamp: 2025-11-21T15:28:06Z\n",
+        )
+        .unwrap();
         assert_eq!(
-            logger.log.operations[0].error,
-            Some("Permission denied".to_string())
+            legacy.error,
+            Some(OperationError::Other("Access is denied.".to_string()))
         );
     }
 
@@ -406,4 +918,313 @@ mod tests {
         assert_eq!(logger.log.mode, "dry_run");
         assert!(logger.log.options.dry_run);
     }
+
+    #[test]
+    fn test_append_operation_saves_running_log() {
+        let temp = TempDir::new().unwrap();
+        let log_path = temp.path().join("transaction.yaml");
+        let options = TransactionOptions {
+            dry_run: false,
+            backup_dir: None,
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+        let mut logger =
+            TransactionLogger::new(&temp.path().join("plan.yaml"), log_path.clone(), options);
+        let result = create_test_execution_result();
+
+        // The first operation writes the log, later ones go to the journal
+        let journal_path = TransactionLogger::journal_path(&log_path);
+        logger.append_operation(&result.operations[0]).unwrap();
+        let saved = TransactionLogger::read(&log_path).unwrap();
+        assert_eq!(saved.status, TransactionStatus::InProgress);
+        assert_eq!(saved.operations.len(), 1);
+
+        let written = std::fs::read(&log_path).unwrap();
+        logger.append_operation(&result.operations[1]).unwrap();
+        assert_eq!(std::fs::read(&log_path).unwrap(), written);
+        assert_eq!(
+            TransactionLogger::read(&log_path).unwrap().operations.len(),
+            2
+        );
+
+        // A forced exit saves everything logged so far, and can be resumed
+        logger.abort().unwrap();
+        assert!(!journal_path.exists());
+        let aborted = TransactionLogger::read(&log_path).unwrap();
+        assert_eq!(aborted.status, TransactionStatus::Aborted);
+        assert_eq!(aborted.operations.len(), 2);
+        assert!(TransactionLogger::resume(log_path).is_ok());
+    }
+
+    #[test]
+    fn test_journal_of_a_killed_run_is_merged() {
+        let temp = TempDir::new().unwrap();
+        let log_path = temp.path().join("transaction.yaml");
+        let options = TransactionOptions {
+            dry_run: false,
+            backup_dir: None,
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+        let mut logger =
+            TransactionLogger::new(&temp.path().join("plan.yaml"), log_path.clone(), options);
+        let result = create_test_execution_result();
+        for op in &result.operations {
+            logger.append_operation(op).unwrap();
+        }
+        drop(logger);
+
+        // A line cut short by the kill ends the journal
+        let journal_path = TransactionLogger::journal_path(&log_path);
+        let mut journal = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&journal_path)
+            .unwrap();
+        journal.write_all(b"{\"index\": 2, \"oper").unwrap();
+        drop(journal);
+
+        let log = TransactionLogger::read(&log_path).unwrap();
+        assert_eq!(log.status, TransactionStatus::InProgress);
+        assert_eq!(log.operations.len(), 2);
+        assert_eq!(log.operations[1].path, "test2.txt");
+
+        // A resumed run rewrites the log with the journal in it first
+        let mut resumed = TransactionLogger::resume(log_path.clone()).unwrap();
+        resumed.append_operation(&result.operations[0]).unwrap();
+        let log = TransactionLogger::read(&log_path).unwrap();
+        assert_eq!(log.operations.len(), 3);
+        resumed
+            .finalize(&result, TransactionStatus::Completed)
+            .unwrap();
+        assert!(!journal_path.exists());
+        assert_eq!(
+            TransactionLogger::read(&log_path).unwrap().operations.len(),
+            3
+        );
+
+        // A journal left by another execution is ignored
+        std::fs::write(
+            &journal_path,
+            "{\"execution_id\": \"other\"}\n{\"index\": 3}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            TransactionLogger::read(&log_path).unwrap().operations.len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_encrypted_journal() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("transaction.yaml.journal");
+        let key = EncryptionKey::from_base64(&format!("{}=", "A".repeat(43)), "test").unwrap();
+        let logger = TransactionLogger::new(
+            Path::new("plan.yaml"),
+            temp.path().join("transaction.yaml"),
+            TransactionOptions {
+                dry_run: false,
+                backup_dir: None,
+                use_recycle_bin: false,
+                fail_fast: false,
+            },
+        );
+        let mut log = logger.log;
+
+        let mut journal = Journal::create(&path, &log.execution_id, Some(key.clone())).unwrap();
+        let operation = LoggedOperation {
+            path: "secret.txt".to_string(),
+            entry_id: None,
+            action: "Delete".to_string(),
+            status: "Success".to_string(),
+            size_freed: Some(1),
+            error: None,
+            timestamp: Utc::now(),
+            streams: Vec::new(),
+            backup_path: None,
+        };
+        journal.append(0, &operation).unwrap();
+        drop(journal);
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("secret"));
+
+        merge_journal(&mut log, &path, None).unwrap();
+        assert!(log.operations.is_empty());
+        merge_journal(&mut log, &path, Some(&key)).unwrap();
+        assert_eq!(log.operations[0].path, "secret.txt");
+    }
+
+    #[test]
+    fn test_resume_appends_and_accumulates() {
+        let temp = TempDir::new().unwrap();
+        let log_path = temp.path().join("transaction.yaml");
+        let plan_path = temp.path().join("plan.yaml");
+
+        let options = TransactionOptions {
+            dry_run: false,
+            backup_dir: None,
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+
+        // First run pauses after two operations, one of which failed
+        let mut logger = TransactionLogger::new(&plan_path, log_path.clone(), options);
+        let mut first = create_test_execution_result();
+        first.operations[1].status = OperationStatus::Failed;
+        first.paused = true;
+        for op in &first.operations {
+            logger.log_operation(op);
+        }
+        logger.finalize(&first, TransactionStatus::Paused).unwrap();
+
+        let mut resumed = TransactionLogger::resume(log_path.clone()).unwrap();
+        assert_eq!(resumed.plan_file(), plan_path.as_path());
+        assert_eq!(resumed.log.status, TransactionStatus::InProgress);
+
+        // Failed operations are retried on resume
+        let processed = resumed.processed_paths();
+        assert!(processed.contains(&PathKey::new(Path::new("test1.txt"))));
+        assert!(!processed.contains(&PathKey::new(Path::new("test2.txt"))));
+
+        // The resumed run retries test2.txt and goes on with test3.txt
+        let mut second = create_test_execution_result();
+        second.operations = vec![
+            create_test_operation("test2.txt", OperationStatus::Success, Some(2000)),
+            create_test_operation("test3.txt", OperationStatus::Success, Some(3000)),
+        ];
+        second.summary.space_freed = 5000;
+        second.summary.space_by_action.deleted = 5000;
+        for op in &second.operations {
+            resumed.log_operation(op);
+        }
+        resumed
+            .finalize(&second, TransactionStatus::Completed)
+            .unwrap();
+
+        // Every attempt is logged, but each entry counts once, as it ended
+        let loaded = TransactionLogger::read(&log_path).unwrap();
+        assert_eq!(loaded.operations.len(), 4);
+        let summary = loaded.summary.unwrap();
+        assert_eq!(summary.total_operations, 3);
+        assert_eq!(summary.successful, 3);
+        assert_eq!(summary.failed, 0);
+        assert_eq!(summary.space_freed, 6000);
+        assert_eq!(summary.space_by_action.deleted, 6000);
+        assert_eq!(summary.duration_seconds, 10.0);
+
+        // A completed transaction cannot be resumed
+        assert!(TransactionLogger::resume(log_path).is_err());
+    }
+
+    #[test]
+    fn test_resumed_dry_run_counts_dry_run_operations() {
+        let temp = TempDir::new().unwrap();
+        let log_path = temp.path().join("transaction.yaml");
+        let options = |dry_run| TransactionOptions {
+            dry_run,
+            backup_dir: None,
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+
+        for dry_run in [true, false] {
+            let mut logger =
+                TransactionLogger::new(Path::new("plan.yaml"), log_path.clone(), options(dry_run));
+            let mut result = create_test_execution_result();
+            result.operations[0].status = OperationStatus::DryRun;
+            for op in &result.operations {
+                logger.log_operation(op);
+            }
+            logger.finalize(&result, TransactionStatus::Paused).unwrap();
+
+            let processed = TransactionLogger::resume(log_path.clone())
+                .unwrap()
+                .processed_paths();
+            assert_eq!(
+                processed.contains(&PathKey::new(Path::new("test1.txt"))),
+                dry_run
+            );
+            assert!(processed.contains(&PathKey::new(Path::new("test2.txt"))));
+        }
+    }
+
+    #[test]
+    fn test_processed_entries_match_by_id_or_path() {
+        use crate::models::CleanupAction;
+
+        let entry = |path: &str| {
+            CleanupEntry::new(
+                path.to_string(),
+                1,
+                "2025-01-01T00:00:00Z".to_string(),
+                CleanupAction::Delete,
+                "test".to_string(),
+                "Test".to_string(),
+            )
+        };
+
+        let mut with_id =
+            create_test_operation("/old/base/a/target", OperationStatus::Success, None);
+        with_id.entry_id = Some(entry("a/target").id());
+        let mut failed = create_test_operation("/data/b", OperationStatus::Failed, None);
+        failed.entry_id = Some(entry("b").id());
+        let legacy = create_test_operation("/data/c", OperationStatus::Success, None);
+        let dry_run = create_test_operation("/data/d", OperationStatus::DryRun, None);
+
+        let temp = TempDir::new().unwrap();
+        let mut logger = TransactionLogger::new(
+            Path::new("plan.yaml"),
+            temp.path().join("log.yaml"),
+            TransactionOptions {
+                dry_run: false,
+                backup_dir: None,
+                use_recycle_bin: false,
+                fail_fast: false,
+            },
+        );
+        for op in [&with_id, &failed, &legacy, &dry_run] {
+            logger.log_operation(op);
+        }
+
+        let processed = ProcessedEntries::from_log(&logger.log);
+        let base = Path::new("/data");
+        assert_eq!(processed.len(), 2);
+        // IDs don't depend on the base path
+        assert!(processed.contains(base, &entry("a/target")));
+        assert!(!processed.contains(base, &entry("b")));
+        assert!(processed.contains(base, &entry("c")));
+        assert!(!processed.contains(base, &entry("d")));
+
+        let failed = ProcessedEntries::failed(&logger.log);
+        assert_eq!(failed.len(), 1);
+        assert!(failed.contains(base, &entry("b")));
+        assert!(!failed.contains(base, &entry("a/target")));
+    }
+
+    #[test]
+    fn test_record_confirmation() {
+        let temp = TempDir::new().unwrap();
+        let log_path = temp.path().join("log.yaml");
+        let options = TransactionOptions {
+            dry_run: false,
+            backup_dir: None,
+            use_recycle_bin: false,
+            fail_fast: false,
+        };
+
+        let mut logger = TransactionLogger::new(Path::new("plan.yaml"), log_path.clone(), options);
+        logger.record_confirmation(ConfirmationMethod::Prompt, 2, 3000);
+        logger
+            .finalize(
+                &create_test_execution_result(),
+                TransactionStatus::Completed,
+            )
+            .unwrap();
+
+        let loaded = TransactionLogger::read(&log_path).unwrap();
+        let confirmation = loaded.confirmation.unwrap();
+        assert_eq!(confirmation.method, ConfirmationMethod::Prompt);
+        assert_eq!(confirmation.entries, 2);
+        assert_eq!(confirmation.bytes, 3000);
+    }
 }
@@ -0,0 +1,207 @@
+//! Index of the items megamaid moved to the recycle bin.
+//!
+//! The recycle bin doesn't record which program deleted an item, so
+//! recycle-bin executions add what they recycled to an index file in the
+//! user's data directory (see [`TrashIndex::default_path`]). `megamaid trash
+//! list` and `megamaid trash purge` use it to show and empty megamaid's own
+//! items without touching anything else in the bin, and executions use it to
+//! tell which duplicates an earlier megamaid run left behind.
+//!
+//! Records are matched to bin items by original path and deletion time. Items
+//! restored or purged outside megamaid are dropped from the index the next
+//! time it is reconciled with the bin.
+
+use crate::executor::engine::{ExecutionResult, OperationAction, OperationStatus};
+use crate::executor::recycle_bin::RecycledItem;
+use crate::models::PathKey;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Seconds a bin item's deletion time may differ from the recorded time.
+///
+/// The shell stamps the item when it moves it, which is slightly before the
+/// engine records the operation.
+const TIME_TOLERANCE_SECS: i64 = 2;
+
+/// An item recycled by a megamaid execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashRecord {
+    /// Where the item lived before it was recycled
+    pub original_path: PathBuf,
+
+    /// Size of the item when it was recycled
+    pub size: Option<u64>,
+
+    /// When it was recycled, in seconds since the Unix epoch
+    pub recycled_at: i64,
+
+    /// Transaction ID of the execution that recycled it
+    pub execution_id: String,
+}
+
+impl TrashRecord {
+    /// Returns true if `item` in the bin is the item this record describes.
+    pub fn matches(&self, item: &RecycledItem) -> bool {
+        (item.time_deleted - self.recycled_at).abs() <= TIME_TOLERANCE_SECS
+            && PathKey::new(&item.original_path) == PathKey::new(&self.original_path)
+    }
+}
+
+/// Items recycled by megamaid executions, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashIndex {
+    pub items: Vec<TrashRecord>,
+}
+
+impl TrashIndex {
+    /// Returns the index file in the user's data directory, or `None` if the
+    /// platform has none.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(
+            dirs::data_local_dir()?
+                .join("megamaid")
+                .join("trash-index.json"),
+        )
+    }
+
+    /// Loads an index file; a missing file is an empty index.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the index file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let data = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    /// Adds the entries an execution moved to the recycle bin.
+    pub fn record(&mut self, execution_id: &str, result: &ExecutionResult) {
+        let recycled = result.operations.iter().filter(|op| {
+            op.action == OperationAction::MoveToRecycleBin && op.status == OperationStatus::Success
+        });
+        for op in recycled {
+            let recycled_at = op
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64);
+            self.items.push(TrashRecord {
+                original_path: op.path.clone(),
+                size: op.size_freed,
+                recycled_at,
+                execution_id: execution_id.to_string(),
+            });
+        }
+    }
+
+    /// Returns the record of `item`, if megamaid recycled it.
+    pub fn find(&self, item: &RecycledItem) -> Option<&TrashRecord> {
+        self.items.iter().rev().find(|record| record.matches(item))
+    }
+
+    /// Drops the records of items no longer in the bin.
+    pub fn retain_present(&mut self, bin: &[RecycledItem]) {
+        self.items
+            .retain(|record| bin.iter().any(|item| record.matches(item)));
+    }
+
+    /// Returns the items in `bin` that megamaid recycled, with their records.
+    pub fn present<'a>(
+        &'a self,
+        bin: &'a [RecycledItem],
+    ) -> Vec<(&'a RecycledItem, &'a TrashRecord)> {
+        bin.iter()
+            .filter_map(|item| Some((item, self.find(item)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::engine::{ExecutionSummary, OperationResult, SpaceByAction};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn recycled(path: &str, secs: u64) -> OperationResult {
+        OperationResult {
+            path: PathBuf::from(path),
+            entry_id: None,
+            action: OperationAction::MoveToRecycleBin,
+            status: OperationStatus::Success,
+            size_freed: Some(10),
+            error: None,
+            timestamp: UNIX_EPOCH + Duration::from_secs(secs),
+            streams: Vec::new(),
+            backup_path: None,
+        }
+    }
+
+    fn item(path: &str, time_deleted: i64) -> RecycledItem {
+        RecycledItem {
+            original_path: PathBuf::from(path),
+            time_deleted,
+            size: Some(10),
+        }
+    }
+
+    #[test]
+    fn test_record_match_and_reconcile() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("megamaid/trash-index.json");
+        assert!(TrashIndex::load(&path).unwrap().items.is_empty());
+
+        let mut failed = recycled("/p/failed", 1_000);
+        failed.status = OperationStatus::Failed;
+        let result = ExecutionResult {
+            operations: vec![
+                recycled("/p/target", 1_000),
+                recycled("/p/app.iso", 1_001),
+                failed,
+            ],
+            summary: ExecutionSummary {
+                total_operations: 3,
+                successful: 2,
+                failed: 1,
+                skipped: 0,
+                space_freed: 20,
+                space_by_action: SpaceByAction {
+                    recycled: 20,
+                    ..SpaceByAction::default()
+                },
+                duration: Duration::ZERO,
+            },
+            paused: false,
+        };
+        let mut index = TrashIndex::default();
+        index.record("run-1", &result);
+        index.save(&path).unwrap();
+
+        let mut index = TrashIndex::load(&path).unwrap();
+        assert_eq!(index.items.len(), 2);
+        let bin = vec![
+            item("/p/target", 999),
+            // Recycled by someone else at a different time
+            item("/p/app.iso", 5_000),
+            item("/elsewhere/notes.txt", 1_000),
+        ];
+        let present = index.present(&bin);
+        assert_eq!(present.len(), 1);
+        assert_eq!(present[0].1.execution_id, "run-1");
+
+        index.retain_present(&bin);
+        assert_eq!(index.items.len(), 1);
+        assert_eq!(index.items[0].original_path, PathBuf::from("/p/target"));
+    }
+}
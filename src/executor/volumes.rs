@@ -0,0 +1,238 @@
+//! Per-volume concurrency limits for parallel execution.
+//!
+//! Deleting in parallel pays off on SSDs, which serve many requests at once,
+//! but slows a spinning disk down, since its head has to seek back and forth
+//! between the entries being removed, and can swamp a file server. Parallel
+//! executions therefore group each batch by the volume its entries are on and
+//! cap how many operations run at once on each, by the kind of volume.
+//!
+//! Volume kinds are detected from the block device's `rotational` flag in
+//! sysfs on Linux; network volumes are recognized as in remote scan mode (see
+//! [`crate::scanner::remote`]). Anything else counts as
+//! [`VolumeKind::Unknown`].
+
+use crate::executor::recycle_bin::volume_of;
+use crate::scanner::remote::is_network_path;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Kind of storage a volume is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VolumeKind {
+    /// Spinning hard disk
+    Rotational,
+    /// SSD or NVMe drive
+    SolidState,
+    /// Network share
+    Network,
+    /// Couldn't be determined
+    Unknown,
+}
+
+impl fmt::Display for VolumeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Rotational => "hard disk",
+            Self::SolidState => "solid-state drive",
+            Self::Network => "network share",
+            Self::Unknown => "unknown storage",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Maximum number of operations running at once on one volume.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumeConcurrency {
+    /// Limit for spinning hard disks
+    pub rotational: usize,
+
+    /// Limit for SSD and NVMe drives
+    pub solid_state: usize,
+
+    /// Limit for network shares
+    pub network: usize,
+
+    /// Limit for volumes whose kind couldn't be determined
+    pub unknown: usize,
+
+    /// Limits for specific volumes by root path (e.g. `D:\` or `/mnt/data`),
+    /// taking precedence over the limit for their kind
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub volumes: BTreeMap<PathBuf, usize>,
+}
+
+impl Default for VolumeConcurrency {
+    fn default() -> Self {
+        Self {
+            rotational: 1,
+            solid_state: 16,
+            network: 4,
+            unknown: 8,
+            volumes: BTreeMap::new(),
+        }
+    }
+}
+
+impl VolumeConcurrency {
+    /// Returns the limit for `volume`; never less than 1.
+    pub fn limit(&self, volume: &Volume) -> usize {
+        let limit = self
+            .volumes
+            .get(&volume.root)
+            .copied()
+            .unwrap_or(match volume.kind {
+                VolumeKind::Rotational => self.rotational,
+                VolumeKind::SolidState => self.solid_state,
+                VolumeKind::Network => self.network,
+                VolumeKind::Unknown => self.unknown,
+            });
+        limit.max(1)
+    }
+}
+
+/// A volume entries are executed on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    /// Root of the volume (see [`volume_of`])
+    pub root: PathBuf,
+    pub kind: VolumeKind,
+}
+
+/// Detects the volume of each path, looking every volume up only once.
+#[derive(Debug, Default)]
+pub struct VolumeCache {
+    volumes: HashMap<DeviceKey, Volume>,
+}
+
+impl VolumeCache {
+    /// Returns the volume holding `path`, which need not exist anymore.
+    pub fn volume(&mut self, path: &Path) -> &Volume {
+        self.volumes.entry(device_key(path)).or_insert_with(|| {
+            let root = volume_of(path);
+            Volume {
+                kind: detect_kind(&root),
+                root,
+            }
+        })
+    }
+}
+
+/// Detects the kind of storage the volume rooted at `root` is on.
+pub fn detect_kind(root: &Path) -> VolumeKind {
+    if is_network_path(root) {
+        VolumeKind::Network
+    } else {
+        platform::detect_kind(root)
+    }
+}
+
+/// Identifies the volume of a path without walking up to its root.
+#[cfg(unix)]
+type DeviceKey = u64;
+
+/// Identifies the volume of a path without walking up to its root.
+#[cfg(not(unix))]
+type DeviceKey = PathBuf;
+
+/// Returns the device of the nearest existing ancestor of `path`.
+#[cfg(unix)]
+fn device_key(path: &Path) -> DeviceKey {
+    use std::os::unix::fs::MetadataExt;
+    path.ancestors()
+        .find_map(|p| p.symlink_metadata().ok())
+        .map_or(0, |meta| meta.dev())
+}
+
+/// Returns the volume root of `path`, which is cheap to find from its prefix.
+#[cfg(not(unix))]
+fn device_key(path: &Path) -> DeviceKey {
+    volume_of(path)
+}
+
+/// Maps the contents of a sysfs `queue/rotational` file to a kind.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_rotational(value: &str) -> VolumeKind {
+    match value.trim() {
+        "1" => VolumeKind::Rotational,
+        "0" => VolumeKind::SolidState,
+        _ => VolumeKind::Unknown,
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+
+    /// Reads the rotational flag of the block device behind `root`.
+    pub fn detect_kind(root: &Path) -> VolumeKind {
+        let Ok(meta) = std::fs::metadata(root) else {
+            return VolumeKind::Unknown;
+        };
+        let dev = meta.dev();
+        let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff);
+        let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0xff);
+        let device = PathBuf::from(format!("/sys/dev/block/{}:{}", major, minor));
+
+        // Partitions keep their queue settings on the parent disk
+        [
+            device.join("queue/rotational"),
+            device.join("../queue/rotational"),
+        ]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map_or(VolumeKind::Unknown, |value| parse_rotational(&value))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::*;
+
+    /// Local drives aren't told apart on this platform.
+    pub fn detect_kind(_root: &Path) -> VolumeKind {
+        VolumeKind::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_prefers_volume_overrides() {
+        let concurrency = VolumeConcurrency {
+            volumes: BTreeMap::from([(PathBuf::from("/mnt/archive"), 2)]),
+            network: 0,
+            ..Default::default()
+        };
+        let volume = |root: &str, kind| Volume {
+            root: PathBuf::from(root),
+            kind,
+        };
+
+        assert_eq!(concurrency.limit(&volume("/", VolumeKind::Rotational)), 1);
+        assert_eq!(concurrency.limit(&volume("/", VolumeKind::SolidState)), 16);
+        assert_eq!(
+            concurrency.limit(&volume("/mnt/archive", VolumeKind::SolidState)),
+            2
+        );
+        // A limit of 0 still lets operations run one at a time
+        assert_eq!(
+            concurrency.limit(&volume("/mnt/nas", VolumeKind::Network)),
+            1
+        );
+    }
+
+    #[test]
+    fn test_parse_rotational() {
+        assert_eq!(parse_rotational("1\n"), VolumeKind::Rotational);
+        assert_eq!(parse_rotational("0\n"), VolumeKind::SolidState);
+        assert_eq!(parse_rotational(""), VolumeKind::Unknown);
+    }
+}
@@ -16,6 +16,19 @@
 //! - **Verifier**: Detects filesystem drift before plan execution
 //! - **CLI**: Command-line interface with progress reporting
 //!
+//! ## Cargo Features
+//!
+//! - `cli` (default): the `megamaid` binary and the `cli` module
+//! - `trash` (default): recycle bin support for `move_to_recycle_bin`
+//! - `parallel` (default): multi-threaded scanning, planning and execution
+//! - `ui-support`: `parallel` and `trash` without the CLI, for embedders such as the desktop UI
+//! - `parquet`: Parquet output for [`export_entries`]; without it, `.parquet` exports fall back to CSV
+//! - `vss`: Volume Shadow Copy snapshots before executions on Windows (btrfs and ZFS snapshots on Linux need no feature), see [`executor::snapshot`]
+//!
+//! Without `trash`, recycle bin operations fail with an unsupported error; without
+//! `parallel`, requesting parallel execution returns
+//! [`ExecutionError::InvalidConfiguration`].
+//!
 //! ## Complete Workflow Example
 //!
 //! ```no_run
@@ -87,27 +100,52 @@ pub mod executor;
 /// Configuration management
 pub mod config;
 
+/// Serializable summaries shared by the CLI's JSON output and the desktop UI
+pub mod dto;
+
+/// Diagnostic bundles and crash reports
+pub mod diagnostics;
+
+/// Disk usage breakdowns of scan results
+pub mod analysis;
+
 /// Command-line interface
+#[cfg(feature = "cli")]
 pub mod cli;
 
 // Re-export commonly used types
+pub use analysis::{UsageAnalyzer, UsageReport};
+#[cfg(feature = "cli")]
 pub use cli::{run_command, Cli, Commands};
 pub use config::{
     load_config, load_default_config, parse_config, validate_config, write_config, MegamaidConfig,
 };
 pub use detector::{
-    BuildArtifactRule, DetectionEngine, DetectionResult, DetectionRule, ScanContext,
-    SizeThresholdRule,
+    AlternateStreamRule, BuildArtifactRule, DetectionEngine, DetectionReport, DetectionResult,
+    DetectionRule, KeepRule, ScanContext, SizeThresholdRule, VetoedDetection,
 };
 pub use executor::{
-    ExecutionConfig, ExecutionEngine, ExecutionError, ExecutionMode, ExecutionResult,
-    ExecutionSummary, ExecutionSummaryLog, LoggedOperation, OperationAction, OperationResult,
-    OperationStatus, TransactionLog, TransactionLogger, TransactionOptions, TransactionStatus,
+    Confirmation, ConfirmationMethod, ExecutionConfig, ExecutionEngine, ExecutionError,
+    ExecutionMode, ExecutionResult, ExecutionSummary, ExecutionSummaryLog, LogSummary,
+    LoggedOperation, OperationAction, OperationError, OperationResult, OperationStatus, Shard,
+    ShardParseError, TransactionLog, TransactionLogger, TransactionOptions, TransactionStatus,
+};
+pub use models::{
+    AggregateStats, ByteSize, ByteSizeParseError, CleanupAction, CleanupActionParseError,
+    CleanupEntry, CleanupPlan, CollapsedSummary, DataStream, EntryType, FileEntry, PathCase,
+    PathKey, RuleStats, SampledFile,
+};
+pub use planner::{
+    expand_entry, read_path_list, ExpandError, PathListError, PathListPlan, PathListPlanner,
+    PlanFormat, PlanGenerator, PlanReader, PlanWriter, ReadError, StreamingPlanWriter, WriteError,
+};
+pub use scanner::{
+    export_entries, import_scan, ExportError, ExportFormat, ExportSummary, FileScanner,
+    ImportError, ImportFormat, ImportedScan, IncrementalStats, ProgressReport, RemoteMode,
+    RemoteScanConfig, ScanCache, ScanConfig, ScanError, ScanProgress,
 };
-pub use models::{CleanupAction, CleanupEntry, CleanupPlan, EntryType, FileEntry};
-pub use planner::{PlanGenerator, PlanWriter, WriteError};
-pub use scanner::{FileScanner, ProgressReport, ScanConfig, ScanError, ScanProgress};
 pub use verifier::{
-    DriftDetection, DriftReporter, DriftType, VerificationConfig, VerificationEngine,
-    VerificationError, VerificationResult,
+    AccessError, AccessErrorCause, DriftDetection, DriftReporter, DriftType, MissingPermissions,
+    RecordError, VerificationConfig, VerificationEngine, VerificationError, VerificationRecord,
+    VerificationResult,
 };
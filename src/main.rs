@@ -1,10 +1,12 @@
 use clap::Parser;
+use megamaid::cli::style;
 use megamaid::{run_command, Cli};
 
 fn main() {
     let cli = Cli::parse();
+    style::set_plain(cli.plain);
 
-    if let Err(e) = run_command(cli.command, cli.config) {
+    if let Err(e) = run_command(cli.command, cli.config, cli.yes, cli.no_encrypt) {
         eprintln!("Error: {:?}", e);
         std::process::exit(1);
     }
@@ -0,0 +1,200 @@
+//! megamaid's own files inside the trees it cleans.
+//!
+//! Plans, transaction logs, scan caches and backup directories are often
+//! written inside the directory being scanned, e.g. a plan saved to the
+//! project root or a backup directory under `build/`. Without care a scan
+//! would flag them (a 200 MB plan is a large file), and executing could
+//! remove the transaction log recording the run. [`OwnArtifacts`] lists
+//! those paths so the scanner can leave them out, the detector can hold back
+//! directories containing them, and the executor can refuse to touch them.
+//!
+//! Read-only btrfs snapshots taken before a run live in [`SNAPSHOT_DIR`] at
+//! the root of the subvolume, inside the tree being cleaned. Each one is a
+//! full copy of the volume, so scanners never walk into that directory and
+//! nothing in it is ever flagged or removed (see [`in_snapshot_dir`]).
+
+use crate::models::paths::PathCase;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// Directory at the root of a btrfs subvolume holding megamaid's snapshots.
+pub const SNAPSHOT_DIR: &str = ".megamaid-snapshots";
+
+/// Returns true if `path` is a snapshot directory or lies inside one.
+pub fn in_snapshot_dir(path: &Path) -> bool {
+    path.components()
+        .any(|c| matches!(c, Component::Normal(name) if name == OsStr::new(SNAPSHOT_DIR)))
+}
+
+/// Kind of file or directory megamaid writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    /// Cleanup plan
+    Plan,
+    /// Transaction log
+    TransactionLog,
+    /// Directory entries are moved to in backup mode
+    BackupDir,
+    /// Incremental scan cache
+    ScanCache,
+    /// Exported scan entries
+    Export,
+    /// Drift report, verification record or other report
+    Report,
+    /// Volume snapshot taken before a run
+    Snapshot,
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Plan => "cleanup plan",
+            Self::TransactionLog => "transaction log",
+            Self::BackupDir => "backup directory",
+            Self::ScanCache => "scan cache",
+            Self::Export => "entry export",
+            Self::Report => "report",
+            Self::Snapshot => "volume snapshot",
+        };
+        f.write_str(label)
+    }
+}
+
+/// A path megamaid writes to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnArtifact {
+    /// Absolute path of the file or directory
+    pub path: PathBuf,
+    /// What megamaid uses it for
+    pub kind: ArtifactKind,
+}
+
+/// The set of paths megamaid writes to during a run.
+///
+/// Paths are made absolute when added, so relative entry paths compare
+/// correctly as long as the working directory doesn't change. Comparisons
+/// follow the platform's case rules (see [`PathCase`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnArtifacts {
+    artifacts: Vec<OwnArtifact>,
+}
+
+impl OwnArtifacts {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `path` as an artifact of the given kind.
+    pub fn add(&mut self, kind: ArtifactKind, path: impl AsRef<Path>) {
+        self.artifacts.push(OwnArtifact {
+            path: absolute(path.as_ref()),
+            kind,
+        });
+    }
+
+    /// Adds `path` and returns the set, for chaining.
+    pub fn with(mut self, kind: ArtifactKind, path: impl AsRef<Path>) -> Self {
+        self.add(kind, path);
+        self
+    }
+
+    /// Returns true if no artifacts were added.
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.is_empty()
+    }
+
+    /// Returns the artifacts inside `root`, joined to `root` as given.
+    ///
+    /// Entries found by walking `root` share its form (relative or not), so
+    /// they can be compared with the result without resolving each one.
+    pub fn within(&self, root: &Path) -> Vec<PathBuf> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let base = absolute(root);
+        self.artifacts
+            .iter()
+            .filter_map(|a| PathCase::native().strip_prefix(&a.path, &base))
+            .map(|rest| root.join(rest))
+            .collect()
+    }
+
+    /// Returns an artifact that removing `path` would remove: `path` itself,
+    /// an artifact it contains, or one it lies inside of.
+    pub fn affected_by(&self, path: &Path) -> Option<&OwnArtifact> {
+        if self.is_empty() {
+            return None;
+        }
+        let path = absolute(path);
+        let case = PathCase::native();
+        self.artifacts
+            .iter()
+            .find(|a| case.starts_with(&a.path, &path) || case.starts_with(&path, &a.path))
+    }
+}
+
+/// Makes `path` absolute without touching the filesystem.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affected_by() {
+        let root = std::env::temp_dir().join("project");
+        let artifacts = OwnArtifacts::new()
+            .with(ArtifactKind::Plan, root.join("cleanup-plan.yaml"))
+            .with(ArtifactKind::BackupDir, root.join("build/backup"));
+
+        let plan = artifacts.affected_by(&root.join("cleanup-plan.yaml"));
+        assert_eq!(plan.unwrap().kind, ArtifactKind::Plan);
+        let inside = artifacts.affected_by(&root.join("build/backup/a.bin"));
+        assert_eq!(inside.unwrap().kind, ArtifactKind::BackupDir);
+        assert!(artifacts
+            .affected_by(&root.join("cleanup-plan.yaml.bak"))
+            .is_none());
+
+        // Removing an ancestor removes the artifact too
+        let build = artifacts.affected_by(&root.join("build"));
+        assert_eq!(build.unwrap().kind, ArtifactKind::BackupDir);
+        assert!(artifacts.affected_by(&root.join("target")).is_none());
+        assert!(OwnArtifacts::new().affected_by(&root).is_none());
+    }
+
+    #[test]
+    fn test_within_joins_the_root() {
+        let root = std::env::temp_dir().join("project");
+        let artifacts = OwnArtifacts::new()
+            .with(
+                ArtifactKind::TransactionLog,
+                root.join("execution-log.yaml"),
+            )
+            .with(ArtifactKind::Plan, root.join("plans/plan.yaml"))
+            .with(ArtifactKind::ScanCache, std::env::temp_dir().join("c.json"));
+
+        assert_eq!(
+            artifacts.within(&root.join("plans")),
+            vec![root.join("plans/plan.yaml")]
+        );
+        assert_eq!(artifacts.within(&root).len(), 2);
+        assert!(OwnArtifacts::new().within(&root).is_empty());
+    }
+
+    #[test]
+    fn test_in_snapshot_dir() {
+        let volume = Path::new("/srv");
+        assert!(in_snapshot_dir(&volume.join(SNAPSHOT_DIR)));
+        assert!(in_snapshot_dir(
+            &volume
+                .join(SNAPSHOT_DIR)
+                .join("megamaid-20260101/app/target")
+        ));
+        assert!(!in_snapshot_dir(&volume.join("app/target")));
+        assert!(!in_snapshot_dir(Path::new("/srv/.megamaid-snapshots.bak")));
+    }
+}
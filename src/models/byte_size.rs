@@ -0,0 +1,409 @@
+//! Human-readable byte sizes for CLI flags and configuration fields.
+//!
+//! Sizes are written as a number followed by a unit, e.g. `500MB`, `1.5GiB`
+//! or `2 T`. Units are binary: `KB`, `K` and `KiB` all mean 1024 bytes,
+//! matching how sizes are reported elsewhere in megamaid.
+//!
+//! Values that could be read more than one way are rejected instead of
+//! guessed at:
+//!
+//! - a lowercase `b` (`500Mb`) usually means bits,
+//! - a comma (`1,5GB` or `1,000MB`) may be a decimal or a thousands separator,
+//! - a number without a unit is only accepted where the field documents a
+//!   default unit (fields named `*_mb` and `--large-file-threshold` use MB).
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+const KIB: u64 = 1024;
+const MIB: u64 = KIB * 1024;
+const GIB: u64 = MIB * 1024;
+const TIB: u64 = GIB * 1024;
+
+/// A size in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(u64);
+
+/// Errors parsing a human-readable size.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ByteSizeParseError {
+    /// The value is empty
+    #[error("size is empty")]
+    Empty,
+
+    /// The numeric part could not be parsed
+    #[error("invalid size '{0}': expected a number followed by a unit, e.g. 500MB or 1.5GiB")]
+    InvalidNumber(String),
+
+    /// The unit is not recognized
+    #[error("invalid size '{0}': unknown unit (use B, KB, MB, GB, TB or KiB, MiB, GiB, TiB)")]
+    UnknownUnit(String),
+
+    /// The value has no unit and the field has no default unit
+    #[error("size '{0}' has no unit; write it as e.g. '{0}MB' or '{0}B'")]
+    MissingUnit(String),
+
+    /// The unit ends in a lowercase `b`, which usually denotes bits
+    #[error("size '{0}' is ambiguous: a lowercase 'b' means bits; use 'B' for bytes (e.g. MB)")]
+    Bits(String),
+
+    /// The value contains a comma
+    #[error("size '{0}' is ambiguous: use '.' for decimals and no thousands separators")]
+    Comma(String),
+
+    /// The value does not fit in 64 bits
+    #[error("size '{0}' is too large")]
+    Overflow(String),
+}
+
+impl ByteSize {
+    /// Creates a size from a byte count.
+    pub const fn from_bytes(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    /// Creates a size from a count of megabytes (1 MB = 1,048,576 bytes).
+    pub const fn from_mb(mb: u64) -> Self {
+        Self(mb.saturating_mul(MIB))
+    }
+
+    /// Returns the size in bytes.
+    pub const fn as_bytes(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the size in whole megabytes, rounded down.
+    pub const fn as_mb(self) -> u64 {
+        self.0 / MIB
+    }
+
+    /// Parses a size, treating a bare number as `default_unit` bytes.
+    ///
+    /// Pass `None` to require an explicit unit.
+    pub fn parse_with_default_unit(
+        s: &str,
+        default_unit: Option<u64>,
+    ) -> Result<Self, ByteSizeParseError> {
+        let value = s.trim();
+        if value.is_empty() {
+            return Err(ByteSizeParseError::Empty);
+        }
+        if value.contains(',') {
+            return Err(ByteSizeParseError::Comma(value.to_string()));
+        }
+
+        let split = value
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+        let unit = unit.trim_start();
+        if number.is_empty() {
+            return Err(ByteSizeParseError::InvalidNumber(value.to_string()));
+        }
+
+        let multiplier = match parse_unit(unit) {
+            Some(multiplier) => multiplier,
+            None if unit.is_empty() => {
+                default_unit.ok_or_else(|| ByteSizeParseError::MissingUnit(value.to_string()))?
+            }
+            None if unit.ends_with('b') => return Err(ByteSizeParseError::Bits(value.to_string())),
+            None => return Err(ByteSizeParseError::UnknownUnit(value.to_string())),
+        };
+
+        let bytes = scale(number, multiplier, value)?;
+        Ok(Self(bytes))
+    }
+
+    /// Parses a size where a bare number means megabytes, e.g. for `*_mb` fields.
+    pub fn parse_mb(s: &str) -> Result<Self, ByteSizeParseError> {
+        Self::parse_with_default_unit(s, Some(MIB))
+    }
+}
+
+/// Returns the multiplier for a unit suffix, or `None` if it is not recognized.
+fn parse_unit(unit: &str) -> Option<u64> {
+    let (prefix, rest) = match unit.chars().next() {
+        Some(c) => unit.split_at(c.len_utf8()),
+        None => return None,
+    };
+    if prefix == "B" && rest.is_empty() {
+        return Some(1);
+    }
+
+    let multiplier = match prefix {
+        "K" | "k" => KIB,
+        "M" | "m" => MIB,
+        "G" | "g" => GIB,
+        "T" | "t" => TIB,
+        _ => return None,
+    };
+    match rest {
+        "" | "B" | "iB" | "IB" => Some(multiplier),
+        _ => None,
+    }
+}
+
+/// Multiplies a decimal number by `multiplier` without going through floats,
+/// rounding to the nearest byte (halves round up).
+fn scale(number: &str, multiplier: u64, original: &str) -> Result<u64, ByteSizeParseError> {
+    let invalid = || ByteSizeParseError::InvalidNumber(original.to_string());
+    let overflow = || ByteSizeParseError::Overflow(original.to_string());
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if (whole.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+        return Err(invalid());
+    }
+
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| invalid())?
+    };
+    let mut bytes = whole.checked_mul(multiplier).ok_or_else(overflow)?;
+
+    let fraction = fraction.trim_end_matches('0');
+    if !fraction.is_empty() {
+        let digits = u32::try_from(fraction.len()).map_err(|_| invalid())?;
+        let denominator = 10u128.checked_pow(digits).ok_or_else(invalid)?;
+        let numerator: u128 = fraction.parse().map_err(|_| invalid())?;
+        let scaled = numerator * multiplier as u128;
+        let extra = (scaled + denominator / 2) / denominator;
+        let extra = u64::try_from(extra).map_err(|_| overflow())?;
+        bytes = bytes.checked_add(extra).ok_or_else(overflow)?;
+    }
+
+    Ok(bytes)
+}
+
+impl FromStr for ByteSize {
+    type Err = ByteSizeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_default_unit(s, None)
+    }
+}
+
+impl fmt::Display for ByteSize {
+    /// Formats the size with the largest unit that represents it exactly.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (unit, multiplier) in [("TiB", TIB), ("GiB", GIB), ("MiB", MIB), ("KiB", KIB)] {
+            if self.0 >= multiplier && self.0.is_multiple_of(multiplier) {
+                return write!(f, "{}{}", self.0 / multiplier, unit);
+            }
+        }
+        write!(f, "{}B", self.0)
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    /// Accepts a string with a unit, or an integer number of bytes.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ByteSizeVisitor { default_unit: 1 })
+    }
+}
+
+struct ByteSizeVisitor {
+    /// Multiplier for integers and unitless strings
+    default_unit: u64,
+}
+
+impl Visitor<'_> for ByteSizeVisitor {
+    type Value = ByteSize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a size such as 500MB or 1.5GiB")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<ByteSize, E> {
+        value
+            .checked_mul(self.default_unit)
+            .map(ByteSize)
+            .ok_or_else(|| E::custom(format!("size {} is too large", value)))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<ByteSize, E> {
+        let value = u64::try_from(value)
+            .map_err(|_| E::custom(format!("size cannot be negative (got {})", value)))?;
+        self.visit_u64(value)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<ByteSize, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<ByteSize, E> {
+        let default_unit = (self.default_unit != 1).then_some(self.default_unit);
+        ByteSize::parse_with_default_unit(value, default_unit).map_err(E::custom)
+    }
+}
+
+/// Serde helpers for sizes stored in `*_mb` fields, where bare numbers mean MB.
+///
+/// Whole megabyte values are written back as plain numbers so existing
+/// configuration files keep their shape.
+pub mod megabytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(size: &ByteSize, serializer: S) -> Result<S::Ok, S::Error> {
+        if size.0.is_multiple_of(MIB) {
+            serializer.serialize_u64(size.as_mb())
+        } else {
+            size.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ByteSize, D::Error> {
+        deserializer.deserialize_any(ByteSizeVisitor { default_unit: MIB })
+    }
+
+    /// The same helpers for optional fields.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            size: &Option<ByteSize>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match size {
+                Some(size) => super::serialize(size, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<ByteSize>, D::Error> {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(deserialize_with = "super::deserialize")] ByteSize);
+
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|w| w.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!("512B".parse(), Ok(ByteSize::from_bytes(512)));
+        assert_eq!("500MB".parse(), Ok(ByteSize::from_mb(500)));
+        assert_eq!("500 MiB".parse(), Ok(ByteSize::from_mb(500)));
+        assert_eq!("2k".parse(), Ok(ByteSize::from_bytes(2048)));
+        assert_eq!("1.5GiB".parse(), Ok(ByteSize::from_mb(1536)));
+        assert_eq!("1TB".parse(), Ok(ByteSize::from_mb(1024 * 1024)));
+        assert_eq!(".5K".parse(), Ok(ByteSize::from_bytes(512)));
+    }
+
+    #[test]
+    fn test_parse_rounds_to_nearest_byte() {
+        // 1.3 * 1024^3 = 1395864371.2
+        assert_eq!("1.3GB".parse(), Ok(ByteSize::from_bytes(1_395_864_371)));
+        // 0.1 * 1024^3 = 107374182.4
+        assert_eq!("0.1GB".parse(), Ok(ByteSize::from_bytes(107_374_182)));
+        // 2.2 * 1024^4 = 2418925581107.2
+        assert_eq!("2.2TB".parse(), Ok(ByteSize::from_bytes(2_418_925_581_107)));
+        // 0.7 * 1024^3 = 751619276.8
+        assert_eq!("0.7GiB".parse(), Ok(ByteSize::from_bytes(751_619_277)));
+        assert_eq!("1.5B".parse(), Ok(ByteSize::from_bytes(2)));
+        assert_eq!("1.4B".parse(), Ok(ByteSize::from_bytes(1)));
+        assert_eq!(ByteSize::parse_mb("0.3"), Ok(ByteSize::from_bytes(314_573)));
+    }
+
+    #[test]
+    fn test_parse_rejects_ambiguous_values() {
+        assert_eq!(
+            "500".parse::<ByteSize>(),
+            Err(ByteSizeParseError::MissingUnit("500".to_string()))
+        );
+        assert!(matches!(
+            "500Mb".parse::<ByteSize>(),
+            Err(ByteSizeParseError::Bits(_))
+        ));
+        assert!(matches!(
+            "1,5GB".parse::<ByteSize>(),
+            Err(ByteSizeParseError::Comma(_))
+        ));
+        assert!(matches!(
+            "10XB".parse::<ByteSize>(),
+            Err(ByteSizeParseError::UnknownUnit(_))
+        ));
+        assert!(matches!(
+            "MB".parse::<ByteSize>(),
+            Err(ByteSizeParseError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            "-5MB".parse::<ByteSize>(),
+            Err(ByteSizeParseError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            "99999999TB".parse::<ByteSize>(),
+            Err(ByteSizeParseError::Overflow(_))
+        ));
+        assert_eq!("".parse::<ByteSize>(), Err(ByteSizeParseError::Empty));
+    }
+
+    #[test]
+    fn test_parse_mb_defaults_bare_numbers_to_megabytes() {
+        assert_eq!(ByteSize::parse_mb("200"), Ok(ByteSize::from_mb(200)));
+        assert_eq!(ByteSize::parse_mb("1GB"), Ok(ByteSize::from_mb(1024)));
+        assert_eq!(ByteSize::parse_mb("0.5"), Ok(ByteSize::from_bytes(MIB / 2)));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(ByteSize::from_mb(1536).to_string(), "1536MiB");
+        assert_eq!(ByteSize::from_mb(2048).to_string(), "2GiB");
+        assert_eq!(ByteSize::from_bytes(1000).to_string(), "1000B");
+        assert_eq!(ByteSize::from_bytes(0).to_string(), "0B");
+    }
+
+    #[test]
+    fn test_serde() {
+        let size: ByteSize = serde_yaml::from_str("1.5GiB").unwrap();
+        assert_eq!(size, ByteSize::from_mb(1536));
+        let size: ByteSize = serde_yaml::from_str("4096").unwrap();
+        assert_eq!(size, ByteSize::from_bytes(4096));
+        assert_eq!(serde_yaml::to_string(&size).unwrap().trim(), "4KiB");
+    }
+
+    #[test]
+    fn test_megabytes_serde() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Config {
+            #[serde(with = "megabytes")]
+            threshold_mb: ByteSize,
+            #[serde(default, with = "megabytes::option")]
+            limit_mb: Option<ByteSize>,
+        }
+
+        let config: Config = serde_yaml::from_str("threshold_mb: 200\n").unwrap();
+        assert_eq!(config.threshold_mb, ByteSize::from_mb(200));
+        assert_eq!(config.limit_mb, None);
+
+        let config: Config = serde_yaml::from_str("threshold_mb: 0.5\n").unwrap();
+        assert_eq!(config.threshold_mb, ByteSize::from_bytes(MIB / 2));
+
+        let config: Config =
+            serde_yaml::from_str("threshold_mb: 1.5GiB\nlimit_mb: \"512KB\"\n").unwrap();
+        assert_eq!(config.threshold_mb, ByteSize::from_mb(1536));
+        assert_eq!(config.limit_mb, Some(ByteSize::from_bytes(512 * KIB)));
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(yaml.contains("threshold_mb: 1536\n"));
+        assert!(yaml.contains("limit_mb: 512KiB"));
+
+        let err = serde_yaml::from_str::<Config>("threshold_mb: 500Mb\n").unwrap_err();
+        assert!(err.to_string().contains("bits"));
+    }
+}
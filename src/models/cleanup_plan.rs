@@ -1,8 +1,13 @@
 //! Cleanup plan representation for YAML serialization.
 
+use crate::models::{paths, DataStream, PathKey, Protection, ProtectionEvent};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
 
 /// A cleanup plan containing entries to be processed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,21 +16,98 @@ pub struct CleanupPlan {
     pub version: String,
 
     /// Timestamp when the plan was created
+    #[serde(with = "crate::models::timestamp::tolerant")]
     pub created_at: DateTime<Utc>,
 
-    /// Base directory that was scanned
+    /// Base directory that was scanned; the first root of multi-root plans
     pub base_path: PathBuf,
 
+    /// Every scanned root of a multi-root plan, starting with `base_path`;
+    /// empty for plans of a single root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<PathBuf>,
+
     /// List of cleanup entries
     pub entries: Vec<CleanupEntry>,
+
+    /// Per-rule statistics collected while the plan was generated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detection_stats: Vec<RuleStats>,
+
+    /// Detections that protections suppressed or downgraded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protections: Vec<ProtectionEvent>,
+}
+
+/// Coverage statistics for a single detection rule.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleStats {
+    /// Name of the detection rule
+    pub rule_name: String,
+
+    /// Number of entries the rule was evaluated against
+    pub entries_evaluated: u64,
+
+    /// Number of entries attributed to this rule (first match wins)
+    pub entries_flagged: u64,
+
+    /// Total size in bytes of the flagged entries
+    pub bytes_flagged: u64,
+
+    /// Cumulative time spent evaluating the rule, in milliseconds
+    pub evaluation_time_ms: f64,
+
+    /// Path of the largest flagged entry; relative to the base path once
+    /// recorded in a plan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub largest_entry: Option<String>,
+
+    /// Size in bytes of the largest flagged entry
+    #[serde(default)]
+    pub largest_entry_bytes: u64,
+}
+
+impl RuleStats {
+    /// Counts a flagged entry of `size` bytes at `path`.
+    pub fn record_flagged(&mut self, path: &Path, size: u64) {
+        self.entries_flagged += 1;
+        self.bytes_flagged += size;
+        if self.largest_entry.is_none() || size > self.largest_entry_bytes {
+            self.largest_entry = Some(path.to_string_lossy().into_owned());
+            self.largest_entry_bytes = size;
+        }
+    }
+}
+
+/// Entry count and total size for a group of plan entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateStats {
+    /// Number of entries in the group
+    pub count: usize,
+
+    /// Total size in bytes of the entries in the group
+    pub size: u64,
+}
+
+impl AggregateStats {
+    fn add(&mut self, size: u64) {
+        self.count += 1;
+        self.size = self.size.saturating_add(size);
+    }
 }
 
 /// A single entry in a cleanup plan.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CleanupEntry {
-    /// Path relative to base_path
+    /// Path relative to the entry's root, written in Unicode NFC form
+    #[serde(serialize_with = "paths::serialize_nfc")]
     pub path: String,
 
+    /// Index of the entry's root in the plan's `roots`; 0 (the base path)
+    /// in single-root plans
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub root: usize,
+
     /// Size in bytes
     pub size: u64,
 
@@ -40,6 +122,60 @@ pub struct CleanupEntry {
 
     /// Reason why this was flagged
     pub reason: String,
+
+    /// Descendant counts for a directory collapsed in summary-only plans
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapsed: Option<CollapsedSummary>,
+
+    /// NTFS alternate data streams of the file, not included in `size`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub streams: Vec<DataStream>,
+
+    /// Owning user, if the scan collected owners
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+
+    /// The executor refuses to remove this entry, whatever its action
+    ///
+    /// Set for precious files such as mail archives and VM disks.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub never_delete: bool,
+}
+
+/// Contents of a directory entry whose children are not listed in the plan.
+///
+/// Produced by summary-only plan generation; `megamaid expand` replaces the
+/// entry with its immediate children. A few sampled files let reviewers
+/// check what the directory holds without expanding it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollapsedSummary {
+    /// Number of files below the directory
+    pub file_count: u64,
+
+    /// Number of directories below the directory
+    pub dir_count: u64,
+
+    /// Largest files below the directory, largest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub largest: Vec<SampledFile>,
+
+    /// Most recently modified file below the directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newest: Option<SampledFile>,
+}
+
+/// A file sampled from below a collapsed directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledFile {
+    /// Path relative to the collapsed directory
+    #[serde(serialize_with = "paths::serialize_nfc")]
+    pub path: String,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// Last modification time in RFC3339 format
+    pub modified: String,
 }
 
 /// Action to perform on a cleanup entry.
@@ -56,6 +192,34 @@ pub enum CleanupAction {
     Review,
 }
 
+/// Error returned when parsing a [`CleanupAction`] from a string.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Unknown action '{0}' (expected delete, review or keep)")]
+pub struct CleanupActionParseError(pub String);
+
+impl FromStr for CleanupAction {
+    type Err = CleanupActionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "delete" => Ok(Self::Delete),
+            "keep" => Ok(Self::Keep),
+            "review" => Ok(Self::Review),
+            _ => Err(CleanupActionParseError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for CleanupAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Delete => "delete",
+            Self::Keep => "keep",
+            Self::Review => "review",
+        })
+    }
+}
+
 impl CleanupPlan {
     /// Creates a new cleanup plan.
     pub fn new(base_path: PathBuf) -> Self {
@@ -63,10 +227,38 @@ impl CleanupPlan {
             version: "1.0".to_string(),
             created_at: Utc::now(),
             base_path,
+            roots: Vec::new(),
             entries: Vec::new(),
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
+        }
+    }
+
+    /// Returns the scanned roots: `roots` if set, otherwise just the base path.
+    pub fn all_roots(&self) -> &[PathBuf] {
+        if self.roots.is_empty() {
+            std::slice::from_ref(&self.base_path)
+        } else {
+            &self.roots
         }
     }
 
+    /// Returns the root `entry`'s path is relative to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entry.root` is not an index into [`CleanupPlan::all_roots`];
+    /// [`PlanReader`](crate::planner::PlanReader) rejects plans with such
+    /// entries.
+    pub fn root_of(&self, entry: &CleanupEntry) -> &Path {
+        &self.all_roots()[entry.root]
+    }
+
+    /// Returns the absolute path of `entry`.
+    pub fn entry_path(&self, entry: &CleanupEntry) -> PathBuf {
+        self.root_of(entry).join(&entry.path)
+    }
+
     /// Adds an entry to the plan.
     pub fn add_entry(&mut self, entry: CleanupEntry) {
         self.entries.push(entry);
@@ -77,6 +269,16 @@ impl CleanupPlan {
         self.entries.iter().map(|e| e.size).sum()
     }
 
+    /// Returns the number and combined size of alternate data streams across
+    /// all entries; these bytes are not part of [`CleanupPlan::total_size`].
+    pub fn stream_totals(&self) -> AggregateStats {
+        let mut totals = AggregateStats::default();
+        for stream in self.entries.iter().flat_map(|e| &e.streams) {
+            totals.add(stream.size);
+        }
+        totals
+    }
+
     /// Returns the number of entries marked for deletion.
     pub fn delete_count(&self) -> usize {
         self.entries
@@ -85,6 +287,16 @@ impl CleanupPlan {
             .count()
     }
 
+    /// Returns the combined size of the entries marked for deletion, leaving
+    /// out those the executor refuses to remove.
+    pub fn delete_size(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e.action, CleanupAction::Delete) && !e.never_delete)
+            .map(|e| e.size)
+            .sum()
+    }
+
     /// Returns the number of entries marked for review.
     pub fn review_count(&self) -> usize {
         self.entries
@@ -100,6 +312,39 @@ impl CleanupPlan {
             .filter(|e| matches!(e.action, CleanupAction::Keep))
             .count()
     }
+
+    /// Groups entries by lowercase file extension.
+    ///
+    /// Entries without an extension (including most directories) are grouped
+    /// under the empty string.
+    pub fn aggregate_by_extension(&self) -> BTreeMap<String, AggregateStats> {
+        let mut groups: BTreeMap<String, AggregateStats> = BTreeMap::new();
+        for entry in &self.entries {
+            groups.entry(entry.extension()).or_default().add(entry.size);
+        }
+        groups
+    }
+
+    /// Groups entries by the detection rule that flagged them.
+    pub fn aggregate_by_rule(&self) -> BTreeMap<String, AggregateStats> {
+        let mut groups: BTreeMap<String, AggregateStats> = BTreeMap::new();
+        for entry in &self.entries {
+            groups
+                .entry(entry.rule_name.clone())
+                .or_default()
+                .add(entry.size);
+        }
+        groups
+    }
+
+    /// Groups the recorded protection events by protection.
+    pub fn aggregate_by_protection(&self) -> BTreeMap<Protection, AggregateStats> {
+        let mut groups: BTreeMap<Protection, AggregateStats> = BTreeMap::new();
+        for event in &self.protections {
+            groups.entry(event.protection).or_default().add(event.size);
+        }
+        groups
+    }
 }
 
 impl CleanupEntry {
@@ -114,13 +359,65 @@ impl CleanupEntry {
     ) -> Self {
         Self {
             path,
+            root: 0,
             size,
             modified,
             action,
             rule_name,
             reason,
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         }
     }
+
+    /// Returns true if this entry stands in for an unlisted directory tree.
+    pub fn is_collapsed(&self) -> bool {
+        self.collapsed.is_some()
+    }
+
+    /// Returns the entry's stable ID, a hash of its relative path (and root
+    /// index, past the first root).
+    ///
+    /// The ID stays the same across plans of the same tree, so transaction
+    /// logs can be matched against later plan versions. Paths are compared
+    /// under the platform's case and Unicode rules, like [`PathKey`].
+    pub fn id(&self) -> String {
+        let key = PathKey::new(Path::new(&self.path));
+        let hash = match self.root {
+            0 => paths::fnv1a(key.as_str()),
+            root => paths::fnv1a(&format!("{}:{}", root, key.as_str())),
+        };
+        format!("{:016x}", hash)
+    }
+
+    /// Returns where backup mode moves the entry, relative to the backup
+    /// directory: its path, inside a `root-N` directory for entries of
+    /// further roots of a multi-root plan.
+    pub fn backup_path(&self) -> PathBuf {
+        match self.root {
+            0 => PathBuf::from(&self.path),
+            root => Path::new(&format!("root-{}", root)).join(&self.path),
+        }
+    }
+
+    /// Returns the combined size of the entry's alternate data streams.
+    pub fn stream_size(&self) -> u64 {
+        self.streams.iter().map(|s| s.size).sum()
+    }
+
+    /// Returns the lowercase extension of the entry's path, or an empty string.
+    pub fn extension(&self) -> String {
+        Path::new(&self.path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
 }
 
 #[cfg(test)]
@@ -215,6 +512,48 @@ mod tests {
         assert_eq!(plan.keep_count(), 1);
     }
 
+    #[test]
+    fn test_aggregate_by_extension_and_rule() {
+        let mut plan = CleanupPlan::new(PathBuf::from("/test"));
+        for (path, size, rule) in [
+            ("videos/a.MP4", 500, "large_file"),
+            ("videos/b.mp4", 300, "large_file"),
+            ("disk.iso", 200, "large_file"),
+            ("app/target", 1000, "build_artifact"),
+        ] {
+            plan.add_entry(CleanupEntry::new(
+                path.to_string(),
+                size,
+                "2025-11-19T12:00:00Z".to_string(),
+                CleanupAction::Delete,
+                rule.to_string(),
+                "Test".to_string(),
+            ));
+        }
+
+        let by_extension = plan.aggregate_by_extension();
+        assert_eq!(
+            by_extension["mp4"],
+            AggregateStats {
+                count: 2,
+                size: 800
+            }
+        );
+        assert_eq!(by_extension["iso"].size, 200);
+        assert_eq!(by_extension[""].count, 1);
+
+        let by_rule = plan.aggregate_by_rule();
+        assert_eq!(by_rule.len(), 2);
+        assert_eq!(
+            by_rule["large_file"],
+            AggregateStats {
+                count: 3,
+                size: 1000
+            }
+        );
+        assert_eq!(by_rule["build_artifact"].size, 1000);
+    }
+
     #[test]
     fn test_cleanup_action_string_representation() {
         // Test serde serialization produces correct strings
@@ -273,6 +612,158 @@ entries:
         assert_eq!(plan.entries.len(), 1);
         assert_eq!(plan.entries[0].path, "target");
         assert_eq!(plan.entries[0].action, CleanupAction::Delete);
+        assert!(plan.detection_stats.is_empty());
+    }
+
+    #[test]
+    fn test_detection_stats_roundtrip() {
+        let mut plan = CleanupPlan::new(PathBuf::from("/test"));
+        plan.detection_stats.push(RuleStats {
+            rule_name: "large_file".to_string(),
+            entries_evaluated: 10,
+            entries_flagged: 2,
+            bytes_flagged: 4096,
+            evaluation_time_ms: 0.5,
+            largest_entry: Some("video.mkv".to_string()),
+            largest_entry_bytes: 3072,
+        });
+
+        let yaml = serde_yaml::to_string(&plan).unwrap();
+        assert!(yaml.contains("detection_stats"));
+
+        let loaded: CleanupPlan = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.detection_stats, plan.detection_stats);
+    }
+
+    #[test]
+    fn test_streams_roundtrip_and_totals() {
+        let mut plan = CleanupPlan::new(PathBuf::from("/test"));
+        let mut entry = CleanupEntry::new(
+            "setup.exe".to_string(),
+            1000,
+            "2025-11-19T12:00:00Z".to_string(),
+            CleanupAction::Review,
+            "alternate_streams".to_string(),
+            "Test".to_string(),
+        );
+        entry.streams = vec![
+            DataStream {
+                name: "Zone.Identifier".to_string(),
+                size: 26,
+            },
+            DataStream {
+                name: "payload".to_string(),
+                size: 4000,
+            },
+        ];
+        plan.add_entry(entry);
+
+        assert_eq!(plan.entries[0].stream_size(), 4026);
+        assert_eq!(plan.total_size(), 1000);
+        assert_eq!(
+            plan.stream_totals(),
+            AggregateStats {
+                count: 2,
+                size: 4026
+            }
+        );
+
+        let yaml = serde_yaml::to_string(&plan).unwrap();
+        let loaded: CleanupPlan = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.entries[0].streams, plan.entries[0].streams);
+    }
+
+    #[test]
+    fn test_protections_roundtrip_and_aggregate() {
+        use crate::models::ProtectionOutcome;
+
+        let mut plan = CleanupPlan::new(PathBuf::from("/test"));
+        for (path, size, protection) in [
+            ("clients", 100, Protection::KeepRule),
+            ("repo", 5, Protection::RepoRoot),
+            ("archive", 50, Protection::KeepRule),
+        ] {
+            plan.protections.push(ProtectionEvent {
+                path: path.to_string(),
+                size,
+                rule_name: "large_file".to_string(),
+                protection,
+                outcome: ProtectionOutcome::Suppressed,
+                detail: String::new(),
+            });
+        }
+
+        let groups = plan.aggregate_by_protection();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[&Protection::KeepRule],
+            AggregateStats {
+                count: 2,
+                size: 150
+            }
+        );
+
+        let yaml = serde_yaml::to_string(&plan).unwrap();
+        let loaded: CleanupPlan = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.protections, plan.protections);
+    }
+
+    #[test]
+    fn test_empty_detection_stats_not_serialized() {
+        let plan = CleanupPlan::new(PathBuf::from("/test"));
+        let yaml = serde_yaml::to_string(&plan).unwrap();
+        assert!(!yaml.contains("detection_stats"));
+        assert!(!yaml.contains("protections"));
+    }
+
+    #[test]
+    fn test_multiple_roots_roundtrip() {
+        let mut plan = CleanupPlan::new(PathBuf::from("/dev"));
+        plan.roots = vec![PathBuf::from("/dev"), PathBuf::from("/data")];
+        let entry = |root| CleanupEntry {
+            root,
+            ..CleanupEntry::new(
+                "app.iso".to_string(),
+                1000,
+                "2025-11-19T12:00:00Z".to_string(),
+                CleanupAction::Review,
+                "large_file".to_string(),
+                "Large file".to_string(),
+            )
+        };
+        plan.add_entry(entry(0));
+        plan.add_entry(entry(1));
+
+        let yaml = serde_yaml::to_string(&plan).unwrap();
+        let loaded: CleanupPlan = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.all_roots(), plan.roots.as_slice());
+        assert_eq!(
+            loaded.entry_path(&loaded.entries[1]),
+            Path::new("/data/app.iso")
+        );
+        assert_eq!(loaded.root_of(&loaded.entries[0]), Path::new("/dev"));
+        assert_ne!(loaded.entries[0].id(), loaded.entries[1].id());
+        assert_eq!(loaded.entries[1].backup_path(), Path::new("root-1/app.iso"));
+
+        // Single-root plans keep their format
+        let yaml = serde_yaml::to_string(&CleanupPlan::new(PathBuf::from("/dev"))).unwrap();
+        assert!(!yaml.contains("roots"));
+    }
+
+    #[test]
+    fn test_cleanup_action_from_str() {
+        assert_eq!("delete".parse(), Ok(CleanupAction::Delete));
+        assert_eq!("Review".parse(), Ok(CleanupAction::Review));
+        assert_eq!(" keep ".parse(), Ok(CleanupAction::Keep));
+        assert!("remove".parse::<CleanupAction>().is_err());
+
+        for action in [
+            CleanupAction::Delete,
+            CleanupAction::Keep,
+            CleanupAction::Review,
+        ] {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
     }
 
     #[test]
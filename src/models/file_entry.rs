@@ -17,11 +17,51 @@ pub struct FileEntry {
     #[serde(with = "systemtime_serde")]
     pub modified: SystemTime,
 
-    /// Type of entry (file or directory)
+    /// Type of entry (file, directory or link)
     pub entry_type: EntryType,
 
+    /// What a symlink or junction points to, as stored in the link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<PathBuf>,
+
     /// Optional NTFS MFT record number for rename detection (Windows-specific)
     pub file_id: Option<u64>,
+
+    /// NTFS alternate data streams, if the scan enumerated them (not counted in `size`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub streams: Vec<DataStream>,
+
+    /// Cloud placeholder whose contents aren't stored locally (e.g. a
+    /// OneDrive Files On-Demand file); `size` is the size in the cloud
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cloud_placeholder: bool,
+
+    /// Owning user, if the scan collected owners (see
+    /// [`crate::scanner::owner`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+
+    /// Owning group (not on Windows), if the scan collected owners
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+
+    /// Read-only, if the scan collected owners
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub readonly: bool,
+
+    /// Windows system file, if the scan collected owners
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub system: bool,
+}
+
+/// A named NTFS alternate data stream attached to a file (e.g. `Zone.Identifier`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataStream {
+    /// Stream name without the leading `:` or the `:$DATA` type suffix
+    pub name: String,
+
+    /// Size of the stream in bytes
+    pub size: u64,
 }
 
 /// Type of file system entry.
@@ -31,6 +71,10 @@ pub enum EntryType {
     File,
     /// Directory
     Directory,
+    /// Symbolic link, to a file or a directory (size 0)
+    Symlink,
+    /// Windows junction, a link to a directory (size 0)
+    Junction,
 }
 
 /// Custom serde module for SystemTime serialization
@@ -65,7 +109,14 @@ impl FileEntry {
             size,
             modified,
             entry_type,
+            link_target: None,
             file_id: None,
+            streams: Vec::new(),
+            cloud_placeholder: false,
+            owner: None,
+            group: None,
+            readonly: false,
+            system: false,
         }
     }
 
@@ -82,7 +133,14 @@ impl FileEntry {
             size,
             modified,
             entry_type,
+            link_target: None,
             file_id,
+            streams: Vec::new(),
+            cloud_placeholder: false,
+            owner: None,
+            group: None,
+            readonly: false,
+            system: false,
         }
     }
 
@@ -95,6 +153,18 @@ impl FileEntry {
     pub fn is_directory(&self) -> bool {
         matches!(self.entry_type, EntryType::Directory)
     }
+
+    /// Returns true if this entry is a symlink or junction.
+    ///
+    /// Links are never directories, even when they point to one.
+    pub fn is_link(&self) -> bool {
+        matches!(self.entry_type, EntryType::Symlink | EntryType::Junction)
+    }
+
+    /// Returns the total size of the entry's alternate data streams.
+    pub fn stream_size(&self) -> u64 {
+        self.streams.iter().map(|s| s.size).sum()
+    }
 }
 
 #[cfg(test)]
@@ -189,7 +259,7 @@ mod tests {
         );
 
         let mut entries = vec![large.clone(), small.clone()];
-        entries.sort_by(|a, b| b.size.cmp(&a.size));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.size));
 
         assert_eq!(entries[0].size, 1000);
         assert_eq!(entries[1].size, 100);
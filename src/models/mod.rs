@@ -1,7 +1,20 @@
 //! Core data models for file system entries and cleanup plans.
 
+pub mod artifacts;
+pub mod byte_size;
 pub mod cleanup_plan;
 pub mod file_entry;
+pub mod paths;
+pub mod protection;
+pub mod timestamp;
 
-pub use cleanup_plan::{CleanupAction, CleanupEntry, CleanupPlan};
-pub use file_entry::{EntryType, FileEntry};
+pub use artifacts::{in_snapshot_dir, ArtifactKind, OwnArtifact, OwnArtifacts, SNAPSHOT_DIR};
+pub use byte_size::{ByteSize, ByteSizeParseError};
+pub use cleanup_plan::{
+    AggregateStats, CleanupAction, CleanupActionParseError, CleanupEntry, CleanupPlan,
+    CollapsedSummary, RuleStats, SampledFile,
+};
+pub use file_entry::{DataStream, EntryType, FileEntry};
+pub use paths::{paths_equal, strip_path_prefix, PathCase, PathKey};
+pub use protection::{Protection, ProtectionEvent, ProtectionOutcome};
+pub use timestamp::{TimeDisplay, TimestampParseError};
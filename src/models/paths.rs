@@ -0,0 +1,288 @@
+//! Platform-aware path comparison.
+//!
+//! NTFS and the default APFS/HFS+ volumes treat names that differ only in
+//! case as the same file, while `Path` compares bytes. Plan paths are often
+//! edited by hand, so on those platforms `Target/` in a plan and `target` on
+//! disk must compare equal. Comparisons here work on path components, so
+//! separators and trailing slashes don't matter either.
+//!
+//! Names are also compared in Unicode NFC form. macOS, iCloud and some
+//! archivers produce decomposed (NFD) names, so `café` may be stored as `cafe`
+//! followed by a combining accent. Plans store NFC paths (see
+//! [`serialize_nfc`]); on filesystems that keep the bytes as given (ext4,
+//! NTFS), [`find_on_disk`] locates the differently normalized name.
+
+use serde::Serializer;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+/// How path components are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathCase {
+    /// Components must match exactly (Linux and most Unix filesystems)
+    Sensitive,
+
+    /// Components that differ only in case are equal (Windows, macOS)
+    Insensitive,
+}
+
+impl PathCase {
+    /// Returns the convention of the platform's default filesystem.
+    pub const fn native() -> Self {
+        if cfg!(any(windows, target_os = "macos")) {
+            Self::Insensitive
+        } else {
+            Self::Sensitive
+        }
+    }
+
+    /// Returns the comparison key for `path`.
+    pub fn key(self, path: &Path) -> PathKey {
+        let parts: Vec<String> = path
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .map(|c| self.fold(c.as_os_str()))
+            .collect();
+        PathKey(parts.join("/"))
+    }
+
+    /// Returns true if both paths refer to the same location.
+    pub fn eq(self, a: &Path, b: &Path) -> bool {
+        self.strip_prefix(a, b)
+            .is_some_and(|rest| rest.as_os_str().is_empty())
+    }
+
+    /// Returns true if `base` is `path` or one of its ancestors.
+    pub fn starts_with(self, path: &Path, base: &Path) -> bool {
+        self.strip_prefix(path, base).is_some()
+    }
+
+    /// Returns `path` relative to `base`, keeping the casing of `path`.
+    pub fn strip_prefix<'a>(self, path: &'a Path, base: &Path) -> Option<&'a Path> {
+        let mut rest = path.components();
+        for base_part in base.components().filter(|c| *c != Component::CurDir) {
+            let mut part = rest.next()?;
+            while part == Component::CurDir {
+                part = rest.next()?;
+            }
+            if !self.component_eq(part, base_part) {
+                return None;
+            }
+        }
+        Some(rest.as_path())
+    }
+
+    fn component_eq(self, a: Component, b: Component) -> bool {
+        a.as_os_str() == b.as_os_str() || self.fold(a.as_os_str()) == self.fold(b.as_os_str())
+    }
+
+    /// Returns the NFC form of a name, lowercased if case is ignored.
+    fn fold(self, name: &OsStr) -> String {
+        let part: String = name.to_string_lossy().nfc().collect();
+        match self {
+            Self::Sensitive => part,
+            Self::Insensitive => part.to_lowercase(),
+        }
+    }
+}
+
+/// A path normalized for hashing and equality under the platform's rules.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathKey(String);
+
+impl PathKey {
+    /// Builds the key for `path` using [`PathCase::native`].
+    pub fn new(path: &Path) -> Self {
+        PathCase::native().key(path)
+    }
+}
+
+impl PathKey {
+    /// Returns the normalized path, with `/` separators.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// 64-bit FNV-1a; stable across platforms, processes and Rust versions.
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Returns true if both paths refer to the same location on this platform.
+pub fn paths_equal(a: &Path, b: &Path) -> bool {
+    PathCase::native().eq(a, b)
+}
+
+/// Returns `path` relative to `base` under this platform's rules.
+pub fn strip_path_prefix<'a>(path: &'a Path, base: &Path) -> Option<&'a Path> {
+    PathCase::native().strip_prefix(path, base)
+}
+
+/// Returns the NFC form of a plan path.
+pub fn normalize_nfc(path: &str) -> String {
+    path.nfc().collect()
+}
+
+/// Serializes a plan path in NFC form.
+pub fn serialize_nfc<S>(path: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&normalize_nfc(path))
+}
+
+/// Returns the path as it exists on disk, or `None` if nothing matches.
+///
+/// `path` itself is returned when it exists. Otherwise each missing component
+/// is looked up among its parent's entries, matching names that differ only
+/// in Unicode normalization (or in case, where the platform ignores case).
+pub fn find_on_disk(path: &Path) -> Option<PathBuf> {
+    if path.symlink_metadata().is_ok() {
+        return Some(path.to_path_buf());
+    }
+
+    let case = PathCase::native();
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        let candidate = resolved.join(component);
+        if !matches!(component, Component::Normal(_)) || candidate.symlink_metadata().is_ok() {
+            resolved = candidate;
+            continue;
+        }
+
+        let parent = if resolved.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            resolved.as_path()
+        };
+        let wanted = case.fold(component.as_os_str());
+        let found = fs::read_dir(parent)
+            .ok()?
+            .filter_map(Result::ok)
+            .find(|e| case.fold(&e.file_name()) == wanted)?;
+        resolved.push(found.file_name());
+    }
+    Some(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensitive_comparison() {
+        let case = PathCase::Sensitive;
+
+        assert!(case.eq(Path::new("a/target"), Path::new("a/target/")));
+        assert!(case.eq(Path::new("./a/target"), Path::new("a/target")));
+        assert!(!case.eq(Path::new("a/Target"), Path::new("a/target")));
+        assert_ne!(
+            case.key(Path::new("a/Target")),
+            case.key(Path::new("a/target"))
+        );
+    }
+
+    #[test]
+    fn test_insensitive_comparison() {
+        let case = PathCase::Insensitive;
+
+        assert!(case.eq(Path::new("a/Target/"), Path::new("A/target")));
+        assert!(!case.eq(Path::new("a/target"), Path::new("a/targets")));
+        assert_eq!(
+            case.key(Path::new("Projects/Target/")),
+            case.key(Path::new("projects/target"))
+        );
+        assert!(case.eq(Path::new("CAFÉ"), Path::new("café")));
+    }
+
+    #[test]
+    fn test_strip_prefix_keeps_path_casing() {
+        let case = PathCase::Insensitive;
+
+        assert_eq!(
+            case.strip_prefix(Path::new("/Data/Projects/App"), Path::new("/data/projects")),
+            Some(Path::new("App"))
+        );
+        assert_eq!(
+            case.strip_prefix(Path::new("/data/projects"), Path::new("/data/projects")),
+            Some(Path::new(""))
+        );
+        assert_eq!(
+            case.strip_prefix(
+                Path::new("/data/projects-old/x"),
+                Path::new("/data/projects")
+            ),
+            None
+        );
+        assert_eq!(
+            PathCase::Sensitive.strip_prefix(Path::new("/Data/x"), Path::new("/data")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_starts_with_is_component_wise() {
+        let case = PathCase::Insensitive;
+
+        assert!(case.starts_with(Path::new("Target/debug/app"), Path::new("target")));
+        assert!(!case.starts_with(Path::new("target-notes.bin"), Path::new("target")));
+        assert!(!case.starts_with(Path::new("target"), Path::new("target/debug")));
+    }
+
+    #[test]
+    fn test_decomposed_names_compare_equal() {
+        let composed = Path::new("photos/caf\u{e9}.jpg");
+        let decomposed = Path::new("photos/cafe\u{301}.jpg");
+
+        for case in [PathCase::Sensitive, PathCase::Insensitive] {
+            assert!(case.eq(composed, decomposed));
+            assert_eq!(case.key(composed), case.key(decomposed));
+            assert_eq!(
+                case.strip_prefix(
+                    Path::new("/Fotos/cafe\u{301}/a.jpg"),
+                    Path::new("/Fotos/caf\u{e9}")
+                ),
+                Some(Path::new("a.jpg"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_nfc() {
+        assert_eq!(
+            normalize_nfc("cafe\u{301}/A\u{30a}.txt"),
+            "caf\u{e9}/\u{c5}.txt"
+        );
+        assert_eq!(normalize_nfc("plain/path"), "plain/path");
+    }
+
+    #[test]
+    fn test_find_on_disk_matches_other_normalization() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().join("cafe\u{301}");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("men\u{fc}.txt"), "x").unwrap();
+
+        let wanted = temp.path().join("caf\u{e9}").join("menu\u{308}.txt");
+        let found = find_on_disk(&wanted).unwrap();
+
+        assert!(found.exists());
+        assert!(paths_equal(&found, &wanted));
+        assert!(find_on_disk(&temp.path().join("missing.txt")).is_none());
+    }
+
+    #[test]
+    fn test_native_case() {
+        let expected = if cfg!(any(windows, target_os = "macos")) {
+            PathCase::Insensitive
+        } else {
+            PathCase::Sensitive
+        };
+        assert_eq!(PathCase::native(), expected);
+    }
+}
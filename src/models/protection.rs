@@ -0,0 +1,101 @@
+//! Audit records of detections changed by protections.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A safeguard that can stop an entry from being flagged or deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protection {
+    /// Source code file or source directory (`src/`, `.git/`, ...)
+    SourceFile,
+
+    /// Directory holding a version control repository
+    RepoRoot,
+
+    /// Directory holding a project manifest such as `Cargo.toml`
+    Manifest,
+
+    /// User-configured keep rule
+    KeepRule,
+
+    /// Precious file class such as mail archives or VM disks
+    Precious,
+
+    /// megamaid's own plan, log, backup or cache, or a directory holding one
+    OwnArtifact,
+
+    /// Build artifacts of a project touched recently
+    ActiveProject,
+}
+
+impl fmt::Display for Protection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::SourceFile => "source file",
+            Self::RepoRoot => "repository root",
+            Self::Manifest => "project manifest",
+            Self::KeepRule => "keep rule",
+            Self::Precious => "precious file",
+            Self::OwnArtifact => "megamaid artifact",
+            Self::ActiveProject => "active project",
+        };
+        f.write_str(label)
+    }
+}
+
+/// What a protection did to a detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectionOutcome {
+    /// The entry was left out of the plan
+    Suppressed,
+
+    /// The entry was planned for review instead of deletion
+    Downgraded,
+}
+
+/// A detection that a protection suppressed or downgraded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectionEvent {
+    /// Path of the entry; relative to the base path once recorded in a plan,
+    /// or absolute for entries of a multi-root plan's further roots
+    pub path: String,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// Rule that flagged (or would have flagged) the entry
+    pub rule_name: String,
+
+    /// Protection that applied
+    pub protection: Protection,
+
+    /// What the protection did
+    pub outcome: ProtectionOutcome,
+
+    /// Specifics, such as the keep rule's name or the marker file found
+    pub detail: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protection_serializes_snake_case() {
+        let event = ProtectionEvent {
+            path: "src".to_string(),
+            size: 1,
+            rule_name: "large_file".to_string(),
+            protection: Protection::SourceFile,
+            outcome: ProtectionOutcome::Suppressed,
+            detail: "source directory".to_string(),
+        };
+
+        let yaml = serde_yaml::to_string(&event).unwrap();
+        assert!(yaml.contains("protection: source_file"));
+        assert!(yaml.contains("outcome: suppressed"));
+        assert_eq!(Protection::RepoRoot.to_string(), "repository root");
+    }
+}
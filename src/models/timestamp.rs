@@ -0,0 +1,215 @@
+//! Timestamp formatting and parsing.
+//!
+//! Everything megamaid writes (plan `modified` fields, plan, log, snapshot
+//! and verification timestamps) is RFC 3339 in UTC with an explicit `Z`
+//! offset, e.g. `2025-11-19T12:00:00.5Z`. Reading is more lenient so files
+//! written by older versions keep loading: besides RFC 3339 with any offset,
+//! [`parse`] accepts date-times without an offset (taken as UTC, which is
+//! what they always were), a space instead of the `T`, RFC 2822 and Unix
+//! seconds.
+//!
+//! [`TimeDisplay`] formats timestamps for people, in UTC or local time.
+
+use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use thiserror::Error;
+
+/// Error returned for a string that isn't a timestamp in any known format.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unrecognized timestamp: {0:?}")]
+pub struct TimestampParseError(String);
+
+/// Offset-less layouts written by older versions, interpreted as UTC.
+const NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// Formats `time` as RFC 3339 with a `Z` offset, keeping sub-second digits.
+pub fn format(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parses a timestamp in RFC 3339 or one of the historical formats.
+pub fn parse(value: &str) -> Result<DateTime<Utc>, TimestampParseError> {
+    let value = value.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    // RFC 3339 allows a space between date and time
+    if let Ok(time) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%:z") {
+        return Ok(time.with_timezone(&Utc));
+    }
+    for format in NAIVE_FORMATS {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(time.and_utc());
+        }
+    }
+    if let Ok(time) = DateTime::parse_from_rfc2822(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Some(time) = value
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+    {
+        return Ok(time);
+    }
+
+    Err(TimestampParseError(value.to_string()))
+}
+
+/// Time zone used to show timestamps in reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeDisplay {
+    /// Coordinated Universal Time
+    #[default]
+    Utc,
+
+    /// The system's local time zone
+    Local,
+}
+
+impl TimeDisplay {
+    /// Formats `time` for display, e.g. `2025-11-19 13:00:00 +01:00`.
+    pub fn format(self, time: DateTime<Utc>) -> String {
+        match self {
+            Self::Utc => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            Self::Local => time
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S %:z")
+                .to_string(),
+        }
+    }
+}
+
+/// Serde helpers for `DateTime<Utc>` fields: written with [`format()`], read
+/// with [`parse`] (strings or Unix seconds).
+pub mod tolerant {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(*time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+
+    /// The same helpers for optional fields.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            time: &Option<DateTime<Utc>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match time {
+                Some(time) => super::serialize(time, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<DateTime<Utc>>, D::Error> {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(deserialize_with = "super::deserialize")] DateTime<Utc>);
+
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|w| w.0))
+        }
+    }
+}
+
+struct TimestampVisitor;
+
+impl Visitor<'_> for TimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an RFC 3339 timestamp")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        parse(value).map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, secs: i64) -> Result<Self::Value, E> {
+        Utc.timestamp_opt(secs, 0)
+            .single()
+            .ok_or_else(|| E::custom(format!("timestamp out of range: {}", secs)))
+    }
+
+    fn visit_u64<E: de::Error>(self, secs: u64) -> Result<Self::Value, E> {
+        let secs = i64::try_from(secs)
+            .map_err(|_| E::custom(format!("timestamp out of range: {}", secs)))?;
+        self.visit_i64(secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_historical_formats() {
+        let expected = Utc.with_ymd_and_hms(2025, 11, 19, 12, 0, 0).unwrap();
+        for value in [
+            "2025-11-19T12:00:00Z",
+            "2025-11-19T12:00:00+00:00",
+            "2025-11-19T13:00:00+01:00",
+            "2025-11-19 12:00:00+00:00",
+            "2025-11-19T12:00:00",
+            "2025-11-19 12:00:00",
+            "Wed, 19 Nov 2025 12:00:00 +0000",
+            "1763553600",
+        ] {
+            assert_eq!(parse(value), Ok(expected), "{}", value);
+        }
+        assert!(parse("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_format_uses_z_offset() {
+        let time = Utc.with_ymd_and_hms(2025, 11, 19, 12, 0, 0).unwrap();
+        assert_eq!(format(time), "2025-11-19T12:00:00Z");
+
+        let precise = time + chrono::Duration::milliseconds(500);
+        assert_eq!(format(precise), "2025-11-19T12:00:00.500Z");
+        assert_eq!(parse(&format(precise)), Ok(precise));
+
+        assert_eq!(TimeDisplay::Utc.format(time), "2025-11-19 12:00:00 UTC");
+        let local = TimeDisplay::Local.format(time);
+        assert_eq!(local.len(), "2025-11-19 12:00:00 +00:00".len());
+    }
+
+    #[test]
+    fn test_tolerant_serde() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Record {
+            #[serde(with = "tolerant")]
+            at: DateTime<Utc>,
+            #[serde(default, with = "tolerant::option")]
+            until: Option<DateTime<Utc>>,
+        }
+
+        let record: Record = serde_yaml::from_str("at: 2025-11-19 12:00:00\n").unwrap();
+        assert_eq!(
+            record.at,
+            Utc.with_ymd_and_hms(2025, 11, 19, 12, 0, 0).unwrap()
+        );
+        assert_eq!(record.until, None);
+
+        let record: Record = serde_json::from_str(r#"{"at":1763553600,"until":null}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"at":"2025-11-19T12:00:00Z","until":null}"#
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Entry-by-entry parsing of large YAML plans.
+//!
+//! Parsing a YAML document with serde_yaml holds all of its parse events in
+//! memory before the first value is produced, so a 300 MB plan costs several
+//! times its size. Plans are block-style mappings whose `entries` sequence
+//! makes up nearly all of the file, which allows splitting them at line
+//! boundaries instead: every item of the sequence is parsed on its own and
+//! handed over right away, and only the small remaining keys are parsed as
+//! one document.
+//!
+//! Splitting needs `entries:` to be a top-level key with a block sequence
+//! below it, which is how plans are written. Plans laid out differently, such
+//! as with a flow sequence (`entries: [...]`), are still read, but their
+//! entries are parsed together with the other keys.
+
+use crate::models::{timestamp, CleanupEntry, CleanupPlan, ProtectionEvent, RuleStats};
+use crate::planner::reader::ReadError;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// Top-level plan keys other than a block `entries` sequence.
+#[derive(Deserialize)]
+struct Header {
+    version: String,
+    #[serde(with = "timestamp::tolerant")]
+    created_at: DateTime<Utc>,
+    base_path: PathBuf,
+    #[serde(default)]
+    roots: Vec<PathBuf>,
+    #[serde(default)]
+    detection_stats: Vec<RuleStats>,
+    #[serde(default)]
+    protections: Vec<ProtectionEvent>,
+    /// Entries written inline, e.g. `entries: []`
+    #[serde(default)]
+    entries: Option<Vec<CleanupEntry>>,
+}
+
+/// Reads a YAML plan from `reader`, passing entries to `on_entry` one at a
+/// time.
+///
+/// Returns the plan metadata with an empty `entries` list.
+pub(crate) fn read_yaml<R: BufRead>(
+    reader: R,
+    on_entry: &mut dyn FnMut(CleanupEntry),
+) -> Result<CleanupPlan, ReadError> {
+    let mut header = String::new();
+    let mut item = String::new();
+    let mut in_entries = false;
+    let mut saw_entries = false;
+    let mut item_indent: Option<usize> = None;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        match line.trim_end() {
+            "---" if header.is_empty() && !saw_entries => continue,
+            "..." => break,
+            _ => {}
+        }
+
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let is_content = !trimmed.is_empty() && !trimmed.starts_with('#');
+
+        // A new top-level key ends the previous one
+        if indent == 0 && is_content && !trimmed.starts_with('-') {
+            flush_item(&mut item, on_entry)?;
+            in_entries = starts_block_entries(line);
+            if in_entries {
+                saw_entries = true;
+                item_indent = None;
+                continue;
+            }
+        }
+
+        if !in_entries {
+            header.push_str(line);
+            header.push('\n');
+            continue;
+        }
+
+        // Each item of the sequence starts with a dash at the same indent
+        if is_content && (trimmed == "-" || trimmed.starts_with("- ")) {
+            match item_indent {
+                None => item_indent = Some(indent),
+                Some(first) if first == indent => flush_item(&mut item, on_entry)?,
+                Some(_) => {}
+            }
+        }
+        item.push_str(line);
+        item.push('\n');
+    }
+    flush_item(&mut item, on_entry)?;
+
+    let header: Header = serde_yaml::from_str(&header)?;
+    match header.entries {
+        Some(entries) => entries.into_iter().for_each(&mut *on_entry),
+        None if saw_entries => {}
+        None => {
+            return Err(ReadError::Deserialization(
+                <serde_yaml::Error as serde::de::Error>::missing_field("entries"),
+            ))
+        }
+    }
+
+    Ok(CleanupPlan {
+        version: header.version,
+        created_at: header.created_at,
+        base_path: header.base_path,
+        roots: header.roots,
+        entries: Vec::new(),
+        detection_stats: header.detection_stats,
+        protections: header.protections,
+    })
+}
+
+/// Returns true if `line` is an `entries:` key with nothing but a comment
+/// after it, so a block sequence follows.
+fn starts_block_entries(line: &str) -> bool {
+    line.strip_prefix("entries:")
+        .map(str::trim)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('#'))
+}
+
+/// Parses the buffered sequence item and passes its entry on.
+fn flush_item(item: &mut String, on_entry: &mut dyn FnMut(CleanupEntry)) -> Result<(), ReadError> {
+    if item.trim().is_empty() {
+        item.clear();
+        return Ok(());
+    }
+
+    let entries: Vec<CleanupEntry> = serde_yaml::from_str(item)?;
+    entries.into_iter().for_each(&mut *on_entry);
+    item.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(yaml: &str) -> Result<(CleanupPlan, Vec<CleanupEntry>), ReadError> {
+        let mut entries = Vec::new();
+        let plan = read_yaml(Cursor::new(yaml), &mut |entry| entries.push(entry))?;
+        Ok((plan, entries))
+    }
+
+    #[test]
+    fn test_reads_indented_items_and_trailing_keys() {
+        let yaml = "---\n\
+            version: \"0.1.0\"\n\
+            created_at: \"2025-11-19T12:00:00Z\"\n\
+            base_path: /test\n\
+            entries: # two entries\n\
+            \x20 - path: a\n\
+            \x20   size: 1\n\
+            \x20   modified: \"2025-11-19T12:00:00Z\"\n\
+            \x20   action: delete\n\
+            \x20   rule_name: build_artifact\n\
+            \x20   reason: |\n\
+            \x20     Build output\n\
+            \x20     - not an item\n\
+            # a comment\n\
+            \n\
+            \x20 - path: b\n\
+            \x20   size: 2\n\
+            \x20   modified: \"2025-11-19T12:00:00Z\"\n\
+            \x20   action: review\n\
+            \x20   rule_name: large_file\n\
+            \x20   reason: Large file\n\
+            detection_stats:\n\
+            - rule_name: large_file\n\
+            \x20 entries_evaluated: 2\n\
+            \x20 entries_flagged: 1\n\
+            \x20 bytes_flagged: 2\n\
+            \x20 evaluation_time_ms: 0.1\n";
+
+        let (plan, entries) = read(yaml).unwrap();
+        assert_eq!(plan.base_path, PathBuf::from("/test"));
+        assert_eq!(plan.detection_stats.len(), 1);
+        let paths: Vec<_> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["a", "b"]);
+        assert_eq!(entries[0].reason, "Build output\n- not an item\n");
+    }
+
+    #[test]
+    fn test_reads_inline_and_missing_entries() {
+        let header = "version: \"0.1.0\"\ncreated_at: \"2025-11-19T12:00:00Z\"\nbase_path: /test\n";
+
+        let (_, entries) = read(&format!("{}entries: []\n", header)).unwrap();
+        assert!(entries.is_empty());
+
+        assert!(read(header).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Transparent zstd compression for plan and log files.
+//!
+//! Files whose name ends in `.zst` (e.g. `cleanup-plan.yaml.zst`) are
+//! compressed; everything else is read and written as plain text. Both
+//! directions stream, so memory use does not grow with file size.
+//!
+//! When encryption is on (see [`crate::planner::encryption`]), the
+//! compressed or plain content is encrypted as well, and encrypted files are
+//! decrypted on reading whatever their name.
+
+use crate::planner::encryption::{self, EncryptingWriter, EncryptionKey};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Compression level used for new files (zstd default).
+const ZSTD_LEVEL: i32 = 3;
+
+/// Returns true if the path should be zstd-compressed.
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zst"))
+}
+
+/// Opens a file for buffered reading, decrypting and decompressing if
+/// needed.
+pub fn open_reader(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    open_reader_with(path, encryption::read_key().as_ref())
+}
+
+fn open_reader_with(path: &Path, key: Option<&EncryptionKey>) -> io::Result<Box<dyn BufRead>> {
+    let content = encryption::decrypting(File::open(path)?, key)?;
+    if is_compressed(path) {
+        Ok(Box::new(BufReader::new(zstd::Decoder::new(content)?)))
+    } else {
+        Ok(Box::new(BufReader::new(content)))
+    }
+}
+
+/// Streaming writer that compresses when the target is a `.zst` file.
+///
+/// Call [`CompressedWriter::finish`] to flush, finalize the zstd frame and
+/// sync the file to disk.
+pub enum CompressedWriter {
+    /// Uncompressed output
+    Plain(BufWriter<FileOutput>),
+
+    /// zstd-compressed output
+    Zstd(zstd::Encoder<'static, BufWriter<FileOutput>>),
+}
+
+/// The file under a [`CompressedWriter`], encrypted when encryption is on.
+pub enum FileOutput {
+    /// Unencrypted file
+    Plain(File),
+
+    /// Encrypted file
+    Encrypted(EncryptingWriter<File>),
+}
+
+impl CompressedWriter {
+    /// Creates `path` for writing, compressing if `compress` is set.
+    pub fn create(path: &Path, compress: bool) -> io::Result<Self> {
+        Self::create_with(path, compress, encryption::write_key().as_ref())
+    }
+
+    fn create_with(path: &Path, compress: bool, key: Option<&EncryptionKey>) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let output = match key {
+            Some(key) => FileOutput::Encrypted(EncryptingWriter::new(file, key)?),
+            None => FileOutput::Plain(file),
+        };
+        let buffered = BufWriter::new(output);
+        if compress {
+            Ok(Self::Zstd(zstd::Encoder::new(buffered, ZSTD_LEVEL)?))
+        } else {
+            Ok(Self::Plain(buffered))
+        }
+    }
+
+    /// Flushes all data and syncs the underlying file.
+    pub fn finish(self) -> io::Result<()> {
+        let buffered = match self {
+            Self::Plain(writer) => writer,
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        let file = match buffered.into_inner().map_err(|e| e.into_error())? {
+            FileOutput::Plain(file) => file,
+            FileOutput::Encrypted(writer) => writer.finish()?,
+        };
+        file.sync_all()
+    }
+}
+
+impl Write for FileOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            Self::Encrypted(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            Self::Encrypted(writer) => writer.flush(),
+        }
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_compressed() {
+        assert!(is_compressed(Path::new("plan.yaml.zst")));
+        assert!(is_compressed(Path::new("log.yaml.ZST")));
+        assert!(!is_compressed(Path::new("plan.yaml")));
+        assert!(!is_compressed(Path::new("plan")));
+    }
+
+    #[test]
+    fn test_roundtrip_compressed() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("data.yaml.zst");
+        let content = "entries:\n".repeat(1000);
+
+        let mut writer = CompressedWriter::create(&path, true).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        writer.finish().unwrap();
+
+        // Compressed output is much smaller than the input
+        assert!(std::fs::metadata(&path).unwrap().len() < content.len() as u64 / 10);
+
+        let mut read_back = String::new();
+        open_reader(&path)
+            .unwrap()
+            .read_to_string(&mut read_back)
+            .unwrap();
+        assert_eq!(read_back, content);
+    }
+
+    #[test]
+    fn test_roundtrip_plain() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("data.yaml");
+
+        let mut writer = CompressedWriter::create(&path, false).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_roundtrip_encrypted_and_compressed() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("data.yaml.zst");
+        let key = EncryptionKey::from_base64(&format!("{}=", "A".repeat(43)), "test").unwrap();
+
+        let mut writer = CompressedWriter::create_with(&path, true, Some(&key)).unwrap();
+        writer.write_all(b"path: secret.txt").unwrap();
+        writer.finish().unwrap();
+
+        let raw = std::fs::read(&path).unwrap();
+        assert!(raw.starts_with(encryption::MAGIC));
+
+        let mut read_back = String::new();
+        open_reader_with(&path, Some(&key))
+            .unwrap()
+            .read_to_string(&mut read_back)
+            .unwrap();
+        assert_eq!(read_back, "path: secret.txt");
+        assert!(open_reader_with(&path, None).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! Encryption at rest for plan, log and scan cache files.
+//!
+//! Plans, transaction logs, scan caches and raw scans list every file of the
+//! scanned trees, which is sensitive on shared or managed machines. With
+//! `encryption.enabled`, new files are encrypted with a 256-bit key taken
+//! from `MEGAMAID_ENCRYPTION_KEY`, the keyring secret named by
+//! `encryption.key_secret` or `encryption.key_file`; readers recognize
+//! encrypted files by their header and decrypt them whenever a key is
+//! configured, whatever the setting, so turning encryption off (or passing
+//! `--no-encrypt`) never locks out existing files.
+//!
+//! # Format
+//!
+//! A file starts with [`MAGIC`] and a random 19-byte nonce prefix. The
+//! (possibly compressed) content follows in segments of up to 64 KiB,
+//! encrypted with XChaCha20-Poly1305 in the STREAM construction
+//! (`aead::stream`, big-endian 32-bit segment counter and last-segment
+//! flag in the nonce). Each segment is authenticated before its plaintext
+//! is released, and the last-segment flag makes truncated files fail
+//! instead of reading as shorter ones. Both directions stream, so memory use
+//! doesn't grow with file size.
+
+use crate::config::secrets::{self, SecretError};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::XChaCha20Poly1305;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::RwLock;
+use thiserror::Error;
+
+/// Environment variable holding the base64 encryption key.
+pub const KEY_ENV: &str = "MEGAMAID_ENCRYPTION_KEY";
+
+/// Header of encrypted files.
+pub const MAGIC: &[u8; 16] = b"megamaid-enc-v2\n";
+
+/// Bytes of the random nonce prefix after the header.
+const NONCE_LEN: usize = 19;
+
+/// Plaintext bytes per segment.
+const SEGMENT_LEN: usize = 64 * 1024;
+
+/// Bytes of the Poly1305 tag after each segment.
+const TAG_LEN: usize = 16;
+
+/// Key used for new files and for reading encrypted ones.
+static ACTIVE: RwLock<Option<Active>> = RwLock::new(None);
+
+struct Active {
+    key: EncryptionKey,
+    encrypt_new: bool,
+}
+
+/// Errors loading an encryption key.
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    /// The key isn't 32 bytes of base64
+    #[error("invalid encryption key from {0}: expected 32 bytes, base64-encoded")]
+    InvalidKey(String),
+
+    #[error("Failed to read encryption key file {path}: {source}")]
+    KeyFile {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// The keyring secret named for the key isn't stored
+    #[error("encryption key secret '{0}' is not in the keyring; store it with `megamaid secrets set {0}`")]
+    MissingSecret(String),
+
+    #[error("Failed to read encryption key secret: {0}")]
+    Secret(#[from] SecretError),
+}
+
+/// A 256-bit key for encrypting files.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Parses a base64-encoded key; `source` names where it came from, for
+    /// errors.
+    pub fn from_base64(text: &str, source: &str) -> Result<Self, EncryptionError> {
+        let bytes = STANDARD
+            .decode(text.trim())
+            .map_err(|_| EncryptionError::InvalidKey(source.to_string()))?;
+        let key = bytes
+            .try_into()
+            .map_err(|_| EncryptionError::InvalidKey(source.to_string()))?;
+        Ok(Self(key))
+    }
+
+    /// Returns the key from [`KEY_ENV`] if set, otherwise from keyring
+    /// secret `key_secret` or file `key_file`, in that order, or `None` if
+    /// none is given.
+    pub fn resolve(
+        key_secret: Option<&str>,
+        key_file: Option<&Path>,
+    ) -> Result<Option<Self>, EncryptionError> {
+        if let Some(text) = std::env::var(KEY_ENV).ok().filter(|t| !t.trim().is_empty()) {
+            return Self::from_base64(&text, KEY_ENV).map(Some);
+        }
+        if let Some(name) = key_secret {
+            let text = secrets::get_secret(name)?
+                .ok_or_else(|| EncryptionError::MissingSecret(name.to_string()))?;
+            return Self::from_base64(&text, &format!("secret '{}'", name)).map(Some);
+        }
+        let Some(path) = key_file else {
+            return Ok(None);
+        };
+        let text = std::fs::read_to_string(path).map_err(|source| EncryptionError::KeyFile {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::from_base64(&text, &path.display().to_string()).map(Some)
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Sets the key for this process: files are decrypted with it, and new
+/// files encrypted if `encrypt_new` is set. `None` clears it.
+pub fn configure(key: Option<EncryptionKey>, encrypt_new: bool) {
+    let mut active = ACTIVE.write().unwrap_or_else(|e| e.into_inner());
+    *active = key.map(|key| Active { key, encrypt_new });
+}
+
+/// Returns the key new files are encrypted with, if encryption is on.
+pub fn write_key() -> Option<EncryptionKey> {
+    let active = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+    active
+        .as_ref()
+        .filter(|a| a.encrypt_new)
+        .map(|a| a.key.clone())
+}
+
+/// Returns the key encrypted files are read with, if one is configured.
+pub fn read_key() -> Option<EncryptionKey> {
+    let active = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+    active.as_ref().map(|a| a.key.clone())
+}
+
+/// Opens `reader`, decrypting its content if it starts with [`MAGIC`].
+///
+/// Fails if the content is encrypted and `key` is `None`.
+pub fn decrypting(
+    mut reader: impl Read + 'static,
+    key: Option<&EncryptionKey>,
+) -> io::Result<Box<dyn Read>> {
+    let mut head = Vec::with_capacity(MAGIC.len());
+    (&mut reader)
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut head)?;
+    if head != MAGIC {
+        return Ok(Box::new(io::Cursor::new(head).chain(reader)));
+    }
+
+    let key = key.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "the file is encrypted; set {} or encryption.key_file to read it",
+                KEY_ENV
+            ),
+        )
+    })?;
+    let mut nonce = [0u8; NONCE_LEN];
+    reader.read_exact(&mut nonce)?;
+    Ok(Box::new(DecryptingReader::new(reader, key, &nonce)))
+}
+
+/// Writer encrypting everything written to it into `inner`.
+///
+/// Call [`EncryptingWriter::finish`] to write the last segment; files
+/// without it fail to decrypt.
+pub struct EncryptingWriter<W: Write> {
+    inner: W,
+    encryptor: EncryptorBE32<XChaCha20Poly1305>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    /// Writes the header to `inner` and starts encrypting with a new nonce.
+    pub fn new(mut inner: W, key: &EncryptionKey) -> io::Result<Self> {
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::fill(&mut nonce).map_err(|e| io::Error::other(e.to_string()))?;
+        inner.write_all(MAGIC)?;
+        inner.write_all(&nonce)?;
+
+        Ok(Self {
+            inner,
+            encryptor: EncryptorBE32::new((&key.0).into(), (&nonce).into()),
+            buffer: Vec::with_capacity(SEGMENT_LEN + 1),
+        })
+    }
+
+    /// Writes the last segment and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let segment = self
+            .encryptor
+            .encrypt_last(self.buffer.as_slice())
+            .map_err(|_| too_long())?;
+        self.inner.write_all(&segment)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        // A full segment is only written once more data follows it, since
+        // the last segment must be flagged as such
+        while self.buffer.len() > SEGMENT_LEN {
+            let segment = self
+                .encryptor
+                .encrypt_next(&self.buffer[..SEGMENT_LEN])
+                .map_err(|_| too_long())?;
+            self.inner.write_all(&segment)?;
+            self.buffer.drain(..SEGMENT_LEN);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader decrypting the segments of an encrypted file.
+struct DecryptingReader<R: Read> {
+    inner: BufReader<R>,
+    /// Taken when the last segment is decrypted
+    decryptor: Option<DecryptorBE32<XChaCha20Poly1305>>,
+
+    /// Decrypted plaintext not yet read
+    plaintext: Vec<u8>,
+    position: usize,
+    done: bool,
+}
+
+impl<R: Read> DecryptingReader<R> {
+    fn new(inner: R, key: &EncryptionKey, nonce: &[u8; NONCE_LEN]) -> Self {
+        Self {
+            inner: BufReader::with_capacity(SEGMENT_LEN + TAG_LEN, inner),
+            decryptor: Some(DecryptorBE32::new((&key.0).into(), nonce.into())),
+            plaintext: Vec::new(),
+            position: 0,
+            done: false,
+        }
+    }
+
+    /// Reads, checks and decrypts the next segment.
+    fn next_segment(&mut self) -> io::Result<()> {
+        let mut segment = Vec::with_capacity(SEGMENT_LEN + TAG_LEN);
+        (&mut self.inner)
+            .take((SEGMENT_LEN + TAG_LEN) as u64)
+            .read_to_end(&mut segment)?;
+        let last = self.inner.fill_buf()?.is_empty();
+        self.plaintext = if last {
+            let decryptor = self.decryptor.take().ok_or_else(tampered)?;
+            decryptor.decrypt_last(segment.as_slice())
+        } else {
+            let decryptor = self.decryptor.as_mut().ok_or_else(tampered)?;
+            decryptor.decrypt_next(segment.as_slice())
+        }
+        .map_err(|_| tampered())?;
+        self.position = 0;
+        self.done = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plaintext.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.next_segment()?;
+        }
+        let len = buf.len().min(self.plaintext.len() - self.position);
+        buf[..len].copy_from_slice(&self.plaintext[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+fn tampered() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "the encrypted file is damaged, or the key is wrong",
+    )
+}
+
+/// The 32-bit segment counter ran out (a file of 256 TiB).
+fn too_long() -> io::Error {
+    io::Error::other("file too large to encrypt")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> EncryptionKey {
+        EncryptionKey([7u8; 32])
+    }
+
+    fn encrypt(content: &[u8]) -> Vec<u8> {
+        let mut writer = EncryptingWriter::new(Vec::new(), &key()).unwrap();
+        writer.write_all(content).unwrap();
+        writer.finish().unwrap()
+    }
+
+    fn decrypt(data: Vec<u8>, key: Option<&EncryptionKey>) -> io::Result<Vec<u8>> {
+        let mut content = Vec::new();
+        decrypting(io::Cursor::new(data), key)?.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    #[test]
+    fn test_roundtrip_across_segments() {
+        for len in [0, 10, SEGMENT_LEN, SEGMENT_LEN * 2 + 17] {
+            let content: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let encrypted = encrypt(&content);
+
+            assert!(encrypted.starts_with(MAGIC));
+            assert_eq!(decrypt(encrypted, Some(&key())).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_plain_content_passes_through() {
+        let content = b"version: '1.0'\n".to_vec();
+        assert_eq!(decrypt(content.clone(), None).unwrap(), content);
+    }
+
+    #[test]
+    fn test_tampering_and_wrong_keys_are_detected() {
+        let content = vec![b'x'; SEGMENT_LEN + 100];
+        let encrypted = encrypt(&content);
+
+        let mut flipped = encrypted.clone();
+        flipped[MAGIC.len() + NONCE_LEN + 5] ^= 1;
+        assert!(decrypt(flipped, Some(&key())).is_err());
+
+        // Dropping the last segment doesn't read as a shorter file
+        let truncated = encrypted[..MAGIC.len() + NONCE_LEN + SEGMENT_LEN + TAG_LEN].to_vec();
+        assert!(decrypt(truncated, Some(&key())).is_err());
+
+        let other = EncryptionKey([8u8; 32]);
+        assert!(decrypt(encrypted.clone(), Some(&other)).is_err());
+        assert!(decrypt(encrypted, None).is_err());
+    }
+
+    #[test]
+    fn test_key_from_base64() {
+        let text = STANDARD.encode([1u8; 32]);
+        let key = EncryptionKey::from_base64(&format!("{}\n", text), "test").unwrap();
+        assert_eq!(key, EncryptionKey([1u8; 32]));
+        assert!(EncryptionKey::from_base64("c2hvcnQ=", "test").is_err());
+        assert_eq!(format!("{:?}", key), "EncryptionKey(..)");
+    }
+}
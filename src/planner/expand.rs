@@ -0,0 +1,343 @@
+//! Expansion of collapsed directory entries in summary-only plans.
+
+use crate::models::{
+    paths_equal, timestamp, CleanupEntry, CleanupPlan, CollapsedSummary, SampledFile,
+};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+use thiserror::Error;
+use walkdir::WalkDir;
+
+/// Errors that can occur while expanding a plan entry.
+#[derive(Debug, Error)]
+pub enum ExpandError {
+    /// No entry with the given path exists in the plan
+    #[error("Entry not found in plan: {0}")]
+    EntryNotFound(String),
+
+    /// The relative path names an entry under more than one root
+    #[error("Entry {0} exists under several roots; give its full path instead")]
+    Ambiguous(String),
+
+    /// The entry is not a collapsed directory
+    #[error("Entry is not a collapsed directory: {0}")]
+    NotCollapsed(String),
+
+    /// I/O error while reading the directory
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Replaces a collapsed directory entry with entries for its immediate children.
+///
+/// Children inherit the parent's action, rule and reason. Child directories are
+/// themselves collapsed so they can be expanded in turn. Returns the number of
+/// entries that replaced the collapsed one.
+///
+/// `entry_path` is either the entry's path as listed in the plan or its full
+/// path. In a plan with several roots, a listed path that occurs under more
+/// than one of them is rejected as ambiguous.
+pub fn expand_entry(plan: &mut CleanupPlan, entry_path: &str) -> Result<usize, ExpandError> {
+    let wanted = entry_path.trim_end_matches(['/', '\\']);
+    let matches: Vec<usize> = plan
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| {
+            paths_equal(Path::new(&e.path), Path::new(wanted))
+                || paths_equal(&plan.entry_path(e), Path::new(wanted))
+        })
+        .map(|(i, _)| i)
+        .collect();
+    let index = match matches[..] {
+        [] => return Err(ExpandError::EntryNotFound(wanted.to_string())),
+        [index] => index,
+        _ => return Err(ExpandError::Ambiguous(wanted.to_string())),
+    };
+
+    if !plan.entries[index].is_collapsed() {
+        return Err(ExpandError::NotCollapsed(wanted.to_string()));
+    }
+
+    let parent = plan.entries[index].clone();
+    let dir = plan.entry_path(&parent);
+
+    let mut children: Vec<_> = fs::read_dir(&dir)?.collect::<Result<_, _>>()?;
+    children.sort_by_key(|c| c.file_name());
+
+    let mut expanded = Vec::with_capacity(children.len());
+    for child in children {
+        let metadata = child.path().symlink_metadata()?;
+        let (size, collapsed) = if metadata.is_dir() {
+            let (size, summary) = summarize_directory(&child.path());
+            (size, Some(summary))
+        } else {
+            (metadata.len(), None)
+        };
+
+        let modified = timestamp::format(metadata.modified()?.into());
+        let path = Path::new(&parent.path).join(child.file_name());
+
+        expanded.push(CleanupEntry {
+            path: path.to_string_lossy().to_string(),
+            root: parent.root,
+            size,
+            modified,
+            action: parent.action,
+            rule_name: parent.rule_name.clone(),
+            reason: parent.reason.clone(),
+            collapsed,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
+        });
+    }
+
+    let count = expanded.len();
+    plan.entries.splice(index..=index, expanded);
+    Ok(count)
+}
+
+/// Number of largest files sampled into a [`CollapsedSummary`].
+const LARGEST_SAMPLES: usize = 3;
+
+/// Returns the total file size and descendant counts below `dir`, with its
+/// largest and newest files as samples.
+///
+/// Unreadable entries are skipped; the summary is informational.
+pub(crate) fn summarize_directory(dir: &Path) -> (u64, CollapsedSummary) {
+    let mut size = 0u64;
+    let mut summary = CollapsedSummary::default();
+    let mut largest: Vec<Sample> = Vec::new();
+    let mut newest: Option<Sample> = None;
+
+    for entry in WalkDir::new(dir)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+    {
+        if entry.file_type().is_dir() {
+            summary.dir_count += 1;
+            continue;
+        }
+        summary.file_count += 1;
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        size = size.saturating_add(metadata.len());
+        let Ok(modified) = metadata.modified() else {
+            continue;
+        };
+        let sample = Sample {
+            size: metadata.len(),
+            modified,
+            entry,
+        };
+
+        // Keep the largest few, breaking ties by path for stable plans
+        let position = largest
+            .iter()
+            .position(|s| (sample.size, s.entry.path()) > (s.size, sample.entry.path()))
+            .unwrap_or(largest.len());
+        if position < LARGEST_SAMPLES {
+            largest.insert(position, sample.clone());
+            largest.truncate(LARGEST_SAMPLES);
+        }
+        if newest.as_ref().is_none_or(|n| sample.modified > n.modified) {
+            newest = Some(sample);
+        }
+    }
+
+    summary.largest = largest.iter().map(|s| s.to_sampled(dir)).collect();
+    summary.newest = newest.map(|s| s.to_sampled(dir));
+    (size, summary)
+}
+
+/// A file seen while summarizing a directory.
+#[derive(Clone)]
+struct Sample {
+    size: u64,
+    modified: SystemTime,
+    entry: walkdir::DirEntry,
+}
+
+impl Sample {
+    fn to_sampled(&self, dir: &Path) -> SampledFile {
+        let path = self
+            .entry
+            .path()
+            .strip_prefix(dir)
+            .unwrap_or(self.entry.path());
+        SampledFile {
+            path: path.to_string_lossy().into_owned(),
+            size: self.size,
+            modified: timestamp::format(self.modified.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CleanupAction;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn collapsed_plan(base: &Path) -> CleanupPlan {
+        let mut plan = CleanupPlan::new(base.to_path_buf());
+        let mut entry = CleanupEntry::new(
+            "node_modules".to_string(),
+            30,
+            "2025-11-19T12:00:00Z".to_string(),
+            CleanupAction::Delete,
+            "build_artifact".to_string(),
+            "Common build artifact directory".to_string(),
+        );
+        entry.collapsed = Some(CollapsedSummary {
+            file_count: 3,
+            dir_count: 1,
+            ..Default::default()
+        });
+        plan.add_entry(entry);
+        plan
+    }
+
+    fn create_tree(base: &Path) {
+        let root = base.join("node_modules");
+        fs::create_dir_all(root.join("lodash/dist")).unwrap();
+        fs::write(root.join(".package-lock.json"), "0123456789").unwrap();
+        fs::write(root.join("lodash/index.js"), "0123456789").unwrap();
+        fs::write(root.join("lodash/dist/lodash.js"), "0123456789").unwrap();
+    }
+
+    #[test]
+    fn test_summarize_directory() {
+        let temp = TempDir::new().unwrap();
+        create_tree(temp.path());
+
+        let (size, summary) = summarize_directory(&temp.path().join("node_modules"));
+
+        assert_eq!(size, 30);
+        assert_eq!(summary.file_count, 3);
+        assert_eq!(summary.dir_count, 2);
+    }
+
+    #[test]
+    fn test_summarize_directory_samples_files() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("cache");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        for (name, size) in [
+            ("a.bin", 50),
+            ("b.bin", 400),
+            ("nested/c.bin", 300),
+            ("d.bin", 10),
+        ] {
+            fs::write(dir.join(name), "x".repeat(size)).unwrap();
+        }
+        let newest = fs::File::options()
+            .write(true)
+            .open(dir.join("d.bin"))
+            .unwrap();
+        newest
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
+            .unwrap();
+
+        let (_, summary) = summarize_directory(&dir);
+
+        let largest: Vec<_> = summary
+            .largest
+            .iter()
+            .map(|s| (PathBuf::from(&s.path), s.size))
+            .collect();
+        assert_eq!(
+            largest,
+            vec![
+                (PathBuf::from("b.bin"), 400),
+                (PathBuf::from("nested/c.bin"), 300),
+                (PathBuf::from("a.bin"), 50),
+            ]
+        );
+        let newest = summary.newest.unwrap();
+        assert_eq!((newest.path.as_str(), newest.size), ("d.bin", 10));
+    }
+
+    #[test]
+    fn test_expand_entry_replaces_with_children() {
+        let temp = TempDir::new().unwrap();
+        create_tree(temp.path());
+        let mut plan = collapsed_plan(temp.path());
+
+        let count = expand_entry(&mut plan, "node_modules/").unwrap();
+
+        assert_eq!(count, 2);
+        let paths: Vec<_> = plan
+            .entries
+            .iter()
+            .map(|e| PathBuf::from(&e.path))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("node_modules/.package-lock.json"),
+                PathBuf::from("node_modules/lodash"),
+            ]
+        );
+
+        let file = &plan.entries[0];
+        assert_eq!(file.size, 10);
+        assert!(!file.is_collapsed());
+        assert_eq!(file.action, CleanupAction::Delete);
+
+        let dir = &plan.entries[1];
+        assert_eq!(dir.size, 20);
+        let summary = dir.collapsed.as_ref().unwrap();
+        assert_eq!((summary.file_count, summary.dir_count), (2, 1));
+        assert_eq!(summary.largest.len(), 2);
+    }
+
+    #[test]
+    fn test_expand_entry_in_multi_root_plan() {
+        let temp = TempDir::new().unwrap();
+        let (first, second) = (temp.path().join("a"), temp.path().join("b"));
+        create_tree(&first);
+        create_tree(&second);
+        let mut plan = collapsed_plan(&first);
+        plan.roots = vec![first.clone(), second.clone()];
+        let mut other = plan.entries[0].clone();
+        other.root = 1;
+        plan.add_entry(other);
+
+        let result = expand_entry(&mut plan, "node_modules");
+        assert!(matches!(result, Err(ExpandError::Ambiguous(_))));
+
+        let full = second.join("node_modules");
+        let count = expand_entry(&mut plan, &full.to_string_lossy()).unwrap();
+
+        assert_eq!(count, 2);
+        assert!(plan.entries[0].is_collapsed());
+        assert!(plan.entries[1..].iter().all(|e| e.root == 1));
+    }
+
+    #[test]
+    fn test_expand_entry_not_found() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = collapsed_plan(temp.path());
+
+        let result = expand_entry(&mut plan, "missing");
+        assert!(matches!(result, Err(ExpandError::EntryNotFound(_))));
+    }
+
+    #[test]
+    fn test_expand_entry_not_collapsed() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = collapsed_plan(temp.path());
+        plan.entries[0].collapsed = None;
+
+        let result = expand_entry(&mut plan, "node_modules");
+        assert!(matches!(result, Err(ExpandError::NotCollapsed(_))));
+    }
+}
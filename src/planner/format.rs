@@ -0,0 +1,58 @@
+//! Plan file format detection.
+
+use std::path::Path;
+
+/// Serialization format of a plan file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanFormat {
+    /// YAML (default, human-editable)
+    Yaml,
+
+    /// JSON
+    Json,
+}
+
+impl PlanFormat {
+    /// Detects the format from the file name, ignoring a trailing `.zst`.
+    ///
+    /// `plan.json` and `plan.json.zst` are JSON; everything else is YAML.
+    pub fn from_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let name = name.strip_suffix(".zst").unwrap_or(&name);
+
+        if name.ends_with(".json") {
+            Self::Json
+        } else {
+            Self::Yaml
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            PlanFormat::from_path(Path::new("plan.yaml")),
+            PlanFormat::Yaml
+        );
+        assert_eq!(
+            PlanFormat::from_path(Path::new("plan.yaml.zst")),
+            PlanFormat::Yaml
+        );
+        assert_eq!(
+            PlanFormat::from_path(Path::new("plan.json")),
+            PlanFormat::Json
+        );
+        assert_eq!(
+            PlanFormat::from_path(Path::new("out/PLAN.JSON.zst")),
+            PlanFormat::Json
+        );
+        assert_eq!(PlanFormat::from_path(Path::new("plan")), PlanFormat::Yaml);
+    }
+}
@@ -1,19 +1,75 @@
 //! Plan generation from detection results.
 
-use crate::detector::DetectionResult;
-use crate::models::{CleanupAction, CleanupEntry, CleanupPlan};
+use crate::detector::{DetectionReport, DetectionResult, PreciousClass, VetoedDetection};
+use crate::models::timestamp;
+use crate::models::{
+    paths_equal, strip_path_prefix, CleanupAction, CleanupEntry, CleanupPlan, PathKey, Protection,
+    ProtectionEvent, ProtectionOutcome, RuleStats,
+};
+use crate::planner::expand::summarize_directory;
 use chrono::Utc;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 /// Generates cleanup plans from detection results.
 pub struct PlanGenerator {
     base_path: PathBuf,
+    roots: Vec<PathBuf>,
+    include_vetoed: bool,
+    summary_mode: bool,
+    rule_actions: HashMap<String, CleanupAction>,
 }
 
 impl PlanGenerator {
     /// Creates a new PlanGenerator for the specified base path.
     pub fn new(base_path: PathBuf) -> Self {
-        Self { base_path }
+        Self {
+            base_path,
+            roots: Vec::new(),
+            include_vetoed: false,
+            summary_mode: false,
+            rule_actions: HashMap::new(),
+        }
+    }
+
+    /// Generates one plan for several scanned roots.
+    ///
+    /// The first root becomes the plan's base path. Each entry's path is
+    /// relative to the deepest root containing it, recorded by index. A single
+    /// root behaves like [`PlanGenerator::new`].
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        if let Some(first) = roots.first() {
+            self.base_path = first.clone();
+            self.roots = if roots.len() > 1 { roots } else { Vec::new() };
+        }
+        self
+    }
+
+    /// Also emits detections vetoed by keep rules, as Keep entries.
+    ///
+    /// Lets reviewers audit what the protections suppressed.
+    pub fn with_vetoed_entries(mut self, include: bool) -> Self {
+        self.include_vetoed = include;
+        self
+    }
+
+    /// Collapses every directory marked Delete into a single summary entry.
+    ///
+    /// The entry records file and directory counts instead of its
+    /// descendants; use `megamaid expand` to list them. Directories with any
+    /// other action are listed as usual, along with their flagged descendants.
+    pub fn with_summary_mode(mut self, enabled: bool) -> Self {
+        self.summary_mode = enabled;
+        self
+    }
+
+    /// Sets the action for entries flagged by the named rules, such as
+    /// custom rules from config, overriding the defaults.
+    pub fn with_rule_actions(mut self, actions: HashMap<String, CleanupAction>) -> Self {
+        self.rule_actions = actions;
+        self
     }
 
     /// Generates a cleanup plan from detection results.
@@ -28,105 +84,318 @@ impl PlanGenerator {
             version: env!("CARGO_PKG_VERSION").to_string(),
             created_at: Utc::now(),
             base_path: self.base_path.clone(),
+            roots: self.roots.clone(),
             entries: Vec::new(),
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         };
 
         // Sort by path length (shorter paths first) to ensure parents come before children
-        detections.sort_by(|a, b| {
-            a.entry
-                .path
-                .as_os_str()
-                .len()
-                .cmp(&b.entry.path.as_os_str().len())
-        });
-
-        // Track paths that are marked for deletion
-        let mut deleted_paths: Vec<PathBuf> = Vec::new();
-
-        for detection in detections {
-            // Never include the root/base path directly in the plan
-            if detection.entry.path == self.base_path {
+        #[cfg(feature = "parallel")]
+        detections.par_sort_by_key(|d| d.entry.path.as_os_str().len());
+        #[cfg(not(feature = "parallel"))]
+        detections.sort_by_key(|d| d.entry.path.as_os_str().len());
+
+        // Resolve actions in parallel; protection checks touch the filesystem
+        #[cfg(feature = "parallel")]
+        let detection_iter = detections.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let detection_iter = detections.iter();
+        let actions: Vec<(CleanupAction, Option<(Protection, String)>)> = detection_iter
+            .map(|detection| self.action_for(detection))
+            .collect();
+
+        // Track directories whose subtrees are covered by a single entry. Lookups
+        // walk the (short) ancestor chain instead of scanning every covered path.
+        // Keys follow the platform's case rules, so `Target/` covers `target/x`
+        // where the filesystem treats them as the same directory.
+        let mut covered_dirs: HashSet<PathKey> = HashSet::new();
+        let mut selected: Vec<(&DetectionResult, CleanupAction, bool)> = Vec::new();
+
+        for (detection, (action, protection)) in detections.iter().zip(actions) {
+            let path = detection.entry.path.as_path();
+
+            // Never include a scanned root directly in the plan
+            if paths_equal(path, &self.base_path)
+                || self.roots.iter().any(|root| paths_equal(path, root))
+            {
+                continue;
+            }
+
+            // Skip entries whose parent is already being deleted (or collapsed)
+            if !covered_dirs.is_empty()
+                && path
+                    .ancestors()
+                    .skip(1)
+                    .any(|a| covered_dirs.contains(&PathKey::new(a)))
+            {
                 continue;
             }
 
-            // Check if this entry is a child of an already-deleted directory
-            let is_child_of_deleted = deleted_paths.iter().any(|deleted_path| {
-                detection.entry.path.starts_with(deleted_path)
-                    && detection.entry.path != *deleted_path
-            });
+            let is_dir = detection.entry.entry_type == crate::models::EntryType::Directory;
+            // Only deleted directories collapse; the children of one that is
+            // reviewed or kept may still be flagged in their own right
+            let collapse = self.summary_mode && is_dir && action == CleanupAction::Delete;
+
+            // If this is a directory marked for deletion, track it
+            if is_dir && action == CleanupAction::Delete {
+                covered_dirs.insert(PathKey::new(path));
+            }
 
-            if is_child_of_deleted {
-                continue; // Skip this entry - parent is already being deleted
+            if let Some((protection, detail)) = protection {
+                plan.protections.push(ProtectionEvent {
+                    path: self.relative_path(path),
+                    size: detection.entry.size,
+                    rule_name: detection.rule_name.clone(),
+                    protection,
+                    outcome: ProtectionOutcome::Downgraded,
+                    detail,
+                });
             }
 
-            let mut action = self.default_action_for_rule(&detection.rule_name);
+            selected.push((detection, action, collapse));
+        }
+
+        // Convert to plan entries in parallel, preserving order
+        #[cfg(feature = "parallel")]
+        let selected = selected.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let selected = selected.into_iter();
+        plan.entries = selected
+            .map(|(detection, action, collapse)| {
+                let mut entry = self.to_entry(detection, action, detection.reason.clone());
+                if collapse {
+                    let (_, summary) = summarize_directory(&detection.entry.path);
+                    entry.collapsed = Some(summary);
+                }
+                entry
+            })
+            .collect();
+
+        plan
+    }
+
+    /// Resolves the action for a detection, with the protection that
+    /// downgraded it from Delete, if any.
+    fn action_for(
+        &self,
+        detection: &DetectionResult,
+    ) -> (CleanupAction, Option<(Protection, String)>) {
+        let action = self.default_action_for_rule(&detection.rule_name);
+
+        // Precious files are only ever reviewed, whichever rule flagged them
+        if let Some(class) = precious_class(detection) {
+            let downgrade = (action == CleanupAction::Delete)
+                .then(|| (Protection::Precious, class.to_string()));
+            return (CleanupAction::Review, downgrade);
+        }
 
-            // Downgrade to Review if path is protected (repo root/source-heavy)
-            if is_protected_path(&detection.entry.path) {
-                action = CleanupAction::Review;
+        // Downgrade to Review if path is protected (repo root/source-heavy).
+        // Only directories can contain the marker files checked for.
+        if action != CleanupAction::Review
+            && detection.entry.entry_type == crate::models::EntryType::Directory
+        {
+            if let Some(marker) = protected_path_marker(&detection.entry.path) {
+                let protection = match marker {
+                    ".git" | ".hg" | ".svn" => Protection::RepoRoot,
+                    _ => Protection::Manifest,
+                };
+                return (
+                    CleanupAction::Review,
+                    Some((protection, format!("contains {}", marker))),
+                );
             }
+        }
 
-            // If this is a directory marked for deletion, track it
-            if detection.entry.entry_type == crate::models::EntryType::Directory
-                && action == CleanupAction::Delete
-            {
-                deleted_paths.push(detection.entry.path.clone());
+        // The rule that flagged it may hold it back, e.g. the artifacts of a
+        // project still being worked on
+        match &detection.downgrade {
+            Some(downgrade) if action == CleanupAction::Delete => {
+                (CleanupAction::Review, Some(downgrade.clone()))
             }
+            _ => (action, None),
+        }
+    }
 
-            // Convert absolute path to relative path string
-            let relative_path = detection
-                .entry
-                .path
-                .strip_prefix(&self.base_path)
-                .unwrap_or(&detection.entry.path);
-
-            let relative_path_str = relative_path.to_string_lossy().to_string();
-
-            // Handle the case where path equals base_path (would result in empty string)
-            let relative_path = if relative_path_str.is_empty() {
-                ".".to_string()
-            } else {
-                relative_path_str
-            };
-
-            // Convert SystemTime to RFC3339 string
-            let modified = chrono::DateTime::<Utc>::from(detection.entry.modified).to_rfc3339();
-
-            plan.add_entry(CleanupEntry {
-                path: relative_path,
-                size: detection.entry.size,
-                modified,
-                action,
-                rule_name: detection.rule_name.clone(),
-                reason: detection.reason.clone(),
-            });
+    /// Generates a cleanup plan from a detection report.
+    ///
+    /// Behaves like [`PlanGenerator::generate`] and additionally records the
+    /// report's per-rule statistics in the plan's `detection_stats` section
+    /// and the detections its protections suppressed in `protections`.
+    /// Vetoed detections are appended as Keep entries when
+    /// [`PlanGenerator::with_vetoed_entries`] is enabled.
+    pub fn generate_with_stats(&self, report: DetectionReport) -> CleanupPlan {
+        let mut plan = self.generate(report.detections);
+        plan.detection_stats = report
+            .rule_stats
+            .into_iter()
+            .map(|stats| RuleStats {
+                largest_entry: stats
+                    .largest_entry
+                    .map(|path| self.relative_path(Path::new(&path))),
+                ..stats
+            })
+            .collect();
+
+        // Detector-side protections come first, then the planner's downgrades
+        let mut protections: Vec<ProtectionEvent> = report
+            .protections
+            .into_iter()
+            .map(|event| ProtectionEvent {
+                path: self.relative_path(Path::new(&event.path)),
+                ..event
+            })
+            .collect();
+        protections.append(&mut plan.protections);
+        plan.protections = protections;
+
+        if self.include_vetoed {
+            for vetoed in &report.vetoed {
+                plan.add_entry(self.vetoed_entry(vetoed));
+            }
         }
 
         plan
     }
 
+    /// Converts a vetoed detection into a Keep entry naming the keep rule.
+    fn vetoed_entry(&self, vetoed: &VetoedDetection) -> CleanupEntry {
+        let reason = format!(
+            "Kept by keep rule '{}' ({})",
+            vetoed.keep_rule, vetoed.detection.reason
+        );
+        self.to_entry(&vetoed.detection, CleanupAction::Keep, reason)
+    }
+
+    /// Builds a plan entry with a path relative to its root.
+    fn to_entry(
+        &self,
+        detection: &DetectionResult,
+        action: CleanupAction,
+        reason: String,
+    ) -> CleanupEntry {
+        let root = self.root_index(&detection.entry.path);
+        let relative_path = self.relative_to(&detection.entry.path, root);
+
+        // Convert SystemTime to RFC3339 string
+        let modified = timestamp::format(detection.entry.modified.into());
+
+        let precious = precious_class(detection);
+        let reason = match precious {
+            Some(class) => format!("{} ({}, never auto-deleted)", reason, class),
+            None => reason,
+        };
+
+        CleanupEntry {
+            path: relative_path,
+            root,
+            size: detection.entry.size,
+            modified,
+            action,
+            rule_name: detection.rule_name.clone(),
+            reason,
+            collapsed: None,
+            streams: detection.entry.streams.clone(),
+            owner: detection.entry.owner.clone(),
+            never_delete: precious.is_some(),
+        }
+    }
+
     /// Determines the default action based on rule type.
     ///
+    /// - Directories with a delete marker default to Delete (authorized by
+    ///   their owner)
     /// - Build artifacts default to Delete (safe to regenerate)
+    /// - Package manager caches default to Delete (downloaded again)
+    /// - Temporary files default to Review (may still be in use)
+    /// - Browser caches default to Review (hold offline data of web apps)
+    /// - Thumbnail caches default to Delete (rebuilt on demand)
+    /// - Crash dumps default to Review (may be needed to debug the crash)
     /// - Large files default to Review (user discretion)
+    /// - Files with large alternate data streams default to Review
+    /// - Orphaned Python environments default to Review
+    /// - Stale Git repositories default to Review (may hold unpushed work)
+    /// - Rapidly growing directories default to Review
+    /// - Redundant copies of duplicate files default to Review
+    /// - Empty files and directories default to Delete (nothing to lose)
     /// - Unknown rules default to Review (conservative)
+    ///
+    /// Actions set with [`PlanGenerator::with_rule_actions`] take precedence.
     fn default_action_for_rule(&self, rule_name: &str) -> CleanupAction {
+        if let Some(action) = self.rule_actions.get(rule_name) {
+            return *action;
+        }
         match rule_name {
+            "delete_marker" => CleanupAction::Delete,
             "build_artifact" => CleanupAction::Delete,
+            "dev_cache" => CleanupAction::Delete,
+            "temp_files" => CleanupAction::Review,
+            "browser_cache" => CleanupAction::Review,
+            "thumbnail_cache" => CleanupAction::Delete,
+            "crash_dump" => CleanupAction::Review,
             "large_file" => CleanupAction::Review,
+            "alternate_streams" => CleanupAction::Review,
+            "orphaned_env" => CleanupAction::Review,
+            "stale_repo" => CleanupAction::Review,
+            "rapid_growth" => CleanupAction::Review,
+            "duplicate" => CleanupAction::Review,
+            "empty_entry" => CleanupAction::Delete,
             _ => CleanupAction::Review,
         }
     }
 
+    /// Converts an absolute path to a path string relative to the base path.
+    ///
+    /// Paths under further roots stay absolute.
+    fn relative_path(&self, path: &Path) -> String {
+        self.relative_to(path, 0)
+    }
+
+    /// Returns the index of the deepest root containing `path`, or 0.
+    fn root_index(&self, path: &Path) -> usize {
+        self.roots
+            .iter()
+            .enumerate()
+            .filter(|(_, root)| strip_path_prefix(path, root).is_some())
+            .max_by_key(|(_, root)| root.as_os_str().len())
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// Converts an absolute path to a path string relative to the root at
+    /// `index`.
+    fn relative_to(&self, path: &Path, index: usize) -> String {
+        let root = self.roots.get(index).unwrap_or(&self.base_path);
+        let relative_path = strip_path_prefix(path, root).unwrap_or(path);
+
+        let relative_path_str = relative_path.to_string_lossy().to_string();
+
+        // Handle the case where path equals base_path (would result in empty string)
+        if relative_path_str.is_empty() {
+            ".".to_string()
+        } else {
+            relative_path_str
+        }
+    }
+
     /// Returns the base path for this generator.
     pub fn base_path(&self) -> &Path {
         &self.base_path
     }
 }
 
-fn is_protected_path(path: &Path) -> bool {
+/// Returns the precious class of a flagged file, if it has one.
+fn precious_class(detection: &DetectionResult) -> Option<PreciousClass> {
+    if detection.entry.is_file() {
+        PreciousClass::of(&detection.entry.path)
+    } else {
+        None
+    }
+}
+
+/// Returns the repository or manifest marker inside `path`, if any.
+fn protected_path_marker(path: &Path) -> Option<&'static str> {
     if is_known_junk_dir(path) {
-        return false;
+        return None;
     }
     let candidates = [
         ".git",
@@ -136,12 +405,7 @@ fn is_protected_path(path: &Path) -> bool {
         "Cargo.toml",
         "pyproject.toml",
     ];
-    for c in candidates {
-        if path.join(c).exists() {
-            return true;
-        }
-    }
-    false
+    candidates.into_iter().find(|c| path.join(c).exists())
 }
 
 fn is_known_junk_dir(path: &Path) -> bool {
@@ -185,6 +449,7 @@ mod tests {
             ),
             rule_name: rule_name.to_string(),
             reason: reason.to_string(),
+            downgrade: None,
         }
     }
 
@@ -245,6 +510,30 @@ mod tests {
         assert_eq!(plan.entries[0].action, CleanupAction::Review);
     }
 
+    #[test]
+    fn test_precious_files_are_marked_never_delete() {
+        let generator = PlanGenerator::new(PathBuf::from("/test"));
+
+        let detections = vec![
+            create_test_detection("/test/mail/archive.pst", 20_000_000_000, "old", "Old"),
+            create_test_detection("/test/large.bin", 200_000_000, "large_file", "Large file"),
+        ];
+
+        let plan = generator.generate(detections);
+
+        let pst = plan
+            .entries
+            .iter()
+            .find(|e| e.path.ends_with("archive.pst"))
+            .unwrap();
+        assert_eq!(pst.action, CleanupAction::Review);
+        assert!(pst.never_delete);
+        assert_eq!(pst.reason, "Old (mail archive, never auto-deleted)");
+
+        let bin = plan.entries.iter().find(|e| e.path == "large.bin").unwrap();
+        assert!(!bin.never_delete);
+    }
+
     #[test]
     fn test_unknown_rule_defaults_to_review() {
         let generator = PlanGenerator::new(PathBuf::from("/test"));
@@ -261,6 +550,49 @@ mod tests {
         assert_eq!(plan.entries[0].action, CleanupAction::Review);
     }
 
+    #[test]
+    fn test_rule_actions_override_defaults() {
+        let generator = PlanGenerator::new(PathBuf::from("/test")).with_rule_actions(
+            HashMap::from([("old_logs".to_string(), CleanupAction::Delete)]),
+        );
+
+        let detections = vec![
+            create_test_detection("/test/app.log", 1000, "old_logs", "Old log"),
+            create_test_detection("/test/large.bin", 200_000_000, "large_file", "Large file"),
+        ];
+
+        let plan = generator.generate(detections);
+
+        assert_eq!(plan.entries[0].action, CleanupAction::Delete);
+        assert_eq!(plan.entries[1].action, CleanupAction::Review);
+    }
+
+    #[test]
+    fn test_multiple_roots() {
+        let generator = PlanGenerator::new(PathBuf::from("/ignored"))
+            .with_roots(vec![PathBuf::from("/dev"), PathBuf::from("/data")]);
+
+        let detections = vec![
+            create_test_detection("/data", 1000, "large_file", "Root"),
+            create_test_detection("/dev/app.iso", 1000, "large_file", "Large file"),
+            create_test_detection("/data/app.iso", 2000, "large_file", "Large file"),
+        ];
+
+        let plan = generator.generate(detections);
+
+        assert_eq!(plan.base_path, PathBuf::from("/dev"));
+        assert_eq!(plan.all_roots().len(), 2);
+        assert_eq!(plan.entries.len(), 2, "roots are never entries");
+        assert_eq!(plan.entries[0].root, 0);
+        assert_eq!(plan.entries[1].root, 1);
+        assert_eq!(plan.entries[1].path, "app.iso");
+        assert_eq!(
+            plan.entry_path(&plan.entries[1]),
+            Path::new("/data/app.iso")
+        );
+        assert_ne!(plan.entries[0].id(), plan.entries[1].id());
+    }
+
     #[test]
     fn test_root_path_is_skipped() {
         let generator = PlanGenerator::new(PathBuf::from("/test"));
@@ -277,6 +609,46 @@ mod tests {
         assert!(plan.entries.is_empty());
     }
 
+    #[test]
+    fn test_children_of_deleted_directories_are_skipped() {
+        let generator = PlanGenerator::new(PathBuf::from("/test"));
+
+        let mut target = create_test_detection("/test/target", 0, "build_artifact", "Artifact");
+        target.entry.entry_type = EntryType::Directory;
+
+        let detections = vec![
+            create_test_detection("/test/target/debug/app", 200, "large_file", "Large"),
+            create_test_detection("/test/target-notes.bin", 200, "large_file", "Large"),
+            target,
+            create_test_detection("/test/src/big.bin", 200, "large_file", "Large"),
+        ];
+
+        let plan = generator.generate(detections);
+
+        let paths: Vec<_> = plan.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["target", "src/big.bin", "target-notes.bin"]);
+        assert_eq!(plan.entries[0].action, CleanupAction::Delete);
+    }
+
+    #[cfg(any(windows, target_os = "macos"))]
+    #[test]
+    fn test_children_of_deleted_directories_ignore_case() {
+        let generator = PlanGenerator::new(PathBuf::from("/test"));
+
+        let mut target = create_test_detection("/Test/Target", 0, "build_artifact", "Artifact");
+        target.entry.entry_type = EntryType::Directory;
+
+        let detections = vec![
+            target,
+            create_test_detection("/test/target/debug/app", 200, "large_file", "Large"),
+        ];
+
+        let plan = generator.generate(detections);
+
+        let paths: Vec<_> = plan.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["Target"]);
+    }
+
     #[test]
     fn test_preserves_detection_metadata() {
         let generator = PlanGenerator::new(PathBuf::from("/test"));
@@ -297,6 +669,175 @@ mod tests {
         assert_eq!(plan.entries[0].reason, "Test reason");
     }
 
+    #[test]
+    fn test_generate_includes_vetoed_entries_when_enabled() {
+        let report = DetectionReport {
+            detections: vec![],
+            rule_stats: vec![],
+            vetoed: vec![VetoedDetection {
+                detection: create_test_detection(
+                    "/test/clients/target",
+                    5000,
+                    "build_artifact",
+                    "Common build artifact directory",
+                ),
+                keep_rule: "clients".to_string(),
+            }],
+            protections: vec![ProtectionEvent {
+                path: "/test/clients/target".to_string(),
+                size: 5000,
+                rule_name: "build_artifact".to_string(),
+                protection: Protection::KeepRule,
+                outcome: ProtectionOutcome::Suppressed,
+                detail: "clients".to_string(),
+            }],
+        };
+
+        let plan = PlanGenerator::new(PathBuf::from("/test")).generate_with_stats(report.clone());
+        assert!(plan.entries.is_empty());
+
+        let plan = PlanGenerator::new(PathBuf::from("/test"))
+            .with_vetoed_entries(true)
+            .generate_with_stats(report);
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].path, "clients/target");
+        assert_eq!(plan.entries[0].action, CleanupAction::Keep);
+        assert_eq!(plan.entries[0].rule_name, "build_artifact");
+        assert!(plan.entries[0].reason.contains("keep rule 'clients'"));
+        assert_eq!(plan.protections.len(), 1);
+        assert_eq!(plan.protections[0].path, "clients/target");
+    }
+
+    #[test]
+    fn test_downgrades_are_recorded_as_protections() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repo = temp.path().join("out");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(temp.path().join("dist")).unwrap();
+
+        let mut dir = create_test_detection(repo.to_str().unwrap(), 10, "build_artifact", "B");
+        dir.entry.entry_type = crate::models::EntryType::Directory;
+        let mut clean = create_test_detection(
+            temp.path().join("dist").to_str().unwrap(),
+            10,
+            "build_artifact",
+            "B",
+        );
+        clean.entry.entry_type = crate::models::EntryType::Directory;
+
+        let plan = PlanGenerator::new(temp.path().to_path_buf()).generate(vec![dir, clean]);
+
+        assert_eq!(plan.protections.len(), 1);
+        let event = &plan.protections[0];
+        assert_eq!(event.path, "out");
+        assert_eq!(event.protection, Protection::RepoRoot);
+        assert_eq!(event.outcome, ProtectionOutcome::Downgraded);
+        assert_eq!(event.detail, "contains .git");
+    }
+
+    #[test]
+    fn test_rule_downgrades_are_planned_for_review() {
+        let detection = || {
+            let mut detection =
+                create_test_detection("/test/app/target", 10, "build_artifact", "B");
+            detection.downgrade = Some((
+                Protection::ActiveProject,
+                "project touched today".to_string(),
+            ));
+            detection
+        };
+
+        let plan = PlanGenerator::new(PathBuf::from("/test")).generate(vec![detection()]);
+        assert_eq!(plan.entries[0].action, CleanupAction::Review);
+        let event = &plan.protections[0];
+        assert_eq!(event.path, "app/target");
+        assert_eq!(event.protection, Protection::ActiveProject);
+        assert_eq!(event.outcome, ProtectionOutcome::Downgraded);
+        assert_eq!(event.detail, "project touched today");
+
+        // Entries not planned for deletion have nothing to downgrade
+        let mut actions = HashMap::new();
+        actions.insert("build_artifact".to_string(), CleanupAction::Keep);
+        let plan = PlanGenerator::new(PathBuf::from("/test"))
+            .with_rule_actions(actions)
+            .generate(vec![detection()]);
+        assert_eq!(plan.entries[0].action, CleanupAction::Keep);
+        assert!(plan.protections.is_empty());
+    }
+
+    #[test]
+    fn test_summary_mode_collapses_directories() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let cache = temp.path().join("cache");
+        std::fs::create_dir_all(cache.join("nested")).unwrap();
+        std::fs::write(cache.join("big.bin"), "data").unwrap();
+
+        let mut dir = create_test_detection(cache.to_str().unwrap(), 4, "large_file", "Large");
+        dir.entry.entry_type = crate::models::EntryType::Directory;
+        let child = create_test_detection(
+            cache.join("big.bin").to_str().unwrap(),
+            4,
+            "large_file",
+            "Large",
+        );
+
+        let generator = PlanGenerator::new(temp.path().to_path_buf());
+        let plan = generator.generate(vec![dir.clone(), child.clone()]);
+        // Review directories keep their children listed by default
+        assert_eq!(plan.entries.len(), 2);
+        assert!(!plan.entries[0].is_collapsed());
+
+        let generator = PlanGenerator::new(temp.path().to_path_buf()).with_summary_mode(true);
+        let plan = generator.generate(vec![dir.clone(), child.clone()]);
+        // Only directories marked Delete collapse
+        assert_eq!(plan.entries.len(), 2);
+        assert!(!plan.entries[0].is_collapsed());
+
+        dir.rule_name = "build_artifact".to_string();
+        let plan = generator.generate(vec![dir, child]);
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.entries[0].action, CleanupAction::Delete);
+        let summary = plan.entries[0].collapsed.as_ref().unwrap();
+        assert_eq!((summary.file_count, summary.dir_count), (1, 1));
+        assert_eq!(summary.largest[0].path, "big.bin");
+        assert_eq!(summary.newest.as_ref().unwrap().size, 4);
+    }
+
+    #[test]
+    fn test_generate_with_stats_records_rule_stats() {
+        let generator = PlanGenerator::new(PathBuf::from("/test"));
+
+        let report = DetectionReport {
+            detections: vec![create_test_detection(
+                "/test/large.bin",
+                200_000_000,
+                "large_file",
+                "Large file",
+            )],
+            rule_stats: vec![crate::models::RuleStats {
+                rule_name: "large_file".to_string(),
+                entries_evaluated: 3,
+                entries_flagged: 1,
+                bytes_flagged: 200_000_000,
+                evaluation_time_ms: 0.1,
+                largest_entry: Some("/test/large.bin".to_string()),
+                largest_entry_bytes: 200_000_000,
+            }],
+            vetoed: Vec::new(),
+            protections: Vec::new(),
+        };
+
+        let plan = generator.generate_with_stats(report);
+
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.detection_stats.len(), 1);
+        assert_eq!(plan.detection_stats[0].entries_flagged, 1);
+        assert_eq!(
+            plan.detection_stats[0].largest_entry.as_deref(),
+            Some("large.bin")
+        );
+    }
+
     #[test]
     fn test_base_path_accessor() {
         let generator = PlanGenerator::new(PathBuf::from("/test"));
@@ -0,0 +1,131 @@
+//! Guard against plans that would delete most of what was scanned.
+//!
+//! A misconfigured rule, such as a custom rule whose `when` matches every
+//! entry or a build-artifact pattern that matches the project itself, can
+//! mark nearly the whole tree for deletion, one `execute` away from wiping
+//! the directory. Before writing a plan, the scan command compares the bytes
+//! it marks Delete with the bytes scanned, and refuses to write a plan over
+//! `detector.max_delete_fraction` without `--force`.
+
+use crate::models::{CleanupPlan, FileEntry};
+use std::path::{Path, PathBuf};
+
+/// How much of the scanned bytes a plan marks for deletion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeleteShare {
+    /// Bytes of the entries marked Delete
+    pub delete_bytes: u64,
+
+    /// Bytes under the scanned roots
+    pub scanned_bytes: u64,
+}
+
+impl DeleteShare {
+    /// Measures the share of `plan`, generated from `entries` scanned under
+    /// `roots`.
+    pub fn new(plan: &CleanupPlan, entries: &[FileEntry], roots: &[PathBuf]) -> Self {
+        Self {
+            delete_bytes: plan.delete_size(),
+            scanned_bytes: roots.iter().map(|root| root_size(entries, root)).sum(),
+        }
+    }
+
+    /// Returns the share of the scanned bytes marked Delete, from 0.0 to 1.0;
+    /// nothing is marked when nothing was scanned.
+    pub fn fraction(&self) -> f64 {
+        if self.scanned_bytes == 0 {
+            return 0.0;
+        }
+        (self.delete_bytes as f64 / self.scanned_bytes as f64).min(1.0)
+    }
+
+    /// Returns whether more than `max_fraction` of the scanned bytes is
+    /// marked Delete. Plans marking no bytes never exceed it.
+    pub fn exceeds(&self, max_fraction: f64) -> bool {
+        self.delete_bytes > 0 && self.fraction() > max_fraction
+    }
+}
+
+/// Returns the bytes scanned under `root`: the size of the root's own entry,
+/// or of its listed children when the root itself wasn't listed (a hidden
+/// root with hidden entries skipped).
+fn root_size(entries: &[FileEntry], root: &Path) -> u64 {
+    match entries.iter().find(|e| e.path == root) {
+        Some(entry) => entry.size,
+        None => entries
+            .iter()
+            .filter(|e| e.path.parent() == Some(root))
+            .map(|e| e.size)
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CleanupAction, CleanupEntry, EntryType};
+    use std::time::SystemTime;
+
+    fn entry(path: &str, size: u64, entry_type: EntryType) -> FileEntry {
+        FileEntry::new(PathBuf::from(path), size, SystemTime::now(), entry_type)
+    }
+
+    fn plan(entries: &[(&str, u64, CleanupAction)]) -> CleanupPlan {
+        let mut plan = CleanupPlan::new(PathBuf::from("/project"));
+        for (path, size, action) in entries {
+            let mut entry = CleanupEntry::new(
+                path.to_string(),
+                *size,
+                "2024-01-01T00:00:00Z".to_string(),
+                *action,
+                "rule".to_string(),
+                "reason".to_string(),
+            );
+            entry.never_delete = path.ends_with(".vhdx");
+            plan.add_entry(entry);
+        }
+        plan
+    }
+
+    #[test]
+    fn test_delete_share_of_scanned_bytes() {
+        let entries = vec![
+            entry("/project", 1_000, EntryType::Directory),
+            entry("/project/target", 700, EntryType::Directory),
+            entry("/project/src", 300, EntryType::Directory),
+        ];
+        let roots = vec![PathBuf::from("/project")];
+
+        let share = DeleteShare::new(
+            &plan(&[
+                ("target", 700, CleanupAction::Delete),
+                ("src", 300, CleanupAction::Review),
+            ]),
+            &entries,
+            &roots,
+        );
+        assert_eq!(share.delete_bytes, 700);
+        assert_eq!(share.scanned_bytes, 1_000);
+        assert!(share.exceeds(0.6));
+        assert!(!share.exceeds(0.7));
+
+        // Entries the executor refuses to remove don't count
+        let share = DeleteShare::new(
+            &plan(&[("disk.vhdx", 900, CleanupAction::Delete)]),
+            &entries,
+            &roots,
+        );
+        assert_eq!(share.delete_bytes, 0);
+        assert!(!share.exceeds(0.0));
+    }
+
+    #[test]
+    fn test_unlisted_root_is_sized_from_its_children() {
+        let entries = vec![
+            entry("/home/.config/a", 40, EntryType::File),
+            entry("/home/.config/b", 60, EntryType::Directory),
+            entry("/home/.config/b/c", 60, EntryType::File),
+        ];
+        assert_eq!(root_size(&entries, Path::new("/home/.config")), 100);
+    }
+}
@@ -1,7 +1,20 @@
 //! Plan generation and serialization.
 
+mod chunked;
+pub mod compression;
+pub mod encryption;
+pub mod expand;
+pub mod format;
 pub mod generator;
+pub mod guard;
+pub mod path_list;
+pub mod reader;
 pub mod writer;
 
+pub use expand::{expand_entry, ExpandError};
+pub use format::PlanFormat;
 pub use generator::PlanGenerator;
-pub use writer::{PlanWriter, WriteError};
+pub use guard::DeleteShare;
+pub use path_list::{read_path_list, PathListError, PathListPlan, PathListPlanner};
+pub use reader::{PlanReader, ReadError, CHUNKED_YAML_THRESHOLD};
+pub use writer::{PlanWriter, StreamingPlanWriter, WriteError};
@@ -0,0 +1,325 @@
+//! Plan generation from a curated list of paths.
+//!
+//! Builds a plan without scanning or running detection rules, so hand-made
+//! deletion lists can go through the same verify/execute/log workflow as
+//! generated plans. Each listed path is statted for its size and mtime so the
+//! verifier can detect drift before anything is deleted.
+
+use crate::models::{
+    strip_path_prefix, timestamp, CleanupAction, CleanupEntry, CleanupPlan, PathKey,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+use walkdir::WalkDir;
+
+/// Rule name recorded on entries of plans built from a path list.
+pub const PATH_LIST_RULE: &str = "path_list";
+
+/// Errors that can occur while building a plan from a path list.
+#[derive(Debug, Error)]
+pub enum PathListError {
+    /// The list contains no paths
+    #[error("Path list is empty")]
+    Empty,
+
+    /// A listed path is outside the plan's base path
+    #[error("Path is not under the base path {base}: {path}")]
+    NotUnderBase { path: PathBuf, base: PathBuf },
+
+    /// A listed path exists but couldn't be statted
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// A plan built from a path list, with the paths that were left out.
+#[derive(Debug)]
+pub struct PathListPlan {
+    /// The generated plan
+    pub plan: CleanupPlan,
+
+    /// Listed paths that don't exist
+    pub missing: Vec<PathBuf>,
+
+    /// Listed paths already covered by a listed ancestor directory, or listed twice
+    pub covered: Vec<PathBuf>,
+}
+
+/// Builds cleanup plans from explicit lists of paths.
+pub struct PathListPlanner {
+    action: CleanupAction,
+    reason: String,
+    base_path: Option<PathBuf>,
+}
+
+impl PathListPlanner {
+    /// Creates a planner that gives every listed path `action` and `reason`.
+    pub fn new(action: CleanupAction, reason: impl Into<String>) -> Self {
+        Self {
+            action,
+            reason: reason.into(),
+            base_path: None,
+        }
+    }
+
+    /// Uses `base_path` as the plan's base instead of the paths' common ancestor.
+    ///
+    /// Relative paths in the list are resolved against it.
+    pub fn with_base_path(mut self, base_path: PathBuf) -> Self {
+        self.base_path = Some(base_path);
+        self
+    }
+
+    /// Builds a plan with one entry per listed path.
+    ///
+    /// Paths inside another listed directory are dropped, as the directory's
+    /// entry already covers them. Missing paths are reported rather than
+    /// failing the plan; the caller decides whether that is acceptable.
+    pub fn build(&self, paths: &[PathBuf]) -> Result<PathListPlan, PathListError> {
+        let mut paths: Vec<PathBuf> = paths.iter().map(|p| self.resolve(p)).collect();
+        if paths.is_empty() {
+            return Err(PathListError::Empty);
+        }
+
+        let base_path = match &self.base_path {
+            Some(base) => absolute(base),
+            None => common_parent(&paths),
+        };
+
+        // Shorter paths first, so directories are seen before their contents
+        paths.sort_by_key(|p| p.as_os_str().len());
+
+        let mut plan = CleanupPlan::new(base_path.clone());
+        let mut missing = Vec::new();
+        let mut covered = Vec::new();
+        let mut listed_dirs: HashSet<PathKey> = HashSet::new();
+        let mut seen: HashSet<PathKey> = HashSet::new();
+
+        for path in paths {
+            let Some(relative) = strip_path_prefix(&path, &base_path)
+                .filter(|r| !r.as_os_str().is_empty())
+                .map(Path::to_path_buf)
+            else {
+                return Err(PathListError::NotUnderBase {
+                    path,
+                    base: base_path,
+                });
+            };
+
+            if !seen.insert(PathKey::new(&path))
+                || path
+                    .ancestors()
+                    .skip(1)
+                    .any(|a| listed_dirs.contains(&PathKey::new(a)))
+            {
+                covered.push(path);
+                continue;
+            }
+
+            // Follows symlinks, as the verifier does
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    missing.push(path);
+                    continue;
+                }
+                Err(source) => return Err(PathListError::Io { path, source }),
+            };
+
+            let io_error = |source| PathListError::Io {
+                path: path.clone(),
+                source,
+            };
+            let size = if metadata.is_dir() {
+                listed_dirs.insert(PathKey::new(&path));
+                dir_size(&path).map_err(io_error)?
+            } else {
+                metadata.len()
+            };
+            let modified = metadata.modified().map_err(io_error)?;
+
+            plan.add_entry(CleanupEntry::new(
+                relative.to_string_lossy().to_string(),
+                size,
+                timestamp::format(modified.into()),
+                self.action,
+                PATH_LIST_RULE.to_string(),
+                self.reason.clone(),
+            ));
+        }
+
+        Ok(PathListPlan {
+            plan,
+            missing,
+            covered,
+        })
+    }
+
+    /// Makes a listed path absolute, relative to the base path if one is set.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.base_path {
+            Some(base) if path.is_relative() => absolute(&base.join(path)),
+            _ => absolute(path),
+        }
+    }
+}
+
+/// Reads a path list: one path per line, ignoring blank lines and `#` comments.
+///
+/// Surrounding whitespace is trimmed from each line.
+pub fn read_path_list(path: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(parse_path_list(&fs::read_to_string(path)?))
+}
+
+fn parse_path_list(contents: &str) -> Vec<PathBuf> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Makes `path` absolute and drops `.` and `..` components without touching
+/// the filesystem, so listed symlinks stay symlinks.
+fn absolute(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Returns the deepest directory containing every path.
+fn common_parent(paths: &[PathBuf]) -> PathBuf {
+    let parent = |p: &PathBuf| p.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut base = parent(&paths[0]);
+    for path in &paths[1..] {
+        let path = parent(path);
+        while !path.starts_with(&base) {
+            if !base.pop() {
+                break;
+            }
+        }
+    }
+    base
+}
+
+/// Returns the total size of the files below `dir`, as the verifier computes it.
+fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut total = 0u64;
+    for entry in WalkDir::new(dir).follow_links(false) {
+        let metadata = entry?.metadata()?;
+        if metadata.is_file() {
+            total = total.saturating_add(metadata.len());
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::paths_equal;
+    use crate::verifier::{VerificationConfig, VerificationEngine};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_path_list() {
+        let list = "# ticket-1234\n/data/old.log\n\n  /data/cache  \r\n# done\n";
+        assert_eq!(
+            parse_path_list(list),
+            vec![PathBuf::from("/data/old.log"), PathBuf::from("/data/cache")]
+        );
+    }
+
+    #[test]
+    fn test_build_plan_from_paths() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path();
+        fs::create_dir_all(base.join("logs/2023")).unwrap();
+        fs::write(base.join("logs/2023/a.log"), "a".repeat(100)).unwrap();
+        fs::write(base.join("logs/2023/b.log"), "b".repeat(50)).unwrap();
+        fs::create_dir_all(base.join("exports")).unwrap();
+        fs::write(base.join("exports/dump.sql"), "x".repeat(10)).unwrap();
+
+        let paths = vec![
+            base.join("exports/dump.sql"),
+            base.join("logs/2023"),
+            base.join("logs/2023/a.log"),
+            base.join("exports/gone.sql"),
+            base.join("exports/dump.sql"),
+        ];
+        let result = PathListPlanner::new(CleanupAction::Delete, "ticket-1234")
+            .build(&paths)
+            .unwrap();
+
+        assert!(paths_equal(&result.plan.base_path, base));
+        assert_eq!(result.plan.entries.len(), 2);
+        assert_eq!(result.missing, vec![base.join("exports/gone.sql")]);
+        assert_eq!(result.covered.len(), 2);
+
+        let logs = result
+            .plan
+            .entries
+            .iter()
+            .find(|e| Path::new(&e.path) == Path::new("logs/2023"))
+            .unwrap();
+        assert_eq!(logs.size, 150);
+        assert_eq!(logs.action, CleanupAction::Delete);
+        assert_eq!(logs.rule_name, PATH_LIST_RULE);
+        assert_eq!(logs.reason, "ticket-1234");
+
+        // The plan passes verification until the listed paths change
+        let verifier = VerificationEngine::new(VerificationConfig::default());
+        assert!(verifier.verify(&result.plan).unwrap().is_safe_to_execute());
+    }
+
+    #[test]
+    fn test_base_path_resolves_relative_paths() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a")).unwrap();
+        fs::write(temp.path().join("a/file.txt"), "content").unwrap();
+
+        let result = PathListPlanner::new(CleanupAction::Review, "cleanup")
+            .with_base_path(temp.path().to_path_buf())
+            .build(&[PathBuf::from("a/./file.txt")])
+            .unwrap();
+
+        assert_eq!(result.plan.entries.len(), 1);
+        assert_eq!(
+            Path::new(&result.plan.entries[0].path),
+            Path::new("a/file.txt")
+        );
+        assert_eq!(result.plan.entries[0].size, 7);
+    }
+
+    #[test]
+    fn test_path_outside_base_rejected() {
+        let temp = TempDir::new().unwrap();
+        let other = TempDir::new().unwrap();
+
+        let result = PathListPlanner::new(CleanupAction::Delete, "cleanup")
+            .with_base_path(temp.path().join("sub"))
+            .build(&[other.path().to_path_buf()]);
+
+        assert!(matches!(result, Err(PathListError::NotUnderBase { .. })));
+        assert!(matches!(
+            PathListPlanner::new(CleanupAction::Delete, "cleanup").build(&[]),
+            Err(PathListError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,419 @@
+//! Plan file reading.
+//!
+//! Plans may be YAML or JSON (chosen by file extension) and optionally
+//! zstd-compressed.
+//!
+//! JSON plans are always streamed. YAML plans larger than
+//! [`CHUNKED_YAML_THRESHOLD`], and all compressed ones, are parsed one entry
+//! at a time so loading them takes little more memory than the entries
+//! themselves; smaller plans are parsed as a single document, which accepts
+//! any YAML layout.
+
+use crate::models::{timestamp, CleanupEntry, CleanupPlan, ProtectionEvent, RuleStats};
+use crate::planner::chunked;
+use crate::planner::compression::{is_compressed, open_reader};
+use crate::planner::format::PlanFormat;
+use chrono::{DateTime, Utc};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors that can occur during plan reading.
+#[derive(Debug, Error)]
+pub enum ReadError {
+    /// I/O error while opening or decompressing the file
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The file is not a valid YAML plan
+    #[error("Failed to parse plan: {0}")]
+    Deserialization(#[from] serde_yaml::Error),
+
+    /// The file is not a valid JSON plan
+    #[error("Failed to parse plan: {0}")]
+    JsonDeserialization(#[from] serde_json::Error),
+
+    /// An entry refers to a root the plan does not list
+    #[error("Entry {path} refers to root {root}, but the plan lists {roots} root(s)")]
+    InvalidRoot {
+        /// The entry's relative path
+        path: String,
+        /// The entry's root index
+        root: usize,
+        /// Number of roots in the plan
+        roots: usize,
+    },
+}
+
+/// Size on disk above which YAML plans are parsed one entry at a time.
+///
+/// Compressed plans are always parsed this way: they are written by megamaid,
+/// and their size on disk says little about their decompressed size.
+pub const CHUNKED_YAML_THRESHOLD: u64 = 32 * 1024 * 1024;
+
+/// Reads cleanup plans from YAML or JSON files.
+pub struct PlanReader;
+
+impl PlanReader {
+    /// Reads a cleanup plan, decompressing `.zst` files transparently.
+    pub fn read(path: &Path) -> Result<CleanupPlan, ReadError> {
+        let mut entries = Vec::new();
+        let mut plan = Self::for_each_entry(path, |entry| entries.push(entry))?;
+        plan.entries = entries;
+        Ok(plan)
+    }
+
+    /// Streams the entries of a plan to `on_entry` one at a time.
+    ///
+    /// Returns the plan metadata with an empty `entries` list. Entries are
+    /// never collected, so callers that aggregate or filter can process very
+    /// large plans without holding them in memory.
+    ///
+    /// Fails with [`ReadError::InvalidRoot`] if an entry's root index is not
+    /// one of the plan's roots; `roots` may follow `entries` in the file, so
+    /// this is only known once the whole plan has been read.
+    pub fn for_each_entry<F>(path: &Path, mut on_entry: F) -> Result<CleanupPlan, ReadError>
+    where
+        F: FnMut(CleanupEntry),
+    {
+        // The entry with the highest root index, as (root, path)
+        let mut highest_root: Option<(usize, String)> = None;
+        let mut on_entry = |entry: CleanupEntry| {
+            if highest_root
+                .as_ref()
+                .is_none_or(|(root, _)| entry.root > *root)
+            {
+                highest_root = Some((entry.root, entry.path.clone()));
+            }
+            on_entry(entry);
+        };
+
+        let reader = open_reader(path)?;
+        let visitor = PlanVisitor {
+            on_entry: &mut on_entry,
+        };
+
+        let plan = match PlanFormat::from_path(path) {
+            PlanFormat::Yaml if Self::is_large(path)? => chunked::read_yaml(reader, &mut on_entry)?,
+            PlanFormat::Yaml => {
+                serde_yaml::Deserializer::from_reader(reader).deserialize_map(visitor)?
+            }
+            PlanFormat::Json => {
+                let mut deserializer = serde_json::Deserializer::from_reader(reader);
+                let plan = deserializer.deserialize_map(visitor)?;
+                deserializer.end()?;
+                plan
+            }
+        };
+
+        let roots = plan.all_roots().len();
+        match highest_root {
+            Some((root, path)) if root >= roots => {
+                Err(ReadError::InvalidRoot { path, root, roots })
+            }
+            _ => Ok(plan),
+        }
+    }
+}
+
+impl PlanReader {
+    /// Returns true if the plan at `path` should be parsed entry by entry.
+    fn is_large(path: &Path) -> io::Result<bool> {
+        Ok(is_compressed(path) || fs::metadata(path)?.len() > CHUNKED_YAML_THRESHOLD)
+    }
+}
+
+/// Visits the top-level plan mapping, forwarding entries to a callback.
+struct PlanVisitor<'a, F> {
+    on_entry: &'a mut F,
+}
+
+impl<'de, F: FnMut(CleanupEntry)> Visitor<'de> for PlanVisitor<'_, F> {
+    type Value = CleanupPlan;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a cleanup plan")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut version: Option<String> = None;
+        let mut created_at: Option<DateTime<Utc>> = None;
+        let mut base_path: Option<PathBuf> = None;
+        let mut roots: Vec<PathBuf> = Vec::new();
+        let mut detection_stats: Vec<RuleStats> = Vec::new();
+        let mut protections: Vec<ProtectionEvent> = Vec::new();
+        let mut saw_entries = false;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => version = Some(map.next_value()?),
+                "created_at" => created_at = Some(map.next_value::<Timestamp>()?.0),
+                "base_path" => base_path = Some(map.next_value()?),
+                "roots" => roots = map.next_value()?,
+                "entries" => {
+                    map.next_value_seed(EntriesSeed {
+                        on_entry: &mut *self.on_entry,
+                    })?;
+                    saw_entries = true;
+                }
+                "detection_stats" => detection_stats = map.next_value()?,
+                "protections" => protections = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        if !saw_entries {
+            return Err(de::Error::missing_field("entries"));
+        }
+
+        Ok(CleanupPlan {
+            version: version.ok_or_else(|| de::Error::missing_field("version"))?,
+            created_at: created_at.ok_or_else(|| de::Error::missing_field("created_at"))?,
+            base_path: base_path.ok_or_else(|| de::Error::missing_field("base_path"))?,
+            roots,
+            entries: Vec::new(),
+            detection_stats,
+            protections,
+        })
+    }
+}
+
+/// A timestamp read with the formats older plans used.
+#[derive(Deserialize)]
+struct Timestamp(#[serde(with = "timestamp::tolerant")] DateTime<Utc>);
+
+/// Deserializes the `entries` sequence element by element.
+struct EntriesSeed<'a, F> {
+    on_entry: &'a mut F,
+}
+
+impl<'de, F: FnMut(CleanupEntry)> DeserializeSeed<'de> for EntriesSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: FnMut(CleanupEntry)> Visitor<'de> for EntriesSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of cleanup entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(entry) = seq.next_element::<CleanupEntry>()? {
+            (self.on_entry)(entry);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CleanupAction;
+    use crate::planner::PlanWriter;
+    use tempfile::TempDir;
+
+    fn create_test_plan(entries: usize) -> CleanupPlan {
+        let mut plan = CleanupPlan::new(PathBuf::from("/test"));
+        for i in 0..entries {
+            plan.add_entry(CleanupEntry::new(
+                format!("file_{}.bin", i),
+                1000,
+                "2025-11-19T12:00:00Z".to_string(),
+                CleanupAction::Review,
+                "large_file".to_string(),
+                "Large file".to_string(),
+            ));
+        }
+        plan.detection_stats = vec![RuleStats {
+            rule_name: "large_file".to_string(),
+            entries_evaluated: 10,
+            entries_flagged: entries as u64,
+            bytes_flagged: 1000 * entries as u64,
+            evaluation_time_ms: 0.5,
+            ..Default::default()
+        }];
+        plan.protections = vec![ProtectionEvent {
+            path: "clients".to_string(),
+            size: 4096,
+            rule_name: "large_file".to_string(),
+            protection: crate::models::Protection::KeepRule,
+            outcome: crate::models::ProtectionOutcome::Suppressed,
+            detail: "clients".to_string(),
+        }];
+        plan
+    }
+
+    #[test]
+    fn test_read_plain_plan() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("plan.yaml");
+        PlanWriter::write(&CleanupPlan::new(PathBuf::from("/test")), &path).unwrap();
+
+        let plan = PlanReader::read(&path).unwrap();
+        assert_eq!(plan.base_path, PathBuf::from("/test"));
+    }
+
+    #[test]
+    fn test_roundtrip_all_formats() {
+        let temp = TempDir::new().unwrap();
+        let plan = create_test_plan(3);
+
+        for name in ["plan.yaml", "plan.yaml.zst", "plan.json", "plan.json.zst"] {
+            let path = temp.path().join(name);
+            PlanWriter::write(&plan, &path).unwrap();
+
+            let loaded = PlanReader::read(&path).unwrap();
+            assert_eq!(loaded.entries.len(), 3, "{}", name);
+            assert_eq!(loaded.entries[2].path, "file_2.bin", "{}", name);
+            assert_eq!(loaded.detection_stats, plan.detection_stats, "{}", name);
+            assert_eq!(loaded.protections, plan.protections, "{}", name);
+            assert_eq!(loaded.created_at, plan.created_at, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_chunked_yaml_matches_document_parse() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("plan.yaml");
+        let mut plan = create_test_plan(3);
+        plan.entries[1].reason = "Line one\n- line two\n".to_string();
+        PlanWriter::write(&plan, &path).unwrap();
+
+        let mut entries = Vec::new();
+        let chunked = chunked::read_yaml(open_reader(&path).unwrap(), &mut |entry| {
+            entries.push(entry)
+        })
+        .unwrap();
+
+        let full = PlanReader::read(&path).unwrap();
+        let yaml = |entries: &[CleanupEntry]| serde_yaml::to_string(entries).unwrap();
+        assert_eq!(yaml(&entries), yaml(&full.entries));
+        assert_eq!(entries[1].reason, "Line one\n- line two\n");
+        assert_eq!(chunked.detection_stats, full.detection_stats);
+        assert_eq!(chunked.protections, full.protections);
+        assert_eq!(chunked.created_at, full.created_at);
+    }
+
+    #[test]
+    fn test_streamed_yaml_matches_serde_yaml() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("plan.yaml");
+        let plan = create_test_plan(2);
+        PlanWriter::write(&plan, &path).unwrap();
+
+        // The streamed document is an ordinary YAML plan
+        let content = std::fs::read_to_string(&path).unwrap();
+        let parsed: CleanupPlan = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(parsed.entries.len(), 2);
+    }
+
+    #[test]
+    fn test_for_each_entry_streams_entries() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("plan.json");
+        PlanWriter::write(&create_test_plan(5), &path).unwrap();
+
+        let mut total = 0;
+        let header = PlanReader::for_each_entry(&path, |entry| total += entry.size).unwrap();
+
+        assert_eq!(total, 5000);
+        assert!(header.entries.is_empty());
+        assert_eq!(header.base_path, PathBuf::from("/test"));
+    }
+
+    #[test]
+    fn test_read_plan_with_historical_timestamps() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("plan.yaml");
+        std::fs::write(
+            &path,
+            "version: \"0.1.0\"\ncreated_at: \"2025-11-19 12:00:00\"\nbase_path: /test\n\
+             entries:\n- path: a.bin\n  size: 1\n  modified: \"2025-11-19T12:00:00+00:00\"\n  \
+             action: review\n  rule_name: large_file\n  reason: Large file\n",
+        )
+        .unwrap();
+
+        let plan = PlanReader::read(&path).unwrap();
+        assert_eq!(timestamp::format(plan.created_at), "2025-11-19T12:00:00Z");
+        assert_eq!(
+            timestamp::parse(&plan.entries[0].modified),
+            Ok(plan.created_at)
+        );
+    }
+
+    #[test]
+    fn test_read_rejects_entry_with_unknown_root() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = create_test_plan(2);
+        plan.roots = vec![PathBuf::from("/a"), PathBuf::from("/b")];
+        plan.entries[1].root = 2;
+
+        for name in ["plan.yaml", "plan.json", "plan.yaml.zst"] {
+            let path = temp.path().join(name);
+            PlanWriter::write(&plan, &path).unwrap();
+
+            let result = PlanReader::read(&path);
+            assert!(
+                matches!(
+                    result,
+                    Err(ReadError::InvalidRoot {
+                        root: 2,
+                        roots: 2,
+                        ..
+                    })
+                ),
+                "{name}"
+            );
+        }
+
+        plan.roots.clear();
+        plan.entries[1].root = 1;
+        let path = temp.path().join("single.yaml");
+        PlanWriter::write(&plan, &path).unwrap();
+        assert!(matches!(
+            PlanReader::read(&path),
+            Err(ReadError::InvalidRoot {
+                root: 1,
+                roots: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_read_empty_plan_json() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("plan.json");
+        PlanWriter::write(&CleanupPlan::new(PathBuf::from("/test")), &path).unwrap();
+
+        let plan = PlanReader::read(&path).unwrap();
+        assert!(plan.entries.is_empty());
+    }
+
+    #[test]
+    fn test_read_missing_file() {
+        let result = PlanReader::read(Path::new("/nonexistent/plan.yaml"));
+        assert!(matches!(result, Err(ReadError::Io(_))));
+    }
+
+    #[test]
+    fn test_read_invalid_plan() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("plan.yaml");
+        std::fs::write(&path, "not: [a, plan").unwrap();
+
+        let result = PlanReader::read(&path);
+        assert!(matches!(result, Err(ReadError::Deserialization(_))));
+    }
+}
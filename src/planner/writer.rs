@@ -1,8 +1,15 @@
 //! Atomic plan file writing with validation.
-
-use crate::models::CleanupPlan;
+//!
+//! Plans are streamed entry by entry, as YAML or JSON depending on the file
+//! extension, optionally zstd-compressed.
+
+use crate::models::{CleanupEntry, CleanupPlan, ProtectionEvent, RuleStats};
+use crate::planner::compression::{is_compressed, CompressedWriter};
+use crate::planner::format::PlanFormat;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -13,6 +20,10 @@ pub enum WriteError {
     #[error("Failed to serialize plan: {0}")]
     Serialization(#[from] serde_yaml::Error),
 
+    /// Failed to serialize plan to JSON
+    #[error("Failed to serialize plan: {0}")]
+    JsonSerialization(#[from] serde_json::Error),
+
     /// I/O error during file operations
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
@@ -22,7 +33,7 @@ pub enum WriteError {
     Validation(String),
 }
 
-/// Writes cleanup plans to YAML files with atomic operations.
+/// Writes cleanup plans to YAML or JSON files with atomic operations.
 pub struct PlanWriter;
 
 impl PlanWriter {
@@ -44,13 +55,12 @@ impl PlanWriter {
         // Validate the plan
         Self::validate(plan)?;
 
-        // Serialize to YAML
-        let yaml_content = serde_yaml::to_string(plan)?;
-
-        // Write atomically via temp file
-        Self::write_atomic(path, &yaml_content)?;
-
-        Ok(())
+        // Stream entries into a temp file, then rename atomically
+        let mut writer = StreamingPlanWriter::create(path, plan)?;
+        for entry in &plan.entries {
+            writer.write_entry(entry)?;
+        }
+        writer.finish()
     }
 
     /// Validates a cleanup plan before writing.
@@ -78,38 +88,184 @@ impl PlanWriter {
         Ok(())
     }
 
-    /// Writes content to a file atomically using a temporary file.
-    fn write_atomic(target: &Path, content: &str) -> Result<(), WriteError> {
+    /// Generates a temporary file path for atomic writes.
+    fn temp_path(target: &Path) -> PathBuf {
+        let mut temp = target.to_path_buf();
+        temp.set_extension("tmp");
+        temp
+    }
+}
+
+/// Writes a plan one entry at a time, keeping memory use flat.
+///
+/// The output format (YAML or JSON) is chosen from the file extension and
+/// `.zst` targets are compressed. Output goes to a temporary file that is only
+/// renamed over the target by [`StreamingPlanWriter::finish`]; dropping the
+/// writer without finishing discards it.
+pub struct StreamingPlanWriter {
+    writer: Option<CompressedWriter>,
+    format: PlanFormat,
+    target: PathBuf,
+    temp_path: PathBuf,
+    protections: Vec<ProtectionEvent>,
+    entries_written: usize,
+}
+
+/// Plan fields written before the entries.
+///
+/// Per-rule totals come first so they can be read without scrolling past
+/// the entries.
+#[derive(Serialize)]
+struct PlanHeader<'a> {
+    version: &'a str,
+    #[serde(with = "crate::models::timestamp::tolerant")]
+    created_at: DateTime<Utc>,
+    base_path: &'a Path,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    roots: &'a [PathBuf],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    detection_stats: &'a [RuleStats],
+}
+
+impl StreamingPlanWriter {
+    /// Starts a plan at `path` using the metadata and statistics of `plan`.
+    ///
+    /// Entries of `plan` are not written; pass them to
+    /// [`StreamingPlanWriter::write_entry`].
+    pub fn create(path: &Path, plan: &CleanupPlan) -> Result<Self, WriteError> {
+        if plan.base_path.as_os_str().is_empty() {
+            return Err(WriteError::Validation(
+                "Base path cannot be empty".to_string(),
+            ));
+        }
+
         // Create parent directory if it doesn't exist
-        if let Some(parent) = target.parent() {
+        if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        // Generate temp file path in same directory as target
-        let temp_path = Self::temp_path(target);
+        let temp_path = PlanWriter::temp_path(path);
+        let mut writer = CompressedWriter::create(&temp_path, is_compressed(path))?;
+        let format = PlanFormat::from_path(path);
 
-        // Write to temp file
-        fs::write(&temp_path, content)?;
+        let header = PlanHeader {
+            version: &plan.version,
+            created_at: plan.created_at,
+            base_path: &plan.base_path,
+            roots: &plan.roots,
+            detection_stats: &plan.detection_stats,
+        };
+        match format {
+            PlanFormat::Yaml => serde_yaml::to_writer(&mut writer, &header)?,
+            PlanFormat::Json => {
+                // Re-open the header object so entries can follow
+                let json = serde_json::to_string(&header)?;
+                writer.write_all(json.trim_end_matches('}').as_bytes())?;
+            }
+        }
 
-        // Atomic rename
-        fs::rename(&temp_path, target)?;
+        Ok(Self {
+            writer: Some(writer),
+            format,
+            target: path.to_path_buf(),
+            temp_path,
+            protections: plan.protections.clone(),
+            entries_written: 0,
+        })
+    }
+
+    /// Appends one entry to the plan.
+    pub fn write_entry(&mut self, entry: &CleanupEntry) -> Result<(), WriteError> {
+        if entry.path.is_empty() {
+            return Err(WriteError::Validation(
+                "Entry path cannot be empty".to_string(),
+            ));
+        }
 
+        let first = self.entries_written == 0;
+        let writer = self.writer.as_mut().expect("writer used after finish");
+
+        match self.format {
+            PlanFormat::Yaml => {
+                if first {
+                    writer.write_all(b"entries:\n")?;
+                }
+                // Emit the entry mapping as a sequence item
+                let yaml = serde_yaml::to_string(entry)?;
+                for (i, line) in yaml.lines().enumerate() {
+                    let prefix = if i == 0 { "- " } else { "  " };
+                    writeln!(writer, "{}{}", prefix, line)?;
+                }
+            }
+            PlanFormat::Json => {
+                writer.write_all(if first { b",\"entries\":[\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut *writer, entry)?;
+            }
+        }
+
+        self.entries_written += 1;
         Ok(())
     }
 
-    /// Generates a temporary file path for atomic writes.
-    fn temp_path(target: &Path) -> PathBuf {
-        let mut temp = target.to_path_buf();
-        temp.set_extension("tmp");
-        temp
+    /// Returns the number of entries written so far.
+    pub fn entries_written(&self) -> usize {
+        self.entries_written
+    }
+
+    /// Completes the plan and atomically moves it into place.
+    pub fn finish(mut self) -> Result<(), WriteError> {
+        let mut writer = self.writer.take().expect("writer used after finish");
+        let empty = self.entries_written == 0;
+
+        match self.format {
+            PlanFormat::Yaml => {
+                if empty {
+                    writer.write_all(b"entries: []\n")?;
+                }
+                if !self.protections.is_empty() {
+                    #[derive(Serialize)]
+                    struct Trailer<'a> {
+                        protections: &'a [ProtectionEvent],
+                    }
+                    serde_yaml::to_writer(
+                        &mut writer,
+                        &Trailer {
+                            protections: &self.protections,
+                        },
+                    )?;
+                }
+            }
+            PlanFormat::Json => {
+                writer.write_all(if empty { b",\"entries\":[]" } else { b"\n]" })?;
+                if !self.protections.is_empty() {
+                    writer.write_all(b",\"protections\":")?;
+                    serde_json::to_writer(&mut writer, &self.protections)?;
+                }
+                writer.write_all(b"}\n")?;
+            }
+        }
+
+        writer.finish()?;
+
+        // Atomic rename
+        fs::rename(&self.temp_path, &self.target)?;
+        Ok(())
+    }
+}
+
+impl Drop for StreamingPlanWriter {
+    fn drop(&mut self) {
+        // Unfinished plans never replace the target
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{CleanupAction, CleanupEntry};
-    use chrono::Utc;
+    use crate::models::CleanupAction;
     use tempfile::TempDir;
 
     fn create_test_plan() -> CleanupPlan {
@@ -117,21 +273,66 @@ mod tests {
             version: "0.1.0".to_string(),
             created_at: Utc::now(),
             base_path: PathBuf::from("/test"),
+            roots: Vec::new(),
             entries: Vec::new(),
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         };
 
         plan.add_entry(CleanupEntry {
             path: "target".to_string(),
+            root: 0,
             size: 1000,
             modified: "2025-11-19T12:00:00Z".to_string(),
             action: CleanupAction::Delete,
             rule_name: "build_artifact".to_string(),
             reason: "Build artifact".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         });
 
         plan
     }
 
+    #[test]
+    fn test_write_normalizes_paths_to_nfc() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut plan = create_test_plan();
+        plan.entries[0].path = "Fotos/cafe\u{301}.jpg".to_string();
+
+        for name in ["plan.yaml", "plan.json"] {
+            let plan_path = temp_dir.path().join(name);
+            PlanWriter::write(&plan, &plan_path).unwrap();
+
+            let content = fs::read_to_string(&plan_path).unwrap();
+            assert!(content.contains("Fotos/caf\u{e9}.jpg"));
+            assert!(!content.contains('\u{301}'));
+
+            let loaded = crate::planner::PlanReader::read(&plan_path).unwrap();
+            assert_eq!(loaded.entries[0].path, "Fotos/caf\u{e9}.jpg");
+        }
+    }
+
+    #[test]
+    fn test_write_compressed_plan() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan_path = temp_dir.path().join("plan.yaml.zst");
+
+        let plan = create_test_plan();
+        PlanWriter::write(&plan, &plan_path).unwrap();
+
+        // Not plain YAML on disk
+        let raw = fs::read(&plan_path).unwrap();
+        assert!(!raw.starts_with(b"version"));
+
+        let loaded = crate::planner::PlanReader::read(&plan_path).unwrap();
+        assert_eq!(loaded.entries.len(), 1);
+        assert_eq!(loaded.entries[0].path, "target");
+    }
+
     #[test]
     fn test_write_and_read_back() {
         let temp_dir = TempDir::new().unwrap();
@@ -175,7 +376,10 @@ mod tests {
             version: "0.1.0".to_string(),
             created_at: Utc::now(),
             base_path: PathBuf::new(), // Empty path
+            roots: Vec::new(),
             entries: Vec::new(),
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         };
 
         let result = PlanWriter::validate(&plan);
@@ -189,16 +393,24 @@ mod tests {
             version: "0.1.0".to_string(),
             created_at: Utc::now(),
             base_path: PathBuf::from("/test"),
+            roots: Vec::new(),
             entries: Vec::new(),
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         };
 
         plan.add_entry(CleanupEntry {
             path: String::new(), // Empty path
+            root: 0,
             size: 1000,
             modified: "2025-11-19T12:00:00Z".to_string(),
             action: CleanupAction::Delete,
             rule_name: "test".to_string(),
             reason: "Test".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         });
 
         let result = PlanWriter::validate(&plan);
@@ -256,4 +468,85 @@ mod tests {
         assert!(content.contains("base_path"));
         assert!(content.contains("entries"));
     }
+
+    #[test]
+    fn test_rule_stats_written_before_entries() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut plan = create_test_plan();
+        plan.detection_stats.push(RuleStats {
+            rule_name: "build_artifact".to_string(),
+            entries_flagged: 1,
+            bytes_flagged: 1000,
+            largest_entry: Some("target".to_string()),
+            largest_entry_bytes: 1000,
+            ..Default::default()
+        });
+
+        for name in ["plan.yaml", "plan.json"] {
+            let plan_path = temp_dir.path().join(name);
+            PlanWriter::write(&plan, &plan_path).unwrap();
+
+            let content = fs::read_to_string(&plan_path).unwrap();
+            assert!(content.find("detection_stats") < content.find("entries"));
+
+            let loaded = crate::planner::PlanReader::read(&plan_path).unwrap();
+            assert_eq!(loaded.detection_stats, plan.detection_stats);
+            assert_eq!(loaded.entries.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_streaming_writer_many_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan_path = temp_dir.path().join("plan.json");
+        let header = CleanupPlan::new(PathBuf::from("/test"));
+
+        let mut writer = StreamingPlanWriter::create(&plan_path, &header).unwrap();
+        for i in 0..1000 {
+            writer
+                .write_entry(&CleanupEntry::new(
+                    format!("dir_{}/target", i),
+                    i,
+                    "2025-11-19T12:00:00Z".to_string(),
+                    CleanupAction::Delete,
+                    "build_artifact".to_string(),
+                    "Build artifact".to_string(),
+                ))
+                .unwrap();
+        }
+        assert_eq!(writer.entries_written(), 1000);
+        writer.finish().unwrap();
+
+        let loaded = crate::planner::PlanReader::read(&plan_path).unwrap();
+        assert_eq!(loaded.entries.len(), 1000);
+        assert_eq!(loaded.entries[999].path, "dir_999/target");
+    }
+
+    #[test]
+    fn test_streaming_writer_dropped_without_finish() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan_path = temp_dir.path().join("plan.yaml");
+        let plan = create_test_plan();
+
+        {
+            let mut writer = StreamingPlanWriter::create(&plan_path, &plan).unwrap();
+            writer.write_entry(&plan.entries[0]).unwrap();
+        }
+
+        // Neither the target nor the temp file is left behind
+        assert!(!plan_path.exists());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_streaming_writer_rejects_empty_entry_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let plan_path = temp_dir.path().join("plan.yaml");
+        let mut plan = create_test_plan();
+        plan.entries[0].path = String::new();
+
+        let mut writer = StreamingPlanWriter::create(&plan_path, &plan).unwrap();
+        assert!(writer.write_entry(&plan.entries[0]).is_err());
+    }
 }
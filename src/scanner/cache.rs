@@ -0,0 +1,336 @@
+//! Scan cache for incremental rescans.
+//!
+//! A cache stores every entry of a previous scan together with the scan
+//! settings. On the next scan, a directory whose modification time is
+//! unchanged is assumed to have an unchanged subtree, and its cached entries
+//! are reused instead of walking it again.
+//!
+//! # Correctness caveats
+//!
+//! A directory's mtime only changes when entries are added, removed or
+//! renamed *directly* inside it. The shortcut therefore misses:
+//!
+//! - files whose contents (and size) changed in place,
+//! - changes deeper in the subtree that did not touch the directory itself,
+//! - filesystems that do not update directory mtimes at all (some network
+//!   shares and FUSE filesystems).
+//!
+//! Directories modified in the same second the cached scan started are
+//! always re-walked, since the cache stores mtimes with one-second
+//! precision. Use a full walk (`--full-walk`) whenever exact results matter,
+//! e.g. before executing a plan on a busy tree.
+//!
+//! `megamaid scan --incremental` keeps one cache per scan root in the user's
+//! cache directory (see [`ScanCache::default_path`]); `--scan-cache` names
+//! the file explicitly.
+
+use crate::models::{EntryType, FileEntry};
+use crate::planner::compression::{is_compressed, open_reader, CompressedWriter};
+use crate::scanner::ScanConfig;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Cache format version; caches with another version are ignored.
+const CACHE_VERSION: u32 = 2;
+
+/// Entries of a previous scan, sorted by path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCache {
+    version: u32,
+    root: PathBuf,
+    follow_links: bool,
+    max_depth: Option<usize>,
+    skip_hidden: bool,
+    #[serde(default)]
+    include_streams: bool,
+    #[serde(default)]
+    skip_dir_sizes: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude_globs: Vec<String>,
+    #[serde(default)]
+    respect_ignore_files: bool,
+    #[serde(default)]
+    same_filesystem: bool,
+    #[serde(default)]
+    collect_owner: bool,
+    /// Start of the cached scan, in seconds since the Unix epoch
+    started_at: u64,
+    entries: Vec<FileEntry>,
+}
+
+impl ScanCache {
+    /// Builds a cache from the results of a scan that started at `started_at`.
+    pub fn new(
+        root: &Path,
+        config: &ScanConfig,
+        started_at: SystemTime,
+        mut entries: Vec<FileEntry>,
+    ) -> Self {
+        // Sorting by path keeps every subtree contiguous
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            version: CACHE_VERSION,
+            root: root.to_path_buf(),
+            follow_links: config.follow_links,
+            max_depth: config.max_depth,
+            skip_hidden: config.skip_hidden,
+            include_streams: config.lists_streams(root),
+            skip_dir_sizes: config.skips_dir_sizes(root),
+            exclude_globs: config.exclude_globs.clone(),
+            respect_ignore_files: config.respect_ignore_files,
+            same_filesystem: config.same_filesystem,
+            collect_owner: config.collect_owner,
+            started_at: unix_secs(started_at),
+            entries,
+        }
+    }
+
+    /// Returns the cache file for scans of `root` in the user's cache
+    /// directory, or `None` if the platform has none.
+    ///
+    /// The file is named after the root's last component and a hash of its
+    /// absolute path, so every root gets its own.
+    pub fn default_path(root: &Path) -> Option<PathBuf> {
+        let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+        let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+        let hash: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        let name: String = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "root".to_string())
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+
+        let dir = dirs::cache_dir()?.join("megamaid").join("scan-cache");
+        Some(dir.join(format!("{}-{}.json.zst", name, hash)))
+    }
+
+    /// Loads a cache file (JSON, zstd-compressed if the name ends in `.zst`).
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let reader = open_reader(path)?;
+        let mut cache: Self = serde_json::from_reader(reader)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        cache.entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(cache)
+    }
+
+    /// Writes the cache file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = CompressedWriter::create(path, is_compressed(path))?;
+        serde_json::to_writer(&mut writer, self).map_err(io::Error::other)?;
+        writer.finish()
+    }
+
+    /// Returns true if the cache was produced by a scan of `root` with the same settings.
+    pub fn is_compatible(&self, root: &Path, config: &ScanConfig) -> bool {
+        self.version == CACHE_VERSION
+            && self.root == root
+            && self.follow_links == config.follow_links
+            && self.max_depth == config.max_depth
+            && self.skip_hidden == config.skip_hidden
+            && self.include_streams == config.lists_streams(root)
+            && self.skip_dir_sizes == config.skips_dir_sizes(root)
+            && self.exclude_globs == config.exclude_globs
+            && self.respect_ignore_files == config.respect_ignore_files
+            && self.same_filesystem == config.same_filesystem
+            && self.collect_owner == config.collect_owner
+    }
+
+    /// Returns the cached entries, sorted by path.
+    pub fn entries(&self) -> &[FileEntry] {
+        &self.entries
+    }
+
+    /// Returns the size of every cached directory, by path.
+    pub fn directory_sizes(&self) -> HashMap<PathBuf, u64> {
+        self.entries
+            .iter()
+            .filter(|e| e.entry_type == EntryType::Directory)
+            .map(|e| (e.path.clone(), e.size))
+            .collect()
+    }
+
+    /// Consumes the cache, returning its entries.
+    pub fn into_entries(self) -> Vec<FileEntry> {
+        self.entries
+    }
+
+    /// Returns the cached directory and its descendants if the directory's
+    /// mtime is unchanged, or `None` if the subtree must be walked again.
+    pub fn unchanged_subtree(&self, dir: &Path, mtime: SystemTime) -> Option<&[FileEntry]> {
+        let subtree = self.subtree(dir)?;
+
+        // Mtimes are stored in whole seconds; anything touched while the
+        // cached scan was running is treated as changed.
+        let mtime = unix_secs(mtime);
+        if mtime != unix_secs(subtree[0].modified) || mtime >= self.started_at {
+            return None;
+        }
+        Some(subtree)
+    }
+
+    /// Returns the cached directory and its descendants, or `None` if `dir`
+    /// isn't a cached directory.
+    pub fn subtree(&self, dir: &Path) -> Option<&[FileEntry]> {
+        let start = self.entries.partition_point(|e| e.path.as_path() < dir);
+        let cached = self.entries.get(start)?;
+        if cached.path != dir || cached.entry_type != EntryType::Directory {
+            return None;
+        }
+
+        let len = self.entries[start..]
+            .iter()
+            .take_while(|e| e.path.starts_with(dir))
+            .count();
+        Some(&self.entries[start..start + len])
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{RemoteMode, RemoteScanConfig};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn cache() -> ScanCache {
+        let entries = vec![
+            FileEntry::new("/r/b".into(), 0, at(100), EntryType::Directory),
+            FileEntry::new("/r".into(), 0, at(100), EntryType::Directory),
+            FileEntry::new("/r/a/x.txt".into(), 5, at(100), EntryType::File),
+            FileEntry::new("/r/a".into(), 5, at(100), EntryType::Directory),
+            FileEntry::new("/r/a-b".into(), 0, at(100), EntryType::Directory),
+        ];
+        ScanCache::new(Path::new("/r"), &ScanConfig::default(), at(200), entries)
+    }
+
+    #[test]
+    fn test_unchanged_subtree_returns_descendants() {
+        let cache = cache();
+
+        let subtree = cache.unchanged_subtree(Path::new("/r/a"), at(100)).unwrap();
+        let paths: Vec<_> = subtree.iter().map(|e| e.path.clone()).collect();
+
+        // "/r/a-b" is a sibling, not a descendant
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("/r/a"), PathBuf::from("/r/a/x.txt")]
+        );
+    }
+
+    #[test]
+    fn test_changed_or_unknown_directories_are_rewalked() {
+        let cache = cache();
+
+        assert!(cache
+            .unchanged_subtree(Path::new("/r/a"), at(150))
+            .is_none());
+        assert!(cache
+            .unchanged_subtree(Path::new("/r/c"), at(100))
+            .is_none());
+        assert!(cache
+            .unchanged_subtree(Path::new("/r/a/x.txt"), at(100))
+            .is_none());
+    }
+
+    #[test]
+    fn test_directories_modified_during_cached_scan_are_rewalked() {
+        let entries = vec![FileEntry::new(
+            "/r/a".into(),
+            0,
+            at(200),
+            EntryType::Directory,
+        )];
+        let cache = ScanCache::new(Path::new("/r"), &ScanConfig::default(), at(200), entries);
+
+        assert!(cache
+            .unchanged_subtree(Path::new("/r/a"), at(200))
+            .is_none());
+    }
+
+    #[test]
+    fn test_is_compatible() {
+        let cache = cache();
+
+        assert!(cache.is_compatible(Path::new("/r"), &ScanConfig::default()));
+        assert!(!cache.is_compatible(Path::new("/other"), &ScanConfig::default()));
+
+        let config = ScanConfig {
+            skip_hidden: true,
+            ..Default::default()
+        };
+        assert!(!cache.is_compatible(Path::new("/r"), &config));
+
+        let config = ScanConfig {
+            include_streams: true,
+            ..Default::default()
+        };
+        assert!(!cache.is_compatible(Path::new("/r"), &config));
+
+        let config = ScanConfig {
+            exclude_globs: vec!["node_modules".to_string()],
+            ..Default::default()
+        };
+        assert!(!cache.is_compatible(Path::new("/r"), &config));
+
+        let config = ScanConfig {
+            remote: RemoteScanConfig {
+                mode: RemoteMode::Always,
+                skip_dir_sizes: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(!cache.is_compatible(Path::new("/r"), &config));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("scan-cache.json.zst");
+
+        let original = cache();
+        original.save(&path).unwrap();
+        let loaded = ScanCache::load(&path).unwrap();
+
+        assert_eq!(loaded.entries(), original.entries());
+        assert_eq!(loaded.directory_sizes().get(Path::new("/r/a")), Some(&5));
+        assert_eq!(loaded.directory_sizes().len(), 4);
+        assert!(loaded.is_compatible(Path::new("/r"), &ScanConfig::default()));
+    }
+
+    #[test]
+    fn test_default_path_per_root() {
+        let Some(projects) = ScanCache::default_path(Path::new("/home/me/my projects")) else {
+            return;
+        };
+        let name = projects.file_name().unwrap().to_string_lossy().into_owned();
+
+        assert!(name.starts_with("my_projects-"));
+        assert!(name.ends_with(".json.zst"));
+        assert_eq!(
+            ScanCache::default_path(Path::new("/home/me/my projects")),
+            Some(projects.clone())
+        );
+        assert_ne!(
+            ScanCache::default_path(Path::new("/home/you/my projects")),
+            Some(projects)
+        );
+    }
+}
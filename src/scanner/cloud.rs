@@ -0,0 +1,38 @@
+//! Detection of cloud placeholders.
+//!
+//! OneDrive "Files On-Demand" (and other sync clients built on the Windows
+//! cloud files API) leave placeholders for files that live only in the
+//! cloud. A placeholder reports the full size of the file but takes up no
+//! disk space until it is opened, so it looks like a large file that
+//! deleting would free space from, when deleting it removes the file from
+//! the cloud instead. Scanners mark placeholders with
+//! [`FileEntry::cloud_placeholder`](crate::models::FileEntry::cloud_placeholder)
+//! and leave their sizes out of directory totals, and the size threshold rule
+//! doesn't flag them unless told to.
+//!
+//! Placeholders are recognized by the recall and offline attributes their
+//! reparse points carry; on other platforms nothing is a placeholder.
+
+use std::fs::Metadata;
+
+/// Returns true if `metadata` is that of a file whose contents aren't stored
+/// locally.
+#[cfg(windows)]
+pub fn is_cloud_placeholder(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_ATTRIBUTE_OFFLINE, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS, FILE_ATTRIBUTE_RECALL_ON_OPEN,
+    };
+
+    const NOT_LOCAL: u32 = FILE_ATTRIBUTE_OFFLINE
+        | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS
+        | FILE_ATTRIBUTE_RECALL_ON_OPEN;
+    metadata.is_file() && metadata.file_attributes() & NOT_LOCAL != 0
+}
+
+/// Returns true if `metadata` is that of a file whose contents aren't stored
+/// locally.
+#[cfg(not(windows))]
+pub fn is_cloud_placeholder(_metadata: &Metadata) -> bool {
+    false
+}
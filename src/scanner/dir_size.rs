@@ -0,0 +1,116 @@
+//! Sizing of directories the parallel scanner doesn't walk into.
+//!
+//! Directories at the scan's depth limit are sized by summing their contents.
+//! Sizing every subdirectory in parallel nests a parallel iterator per level,
+//! which on network storage keeps far more requests in flight than the share
+//! can serve and makes the disk seek between them. Contents are summed in
+//! parallel only within `parallel_depth` levels of the sized directory and
+//! only for directories with at least `min_parallel_entries` entries; smaller
+//! and deeper directories are summed sequentially on the thread that reached
+//! them. Cloud placeholders take up no disk space and count as 0.
+
+use serde::{Deserialize, Serialize};
+
+/// Limits on the parallelism of directory size calculation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirSizeConfig {
+    /// Levels below a sized directory whose contents are still summed in
+    /// parallel (0 = always sequential)
+    pub parallel_depth: usize,
+
+    /// Directories with fewer entries than this are summed sequentially
+    pub min_parallel_entries: usize,
+}
+
+impl Default for DirSizeConfig {
+    fn default() -> Self {
+        Self {
+            parallel_depth: 2,
+            min_parallel_entries: 32,
+        }
+    }
+}
+
+impl DirSizeConfig {
+    /// Returns true if a directory `depth` levels below the sized one, with
+    /// `entries` entries, is summed in parallel.
+    pub fn is_parallel(&self, depth: usize, entries: usize) -> bool {
+        depth < self.parallel_depth && entries >= self.min_parallel_entries
+    }
+}
+
+/// Returns the total size of the files below `dir`.
+///
+/// Entries that can't be read count as 0.
+#[cfg(feature = "parallel")]
+pub(crate) fn dir_size(dir: &std::path::Path, config: &DirSizeConfig) -> std::io::Result<u64> {
+    sum_level(dir, config, 0)
+}
+
+#[cfg(feature = "parallel")]
+fn sum_level(dir: &std::path::Path, config: &DirSizeConfig, depth: usize) -> std::io::Result<u64> {
+    use crate::scanner::cloud::is_cloud_placeholder;
+    use rayon::prelude::*;
+
+    // Volume snapshots are copies of the tree, not part of it
+    let entries: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() != crate::models::SNAPSHOT_DIR)
+        .collect();
+    let size_of = |entry: &std::fs::DirEntry| match entry.metadata() {
+        Ok(metadata) if is_cloud_placeholder(&metadata) => 0,
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        Ok(metadata) if metadata.is_dir() => {
+            sum_level(&entry.path(), config, depth + 1).unwrap_or(0)
+        }
+        _ => 0,
+    };
+
+    if config.is_parallel(depth, entries.len()) {
+        Ok(entries.par_iter().map(size_of).sum())
+    } else {
+        Ok(entries.iter().map(size_of).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_only_near_the_top_of_large_directories() {
+        let config = DirSizeConfig::default();
+        assert!(config.is_parallel(0, 32));
+        assert!(config.is_parallel(1, 1000));
+        assert!(!config.is_parallel(2, 1000), "too deep");
+        assert!(!config.is_parallel(0, 31), "too few entries");
+
+        let sequential = DirSizeConfig {
+            parallel_depth: 0,
+            ..DirSizeConfig::default()
+        };
+        assert!(!sequential.is_parallel(0, 1000));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_dir_size_sums_nested_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let deep = temp.path().join("a/b/c/d");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(temp.path().join("top.bin"), vec![0; 10]).unwrap();
+        std::fs::write(deep.join("deep.bin"), vec![0; 5]).unwrap();
+        for i in 0..40 {
+            std::fs::write(temp.path().join(format!("a/{}.bin", i)), [0]).unwrap();
+        }
+
+        for parallel_depth in [0, 1, 8] {
+            let config = DirSizeConfig {
+                parallel_depth,
+                min_parallel_entries: 4,
+            };
+            assert_eq!(dir_size(temp.path(), &config).unwrap(), 55);
+        }
+    }
+}
@@ -0,0 +1,483 @@
+//! Approximate subtree sizes from a sample of directories.
+//!
+//! A full walk of a large tree can take minutes or hours. The estimator
+//! instead lists each top-level subtree of the root exactly when it has few
+//! directories, and otherwise takes random root-to-leaf probes through it
+//! (Knuth's tree-size estimator): at every directory it reads the listing,
+//! counts the files and descends into one subdirectory chosen at random,
+//! weighting what it finds by the number of choices made on the way down.
+//! Averaged over many probes the estimate is unbiased, and the spread between
+//! probes gives a confidence interval. Listings are cached, so the upper
+//! levels are read only once however many probes pass through them.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// z-score of a two-sided 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+/// Configuration for size estimation.
+#[derive(Debug, Clone)]
+pub struct EstimateConfig {
+    /// Random probes taken through each subtree that isn't listed exactly
+    pub probes: usize,
+
+    /// Subtrees with at most this many directories are listed exactly
+    pub exact_dir_limit: usize,
+
+    /// Files whose size is read per directory; larger directories are
+    /// extrapolated from this many
+    pub files_per_dir: usize,
+
+    /// Seed of the probe sequence, so repeated runs give the same answer
+    pub seed: u64,
+}
+
+impl Default for EstimateConfig {
+    fn default() -> Self {
+        Self {
+            probes: 200,
+            exact_dir_limit: 200,
+            files_per_dir: 64,
+            seed: 0x6d65_6761_6d61_6964,
+        }
+    }
+}
+
+/// An approximate size with its 95% confidence margin.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Estimate {
+    /// Estimated total size in bytes
+    pub bytes: f64,
+
+    /// Half-width of the 95% confidence interval for `bytes`
+    pub bytes_margin: f64,
+
+    /// Estimated number of files
+    pub files: f64,
+
+    /// Half-width of the 95% confidence interval for `files`
+    pub files_margin: f64,
+
+    /// True if the value was counted rather than sampled
+    pub exact: bool,
+}
+
+impl Estimate {
+    /// Returns the lower and upper bounds of the byte estimate.
+    pub fn bytes_range(&self) -> (u64, u64) {
+        let low = (self.bytes - self.bytes_margin).max(0.0);
+        (low as u64, (self.bytes + self.bytes_margin) as u64)
+    }
+
+    /// Combines estimates of disjoint subtrees, assuming independent errors.
+    fn sum<'a>(estimates: impl IntoIterator<Item = &'a Estimate>) -> Estimate {
+        let mut total = Estimate {
+            exact: true,
+            ..Default::default()
+        };
+        let (mut bytes_var, mut files_var) = (0.0, 0.0);
+        for e in estimates {
+            total.bytes += e.bytes;
+            total.files += e.files;
+            bytes_var += e.bytes_margin * e.bytes_margin;
+            files_var += e.files_margin * e.files_margin;
+            total.exact &= e.exact;
+        }
+        total.bytes_margin = bytes_var.sqrt();
+        total.files_margin = files_var.sqrt();
+        total
+    }
+}
+
+/// Estimated size of one top-level entry of the root.
+#[derive(Debug, Clone)]
+pub struct SubtreeEstimate {
+    /// Full path of the subtree
+    pub path: PathBuf,
+
+    /// Estimated size of everything below it
+    pub estimate: Estimate,
+}
+
+/// Result of estimating a directory tree.
+#[derive(Debug, Clone)]
+pub struct EstimateReport {
+    /// The estimated root
+    pub root: PathBuf,
+
+    /// Top-level subdirectories, largest estimate first
+    pub subtrees: Vec<SubtreeEstimate>,
+
+    /// Files directly in the root, counted exactly
+    pub root_files: Estimate,
+
+    /// Estimate for the whole tree
+    pub total: Estimate,
+
+    /// Directories listed to produce the estimate
+    pub dirs_read: usize,
+
+    /// Directories that couldn't be listed and were counted as empty
+    pub dirs_unreadable: usize,
+
+    /// Time taken
+    pub elapsed: Duration,
+}
+
+/// What a directory listing contributes to an estimate.
+#[derive(Debug, Clone, Default)]
+struct Listing {
+    subdirs: Vec<PathBuf>,
+    files: u64,
+    /// Total file size, extrapolated if only some files were read
+    bytes: f64,
+}
+
+/// Estimates directory tree sizes by sampling.
+pub struct Estimator {
+    config: EstimateConfig,
+    listings: HashMap<PathBuf, Listing>,
+    unreadable: usize,
+    rng: SplitMix64,
+}
+
+impl Estimator {
+    /// Creates an estimator with the given configuration.
+    pub fn new(config: EstimateConfig) -> Self {
+        let rng = SplitMix64(config.seed);
+        Self {
+            config,
+            listings: HashMap::new(),
+            unreadable: 0,
+            rng,
+        }
+    }
+
+    /// Estimates the size of `root` and of each of its subdirectories.
+    pub fn estimate(&mut self, root: &Path) -> io::Result<EstimateReport> {
+        let started = Instant::now();
+
+        // The root must be readable; deeper failures only reduce accuracy
+        let listing = read_listing(root, self.config.files_per_dir)?;
+        let root_files = Estimate {
+            bytes: listing.bytes,
+            files: listing.files as f64,
+            exact: listing.files <= self.config.files_per_dir as u64,
+            ..Default::default()
+        };
+        self.listings.insert(root.to_path_buf(), listing.clone());
+
+        let mut subtrees: Vec<SubtreeEstimate> = listing
+            .subdirs
+            .iter()
+            .map(|dir| SubtreeEstimate {
+                path: dir.clone(),
+                estimate: self.estimate_subtree(dir),
+            })
+            .collect();
+        subtrees.sort_by(|a, b| b.estimate.bytes.total_cmp(&a.estimate.bytes));
+
+        let total = Estimate::sum(
+            subtrees
+                .iter()
+                .map(|s| &s.estimate)
+                .chain(Some(&root_files)),
+        );
+
+        Ok(EstimateReport {
+            root: root.to_path_buf(),
+            subtrees,
+            root_files,
+            total,
+            dirs_read: self.listings.len(),
+            dirs_unreadable: self.unreadable,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Counts a subtree exactly if it is small enough, otherwise samples it.
+    fn estimate_subtree(&mut self, dir: &Path) -> Estimate {
+        self.count_exactly(dir).unwrap_or_else(|| self.sample(dir))
+    }
+
+    /// Lists the whole subtree, giving up past `exact_dir_limit` directories.
+    fn count_exactly(&mut self, dir: &Path) -> Option<Estimate> {
+        let mut total = Estimate {
+            exact: true,
+            ..Default::default()
+        };
+        let mut queue = VecDeque::from([dir.to_path_buf()]);
+        let mut visited = 0;
+
+        while let Some(next) = queue.pop_front() {
+            visited += 1;
+            if visited > self.config.exact_dir_limit {
+                return None;
+            }
+            let listing = self.listing(&next);
+            total.bytes += listing.bytes;
+            total.files += listing.files as f64;
+            // An extrapolated directory makes the total approximate
+            total.exact &= listing.files <= self.config.files_per_dir as u64;
+            queue.extend(listing.subdirs.iter().cloned());
+        }
+
+        Some(total)
+    }
+
+    /// Averages random probes through the subtree.
+    fn sample(&mut self, dir: &Path) -> Estimate {
+        let probes = self.config.probes.max(1);
+        let mut bytes = Vec::with_capacity(probes);
+        let mut files = Vec::with_capacity(probes);
+        for _ in 0..probes {
+            let (b, f) = self.probe(dir);
+            bytes.push(b);
+            files.push(f);
+        }
+
+        let (bytes, bytes_margin) = mean_and_margin(&bytes);
+        let (files, files_margin) = mean_and_margin(&files);
+        Estimate {
+            bytes,
+            bytes_margin,
+            files,
+            files_margin,
+            exact: false,
+        }
+    }
+
+    /// Walks one random path down from `dir`, returning its (bytes, files) estimate.
+    fn probe(&mut self, dir: &Path) -> (f64, f64) {
+        let (mut bytes, mut files) = (0.0, 0.0);
+        let mut weight = 1.0;
+        let mut current = dir.to_path_buf();
+
+        loop {
+            let listing = self.listing(&current);
+            bytes += weight * listing.bytes;
+            files += weight * listing.files as f64;
+            if listing.subdirs.is_empty() {
+                return (bytes, files);
+            }
+            weight *= listing.subdirs.len() as f64;
+            let pick = self.rng.below(listing.subdirs.len());
+            current = listing.subdirs[pick].clone();
+        }
+    }
+
+    /// Returns the cached listing of `dir`, reading it on first use.
+    fn listing(&mut self, dir: &Path) -> Listing {
+        if let Some(listing) = self.listings.get(dir) {
+            return listing.clone();
+        }
+        let listing = read_listing(dir, self.config.files_per_dir).unwrap_or_else(|_| {
+            self.unreadable += 1;
+            Listing::default()
+        });
+        self.listings.insert(dir.to_path_buf(), listing.clone());
+        listing
+    }
+}
+
+/// Reads a directory, sizing up to `files_per_dir` evenly spaced files.
+///
+/// Symlinks are neither followed nor counted, as in a scan.
+fn read_listing(dir: &Path, files_per_dir: usize) -> io::Result<Listing> {
+    let mut listing = Listing::default();
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let Ok(entry) = entry else { continue };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            listing.subdirs.push(entry.path());
+        } else if file_type.is_file() {
+            files.push(entry);
+        }
+    }
+    // Listing order is arbitrary; sorting makes probes reproducible
+    listing.subdirs.sort();
+
+    listing.files = files.len() as u64;
+    if files.is_empty() || files_per_dir == 0 {
+        return Ok(listing);
+    }
+    let step = files.len().div_ceil(files_per_dir);
+    let (sum, read) = files
+        .iter()
+        .step_by(step)
+        .filter_map(|f| f.metadata().ok())
+        .fold((0u64, 0u64), |(sum, n), m| {
+            (sum.saturating_add(m.len()), n + 1)
+        });
+    if read > 0 {
+        listing.bytes = sum as f64 * files.len() as f64 / read as f64;
+    }
+    Ok(listing)
+}
+
+/// Returns the mean of `samples` and the half-width of its 95% confidence interval.
+fn mean_and_margin(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    if samples.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, Z_95 * (variance / n).sqrt())
+}
+
+/// Small deterministic PRNG (SplitMix64); probes don't need more.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Builds `width` directories per level, `depth` levels deep, each holding one file.
+    fn build_tree(dir: &Path, width: usize, depth: usize, file_size: usize) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("data.bin"), "x".repeat(file_size)).unwrap();
+        if depth == 0 {
+            return;
+        }
+        for i in 0..width {
+            build_tree(&dir.join(format!("d{}", i)), width, depth - 1, file_size);
+        }
+    }
+
+    #[test]
+    fn test_small_subtrees_are_exact() {
+        let temp = TempDir::new().unwrap();
+        build_tree(&temp.path().join("small"), 2, 2, 10);
+        fs::write(temp.path().join("top.txt"), "12345").unwrap();
+
+        let report = Estimator::new(EstimateConfig::default())
+            .estimate(temp.path())
+            .unwrap();
+
+        assert_eq!(report.subtrees.len(), 1);
+        let small = &report.subtrees[0].estimate;
+        // 1 + 2 + 4 directories with one 10-byte file each
+        assert!(small.exact);
+        assert_eq!(small.bytes, 70.0);
+        assert_eq!(small.files, 7.0);
+        assert_eq!(small.bytes_margin, 0.0);
+
+        assert_eq!(report.root_files.bytes, 5.0);
+        assert!(report.total.exact);
+        assert_eq!(report.total.bytes, 75.0);
+    }
+
+    #[test]
+    fn test_sampling_a_regular_tree_is_exact() {
+        let temp = TempDir::new().unwrap();
+        build_tree(&temp.path().join("big"), 3, 3, 4);
+
+        let config = EstimateConfig {
+            exact_dir_limit: 0,
+            probes: 10,
+            ..Default::default()
+        };
+        let report = Estimator::new(config).estimate(temp.path()).unwrap();
+
+        // Every probe sees the same shape, so the estimate has no spread
+        let big = &report.subtrees[0].estimate;
+        assert!(!big.exact);
+        assert_eq!(big.files, 40.0);
+        assert_eq!(big.bytes, 160.0);
+        assert_eq!(big.bytes_margin, 0.0);
+    }
+
+    #[test]
+    fn test_sampling_an_uneven_tree_brackets_the_true_size() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("uneven");
+        build_tree(&root.join("a"), 2, 3, 100);
+        build_tree(&root.join("b"), 1, 1, 1000);
+        fs::create_dir_all(root.join("c")).unwrap();
+        let true_size = 15.0 * 100.0 + 2.0 * 1000.0;
+
+        let config = EstimateConfig {
+            exact_dir_limit: 0,
+            probes: 400,
+            ..Default::default()
+        };
+        let report = Estimator::new(config).estimate(temp.path()).unwrap();
+        let estimate = &report.subtrees[0].estimate;
+
+        assert!(estimate.bytes_margin > 0.0);
+        let (low, high) = estimate.bytes_range();
+        assert!(
+            (low as f64) <= true_size && true_size <= high as f64,
+            "{} not within {}..{}",
+            true_size,
+            low,
+            high
+        );
+        // Each directory was listed once despite hundreds of probes
+        assert!(report.dirs_read <= 1 + 1 + 15 + 2 + 1);
+    }
+
+    #[test]
+    fn test_large_directories_are_extrapolated() {
+        let temp = TempDir::new().unwrap();
+        for i in 0..50 {
+            fs::write(temp.path().join(format!("f{}.bin", i)), "x".repeat(8)).unwrap();
+        }
+
+        let listing = read_listing(temp.path(), 10).unwrap();
+
+        assert_eq!(listing.files, 50);
+        assert_eq!(listing.bytes, 400.0);
+    }
+
+    #[test]
+    fn test_same_seed_same_estimate() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("t");
+        build_tree(&root.join("a"), 3, 2, 10);
+        build_tree(&root.join("b"), 1, 4, 500);
+
+        let config = EstimateConfig {
+            exact_dir_limit: 0,
+            probes: 5,
+            ..Default::default()
+        };
+        let first = Estimator::new(config.clone())
+            .estimate(temp.path())
+            .unwrap();
+        let second = Estimator::new(config).estimate(temp.path()).unwrap();
+
+        assert_eq!(first.total, second.total);
+    }
+
+    #[test]
+    fn test_missing_root_fails() {
+        let temp = TempDir::new().unwrap();
+        let result = Estimator::new(EstimateConfig::default()).estimate(&temp.path().join("no"));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! User-configured glob patterns for paths left out of scans.
+//!
+//! A pattern without a path separator, such as `node_modules` or `*.iso`,
+//! matches entries by name anywhere in the tree. Any other pattern matches
+//! whole paths: absolute patterns (`C:\Windows`, `/var/lib/docker`) as
+//! written, relative ones (`clients/*/archive`) at any depth. Environment
+//! variables (`%APPDATA%`, `$HOME`, `${HOME}`) and a leading `~` are expanded
+//! first, and backslashes are treated as path separators so Windows-style
+//! patterns work on every platform.
+//!
+//! Excluded directories are not walked at all, which is the point of
+//! excluding something like `C:\Windows`; their contents therefore don't
+//! count toward the sizes of their parents.
+//!
+//! Directories holding megamaid's own volume snapshots ([`SNAPSHOT_DIR`])
+//! are always excluded, whatever the patterns.
+
+use crate::models::{PathCase, SNAPSHOT_DIR};
+use glob::{MatchOptions, Pattern, PatternError};
+use std::path::Path;
+use walkdir::DirEntry;
+
+/// Compiled exclude patterns.
+#[derive(Debug, Clone, Default)]
+pub struct ExcludeSet {
+    /// Patterns matched against entry names
+    names: Vec<Pattern>,
+    /// Patterns matched against whole paths
+    paths: Vec<Pattern>,
+    /// Whether any path pattern is absolute, so relative entry paths must be
+    /// made absolute before matching
+    absolute: bool,
+}
+
+impl ExcludeSet {
+    /// Compiles the given glob patterns.
+    pub fn new<S: AsRef<str>>(globs: &[S]) -> Result<Self, PatternError> {
+        let mut set = Self::default();
+        for glob in globs {
+            let glob = normalize(&expand_vars(glob.as_ref()));
+            let glob = glob.trim_end_matches('/');
+            if !glob.contains('/') {
+                set.names.push(Pattern::new(glob)?);
+            } else if is_absolute(glob) {
+                set.absolute = true;
+                set.paths.push(Pattern::new(glob)?);
+            } else {
+                let glob = glob.trim_start_matches("./");
+                set.paths.push(Pattern::new(&format!("**/{}", glob))?);
+            }
+        }
+        Ok(set)
+    }
+
+    /// Returns true if there are no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.paths.is_empty()
+    }
+
+    /// Returns true if `path` itself matches a pattern.
+    ///
+    /// Ancestors aren't checked; the scanners never walk into an excluded
+    /// directory in the first place.
+    pub fn matches(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            case_sensitive: PathCase::native() == PathCase::Sensitive,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+
+        if let Some(name) = path.file_name() {
+            let name = name.to_string_lossy();
+            if self.names.iter().any(|p| p.matches_with(&name, options)) {
+                return true;
+            }
+        }
+        if self.paths.is_empty() {
+            return false;
+        }
+
+        let mut candidate = normalize(&path.to_string_lossy());
+        if self.absolute && !is_absolute(&candidate) {
+            if let Ok(path) = std::path::absolute(path) {
+                candidate = normalize(&path.to_string_lossy());
+            }
+        }
+        self.paths
+            .iter()
+            .any(|p| p.matches_with(&candidate, options))
+    }
+}
+
+/// Returns true if `entry` is a directory of megamaid's volume snapshots,
+/// each a full copy of the tree being scanned.
+pub(crate) fn is_snapshot_dir(entry: &DirEntry) -> bool {
+    entry.depth() > 0 && entry.file_type().is_dir() && entry.file_name() == SNAPSHOT_DIR
+}
+
+/// Expands `%VAR%`, `$VAR` and `${VAR}` references and a leading `~`.
+///
+/// References to unset variables are left as written.
+pub fn expand_vars(pattern: &str) -> String {
+    let expanded = match pattern.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home) => format!("{}{}", home.display(), rest),
+            None => pattern.to_string(),
+        },
+        _ => pattern.to_string(),
+    };
+
+    let mut out = String::with_capacity(expanded.len());
+    let mut rest = expanded.as_str();
+    while let Some(start) = rest.find(['%', '$']) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, len) = if rest[start..].starts_with('%') {
+            match after.find('%') {
+                Some(end) => (&after[..end], end + 2),
+                None => ("", 1),
+            }
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 3),
+                None => ("", 1),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end + 1)
+        };
+
+        match std::env::var(name) {
+            Ok(value) if !name.is_empty() => out.push_str(&value),
+            _ => out.push_str(&rest[start..start + len]),
+        }
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Returns true for `/...`, `//server/...` and `C:/...` patterns.
+fn is_absolute(pattern: &str) -> bool {
+    let bytes = pattern.as_bytes();
+    pattern.starts_with('/')
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_and_paths() {
+        let set = ExcludeSet::new(&[
+            "node_modules",
+            "*.iso",
+            "C:\\Windows",
+            "/var/lib/docker/",
+            "clients/*/archive",
+        ])
+        .unwrap();
+
+        assert!(set.matches(Path::new("/home/me/app/node_modules")));
+        assert!(set.matches(Path::new("/home/me/disk.iso")));
+        assert!(set.matches(Path::new("C:\\Windows")));
+        assert!(set.matches(Path::new("/var/lib/docker")));
+        assert!(set.matches(Path::new("/srv/clients/acme/archive")));
+
+        assert!(!set.matches(Path::new("/home/me/app/src")));
+        assert!(!set.matches(Path::new("/var/lib/docker-data")));
+        assert!(!set.matches(Path::new("/srv/clients/acme/2024/archive")));
+        assert!(ExcludeSet::new::<&str>(&[]).unwrap().is_empty());
+        assert!(ExcludeSet::new(&["[unclosed"]).is_err());
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        std::env::set_var("MEGAMAID_EXCLUDE_TEST", "/data");
+
+        assert_eq!(expand_vars("%MEGAMAID_EXCLUDE_TEST%/a"), "/data/a");
+        assert_eq!(expand_vars("$MEGAMAID_EXCLUDE_TEST/a"), "/data/a");
+        assert_eq!(expand_vars("${MEGAMAID_EXCLUDE_TEST}/a"), "/data/a");
+        assert_eq!(
+            expand_vars("%MEGAMAID_UNSET_VAR%/a"),
+            "%MEGAMAID_UNSET_VAR%/a"
+        );
+        assert_eq!(expand_vars("100%"), "100%");
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_vars("~/x"), format!("{}/x", home.display()));
+        }
+        assert_eq!(expand_vars("~user"), "~user");
+    }
+}
@@ -0,0 +1,382 @@
+//! Export of scan entries for external analysis.
+//!
+//! Writes one row per scanned entry as CSV or, with the `parquet` feature,
+//! as Parquet, for slicing in DuckDB, pandas or a spreadsheet. The columns are
+//! part of megamaid's stable interface; new columns are only ever appended.
+//!
+//! | Column         | Parquet type            | CSV form                    |
+//! |----------------|-------------------------|-----------------------------|
+//! | `path`         | string                  | absolute path               |
+//! | `name`         | string                  | final path component        |
+//! | `extension`    | string                  | lowercase, empty if none    |
+//! | `entry_type`   | string                  | `file` or `directory`       |
+//! | `size`         | uint64                  | bytes                       |
+//! | `stream_size`  | uint64                  | bytes in alternate streams  |
+//! | `modified`     | timestamp (µs, UTC)     | RFC 3339, e.g. `2025-01-31T08:00:00Z` |
+//! | `depth`        | uint32                  | levels below the scan root  |
+//!
+//! Directory sizes include everything below them, so summing `size` over all
+//! rows counts files several times; filter on `entry_type = 'file'` first.
+
+use crate::models::{EntryType, FileEntry};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Column names of exported entries, in order.
+pub const EXPORT_COLUMNS: [&str; 8] = [
+    "path",
+    "name",
+    "extension",
+    "entry_type",
+    "size",
+    "stream_size",
+    "modified",
+    "depth",
+];
+
+/// File formats scan entries can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+
+    /// Apache Parquet, zstd-compressed (requires the `parquet` feature)
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Picks the format from the file extension: `.parquet` is Parquet, anything else CSV.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("parquet") => Self::Parquet,
+            _ => Self::Csv,
+        }
+    }
+
+    /// Returns true if this build can write the format.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Csv => true,
+            Self::Parquet => cfg!(feature = "parquet"),
+        }
+    }
+}
+
+/// Errors that can occur while exporting entries.
+#[derive(Debug, Error)]
+pub enum ExportError {
+    /// I/O error while writing the file
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// Error from the Parquet writer
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// Error building the Arrow batches written to Parquet
+    #[cfg(feature = "parquet")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+}
+
+/// Where and how entries were exported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// File that was written; differs from the requested one after a CSV fallback
+    pub path: PathBuf,
+
+    /// Format that was written
+    pub format: ExportFormat,
+
+    /// Number of rows written
+    pub rows: usize,
+}
+
+/// Exports `entries` of a scan of `roots` to `path`.
+///
+/// The format follows the extension. Without the `parquet` feature, a
+/// `.parquet` path falls back to CSV next to it, with a `.csv` extension.
+pub fn export_entries(
+    entries: &[FileEntry],
+    roots: &[PathBuf],
+    path: &Path,
+) -> Result<ExportSummary, ExportError> {
+    let mut format = ExportFormat::from_path(path);
+    let mut path = path.to_path_buf();
+    if !format.is_supported() {
+        format = ExportFormat::Csv;
+        path.set_extension("csv");
+    }
+
+    let rows: Vec<ExportRow> = entries.iter().map(|e| ExportRow::new(e, roots)).collect();
+    match format {
+        ExportFormat::Csv => write_csv(&rows, &path)?,
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => write_parquet(&rows, &path)?,
+        #[cfg(not(feature = "parquet"))]
+        ExportFormat::Parquet => unreachable!("unsupported formats fall back to CSV"),
+    }
+
+    Ok(ExportSummary {
+        path,
+        format,
+        rows: rows.len(),
+    })
+}
+
+/// One exported entry, in column order.
+struct ExportRow {
+    path: String,
+    name: String,
+    extension: String,
+    entry_type: &'static str,
+    size: u64,
+    stream_size: u64,
+    modified: DateTime<Utc>,
+    depth: u32,
+}
+
+impl ExportRow {
+    fn new(entry: &FileEntry, roots: &[PathBuf]) -> Self {
+        let depth = roots
+            .iter()
+            .find_map(|root| entry.path.strip_prefix(root).ok())
+            .map_or(0, |relative| relative.components().count() as u32);
+
+        Self {
+            path: entry.path.to_string_lossy().into_owned(),
+            name: entry
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            extension: match entry.entry_type {
+                EntryType::File => entry
+                    .path
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+                    .unwrap_or_default(),
+                EntryType::Directory | EntryType::Symlink | EntryType::Junction => String::new(),
+            },
+            entry_type: match entry.entry_type {
+                EntryType::File => "file",
+                EntryType::Directory => "directory",
+                EntryType::Symlink => "symlink",
+                EntryType::Junction => "junction",
+            },
+            size: entry.size,
+            stream_size: entry.stream_size(),
+            modified: DateTime::<Utc>::from(entry.modified),
+            depth,
+        }
+    }
+}
+
+fn write_csv(rows: &[ExportRow], path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "{}", EXPORT_COLUMNS.join(","))?;
+    for row in rows {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            csv_field(&row.path),
+            csv_field(&row.name),
+            csv_field(&row.extension),
+            row.entry_type,
+            row.size,
+            row.stream_size,
+            row.modified.to_rfc3339_opts(SecondsFormat::Secs, true),
+            row.depth
+        )?;
+    }
+    out.flush()
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet(rows: &[ExportRow], path: &Path) -> Result<(), ExportError> {
+    use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampMicrosecondArray};
+    use arrow_array::{UInt32Array, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::{Compression, ZstdLevel};
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    /// Rows per record batch, bounding memory while writing
+    const BATCH_ROWS: usize = 65_536;
+
+    let utc = Some(Arc::from("UTC"));
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(EXPORT_COLUMNS[0], DataType::Utf8, false),
+        Field::new(EXPORT_COLUMNS[1], DataType::Utf8, false),
+        Field::new(EXPORT_COLUMNS[2], DataType::Utf8, false),
+        Field::new(EXPORT_COLUMNS[3], DataType::Utf8, false),
+        Field::new(EXPORT_COLUMNS[4], DataType::UInt64, false),
+        Field::new(EXPORT_COLUMNS[5], DataType::UInt64, false),
+        Field::new(
+            EXPORT_COLUMNS[6],
+            DataType::Timestamp(TimeUnit::Microsecond, utc.clone()),
+            false,
+        ),
+        Field::new(EXPORT_COLUMNS[7], DataType::UInt32, false),
+    ]));
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(properties))?;
+
+    for chunk in rows.chunks(BATCH_ROWS) {
+        let strings = |field: fn(&ExportRow) -> &str| -> ArrayRef {
+            Arc::new(StringArray::from_iter_values(chunk.iter().map(field)))
+        };
+        let columns: Vec<ArrayRef> = vec![
+            strings(|r| &r.path),
+            strings(|r| &r.name),
+            strings(|r| &r.extension),
+            strings(|r| r.entry_type),
+            Arc::new(UInt64Array::from_iter_values(chunk.iter().map(|r| r.size))),
+            Arc::new(UInt64Array::from_iter_values(
+                chunk.iter().map(|r| r.stream_size),
+            )),
+            Arc::new(
+                TimestampMicrosecondArray::from_iter_values(
+                    chunk.iter().map(|r| r.modified.timestamp_micros()),
+                )
+                .with_timezone_opt(utc.clone()),
+            ),
+            Arc::new(UInt32Array::from_iter_values(chunk.iter().map(|r| r.depth))),
+        ];
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    }
+
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DataStream;
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::TempDir;
+
+    fn sample_entries(root: &Path) -> Vec<FileEntry> {
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut report = FileEntry::new(
+            root.join("docs/Q1, \"final\".PDF"),
+            2048,
+            modified,
+            EntryType::File,
+        );
+        report.streams.push(DataStream {
+            name: "Zone.Identifier".to_string(),
+            size: 26,
+        });
+        vec![
+            FileEntry::new(root.to_path_buf(), 2048, modified, EntryType::Directory),
+            FileEntry::new(root.join("docs"), 2048, modified, EntryType::Directory),
+            report,
+        ]
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let temp = TempDir::new().unwrap();
+        let root = Path::new("/data");
+        let path = temp.path().join("entries.csv");
+
+        let summary = export_entries(&sample_entries(root), &[root.to_path_buf()], &path).unwrap();
+        assert_eq!(summary.format, ExportFormat::Csv);
+        assert_eq!(summary.rows, 3);
+
+        let csv = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "path,name,extension,entry_type,size,stream_size,modified,depth"
+        );
+        assert_eq!(
+            lines[1],
+            format!(
+                "{},data,,directory,2048,0,2023-11-14T22:13:20Z,0",
+                root.display()
+            )
+        );
+        assert!(lines[3].contains(r#","Q1, ""final"".PDF",pdf,file,2048,26,"#));
+        assert!(lines[3].ends_with(",2"));
+    }
+
+    #[test]
+    fn test_export_format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("entries.parquet")),
+            ExportFormat::Parquet
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("entries.csv")),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("entries")),
+            ExportFormat::Csv
+        );
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[test]
+    fn test_parquet_falls_back_to_csv() {
+        let temp = TempDir::new().unwrap();
+        let root = Path::new("/data");
+
+        let summary = export_entries(
+            &sample_entries(root),
+            &[root.to_path_buf()],
+            &temp.path().join("e.parquet"),
+        )
+        .unwrap();
+
+        assert_eq!(summary.format, ExportFormat::Csv);
+        assert_eq!(summary.path, temp.path().join("e.csv"));
+        assert!(summary.path.exists());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_export_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let temp = TempDir::new().unwrap();
+        let root = Path::new("/data");
+        let path = temp.path().join("entries.parquet");
+
+        let summary = export_entries(&sample_entries(root), &[root.to_path_buf()], &path).unwrap();
+        assert_eq!(summary.format, ExportFormat::Parquet);
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 3);
+        let columns: Vec<&str> = metadata
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        assert_eq!(columns, EXPORT_COLUMNS);
+    }
+}
@@ -0,0 +1,141 @@
+//! Durations and entry counts of earlier scans.
+//!
+//! The number of entries under a root isn't known until the walk finishes,
+//! so a scan alone can only show a spinner. The CLI records how many entries
+//! each root had and how long walking them took in a history file in the
+//! user's cache directory (see [`ScanHistory::default_path`]), and seeds the
+//! next scan's [`AdvancedProgress`](crate::scanner::AdvancedProgress) with
+//! them, so its first progress report already has a percentage and an ETA.
+//!
+//! Only the latest scan of each root is kept. Roots are keyed by absolute
+//! path, so a relative root scanned from another directory is another root.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The latest scan of a root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanRecord {
+    /// Entries walked, including those reused from the scan cache
+    pub entries: u64,
+
+    /// How long the walk took, in seconds
+    pub duration_secs: f64,
+
+    /// When the scan finished
+    pub scanned_at: DateTime<Utc>,
+}
+
+impl ScanRecord {
+    /// How long the walk took.
+    pub fn duration(&self) -> Duration {
+        Duration::try_from_secs_f64(self.duration_secs).unwrap_or_default()
+    }
+}
+
+/// The latest scan of each root scanned before.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScanHistory {
+    pub roots: BTreeMap<String, ScanRecord>,
+}
+
+impl ScanHistory {
+    /// Returns the history file in the user's cache directory, or `None` if
+    /// the platform has none.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(
+            dirs::cache_dir()?
+                .join("megamaid")
+                .join("scan-history.json"),
+        )
+    }
+
+    /// Loads a history file; a missing file is an empty history.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the history file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let data = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    /// Records a scan of `root`, replacing the previous one.
+    pub fn record(&mut self, root: &Path, entries: u64, duration: Duration) {
+        self.roots.insert(
+            key(root),
+            ScanRecord {
+                entries,
+                duration_secs: duration.as_secs_f64(),
+                scanned_at: Utc::now(),
+            },
+        );
+    }
+
+    /// Returns the latest scan of `root`, if it was scanned before.
+    pub fn get(&self, root: &Path) -> Option<&ScanRecord> {
+        self.roots.get(&key(root))
+    }
+
+    /// Returns the total entries and duration of the latest scans of all
+    /// `roots`, or `None` unless each of them was scanned before.
+    pub fn expected(&self, roots: &[PathBuf]) -> Option<(u64, Duration)> {
+        roots
+            .iter()
+            .try_fold((0, Duration::ZERO), |(entries, duration), root| {
+                let record = self.get(root)?;
+                Some((entries + record.entries, duration + record.duration()))
+            })
+    }
+}
+
+/// Returns the key `root` is recorded under.
+fn key(root: &Path) -> String {
+    std::path::absolute(root)
+        .unwrap_or_else(|_| root.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_expect_previous_scans() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("megamaid/scan-history.json");
+        assert!(ScanHistory::load(&path).unwrap().roots.is_empty());
+
+        let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+        let mut history = ScanHistory::default();
+        history.record(&a, 100, Duration::from_secs(1));
+        history.record(&a, 1_000, Duration::from_secs(4));
+        history.save(&path).unwrap();
+
+        let mut history = ScanHistory::load(&path).unwrap();
+        assert_eq!(history.get(&a).unwrap().entries, 1_000);
+        assert_eq!(history.expected(&[a.clone(), b.clone()]), None);
+
+        history.record(&b, 500, Duration::from_secs(2));
+        assert_eq!(
+            history.expected(&[a, b]),
+            Some((1_500, Duration::from_secs(6)))
+        );
+    }
+}
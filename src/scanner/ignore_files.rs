@@ -0,0 +1,142 @@
+//! `.gitignore` and `.megamaidignore` files found during traversal.
+//!
+//! With `respect_ignore_files` set, the scanners read these files from every
+//! directory they walk, at or below the scan root, and leave out what they
+//! ignore, without walking into ignored directories. Rules follow gitignore
+//! syntax and precedence: a file applies to its own directory's subtree, a
+//! deeper file overrides a shallower one, and within a directory
+//! `.megamaidignore` overrides `.gitignore`. Global git excludes and
+//! `.git/info/exclude` aren't read.
+//!
+//! Build output such as `target/` or `node_modules/` is usually gitignored,
+//! so scans respecting `.gitignore` won't find it; list exceptions with `!`
+//! patterns in a `.megamaidignore` to keep flagging them.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+use walkdir::DirEntry;
+
+/// Names of the ignore files read in each directory, lowest precedence first.
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".megamaidignore"];
+
+/// Ignore rules of the directories above the entry being walked.
+#[derive(Debug, Default)]
+pub(crate) struct IgnoreStack {
+    /// Rules by directory, shallowest first
+    levels: Vec<(PathBuf, Gitignore)>,
+}
+
+impl IgnoreStack {
+    /// Returns true if `entry` is ignored, and otherwise reads its ignore
+    /// files if it's a directory.
+    ///
+    /// Entries must come in walk order (each directory before its contents),
+    /// as `walkdir` yields them.
+    pub(crate) fn skips(&mut self, entry: &DirEntry) -> bool {
+        let path = entry.path();
+        while self
+            .levels
+            .last()
+            .is_some_and(|(dir, _)| !path.starts_with(dir) || path == dir)
+        {
+            self.levels.pop();
+        }
+
+        let is_dir = entry.file_type().is_dir();
+        if entry.depth() > 0 && self.is_ignored(path, is_dir) {
+            return true;
+        }
+        if is_dir {
+            if let Some(rules) = load(path) {
+                self.levels.push((path.to_path_buf(), rules));
+            }
+        }
+        false
+    }
+
+    /// Checks `path` against the rules from the deepest directory up; the
+    /// first that matches decides.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for (_, rules) in self.levels.iter().rev() {
+            match rules.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+}
+
+/// Reads the ignore files in `dir`, or `None` if it has no rules.
+///
+/// Lines that aren't valid patterns are skipped, as git does.
+fn load(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for name in IGNORE_FILES {
+        let file = dir.join(name);
+        if file.is_file() {
+            found = true;
+            builder.add(file);
+        }
+    }
+    if !found {
+        return None;
+    }
+    builder.build().ok().filter(|rules| !rules.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    use walkdir::WalkDir;
+
+    fn walk(root: &Path) -> Vec<PathBuf> {
+        let mut stack = IgnoreStack::default();
+        WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| !stack.skips(entry))
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_path_buf()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("app/target/debug")).unwrap();
+        fs::create_dir_all(root.join("app/logs")).unwrap();
+        fs::create_dir_all(root.join("web/dist")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\ndist/\n").unwrap();
+        fs::write(root.join("app/.gitignore"), "target/\n").unwrap();
+        fs::write(root.join("app/.megamaidignore"), "!target/\n").unwrap();
+        fs::write(root.join("app/logs/.gitignore"), "!keep.log\n").unwrap();
+        fs::write(root.join("app/logs/run.log"), "x").unwrap();
+        fs::write(root.join("app/logs/keep.log"), "x").unwrap();
+        fs::write(root.join("web/dist/app.js"), "x").unwrap();
+
+        let paths = walk(root);
+        let has = |p: &str| paths.contains(&PathBuf::from(p));
+
+        assert!(!has("web/dist"), "ignored directories aren't walked");
+        assert!(!has("app/logs/run.log"));
+        assert!(has("app/logs/keep.log"), "deeper files override");
+        assert!(
+            has("app/target/debug"),
+            ".megamaidignore overrides .gitignore"
+        );
+        assert!(has("web"));
+    }
+}
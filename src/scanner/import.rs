@@ -0,0 +1,450 @@
+//! Import of scan results produced by other disk usage tools.
+//!
+//! Users often already have an `ncdu` export or `du` listing of a large tree;
+//! importing it yields the same entries a megamaid scan would, so plans can be
+//! generated without walking the tree again.
+//!
+//! Imported sizes are whatever the tool recorded. `ncdu` exports carry
+//! apparent sizes, which match what `megamaid verify` measures; `du` reports
+//! disk usage unless run with `-b`, so such plans will show size drift.
+
+use crate::models::{EntryType, FileEntry};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Output formats of other tools that can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// JSON export written by `ncdu -o`
+    Ncdu,
+
+    /// `du` output: a size and a path per line, separated by whitespace
+    Du,
+}
+
+impl ImportFormat {
+    /// Guesses the format from the file extension: `.json` is ncdu, anything else du.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Ncdu,
+            _ => Self::Du,
+        }
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = ImportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ncdu" => Ok(Self::Ncdu),
+            "du" => Ok(Self::Du),
+            _ => Err(ImportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ncdu => "ncdu",
+            Self::Du => "du",
+        })
+    }
+}
+
+/// Errors that can occur while importing another tool's output.
+#[derive(Debug, Error)]
+pub enum ImportError {
+    /// The format name is not recognized
+    #[error("Unknown import format '{0}' (expected ncdu or du)")]
+    UnknownFormat(String),
+
+    /// I/O error while reading the file
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The ncdu export is not valid JSON
+    #[error("Invalid ncdu export: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The ncdu export is valid JSON but not in the expected layout
+    #[error("Invalid ncdu export: {0}")]
+    InvalidNcdu(String),
+
+    /// A du line has no size or path
+    #[error("Invalid du output on line {line}: {content}")]
+    InvalidDuLine { line: usize, content: String },
+
+    /// The file contains no entries
+    #[error("No entries found in import")]
+    Empty,
+}
+
+/// Entries imported from another tool's output.
+#[derive(Debug)]
+pub struct ImportedScan {
+    /// Directory the tool scanned
+    pub root: PathBuf,
+
+    /// Imported entries, as a scan of `root` would have produced them
+    pub entries: Vec<FileEntry>,
+
+    /// Whether modification times came from the import; when false, entries
+    /// carry the Unix epoch until [`refresh_mtime`] is used
+    pub has_mtimes: bool,
+}
+
+/// Replaces an entry's placeholder mtime with the current one on disk.
+///
+/// Entries that no longer exist keep the placeholder, which verification
+/// reports as missing anyway.
+pub fn refresh_mtime(entry: &mut FileEntry) {
+    if let Ok(modified) = fs::metadata(&entry.path).and_then(|m| m.modified()) {
+        entry.modified = modified;
+    }
+}
+
+/// Imports `path` in the given format.
+///
+/// `du_block_size` is the unit of bare numbers in du output: 1024 for plain
+/// `du`, 1 for `du -b`. Sizes with a suffix (`du -h`) are read as written.
+pub fn import_scan(
+    path: &Path,
+    format: ImportFormat,
+    du_block_size: u64,
+) -> Result<ImportedScan, ImportError> {
+    let reader = BufReader::new(File::open(path)?);
+    match format {
+        ImportFormat::Ncdu => import_ncdu(reader),
+        ImportFormat::Du => import_du(reader, du_block_size),
+    }
+}
+
+/// Reads an ncdu JSON export.
+///
+/// The export is `[major, minor, metadata, root]`, where a directory is an
+/// array of its own info object followed by its children and a file is a
+/// single info object. The root's name is its full path. Excluded and
+/// non-regular entries (symlinks, devices) are skipped; directory sizes are
+/// the sum of the files below them, as megamaid computes them.
+pub fn import_ncdu(reader: impl BufRead) -> Result<ImportedScan, ImportError> {
+    let export: Value = serde_json::from_reader(reader)?;
+    let root = export
+        .as_array()
+        .and_then(|parts| parts.get(3))
+        .ok_or_else(|| ImportError::InvalidNcdu("missing root directory".to_string()))?;
+    let root_name = root
+        .as_array()
+        .and_then(|dir| dir.first())
+        .and_then(|info| info.get("name"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| ImportError::InvalidNcdu("root directory has no name".to_string()))?;
+    let root_path = PathBuf::from(root_name);
+
+    let mut entries = Vec::new();
+    let mut has_mtimes = true;
+    read_ncdu_node(root, &root_path, &mut entries, &mut has_mtimes)?;
+    if entries.is_empty() {
+        return Err(ImportError::Empty);
+    }
+
+    Ok(ImportedScan {
+        root: root_path,
+        entries,
+        has_mtimes,
+    })
+}
+
+/// Adds the entry for `node` (at `path`) and its descendants; returns its size.
+fn read_ncdu_node(
+    node: &Value,
+    path: &Path,
+    entries: &mut Vec<FileEntry>,
+    has_mtimes: &mut bool,
+) -> Result<u64, ImportError> {
+    let (info, children) = match node {
+        Value::Array(dir) => match dir.split_first() {
+            Some((info, children)) => (info, Some(children)),
+            None => {
+                return Err(ImportError::InvalidNcdu(
+                    "empty directory array".to_string(),
+                ))
+            }
+        },
+        info => (info, None),
+    };
+    if !info.is_object() {
+        return Err(ImportError::InvalidNcdu(format!(
+            "expected an object for {}",
+            path.display()
+        )));
+    }
+    if info.get("excluded").is_some() || info.get("notreg").and_then(Value::as_bool) == Some(true) {
+        return Ok(0);
+    }
+
+    let modified = match info.get("mtime").and_then(Value::as_u64) {
+        Some(secs) => UNIX_EPOCH + Duration::from_secs(secs),
+        None => {
+            *has_mtimes = false;
+            UNIX_EPOCH
+        }
+    };
+
+    let Some(children) = children else {
+        let size = ["asize", "dsize"]
+            .iter()
+            .find_map(|key| info.get(*key).and_then(Value::as_u64))
+            .unwrap_or(0);
+        entries.push(FileEntry::new(
+            path.to_path_buf(),
+            size,
+            modified,
+            EntryType::File,
+        ));
+        return Ok(size);
+    };
+
+    // Reserve the directory's slot so it precedes its contents, as in a scan
+    let index = entries.len();
+    entries.push(FileEntry::new(
+        path.to_path_buf(),
+        0,
+        modified,
+        EntryType::Directory,
+    ));
+
+    let mut size = 0u64;
+    for child in children {
+        let name = match child {
+            Value::Array(dir) => dir.first(),
+            info => Some(info),
+        }
+        .and_then(|info| info.get("name"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| ImportError::InvalidNcdu(format!("unnamed entry in {}", path.display())))?;
+        let child_size = read_ncdu_node(child, &path.join(name), entries, has_mtimes)?;
+        size = size.saturating_add(child_size);
+    }
+
+    entries[index].size = size;
+    Ok(size)
+}
+
+/// Reads `du` output (`du`, `du -a`, `du -b`, `du -h`).
+///
+/// Each line is a size followed by a path. The root is the shortest listed
+/// path. du doesn't say which paths are files, so a path is a directory if
+/// other listed paths are inside it or it is one on disk.
+pub fn import_du(reader: impl BufRead, block_size: u64) -> Result<ImportedScan, ImportError> {
+    let mut entries = Vec::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let invalid = || ImportError::InvalidDuLine {
+            line: number + 1,
+            content: line.clone(),
+        };
+        let (size, path) = trimmed
+            .split_once(|c: char| c.is_whitespace())
+            .ok_or_else(invalid)?;
+        let path = path.trim_start();
+        if path.is_empty() {
+            return Err(invalid());
+        }
+        let size = parse_du_size(size, block_size).ok_or_else(invalid)?;
+
+        entries.push(FileEntry::new(
+            PathBuf::from(path),
+            size,
+            UNIX_EPOCH,
+            EntryType::File,
+        ));
+    }
+
+    let root = entries
+        .iter()
+        .min_by_key(|e| e.path.components().count())
+        .map(|e| e.path.clone())
+        .ok_or(ImportError::Empty)?;
+
+    let parents: HashSet<PathBuf> = entries
+        .iter()
+        .filter_map(|e| e.path.parent().map(Path::to_path_buf))
+        .collect();
+    for entry in &mut entries {
+        if parents.contains(&entry.path) || entry.path.is_dir() {
+            entry.entry_type = EntryType::Directory;
+        }
+    }
+
+    // du lists contents before their directory; scans list them after
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(ImportedScan {
+        root,
+        entries,
+        has_mtimes: false,
+    })
+}
+
+/// Parses a du size: a bare number of `block_size` units, or a human-readable
+/// size such as `1.5G` (powers of 1024, as du uses).
+fn parse_du_size(size: &str, block_size: u64) -> Option<u64> {
+    if let Ok(blocks) = size.parse::<u64>() {
+        return blocks.checked_mul(block_size);
+    }
+
+    let split = size.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = size.split_at(split);
+    let exponent = match unit {
+        "K" | "k" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        "P" => 5,
+        _ => return None,
+    };
+    let number: f64 = number.parse().ok()?;
+    Some((number * 1024f64.powi(exponent)) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::time::SystemTime;
+
+    fn now_secs() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn test_import_ncdu() {
+        let export = format!(
+            r#"[1,2,{{"progname":"ncdu","progver":"1.19","timestamp":1700000000}},
+            [{{"name":"/data","asize":4096,"dsize":4096,"mtime":{now}}},
+              {{"name":"big.iso","asize":3000,"dsize":4096,"mtime":{now}}},
+              {{"name":"link","asize":10,"notreg":true}},
+              [{{"name":"cache","asize":4096,"mtime":{now}}},
+                {{"name":"a.bin","asize":100,"mtime":{now}}},
+                {{"name":"b.bin","dsize":50,"mtime":{now}}},
+                {{"name":"skip.tmp","excluded":"pattern"}}
+              ],
+              [{{"name":"empty","asize":4096,"mtime":{now}}}]
+            ]]"#,
+            now = now_secs()
+        );
+
+        let scan = import_ncdu(Cursor::new(export)).unwrap();
+
+        assert_eq!(scan.root, PathBuf::from("/data"));
+        assert!(scan.has_mtimes);
+        let size_of = |path: &str| {
+            scan.entries
+                .iter()
+                .find(|e| e.path == Path::new(path))
+                .map(|e| (e.size, e.entry_type))
+        };
+        assert_eq!(size_of("/data"), Some((3150, EntryType::Directory)));
+        assert_eq!(size_of("/data/big.iso"), Some((3000, EntryType::File)));
+        assert_eq!(size_of("/data/cache"), Some((150, EntryType::Directory)));
+        assert_eq!(size_of("/data/cache/b.bin"), Some((50, EntryType::File)));
+        assert_eq!(size_of("/data/empty"), Some((0, EntryType::Directory)));
+        assert_eq!(size_of("/data/link"), None);
+        assert_eq!(size_of("/data/cache/skip.tmp"), None);
+        assert_eq!(scan.entries.len(), 6);
+        assert_eq!(scan.entries[0].path, PathBuf::from("/data"));
+    }
+
+    #[test]
+    fn test_import_ncdu_without_mtimes() {
+        let export = r#"[1,0,{},[{"name":"/r"},{"name":"f","asize":1}]]"#;
+        let scan = import_ncdu(Cursor::new(export)).unwrap();
+        assert!(!scan.has_mtimes);
+
+        assert!(matches!(
+            import_ncdu(Cursor::new("[1,0,{}]")),
+            Err(ImportError::InvalidNcdu(_))
+        ));
+        assert!(matches!(
+            import_ncdu(Cursor::new("not json")),
+            Err(ImportError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_import_du() {
+        let output = "\
+8\t/nonexistent-du-root/a/b
+20\t/nonexistent-du-root/a
+4\t/nonexistent-du-root/file with spaces.txt
+32\t/nonexistent-du-root
+";
+        let scan = import_du(Cursor::new(output), 1024).unwrap();
+
+        assert_eq!(scan.root, PathBuf::from("/nonexistent-du-root"));
+        assert!(!scan.has_mtimes);
+        assert_eq!(scan.entries.len(), 4);
+        assert_eq!(scan.entries[0].path, PathBuf::from("/nonexistent-du-root"));
+        assert_eq!(scan.entries[0].size, 32 * 1024);
+        assert_eq!(scan.entries[0].entry_type, EntryType::Directory);
+
+        let spaces = scan
+            .entries
+            .iter()
+            .find(|e| e.path.ends_with("file with spaces.txt"))
+            .unwrap();
+        assert_eq!(spaces.size, 4096);
+        assert_eq!(spaces.entry_type, EntryType::File);
+
+        let err = import_du(Cursor::new("12\n"), 1024).unwrap_err();
+        assert!(matches!(err, ImportError::InvalidDuLine { line: 1, .. }));
+        assert!(matches!(
+            import_du(Cursor::new("\n"), 1024),
+            Err(ImportError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_parse_du_size() {
+        assert_eq!(parse_du_size("12", 1024), Some(12 * 1024));
+        assert_eq!(parse_du_size("12", 1), Some(12));
+        assert_eq!(parse_du_size("4.0K", 1024), Some(4096));
+        assert_eq!(parse_du_size("1.5G", 1024), Some(1_610_612_736));
+        assert_eq!(parse_du_size("0", 1024), Some(0));
+        assert_eq!(parse_du_size("12X", 1024), None);
+        assert_eq!(parse_du_size("abc", 1024), None);
+    }
+
+    #[test]
+    fn test_format_from_name_and_path() {
+        assert_eq!("ncdu".parse::<ImportFormat>().unwrap(), ImportFormat::Ncdu);
+        assert_eq!("DU".parse::<ImportFormat>().unwrap(), ImportFormat::Du);
+        assert!("windirstat".parse::<ImportFormat>().is_err());
+        assert_eq!(
+            ImportFormat::from_path(Path::new("export.JSON")),
+            ImportFormat::Ncdu
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("du.txt")),
+            ImportFormat::Du
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Classification of symbolic links and junctions.
+//!
+//! A link must never be treated as the directory it points to: planning it
+//! as one could flag the target's contents, which may live outside the scan
+//! root, and deleting it as one would delete them. Scanners record links as
+//! [`EntryType::Symlink`] or [`EntryType::Junction`] with their target, and
+//! the executor removes only the link itself.
+//!
+//! Windows junctions (mount point reparse points) are told apart from
+//! symbolic links by their reparse tag; on other platforms every link is a
+//! symlink.
+
+use crate::models::EntryType;
+use std::path::{Path, PathBuf};
+use walkdir::DirEntry;
+
+/// Returns the link type and target of a walked entry, or `None` if it isn't
+/// a link.
+///
+/// Links are recognized even when the walk follows them. A target that
+/// can't be read is left out.
+pub(crate) fn link_of(entry: &DirEntry) -> Option<(EntryType, Option<PathBuf>)> {
+    if !entry.path_is_symlink() {
+        return None;
+    }
+    let path = entry.path();
+    Some((platform::link_type(path), std::fs::read_link(path).ok()))
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileAttributeTagInfo, GetFileInformationByHandleEx, FILE_ATTRIBUTE_TAG_INFO,
+        FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+    };
+
+    /// Reparse tag of junctions (`IO_REPARSE_TAG_MOUNT_POINT`).
+    const MOUNT_POINT_TAG: u32 = 0xA000_0003;
+
+    /// Returns the type of the link at `path`; links whose reparse tag can't
+    /// be read are symlinks.
+    pub fn link_type(path: &Path) -> EntryType {
+        if reparse_tag(path).is_ok_and(|tag| tag == MOUNT_POINT_TAG) {
+            EntryType::Junction
+        } else {
+            EntryType::Symlink
+        }
+    }
+
+    fn reparse_tag(path: &Path) -> std::io::Result<u32> {
+        // Open the link itself rather than its target; directories can only
+        // be opened with backup semantics
+        let file = std::fs::OpenOptions::new()
+            .access_mode(0)
+            .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT | FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)?;
+        // SAFETY: `info` is a plain struct of the size passed, which the call
+        // fills in, and the handle stays open for the duration of the call
+        let mut info: FILE_ATTRIBUTE_TAG_INFO = unsafe { std::mem::zeroed() };
+        let filled = unsafe {
+            GetFileInformationByHandleEx(
+                file.as_raw_handle() as _,
+                FileAttributeTagInfo,
+                &mut info as *mut _ as *mut _,
+                std::mem::size_of::<FILE_ATTRIBUTE_TAG_INFO>() as u32,
+            )
+        };
+        if filled == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(info.ReparseTag)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    /// Junctions only exist on Windows.
+    pub fn link_type(_path: &Path) -> EntryType {
+        EntryType::Symlink
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use walkdir::WalkDir;
+
+    #[test]
+    fn test_links_are_classified_with_their_target() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("target");
+        std::fs::create_dir(&target).unwrap();
+        std::os::unix::fs::symlink(&target, temp.path().join("link")).unwrap();
+
+        let links: Vec<_> = WalkDir::new(temp.path())
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(|entry| link_of(&entry))
+            .collect();
+
+        assert_eq!(
+            links,
+            vec![None, Some((EntryType::Symlink, Some(target))), None]
+        );
+    }
+}
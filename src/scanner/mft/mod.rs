@@ -0,0 +1,137 @@
+//! NTFS master file table scan backend (Windows, `mft` feature).
+//!
+//! Walking a large NTFS volume lists every directory and reads the metadata
+//! of every entry through the filesystem, which takes minutes on drives with
+//! millions of files. The name, parent directory, size, timestamps and
+//! attributes of every file are also stored in the volume's master file
+//! table (MFT), which can be read sequentially in large chunks instead. With
+//! `scanner.backend: mft`, [`MftScanner`] reads the whole table, rebuilds the
+//! directory tree from it and returns the entries under the scan root, with
+//! the same [`FileEntry`] output and directory sizes as a walk.
+//!
+//! Reading the MFT needs an elevated (administrator) process and a local
+//! NTFS volume, and the whole volume's table is read whatever the root, so
+//! the backend pays off most on whole-drive scans. Where it is unavailable
+//! (other platforms, builds without the `mft` feature, other filesystems, no
+//! administrator rights) [`MftScanner::scan`] fails and the CLI walks the
+//! tree instead. Ignore files aren't read, alternate data streams aren't
+//! listed, links are never followed and link targets aren't recorded.
+//! Owners aren't in the file records themselves, so `scanner.collect_owner`
+//! looks them up entry by entry, giving up much of the speed.
+
+#[cfg_attr(not(all(windows, feature = "mft")), allow(dead_code))]
+mod record;
+#[cfg_attr(not(all(windows, feature = "mft")), allow(dead_code))]
+mod tree;
+#[cfg(all(windows, feature = "mft"))]
+mod volume;
+
+use crate::models::{FileEntry, OwnArtifacts};
+use crate::scanner::traversal::ScanConfig;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+/// How scans list the entries under their roots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanBackend {
+    /// Walk the directory tree
+    #[default]
+    Walk,
+
+    /// Read the NTFS master file table, walking where it is unavailable
+    Mft,
+}
+
+/// Errors reading the MFT.
+#[derive(Debug, Error)]
+pub enum MftError {
+    /// The platform or build can't read the MFT
+    #[error("reading the MFT needs Windows and a build with the mft feature")]
+    Unsupported,
+
+    /// The root isn't on a local NTFS volume
+    #[error("{0} is not on a local NTFS volume")]
+    NotNtfs(String),
+
+    /// The process isn't elevated
+    #[error("reading the MFT requires administrator rights")]
+    AccessDenied,
+
+    /// The root isn't in the MFT
+    #[error("Path does not exist: {0}")]
+    PathNotFound(String),
+
+    /// The MFT couldn't be parsed
+    #[error("unreadable MFT: {0}")]
+    Corrupt(String),
+
+    #[error("Invalid exclude pattern: {0}")]
+    Pattern(#[from] glob::PatternError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Scans directories by reading the MFT of their volume.
+pub struct MftScanner {
+    #[cfg_attr(not(all(windows, feature = "mft")), allow(dead_code))]
+    config: ScanConfig,
+    #[cfg_attr(not(all(windows, feature = "mft")), allow(dead_code))]
+    exclude: OwnArtifacts,
+}
+
+impl MftScanner {
+    /// Creates a new MftScanner with the given configuration.
+    pub fn new(config: ScanConfig) -> Self {
+        Self {
+            config,
+            exclude: OwnArtifacts::default(),
+        }
+    }
+
+    /// Leaves megamaid's own files, and everything inside its own
+    /// directories, out of scans.
+    pub fn with_exclusions(mut self, artifacts: OwnArtifacts) -> Self {
+        self.exclude = artifacts;
+        self
+    }
+
+    /// Scans the given root directory and returns all entries.
+    #[cfg(all(windows, feature = "mft"))]
+    pub fn scan(&self, root: &Path) -> Result<Vec<FileEntry>, MftError> {
+        use crate::scanner::exclude::ExcludeSet;
+
+        let volume = volume::read(root)?;
+        let not_found = || MftError::PathNotFound(root.display().to_string());
+        let absolute = std::path::absolute(root)?;
+        let relative = absolute
+            .strip_prefix(&volume.mount)
+            .map_err(|_| not_found())?;
+        let root_record = volume.table.lookup(relative).ok_or_else(not_found)?;
+
+        let globs = ExcludeSet::new(&self.config.exclude_globs)?;
+        let excluded = self.exclude.within(root);
+        let mut entries = volume
+            .table
+            .entries(root_record, root, &self.config, &globs, &excluded);
+        if self.config.collect_owner {
+            for entry in &mut entries {
+                if let Ok(metadata) = std::fs::symlink_metadata(&entry.path) {
+                    crate::scanner::owner::collect_owner(entry, &metadata);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Scans the given root directory and returns all entries.
+    ///
+    /// Always fails with [`MftError::Unsupported`] on this platform or build.
+    #[cfg(not(all(windows, feature = "mft")))]
+    pub fn scan(&self, _root: &Path) -> Result<Vec<FileEntry>, MftError> {
+        Err(MftError::Unsupported)
+    }
+}
@@ -0,0 +1,465 @@
+//! Parsing of MFT file records.
+//!
+//! Each file and directory has a fixed-size file record (usually 1 KiB) in
+//! the MFT, holding a header and a list of attributes. Only the attributes a
+//! [`FileEntry`](crate::models::FileEntry) needs are read: the standard
+//! information (modification time and file attributes), the file names
+//! (parent directory and name), the unnamed data stream (size) and the
+//! reparse point (tag).
+
+use super::MftError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Record number of the volume's root directory.
+pub(crate) const ROOT_RECORD: u64 = 5;
+
+/// Bytes covered by each update sequence entry of a record.
+const SECTOR_STRIDE: usize = 512;
+
+/// Record header flag of records in use.
+const FLAG_IN_USE: u16 = 0x01;
+
+/// Record header flag of directories.
+const FLAG_DIRECTORY: u16 = 0x02;
+
+const ATTR_STANDARD_INFORMATION: u32 = 0x10;
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_DATA: u32 = 0x80;
+const ATTR_REPARSE_POINT: u32 = 0xC0;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+
+/// File name namespace of names only kept for DOS (8.3 short names).
+const NAMESPACE_DOS: u8 = 2;
+
+/// 100-nanosecond intervals between 1601-01-01 and the Unix epoch.
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// A name of a file in a directory; hard links have several.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileName {
+    /// Record number of the directory
+    pub parent: u64,
+    pub name: String,
+}
+
+/// A contiguous extent of a non-resident attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DataRun {
+    /// First cluster on the volume
+    pub lcn: u64,
+    /// Length in clusters
+    pub clusters: u64,
+}
+
+/// The parts of a file record a scan needs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct FileRecord {
+    /// Record number
+    pub number: u64,
+
+    /// Record number of the base record, for extension records holding
+    /// attributes that didn't fit in it
+    pub base: Option<u64>,
+
+    pub directory: bool,
+    pub names: Vec<FileName>,
+
+    /// Size of the unnamed data stream
+    pub size: Option<u64>,
+
+    /// Last modification time, as a Windows FILETIME
+    pub modified: Option<u64>,
+
+    /// Windows file attributes (`FILE_ATTRIBUTE_*`)
+    pub attributes: Option<u32>,
+
+    /// Reparse tag of symlinks, junctions and other reparse points
+    pub reparse_tag: Option<u32>,
+
+    /// Extents of the unnamed data stream, if it is non-resident
+    pub data_runs: Vec<DataRun>,
+}
+
+impl FileRecord {
+    /// Adds the attributes of an extension record of this record.
+    pub(crate) fn merge(&mut self, extension: FileRecord) {
+        self.names.extend(extension.names);
+        self.size = self.size.or(extension.size);
+        self.modified = self.modified.or(extension.modified);
+        self.attributes = self.attributes.or(extension.attributes);
+        self.reparse_tag = self.reparse_tag.or(extension.reparse_tag);
+        self.data_runs.extend(extension.data_runs);
+    }
+
+    /// Last modification time, or the Unix epoch if it isn't recorded.
+    pub(crate) fn modified_time(&self) -> SystemTime {
+        let since_unix = self
+            .modified
+            .unwrap_or(0)
+            .saturating_sub(FILETIME_UNIX_EPOCH);
+        UNIX_EPOCH + Duration::from_nanos(since_unix.saturating_mul(100))
+    }
+}
+
+/// Parses file record `number` from `data`, the record as stored on disk.
+///
+/// Returns `None` for records not in use. The update sequence fixups are
+/// applied to `data` in place.
+pub(crate) fn parse(number: u64, data: &mut [u8]) -> Result<Option<FileRecord>, MftError> {
+    if data.len() < 0x30 || &data[..4] != b"FILE" {
+        // Never-used records are zeroed; "BAAD" records failed a write
+        return Ok(None);
+    }
+    apply_fixups(number, data)?;
+
+    let flags = u16_at(data, 0x16);
+    if flags & FLAG_IN_USE == 0 {
+        return Ok(None);
+    }
+    let base = u64_at(data, 0x20) & 0xFFFF_FFFF_FFFF;
+    let mut record = FileRecord {
+        number,
+        base: (base != 0).then_some(base),
+        directory: flags & FLAG_DIRECTORY != 0,
+        ..FileRecord::default()
+    };
+
+    let mut offset = usize::from(u16_at(data, 0x14));
+    while offset + 0x10 <= data.len() {
+        let kind = u32_at(data, offset);
+        let length = u32_at(data, offset + 4) as usize;
+        if kind == ATTR_END {
+            break;
+        }
+        // Every attribute has at least the 0x18-byte resident header
+        let attr = data
+            .get(offset..offset.saturating_add(length))
+            .filter(|attr| attr.len() >= 0x18)
+            .ok_or_else(|| {
+                MftError::Corrupt(format!(
+                    "record {} has an attribute of invalid length {}",
+                    number, length
+                ))
+            })?;
+        parse_attribute(&mut record, attr)?;
+        offset += length;
+    }
+    Ok(Some(record))
+}
+
+fn parse_attribute(record: &mut FileRecord, attr: &[u8]) -> Result<(), MftError> {
+    let kind = u32_at(attr, 0);
+    let non_resident = attr[8] != 0;
+    let unnamed = attr[9] == 0;
+
+    if non_resident {
+        if attr.len() < 0x40 {
+            return Err(MftError::Corrupt(format!(
+                "non-resident attribute 0x{:X} is too short",
+                kind
+            )));
+        }
+        // Only the data stream's size and extents are read from non-resident
+        // attributes; the size is in the extent starting at cluster 0
+        if kind == ATTR_DATA && unnamed {
+            if u64_at(attr, 0x10) == 0 {
+                record.size = Some(u64_at(attr, 0x30));
+            }
+            let runs = usize::from(u16_at(attr, 0x20));
+            record
+                .data_runs
+                .extend(parse_data_runs(attr.get(runs..).unwrap_or(&[]))?);
+        }
+        return Ok(());
+    }
+
+    let value_length = u32_at(attr, 0x10) as usize;
+    let value_offset = usize::from(u16_at(attr, 0x14));
+    let value = attr
+        .get(value_offset..value_offset.saturating_add(value_length))
+        .ok_or_else(|| MftError::Corrupt(format!("attribute 0x{:X} overruns its record", kind)))?;
+
+    match kind {
+        ATTR_STANDARD_INFORMATION if value.len() >= 0x24 => {
+            record.modified = Some(u64_at(value, 0x08));
+            record.attributes = Some(u32_at(value, 0x20));
+        }
+        ATTR_FILE_NAME if value.len() >= 0x42 => {
+            let chars = usize::from(value[0x40]);
+            let namespace = value[0x41];
+            let Some(name) = value.get(0x42..0x42 + chars * 2) else {
+                return Ok(());
+            };
+            if namespace != NAMESPACE_DOS {
+                let units: Vec<u16> = name
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                record.names.push(FileName {
+                    parent: u64_at(value, 0) & 0xFFFF_FFFF_FFFF,
+                    name: String::from_utf16_lossy(&units),
+                });
+            }
+        }
+        ATTR_DATA if unnamed => record.size = Some(value_length as u64),
+        ATTR_REPARSE_POINT if value.len() >= 4 => record.reparse_tag = Some(u32_at(value, 0)),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Restores the last two bytes of each sector, which the record's update
+/// sequence array holds while the sector ends with the sequence number.
+fn apply_fixups(number: u64, data: &mut [u8]) -> Result<(), MftError> {
+    let usa_offset = usize::from(u16_at(data, 0x04));
+    let usa_count = usize::from(u16_at(data, 0x06));
+    if usa_count == 0 || usa_offset + usa_count * 2 > data.len() {
+        return Err(MftError::Corrupt(format!(
+            "record {} has an invalid update sequence",
+            number
+        )));
+    }
+    let sequence = [data[usa_offset], data[usa_offset + 1]];
+    for i in 1..usa_count {
+        let end = i * SECTOR_STRIDE;
+        if end > data.len() {
+            break;
+        }
+        if data[end - 2..end] != sequence {
+            return Err(MftError::Corrupt(format!(
+                "record {} was torn by an interrupted write",
+                number
+            )));
+        }
+        data[end - 2] = data[usa_offset + i * 2];
+        data[end - 1] = data[usa_offset + i * 2 + 1];
+    }
+    Ok(())
+}
+
+/// Decodes a mapping pairs array into the extents it describes; sparse
+/// extents are left out.
+pub(crate) fn parse_data_runs(mut data: &[u8]) -> Result<Vec<DataRun>, MftError> {
+    let mut runs = Vec::new();
+    let mut lcn: i64 = 0;
+    while let Some((&header, rest)) = data.split_first() {
+        if header == 0 {
+            break;
+        }
+        let length_size = usize::from(header & 0x0F);
+        let offset_size = usize::from(header >> 4);
+        if rest.len() < length_size + offset_size
+            || length_size == 0
+            || length_size > 8
+            || offset_size > 8
+        {
+            break;
+        }
+        let clusters = unsigned(&rest[..length_size]);
+        if offset_size > 0 {
+            lcn = lcn
+                .checked_add(signed(&rest[length_size..length_size + offset_size]))
+                .filter(|lcn| *lcn >= 0)
+                .ok_or_else(|| MftError::Corrupt("data run points outside the volume".into()))?;
+            runs.push(DataRun {
+                lcn: lcn as u64,
+                clusters,
+            });
+        }
+        data = &rest[length_size + offset_size..];
+    }
+    Ok(runs)
+}
+
+fn unsigned(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8) | u64::from(byte))
+}
+
+fn signed(bytes: &[u8]) -> i64 {
+    let shift = 64 - 8 * bytes.len() as u32;
+    ((unsigned(bytes) << shift) as i64) >> shift
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a 1 KiB file record with the given attributes, protected by an
+    /// update sequence as on disk.
+    pub(crate) fn record(flags: u16, attributes: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0u8; 1024];
+        data[..4].copy_from_slice(b"FILE");
+        data[0x04..0x06].copy_from_slice(&0x30u16.to_le_bytes());
+        data[0x06..0x08].copy_from_slice(&3u16.to_le_bytes());
+        data[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+        data[0x16..0x18].copy_from_slice(&flags.to_le_bytes());
+
+        let mut offset = 0x38;
+        for attr in attributes {
+            data[offset..offset + attr.len()].copy_from_slice(attr);
+            offset += attr.len();
+        }
+        data[offset..offset + 4].copy_from_slice(&ATTR_END.to_le_bytes());
+
+        // Move the last two bytes of each sector into the update sequence
+        data[0x30..0x32].copy_from_slice(&[0xAB, 0xCD]);
+        for i in 1..3 {
+            let end = i * SECTOR_STRIDE;
+            data[0x30 + i * 2] = data[end - 2];
+            data[0x31 + i * 2] = data[end - 1];
+            data[end - 2..end].copy_from_slice(&[0xAB, 0xCD]);
+        }
+        data
+    }
+
+    /// Builds a resident attribute with `value`.
+    pub(crate) fn resident(kind: u32, value: &[u8]) -> Vec<u8> {
+        let length = (0x18 + value.len()).next_multiple_of(8);
+        let mut attr = vec![0u8; length];
+        attr[..4].copy_from_slice(&kind.to_le_bytes());
+        attr[4..8].copy_from_slice(&(length as u32).to_le_bytes());
+        attr[0x10..0x14].copy_from_slice(&(value.len() as u32).to_le_bytes());
+        attr[0x14..0x16].copy_from_slice(&0x18u16.to_le_bytes());
+        attr[0x18..0x18 + value.len()].copy_from_slice(value);
+        attr
+    }
+
+    pub(crate) fn file_name(parent: u64, name: &str, namespace: u8) -> Vec<u8> {
+        let units: Vec<u16> = name.encode_utf16().collect();
+        let mut value = vec![0u8; 0x42];
+        value[..8].copy_from_slice(&parent.to_le_bytes());
+        value[0x40] = units.len() as u8;
+        value[0x41] = namespace;
+        value.extend(units.iter().flat_map(|u| u.to_le_bytes()));
+        resident(ATTR_FILE_NAME, &value)
+    }
+
+    pub(crate) fn standard_information(modified: u64, attributes: u32) -> Vec<u8> {
+        let mut value = vec![0u8; 0x48];
+        value[0x08..0x10].copy_from_slice(&modified.to_le_bytes());
+        value[0x20..0x24].copy_from_slice(&attributes.to_le_bytes());
+        resident(ATTR_STANDARD_INFORMATION, &value)
+    }
+
+    fn non_resident_data(size: u64, runs: &[u8]) -> Vec<u8> {
+        let length = (0x40 + runs.len()).next_multiple_of(8);
+        let mut attr = vec![0u8; length];
+        attr[..4].copy_from_slice(&ATTR_DATA.to_le_bytes());
+        attr[4..8].copy_from_slice(&(length as u32).to_le_bytes());
+        attr[8] = 1;
+        attr[0x20..0x22].copy_from_slice(&0x40u16.to_le_bytes());
+        attr[0x30..0x38].copy_from_slice(&size.to_le_bytes());
+        attr[0x40..0x40 + runs.len()].copy_from_slice(runs);
+        attr
+    }
+
+    #[test]
+    fn test_parse_file_record() {
+        let modified = FILETIME_UNIX_EPOCH + 10_000_000 * 60;
+        let mut data = record(
+            FLAG_IN_USE,
+            &[
+                standard_information(modified, 0x20),
+                file_name(ROOT_RECORD, "REPORT~1.PDF", NAMESPACE_DOS),
+                file_name(ROOT_RECORD, "report 2024.pdf", 1),
+                // Two extents, the second 0x20 clusters before the first
+                non_resident_data(5_000, &[0x21, 0x04, 0x00, 0x01, 0x11, 0x02, 0xE0, 0x00]),
+            ],
+        );
+        // A byte at the end of the first sector, covered by the fixup
+        assert_eq!(data[510..512], [0xAB, 0xCD]);
+
+        let record = parse(42, &mut data).unwrap().unwrap();
+        assert!(!record.directory);
+        assert_eq!(record.base, None);
+        assert_eq!(
+            record.names,
+            vec![FileName {
+                parent: ROOT_RECORD,
+                name: "report 2024.pdf".to_string()
+            }]
+        );
+        assert_eq!(record.size, Some(5_000));
+        assert_eq!(record.attributes, Some(0x20));
+        assert_eq!(record.modified_time(), UNIX_EPOCH + Duration::from_secs(60));
+        assert_eq!(
+            record.data_runs,
+            vec![
+                DataRun {
+                    lcn: 0x100,
+                    clusters: 4
+                },
+                DataRun {
+                    lcn: 0xE0,
+                    clusters: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unused_and_torn_records() {
+        let mut unused = record(0, &[]);
+        assert_eq!(parse(1, &mut unused).unwrap(), None);
+        assert_eq!(parse(2, &mut vec![0u8; 1024]).unwrap(), None);
+
+        let mut torn = record(FLAG_IN_USE, &[]);
+        torn[1022] = 0;
+        assert!(matches!(parse(3, &mut torn), Err(MftError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_malformed_records() {
+        // An attribute shorter than the resident header
+        let mut short = vec![0u8; 0x10];
+        short[..4].copy_from_slice(&ATTR_DATA.to_le_bytes());
+        short[4..8].copy_from_slice(&0x10u32.to_le_bytes());
+        let mut data = record(FLAG_IN_USE, &[short]);
+        assert!(matches!(parse(4, &mut data), Err(MftError::Corrupt(_))));
+
+        // A non-resident attribute shorter than the non-resident header
+        let mut non_resident = resident(ATTR_DATA, &[0; 8]);
+        non_resident[8] = 1;
+        let mut data = record(FLAG_IN_USE, &[non_resident]);
+        assert!(matches!(parse(5, &mut data), Err(MftError::Corrupt(_))));
+
+        // An attribute running past the end of the record
+        let mut long = resident(ATTR_DATA, &[0; 8]);
+        long[4..8].copy_from_slice(&0x1000u32.to_le_bytes());
+        let mut data = record(FLAG_IN_USE, &[long]);
+        assert!(matches!(parse(6, &mut data), Err(MftError::Corrupt(_))));
+
+        // A resident value running past the end of its attribute
+        let mut value = resident(ATTR_DATA, &[0; 8]);
+        value[0x10..0x14].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut data = record(FLAG_IN_USE, &[value]);
+        assert!(matches!(parse(7, &mut data), Err(MftError::Corrupt(_))));
+
+        // Data runs whose cluster offsets overflow or go below cluster 0
+        let overflow = [0x81, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F];
+        let overflow = [&overflow[..], &overflow[..]].concat();
+        assert!(matches!(
+            parse_data_runs(&overflow),
+            Err(MftError::Corrupt(_))
+        ));
+        assert!(matches!(
+            parse_data_runs(&[0x11, 0x01, 0xF0, 0x00]),
+            Err(MftError::Corrupt(_))
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+//! Rebuilding the directory tree from MFT file records.
+//!
+//! File records only name their parent directory, so the tree is rebuilt by
+//! indexing the records by parent. Entries under the scan root are then
+//! listed as a walk would list them: the root first, each directory before
+//! its contents, with directory sizes summed from the files below them.
+
+use super::record::{FileRecord, ROOT_RECORD};
+use crate::models::{EntryType, FileEntry, PathCase};
+use crate::scanner::exclude::ExcludeSet;
+use crate::scanner::traversal::ScanConfig;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Reparse tag of junctions and mounted folders.
+const TAG_MOUNT_POINT: u32 = 0xA000_0003;
+
+/// Reparse tag of symbolic links.
+const TAG_SYMLINK: u32 = 0xA000_000C;
+
+/// File attributes of files whose contents aren't stored locally, see
+/// [`crate::scanner::cloud`].
+const NOT_LOCAL: u32 = 0x0000_1000 | 0x0004_0000 | 0x0040_0000;
+
+/// The file records of a volume, by record number.
+#[derive(Debug, Default)]
+pub(crate) struct FileTable {
+    records: HashMap<u64, FileRecord>,
+
+    /// Children of each directory, as record number and name
+    children: HashMap<u64, Vec<(u64, String)>>,
+}
+
+impl FileTable {
+    /// Builds the table from parsed records, merging extension records into
+    /// their base records.
+    pub(crate) fn new(parsed: impl IntoIterator<Item = FileRecord>) -> Self {
+        let mut records = HashMap::new();
+        let mut extensions = Vec::new();
+        for record in parsed {
+            match record.base {
+                Some(_) => extensions.push(record),
+                None => {
+                    records.insert(record.number, record);
+                }
+            }
+        }
+        for extension in extensions {
+            if let Some(base) = extension.base.and_then(|base| records.get_mut(&base)) {
+                base.merge(extension);
+            }
+        }
+
+        let mut children: HashMap<u64, Vec<(u64, String)>> = HashMap::new();
+        for record in records.values() {
+            for name in &record.names {
+                // The root directory is its own parent
+                if record.number != ROOT_RECORD {
+                    children
+                        .entry(name.parent)
+                        .or_default()
+                        .push((record.number, name.name.clone()));
+                }
+            }
+        }
+        for list in children.values_mut() {
+            list.sort_by(|a, b| a.1.cmp(&b.1));
+        }
+        Self { records, children }
+    }
+
+    /// Returns the record of the directory at `relative`, a path relative to
+    /// the volume root; names are matched case-insensitively, as NTFS does.
+    pub(crate) fn lookup(&self, relative: &Path) -> Option<u64> {
+        relative
+            .components()
+            .try_fold(ROOT_RECORD, |dir, component| match component {
+                Component::Normal(name) => {
+                    let name = name.to_str()?;
+                    self.children
+                        .get(&dir)?
+                        .iter()
+                        .find(|(_, child)| {
+                            PathCase::Insensitive.eq(Path::new(child), Path::new(name))
+                        })
+                        .map(|(number, _)| *number)
+                }
+                _ => Some(dir),
+            })
+    }
+
+    /// Lists the entries under directory `root_record`, with `root` as the
+    /// path of that directory.
+    ///
+    /// `excluded` holds megamaid's own artifacts under the root, which are
+    /// left out like hidden entries: their sizes still count toward their
+    /// directories. Glob-excluded subtrees don't count toward them, since a
+    /// walk never visits them.
+    pub(crate) fn entries(
+        &self,
+        root_record: u64,
+        root: &Path,
+        config: &ScanConfig,
+        globs: &ExcludeSet,
+        excluded: &[PathBuf],
+    ) -> Vec<FileEntry> {
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut visited = HashSet::from([root_record]);
+        let mut stack = vec![(root_record, root.to_path_buf(), None, 0usize)];
+        let max_depth = config.max_depth.unwrap_or(usize::MAX);
+
+        while let Some((number, path, parent, depth)) = stack.pop() {
+            let Some(record) = self.records.get(&number) else {
+                continue;
+            };
+            let link = link_type(record);
+            let placeholder = !record.directory
+                && record
+                    .attributes
+                    .is_some_and(|attrs| attrs & NOT_LOCAL != 0);
+
+            let hidden = config.skip_hidden
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with('.'));
+            let own = excluded
+                .iter()
+                .any(|artifact| PathCase::native().starts_with(&path, artifact));
+
+            let entry_type = match link {
+                Some(link_type) => link_type,
+                None if record.directory => EntryType::Directory,
+                None => EntryType::File,
+            };
+            let size = match link {
+                Some(_) => 0,
+                None if record.directory => 0,
+                None => record.size.unwrap_or(0),
+            };
+            let mut entry = FileEntry::with_file_id(
+                path.clone(),
+                size,
+                record.modified_time(),
+                entry_type,
+                Some(number),
+            );
+            entry.cloud_placeholder = placeholder;
+
+            let index = nodes.len();
+            nodes.push(Node {
+                entry,
+                parent,
+                listed: depth <= max_depth && !hidden && !own,
+                counted: link.is_none() && !placeholder,
+            });
+
+            // Links are never followed; directories below the depth limit
+            // are still read for their sizes
+            if record.directory && link.is_none() {
+                let children = self.children.get(&number).map(Vec::as_slice).unwrap_or(&[]);
+                // Reversed, so the stack yields them in name order
+                for (child, name) in children.iter().rev() {
+                    let child_path = path.join(name);
+                    if globs.matches(&child_path) {
+                        continue;
+                    }
+                    let is_dir = self.records.get(child).is_some_and(|r| r.directory);
+                    if is_dir && !visited.insert(*child) {
+                        continue;
+                    }
+                    stack.push((*child, child_path, Some(index), depth + 1));
+                }
+            }
+        }
+
+        // Children come after their parents, so summing backwards finishes
+        // each directory before its own parent is reached
+        for index in (1..nodes.len()).rev() {
+            let node = &nodes[index];
+            if let (Some(parent), true) = (node.parent, node.counted) {
+                let size = node.entry.size;
+                nodes[parent].entry.size += size;
+            }
+        }
+
+        nodes
+            .into_iter()
+            .filter(|node| node.listed)
+            .map(|node| node.entry)
+            .collect()
+    }
+}
+
+/// An entry found under the scan root.
+struct Node {
+    entry: FileEntry,
+
+    /// Index of the parent directory's node
+    parent: Option<usize>,
+
+    /// Returned by the scan, rather than only counted toward sizes
+    listed: bool,
+
+    /// Counts toward the parent directory's size
+    counted: bool,
+}
+
+/// Returns the link type of a record, if it is a symlink or junction.
+fn link_type(record: &FileRecord) -> Option<EntryType> {
+    match record.reparse_tag? {
+        TAG_MOUNT_POINT => Some(EntryType::Junction),
+        TAG_SYMLINK => Some(EntryType::Symlink),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::mft::record::FileName;
+
+    fn record(number: u64, parent: u64, name: &str, size: Option<u64>) -> FileRecord {
+        FileRecord {
+            number,
+            directory: size.is_none(),
+            names: vec![FileName {
+                parent,
+                name: name.to_string(),
+            }],
+            size,
+            ..FileRecord::default()
+        }
+    }
+
+    fn table() -> FileTable {
+        let mut link = record(30, 20, "node_modules", None);
+        link.reparse_tag = Some(TAG_MOUNT_POINT);
+        let mut online_only = record(31, 20, "video.mp4", Some(9_000));
+        online_only.attributes = Some(0x0040_0000);
+        // The data attribute of record 32 lives in an extension record
+        let extension = FileRecord {
+            number: 40,
+            base: Some(32),
+            size: Some(7),
+            ..FileRecord::default()
+        };
+        FileTable::new([
+            record(ROOT_RECORD, ROOT_RECORD, ".", None),
+            record(20, ROOT_RECORD, "Users", None),
+            record(21, 20, "a.txt", Some(100)),
+            record(22, 20, ".cache", None),
+            record(23, 22, "blob", Some(50)),
+            record(24, 20, "logs", None),
+            record(25, 24, "old.log", Some(1_000)),
+            link,
+            online_only,
+            FileRecord {
+                directory: false,
+                ..record(32, 24, "new.log", None)
+            },
+            extension,
+        ])
+    }
+
+    fn scan(config: ScanConfig, globs: &[&str]) -> Vec<FileEntry> {
+        let table = table();
+        let users = table.lookup(Path::new("users")).unwrap();
+        let globs = ExcludeSet::new(globs).unwrap();
+        table.entries(users, Path::new("C:/Users"), &config, &globs, &[])
+    }
+
+    fn listed(entries: &[FileEntry]) -> Vec<(String, u64)> {
+        entries
+            .iter()
+            .map(|e| (e.path.to_string_lossy().replace('\\', "/"), e.size))
+            .collect()
+    }
+
+    #[test]
+    fn test_entries_are_listed_like_a_walk() {
+        let config = ScanConfig {
+            skip_hidden: true,
+            ..ScanConfig::default()
+        };
+        let entries = scan(config, &[]);
+
+        assert_eq!(
+            listed(&entries),
+            vec![
+                // Hidden contents count, links and online-only files don't
+                ("C:/Users".to_string(), 1_157),
+                ("C:/Users/.cache/blob".to_string(), 50),
+                ("C:/Users/a.txt".to_string(), 100),
+                ("C:/Users/logs".to_string(), 1_007),
+                ("C:/Users/logs/new.log".to_string(), 7),
+                ("C:/Users/logs/old.log".to_string(), 1_000),
+                ("C:/Users/node_modules".to_string(), 0),
+                ("C:/Users/video.mp4".to_string(), 9_000),
+            ]
+        );
+        let link = entries.iter().find(|e| e.path.ends_with("node_modules"));
+        assert_eq!(link.unwrap().entry_type, EntryType::Junction);
+        assert!(entries.last().unwrap().cloud_placeholder);
+    }
+
+    #[test]
+    fn test_depth_limit_and_exclusions() {
+        let config = ScanConfig {
+            max_depth: Some(1),
+            skip_hidden: false,
+            ..ScanConfig::default()
+        };
+        let listed = listed(&scan(config, &["logs"]));
+
+        assert_eq!(listed[0], ("C:/Users".to_string(), 150));
+        assert_eq!(listed[1], ("C:/Users/.cache".to_string(), 50));
+        assert_eq!(listed.len(), 5, "nothing below depth 1 or under logs");
+    }
+}
@@ -0,0 +1,213 @@
+//! Reading the MFT of a local NTFS volume (Windows, `mft` feature).
+//!
+//! The volume is opened as a device, the MFT's location is taken from the
+//! volume's NTFS data, and the MFT is read extent by extent in large chunks,
+//! which is what makes the backend fast: the file records are read
+//! sequentially instead of through one directory listing per directory.
+
+use super::record::{self, DataRun};
+use super::tree::FileTable;
+use super::MftError;
+use std::fs::File;
+use std::io;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::os::windows::fs::{FileExt, OpenOptionsExt};
+use std::os::windows::io::AsRawHandle;
+use std::path::{Path, PathBuf};
+use windows_sys::Win32::Storage::FileSystem::{
+    GetVolumeInformationW, GetVolumeNameForVolumeMountPointW, GetVolumePathNameW,
+    FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+};
+use windows_sys::Win32::System::Ioctl::{FSCTL_GET_NTFS_VOLUME_DATA, NTFS_VOLUME_DATA_BUFFER};
+use windows_sys::Win32::System::IO::DeviceIoControl;
+
+/// Bytes read from the MFT at once.
+const CHUNK_SIZE: u64 = 4 << 20;
+
+/// A volume's file table, and the path the volume is mounted at.
+pub(super) struct Volume {
+    /// Mount path of the volume, such as `C:\`
+    pub mount: PathBuf,
+    pub table: FileTable,
+}
+
+/// Reads the MFT of the NTFS volume holding `path`.
+pub(super) fn read(path: &Path) -> Result<Volume, MftError> {
+    let mount = volume_path(path)?;
+    if filesystem_name(&mount)? != "NTFS" {
+        return Err(MftError::NotNtfs(path.display().to_string()));
+    }
+
+    // `\\?\Volume{GUID}\` names the volume even where it is mounted in a
+    // folder; without the trailing backslash it opens the device itself
+    let device = volume_name(&mount)?;
+    let volume = std::fs::OpenOptions::new()
+        .read(true)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE)
+        .open(device.trim_end_matches('\\'))
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => MftError::AccessDenied,
+            _ => MftError::Io(e),
+        })?;
+
+    let data = ntfs_volume_data(&volume)?;
+    let cluster_size = u64::from(data.BytesPerCluster);
+    let record_size = u64::from(data.BytesPerFileRecordSegment);
+    let mft_length = data.MftValidDataLength as u64;
+
+    // The MFT's own record, number 0, maps the rest of it
+    let mut first = vec![0u8; record_size as usize];
+    volume.seek_read(&mut first, data.MftStartLcn as u64 * cluster_size)?;
+    let mft = record::parse(0, &mut first)?
+        .ok_or_else(|| MftError::Corrupt("the MFT's own record is unused".to_string()))?;
+    let mapped: u64 = mft.data_runs.iter().map(|run| run.clusters).sum::<u64>() * cluster_size;
+    if mapped < mft_length {
+        // The remaining extents are listed in extension records
+        return Err(MftError::Corrupt(
+            "the MFT is too fragmented to map from its first record".to_string(),
+        ));
+    }
+
+    let mut records = Vec::new();
+    let mut number = 0u64;
+    let mut remaining = mft_length;
+    for run in &mft.data_runs {
+        read_run(
+            &volume,
+            run,
+            cluster_size,
+            record_size,
+            &mut remaining,
+            |data| {
+                // Records torn by an interrupted write are left out
+                if let Ok(Some(record)) = record::parse(number, data) {
+                    records.push(record);
+                }
+                number += 1;
+            },
+        )?;
+    }
+
+    Ok(Volume {
+        mount,
+        table: FileTable::new(records),
+    })
+}
+
+/// Reads the records of one MFT extent, up to `remaining` bytes of the MFT.
+fn read_run(
+    volume: &File,
+    run: &DataRun,
+    cluster_size: u64,
+    record_size: u64,
+    remaining: &mut u64,
+    mut on_record: impl FnMut(&mut [u8]),
+) -> Result<(), MftError> {
+    let mut offset = run.lcn * cluster_size;
+    let mut left = (run.clusters * cluster_size).min(*remaining);
+    let chunk_size = CHUNK_SIZE - CHUNK_SIZE % record_size;
+    let mut buffer = vec![0u8; chunk_size.min(left) as usize];
+
+    while left > 0 {
+        let length = chunk_size.min(left) as usize;
+        volume.seek_read(&mut buffer[..length], offset)?;
+        for data in buffer[..length].chunks_exact_mut(record_size as usize) {
+            on_record(data);
+        }
+        offset += length as u64;
+        left -= length as u64;
+        *remaining -= length as u64;
+    }
+    Ok(())
+}
+
+/// Returns the location and layout of the volume's MFT.
+fn ntfs_volume_data(volume: &File) -> Result<NTFS_VOLUME_DATA_BUFFER, MftError> {
+    // SAFETY: the output buffer is a plain struct of the size passed, and the
+    // handle stays open for the duration of the call
+    let mut data: NTFS_VOLUME_DATA_BUFFER = unsafe { std::mem::zeroed() };
+    let mut returned = 0u32;
+    let ok = unsafe {
+        DeviceIoControl(
+            volume.as_raw_handle() as _,
+            FSCTL_GET_NTFS_VOLUME_DATA,
+            std::ptr::null(),
+            0,
+            &mut data as *mut _ as *mut _,
+            std::mem::size_of::<NTFS_VOLUME_DATA_BUFFER>() as u32,
+            &mut returned,
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(MftError::Io(io::Error::last_os_error()));
+    }
+    Ok(data)
+}
+
+/// Returns the mount path of the volume holding `path`, such as `C:\`.
+fn volume_path(path: &Path) -> Result<PathBuf, MftError> {
+    let path = std::path::absolute(path)?;
+    let mut buffer = [0u16; 1024];
+    // SAFETY: the path is NUL-terminated and the buffer length is passed
+    let ok = unsafe {
+        GetVolumePathNameW(
+            wide(&path).as_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+        )
+    };
+    if ok == 0 {
+        return Err(MftError::Io(io::Error::last_os_error()));
+    }
+    Ok(PathBuf::from(from_wide(&buffer)))
+}
+
+/// Returns the `\\?\Volume{GUID}\` name of the volume mounted at `mount`.
+fn volume_name(mount: &Path) -> Result<String, MftError> {
+    let mut buffer = [0u16; 64];
+    // SAFETY: the mount path is NUL-terminated and the buffer length is passed
+    let ok = unsafe {
+        GetVolumeNameForVolumeMountPointW(
+            wide(mount).as_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+        )
+    };
+    if ok == 0 {
+        return Err(MftError::NotNtfs(mount.display().to_string()));
+    }
+    Ok(from_wide(&buffer).to_string_lossy().into_owned())
+}
+
+/// Returns the name of the filesystem of the volume mounted at `mount`.
+fn filesystem_name(mount: &Path) -> Result<String, MftError> {
+    let mut name = [0u16; 32];
+    // SAFETY: the mount path is NUL-terminated, unused outputs are null and
+    // the buffer length is passed
+    let ok = unsafe {
+        GetVolumeInformationW(
+            wide(mount).as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            name.as_mut_ptr(),
+            name.len() as u32,
+        )
+    };
+    if ok == 0 {
+        return Err(MftError::Io(io::Error::last_os_error()));
+    }
+    Ok(from_wide(&name).to_string_lossy().into_owned())
+}
+
+fn wide(path: &Path) -> Vec<u16> {
+    path.as_os_str().encode_wide().chain(Some(0)).collect()
+}
+
+fn from_wide(buffer: &[u16]) -> std::ffi::OsString {
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    std::ffi::OsString::from_wide(&buffer[..end])
+}
@@ -1,9 +1,45 @@
 //! File system scanning and traversal.
 
+pub mod cache;
+pub mod cloud;
+pub mod dir_size;
+pub mod estimate;
+pub mod exclude;
+pub mod export;
+pub mod history;
+pub mod ignore_files;
+pub mod import;
+mod links;
+pub mod mft;
+pub(crate) mod mounts;
+pub mod owner;
+#[cfg(feature = "parallel")]
 pub mod parallel;
 pub mod progress;
+pub mod raw;
+pub mod remote;
+mod sizes;
+pub mod streams;
+pub mod throttle;
 pub mod traversal;
+pub mod watcher;
 
+pub use cache::ScanCache;
+pub use dir_size::DirSizeConfig;
+pub use estimate::{Estimate, EstimateConfig, EstimateReport, Estimator, SubtreeEstimate};
+pub use exclude::ExcludeSet;
+pub use export::{export_entries, ExportError, ExportFormat, ExportSummary, EXPORT_COLUMNS};
+pub use history::{ScanHistory, ScanRecord};
+pub use import::{import_scan, refresh_mtime, ImportError, ImportFormat, ImportedScan};
+pub use mft::{MftError, MftScanner, ScanBackend};
+#[cfg(feature = "parallel")]
 pub use parallel::{ErrorCollector, ParallelScanner, ScannerConfig};
 pub use progress::{AdvancedProgress, ProgressReport, ScanProgress};
-pub use traversal::{FileScanner, ScanConfig, ScanError};
+pub use raw::{read_raw_scan, write_raw_scan, RawScan, RawScanError, RAW_SCAN_VERSION};
+pub use remote::{RemoteMode, RemoteScanConfig};
+pub use throttle::Throttle;
+pub use traversal::{
+    FileScanner, IncrementalStats, ScanConfig, ScanError, ScanErrorReport, ScannedSubtree,
+    UnreadableEntry,
+};
+pub use watcher::{WatchChanges, Watcher};
@@ -0,0 +1,222 @@
+//! Filesystem boundaries for scans that stay on one filesystem.
+//!
+//! With `same_filesystem` set, the scanners leave out directories on another
+//! filesystem than the scan root, such as mounted network shares, USB drives
+//! and bind mounts, without walking into them. Filesystems are told apart by
+//! device ID (`st_dev`) on Unix and by volume serial number on Windows.
+//!
+//! Mount points are left out entirely rather than listed as empty
+//! directories, so a plan can never flag one for deletion.
+//!
+//! Mount tables in `/proc/mounts` format, which tell the remote scan mode
+//! and volume snapshots what is mounted where, are read with
+//! [`parse_mount_table`].
+
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::DirEntry;
+
+/// The filesystem a scan started on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Filesystem {
+    device: u64,
+}
+
+impl Filesystem {
+    /// Returns the filesystem holding `root`.
+    pub(crate) fn of(root: &Path) -> io::Result<Self> {
+        Ok(Self {
+            device: platform::device_of(root)?,
+        })
+    }
+
+    /// Returns true if `entry` is a directory on another filesystem.
+    ///
+    /// Directories whose filesystem can't be determined are kept; the walk
+    /// reports their errors as usual.
+    pub(crate) fn excludes(&self, entry: &DirEntry) -> bool {
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && platform::device_of_entry(entry).is_ok_and(|device| device != self.device)
+    }
+}
+
+/// A line of a mount table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MountEntry<'a> {
+    /// Mounted device, dataset or share
+    pub source: OsString,
+    pub mount_point: PathBuf,
+    pub fs_type: &'a str,
+}
+
+/// Parses a mount table in `/proc/mounts` format, in mount order; malformed
+/// lines are skipped.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_mount_table(mounts: &str) -> impl Iterator<Item = MountEntry<'_>> {
+    mounts.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        Some(MountEntry {
+            source: unescape_mount_field(fields.next()?),
+            mount_point: PathBuf::from(unescape_mount_field(fields.next()?)),
+            fs_type: fields.next()?,
+        })
+    })
+}
+
+/// Decodes the octal escapes (`\040` for a space) used in mount tables.
+///
+/// The escapes stand for bytes, so the field is decoded to bytes first:
+/// non-ASCII paths come through as the kernel stored them.
+fn unescape_mount_field(field: &str) -> OsString {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = field
+            .get(i + 1..i + 4)
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match (bytes[i], code) {
+            (b'\\', Some(byte)) => {
+                decoded.push(byte);
+                i += 4;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    bytes_to_os_string(decoded)
+}
+
+#[cfg(unix)]
+fn bytes_to_os_string(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+fn bytes_to_os_string(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+#[cfg(unix)]
+mod platform {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+
+    pub fn device_of(path: &Path) -> io::Result<u64> {
+        Ok(std::fs::metadata(path)?.dev())
+    }
+
+    pub fn device_of_entry(entry: &DirEntry) -> io::Result<u64> {
+        Ok(entry.metadata()?.dev())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
+    };
+
+    /// Returns the serial number of the volume holding `path`.
+    pub fn device_of(path: &Path) -> io::Result<u64> {
+        // Directories can only be opened with backup semantics
+        let file = std::fs::OpenOptions::new()
+            .access_mode(0)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)?;
+        // SAFETY: `info` is a plain struct the call fills in, and the handle
+        // stays open for the duration of the call
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(u64::from(info.dwVolumeSerialNumber))
+    }
+
+    pub fn device_of_entry(entry: &DirEntry) -> io::Result<u64> {
+        device_of(entry.path())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use super::*;
+
+    /// Filesystems aren't told apart on this platform.
+    pub fn device_of(_path: &Path) -> io::Result<u64> {
+        Ok(0)
+    }
+
+    pub fn device_of_entry(_entry: &DirEntry) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+    use walkdir::WalkDir;
+
+    fn walk(root: &Path, max_depth: usize) -> Vec<PathBuf> {
+        let filesystem = Filesystem::of(root).unwrap();
+        WalkDir::new(root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|entry| !filesystem.excludes(entry))
+            .filter_map(Result::ok)
+            .map(DirEntry::into_path)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_mount_table() {
+        let mounts = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+/dev/sdb1 /mnt/my\\040data btrfs rw 0 0
+truncated
+//nas/m\\303\\251dia /mnt/m\\303\\251dia cifs rw 0 0
+";
+        let entries: Vec<_> = parse_mount_table(mounts).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].mount_point, PathBuf::from("/mnt/my data"));
+        assert_eq!(entries[1].fs_type, "btrfs");
+        // Escaped UTF-8 bytes decode to the characters they spell
+        assert_eq!(entries[2].source, OsString::from("//nas/média"));
+        assert_eq!(entries[2].mount_point, PathBuf::from("/mnt/média"));
+        assert_eq!(
+            unescape_mount_field("a\\134b\\x"),
+            OsString::from("a\\b\\x")
+        );
+    }
+
+    #[test]
+    fn test_same_filesystem_is_walked() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("a/b")).unwrap();
+
+        assert_eq!(walk(temp.path(), usize::MAX).len(), 3);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_other_filesystems_are_left_out() {
+        // procfs is always mounted separately from the root filesystem
+        if !Path::new("/proc/self").exists() {
+            return;
+        }
+        let paths = walk(Path::new("/"), 1);
+
+        assert!(paths.contains(&PathBuf::from("/")));
+        assert!(!paths.contains(&PathBuf::from("/proc")));
+    }
+}
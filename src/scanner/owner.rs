@@ -0,0 +1,224 @@
+//! Collection of entry owners and attributes.
+//!
+//! With `scanner.collect_owner`, scanners record who owns each entry and
+//! whether it is read-only or a system file, so custom rules can flag
+//! `owner('jdoe')`'s files or leave `system` files alone, and plans show the
+//! owner of every entry up for review. Looking owners up costs a call per
+//! entry, which is why it is off by default.
+//!
+//! On Windows the owner is the account name (`DOMAIN\user`) of the owner SID,
+//! or the SID itself (`S-1-5-21-...`) when the account no longer resolves,
+//! such as for deleted users; entries have no group. Elsewhere the owner and
+//! group are the user and group names from `/etc/passwd` and `/etc/group`,
+//! or the numeric uid and gid for ids not listed there (directory services
+//! such as LDAP aren't consulted). Read-only means the read-only attribute on
+//! Windows, and no write permission bit elsewhere, where nothing is a system
+//! file.
+
+use crate::models::FileEntry;
+use std::fs::Metadata;
+
+/// Records the owner and attributes of `entry`, whose metadata is
+/// `metadata`.
+///
+/// Owners that can't be read (e.g. access denied) are left unset rather
+/// than failing the scan.
+pub fn collect_owner(entry: &mut FileEntry, metadata: &Metadata) {
+    let (owner, group) = platform::owner(&entry.path, metadata);
+    entry.owner = owner;
+    entry.group = group;
+    entry.readonly = metadata.permissions().readonly();
+    entry.system = platform::is_system(metadata);
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::collections::HashMap;
+    use std::fs::Metadata;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::{Mutex, OnceLock};
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertSidToStringSidW, GetNamedSecurityInfoW, SE_FILE_OBJECT,
+    };
+    use windows_sys::Win32::Security::{
+        LookupAccountSidW, OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID,
+    };
+
+    /// Account names by SID string; a tree is mostly owned by a handful of
+    /// accounts, and each lookup may ask a domain controller
+    fn names() -> &'static Mutex<HashMap<String, String>> {
+        static NAMES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+        NAMES.get_or_init(Default::default)
+    }
+
+    pub fn owner(path: &Path, _metadata: &Metadata) -> (Option<String>, Option<String>) {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut sid: PSID = std::ptr::null_mut();
+        let mut descriptor: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+        // SAFETY: the path is NUL-terminated and unused outputs are null; the
+        // owner SID points into the descriptor, which is freed below
+        let status = unsafe {
+            GetNamedSecurityInfoW(
+                wide.as_ptr(),
+                SE_FILE_OBJECT,
+                OWNER_SECURITY_INFORMATION,
+                &mut sid,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut descriptor,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return (None, None);
+        }
+        let owner = sid_string(sid).map(|text| {
+            let mut names = names().lock().unwrap();
+            names
+                .entry(text.clone())
+                .or_insert_with(|| account_name(sid).unwrap_or(text))
+                .clone()
+        });
+        unsafe { LocalFree(descriptor) };
+        (owner, None)
+    }
+
+    pub fn is_system(metadata: &Metadata) -> bool {
+        use std::os::windows::fs::MetadataExt;
+        use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_SYSTEM;
+
+        metadata.file_attributes() & FILE_ATTRIBUTE_SYSTEM != 0
+    }
+
+    fn sid_string(sid: PSID) -> Option<String> {
+        let mut text = std::ptr::null_mut();
+        // SAFETY: the SID is valid; the string is freed below
+        if unsafe { ConvertSidToStringSidW(sid, &mut text) } == 0 {
+            return None;
+        }
+        let result = unsafe {
+            let len = (0..).take_while(|&i| *text.add(i) != 0).count();
+            String::from_utf16_lossy(std::slice::from_raw_parts(text, len))
+        };
+        unsafe { LocalFree(text as _) };
+        Some(result)
+    }
+
+    /// Returns `DOMAIN\user` for `sid`, or `None` if no account has it.
+    fn account_name(sid: PSID) -> Option<String> {
+        let mut name = [0u16; 256];
+        let mut domain = [0u16; 256];
+        let (mut name_len, mut domain_len) = (name.len() as u32, domain.len() as u32);
+        let mut kind = 0;
+        // SAFETY: the buffer lengths are passed and the SID is valid
+        let ok = unsafe {
+            LookupAccountSidW(
+                std::ptr::null(),
+                sid,
+                name.as_mut_ptr(),
+                &mut name_len,
+                domain.as_mut_ptr(),
+                &mut domain_len,
+                &mut kind,
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        let name = String::from_utf16_lossy(&name[..name_len as usize]);
+        let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+        Some(if domain.is_empty() {
+            name
+        } else {
+            format!("{}\\{}", domain, name)
+        })
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::collections::HashMap;
+    use std::fs::Metadata;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+    use std::sync::OnceLock;
+
+    pub fn owner(_path: &Path, metadata: &Metadata) -> (Option<String>, Option<String>) {
+        static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+        static GROUPS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+        let users = USERS.get_or_init(|| read_ids("/etc/passwd"));
+        let groups = GROUPS.get_or_init(|| read_ids("/etc/group"));
+
+        let name = |ids: &HashMap<u32, String>, id: u32| {
+            ids.get(&id).cloned().unwrap_or_else(|| id.to_string())
+        };
+        (
+            Some(name(users, metadata.uid())),
+            Some(name(groups, metadata.gid())),
+        )
+    }
+
+    pub fn is_system(_metadata: &Metadata) -> bool {
+        false
+    }
+
+    /// Reads the names by id of a `name:password:id:...` database.
+    fn read_ids(path: &str) -> HashMap<u32, String> {
+        std::fs::read_to_string(path)
+            .map(|text| super::parse_ids(&text))
+            .unwrap_or_default()
+    }
+}
+
+/// Parses the names by id of `/etc/passwd` or `/etc/group` contents.
+#[cfg_attr(windows, allow(dead_code))]
+fn parse_ids(text: &str) -> std::collections::HashMap<u32, String> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use std::fs;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_ids() {
+        let ids = parse_ids(
+            "# users\nroot:x:0:0:root:/root:/bin/sh\njdoe:x:1000:1000::/home/jdoe:\nbroken\n",
+        );
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[&0], "root");
+        assert_eq!(ids[&1000], "jdoe");
+    }
+
+    #[test]
+    fn test_collect_owner() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("file.txt");
+        fs::write(&path, "data").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        let metadata = fs::metadata(&path).unwrap();
+        let mut entry = FileEntry::new(path.clone(), 4, SystemTime::now(), EntryType::File);
+        collect_owner(&mut entry, &metadata);
+
+        assert!(entry.owner.is_some());
+        assert!(entry.readonly);
+        assert!(!entry.system);
+    }
+}
@@ -1,11 +1,23 @@
 //! Parallel file system scanning using rayon.
 
 use crate::models::{EntryType, FileEntry};
+use crate::scanner::cloud::is_cloud_placeholder;
+use crate::scanner::dir_size::{self, DirSizeConfig};
+use crate::scanner::exclude::{is_snapshot_dir, ExcludeSet};
+use crate::scanner::ignore_files::IgnoreStack;
+use crate::scanner::links::link_of;
+use crate::scanner::mounts::Filesystem;
+use crate::scanner::owner::collect_owner;
 use crate::scanner::progress::AdvancedProgress;
+use crate::scanner::remote::RemoteScanConfig;
+use crate::scanner::sizes::DeferredSizes;
+use crate::scanner::throttle::Throttle;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use walkdir::WalkDir;
 
 /// Configuration for the parallel scanner.
@@ -19,6 +31,31 @@ pub struct ScannerConfig {
     pub follow_symlinks: bool,
     /// Number of threads to use (0 = auto-detect)
     pub thread_count: usize,
+    /// Network filesystem strategy (see [`crate::scanner::remote`])
+    #[serde(default)]
+    pub remote: RemoteScanConfig,
+    /// Glob patterns of paths to leave out (see [`crate::scanner::exclude`])
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    /// Leave out what `.gitignore` and `.megamaidignore` files ignore (see
+    /// [`crate::scanner::ignore_files`])
+    #[serde(default)]
+    pub respect_ignore_files: bool,
+    /// Stay on the root's filesystem, leaving out other mounts
+    #[serde(default)]
+    pub same_filesystem: bool,
+    /// Record the owner and attributes of entries (see
+    /// [`crate::scanner::owner`])
+    #[serde(default)]
+    pub collect_owner: bool,
+    /// Maximum entries read per second across all workers, 0 for no limit
+    /// (see [`crate::scanner::throttle`])
+    #[serde(default)]
+    pub max_iops: u32,
+    /// Parallelism of sizing directories at the depth limit (see
+    /// [`crate::scanner::dir_size`])
+    #[serde(default)]
+    pub dir_size: DirSizeConfig,
 }
 
 impl Default for ScannerConfig {
@@ -28,15 +65,33 @@ impl Default for ScannerConfig {
             skip_hidden: true,
             follow_symlinks: false,
             thread_count: 0, // Auto-detect
+            remote: RemoteScanConfig::default(),
+            exclude_globs: Vec::new(),
+            respect_ignore_files: false,
+            same_filesystem: false,
+            collect_owner: false,
+            max_iops: 0,
+            dir_size: DirSizeConfig::default(),
         }
     }
 }
 
+/// How directory sizes are computed for a scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirSizing {
+    /// Summed from the walk's own entries afterwards
+    Summed,
+    /// Left at 0 (remote mode with `skip_dir_sizes`)
+    Skipped,
+}
+
 /// Parallel scanner for high-performance directory traversal.
 pub struct ParallelScanner {
     config: ScannerConfig,
     progress: Arc<AdvancedProgress>,
     error_collector: Arc<ErrorCollector>,
+    cancel: Arc<AtomicBool>,
+    throttle: Option<Throttle>,
 }
 
 /// Collects errors that occur during parallel scanning.
@@ -55,6 +110,12 @@ pub enum ScanError {
 
     #[error("Invalid path: {0}")]
     InvalidPath(String),
+
+    #[error("Invalid exclude pattern: {0}")]
+    InvalidPattern(String),
+
+    #[error("Scan cancelled")]
+    Cancelled,
 }
 
 impl From<std::io::Error> for ScanError {
@@ -97,19 +158,25 @@ impl ErrorCollector {
 
     /// Records an error.
     pub fn record(&self, error: ScanError) {
-        if let Ok(mut errors) = self.errors.lock() {
-            errors.push(error);
-        }
+        self.lock().push(error);
     }
 
     /// Returns all collected errors.
     pub fn get_errors(&self) -> Vec<ScanError> {
-        self.errors.lock().map(|e| e.clone()).unwrap_or_default()
+        self.lock().clone()
     }
 
     /// Returns the number of errors collected.
     pub fn error_count(&self) -> usize {
-        self.errors.lock().map(|e| e.len()).unwrap_or(0)
+        self.lock().len()
+    }
+
+    /// Locks the error list, recovering it if a worker panicked while holding it.
+    ///
+    /// Pushing to a `Vec` can't leave it half-updated, so the list is still
+    /// valid after a poisoning panic and dropping errors would hide failures.
+    fn lock(&self) -> MutexGuard<'_, Vec<ScanError>> {
+        self.errors.lock().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
@@ -135,18 +202,38 @@ impl ParallelScanner {
             .build_global();
 
         Self {
+            throttle: Throttle::new(config.max_iops),
             config,
             progress: Arc::new(AdvancedProgress::new()),
             error_collector: Arc::new(ErrorCollector::new()),
+            cancel: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Returns a handle that cancels the scan when set to `true`.
+    ///
+    /// The scan stops walking and processing entries and returns
+    /// [`ScanError::Cancelled`]; entries found so far are discarded.
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel)
+    }
+
+    fn cancel_requested(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
     /// Scans the given directory path in parallel.
     pub fn scan(&self, path: &Path) -> Result<Vec<FileEntry>, ScanError> {
         self.scan_with_progress(path, |_| {})
     }
 
     /// Scans with a progress callback.
+    ///
+    /// Every path is walked once, including hidden subtrees so their sizes
+    /// still count toward their parents; directory sizes are then summed
+    /// from the walk instead of being re-walked. On a network filesystem
+    /// metadata is read on a dedicated pool of `remote.max_concurrency`
+    /// threads.
     pub fn scan_with_progress<F>(
         &self,
         path: &Path,
@@ -155,102 +242,170 @@ impl ParallelScanner {
     where
         F: Fn(usize) + Send + Sync,
     {
-        // Phase 1: Collect all paths (sequential, fast)
-        let walker = WalkDir::new(path)
-            .follow_links(self.config.follow_symlinks)
-            .max_depth(self.config.max_depth.unwrap_or(usize::MAX));
+        let remote = self.config.remote;
+        let is_remote = remote.is_active(path);
+        let sizing = if is_remote && remote.skip_dir_sizes {
+            DirSizing::Skipped
+        } else {
+            DirSizing::Summed
+        };
 
-        let paths: Vec<_> = walker
+        let globs = ExcludeSet::new(&self.config.exclude_globs)
+            .map_err(|e| ScanError::InvalidPattern(e.to_string()))?;
+
+        // Phase 1: Collect all paths (sequential, fast), without walking
+        // into excluded or ignored directories or other filesystems
+        let mut hidden_dirs = HashSet::new();
+        let mut ignores = self.config.respect_ignore_files.then(IgnoreStack::default);
+        let filesystem = self
+            .config
+            .same_filesystem
+            .then(|| Filesystem::of(path))
+            .transpose()?;
+        let paths: Vec<_> = WalkDir::new(path)
+            .follow_links(self.config.follow_symlinks)
+            .max_depth(self.config.max_depth.unwrap_or(usize::MAX))
             .into_iter()
             .filter_entry(|e| {
-                // Never filter the root directory (depth 0)
-                if e.depth() == 0 {
-                    return true;
-                }
-
-                if self.config.skip_hidden {
-                    !is_hidden(e)
-                } else {
-                    true
-                }
+                let excluded = (e.depth() > 0 && !globs.is_empty() && globs.matches(e.path()))
+                    || filesystem.is_some_and(|fs| fs.excludes(e))
+                    || is_snapshot_dir(e);
+                !excluded && !ignores.as_mut().is_some_and(|stack| stack.skips(e))
             })
             .filter_map(|e| e.ok())
+            .take_while(|_| !self.cancel_requested())
+            .map(|e| {
+                let hidden = self.config.skip_hidden
+                    && e.depth() > 0
+                    && (is_hidden(&e)
+                        || e.path().parent().is_some_and(|p| hidden_dirs.contains(p)));
+                if hidden && e.file_type().is_dir() {
+                    hidden_dirs.insert(e.path().to_path_buf());
+                }
+                (e, hidden)
+            })
+            .filter(|(_, hidden)| !hidden || sizing == DirSizing::Summed)
             .collect();
 
         self.progress.set_total(paths.len() as u64);
 
         // Phase 2: Process paths in parallel
-        let entries: Vec<_> = paths
-            .par_iter()
-            .filter_map(|entry| {
-                let result = self.process_entry(entry);
-                self.progress.increment();
-
-                match result {
-                    Ok(Some(file_entry)) => Some(file_entry),
-                    Ok(None) => None,
-                    Err(e) => {
-                        self.error_collector.record(e);
-                        None
-                    }
+        let process = || -> Vec<_> {
+            paths
+                .par_iter()
+                .map(|(entry, _)| self.process_recorded(entry, sizing))
+                .collect()
+        };
+        let results = if is_remote {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(remote.max_concurrency.max(1))
+                .build()
+                .map_err(|e| ScanError::Io(e.to_string()))?
+                .install(process)
+        } else {
+            process()
+        };
+
+        if self.cancel_requested() {
+            return Err(ScanError::Cancelled);
+        }
+
+        // Phase 3: Sum directory sizes bottom-up
+        let mut entries = Vec::with_capacity(results.len());
+        let mut deferred = DeferredSizes::default();
+        for ((entry, hidden), result) in paths.iter().zip(results) {
+            if let Some(file_entry) = result {
+                if sizing == DirSizing::Summed {
+                    deferred.record(entries.len(), entry, *hidden, self.config.max_depth);
                 }
-            })
-            .collect();
+                entries.push(file_entry);
+            }
+        }
+
+        Ok(deferred.finish(entries))
+    }
+
+    /// Processes one walked entry, recording any error and advancing progress.
+    fn process_recorded(&self, entry: &walkdir::DirEntry, sizing: DirSizing) -> Option<FileEntry> {
+        if self.cancel_requested() {
+            return None;
+        }
+
+        // Workers share the throttle, so the limit holds for the whole scan
+        if let Some(throttle) = &self.throttle {
+            throttle.acquire();
+        }
+        let result = self.process_entry(entry, sizing);
+        self.progress.increment();
 
-        Ok(entries)
+        match result {
+            Ok(file_entry) => file_entry,
+            Err(e) => {
+                self.error_collector.record(e);
+                None
+            }
+        }
     }
 
-    fn process_entry(&self, entry: &walkdir::DirEntry) -> Result<Option<FileEntry>, ScanError> {
+    fn process_entry(
+        &self,
+        entry: &walkdir::DirEntry,
+        sizing: DirSizing,
+    ) -> Result<Option<FileEntry>, ScanError> {
         let path = entry.path();
         let metadata = entry.metadata()?;
+        let link = link_of(entry);
 
         // Determine entry type
-        let entry_type = if metadata.is_dir() {
-            EntryType::Directory
-        } else {
-            EntryType::File
+        let entry_type = match &link {
+            Some((link_type, _)) => *link_type,
+            None if metadata.is_dir() => EntryType::Directory,
+            None => EntryType::File,
         };
 
-        // Calculate size
-        let size = if metadata.is_dir() {
-            self.calculate_dir_size(path)?
-        } else {
+        // Directory sizes are summed after the walk; directories at the
+        // depth limit have no walked children to sum, so they are sized
+        // recursively. A link that isn't followed takes up no space.
+        let at_depth_limit = Some(entry.depth()) == self.config.max_depth;
+        let size = if metadata.is_symlink() {
+            0
+        } else if !metadata.is_dir() {
             metadata.len()
+        } else {
+            match sizing {
+                DirSizing::Summed if at_depth_limit => self.calculate_dir_size(path)?,
+                DirSizing::Summed | DirSizing::Skipped => 0,
+            }
         };
 
         // Get modification time
         let modified = metadata.modified()?;
 
-        Ok(Some(FileEntry::new(
-            path.to_path_buf(),
-            size,
-            modified,
-            entry_type,
-        )))
+        let mut file_entry = FileEntry::new(path.to_path_buf(), size, modified, entry_type);
+        file_entry.link_target = link.and_then(|(_, target)| target);
+        file_entry.cloud_placeholder = is_cloud_placeholder(&metadata);
+        if self.config.collect_owner {
+            collect_owner(&mut file_entry, &metadata);
+        }
+        Ok(Some(file_entry))
     }
 
     fn calculate_dir_size(&self, dir_path: &Path) -> Result<u64, ScanError> {
-        // Use parallel iteration for large directories
-        let entries: Vec<_> = std::fs::read_dir(dir_path)?
-            .filter_map(|e| e.ok())
-            .collect();
-
-        let total: u64 = entries
-            .par_iter()
-            .map(|entry| {
-                let metadata = entry.metadata().ok()?;
-                if metadata.is_file() {
-                    Some(metadata.len())
-                } else if metadata.is_dir() {
-                    calculate_dir_size_recursive(&entry.path()).ok()
-                } else {
-                    None
-                }
-            })
-            .flatten()
-            .sum();
+        // Other filesystems can only be skipped by walking with the boundary check
+        if self.config.same_filesystem {
+            let total = WalkDir::new(dir_path)
+                .same_file_system(true)
+                .into_iter()
+                .filter_entry(|e| !is_snapshot_dir(e))
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file() && !is_cloud_placeholder(m))
+                .map(|m| m.len())
+                .sum();
+            return Ok(total);
+        }
 
-        Ok(total)
+        Ok(dir_size::dir_size(dir_path, &self.config.dir_size)?)
     }
 
     /// Returns a reference to the progress tracker.
@@ -269,30 +424,6 @@ impl ParallelScanner {
     }
 }
 
-/// Helper function for recursive directory size calculation.
-fn calculate_dir_size_recursive(dir_path: &Path) -> Result<u64, ScanError> {
-    let entries: Vec<_> = std::fs::read_dir(dir_path)?
-        .filter_map(|e| e.ok())
-        .collect();
-
-    let total: u64 = entries
-        .par_iter()
-        .map(|entry| {
-            let metadata = entry.metadata().ok()?;
-            if metadata.is_file() {
-                Some(metadata.len())
-            } else if metadata.is_dir() {
-                calculate_dir_size_recursive(&entry.path()).ok()
-            } else {
-                None
-            }
-        })
-        .flatten()
-        .sum();
-
-    Ok(total)
-}
-
 /// Checks if a directory entry is hidden.
 #[cfg(windows)]
 fn is_hidden(entry: &walkdir::DirEntry) -> bool {
@@ -335,6 +466,20 @@ mod tests {
         assert!(result[0].is_directory());
     }
 
+    #[test]
+    fn test_cancelled_scan() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("file.txt"), "content").unwrap();
+
+        let scanner = ParallelScanner::new(ScannerConfig::default());
+        scanner.cancel_handle().store(true, Ordering::SeqCst);
+
+        assert!(matches!(
+            scanner.scan(temp.path()),
+            Err(ScanError::Cancelled)
+        ));
+    }
+
     #[test]
     fn test_parallel_scan_with_files() {
         let temp = TempDir::new().unwrap();
@@ -351,6 +496,25 @@ mod tests {
         assert_eq!(result.len(), 101);
     }
 
+    #[test]
+    fn test_max_iops_throttles_workers() {
+        let temp = TempDir::new().unwrap();
+        for i in 0..20 {
+            fs::write(temp.path().join(format!("file{}.txt", i)), "content").unwrap();
+        }
+
+        let scanner = ParallelScanner::new(ScannerConfig {
+            max_iops: 100,
+            ..Default::default()
+        });
+        let started = std::time::Instant::now();
+        let result = scanner.scan(temp.path()).unwrap();
+
+        // 21 entries at 100 per second, however many workers share them
+        assert_eq!(result.len(), 21);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+    }
+
     #[test]
     fn test_parallel_scan_with_nested_directories() {
         let temp = TempDir::new().unwrap();
@@ -368,6 +532,53 @@ mod tests {
         assert_eq!(result.len(), 7);
     }
 
+    #[test]
+    fn test_remote_mode_matches_local_sizes() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a/b")).unwrap();
+        fs::create_dir_all(temp.path().join(".cache")).unwrap();
+        fs::write(temp.path().join("a/file1.txt"), "x".repeat(100)).unwrap();
+        fs::write(temp.path().join("a/b/file2.txt"), "x".repeat(20)).unwrap();
+        fs::write(temp.path().join(".cache/blob"), "x".repeat(7)).unwrap();
+
+        let sizes = |config: ScannerConfig| {
+            let mut sizes: Vec<_> = ParallelScanner::new(config)
+                .scan(temp.path())
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.path, e.size))
+                .collect();
+            sizes.sort();
+            sizes
+        };
+        let remote = ScannerConfig {
+            remote: RemoteScanConfig {
+                mode: crate::scanner::RemoteMode::Always,
+                max_concurrency: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let expected = sizes(ScannerConfig::default());
+        let actual = sizes(remote.clone());
+        assert_eq!(actual, expected);
+        assert!(!actual.iter().any(|(p, _)| p.ends_with(".cache/blob")));
+
+        let skipped = sizes(ScannerConfig {
+            remote: RemoteScanConfig {
+                skip_dir_sizes: true,
+                ..remote.remote
+            },
+            ..remote
+        });
+        assert_eq!(skipped.len(), expected.len());
+        assert!(skipped
+            .iter()
+            .filter(|(p, _)| p.is_dir())
+            .all(|(_, size)| *size == 0));
+    }
+
     #[test]
     fn test_parallel_scan_performance() {
         let temp = TempDir::new().unwrap();
@@ -432,6 +643,25 @@ mod tests {
         assert_eq!(errors.len(), 10);
     }
 
+    #[test]
+    fn test_error_collection_survives_poisoning() {
+        let collector = Arc::new(ErrorCollector::new());
+        collector.record(ScanError::Io("before".to_string()));
+
+        let poisoner = Arc::clone(&collector);
+        let result = std::thread::spawn(move || {
+            let _guard = poisoner.errors.lock().unwrap();
+            panic!("worker panicked while holding the lock");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(collector.errors.is_poisoned());
+
+        collector.record(ScanError::Io("after".to_string()));
+        assert_eq!(collector.error_count(), 2);
+        assert_eq!(collector.get_errors().len(), 2);
+    }
+
     #[test]
     fn test_max_depth_limiting() {
         let temp = TempDir::new().unwrap();
@@ -458,6 +688,81 @@ mod tests {
         assert!(!paths.iter().any(|p| p.ends_with("e/file5.txt")));
     }
 
+    #[test]
+    fn test_exclude_globs() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("app/node_modules/pkg")).unwrap();
+        fs::write(temp.path().join("app/node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(temp.path().join("app/main.rs"), "main").unwrap();
+
+        let config = ScannerConfig {
+            exclude_globs: vec!["node_modules".to_string()],
+            ..Default::default()
+        };
+        let result = ParallelScanner::new(config).scan(temp.path()).unwrap();
+
+        assert!(!result
+            .iter()
+            .any(|e| e.path.starts_with(temp.path().join("app/node_modules"))));
+        let app = result
+            .iter()
+            .find(|e| e.path == temp.path().join("app"))
+            .unwrap();
+        assert_eq!(app.size, 4);
+
+        let config = ScannerConfig {
+            exclude_globs: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            ParallelScanner::new(config).scan(temp.path()),
+            Err(ScanError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_volume_snapshots_are_never_walked() {
+        let temp = TempDir::new().unwrap();
+        let copy = temp
+            .path()
+            .join(".megamaid-snapshots/megamaid-1/app/target");
+        fs::create_dir_all(&copy).unwrap();
+        fs::write(copy.join("app.bin"), "binary").unwrap();
+        fs::write(temp.path().join("main.rs"), "main").unwrap();
+
+        let result = ParallelScanner::new(ScannerConfig::default())
+            .scan(temp.path())
+            .unwrap();
+
+        assert!(!result
+            .iter()
+            .any(|e| e.path.starts_with(temp.path().join(".megamaid-snapshots"))));
+        let root = result.iter().find(|e| e.path == temp.path()).unwrap();
+        assert_eq!(root.size, 4);
+    }
+
+    #[test]
+    fn test_respect_ignore_files() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("app/dist")).unwrap();
+        fs::write(temp.path().join("app/.megamaidignore"), "dist/\n").unwrap();
+        fs::write(temp.path().join("app/dist/bundle.js"), "bundle").unwrap();
+
+        let config = ScannerConfig {
+            skip_hidden: false,
+            respect_ignore_files: true,
+            ..Default::default()
+        };
+        let result = ParallelScanner::new(config).scan(temp.path()).unwrap();
+
+        assert!(result
+            .iter()
+            .any(|e| e.path.ends_with("app/.megamaidignore")));
+        assert!(!result
+            .iter()
+            .any(|e| e.path.starts_with(temp.path().join("app/dist"))));
+    }
+
     #[test]
     fn test_directory_size_calculation() {
         let temp = TempDir::new().unwrap();
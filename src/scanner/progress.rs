@@ -71,6 +71,7 @@ pub struct AdvancedProgress {
     processed: AtomicU64,
     start_time: Instant,
     throughput_history: Arc<Mutex<VecDeque<ThroughputSample>>>,
+    seeded_throughput: Mutex<Option<f64>>,
 }
 
 struct ThroughputSample {
@@ -86,6 +87,20 @@ impl AdvancedProgress {
             processed: AtomicU64::new(0),
             start_time: Instant::now(),
             throughput_history: Arc::new(Mutex::new(VecDeque::with_capacity(100))),
+            seeded_throughput: Mutex::new(None),
+        }
+    }
+
+    /// Seeds the total and throughput from an earlier run that processed
+    /// `total` items in `duration`, so the percentage and ETA are available
+    /// before the current run has measured its own throughput.
+    ///
+    /// A later [`set_total`](Self::set_total) replaces the seeded total, and
+    /// measured throughput takes over from the seeded one.
+    pub fn seed(&self, total: u64, duration: Duration) {
+        self.set_total(total);
+        if let Ok(mut seeded) = self.seeded_throughput.lock() {
+            *seeded = (duration.as_secs_f64() > 0.0).then(|| total as f64 / duration.as_secs_f64());
         }
     }
 
@@ -137,13 +152,14 @@ impl AdvancedProgress {
         self.total.load(Ordering::Relaxed)
     }
 
-    /// Returns the completion percentage.
+    /// Returns the completion percentage, at most 100 when a seeded total
+    /// turns out too low.
     pub fn percentage(&self) -> f64 {
         let total = self.get_total();
         if total == 0 {
             return 0.0;
         }
-        (self.get_processed() as f64 / total as f64) * 100.0
+        ((self.get_processed() as f64 / total as f64) * 100.0).min(100.0)
     }
 
     /// Returns the elapsed time since start.
@@ -173,8 +189,12 @@ impl AdvancedProgress {
     }
 
     /// Estimates the time remaining until completion.
+    ///
+    /// Falls back to the seeded throughput until enough samples are recorded.
     pub fn estimate_eta(&self) -> Option<Duration> {
-        let throughput = self.current_throughput()?;
+        let throughput = self
+            .current_throughput()
+            .or_else(|| *self.seeded_throughput.lock().ok()?)?;
         if throughput < 0.1 {
             return None;
         }
@@ -364,6 +384,24 @@ mod tests {
         assert!(!eta.is_empty());
     }
 
+    #[test]
+    fn test_advanced_progress_seeded_from_earlier_run() {
+        let progress = AdvancedProgress::new();
+        progress.seed(1000, Duration::from_secs(10));
+
+        // 100 items per second before any throughput is measured
+        progress.increment_by(500);
+        assert_eq!(progress.percentage(), 50.0);
+        assert_eq!(progress.estimate_eta(), Some(Duration::from_secs(5)));
+
+        // More entries than last time
+        progress.increment_by(600);
+        assert_eq!(progress.percentage(), 100.0);
+
+        progress.set_total(2000);
+        assert_eq!(progress.get_total(), 2000);
+    }
+
     #[test]
     fn test_advanced_progress_zero_total() {
         let progress = AdvancedProgress::new();
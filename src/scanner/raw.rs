@@ -0,0 +1,164 @@
+//! Raw scan output, for running detection apart from the scan.
+//!
+//! `megamaid scan --raw-output scan.jsonl` writes the scanned entries as
+//! JSON Lines, and `megamaid detect --from-scan scan.jsonl` turns them into a
+//! plan, so detection rules can be tried again and again without walking a
+//! slow network share each time. The first line is a header naming the scan
+//! roots; every further line is one [`FileEntry`] as serialized by serde.
+//! Like scan caches, files ending in `.zst` are zstd-compressed, and files
+//! are encrypted under `encryption.enabled`.
+//!
+//! ```text
+//! {"megamaid_scan":1,"roots":["/data"]}
+//! {"path":"/data","size":1024,"modified":{...},"entry_type":"Directory",...}
+//! ```
+
+use crate::models::FileEntry;
+use crate::planner::compression::{is_compressed, open_reader, CompressedWriter};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Version of the raw scan format written by this build.
+pub const RAW_SCAN_VERSION: u32 = 1;
+
+/// Errors reading or writing raw scan files.
+#[derive(Debug, Error)]
+pub enum RawScanError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The file doesn't start with a raw scan header
+    #[error("not a megamaid raw scan (no header line)")]
+    MissingHeader,
+
+    /// The file was written by a newer megamaid
+    #[error("raw scan format version {0} is not supported (expected {RAW_SCAN_VERSION})")]
+    UnsupportedVersion(u32),
+
+    /// A line isn't valid JSON for its record
+    #[error("line {line}: {source}")]
+    Json {
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+/// First line of a raw scan file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    megamaid_scan: u32,
+    roots: Vec<PathBuf>,
+}
+
+/// Entries read back from a raw scan file.
+#[derive(Debug)]
+pub struct RawScan {
+    /// Roots the entries were scanned from
+    pub roots: Vec<PathBuf>,
+
+    /// Scanned entries, in the order they were written
+    pub entries: Vec<FileEntry>,
+}
+
+/// Writes `entries`, scanned from `roots`, to `path` as a raw scan.
+pub fn write_raw_scan(
+    path: &Path,
+    roots: &[PathBuf],
+    entries: &[FileEntry],
+) -> Result<(), RawScanError> {
+    let mut writer = CompressedWriter::create(path, is_compressed(path))?;
+    let header = Header {
+        megamaid_scan: RAW_SCAN_VERSION,
+        roots: roots.to_vec(),
+    };
+    serde_json::to_writer(&mut writer, &header).map_err(io::Error::other)?;
+    writer.write_all(b"\n")?;
+    for entry in entries {
+        serde_json::to_writer(&mut writer, entry).map_err(io::Error::other)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Reads a raw scan written by [`write_raw_scan`]; blank lines are ignored.
+pub fn read_raw_scan(path: &Path) -> Result<RawScan, RawScanError> {
+    let mut lines = open_reader(path)?
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line));
+
+    let (line, text) = lines.next().ok_or(RawScanError::MissingHeader)?;
+    let header: Header =
+        serde_json::from_str(&text?).map_err(|source| match source.classify() {
+            serde_json::error::Category::Data => RawScanError::MissingHeader,
+            _ => RawScanError::Json { line, source },
+        })?;
+    if header.megamaid_scan > RAW_SCAN_VERSION {
+        return Err(RawScanError::UnsupportedVersion(header.megamaid_scan));
+    }
+
+    let mut entries = Vec::new();
+    for (line, text) in lines {
+        let text = text?;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let entry =
+            serde_json::from_str(&text).map_err(|source| RawScanError::Json { line, source })?;
+        entries.push(entry);
+    }
+    Ok(RawScan {
+        roots: header.roots,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_raw_scan_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let entries = vec![
+            FileEntry::new(PathBuf::from("/data"), 10, modified, EntryType::Directory),
+            FileEntry {
+                owner: Some("jdoe".to_string()),
+                ..FileEntry::new(PathBuf::from("/data/a.bin"), 10, modified, EntryType::File)
+            },
+        ];
+        let roots = vec![PathBuf::from("/data")];
+
+        for name in ["scan.jsonl", "scan.jsonl.zst"] {
+            let path = temp.path().join(name);
+            write_raw_scan(&path, &roots, &entries).unwrap();
+            let scan = read_raw_scan(&path).unwrap();
+            assert_eq!(scan.roots, roots);
+            assert_eq!(scan.entries, entries);
+        }
+
+        let plain = temp.path().join("scan.jsonl");
+        let text = fs::read_to_string(&plain).unwrap();
+        assert!(text.starts_with("{\"megamaid_scan\":1,"));
+        assert_eq!(text.lines().count(), 3);
+
+        fs::write(&plain, "{\"path\":\"/data\"}\n").unwrap();
+        assert!(matches!(
+            read_raw_scan(&plain),
+            Err(RawScanError::MissingHeader)
+        ));
+        fs::write(&plain, "{\"megamaid_scan\":1,\"roots\":[]}\n\nnot json\n").unwrap();
+        assert!(matches!(
+            read_raw_scan(&plain),
+            Err(RawScanError::Json { line: 3, .. })
+        ));
+    }
+}
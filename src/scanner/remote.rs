@@ -0,0 +1,197 @@
+//! Scan strategy for network filesystems.
+//!
+//! On SMB and NFS shares every metadata call is a round trip to the server.
+//! Remote mode skips alternate data stream enumeration, limits how many
+//! requests are in flight at once, and can leave directory sizes at 0 to save
+//! reading the metadata of hidden subtrees. It is chosen automatically for UNC paths and mapped network
+//! drives on Windows and for network mounts listed in `/proc/self/mounts` on
+//! Linux; elsewhere it has to be forced with [`RemoteMode::Always`].
+
+use crate::scanner::mounts::parse_mount_table;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// When the remote filesystem strategy is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteMode {
+    /// Use it when the scan root is on a network filesystem
+    #[default]
+    Auto,
+
+    /// Always use it
+    Always,
+
+    /// Never use it
+    Never,
+}
+
+/// Settings of the remote filesystem scan strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteScanConfig {
+    /// When to use the strategy
+    pub mode: RemoteMode,
+
+    /// Maximum number of threads querying the server at once
+    pub max_concurrency: usize,
+
+    /// Leave directory sizes at 0 instead of summing their contents
+    pub skip_dir_sizes: bool,
+}
+
+impl Default for RemoteScanConfig {
+    fn default() -> Self {
+        Self {
+            mode: RemoteMode::Auto,
+            max_concurrency: 4,
+            skip_dir_sizes: false,
+        }
+    }
+}
+
+impl RemoteScanConfig {
+    /// Returns true if a scan of `root` should use the remote strategy.
+    pub fn is_active(&self, root: &Path) -> bool {
+        match self.mode {
+            RemoteMode::Auto => is_network_path(root),
+            RemoteMode::Always => true,
+            RemoteMode::Never => false,
+        }
+    }
+}
+
+/// Filesystem types mounted from another machine.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "ncpfs",
+    "afs",
+    "9p",
+    "ceph",
+    "glusterfs",
+    "lustre",
+    "davfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+];
+
+/// Returns true if `path` is on a network filesystem.
+#[cfg(windows)]
+pub fn is_network_path(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Component, Prefix};
+    use windows_sys::Win32::Storage::FileSystem::GetDriveTypeW;
+
+    // DRIVE_REMOTE
+    const DRIVE_REMOTE: u32 = 4;
+
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return false;
+    };
+    match prefix.kind() {
+        Prefix::UNC(..) | Prefix::VerbatimUNC(..) => true,
+        Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+            let root: Vec<u16> = std::ffi::OsStr::new(&format!("{}:\\", letter as char))
+                .encode_wide()
+                .chain(Some(0))
+                .collect();
+            // SAFETY: `root` is a NUL-terminated drive root.
+            unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if `path` is on a network filesystem.
+#[cfg(target_os = "linux")]
+pub fn is_network_path(path: &Path) -> bool {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    std::fs::read_to_string("/proc/self/mounts")
+        .ok()
+        .and_then(|mounts| mount_fs_type(&path, &mounts).map(is_network_fs_type))
+        .unwrap_or(false)
+}
+
+/// Returns true if `path` is on a network filesystem; never detected on this platform.
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn is_network_path(_path: &Path) -> bool {
+    false
+}
+
+/// Returns true for filesystem types that live on another machine.
+pub fn is_network_fs_type(fs_type: &str) -> bool {
+    NETWORK_FS_TYPES.contains(&fs_type)
+}
+
+/// Returns the type of the filesystem `path` is on, given a mount table in
+/// `/proc/mounts` format.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn mount_fs_type<'a>(path: &Path, mounts: &'a str) -> Option<&'a str> {
+    parse_mount_table(mounts)
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+        .map(|mount| mount.fs_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTS: &str = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+proc /proc proc rw,nosuid 0 0
+//nas/share /mnt/nas cifs rw,vers=3.0 0 0
+nas:/export /mnt/nas/nfs nfs4 rw,hard 0 0
+/dev/sdb1 /mnt/nas/local ext4 rw 0 0
+//nas/media /mnt/My\\040Media cifs rw 0 0
+";
+
+    #[test]
+    fn test_mount_fs_type_picks_longest_mount_point() {
+        assert_eq!(mount_fs_type(Path::new("/home/user"), MOUNTS), Some("ext4"));
+        assert_eq!(
+            mount_fs_type(Path::new("/mnt/nas/docs"), MOUNTS),
+            Some("cifs")
+        );
+        assert_eq!(
+            mount_fs_type(Path::new("/mnt/nas/nfs/projects"), MOUNTS),
+            Some("nfs4")
+        );
+        assert_eq!(
+            mount_fs_type(Path::new("/mnt/nas/local"), MOUNTS),
+            Some("ext4")
+        );
+        assert_eq!(
+            mount_fs_type(Path::new("/mnt/My Media/film.mkv"), MOUNTS),
+            Some("cifs")
+        );
+        // Component-wise: /mnt/nasty is not under /mnt/nas
+        assert_eq!(mount_fs_type(Path::new("/mnt/nasty"), MOUNTS), Some("ext4"));
+    }
+
+    #[test]
+    fn test_network_fs_types() {
+        assert!(is_network_fs_type("cifs"));
+        assert!(is_network_fs_type("nfs4"));
+        assert!(is_network_fs_type("fuse.sshfs"));
+        assert!(!is_network_fs_type("ext4"));
+        assert!(!is_network_fs_type("ntfs3"));
+    }
+
+    #[test]
+    fn test_mode_overrides_detection() {
+        let root = Path::new(".");
+        let config = |mode| RemoteScanConfig {
+            mode,
+            ..Default::default()
+        };
+
+        assert!(config(RemoteMode::Always).is_active(root));
+        assert!(!config(RemoteMode::Never).is_active(root));
+    }
+}
@@ -0,0 +1,133 @@
+//! Directory sizes summed from a single walk.
+//!
+//! A directory's size is the total of the files below it. Walking each
+//! directory's subtree again to total it touches every file once per
+//! ancestor, which is quadratic on deep trees. Instead the scanners walk the
+//! tree once, keep every entry (including the skipped ones, whose sizes still
+//! count), and add child sizes to their parents from the deepest entries up.
+
+use crate::models::FileEntry;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use walkdir::DirEntry;
+
+/// Bookkeeping for directory sizes summed after a walk.
+#[derive(Debug, Default)]
+pub(crate) struct DeferredSizes {
+    /// Directories whose size is still to be summed
+    pending: Vec<usize>,
+    /// Symlinks, which don't count toward their parent
+    excluded: HashSet<usize>,
+    /// Skipped (hidden or excluded) entries kept only so their size is counted
+    hidden: HashSet<usize>,
+}
+
+impl DeferredSizes {
+    /// Records the walked entry about to be stored at `index`.
+    ///
+    /// Directories at the depth limit have no walked children to sum, so
+    /// they must be sized separately.
+    pub(crate) fn record(
+        &mut self,
+        index: usize,
+        entry: &DirEntry,
+        hidden: bool,
+        max_depth: Option<usize>,
+    ) {
+        if hidden {
+            self.hidden.insert(index);
+        }
+        if entry.path_is_symlink() {
+            self.excluded.insert(index);
+        }
+        if entry.file_type().is_dir() && Some(entry.depth()) != max_depth {
+            self.pending.push(index);
+        }
+    }
+
+    /// Sums the pending directory sizes and drops the hidden entries.
+    pub(crate) fn finish(self, mut entries: Vec<FileEntry>) -> Vec<FileEntry> {
+        if self.pending.is_empty() && self.hidden.is_empty() {
+            return entries;
+        }
+
+        aggregate_dir_sizes(&mut entries, &self.pending, &self.excluded);
+
+        let mut index = 0;
+        entries.retain(|_| {
+            let keep = !self.hidden.contains(&index);
+            index += 1;
+            keep
+        });
+        entries
+    }
+}
+
+/// Fills in directory sizes from the entries of a single walk.
+///
+/// Each directory in `pending` gets the sum of its children's sizes; deeper
+/// entries are summed first, so child directories are complete by then.
+/// Directories not in `pending` (taken from a cache or sized separately) keep
+/// their size and count toward their parent. `excluded` entries, such as
+/// symlinks, and cloud placeholders, which take up no disk space, don't count
+/// toward their parent.
+fn aggregate_dir_sizes(entries: &mut [FileEntry], pending: &[usize], excluded: &HashSet<usize>) {
+    let index: HashMap<PathBuf, usize> = pending
+        .iter()
+        .map(|&i| (entries[i].path.clone(), i))
+        .collect();
+
+    let mut order: Vec<usize> = (0..entries.len())
+        .filter(|&i| !excluded.contains(&i) && !entries[i].cloud_placeholder)
+        .collect();
+    order.sort_by_key(|&i| Reverse(entries[i].path.components().count()));
+
+    for i in order {
+        let Some(parent) = entries[i].path.parent() else {
+            continue;
+        };
+        if let Some(&p) = index.get(parent) {
+            entries[p].size = entries[p].size.saturating_add(entries[i].size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EntryType;
+    use std::time::SystemTime;
+
+    fn entry(path: &str, size: u64, entry_type: EntryType) -> FileEntry {
+        FileEntry::new(PathBuf::from(path), size, SystemTime::now(), entry_type)
+    }
+
+    #[test]
+    fn test_aggregate_dir_sizes() {
+        let mut entries = vec![
+            entry("/r", 0, EntryType::Directory),
+            entry("/r/a", 0, EntryType::Directory),
+            entry("/r/a/one.bin", 100, EntryType::File),
+            entry("/r/a/b", 0, EntryType::Directory),
+            entry("/r/a/b/two.bin", 20, EntryType::File),
+            entry("/r/cached", 300, EntryType::Directory),
+            entry("/r/cached/old.bin", 300, EntryType::File),
+            entry("/r/link", 9, EntryType::File),
+            entry("/r/three.bin", 3, EntryType::File),
+            FileEntry {
+                cloud_placeholder: true,
+                ..entry("/r/a/online-only.iso", 5_000, EntryType::File)
+            },
+        ];
+        let pending = [0, 1, 3];
+        let excluded = HashSet::from([7]);
+
+        aggregate_dir_sizes(&mut entries, &pending, &excluded);
+
+        assert_eq!(entries[3].size, 20);
+        assert_eq!(entries[1].size, 120);
+        assert_eq!(entries[5].size, 300, "cached size is kept");
+        assert_eq!(entries[0].size, 423);
+    }
+}
@@ -0,0 +1,106 @@
+//! Enumeration of NTFS alternate data streams.
+//!
+//! Files on NTFS can carry named data streams next to their main contents,
+//! such as the `Zone.Identifier` stream browsers attach to downloads. They
+//! don't show up in the file's reported length, so large streams can hide a
+//! lot of disk usage. On other platforms files have no such streams and
+//! [`alternate_streams`] always returns an empty list.
+
+use crate::models::DataStream;
+use std::io;
+use std::path::Path;
+
+/// Lists the alternate data streams of a file, excluding its main stream.
+#[cfg(windows)]
+pub fn alternate_streams(path: &Path) -> io::Result<Vec<DataStream>> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::{ERROR_HANDLE_EOF, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard,
+        WIN32_FIND_STREAM_DATA,
+    };
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut data: WIN32_FIND_STREAM_DATA = unsafe { std::mem::zeroed() };
+
+    // SAFETY: `wide` is NUL-terminated and `data` is a valid output buffer.
+    let handle = unsafe {
+        FindFirstStreamW(
+            wide.as_ptr(),
+            FindStreamInfoStandard,
+            &mut data as *mut _ as *mut _,
+            0,
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(code) if code == ERROR_HANDLE_EOF as i32 => Ok(Vec::new()),
+            _ => Err(err),
+        };
+    }
+
+    let mut streams = Vec::new();
+    loop {
+        let len = data
+            .cStreamName
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(data.cStreamName.len());
+        let raw = String::from_utf16_lossy(&data.cStreamName[..len]);
+        if let Some(name) = parse_stream_name(&raw) {
+            streams.push(DataStream {
+                name,
+                size: data.StreamSize.max(0) as u64,
+            });
+        }
+
+        // SAFETY: `handle` came from FindFirstStreamW and is still open.
+        if unsafe { FindNextStreamW(handle, &mut data as *mut _ as *mut _) } == 0 {
+            break;
+        }
+    }
+
+    // SAFETY: `handle` is closed exactly once.
+    unsafe { FindClose(handle) };
+    Ok(streams)
+}
+
+/// Lists the alternate data streams of a file; always empty off Windows.
+#[cfg(not(windows))]
+pub fn alternate_streams(_path: &Path) -> io::Result<Vec<DataStream>> {
+    Ok(Vec::new())
+}
+
+/// Extracts the stream name from `:name:$DATA`; `None` for the main stream (`::$DATA`).
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_stream_name(raw: &str) -> Option<String> {
+    let name = raw.strip_prefix(':').unwrap_or(raw);
+    let name = name.strip_suffix(":$DATA").unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_name() {
+        assert_eq!(parse_stream_name("::$DATA"), None);
+        assert_eq!(
+            parse_stream_name(":Zone.Identifier:$DATA"),
+            Some("Zone.Identifier".to_string())
+        );
+        assert_eq!(parse_stream_name(":payload"), Some("payload".to_string()));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_no_streams_off_windows() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("file.txt");
+        std::fs::write(&file, "data").unwrap();
+
+        assert!(alternate_streams(&file).unwrap().is_empty());
+    }
+}
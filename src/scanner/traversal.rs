@@ -1,7 +1,24 @@
 //! Directory traversal implementation.
 
-use crate::models::{EntryType, FileEntry};
-use std::path::Path;
+use crate::models::{EntryType, FileEntry, OwnArtifacts, PathCase};
+use crate::scanner::cache::ScanCache;
+use crate::scanner::cloud::is_cloud_placeholder;
+use crate::scanner::estimate::{EstimateConfig, Estimator};
+use crate::scanner::exclude::{is_snapshot_dir, ExcludeSet};
+use crate::scanner::ignore_files::IgnoreStack;
+use crate::scanner::links::link_of;
+use crate::scanner::mounts::Filesystem;
+use crate::scanner::owner::collect_owner;
+use crate::scanner::progress::AdvancedProgress;
+use crate::scanner::remote::RemoteScanConfig;
+use crate::scanner::sizes::DeferredSizes;
+use crate::scanner::streams::alternate_streams;
+use crate::scanner::throttle::Throttle;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use thiserror::Error;
 use walkdir::{DirEntry, WalkDir};
 
@@ -16,6 +33,12 @@ pub enum ScanError {
 
     #[error("Path does not exist: {0}")]
     PathNotFound(String),
+
+    #[error("Invalid exclude pattern: {0}")]
+    Pattern(#[from] glob::PatternError),
+
+    #[error("Scan cancelled")]
+    Cancelled,
 }
 
 /// Configuration for file system scanning.
@@ -29,46 +52,488 @@ pub struct ScanConfig {
 
     /// Whether to skip hidden files/directories
     pub skip_hidden: bool,
+
+    /// Whether to enumerate NTFS alternate data streams of files
+    pub include_streams: bool,
+
+    /// Network filesystem strategy (see [`crate::scanner::remote`])
+    pub remote: RemoteScanConfig,
+
+    /// Glob patterns of paths to leave out, without walking into them (see
+    /// [`crate::scanner::exclude`])
+    pub exclude_globs: Vec<String>,
+
+    /// Whether to leave out what `.gitignore` and `.megamaidignore` files
+    /// ignore (see [`crate::scanner::ignore_files`])
+    pub respect_ignore_files: bool,
+
+    /// Whether to stay on the root's filesystem, leaving out other mounts
+    /// such as network shares and USB drives
+    pub same_filesystem: bool,
+
+    /// Whether to record the owner and attributes of entries (see
+    /// [`crate::scanner::owner`])
+    pub collect_owner: bool,
+
+    /// Whether to walk the root's largest subdirectories first, by a quick
+    /// size estimate, so the biggest space consumers are known early (see
+    /// [`FileScanner::scan_streaming`])
+    pub largest_first: bool,
+
+    /// Maximum entries read per second, 0 for no limit (see
+    /// [`crate::scanner::throttle`])
+    pub max_iops: u32,
+}
+
+impl ScanConfig {
+    /// Returns true if a scan of `root` lists alternate data streams.
+    pub(crate) fn lists_streams(&self, root: &Path) -> bool {
+        self.include_streams && !self.remote.is_active(root)
+    }
+
+    /// Returns true if a scan of `root` leaves directory sizes at 0.
+    pub(crate) fn skips_dir_sizes(&self, root: &Path) -> bool {
+        self.remote.skip_dir_sizes && self.remote.is_active(root)
+    }
+}
+
+/// How directory sizes are computed during a walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirSizing {
+    /// Summed from the walk's own entries once it finishes
+    Summed,
+    /// Left at 0 (remote mode with `skip_dir_sizes`)
+    Skipped,
+}
+
+/// Counts of cached entries reused by an incremental scan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalStats {
+    /// Directories whose subtrees were taken from the cache
+    pub reused_subtrees: usize,
+
+    /// Entries taken from the cache (including those directories)
+    pub reused_entries: usize,
+}
+
+/// A subdirectory of the scan root whose walk finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedSubtree {
+    /// Full path of the subdirectory
+    pub path: PathBuf,
+
+    /// Bytes below it, as its directory entry will be sized
+    pub bytes: u64,
+
+    /// Entries walked below it, itself included
+    pub entries: usize,
+}
+
+/// An entry a scan couldn't read, such as a directory without access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreadableEntry {
+    /// Path of the entry
+    pub path: PathBuf,
+
+    /// Kind of the error, [`io::ErrorKind::Other`] for symlink loops
+    pub kind: io::ErrorKind,
+
+    /// Error message
+    pub message: String,
+}
+
+/// Entries scans couldn't read. Their contents are missing from the scan
+/// results, and a directory's size leaves them out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanErrorReport {
+    /// Unreadable entries, in the order the walk reached them
+    pub entries: Vec<UnreadableEntry>,
+}
+
+impl ScanErrorReport {
+    /// Returns true if every entry was read.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of unreadable entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the number of entries that couldn't be read for lack of
+    /// permission.
+    pub fn permission_denied(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.kind == io::ErrorKind::PermissionDenied)
+            .count()
+    }
 }
 
 /// Scans directories and collects file metadata.
 pub struct FileScanner {
     config: ScanConfig,
+    exclude: OwnArtifacts,
+    cancel: Arc<AtomicBool>,
+    progress: Arc<AdvancedProgress>,
+    throttle: Option<Throttle>,
+    errors: Mutex<Vec<UnreadableEntry>>,
 }
 
 impl FileScanner {
     /// Creates a new FileScanner with the given configuration.
     pub fn new(config: ScanConfig) -> Self {
-        Self { config }
+        Self {
+            throttle: Throttle::new(config.max_iops),
+            config,
+            exclude: OwnArtifacts::default(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            progress: Arc::new(AdvancedProgress::new()),
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Leaves megamaid's own files, and everything inside its own
+    /// directories, out of scans.
+    pub fn with_exclusions(mut self, artifacts: OwnArtifacts) -> Self {
+        self.exclude = artifacts;
+        self
+    }
+
+    /// Returns a handle that cancels the scan when set to `true`.
+    ///
+    /// The walk stops at the next entry and the scan returns
+    /// [`ScanError::Cancelled`]; entries found so far are discarded, since
+    /// the sizes of directories still being walked would be incomplete.
+    pub fn cancel_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel)
+    }
+
+    /// Returns the scan's progress, counting walked entries and those reused
+    /// from the cache across all scans by this scanner.
+    ///
+    /// The total isn't known until the walk finishes; seed it with
+    /// [`AdvancedProgress::seed`] from an earlier scan of the same root.
+    pub fn progress_handle(&self) -> Arc<AdvancedProgress> {
+        Arc::clone(&self.progress)
+    }
+
+    /// Returns the entries that couldn't be read, across all scans by this
+    /// scanner.
+    ///
+    /// An unreadable entry doesn't fail the scan: the walk goes on without
+    /// it, and without its contents for a directory that can't be listed.
+    pub fn errors(&self) -> ScanErrorReport {
+        ScanErrorReport {
+            entries: self.lock_errors().clone(),
+        }
+    }
+
+    /// Returns the scan configuration.
+    pub fn config(&self) -> &ScanConfig {
+        &self.config
+    }
+
+    fn cancel_requested(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// Locks the error list, recovering it if a panic poisoned it, like the
+    /// parallel scanner's `ErrorCollector`.
+    fn lock_errors(&self) -> MutexGuard<'_, Vec<UnreadableEntry>> {
+        self.errors.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records that `path` couldn't be read because of `error`.
+    fn record_unreadable(&self, path: &Path, error: &ScanError) {
+        let (kind, message) = match error {
+            ScanError::Io(e) => (e.kind(), e.to_string()),
+            ScanError::Walk(e) => match e.io_error() {
+                Some(io) => (io.kind(), io.to_string()),
+                None => (io::ErrorKind::Other, e.to_string()),
+            },
+            other => (io::ErrorKind::Other, other.to_string()),
+        };
+        self.lock_errors().push(UnreadableEntry {
+            path: path.to_path_buf(),
+            kind,
+            message,
+        });
+    }
+
+    /// Unwraps a walked entry, recording a walk error instead of failing.
+    fn walked(&self, root: &Path, entry: walkdir::Result<DirEntry>) -> Option<DirEntry> {
+        match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                let path = e.path().unwrap_or(root).to_path_buf();
+                self.record_unreadable(&path, &ScanError::Walk(e));
+                None
+            }
+        }
     }
 
     /// Scans the given root directory and returns all entries.
+    ///
+    /// The tree is walked once; directory sizes are summed from the walked
+    /// entries afterwards, deepest first.
     pub fn scan(&self, root: &Path) -> Result<Vec<FileEntry>, ScanError> {
+        self.scan_streaming(root, |_| {})
+    }
+
+    /// Scans the given root directory like [`FileScanner::scan`], calling
+    /// `on_subtree` as the walk of each subdirectory of the root finishes.
+    ///
+    /// With [`ScanConfig::largest_first`], the subdirectories are walked in
+    /// order of their estimated size, largest first, so the first subtrees
+    /// reported are the ones taking up the most space while the walk of the
+    /// rest goes on. The estimate samples a few paths through each subtree
+    /// (see [`crate::scanner::estimate`]); it only orders the walk, and the
+    /// reported sizes are exact.
+    pub fn scan_streaming(
+        &self,
+        root: &Path,
+        mut on_subtree: impl FnMut(&ScannedSubtree),
+    ) -> Result<Vec<FileEntry>, ScanError> {
         if !root.exists() {
             return Err(ScanError::PathNotFound(root.display().to_string()));
         }
 
         let mut entries = Vec::new();
+        let mut deferred = DeferredSizes::default();
+        let sizing = self.dir_sizing(root);
+        let streams = self.config.lists_streams(root);
         let max_depth = self.config.max_depth.unwrap_or(usize::MAX);
+        let excluded = self.exclude.within(root);
+        let globs = ExcludeSet::new(&self.config.exclude_globs)?;
+        let mut ignores = self.ignore_stack();
+        let filesystem = self.filesystem(root)?;
 
-        for entry in WalkDir::new(root)
+        let mut walk = WalkDir::new(root)
             .follow_links(self.config.follow_links)
-            .max_depth(max_depth)
+            .max_depth(max_depth);
+        if self.config.largest_first {
+            let ranks = subtree_ranks(root);
+            let rank = move |entry: &DirEntry| match entry.depth() {
+                1 => ranks.get(entry.path()).copied().unwrap_or(usize::MAX),
+                _ => 0,
+            };
+            walk = walk.sort_by(move |a, b| rank(a).cmp(&rank(b)));
+        }
+
+        let mut subtree: Option<ScannedSubtree> = None;
+        for entry in walk
+            .into_iter()
+            .filter_entry(|entry| !is_excluded(entry, &globs, &mut ignores, filesystem))
         {
-            let entry = entry?;
+            if self.cancel_requested() {
+                return Err(ScanError::Cancelled);
+            }
+            let Some(entry) = self.walked(root, entry) else {
+                continue;
+            };
+            if entry.depth() == 1 {
+                if let Some(done) = subtree.take() {
+                    on_subtree(&done);
+                }
+                let reported = entry.file_type().is_dir() && !self.should_skip(&entry, &excluded);
+                subtree = reported.then(|| ScannedSubtree {
+                    path: entry.path().to_path_buf(),
+                    bytes: 0,
+                    entries: 0,
+                });
+            }
+
+            let pushed = entries.len();
+            self.push_entry(
+                &mut entries,
+                &mut deferred,
+                entry,
+                sizing,
+                streams,
+                &excluded,
+            )?;
+            // Directory sizes are still 0 here, except at the depth limit
+            if let (Some(current), Some(entry)) = (subtree.as_mut(), entries.get(pushed)) {
+                current.entries += 1;
+                if !entry.cloud_placeholder {
+                    current.bytes += entry.size;
+                }
+            }
+        }
+        if let Some(done) = subtree {
+            on_subtree(&done);
+        }
+
+        Ok(deferred.finish(entries))
+    }
+
+    /// Scans the root, reusing cached subtrees whose directory mtime is unchanged.
+    ///
+    /// The root itself is always listed again. See [`crate::scanner::cache`]
+    /// for the cases this shortcut cannot detect.
+    pub fn scan_incremental(
+        &self,
+        root: &Path,
+        cache: &ScanCache,
+    ) -> Result<(Vec<FileEntry>, IncrementalStats), ScanError> {
+        self.scan_reusing(root, |entry| {
+            // An unreadable mtime just means the subtree is walked again
+            let mtime = entry.metadata().ok().and_then(|m| m.modified().ok())?;
+            cache.unchanged_subtree(entry.path(), mtime)
+        })
+    }
+
+    /// Scans the root again after the paths in `changed` changed, reusing
+    /// the cached subtree of every directory that contains none of them.
+    ///
+    /// Only the changed paths and the directories above them are walked
+    /// again, so a change costs about as much as listing its ancestors.
+    pub fn rescan_changed(
+        &self,
+        root: &Path,
+        cache: &ScanCache,
+        changed: &[PathBuf],
+    ) -> Result<(Vec<FileEntry>, IncrementalStats), ScanError> {
+        self.scan_reusing(root, |entry| {
+            let dir = entry.path();
+            if changed.iter().any(|path| path.starts_with(dir)) {
+                None
+            } else {
+                cache.subtree(dir)
+            }
+        })
+    }
+
+    /// Walks the root, taking the entries of each subdirectory for which
+    /// `reuse` returns some instead of walking it.
+    fn scan_reusing<'c>(
+        &self,
+        root: &Path,
+        mut reuse: impl FnMut(&DirEntry) -> Option<&'c [FileEntry]>,
+    ) -> Result<(Vec<FileEntry>, IncrementalStats), ScanError> {
+        if !root.exists() {
+            return Err(ScanError::PathNotFound(root.display().to_string()));
+        }
+
+        let mut entries = Vec::new();
+        let mut deferred = DeferredSizes::default();
+        let mut stats = IncrementalStats::default();
+        let sizing = self.dir_sizing(root);
+        let streams = self.config.lists_streams(root);
+        let max_depth = self.config.max_depth.unwrap_or(usize::MAX);
+        let excluded = self.exclude.within(root);
+        let globs = ExcludeSet::new(&self.config.exclude_globs)?;
+        let mut ignores = self.ignore_stack();
+        let filesystem = self.filesystem(root)?;
 
-            if self.should_skip(&entry) {
+        let mut walker = WalkDir::new(root)
+            .follow_links(self.config.follow_links)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|entry| !is_excluded(entry, &globs, &mut ignores, filesystem));
+
+        while let Some(entry) = walker.next() {
+            if self.cancel_requested() {
+                return Err(ScanError::Cancelled);
+            }
+            let Some(entry) = self.walked(root, entry) else {
                 continue;
+            };
+
+            if !self.should_skip(&entry, &excluded)
+                && entry.depth() > 0
+                && entry.file_type().is_dir()
+            {
+                if let Some(subtree) = reuse(&entry) {
+                    stats.reused_subtrees += 1;
+                    stats.reused_entries += subtree.len();
+                    self.progress.increment_by(subtree.len() as u64);
+                    entries.extend_from_slice(subtree);
+                    walker.skip_current_dir();
+                    continue;
+                }
             }
 
-            entries.push(self.to_file_entry(entry)?);
+            self.push_entry(
+                &mut entries,
+                &mut deferred,
+                entry,
+                sizing,
+                streams,
+                &excluded,
+            )?;
+        }
+
+        Ok((deferred.finish(entries), stats))
+    }
+
+    /// Returns the ignore file rules to track, if they are respected.
+    fn ignore_stack(&self) -> Option<IgnoreStack> {
+        self.config.respect_ignore_files.then(IgnoreStack::default)
+    }
+
+    /// Returns the filesystem to stay on, if the scan doesn't cross mounts.
+    fn filesystem(&self, root: &Path) -> Result<Option<Filesystem>, ScanError> {
+        if !self.config.same_filesystem {
+            return Ok(None);
         }
+        Ok(Some(Filesystem::of(root)?))
+    }
 
-        Ok(entries)
+    /// Picks the directory sizing strategy for a scan of `root`.
+    fn dir_sizing(&self, root: &Path) -> DirSizing {
+        if self.config.skips_dir_sizes(root) {
+            DirSizing::Skipped
+        } else {
+            DirSizing::Summed
+        }
+    }
+
+    /// Adds a walked entry, recording what its directory sizing needs.
+    ///
+    /// Skipped entries are kept until the sizes are summed, since they still
+    /// count toward their parent. An entry whose metadata can't be read is
+    /// recorded as unreadable and left out.
+    fn push_entry(
+        &self,
+        entries: &mut Vec<FileEntry>,
+        deferred: &mut DeferredSizes,
+        entry: DirEntry,
+        sizing: DirSizing,
+        streams: bool,
+        excluded: &[PathBuf],
+    ) -> Result<(), ScanError> {
+        self.progress.increment();
+        if let Some(throttle) = &self.throttle {
+            throttle.acquire();
+        }
+        let skip = self.should_skip(&entry, excluded);
+        if skip && sizing == DirSizing::Skipped {
+            return Ok(());
+        }
+
+        let file_entry = match self.to_file_entry(&entry, sizing, streams && !skip) {
+            Ok(file_entry) => file_entry,
+            Err(e @ (ScanError::Io(_) | ScanError::Walk(_))) => {
+                self.record_unreadable(entry.path(), &e);
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if sizing == DirSizing::Summed {
+            deferred.record(entries.len(), &entry, skip, self.config.max_depth);
+        }
+        entries.push(file_entry);
+        Ok(())
     }
 
     /// Determines if an entry should be skipped.
-    fn should_skip(&self, entry: &DirEntry) -> bool {
+    ///
+    /// `excluded` holds the excluded artifacts under the scan root, see
+    /// [`OwnArtifacts::within`].
+    fn should_skip(&self, entry: &DirEntry, excluded: &[PathBuf]) -> bool {
         if self.config.skip_hidden {
             if let Some(name) = entry.file_name().to_str() {
                 if name.starts_with('.') && name != "." {
@@ -76,44 +541,84 @@ impl FileScanner {
                 }
             }
         }
-        false
+        excluded
+            .iter()
+            .any(|path| PathCase::native().starts_with(entry.path(), path))
     }
 
-    /// Converts a DirEntry to a FileEntry.
-    fn to_file_entry(&self, entry: DirEntry) -> Result<FileEntry, ScanError> {
+    /// Converts a DirEntry to a FileEntry, listing a file's alternate data
+    /// streams if `streams` is set.
+    fn to_file_entry(
+        &self,
+        entry: &DirEntry,
+        sizing: DirSizing,
+        streams: bool,
+    ) -> Result<FileEntry, ScanError> {
         let metadata = entry.metadata()?;
+        let link = link_of(entry);
 
-        let entry_type = if metadata.is_dir() {
-            EntryType::Directory
-        } else {
-            EntryType::File
+        let entry_type = match &link {
+            Some((link_type, _)) => *link_type,
+            None if metadata.is_dir() => EntryType::Directory,
+            None => EntryType::File,
         };
 
-        // For directories, calculate recursive size
-        let size = if metadata.is_dir() {
-            self.calculate_dir_size(entry.path())?
-        } else {
+        // Directory sizes are summed after the walk, except at the depth
+        // limit where there are no walked children to sum. A link that isn't
+        // followed takes up no space.
+        let at_depth_limit = Some(entry.depth()) == self.config.max_depth;
+        let size = if metadata.is_symlink() {
+            0
+        } else if !metadata.is_dir() {
             metadata.len()
+        } else {
+            match sizing {
+                DirSizing::Summed if at_depth_limit => self.calculate_dir_size(entry.path())?,
+                DirSizing::Summed | DirSizing::Skipped => 0,
+            }
         };
 
-        Ok(FileEntry::new(
+        let mut file_entry = FileEntry::new(
             entry.path().to_path_buf(),
             size,
             metadata.modified()?,
             entry_type,
-        ))
+        );
+        file_entry.link_target = link.and_then(|(_, target)| target);
+        file_entry.cloud_placeholder = is_cloud_placeholder(&metadata);
+        if self.config.collect_owner {
+            collect_owner(&mut file_entry, &metadata);
+        }
+
+        // Streams that can't be listed (e.g. access denied) are left out
+        // rather than failing the scan. Remote mode never lists them: each
+        // lookup is another round trip to the server.
+        if streams && metadata.is_file() {
+            file_entry.streams = alternate_streams(entry.path()).unwrap_or_default();
+        }
+
+        Ok(file_entry)
     }
 
-    /// Calculates the total size of all files in a directory recursively.
+    /// Calculates the total size of all files in a directory recursively,
+    /// for directories at the depth limit.
     fn calculate_dir_size(&self, dir_path: &Path) -> Result<u64, ScanError> {
         let mut total_size = 0u64;
 
-        for entry in WalkDir::new(dir_path).follow_links(false) {
+        for entry in WalkDir::new(dir_path)
+            .follow_links(false)
+            .same_file_system(self.config.same_filesystem)
+            .into_iter()
+            .filter_entry(|entry| !is_snapshot_dir(entry))
+        {
+            if self.cancel_requested() {
+                return Err(ScanError::Cancelled);
+            }
             let entry = entry?;
             let metadata = entry.metadata()?;
 
-            // Only count files, not directories themselves
-            if metadata.is_file() {
+            // Only count files stored locally, not directories themselves
+            if metadata.is_file() && !is_cloud_placeholder(&metadata) {
                 total_size = total_size.saturating_add(metadata.len());
             }
         }
@@ -122,6 +627,50 @@ impl FileScanner {
     }
 }
 
+/// Returns the position of each subdirectory of `root` by estimated size,
+/// largest first; empty if `root` can't be listed.
+fn subtree_ranks(root: &Path) -> HashMap<PathBuf, usize> {
+    // A rough estimate is enough to order the walk
+    let config = EstimateConfig {
+        probes: 16,
+        exact_dir_limit: 16,
+        files_per_dir: 16,
+        ..Default::default()
+    };
+    Estimator::new(config)
+        .estimate(root)
+        .map(|report| {
+            report
+                .subtrees
+                .into_iter()
+                .enumerate()
+                .map(|(rank, subtree)| (subtree.path, rank))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns true if an exclude glob or ignore file excludes `entry`, or it's
+/// a directory off `filesystem` or holding megamaid's volume snapshots; the
+/// root never is.
+fn is_excluded(
+    entry: &DirEntry,
+    globs: &ExcludeSet,
+    ignores: &mut Option<IgnoreStack>,
+    filesystem: Option<Filesystem>,
+) -> bool {
+    if is_snapshot_dir(entry) {
+        return true;
+    }
+    if entry.depth() > 0 && !globs.is_empty() && globs.matches(entry.path()) {
+        return true;
+    }
+    if filesystem.is_some_and(|fs| fs.excludes(entry)) {
+        return true;
+    }
+    ignores.as_mut().is_some_and(|stack| stack.skips(entry))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,6 +727,60 @@ mod tests {
         assert_eq!(file.size, 4); // "test" = 4 bytes
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_lists_symlinks_as_links() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("store");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("file.txt"), "content").unwrap();
+        std::os::unix::fs::symlink(&target, temp.path().join("node_modules")).unwrap();
+
+        let results = FileScanner::new(ScanConfig::default())
+            .scan(temp.path())
+            .unwrap();
+
+        let link = results
+            .iter()
+            .find(|e| e.path.ends_with("node_modules"))
+            .unwrap();
+        assert!(link.is_link() && !link.is_directory());
+        assert_eq!(link.entry_type, EntryType::Symlink);
+        assert_eq!(link.link_target.as_deref(), Some(target.as_path()));
+        assert_eq!(link.size, 0);
+        assert_eq!(results.len(), 4, "the target is listed once");
+        assert_eq!(results[0].size, 7, "the link doesn't count toward the root");
+    }
+
+    #[test]
+    fn test_dir_sizes_include_nested_and_hidden_contents() {
+        // Temp dir names start with a dot, so scan a directory inside it
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("a/b/.cache")).unwrap();
+        fs::write(root.join("a/one.bin"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("a/b/two.bin"), vec![0u8; 20]).unwrap();
+        fs::write(root.join("a/b/.cache/three.bin"), vec![0u8; 3]).unwrap();
+
+        let config = ScanConfig {
+            skip_hidden: true,
+            ..Default::default()
+        };
+        let results = FileScanner::new(config).scan(&root).unwrap();
+        let size = |rel: &str| {
+            results
+                .iter()
+                .find(|e| e.path == root.join(rel))
+                .map(|e| e.size)
+        };
+
+        assert_eq!(size("a/b"), Some(23));
+        assert_eq!(size("a"), Some(123));
+        let top = results.iter().find(|e| e.path == root).unwrap();
+        assert_eq!(top.size, 123);
+        assert_eq!(size("a/b/.cache"), None);
+    }
+
     #[test]
     fn test_skip_hidden_files() {
         let temp = TempDir::new().unwrap();
@@ -204,6 +807,121 @@ mod tests {
         assert!(results.iter().any(|e| e.path.ends_with("visible.txt")));
     }
 
+    #[test]
+    fn test_exclude_globs() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("app/node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join("clients/acme/archive")).unwrap();
+        fs::write(root.join("app/node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(root.join("clients/acme/archive/old.zip"), "zip").unwrap();
+        fs::write(root.join("app/main.rs"), "main").unwrap();
+        fs::write(root.join("disk.iso"), "iso").unwrap();
+
+        let config = ScanConfig {
+            exclude_globs: vec![
+                "node_modules".to_string(),
+                "*.iso".to_string(),
+                "clients\\*\\archive".to_string(),
+            ],
+            ..Default::default()
+        };
+        let results = FileScanner::new(config).scan(&root).unwrap();
+        let paths: Vec<_> = results
+            .iter()
+            .filter_map(|e| e.path.strip_prefix(&root).ok())
+            .collect();
+
+        assert!(paths.contains(&Path::new("app/main.rs")));
+        assert!(paths.contains(&Path::new("clients/acme")));
+        assert!(!paths.iter().any(|p| p.ends_with("node_modules")
+            || p.starts_with("app/node_modules")
+            || p.starts_with("clients/acme/archive")
+            || p.ends_with("disk.iso")));
+        // Excluded subtrees are not walked, so they don't count toward sizes
+        let app = results.iter().find(|e| e.path == root.join("app")).unwrap();
+        assert_eq!(app.size, 4);
+
+        let config = ScanConfig {
+            exclude_globs: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            FileScanner::new(config).scan(&root),
+            Err(ScanError::Pattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_volume_snapshots_are_never_walked() {
+        let temp = TempDir::new().unwrap();
+        let copy = temp
+            .path()
+            .join(".megamaid-snapshots/megamaid-1/app/target");
+        fs::create_dir_all(&copy).unwrap();
+        fs::write(copy.join("app.bin"), "binary").unwrap();
+        fs::write(temp.path().join("main.rs"), "main").unwrap();
+
+        let results = FileScanner::new(ScanConfig::default())
+            .scan(temp.path())
+            .unwrap();
+
+        assert!(!results
+            .iter()
+            .any(|e| e.path.starts_with(temp.path().join(".megamaid-snapshots"))));
+        assert_eq!(results[0].size, 4);
+    }
+
+    #[test]
+    fn test_respect_ignore_files() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir_all(root.join("app/dist")).unwrap();
+        fs::write(root.join("app/.gitignore"), "dist/\n*.tmp\n").unwrap();
+        fs::write(root.join("app/dist/bundle.js"), "bundle").unwrap();
+        fs::write(root.join("app/scratch.tmp"), "tmp").unwrap();
+        fs::write(root.join("app/main.js"), "main").unwrap();
+
+        let scan = |respect_ignore_files| {
+            let config = ScanConfig {
+                respect_ignore_files,
+                ..Default::default()
+            };
+            FileScanner::new(config).scan(&root).unwrap()
+        };
+        let has =
+            |entries: &[FileEntry], rel: &str| entries.iter().any(|e| e.path == root.join(rel));
+
+        let entries = scan(true);
+        assert!(has(&entries, "app/main.js"));
+        assert!(!has(&entries, "app/dist"));
+        assert!(!has(&entries, "app/scratch.tmp"));
+
+        let entries = scan(false);
+        assert!(has(&entries, "app/dist/bundle.js"));
+        assert!(has(&entries, "app/scratch.tmp"));
+    }
+
+    #[test]
+    fn test_same_filesystem_keeps_local_tree() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a/b")).unwrap();
+        fs::write(temp.path().join("a/b/file.txt"), "test").unwrap();
+
+        let config = ScanConfig {
+            same_filesystem: true,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let entries = FileScanner::new(config).scan(temp.path()).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[1].size, 4,
+            "depth-limited sizes stay on the filesystem"
+        );
+    }
+
     #[test]
     fn test_max_depth_limiting() {
         let temp = TempDir::new().unwrap();
@@ -255,6 +973,64 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_entries_are_reported_not_fatal() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("kept.txt"), "content").unwrap();
+        symlink(temp.path().join("missing"), temp.path().join("dangling")).unwrap();
+        let locked = temp.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("inside.txt"), "content").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        // Root reads the directory anyway
+        let denied = fs::read_dir(&locked).is_err();
+
+        let scanner = FileScanner::new(ScanConfig {
+            follow_links: true,
+            ..Default::default()
+        });
+        let results = scanner.scan(temp.path());
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        let results = results.unwrap();
+
+        assert!(results.iter().any(|e| e.path.ends_with("kept.txt")));
+        let report = scanner.errors();
+        let dangling = &report.entries[report
+            .entries
+            .iter()
+            .position(|e| e.path.ends_with("dangling"))
+            .unwrap()];
+        assert_eq!(dangling.kind, io::ErrorKind::NotFound);
+        assert!(!results.iter().any(|e| e.path.ends_with("dangling")));
+
+        if denied {
+            assert_eq!(report.len(), 2);
+            assert_eq!(report.permission_denied(), 1);
+            assert!(report.entries.iter().any(|e| e.path == locked));
+            assert!(results.iter().any(|e| e.path == locked));
+            assert!(!results.iter().any(|e| e.path.ends_with("inside.txt")));
+        } else {
+            assert_eq!(report.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_cancelled_scan() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("file.txt"), "content").unwrap();
+
+        let scanner = FileScanner::new(ScanConfig::default());
+        scanner.cancel_handle().store(true, Ordering::SeqCst);
+
+        assert!(matches!(
+            scanner.scan(temp.path()),
+            Err(ScanError::Cancelled)
+        ));
+    }
+
     #[test]
     fn test_default_config() {
         let config = ScanConfig::default();
@@ -263,4 +1039,205 @@ mod tests {
         assert_eq!(config.max_depth, None);
         assert!(!config.skip_hidden);
     }
+
+    #[test]
+    fn test_incremental_scan_reuses_unchanged_subtrees() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a/nested")).unwrap();
+        fs::create_dir_all(temp.path().join("b")).unwrap();
+        fs::write(temp.path().join("a/nested/file.txt"), "1234").unwrap();
+        fs::write(temp.path().join("b/file.txt"), "1234").unwrap();
+
+        let scanner = FileScanner::new(ScanConfig::default());
+        let entries = scanner.scan(temp.path()).unwrap();
+        let total = entries.len();
+
+        // Pretend the cached scan started after every directory was last modified
+        let started_at = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        let mut cache = ScanCache::new(temp.path(), &ScanConfig::default(), started_at, entries);
+
+        // In-place content changes do not touch directory mtimes, so the stale
+        // cached size is reused for "a"
+        fs::write(temp.path().join("a/nested/file.txt"), "12345678").unwrap();
+
+        let (entries, stats) = scanner.scan_incremental(temp.path(), &cache).unwrap();
+        assert_eq!(entries.len(), total);
+        assert_eq!(stats.reused_subtrees, 2);
+        let file = entries
+            .iter()
+            .find(|e| e.path.ends_with("nested/file.txt"))
+            .unwrap();
+        assert_eq!(file.size, 4);
+
+        // Directories whose mtime differs from the cache are walked again
+        cache = ScanCache::new(
+            temp.path(),
+            &ScanConfig::default(),
+            started_at,
+            cache
+                .into_entries()
+                .into_iter()
+                .map(|mut e| {
+                    if e.path.starts_with(temp.path().join("a")) && e.is_directory() {
+                        e.modified = std::time::UNIX_EPOCH;
+                    }
+                    e
+                })
+                .collect(),
+        );
+        let (entries, stats) = scanner.scan_incremental(temp.path(), &cache).unwrap();
+        assert_eq!(stats.reused_subtrees, 1);
+        let file = entries
+            .iter()
+            .find(|e| e.path.ends_with("nested/file.txt"))
+            .unwrap();
+        assert_eq!(file.size, 8);
+    }
+
+    #[test]
+    fn test_rescan_changed_walks_only_changed_subtrees() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a/nested")).unwrap();
+        fs::create_dir_all(temp.path().join("b")).unwrap();
+        fs::write(temp.path().join("a/nested/file.txt"), "1234").unwrap();
+        fs::write(temp.path().join("b/file.txt"), "1234").unwrap();
+
+        let scanner = FileScanner::new(ScanConfig::default());
+        let entries = scanner.scan(temp.path()).unwrap();
+        let cache = ScanCache::new(
+            temp.path(),
+            &ScanConfig::default(),
+            std::time::SystemTime::now(),
+            entries,
+        );
+
+        // An in-place change is picked up when its path is reported
+        let changed = temp.path().join("a/nested/file.txt");
+        fs::write(&changed, "12345678").unwrap();
+        let (entries, stats) = scanner
+            .rescan_changed(temp.path(), &cache, &[changed])
+            .unwrap();
+
+        // Only "b" is reused; "a" and "a/nested" lead to the change
+        assert_eq!(stats.reused_subtrees, 1);
+        assert_eq!(stats.reused_entries, 2);
+        let size = |path: &str| {
+            entries
+                .iter()
+                .find(|e| e.path == temp.path().join(path))
+                .unwrap()
+                .size
+        };
+        assert_eq!(size("a/nested/file.txt"), 8);
+        assert_eq!(size("a"), 8);
+        assert_eq!(size(""), 12);
+    }
+
+    fn remote_config(skip_dir_sizes: bool) -> ScanConfig {
+        ScanConfig {
+            remote: RemoteScanConfig {
+                mode: crate::scanner::RemoteMode::Always,
+                skip_dir_sizes,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn sizes(entries: &[FileEntry]) -> Vec<(std::path::PathBuf, u64)> {
+        let mut sizes: Vec<_> = entries.iter().map(|e| (e.path.clone(), e.size)).collect();
+        sizes.sort();
+        sizes
+    }
+
+    #[test]
+    fn test_remote_mode_matches_local_sizes() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a/b/c")).unwrap();
+        fs::create_dir_all(temp.path().join(".cache/inner")).unwrap();
+        fs::write(temp.path().join("a/one.bin"), "x".repeat(100)).unwrap();
+        fs::write(temp.path().join("a/b/c/two.bin"), "x".repeat(20)).unwrap();
+        fs::write(temp.path().join("a/.hidden"), "x".repeat(5)).unwrap();
+        fs::write(temp.path().join(".cache/inner/blob"), "x".repeat(7)).unwrap();
+
+        for skip_hidden in [false, true] {
+            for max_depth in [None, Some(2)] {
+                let local = ScanConfig {
+                    skip_hidden,
+                    max_depth,
+                    ..Default::default()
+                };
+                let remote = ScanConfig {
+                    skip_hidden,
+                    max_depth,
+                    ..remote_config(false)
+                };
+
+                let expected = FileScanner::new(local).scan(temp.path()).unwrap();
+                let actual = FileScanner::new(remote).scan(temp.path()).unwrap();
+                assert_eq!(sizes(&actual), sizes(&expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_remote_mode_skip_dir_sizes() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("a")).unwrap();
+        fs::write(temp.path().join("a/file.bin"), "x".repeat(100)).unwrap();
+
+        let entries = FileScanner::new(remote_config(true))
+            .scan(temp.path())
+            .unwrap();
+
+        assert!(entries
+            .iter()
+            .filter(|e| e.is_directory())
+            .all(|e| e.size == 0));
+        let file = entries.iter().find(|e| e.is_file()).unwrap();
+        assert_eq!(file.size, 100);
+    }
+
+    #[test]
+    fn test_scan_streaming_largest_first() {
+        let temp = TempDir::new().unwrap();
+        for (dir, size) in [("a", 10), ("b", 5_000), ("c", 300)] {
+            fs::create_dir_all(temp.path().join(dir).join("sub")).unwrap();
+            fs::write(temp.path().join(dir).join("sub/file.bin"), "x".repeat(size)).unwrap();
+        }
+        fs::write(temp.path().join("top.txt"), "top").unwrap();
+        fs::create_dir(temp.path().join(".hidden")).unwrap();
+
+        let config = ScanConfig {
+            skip_hidden: true,
+            largest_first: true,
+            ..ScanConfig::default()
+        };
+        let mut reported = Vec::new();
+        let entries = FileScanner::new(config.clone())
+            .scan_streaming(temp.path(), |subtree| reported.push(subtree.clone()))
+            .unwrap();
+
+        let names: Vec<_> = reported
+            .iter()
+            .map(|s| (s.path.file_name().unwrap().to_str().unwrap(), s.bytes))
+            .collect();
+        assert_eq!(names, vec![("b", 5_000), ("c", 300), ("a", 10)]);
+        assert!(reported.iter().all(|s| s.entries == 3));
+
+        // The order of the walk doesn't change the results
+        let unordered = ScanConfig {
+            largest_first: false,
+            ..config
+        };
+        let mut expected = FileScanner::new(unordered).scan(temp.path()).unwrap();
+        let mut entries = entries;
+        for list in [&mut expected, &mut entries] {
+            list.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        let sizes = |list: &[FileEntry]| -> Vec<_> {
+            list.iter().map(|e| (e.path.clone(), e.size)).collect()
+        };
+        assert_eq!(sizes(&entries), sizes(&expected));
+    }
 }
@@ -0,0 +1,341 @@
+//! Keeping scan results up to date while the tree changes.
+//!
+//! A [`Watcher`] scans a root once and keeps its entries in memory as an
+//! index. It subscribes to the operating system's file change notifications
+//! (through `notify`) for the root, and each [`Watcher::poll`] rescans only
+//! the paths reported changed since the previous poll, together with the
+//! directories above them (see [`FileScanner::rescan_changed`]). A poll with
+//! no reported changes doesn't touch the disk.
+//!
+//! Notifications can't always be set up: network shares don't report
+//! changes made by other machines, and the system may be out of watches
+//! (`fs.inotify.max_user_watches` on Linux). The watcher then falls back to
+//! walking the whole tree on every poll, or with
+//! [`Watcher::with_incremental`] to rescanning as `scan --incremental`
+//! does, with the same caveats (see [`crate::scanner::cache`]). When the
+//! system reports that notifications were lost, the next poll walks the
+//! whole tree too.
+//!
+//! `megamaid watch` re-runs detection on the index whenever a poll finds
+//! changes and prints the updated candidates.
+
+use crate::models::FileEntry;
+use crate::scanner::{FileScanner, ScanCache, ScanError};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::SystemTime;
+
+/// Watches one root, keeping its scanned entries up to date.
+pub struct Watcher {
+    scanner: FileScanner,
+    root: PathBuf,
+    incremental: bool,
+
+    /// File change notifications, if they could be set up
+    notifications: Option<Notifications>,
+
+    /// Entries of the latest poll, sorted by path
+    index: ScanCache,
+}
+
+/// A subscription to file change notifications under the root.
+struct Notifications {
+    /// Dropping it ends the subscription
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+
+    /// Spellings of the root that event paths may start with: absolute, and
+    /// with links resolved
+    prefixes: Vec<PathBuf>,
+}
+
+impl Notifications {
+    fn subscribe(root: &Path) -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(root, RecursiveMode::Recursive)?;
+        let prefixes = [std::path::absolute(root), root.canonicalize()]
+            .into_iter()
+            .flatten()
+            .collect();
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            prefixes,
+        })
+    }
+
+    /// Spells an event path the way the scan of `root` does, or returns
+    /// `None` if it isn't under the root.
+    fn under_root(&self, root: &Path, path: &Path) -> Option<PathBuf> {
+        if path.starts_with(root) {
+            return Some(path.to_path_buf());
+        }
+        self.prefixes
+            .iter()
+            .find_map(|prefix| path.strip_prefix(prefix).ok())
+            .map(|relative| root.join(relative))
+    }
+}
+
+/// What the notifications received since the previous poll call for.
+enum Pending {
+    /// Rescan these paths
+    Paths(Vec<PathBuf>),
+    /// Walk the whole tree
+    Everything,
+}
+
+/// How the entries changed between two polls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WatchChanges {
+    /// Entries that weren't there before
+    pub added: usize,
+
+    /// Entries that are gone
+    pub removed: usize,
+
+    /// Entries whose size or mtime changed, including directories whose
+    /// contents changed
+    pub modified: usize,
+}
+
+impl WatchChanges {
+    /// Compares two entry lists sorted by path.
+    pub fn between(before: &[FileEntry], after: &[FileEntry]) -> Self {
+        let mut changes = Self::default();
+        let (mut old, mut new) = (before.iter().peekable(), after.iter().peekable());
+        loop {
+            match (old.peek(), new.peek()) {
+                (Some(a), Some(b)) => match a.path.cmp(&b.path) {
+                    Ordering::Less => {
+                        changes.removed += 1;
+                        old.next();
+                    }
+                    Ordering::Greater => {
+                        changes.added += 1;
+                        new.next();
+                    }
+                    Ordering::Equal => {
+                        if a.size != b.size || a.modified != b.modified {
+                            changes.modified += 1;
+                        }
+                        old.next();
+                        new.next();
+                    }
+                },
+                (Some(_), None) => {
+                    changes.removed += old.count();
+                    break;
+                }
+                (None, Some(_)) => {
+                    changes.added += new.count();
+                    break;
+                }
+                (None, None) => break,
+            }
+        }
+        changes
+    }
+
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.modified == 0
+    }
+}
+
+impl Watcher {
+    /// Scans `root` with `scanner` and starts watching it.
+    ///
+    /// Roots scanned in remote mode are polled without notifications, since
+    /// network shares don't report changes made elsewhere.
+    pub fn start(scanner: FileScanner, root: &Path) -> Result<Self, ScanError> {
+        // Subscribing first catches the changes made while the scan runs
+        let notifications = if scanner.config().remote.is_active(root) {
+            None
+        } else {
+            Notifications::subscribe(root).ok()
+        };
+        let started_at = SystemTime::now();
+        let entries = scanner.scan(root)?;
+        let index = ScanCache::new(root, scanner.config(), started_at, entries);
+        Ok(Self {
+            scanner,
+            root: root.to_path_buf(),
+            incremental: false,
+            notifications,
+            index,
+        })
+    }
+
+    /// Without notifications, reuses the indexed entries of directories
+    /// whose mtime is unchanged instead of walking the whole tree on every
+    /// poll.
+    pub fn with_incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Returns true if polls rescan only what notifications reported changed.
+    pub fn is_notified(&self) -> bool {
+        self.notifications.is_some()
+    }
+
+    /// Returns the watched root.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the entries of the latest poll, sorted by path.
+    pub fn entries(&self) -> &[FileEntry] {
+        self.index.entries()
+    }
+
+    /// Rescans what changed since the previous poll, updating the entries,
+    /// and returns how they changed.
+    pub fn poll(&mut self) -> Result<WatchChanges, ScanError> {
+        let started_at = SystemTime::now();
+        let entries = match self.pending() {
+            Pending::Paths(paths) if paths.is_empty() => return Ok(WatchChanges::default()),
+            Pending::Paths(paths) => {
+                self.scanner
+                    .rescan_changed(&self.root, &self.index, &paths)?
+                    .0
+            }
+            Pending::Everything if self.incremental && self.notifications.is_none() => {
+                self.scanner.scan_incremental(&self.root, &self.index)?.0
+            }
+            Pending::Everything => self.scanner.scan(&self.root)?,
+        };
+        let index = ScanCache::new(&self.root, self.scanner.config(), started_at, entries);
+        let changes = WatchChanges::between(self.index.entries(), index.entries());
+        self.index = index;
+        Ok(changes)
+    }
+
+    /// Takes the notifications received since the previous poll.
+    fn pending(&mut self) -> Pending {
+        let Some(notifications) = &self.notifications else {
+            return Pending::Everything;
+        };
+
+        let mut paths = Vec::new();
+        loop {
+            match notifications.events.try_recv() {
+                Ok(Ok(event)) => {
+                    if event.need_rescan() {
+                        return Pending::Everything;
+                    }
+                    // Reads don't change anything
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+                    if event.paths.is_empty() {
+                        return Pending::Everything;
+                    }
+                    for path in &event.paths {
+                        match notifications.under_root(&self.root, path) {
+                            Some(path) => paths.push(path),
+                            None => return Pending::Everything,
+                        }
+                    }
+                }
+                // The events of the failure are lost
+                Ok(Err(_)) => return Pending::Everything,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.notifications = None;
+                    return Pending::Everything;
+                }
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        Pending::Paths(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{RemoteMode, RemoteScanConfig, ScanConfig};
+    use std::fs;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    /// Polls until the expected number of entries were added and removed,
+    /// giving notifications time to arrive, and returns all changes seen.
+    fn poll_until(watcher: &mut Watcher, added: usize, removed: usize) -> WatchChanges {
+        let mut seen = WatchChanges::default();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while (seen.added < added || seen.removed < removed) && Instant::now() < deadline {
+            let changes = watcher.poll().unwrap();
+            seen.added += changes.added;
+            seen.removed += changes.removed;
+            seen.modified += changes.modified;
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        seen
+    }
+
+    fn tree() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("logs")).unwrap();
+        fs::write(temp.path().join("logs/a.log"), "a").unwrap();
+        fs::create_dir(temp.path().join("src")).unwrap();
+        fs::write(temp.path().join("src/main.rs"), "fn main() {}").unwrap();
+        temp
+    }
+
+    #[test]
+    fn test_poll_rescans_notified_changes() {
+        let temp = tree();
+        let scanner = FileScanner::new(ScanConfig::default());
+        let mut watcher = Watcher::start(scanner, temp.path()).unwrap();
+        assert!(watcher.is_notified());
+        assert_eq!(watcher.entries().len(), 5);
+        assert!(watcher.poll().unwrap().is_empty());
+
+        fs::remove_file(temp.path().join("logs/a.log")).unwrap();
+        fs::write(temp.path().join("logs/b.log"), "bigger").unwrap();
+        let changes = poll_until(&mut watcher, 1, 1);
+
+        assert_eq!(changes.added, 1);
+        assert_eq!(changes.removed, 1);
+        // The size of logs and of the root changed
+        assert!(changes.modified >= 2);
+        let size = |name: &str| {
+            watcher
+                .entries()
+                .iter()
+                .find(|e| e.path == temp.path().join(name))
+                .map(|e| e.size)
+        };
+        assert_eq!(size("logs/b.log"), Some(6));
+        assert_eq!(size("logs"), Some(6));
+        assert_eq!(size(""), Some(6 + 12));
+    }
+
+    #[test]
+    fn test_remote_roots_are_polled() {
+        let temp = tree();
+        let config = ScanConfig {
+            remote: RemoteScanConfig {
+                mode: RemoteMode::Always,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut watcher = Watcher::start(FileScanner::new(config), temp.path()).unwrap();
+        assert!(!watcher.is_notified());
+        assert!(watcher.poll().unwrap().is_empty());
+
+        fs::remove_file(temp.path().join("logs/a.log")).unwrap();
+        fs::write(temp.path().join("logs/b.log"), "bigger").unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.added, 1);
+        assert_eq!(changes.removed, 1);
+    }
+}
@@ -0,0 +1,198 @@
+//! Classification and retry of metadata read failures.
+//!
+//! A file that can't be read during verification is either briefly locked by
+//! another process (a sharing violation, e.g. an editor saving or an
+//! antivirus scan) or protected by its ACL or mode. The first usually clears
+//! up within moments and is retried; the second needs elevated rights, so the
+//! report recommends elevation for it and skipping for the rest.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+/// Attempts made for an operation failing with a transient error.
+pub const TRANSIENT_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each further one.
+pub const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Why an entry's metadata could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessErrorCause {
+    /// The file stayed locked by another process through every retry
+    SharingViolation,
+
+    /// The file's ACL or mode denies access to the current user
+    AccessDenied,
+
+    /// Any other I/O error
+    Other,
+}
+
+impl AccessErrorCause {
+    /// Classifies an I/O error.
+    pub fn of(err: &io::Error) -> Self {
+        if is_sharing_violation(err) {
+            Self::SharingViolation
+        } else if err.kind() == io::ErrorKind::PermissionDenied {
+            Self::AccessDenied
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Returns true if retrying the operation may succeed.
+    pub fn is_transient(self) -> bool {
+        self == Self::SharingViolation
+    }
+
+    /// Human-readable heading for this cause.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SharingViolation => "Locked by another process",
+            Self::AccessDenied => "Access denied",
+            Self::Other => "Other errors",
+        }
+    }
+
+    /// What the user can do about entries failing with this cause.
+    pub fn recommendation(self) -> &'static str {
+        match self {
+            Self::SharingViolation => {
+                "Close the programs using these files and verify again, or skip them"
+            }
+            Self::AccessDenied => {
+                "Run megamaid elevated (as Administrator or root) or as the files' owner"
+            }
+            Self::Other => "Check these paths manually, or skip them",
+        }
+    }
+}
+
+/// An entry whose metadata could not be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessError {
+    /// Full path of the entry
+    pub path: PathBuf,
+
+    /// Classified cause of the last failure
+    pub cause: AccessErrorCause,
+
+    /// The last error message
+    pub message: String,
+}
+
+impl AccessError {
+    /// Records `err` as the failure for `path`.
+    pub fn new(path: PathBuf, err: &io::Error) -> Self {
+        Self {
+            path,
+            cause: AccessErrorCause::of(err),
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Runs `op`, retrying it while it fails with a transient error.
+///
+/// Gives up after [`TRANSIENT_ATTEMPTS`] attempts, backing off from
+/// [`RETRY_DELAY`]; other errors are returned immediately.
+pub fn retry_transient<T>(mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < TRANSIENT_ATTEMPTS && AccessErrorCause::of(&e).is_transient() => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn is_sharing_violation(err: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+    matches!(err.raw_os_error(), Some(32) | Some(33))
+}
+
+#[cfg(unix)]
+fn is_sharing_violation(err: &io::Error) -> bool {
+    // EAGAIN (mandatory locks), EBUSY, ETXTBSY
+    matches!(err.raw_os_error(), Some(11) | Some(16) | Some(26))
+}
+
+#[cfg(not(any(windows, unix)))]
+fn is_sharing_violation(_err: &io::Error) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn sharing_violation() -> io::Error {
+        io::Error::from_raw_os_error(16)
+    }
+
+    #[cfg(windows)]
+    fn sharing_violation() -> io::Error {
+        io::Error::from_raw_os_error(32)
+    }
+
+    #[test]
+    fn test_classify_errors() {
+        assert_eq!(
+            AccessErrorCause::of(&sharing_violation()),
+            AccessErrorCause::SharingViolation
+        );
+        assert_eq!(
+            AccessErrorCause::of(&io::Error::from(io::ErrorKind::PermissionDenied)),
+            AccessErrorCause::AccessDenied
+        );
+        assert_eq!(
+            AccessErrorCause::of(&io::Error::other("bad sector")),
+            AccessErrorCause::Other
+        );
+    }
+
+    #[test]
+    fn test_retry_transient_recovers() {
+        let mut calls = 0;
+        let result = retry_transient(|| {
+            calls += 1;
+            if calls < TRANSIENT_ATTEMPTS {
+                Err(sharing_violation())
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), TRANSIENT_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_retry_gives_up_and_skips_persistent_errors() {
+        let mut calls = 0;
+        let result: io::Result<()> = retry_transient(|| {
+            calls += 1;
+            Err(sharing_violation())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, TRANSIENT_ATTEMPTS);
+
+        let mut calls = 0;
+        let result: io::Result<()> = retry_transient(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
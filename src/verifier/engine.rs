@@ -1,7 +1,11 @@
 //! Verification engine for detecting filesystem drift.
 
-use crate::models::{CleanupAction, CleanupPlan};
+use crate::models::paths::find_on_disk;
+use crate::models::{timestamp, CleanupAction, CleanupPlan, PathKey};
+use crate::verifier::access::{retry_transient, AccessError, AccessErrorCause};
+use crate::verifier::permissions::MissingPermissions;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use walkdir::WalkDir;
@@ -15,6 +19,8 @@ pub struct VerificationConfig {
     pub check_size: bool,
     /// Fail fast on first drift
     pub fail_fast: bool,
+    /// Check that the current user has the permissions deletion takes
+    pub check_permissions: bool,
 }
 
 impl Default for VerificationConfig {
@@ -23,6 +29,7 @@ impl Default for VerificationConfig {
             check_mtime: true,
             check_size: true,
             fail_fast: false,
+            check_permissions: true,
         }
     }
 }
@@ -39,7 +46,16 @@ pub struct VerificationResult {
     pub verified: usize,
     pub drifted: Vec<DriftDetection>,
     pub missing: Vec<PathBuf>,
-    pub permission_errors: Vec<PathBuf>,
+    /// Entries whose metadata could not be read, with the classified cause
+    pub permission_errors: Vec<AccessError>,
+    /// Entries naming the same path as an earlier entry under the platform's
+    /// case rules (e.g. `Target` and `target` on Windows); checked only once
+    #[serde(default)]
+    pub duplicates: Vec<PathBuf>,
+    /// Entries the current user lacks permissions to delete, with the
+    /// permissions missing; warnings only, like permission errors
+    #[serde(default)]
+    pub missing_permissions: Vec<MissingPermissions>,
 }
 
 impl VerificationResult {
@@ -54,6 +70,15 @@ impl VerificationResult {
     pub fn is_safe_to_execute(&self) -> bool {
         !self.has_drift()
     }
+
+    /// Groups entries that could not be read by cause.
+    pub fn permission_errors_by_cause(&self) -> BTreeMap<AccessErrorCause, Vec<&AccessError>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for error in &self.permission_errors {
+            groups.entry(error.cause).or_default().push(error);
+        }
+        groups
+    }
 }
 
 /// Details of a detected drift.
@@ -86,7 +111,10 @@ impl VerificationEngine {
             drifted: Vec::new(),
             missing: Vec::new(),
             permission_errors: Vec::new(),
+            duplicates: Vec::new(),
+            missing_permissions: Vec::new(),
         };
+        let mut seen = HashSet::new();
 
         for entry in &plan.entries {
             // Skip entries marked as "keep" - we're not going to touch them
@@ -95,25 +123,33 @@ impl VerificationEngine {
                 continue;
             }
 
-            let full_path = plan.base_path.join(&entry.path);
-
-            // Check 1: Does file exist?
-            if !full_path.exists() {
-                result.missing.push(full_path.clone());
-                if self.config.fail_fast {
-                    return Ok(result);
-                }
+            let full_path = plan.entry_path(entry);
+            // Match on-disk names that differ only in Unicode normalization
+            let full_path = find_on_disk(&full_path).unwrap_or(full_path);
+            if !seen.insert(PathKey::new(&full_path)) {
+                result.duplicates.push(full_path);
                 continue;
             }
 
-            // Check 2: Can we read metadata?
-            let metadata = match std::fs::metadata(&full_path) {
+            // Check 1 and 2: Does the file exist, and can we read its metadata?
+            // Files briefly locked by another process are retried.
+            let metadata = match retry_transient(|| std::fs::metadata(&full_path)) {
                 Ok(m) => m,
-                Err(_) => {
-                    result.permission_errors.push(full_path.clone());
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    result.missing.push(full_path.clone());
+                    if self.config.fail_fast {
+                        return Ok(result);
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    result
+                        .permission_errors
+                        .push(AccessError::new(full_path, &e));
                     continue;
                 }
             };
+            self.check_permissions(&full_path, &mut result);
 
             // Check 3: Size verification
             if self.config.check_size {
@@ -141,9 +177,8 @@ impl VerificationEngine {
             // Check 4: Modification time verification
             if self.config.check_mtime {
                 let current_mtime = metadata.modified()?;
-                let expected_mtime = chrono::DateTime::parse_from_rfc3339(&entry.modified)
-                    .map_err(|e| VerificationError::InvalidTimestamp(e.to_string()))?
-                    .with_timezone(&chrono::Utc);
+                let expected_mtime = timestamp::parse(&entry.modified)
+                    .map_err(|e| VerificationError::InvalidTimestamp(e.to_string()))?;
 
                 let expected_systime: SystemTime = expected_mtime.into();
 
@@ -159,7 +194,7 @@ impl VerificationEngine {
                         path: full_path.clone(),
                         drift_type: DriftType::ModificationTimeMismatch,
                         expected: entry.modified.clone(),
-                        actual: chrono::DateTime::<chrono::Utc>::from(current_mtime).to_rfc3339(),
+                        actual: timestamp::format(current_mtime.into()),
                     });
                     if self.config.fail_fast {
                         return Ok(result);
@@ -175,6 +210,83 @@ impl VerificationEngine {
         Ok(result)
     }
 
+    /// Perform cheap per-entry checks without walking directories.
+    ///
+    /// Used when a recent verification record already covers the plan: every
+    /// non-Keep entry must still exist, and files must keep their recorded
+    /// size. Directory sizes and modification times are not rechecked.
+    pub fn quick_check(&self, plan: &CleanupPlan) -> VerificationResult {
+        let mut result = VerificationResult {
+            total_entries: plan.entries.len(),
+            verified: 0,
+            drifted: Vec::new(),
+            missing: Vec::new(),
+            permission_errors: Vec::new(),
+            duplicates: Vec::new(),
+            missing_permissions: Vec::new(),
+        };
+        let mut seen = HashSet::new();
+
+        for entry in &plan.entries {
+            if entry.action == CleanupAction::Keep {
+                result.verified += 1;
+                continue;
+            }
+
+            let full_path = plan.entry_path(entry);
+            // Match on-disk names that differ only in Unicode normalization
+            let full_path = find_on_disk(&full_path).unwrap_or(full_path);
+            if !seen.insert(PathKey::new(&full_path)) {
+                result.duplicates.push(full_path);
+                continue;
+            }
+            let metadata = match retry_transient(|| std::fs::symlink_metadata(&full_path)) {
+                Ok(m) => m,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    result.missing.push(full_path);
+                    if self.config.fail_fast {
+                        return result;
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    result
+                        .permission_errors
+                        .push(AccessError::new(full_path, &e));
+                    continue;
+                }
+            };
+            self.check_permissions(&full_path, &mut result);
+
+            if self.config.check_size && metadata.is_file() && metadata.len() != entry.size {
+                result.drifted.push(DriftDetection {
+                    path: full_path,
+                    drift_type: DriftType::SizeMismatch,
+                    expected: format!("{} bytes", entry.size),
+                    actual: format!("{} bytes", metadata.len()),
+                });
+                if self.config.fail_fast {
+                    return result;
+                }
+                continue;
+            }
+
+            result.verified += 1;
+        }
+
+        result
+    }
+
+    /// Records the permissions the current user lacks to delete `path`.
+    fn check_permissions(&self, path: &Path, result: &mut VerificationResult) {
+        if !self.config.check_permissions {
+            return;
+        }
+        if let Some(missing) = MissingPermissions::check(path) {
+            result.missing_permissions.push(missing);
+        }
+    }
+
     /// Calculate the total size of all files in a directory recursively.
     fn calculate_dir_size(&self, dir_path: &Path) -> Result<u64, VerificationError> {
         let mut total_size = 0u64;
@@ -207,7 +319,7 @@ pub enum VerificationError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::CleanupEntry;
+    use crate::models::{CleanupEntry, PathCase};
     use chrono::Utc;
     use std::fs;
     use tempfile::TempDir;
@@ -217,18 +329,26 @@ mod tests {
             version: "0.1.0".to_string(),
             created_at: Utc::now(),
             base_path: base_path.to_path_buf(),
+            roots: Vec::new(),
             entries,
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         }
     }
 
     fn create_cleanup_entry(path: &str, size: u64, action: CleanupAction) -> CleanupEntry {
         CleanupEntry {
             path: path.to_string(),
+            root: 0,
             size,
             modified: Utc::now().to_rfc3339(),
             action,
             rule_name: "test".to_string(),
             reason: "test reason".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         }
     }
 
@@ -241,12 +361,17 @@ mod tests {
         let metadata = fs::metadata(&file_path).unwrap();
         let entry = CleanupEntry {
             path: "test.txt".to_string(),
+            root: 0,
             size: metadata.len(),
             modified: chrono::DateTime::<chrono::Utc>::from(metadata.modified().unwrap())
                 .to_rfc3339(),
             action: CleanupAction::Delete,
             rule_name: "test".to_string(),
             reason: "test".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         };
 
         let plan = create_test_plan(temp.path(), vec![entry]);
@@ -282,12 +407,17 @@ mod tests {
         let metadata = fs::metadata(&file_path).unwrap();
         let entry = CleanupEntry {
             path: "modified.txt".to_string(),
+            root: 0,
             size: metadata.len(),
             modified: chrono::DateTime::<chrono::Utc>::from(metadata.modified().unwrap())
                 .to_rfc3339(),
             action: CleanupAction::Delete,
             rule_name: "test".to_string(),
             reason: "test".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         };
 
         let plan = create_test_plan(temp.path(), vec![entry]);
@@ -312,12 +442,17 @@ mod tests {
         let metadata = fs::metadata(&file_path).unwrap();
         let entry = CleanupEntry {
             path: "touched.txt".to_string(),
+            root: 0,
             size: metadata.len(),
             modified: chrono::DateTime::<chrono::Utc>::from(metadata.modified().unwrap())
                 .to_rfc3339(),
             action: CleanupAction::Delete,
             rule_name: "test".to_string(),
             reason: "test".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         };
 
         let plan = create_test_plan(temp.path(), vec![entry]);
@@ -349,6 +484,60 @@ mod tests {
         assert!(!result.has_drift());
     }
 
+    #[test]
+    fn test_duplicate_entries_reported() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("dup.txt"), "content").unwrap();
+
+        let mut entries = vec![
+            create_cleanup_entry("dup.txt", 7, CleanupAction::Delete),
+            create_cleanup_entry("./dup.txt", 7, CleanupAction::Delete),
+        ];
+        if PathCase::native() == PathCase::Insensitive {
+            entries.push(create_cleanup_entry("DUP.txt", 7, CleanupAction::Delete));
+        }
+        let duplicates = entries.len() - 1;
+        let plan = create_test_plan(temp.path(), entries);
+
+        let config = VerificationConfig {
+            check_mtime: false,
+            ..Default::default()
+        };
+        let verifier = VerificationEngine::new(config);
+        let result = verifier.verify(&plan).unwrap();
+
+        assert_eq!(result.verified, 1);
+        assert_eq!(result.duplicates.len(), duplicates);
+        assert!(result.is_safe_to_execute());
+
+        let quick = verifier.quick_check(&plan);
+        assert_eq!(quick.duplicates.len(), duplicates);
+    }
+
+    #[test]
+    fn test_verify_matches_decomposed_name_on_disk() {
+        let temp = TempDir::new().unwrap();
+        // Written by a tool that produces NFD names; the plan stores NFC
+        fs::write(temp.path().join("cafe\u{301}.txt"), "content").unwrap();
+
+        let entry = create_cleanup_entry("caf\u{e9}.txt", 7, CleanupAction::Delete);
+        let plan = create_test_plan(temp.path(), vec![entry]);
+
+        let config = VerificationConfig {
+            check_mtime: false,
+            ..Default::default()
+        };
+        let verifier = VerificationEngine::new(config);
+
+        let result = verifier.verify(&plan).unwrap();
+        assert!(result.missing.is_empty());
+        assert_eq!(result.verified, 1);
+
+        let quick = verifier.quick_check(&plan);
+        assert!(quick.missing.is_empty());
+        assert_eq!(quick.verified, 1);
+    }
+
     #[test]
     fn test_fail_fast_mode() {
         let temp = TempDir::new().unwrap();
@@ -383,12 +572,17 @@ mod tests {
         let metadata = fs::metadata(&dir_path).unwrap();
         let entry = CleanupEntry {
             path: "test_dir".to_string(),
+            root: 0,
             size: 300, // 100 + 200
             modified: chrono::DateTime::<chrono::Utc>::from(metadata.modified().unwrap())
                 .to_rfc3339(),
             action: CleanupAction::Delete,
             rule_name: "test".to_string(),
             reason: "test".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         };
 
         let plan = create_test_plan(temp.path(), vec![entry]);
@@ -409,12 +603,17 @@ mod tests {
         let metadata = fs::metadata(&file_path).unwrap();
         let entry = CleanupEntry {
             path: "test.txt".to_string(),
+            root: 0,
             size: metadata.len(),
             modified: chrono::DateTime::<chrono::Utc>::from(metadata.modified().unwrap())
                 .to_rfc3339(),
             action: CleanupAction::Delete,
             rule_name: "test".to_string(),
             reason: "test".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         };
 
         let plan = create_test_plan(temp.path(), vec![entry]);
@@ -436,4 +635,32 @@ mod tests {
         assert_eq!(result.verified, 1);
         assert!(!result.has_drift());
     }
+
+    #[test]
+    fn test_quick_check_detects_missing_and_resized_files() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("same.txt"), "content").unwrap();
+        fs::write(temp.path().join("grown.txt"), "content plus more").unwrap();
+        fs::create_dir(temp.path().join("target")).unwrap();
+
+        let plan = create_test_plan(
+            temp.path(),
+            vec![
+                create_cleanup_entry("same.txt", 7, CleanupAction::Delete),
+                create_cleanup_entry("grown.txt", 7, CleanupAction::Delete),
+                create_cleanup_entry("missing.txt", 7, CleanupAction::Delete),
+                // Directory sizes are not recomputed by the quick check
+                create_cleanup_entry("target", 12345, CleanupAction::Delete),
+                create_cleanup_entry("kept.txt", 7, CleanupAction::Keep),
+            ],
+        );
+
+        let verifier = VerificationEngine::new(VerificationConfig::default());
+        let result = verifier.quick_check(&plan);
+
+        assert_eq!(result.verified, 3);
+        assert_eq!(result.drifted.len(), 1);
+        assert_eq!(result.missing.len(), 1);
+        assert!(!result.is_safe_to_execute());
+    }
 }
@@ -4,11 +4,17 @@
 //! filesystem state, detecting any changes (drift) that have occurred since the
 //! plan was created.
 
+pub mod access;
 pub mod engine;
+pub mod permissions;
+pub mod record;
 pub mod report;
 
+pub use access::{AccessError, AccessErrorCause};
 pub use engine::{
     DriftDetection, DriftType, VerificationConfig, VerificationEngine, VerificationError,
     VerificationResult,
 };
+pub use permissions::{MissingPermissions, Permission};
+pub use record::{RecordError, VerificationRecord};
 pub use report::DriftReporter;
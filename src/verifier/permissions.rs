@@ -0,0 +1,406 @@
+//! Simulation of the permissions deleting an entry takes.
+//!
+//! An entry the current user can't delete otherwise only fails at execution
+//! time, with a bare "permission denied". Verification works out beforehand
+//! which permission is missing, so the report can show a remediation matrix
+//! of entries against permissions:
+//!
+//! - **write on the parent**: removing a name from a directory changes the
+//!   directory, so it takes write and search on it on Linux, or "Delete
+//!   subfolders and files" on Windows;
+//! - **delete on the item**: on Windows the Delete right on the item itself
+//!   (either this or the parent's right is enough), with the read-only
+//!   attribute cleared; on Linux, for directories, read, write and search on
+//!   the directory, which has to be emptied first;
+//! - **ownership**: on Linux, entries in a sticky directory such as `/tmp`
+//!   can only be deleted by their owner or the directory's; on Windows, an
+//!   item whose ACL the user can't change has to be taken ownership of
+//!   before rights can be granted on it.
+//!
+//! On Windows the ACLs are evaluated by Windows itself, by opening the item
+//! with the rights deletion needs without deleting anything, so group
+//! memberships, privileges and `execute --run-as` impersonation all count.
+//! On Linux the mode bits and owners are compared with the effective user
+//! and groups from `/proc/self/status`; root is never short of permissions,
+//! ACLs and immutable attributes aren't considered, and only the top level
+//! of a directory is checked. Where the user can't be determined, nothing
+//! is reported.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A permission deleting an entry takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Removing names from the containing directory
+    WriteParent,
+
+    /// Deleting the item itself, or emptying it if it is a directory
+    DeleteItem,
+
+    /// Owning the item or, on Linux, its sticky parent
+    Ownership,
+}
+
+impl Permission {
+    /// Every permission, in the column order of the remediation matrix.
+    pub const ALL: [Permission; 3] = [Self::WriteParent, Self::DeleteItem, Self::Ownership];
+
+    /// Human-readable name of this permission.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::WriteParent => "Write parent",
+            Self::DeleteItem => "Delete item",
+            Self::Ownership => "Ownership",
+        }
+    }
+
+    /// How to grant this permission on this platform.
+    pub fn remediation(self) -> &'static str {
+        platform::remediation(self)
+    }
+}
+
+/// An entry the current user lacks permissions to delete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingPermissions {
+    /// Full path of the entry
+    pub path: PathBuf,
+
+    /// The missing permissions, in [`Permission::ALL`] order
+    pub missing: Vec<Permission>,
+}
+
+impl MissingPermissions {
+    /// Checks `path`, returning `None` when deleting it should succeed or
+    /// can't be simulated.
+    pub fn check(path: &Path) -> Option<Self> {
+        let mut missing = platform::missing(path);
+        if missing.is_empty() {
+            return None;
+        }
+        missing.sort();
+        missing.dedup();
+        Some(Self {
+            path: path.to_path_buf(),
+            missing,
+        })
+    }
+
+    /// Returns true if `permission` is missing.
+    pub fn lacks(&self, permission: Permission) -> bool {
+        self.missing.contains(&permission)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::Permission;
+    use std::fs;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_ACCESS_DENIED, INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{
+        CreateFileW, DELETE, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+        FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING, WRITE_DAC,
+    };
+
+    /// Returns false if opening `path` with `access` is denied; other
+    /// failures, such as sharing violations, say nothing about rights.
+    fn granted(path: &Path, access: u32) -> bool {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        // SAFETY: the path is NUL-terminated; the handle is closed below
+        let handle = unsafe {
+            CreateFileW(
+                wide.as_ptr(),
+                access,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return unsafe { GetLastError() } != ERROR_ACCESS_DENIED;
+        }
+        unsafe { CloseHandle(handle) };
+        true
+    }
+
+    pub fn missing(path: &Path) -> Vec<Permission> {
+        let mut missing = Vec::new();
+        // Opening for Delete succeeds if the item grants it or the parent
+        // grants Delete subfolders and files
+        if !granted(path, DELETE) {
+            missing.extend([Permission::WriteParent, Permission::DeleteItem]);
+            if !granted(path, WRITE_DAC) {
+                missing.push(Permission::Ownership);
+            }
+        }
+        let readonly =
+            fs::symlink_metadata(path).is_ok_and(|m| m.is_file() && m.permissions().readonly());
+        if readonly {
+            missing.push(Permission::DeleteItem);
+        }
+        missing
+    }
+
+    pub fn remediation(permission: Permission) -> &'static str {
+        match permission {
+            Permission::WriteParent => {
+                "Grant Delete subfolders and files on the parent folder \
+                 (icacls <parent> /grant %USERNAME%:(DC)), or run elevated"
+            }
+            Permission::DeleteItem => {
+                "Grant Delete on the item (icacls <item> /grant %USERNAME%:(D)) and clear its \
+                 read-only attribute (attrib -r <item>)"
+            }
+            Permission::Ownership => {
+                "Take ownership of the item (takeown /f <item>) before granting rights on it"
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use super::Permission;
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+    use std::sync::OnceLock;
+
+    /// Sticky bit: only owners may delete entries in the directory
+    const STICKY: u32 = 0o1000;
+    const READ: u32 = 0o4;
+    const WRITE: u32 = 0o2;
+    const SEARCH: u32 = 0o1;
+
+    /// The effective user and groups permissions are checked for.
+    #[derive(Debug, PartialEq)]
+    pub struct Identity {
+        pub uid: u32,
+        pub gids: Vec<u32>,
+    }
+
+    impl Identity {
+        /// Parses the `Uid`, `Gid` and `Groups` lines of `/proc/self/status`.
+        pub fn parse_status(text: &str) -> Option<Self> {
+            let field = |name: &str| {
+                text.lines()
+                    .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                    .map(|rest| rest.split_whitespace().filter_map(|id| id.parse().ok()))
+            };
+            // Real, effective, saved and filesystem ids
+            let uid = field("Uid")?.nth(1)?;
+            let mut gids = vec![field("Gid")?.nth(1)?];
+            gids.extend(field("Groups").into_iter().flatten());
+            Some(Self { uid, gids })
+        }
+
+        fn current() -> Option<&'static Self> {
+            static CURRENT: OnceLock<Option<Identity>> = OnceLock::new();
+            CURRENT
+                .get_or_init(|| {
+                    let status = fs::read_to_string("/proc/self/status").ok()?;
+                    Self::parse_status(&status)
+                })
+                .as_ref()
+        }
+
+        /// Returns true if `mode`, owned by `uid` and `gid`, grants all of
+        /// `bits` to this identity.
+        fn allows(&self, mode: u32, uid: u32, gid: u32, bits: u32) -> bool {
+            let class = if self.uid == 0 {
+                return true;
+            } else if self.uid == uid {
+                mode >> 6
+            } else if self.gids.contains(&gid) {
+                mode >> 3
+            } else {
+                mode
+            };
+            class & bits == bits
+        }
+    }
+
+    /// Owner and mode of a filesystem entry.
+    pub struct Node {
+        pub mode: u32,
+        pub uid: u32,
+        pub gid: u32,
+    }
+
+    impl From<&fs::Metadata> for Node {
+        fn from(metadata: &fs::Metadata) -> Self {
+            Self {
+                mode: metadata.mode(),
+                uid: metadata.uid(),
+                gid: metadata.gid(),
+            }
+        }
+    }
+
+    /// Works out what `identity` lacks to delete `item` from `parent`.
+    pub fn evaluate(
+        identity: &Identity,
+        item: &Node,
+        is_dir: bool,
+        parent: &Node,
+    ) -> Vec<Permission> {
+        let mut missing = Vec::new();
+        if !identity.allows(parent.mode, parent.uid, parent.gid, WRITE | SEARCH) {
+            missing.push(Permission::WriteParent);
+        }
+        if is_dir && !identity.allows(item.mode, item.uid, item.gid, READ | WRITE | SEARCH) {
+            missing.push(Permission::DeleteItem);
+        }
+        let owner = identity.uid == 0 || identity.uid == item.uid || identity.uid == parent.uid;
+        if parent.mode & STICKY != 0 && !owner {
+            missing.push(Permission::Ownership);
+        }
+        missing
+    }
+
+    pub fn missing(path: &Path) -> Vec<Permission> {
+        let Some(identity) = Identity::current() else {
+            return Vec::new();
+        };
+        let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+        let (Ok(item), Some(Ok(parent))) = (fs::symlink_metadata(path), parent.map(fs::metadata))
+        else {
+            return Vec::new();
+        };
+        evaluate(identity, &(&item).into(), item.is_dir(), &(&parent).into())
+    }
+
+    pub fn remediation(permission: Permission) -> &'static str {
+        match permission {
+            Permission::WriteParent => {
+                "Grant write and search on the parent directory (chmod u+wx <parent>), or run \
+                 as root"
+            }
+            Permission::DeleteItem => {
+                "Grant read, write and search on the directory so it can be emptied \
+                 (chmod -R u+rwx <item>), or run as root"
+            }
+            Permission::Ownership => {
+                "The parent directory is sticky: run as the entry's owner, the directory's \
+                 owner or root"
+            }
+        }
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+mod platform {
+    use super::Permission;
+    use std::path::Path;
+
+    pub fn missing(_path: &Path) -> Vec<Permission> {
+        Vec::new()
+    }
+
+    pub fn remediation(_permission: Permission) -> &'static str {
+        "Run megamaid with rights to delete the item"
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::platform::{evaluate, Identity, Node};
+    use super::*;
+
+    fn node(mode: u32, uid: u32) -> Node {
+        Node {
+            mode,
+            uid,
+            gid: 100,
+        }
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let status = "Name:\tmegamaid\nUid:\t1000\t1001\t1000\t1000\n\
+                      Gid:\t100\t101\t100\t100\nGroups:\t4 27 \n";
+        assert_eq!(
+            Identity::parse_status(status),
+            Some(Identity {
+                uid: 1001,
+                gids: vec![101, 4, 27],
+            })
+        );
+        assert_eq!(Identity::parse_status("Name:\tmegamaid\n"), None);
+    }
+
+    #[test]
+    fn test_evaluate_mode_and_ownership() {
+        let user = Identity {
+            uid: 1000,
+            gids: vec![100],
+        };
+        let other = Identity {
+            uid: 2000,
+            gids: vec![200],
+        };
+        let root = Identity {
+            uid: 0,
+            gids: vec![0],
+        };
+
+        // Group-writable parent, private directory
+        let parent = node(0o775, 1000);
+        let dir = node(0o700, 1000);
+        assert!(evaluate(&user, &dir, true, &parent).is_empty());
+        assert_eq!(
+            evaluate(&other, &dir, true, &parent),
+            vec![Permission::WriteParent, Permission::DeleteItem]
+        );
+        // A file's own mode doesn't matter
+        assert_eq!(
+            evaluate(&other, &node(0o600, 1000), false, &parent),
+            vec![Permission::WriteParent]
+        );
+
+        // World-writable sticky directory such as /tmp
+        let tmp = node(0o1777, 0);
+        assert!(evaluate(&user, &node(0o644, 1000), false, &tmp).is_empty());
+        assert_eq!(
+            evaluate(&other, &node(0o644, 1000), false, &tmp),
+            vec![Permission::Ownership]
+        );
+        assert!(evaluate(&root, &dir, true, &node(0o1555, 1000)).is_empty());
+    }
+
+    #[test]
+    fn test_check_read_only_parent() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let locked = temp.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        let file = locked.join("file.txt");
+        fs::write(&file, "data").unwrap();
+        assert_eq!(MissingPermissions::check(&file), None);
+
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        let check = MissingPermissions::check(&file);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Root can delete regardless
+        let is_root = fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| Identity::parse_status(&status))
+            .is_none_or(|identity| identity.uid == 0);
+        if !is_root {
+            let check = check.unwrap();
+            assert!(check.lacks(Permission::WriteParent));
+            assert!(!check.lacks(Permission::Ownership));
+        }
+    }
+}
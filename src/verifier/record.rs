@@ -0,0 +1,220 @@
+//! Persisted verification results.
+//!
+//! `verify` writes a [`VerificationRecord`] next to the plan so a later
+//! `execute` can skip a full re-verification of very large plans. The record
+//! is bound to the exact plan contents by a SHA-256 hash of the plan file.
+
+use crate::verifier::VerificationResult;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Machine-readable summary of a verification run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationRecord {
+    /// Hex-encoded SHA-256 hash of the verified plan file
+    pub plan_hash: String,
+
+    /// When verification finished
+    #[serde(with = "crate::models::timestamp::tolerant")]
+    pub verified_at: DateTime<Utc>,
+
+    /// Whether the plan was safe to execute
+    pub passed: bool,
+
+    /// Total entries in the plan
+    pub total_entries: usize,
+
+    /// Entries that passed all checks
+    pub verified: usize,
+
+    /// Entries whose size or mtime changed
+    pub drifted: usize,
+
+    /// Entries that no longer exist
+    pub missing: usize,
+
+    /// Entries whose metadata could not be read
+    pub permission_errors: usize,
+}
+
+/// Reasons a verification record cannot be used.
+#[derive(Debug, Error)]
+pub enum RecordError {
+    /// I/O error while reading the record or hashing the plan
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The record file is malformed
+    #[error("Invalid verification record: {0}")]
+    Parse(#[from] serde_yaml::Error),
+
+    /// The plan changed after it was verified
+    #[error("Plan has changed since verification (hash mismatch)")]
+    HashMismatch,
+
+    /// The record is older than the allowed age
+    #[error("Verification record is stale (verified at {0})")]
+    Stale(DateTime<Utc>),
+
+    /// The recorded verification did not pass
+    #[error("Recorded verification did not pass")]
+    Failed,
+}
+
+impl VerificationRecord {
+    /// Builds a record for a verification result of the plan at `plan_path`.
+    pub fn new(plan_path: &Path, result: &VerificationResult) -> io::Result<Self> {
+        Ok(Self {
+            plan_hash: hash_plan_file(plan_path)?,
+            verified_at: Utc::now(),
+            passed: result.is_safe_to_execute(),
+            total_entries: result.total_entries,
+            verified: result.verified,
+            drifted: result.drifted.len(),
+            missing: result.missing.len(),
+            permission_errors: result.permission_errors.len(),
+        })
+    }
+
+    /// Returns the default record location for a plan
+    /// (e.g. `cleanup-plan.yaml` → `cleanup-plan.yaml.verification.yaml`).
+    pub fn default_path(plan_path: &Path) -> PathBuf {
+        let mut name = plan_path.as_os_str().to_os_string();
+        name.push(".verification.yaml");
+        PathBuf::from(name)
+    }
+
+    /// Writes the record as YAML.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let yaml = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, yaml)
+    }
+
+    /// Reads a record from a YAML file.
+    pub fn read(path: &Path) -> Result<Self, RecordError> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&content)?)
+    }
+
+    /// Checks that the record covers the plan at `plan_path` as it is now.
+    ///
+    /// The record must have passed, be no older than `max_age`, and carry the
+    /// hash of the current plan file.
+    pub fn validate_for(&self, plan_path: &Path, max_age: Duration) -> Result<(), RecordError> {
+        if !self.passed {
+            return Err(RecordError::Failed);
+        }
+        if Utc::now() - self.verified_at > max_age {
+            return Err(RecordError::Stale(self.verified_at));
+        }
+        if hash_plan_file(plan_path)? != self.plan_hash {
+            return Err(RecordError::HashMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Computes the hex-encoded SHA-256 hash of a plan file's raw bytes.
+pub fn hash_plan_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn passing_result() -> VerificationResult {
+        VerificationResult {
+            total_entries: 2,
+            verified: 2,
+            drifted: Vec::new(),
+            missing: Vec::new(),
+            permission_errors: Vec::new(),
+            duplicates: Vec::new(),
+            missing_permissions: Vec::new(),
+        }
+    }
+
+    fn write_plan(dir: &Path) -> PathBuf {
+        let plan_path = dir.join("plan.yaml");
+        fs::write(&plan_path, "version: 0.1.0\n").unwrap();
+        plan_path
+    }
+
+    #[test]
+    fn test_default_path() {
+        assert_eq!(
+            VerificationRecord::default_path(Path::new("out/plan.yaml")),
+            PathBuf::from("out/plan.yaml.verification.yaml")
+        );
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let plan_path = write_plan(temp.path());
+        let record = VerificationRecord::new(&plan_path, &passing_result()).unwrap();
+
+        let record_path = VerificationRecord::default_path(&plan_path);
+        record.write(&record_path).unwrap();
+
+        let loaded = VerificationRecord::read(&record_path).unwrap();
+        assert_eq!(loaded, record);
+        assert_eq!(loaded.plan_hash.len(), 64);
+    }
+
+    #[test]
+    fn test_validate_for_accepts_fresh_matching_record() {
+        let temp = TempDir::new().unwrap();
+        let plan_path = write_plan(temp.path());
+        let record = VerificationRecord::new(&plan_path, &passing_result()).unwrap();
+
+        assert!(record
+            .validate_for(&plan_path, Duration::minutes(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_for_rejects_modified_plan() {
+        let temp = TempDir::new().unwrap();
+        let plan_path = write_plan(temp.path());
+        let record = VerificationRecord::new(&plan_path, &passing_result()).unwrap();
+
+        fs::write(&plan_path, "version: 0.2.0\n").unwrap();
+
+        let result = record.validate_for(&plan_path, Duration::minutes(60));
+        assert!(matches!(result, Err(RecordError::HashMismatch)));
+    }
+
+    #[test]
+    fn test_validate_for_rejects_stale_record() {
+        let temp = TempDir::new().unwrap();
+        let plan_path = write_plan(temp.path());
+        let mut record = VerificationRecord::new(&plan_path, &passing_result()).unwrap();
+        record.verified_at = Utc::now() - Duration::hours(2);
+
+        let result = record.validate_for(&plan_path, Duration::minutes(60));
+        assert!(matches!(result, Err(RecordError::Stale(_))));
+    }
+
+    #[test]
+    fn test_validate_for_rejects_failed_verification() {
+        let temp = TempDir::new().unwrap();
+        let plan_path = write_plan(temp.path());
+        let mut result = passing_result();
+        result.missing.push(PathBuf::from("gone"));
+        let record = VerificationRecord::new(&plan_path, &result).unwrap();
+
+        assert!(!record.passed);
+        let result = record.validate_for(&plan_path, Duration::minutes(60));
+        assert!(matches!(result, Err(RecordError::Failed)));
+    }
+}
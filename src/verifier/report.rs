@@ -1,6 +1,7 @@
 //! Drift report generation for human-readable output.
 
 use crate::verifier::engine::{DriftType, VerificationResult};
+use crate::verifier::permissions::Permission;
 use std::io::Write;
 use std::path::Path;
 
@@ -20,9 +21,16 @@ impl DriftReporter {
         report.push_str(&format!("Drifted: {}\n", result.drifted.len()));
         report.push_str(&format!("Missing: {}\n", result.missing.len()));
         report.push_str(&format!(
-            "Permission errors: {}\n\n",
+            "Permission errors: {}\n",
             result.permission_errors.len()
         ));
+        if !result.missing_permissions.is_empty() {
+            report.push_str(&format!(
+                "Missing permissions: {}\n",
+                result.missing_permissions.len()
+            ));
+        }
+        report.push('\n');
 
         if result.is_safe_to_execute() {
             report.push_str("✅ SAFE TO EXECUTE\n\n");
@@ -56,13 +64,32 @@ impl DriftReporter {
             }
         }
 
-        // Permission errors (warnings)
+        // Permission errors (warnings), grouped by what can be done about them
         if !result.permission_errors.is_empty() {
             report.push_str("## Permission Warnings\n\n");
             report
                 .push_str("The following files could not be verified due to permission errors.\n");
             report.push_str("These are warnings only and will not block execution:\n\n");
-            for path in &result.permission_errors {
+            for (cause, errors) in result.permission_errors_by_cause() {
+                report.push_str(&format!("### {} ({})\n", cause.label(), errors.len()));
+                report.push_str(&format!("Recommendation: {}\n\n", cause.recommendation()));
+                for error in errors {
+                    report.push_str(&format!("- {}: {}\n", error.path.display(), error.message));
+                }
+                report.push('\n');
+            }
+        }
+
+        report.push_str(&Self::permission_matrix(result));
+
+        // Duplicate entries (warnings)
+        if !result.duplicates.is_empty() {
+            report.push_str("## Duplicate Entries\n\n");
+            report.push_str(
+                "The following entries name the same path as an earlier entry on this platform\n",
+            );
+            report.push_str("(e.g. differing only in case) and will only be processed once:\n\n");
+            for path in &result.duplicates {
                 report.push_str(&format!("- {}\n", path.display()));
             }
             report.push('\n');
@@ -83,6 +110,53 @@ impl DriftReporter {
         report
     }
 
+    /// Generate the remediation matrix of entries the current user lacks
+    /// permissions to delete; empty if there are none.
+    pub fn permission_matrix(result: &VerificationResult) -> String {
+        let mut report = String::new();
+        if result.missing_permissions.is_empty() {
+            return report;
+        }
+
+        report.push_str("## Missing Permissions\n\n");
+        report.push_str("Deleting the following entries will fail with the current rights.\n");
+        report.push_str("Grant the permissions marked ✗ before executing:\n\n");
+        let labels: Vec<&str> = Permission::ALL.iter().map(|p| p.label()).collect();
+        report.push_str(&format!("| {} | Path |\n", labels.join(" | ")));
+        report.push_str(&format!("|{}------|\n", "---|".repeat(labels.len())));
+        for entry in &result.missing_permissions {
+            let marks: Vec<&str> = Permission::ALL
+                .iter()
+                .map(|&p| if entry.lacks(p) { "✗" } else { " " })
+                .collect();
+            report.push_str(&format!(
+                "| {} | {} |\n",
+                marks.join(" | "),
+                entry.path.display()
+            ));
+        }
+        report.push('\n');
+
+        report.push_str("Remediation:\n\n");
+        for permission in Permission::ALL {
+            let count = result
+                .missing_permissions
+                .iter()
+                .filter(|entry| entry.lacks(permission))
+                .count();
+            if count > 0 {
+                report.push_str(&format!(
+                    "- {} ({}): {}\n",
+                    permission.label(),
+                    count,
+                    permission.remediation()
+                ));
+            }
+        }
+        report.push('\n');
+        report
+    }
+
     /// Write a drift report to a file.
     pub fn write_report(result: &VerificationResult, path: &Path) -> std::io::Result<()> {
         let report = Self::generate_report(result);
@@ -96,7 +170,9 @@ impl DriftReporter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::verifier::access::{AccessError, AccessErrorCause};
     use crate::verifier::engine::{DriftDetection, DriftType};
+    use crate::verifier::permissions::MissingPermissions;
     use std::path::PathBuf;
     use tempfile::TempDir;
 
@@ -107,6 +183,8 @@ mod tests {
             drifted: Vec::new(),
             missing: Vec::new(),
             permission_errors: Vec::new(),
+            duplicates: Vec::new(),
+            missing_permissions: Vec::new(),
         }
     }
 
@@ -129,7 +207,23 @@ mod tests {
                 },
             ],
             missing: vec![PathBuf::from("/test/missing.txt")],
-            permission_errors: vec![PathBuf::from("/test/locked.txt")],
+            permission_errors: vec![
+                AccessError {
+                    path: PathBuf::from("/test/locked.txt"),
+                    cause: AccessErrorCause::SharingViolation,
+                    message: "file in use".to_string(),
+                },
+                AccessError {
+                    path: PathBuf::from("/test/secret.txt"),
+                    cause: AccessErrorCause::AccessDenied,
+                    message: "permission denied".to_string(),
+                },
+            ],
+            duplicates: Vec::new(),
+            missing_permissions: vec![MissingPermissions {
+                path: PathBuf::from("/test/shared/build"),
+                missing: vec![Permission::WriteParent, Permission::Ownership],
+            }],
         }
     }
 
@@ -176,6 +270,37 @@ mod tests {
         assert!(report.contains("warnings only"));
     }
 
+    #[test]
+    fn test_permission_warnings_grouped_by_cause() {
+        let result = create_drifted_result();
+        let report = DriftReporter::generate_report(&result);
+
+        let locked = report.find("### Locked by another process (1)").unwrap();
+        let denied = report.find("### Access denied (1)").unwrap();
+        assert!(locked < denied);
+        assert!(report.contains("Run megamaid elevated"));
+        assert!(report.contains("secret.txt: permission denied"));
+    }
+
+    #[test]
+    fn test_report_includes_permission_matrix() {
+        let result = create_drifted_result();
+        let report = DriftReporter::generate_report(&result);
+
+        assert!(report.contains("Missing permissions: 1"));
+        assert!(report.contains("| Write parent | Delete item | Ownership | Path |"));
+        assert!(report.contains("| ✗ |   | ✗ | /test/shared/build |"));
+        assert!(report.contains(&format!(
+            "- Ownership (1): {}",
+            Permission::Ownership.remediation()
+        )));
+        assert!(!report.contains("- Delete item"));
+
+        let clean = create_clean_result();
+        assert!(DriftReporter::permission_matrix(&clean).is_empty());
+        assert!(!DriftReporter::generate_report(&clean).contains("Missing permissions"));
+    }
+
     #[test]
     fn test_report_includes_recommendations() {
         let result = create_drifted_result();
@@ -5,6 +5,7 @@
 use megamaid::config::{load_config, load_default_config, validate_config, MegamaidConfig};
 use megamaid::detector::{DetectionEngine, ScanContext};
 use megamaid::executor::{ExecutionEngine, ExecutionMode};
+use megamaid::models::ByteSize;
 use megamaid::planner::PlanGenerator;
 use megamaid::scanner::{FileScanner, ScanConfig};
 use std::fs;
@@ -62,7 +63,10 @@ verifier:
     assert_eq!(config.scanner.max_depth, Some(10));
     assert!(!config.scanner.skip_hidden);
     assert_eq!(config.scanner.thread_count, 4);
-    assert_eq!(config.detector.rules.size_threshold.threshold_mb, 50);
+    assert_eq!(
+        config.detector.rules.size_threshold.threshold_mb,
+        ByteSize::from_mb(50)
+    );
     assert!(config.executor.parallel);
     assert_eq!(config.executor.batch_size, 50);
 }
@@ -119,6 +123,14 @@ detector:
         follow_links: config.scanner.follow_symlinks,
         max_depth: Some(5), // CLI override
         skip_hidden: true,  // CLI override
+        include_streams: config.scanner.include_streams,
+        remote: config.scanner.remote,
+        exclude_globs: config.scanner.exclude.clone(),
+        respect_ignore_files: config.scanner.respect_ignore_files,
+        same_filesystem: config.scanner.same_filesystem,
+        collect_owner: config.scanner.collect_owner,
+        largest_first: config.scanner.largest_first,
+        max_iops: config.scanner.max_iops,
     };
 
     // Verify overrides took effect
@@ -244,7 +256,10 @@ scanner:
 
     // Other values should use defaults
     assert_eq!(config.executor.batch_size, 100); // default
-    assert_eq!(config.detector.rules.size_threshold.threshold_mb, 100); // default
+    assert_eq!(
+        config.detector.rules.size_threshold.threshold_mb,
+        ByteSize::from_mb(100)
+    ); // default
 }
 
 #[test]
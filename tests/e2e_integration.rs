@@ -4,7 +4,7 @@
 
 use megamaid::detector::engine::{DetectionEngine, ScanContext};
 use megamaid::detector::rules::SizeThresholdRule;
-use megamaid::executor::{ExecutionConfig, ExecutionEngine, ExecutionMode};
+use megamaid::executor::{ExecutionConfig, ExecutionEngine, ExecutionMode, VolumeConcurrency};
 use megamaid::planner::generator::PlanGenerator;
 use megamaid::planner::writer::PlanWriter;
 use megamaid::scanner::traversal::{FileScanner, ScanConfig};
@@ -40,6 +40,14 @@ fn test_complete_workflow_scan_to_execution() {
         follow_links: false,
         max_depth: None,
         skip_hidden: true,
+        include_streams: false,
+        remote: Default::default(),
+        exclude_globs: Vec::new(),
+        respect_ignore_files: false,
+        same_filesystem: false,
+        collect_owner: false,
+        largest_first: false,
+        max_iops: 0,
     });
     let entries = scanner.scan(temp.path()).unwrap();
 
@@ -48,9 +56,7 @@ fn test_complete_workflow_scan_to_execution() {
     // Step 2: Detect
     let mut engine = DetectionEngine::empty();
     engine.add_rule(Box::new(megamaid::detector::BuildArtifactRule::default()));
-    engine.add_rule(Box::new(SizeThresholdRule {
-        threshold_bytes: 100 * 1_048_576, // 100 MB
-    }));
+    engine.add_rule(Box::new(SizeThresholdRule::new(100 * 1_048_576))); // 100 MB
 
     let context = ScanContext::default();
     let detections = engine.analyze(&entries, &context);
@@ -94,6 +100,8 @@ fn test_complete_workflow_scan_to_execution() {
         use_recycle_bin: false,
         parallel: false,
         batch_size: 100,
+        operation_timeout: None,
+        volume_concurrency: VolumeConcurrency::default(),
     });
 
     let result = executor.execute(&plan).unwrap();
@@ -113,6 +121,8 @@ fn test_complete_workflow_scan_to_execution() {
         use_recycle_bin: false,
         parallel: false,
         batch_size: 100,
+        operation_timeout: None,
+        volume_concurrency: VolumeConcurrency::default(),
     });
 
     let result = executor.execute(&plan).unwrap();
@@ -128,6 +138,7 @@ fn test_complete_workflow_scan_to_execution() {
     );
 }
 
+#[cfg(feature = "parallel")]
 #[test]
 fn test_workflow_with_parallel_execution() {
     let temp = TempDir::new().unwrap();
@@ -147,9 +158,7 @@ fn test_workflow_with_parallel_execution() {
 
     // Detect (all files > 0.5 MB)
     let mut engine = DetectionEngine::empty();
-    engine.add_rule(Box::new(SizeThresholdRule {
-        threshold_bytes: 500_000,
-    }));
+    engine.add_rule(Box::new(SizeThresholdRule::new(500_000)));
 
     let detections = engine.analyze(&entries, &ScanContext::default());
 
@@ -191,6 +200,8 @@ fn test_workflow_with_parallel_execution() {
         use_recycle_bin: false,
         parallel: true,
         batch_size: 25,
+        operation_timeout: None,
+        volume_concurrency: VolumeConcurrency::default(),
     });
 
     let result = executor.execute(&plan).unwrap();
@@ -243,6 +254,8 @@ fn test_workflow_with_backup_mode() {
         use_recycle_bin: false,
         parallel: false,
         batch_size: 100,
+        operation_timeout: None,
+        volume_concurrency: VolumeConcurrency::default(),
     });
 
     let result = executor.execute(&plan).unwrap();
@@ -273,9 +286,7 @@ fn test_workflow_with_drift_detection() {
     let entries = scanner.scan(temp.path()).unwrap();
 
     let mut engine = DetectionEngine::empty();
-    engine.add_rule(Box::new(SizeThresholdRule {
-        threshold_bytes: 500_000,
-    }));
+    engine.add_rule(Box::new(SizeThresholdRule::new(500_000)));
 
     let detections = engine.analyze(&entries, &ScanContext::default());
     let generator = PlanGenerator::new(temp.path().to_path_buf());
@@ -310,7 +321,7 @@ fn test_workflow_resilience_to_missing_files() {
     let entries = scanner.scan(temp.path()).unwrap();
 
     let mut engine = DetectionEngine::empty();
-    engine.add_rule(Box::new(SizeThresholdRule { threshold_bytes: 1 }));
+    engine.add_rule(Box::new(SizeThresholdRule::new(1)));
 
     let detections = engine.analyze(&entries, &ScanContext::default());
 
@@ -345,6 +356,8 @@ fn test_workflow_resilience_to_missing_files() {
         use_recycle_bin: false,
         parallel: false,
         batch_size: 100,
+        operation_timeout: None,
+        volume_concurrency: VolumeConcurrency::default(),
     });
 
     let result = executor.execute(&plan).unwrap();
@@ -3,6 +3,8 @@
 //! These tests verify end-to-end execution workflows including parallel deletion,
 //! combining scanning, detection, planning, and execution.
 
+#![cfg(feature = "parallel")]
+
 use chrono::Utc;
 use megamaid::executor::{ExecutionConfig, ExecutionEngine, ExecutionMode};
 use megamaid::models::{CleanupAction, CleanupEntry, CleanupPlan};
@@ -16,11 +18,16 @@ fn create_plan_from_files(base_path: &std::path::Path, files: Vec<&str>, size: u
         .into_iter()
         .map(|path| CleanupEntry {
             path: path.to_string(),
+            root: 0,
             size,
             modified: Utc::now().to_rfc3339(),
             action: CleanupAction::Delete,
             rule_name: "test".to_string(),
             reason: "test file".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         })
         .collect();
 
@@ -28,7 +35,10 @@ fn create_plan_from_files(base_path: &std::path::Path, files: Vec<&str>, size: u
         version: "0.1.0".to_string(),
         created_at: Utc::now(),
         base_path: base_path.to_path_buf(),
+        roots: Vec::new(),
         entries,
+        detection_stats: Vec::new(),
+        protections: Vec::new(),
     }
 }
 
@@ -53,6 +63,7 @@ fn test_end_to_end_parallel_execution() {
         mode: ExecutionMode::Batch,
         parallel: true,
         batch_size: 25,
+        operation_timeout: None,
         ..Default::default()
     };
 
@@ -108,6 +119,7 @@ fn test_parallel_vs_sequential_performance() {
         mode: ExecutionMode::Batch,
         parallel: true,
         batch_size: 50,
+        operation_timeout: None,
         ..Default::default()
     };
 
@@ -161,6 +173,7 @@ fn test_parallel_execution_with_nested_directories() {
         mode: ExecutionMode::Batch,
         parallel: true,
         batch_size: 20,
+        operation_timeout: None,
         ..Default::default()
     };
 
@@ -199,6 +212,7 @@ fn test_parallel_execution_with_errors() {
         parallel: true,
         fail_fast: false, // Continue despite errors
         batch_size: 10,
+        operation_timeout: None,
         ..Default::default()
     };
 
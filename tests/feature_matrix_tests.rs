@@ -131,9 +131,7 @@ fn test_max_depth_with_size_threshold() {
     let entries = scanner.scan(temp.path()).unwrap();
 
     let mut engine = DetectionEngine::empty();
-    engine.add_rule(Box::new(SizeThresholdRule {
-        threshold_bytes: 10_000_000, // 10MB
-    }));
+    engine.add_rule(Box::new(SizeThresholdRule::new(10_000_000))); // 10MB
 
     let detections = engine.analyze(&entries, &ScanContext::default());
 
@@ -278,9 +276,7 @@ fn test_custom_size_threshold_with_plan_filtering() {
 
     // Detect with custom 100MB threshold
     let mut engine = DetectionEngine::empty();
-    engine.add_rule(Box::new(SizeThresholdRule {
-        threshold_bytes: 100 * 1_048_576, // 100MB
-    }));
+    engine.add_rule(Box::new(SizeThresholdRule::new(100 * 1_048_576))); // 100MB
 
     let detections = engine.analyze(&entries, &ScanContext::default());
 
@@ -57,7 +57,14 @@ fn create_mock_entries(count: usize) -> Vec<FileEntry> {
             size: 1024,
             modified: SystemTime::now(),
             entry_type: EntryType::File,
+            link_target: None,
             file_id: None,
+            streams: Vec::new(),
+            cloud_placeholder: false,
+            owner: None,
+            group: None,
+            readonly: false,
+            system: false,
         })
         .collect()
 }
@@ -178,6 +185,7 @@ fn test_plan_generation_100k_entries() {
             entry: e,
             rule_name: "test".to_string(),
             reason: "test".to_string(),
+            downgrade: None,
         })
         .collect();
 
@@ -217,6 +225,7 @@ fn test_plan_generation_1m_entries() {
             entry: e,
             rule_name: "test".to_string(),
             reason: "test".to_string(),
+            downgrade: None,
         })
         .collect();
 
@@ -327,6 +336,7 @@ fn test_scan_small_dataset() {
     assert!(duration.as_secs() < 1); // Should be near-instant
 }
 
+#[cfg(feature = "parallel")]
 #[test]
 fn test_parallel_scan_performance() {
     // Test parallel scanning performance on realistic dataset
@@ -340,6 +350,13 @@ fn test_parallel_scan_performance() {
             skip_hidden: true,
             follow_symlinks: false,
             thread_count: 4,
+            remote: Default::default(),
+            exclude_globs: Vec::new(),
+            respect_ignore_files: false,
+            same_filesystem: false,
+            collect_owner: false,
+            max_iops: 0,
+            dir_size: Default::default(),
         });
     let results = scanner.scan(temp.path()).unwrap();
     let duration = start.elapsed();
@@ -398,9 +415,7 @@ fn test_complete_pipeline_performance() {
     let detect_start = Instant::now();
     let mut engine = DetectionEngine::empty();
     engine.add_rule(Box::new(BuildArtifactRule::default()));
-    engine.add_rule(Box::new(SizeThresholdRule {
-        threshold_bytes: 100,
-    }));
+    engine.add_rule(Box::new(SizeThresholdRule::new(100)));
     let detections = engine.analyze(&entries, &ScanContext::default());
     let detect_duration = detect_start.elapsed();
     println!(
@@ -446,9 +461,9 @@ fn test_memory_efficiency() {
 
     // Run detection
     let mut engine = DetectionEngine::empty();
-    engine.add_rule(Box::new(megamaid::detector::rules::SizeThresholdRule {
-        threshold_bytes: 1_048_576, // 1 MB
-    }));
+    engine.add_rule(Box::new(megamaid::detector::rules::SizeThresholdRule::new(
+        1_048_576,
+    ))); // 1 MB
 
     let detections = engine.analyze(&entries, &ScanContext::default());
 
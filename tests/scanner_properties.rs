@@ -88,18 +88,26 @@ proptest! {
 
         let entry = CleanupEntry {
             path: path.clone(),
+            root: 0,
             size,
             modified: Utc::now().to_rfc3339(),
             action: CleanupAction::Delete,
             rule_name: "test_rule".to_string(),
             reason: "test".to_string(),
+            collapsed: None,
+            streams: Vec::new(),
+            owner: None,
+            never_delete: false,
         };
 
         let plan = CleanupPlan {
             version: "1.0".to_string(),
             created_at: Utc::now(),
             base_path: PathBuf::from("/test"),
+            roots: Vec::new(),
             entries: vec![entry],
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         };
 
         // Serialize to YAML
@@ -129,11 +137,16 @@ proptest! {
             .enumerate()
             .map(|(i, &size)| CleanupEntry {
                 path: format!("file_{}.txt", i),
+                root: 0,
                 size,
                 modified: Utc::now().to_rfc3339(),
                 action: CleanupAction::Delete,
                 rule_name: "test_rule".to_string(),
                 reason: "test".to_string(),
+                collapsed: None,
+                streams: Vec::new(),
+                owner: None,
+                never_delete: false,
             })
             .collect();
 
@@ -141,7 +154,10 @@ proptest! {
             version: "1.0".to_string(),
             created_at: Utc::now(),
             base_path: temp.path().to_path_buf(),
+            roots: Vec::new(),
             entries,
+            detection_stats: Vec::new(),
+            protections: Vec::new(),
         };
 
         // Write plan